@@ -0,0 +1,27 @@
+//! Claims deserialization from arbitrary JSON payloads.
+//!
+//! The input is tried both as serialized `AttestationClaims` and as the
+//! message of an (unsigned) token, which exercises the wire-format claim
+//! parser including capability constraints, `cnf` and `ver` migration.
+
+#![no_main]
+
+use agent_uri_attestation::AttestationClaims;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(claims) = serde_json::from_slice::<AttestationClaims>(data) {
+        let _ = claims.capability_paths();
+        let _ = claims.migrate();
+    }
+
+    // The decoder strips a trailing 64-byte signature before parsing
+    let mut message = data.to_vec();
+    message.extend_from_slice(&[0; 64]);
+    let token = format!("v4.public.{}", URL_SAFE_NO_PAD.encode(&message));
+    if let Ok(claims) = AttestationClaims::decode_unverified(&token) {
+        let _ = claims.capability_paths();
+    }
+});
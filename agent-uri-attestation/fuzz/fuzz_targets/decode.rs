@@ -0,0 +1,15 @@
+//! Unverified decoding: header, base64url payload and footer splitting.
+
+#![no_main]
+
+use agent_uri_attestation::{format_token, AttestationClaims};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(token) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let _ = AttestationClaims::decode_unverified(token);
+    let _ = format_token(token);
+});
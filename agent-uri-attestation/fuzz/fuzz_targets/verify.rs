@@ -0,0 +1,36 @@
+//! Full verification of arbitrary token strings.
+//!
+//! Oversized tokens must be rejected by the length check before any
+//! base64 decoding or signature work.
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use agent_uri_attestation::{AttestationError, Verifier};
+use libfuzzer_sys::fuzz_target;
+
+/// Maximum token length from `grammar.abnf`.
+const MAX_TOKEN_LEN: usize = 8192;
+
+static VERIFIER: OnceLock<Verifier> = OnceLock::new();
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(token) = std::str::from_utf8(data) else {
+        return;
+    };
+    let verifier = VERIFIER.get_or_init(agent_uri_attestation_fuzz::verifier);
+
+    let result = verifier.verify(token);
+    if token.len() > MAX_TOKEN_LEN {
+        assert!(
+            matches!(result, Err(AttestationError::LengthLimitExceeded { .. })),
+            "oversized token was not rejected by length: {result:?}"
+        );
+    }
+
+    // Errors must render without panicking
+    if let Err(e) = result {
+        let _ = e.to_string();
+    }
+});
@@ -0,0 +1,72 @@
+//! Writes seed inputs for every fuzz target into `corpus/<target>/`.
+//!
+//! Run from the `fuzz` directory before the first fuzzing session:
+//!
+//! ```sh
+//! cargo run --bin seed_corpus
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use agent_uri_attestation::{AttestationClaims, CapabilityConstraints, SigningKey};
+use agent_uri_attestation_fuzz::{issuer, TRUST_ROOT};
+
+fn main() -> std::io::Result<()> {
+    let claims = AttestationClaims::builder()
+        .agent_uri("agent://acme.com/workflow/approval/agent_01h455vb4pex5vsknk084sn02q")
+        .issuer(TRUST_ROOT)
+        .add_capability("workflow")
+        .add_constrained_capability(
+            "workflow/payments",
+            CapabilityConstraints::new().max_uses(3).limit("max_amount", 1000),
+        )
+        .deny_capability("workflow/payments/refund")
+        .audience("api.acme.com")
+        .status_index(7)
+        .nonce("n0nce")
+        .holder_key(SigningKey::from_bytes(&[0x11; 32]).expect("valid key").verifying_key())
+        .build()
+        .expect("seed claims are valid");
+    let minimal = AttestationClaims::builder()
+        .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+        .issuer(TRUST_ROOT)
+        .build()
+        .expect("seed claims are valid");
+
+    let issuer = issuer();
+    let full = issuer.issue_claims(&claims).expect("seed token signs");
+    let small = issuer.issue_claims(&minimal).expect("seed token signs");
+
+    let tokens = [
+        full.clone(),
+        small.clone(),
+        format!("{full}.eyJraWQiOiJrMSJ9"),
+        format!("{small}."),
+        full[..full.len() / 2].to_string(),
+        full.replace("v4.public.", "v4.local."),
+        format!("v4.public.{}", "A".repeat(8200)),
+        "v4.public.".to_string(),
+        "v4.public.=".to_string(),
+    ];
+    write_all("verify", tokens.iter().map(String::as_bytes))?;
+    write_all("decode", tokens.iter().map(String::as_bytes))?;
+
+    let json = [
+        serde_json::to_vec(&claims).expect("claims serialize"),
+        serde_json::to_vec(&minimal).expect("claims serialize"),
+        br#"{"agent_uri":"agent://acme.com/a/agent_01h455vb4pex5vsknk084sn02q","capabilities":["b","a","a"],"iss":"acme.com","iat":"2024-01-15T14:30:00.000Z","exp":"2024-01-15T15:30:00.000Z"}"#.to_vec(),
+        br#"{"capabilities":[{"cap":"x","max_uses":18446744073709551615}],"ver":4294967295}"#.to_vec(),
+        br#"{"cnf":{"jwk":{"kty":"OKP","crv":"Ed25519","x":""}}}"#.to_vec(),
+    ];
+    write_all("claims", json.iter().map(Vec::as_slice))
+}
+
+fn write_all<'a>(target: &str, seeds: impl Iterator<Item = &'a [u8]>) -> std::io::Result<()> {
+    let dir = Path::new("corpus").join(target);
+    fs::create_dir_all(&dir)?;
+    for (i, seed) in seeds.enumerate() {
+        fs::write(dir.join(format!("seed-{i:02}")), seed)?;
+    }
+    Ok(())
+}
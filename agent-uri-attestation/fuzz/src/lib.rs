@@ -0,0 +1,27 @@
+//! Shared fixtures for the fuzz targets and the corpus seeder.
+//!
+//! Every target trusts the same fixed key, so seeds signed by
+//! [`issuer`] carry valid signatures and let the fuzzer reach the claims
+//! and capability checks behind signature verification.
+
+use std::time::Duration;
+
+use agent_uri_attestation::{Issuer, SigningKey, Verifier};
+
+/// Trust root of the fixture issuer.
+pub const TRUST_ROOT: &str = "acme.com";
+
+/// Returns the issuer whose tokens the fuzz targets trust.
+#[must_use]
+pub fn issuer() -> Issuer {
+    let key = SigningKey::from_bytes(&[0x5a; 32]).expect("fixed key is valid");
+    Issuer::new(TRUST_ROOT, key, Duration::from_secs(365 * 24 * 3600))
+}
+
+/// Returns a verifier trusting [`issuer`].
+#[must_use]
+pub fn verifier() -> Verifier {
+    let mut verifier = Verifier::new();
+    verifier.add_trusted_root(TRUST_ROOT, issuer().verifying_key());
+    verifier
+}
@@ -0,0 +1,447 @@
+//! Signed trust bundles for offline distribution.
+//!
+//! Air-gapped relying parties cannot fetch keys or status lists on demand.
+//! A [`TrustBundle`] packs everything a [`Verifier`] trusts — exact and
+//! wildcard roots, threshold key sets, published [`AgentKeySet`]s, and the
+//! issuers' current status list tokens — into one PASETO v4.public token signed by a distribution key.
+//! The token is the file to carry across the gap; [`Verifier::load_bundle`]
+//! checks its signature and expiry before merging it into the trust store.
+//!
+//! Status list tokens keep their issuers' signatures inside the bundle, so
+//! the distribution key vouches for which lists are current but cannot
+//! forge revocation data.
+//!
+//! [`AgentKeySet`]: crate::AgentKeySet
+//! [`Verifier`]: crate::Verifier
+//! [`Verifier::load_bundle`]: crate::Verifier::load_bundle
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use rusty_paseto::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AttestationError;
+use crate::keys::{SigningKey, ThresholdKeySet, VerifyingKey};
+use crate::keyset::AgentKeySet;
+use crate::status::{SharedStatusSource, StatusListSource};
+use crate::verifier::parse_with_key;
+
+/// Name of the claim carrying the bundle contents.
+const BUNDLE_CLAIM: &str = "trust_bundle";
+
+/// Trusted roots, key sets and revocation data for a [`Verifier`](crate::Verifier).
+///
+/// # Example
+///
+/// ```
+/// use agent_uri_attestation::{Issuer, SigningKey, TrustBundle, Verifier};
+/// use agent_uri::AgentUri;
+/// use std::time::Duration;
+///
+/// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+/// let distribution_key = SigningKey::generate();
+///
+/// // On the connected side
+/// let mut bundle = TrustBundle::new();
+/// bundle.add_trusted_root("acme.com", issuer.verifying_key());
+/// let file = bundle.sign(&distribution_key, Duration::from_secs(86400)).unwrap();
+///
+/// // On the air-gapped side
+/// let mut verifier = Verifier::new();
+/// verifier.load_bundle(&file, &distribution_key.verifying_key()).unwrap();
+///
+/// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+/// assert!(verifier.verify(&token).is_ok());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustBundle {
+    trusted_roots: BTreeMap<String, Vec<VerifyingKey>>,
+    trusted_patterns: BTreeMap<String, VerifyingKey>,
+    threshold_roots: BTreeMap<String, ThresholdKeySet>,
+    agent_key_sets: BTreeMap<String, AgentKeySet>,
+    status_lists: BTreeMap<String, String>,
+}
+
+impl TrustBundle {
+    /// Creates an empty bundle.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a trusted root; see
+    /// [`Verifier::add_trusted_root`](crate::Verifier::add_trusted_root).
+    pub fn add_trusted_root(&mut self, trust_root: impl Into<String>, public_key: VerifyingKey) {
+        self.trusted_roots.insert(trust_root.into(), vec![public_key]);
+    }
+
+    /// Adds another key for a trusted root, keeping those already in the
+    /// bundle; see
+    /// [`Verifier::add_trusted_key`](crate::Verifier::add_trusted_key).
+    pub fn add_trusted_key(&mut self, trust_root: impl Into<String>, public_key: VerifyingKey) {
+        let keys = self.trusted_roots.entry(trust_root.into()).or_default();
+        if !keys.contains(&public_key) {
+            keys.push(public_key);
+        }
+    }
+
+    /// Adds a wildcard root; see
+    /// [`Verifier::add_trusted_root_pattern`](crate::Verifier::add_trusted_root_pattern).
+    pub fn add_trusted_root_pattern(
+        &mut self,
+        pattern: impl Into<String>,
+        public_key: VerifyingKey,
+    ) {
+        self.trusted_patterns.insert(pattern.into(), public_key);
+    }
+
+    /// Adds a threshold root; see
+    /// [`Verifier::add_threshold_root`](crate::Verifier::add_threshold_root).
+    pub fn add_threshold_root(&mut self, trust_root: impl Into<String>, key_set: ThresholdKeySet) {
+        self.threshold_roots.insert(trust_root.into(), key_set);
+    }
+
+    /// Adds a trust root's published key set, replacing any earlier one for
+    /// the same root; see
+    /// [`Verifier::trust_key_set`](crate::Verifier::trust_key_set).
+    ///
+    /// Unlike [`add_trusted_root`](Self::add_trusted_root), the validity
+    /// windows travel with the keys and are checked when the bundle is
+    /// loaded.
+    pub fn add_key_set(&mut self, key_set: AgentKeySet) {
+        self.agent_key_sets.insert(key_set.trust_root().to_string(), key_set);
+    }
+
+    /// Adds `issuer`'s current status list token (see
+    /// [`Issuer::issue_status_list`](crate::Issuer::issue_status_list)).
+    ///
+    /// The token is stored as is and verified against the issuer's key each
+    /// time a verifier consults it, so it must still be valid when used.
+    pub fn add_status_list(&mut self, issuer: impl Into<String>, token: impl Into<String>) {
+        self.status_lists.insert(issuer.into(), token.into());
+    }
+
+    /// Returns the exact trusted roots and their keys, oldest first.
+    #[must_use]
+    pub fn trusted_roots(&self) -> &BTreeMap<String, Vec<VerifyingKey>> {
+        &self.trusted_roots
+    }
+
+    /// Returns the wildcard trusted roots.
+    #[must_use]
+    pub fn trusted_patterns(&self) -> &BTreeMap<String, VerifyingKey> {
+        &self.trusted_patterns
+    }
+
+    /// Returns the threshold roots.
+    #[must_use]
+    pub fn threshold_roots(&self) -> &BTreeMap<String, ThresholdKeySet> {
+        &self.threshold_roots
+    }
+
+    /// Returns the published key sets, keyed by trust root.
+    #[must_use]
+    pub fn agent_key_sets(&self) -> &BTreeMap<String, AgentKeySet> {
+        &self.agent_key_sets
+    }
+
+    /// Returns the status list tokens, keyed by issuer.
+    #[must_use]
+    pub fn status_lists(&self) -> &BTreeMap<String, String> {
+        &self.status_lists
+    }
+
+    /// Signs the bundle as a PASETO v4.public token valid for `ttl`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidTtl` if `ttl` is out of range, or
+    /// `AttestationError::InvalidTokenFormat` if signing fails.
+    pub fn sign(
+        &self,
+        distribution_key: &SigningKey,
+        ttl: Duration,
+    ) -> Result<String, AttestationError> {
+        let key_bytes = distribution_key.as_dalek().to_keypair_bytes();
+        let key_wrapper = Key::<64>::from(&key_bytes);
+        let paseto_key = PasetoAsymmetricPrivateKey::<V4, Public>::from(&key_wrapper);
+
+        let now = chrono::Utc::now();
+        let exp = now + chrono::Duration::from_std(ttl).map_err(|_| AttestationError::InvalidTtl)?;
+        let exp_str = exp.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+        let iat_str = now.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+
+        let exp_claim =
+            ExpirationClaim::try_from(exp_str.as_str()).map_err(|e| AttestationError::InvalidClaims {
+                reason: format!("invalid expiration: {e}"),
+            })?;
+        let iat_claim =
+            IssuedAtClaim::try_from(iat_str.as_str()).map_err(|e| AttestationError::InvalidClaims {
+                reason: format!("invalid issued at: {e}"),
+            })?;
+        let bundle_json =
+            serde_json::to_value(self.to_wire()).map_err(|e| AttestationError::InvalidClaims {
+                reason: format!("invalid trust bundle: {e}"),
+            })?;
+        let bundle_claim = CustomClaim::try_from((BUNDLE_CLAIM, bundle_json)).map_err(|e| {
+            AttestationError::InvalidClaims {
+                reason: format!("invalid trust_bundle claim: {e}"),
+            }
+        })?;
+
+        PasetoBuilder::<V4, Public>::default()
+            .set_claim(exp_claim)
+            .set_claim(iat_claim)
+            .set_claim(bundle_claim)
+            .build(&paseto_key)
+            .map_err(|e| AttestationError::InvalidTokenFormat {
+                reason: e.to_string(),
+            })
+    }
+
+    /// Verifies a signed bundle and returns its contents.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidSignature` if `token` was not signed
+    /// by `distribution_key`, `AttestationError::TokenExpired` if the bundle
+    /// has expired, or `AttestationError::InvalidClaims` if its contents are
+    /// malformed.
+    pub fn open(token: &str, distribution_key: &VerifyingKey) -> Result<Self, AttestationError> {
+        Self::open_at(token, distribution_key, chrono::Utc::now())
+    }
+
+    /// Like [`open`](Self::open), checking expiry against `now`.
+    pub(crate) fn open_at(
+        token: &str,
+        distribution_key: &VerifyingKey,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Self, AttestationError> {
+        let json = parse_with_key(token, distribution_key, now)?;
+        let wire: WireBundle = json
+            .get(BUNDLE_CLAIM)
+            .map(|v| serde_json::from_value(v.clone()))
+            .transpose()
+            .map_err(|e| invalid(&e.to_string()))?
+            .ok_or_else(|| invalid("token has no trust_bundle claim"))?;
+        Self::from_wire(wire)
+    }
+
+    fn to_wire(&self) -> WireBundle {
+        let hex = |keys: &BTreeMap<String, VerifyingKey>| {
+            keys.iter().map(|(root, key)| (root.clone(), key.to_string())).collect()
+        };
+        WireBundle {
+            roots: self
+                .trusted_roots
+                .iter()
+                .map(|(root, keys)| {
+                    let wire = match keys.as_slice() {
+                        [key] => WireKeys::One(key.to_string()),
+                        keys => WireKeys::Many(keys.iter().map(ToString::to_string).collect()),
+                    };
+                    (root.clone(), wire)
+                })
+                .collect(),
+            patterns: hex(&self.trusted_patterns),
+            key_sets: self
+                .threshold_roots
+                .iter()
+                .map(|(root, set)| {
+                    let wire = WireKeySet {
+                        keys: set.keys().iter().map(ToString::to_string).collect(),
+                        threshold: set.threshold(),
+                    };
+                    (root.clone(), wire)
+                })
+                .collect(),
+            agent_keys: self.agent_key_sets.clone(),
+            status_lists: self.status_lists.clone(),
+        }
+    }
+
+    fn from_wire(wire: WireBundle) -> Result<Self, AttestationError> {
+        let keys = |hex: BTreeMap<String, String>| {
+            hex.into_iter()
+                .map(|(root, key)| Ok((root, key.parse()?)))
+                .collect::<Result<BTreeMap<_, _>, AttestationError>>()
+        };
+        let threshold_roots = wire
+            .key_sets
+            .into_iter()
+            .map(|(root, set)| {
+                let keys = set
+                    .keys
+                    .iter()
+                    .map(|key| key.parse())
+                    .collect::<Result<_, _>>()?;
+                Ok((root, ThresholdKeySet::new(keys, set.threshold)?))
+            })
+            .collect::<Result<_, AttestationError>>()?;
+        let trusted_roots = wire
+            .roots
+            .into_iter()
+            .map(|(root, wire)| {
+                let keys = match wire {
+                    WireKeys::One(key) => vec![key.parse()?],
+                    WireKeys::Many(keys) if keys.is_empty() => {
+                        return Err(invalid(&format!("root '{root}' has no keys")));
+                    }
+                    WireKeys::Many(keys) => {
+                        keys.iter().map(|key| key.parse()).collect::<Result<_, _>>()?
+                    }
+                };
+                Ok((root, keys))
+            })
+            .collect::<Result<_, AttestationError>>()?;
+        Ok(Self {
+            trusted_roots,
+            trusted_patterns: keys(wire.patterns)?,
+            threshold_roots,
+            agent_key_sets: wire.agent_keys,
+            status_lists: wire.status_lists,
+        })
+    }
+}
+
+fn invalid(reason: &str) -> AttestationError {
+    AttestationError::InvalidClaims {
+        reason: format!("invalid trust bundle: {reason}"),
+    }
+}
+
+/// Wire form of the `trust_bundle` claim, with keys as hex strings.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WireBundle {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    roots: BTreeMap<String, WireKeys>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    patterns: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    key_sets: BTreeMap<String, WireKeySet>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    agent_keys: BTreeMap<String, AgentKeySet>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    status_lists: BTreeMap<String, String>,
+}
+
+/// A root's keys: a single hex string, as bundles with one key per root
+/// were written, or an array once a root has several.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum WireKeys {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WireKeySet {
+    keys: Vec<String>,
+    threshold: usize,
+}
+
+/// Serves status lists from a loaded bundle, deferring to the previously
+/// configured source for issuers the bundle does not cover.
+pub(crate) struct BundleStatusSource {
+    pub(crate) lists: BTreeMap<String, String>,
+    pub(crate) fallback: Option<SharedStatusSource>,
+}
+
+impl StatusListSource for BundleStatusSource {
+    fn status_list(&self, issuer: &str) -> Option<String> {
+        self.lists.get(issuer).cloned().or_else(|| {
+            self.fallback
+                .as_ref()
+                .and_then(|source| source.0.status_list(issuer))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_and_open_roundtrip() {
+        let distribution = SigningKey::generate();
+        let keys: Vec<VerifyingKey> =
+            (0..3).map(|_| SigningKey::generate().verifying_key()).collect();
+
+        let mut bundle = TrustBundle::new();
+        bundle.add_trusted_root("acme.com", keys[0].clone());
+        bundle.add_trusted_root("globex.com", keys[1].clone());
+        bundle.add_trusted_key("globex.com", keys[2].clone());
+        bundle.add_trusted_root_pattern("*.acme.com", keys[1].clone());
+        bundle.add_threshold_root("bank.com", ThresholdKeySet::new(keys.clone(), 2).unwrap());
+        bundle.add_status_list("acme.com", "v4.public.status");
+        let mut key_set = AgentKeySet::new("partner.com");
+        let now = chrono::Utc::now();
+        key_set.add_key(crate::keyset::PublishedKey::new(
+            keys[2].clone(),
+            now,
+            now + chrono::Duration::days(1),
+        ));
+        bundle.add_key_set(key_set);
+
+        let token = bundle.sign(&distribution, Duration::from_mins(1)).unwrap();
+        let opened = TrustBundle::open(&token, &distribution.verifying_key()).unwrap();
+
+        assert_eq!(opened, bundle);
+    }
+
+    #[test]
+    fn open_rejects_other_distribution_keys() {
+        let token = TrustBundle::new()
+            .sign(&SigningKey::generate(), Duration::from_mins(1))
+            .unwrap();
+
+        assert_eq!(
+            TrustBundle::open(&token, &SigningKey::generate().verifying_key()),
+            Err(AttestationError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn open_rejects_invalid_key_sets() {
+        let distribution = SigningKey::generate();
+        let key = SigningKey::generate().verifying_key();
+        let mut wire = WireBundle::default();
+        wire.key_sets.insert(
+            "bank.com".to_string(),
+            WireKeySet {
+                keys: vec![key.to_string()],
+                threshold: 2,
+            },
+        );
+
+        assert!(matches!(
+            TrustBundle::from_wire(wire),
+            Err(AttestationError::InvalidThreshold { .. })
+        ));
+        assert!(TrustBundle::open("v4.public.garbage", &distribution.verifying_key()).is_err());
+    }
+
+    #[test]
+    fn roots_with_one_key_keep_the_single_key_encoding() {
+        let keys: Vec<VerifyingKey> =
+            (0..2).map(|_| SigningKey::generate().verifying_key()).collect();
+        let mut bundle = TrustBundle::new();
+        bundle.add_trusted_root("acme.com", keys[0].clone());
+        bundle.add_trusted_key("globex.com", keys[0].clone());
+        bundle.add_trusted_key("globex.com", keys[1].clone());
+
+        let wire = serde_json::to_value(bundle.to_wire()).unwrap();
+        assert_eq!(wire["roots"]["acme.com"], keys[0].to_string());
+        assert_eq!(wire["roots"]["globex.com"][1], keys[1].to_string());
+        let wire: WireBundle = serde_json::from_value(wire).unwrap();
+        assert_eq!(TrustBundle::from_wire(wire).unwrap(), bundle);
+
+        let empty = serde_json::json!({ "roots": { "acme.com": [] } });
+        assert!(matches!(
+            TrustBundle::from_wire(serde_json::from_value(empty).unwrap()),
+            Err(AttestationError::InvalidClaims { .. })
+        ));
+    }
+}
@@ -0,0 +1,372 @@
+//! Opt-in caching of verification results.
+//!
+//! Ed25519 verification dominates the cost of [`Verifier::verify`]. Bearer
+//! tokens are typically presented many times during their lifetime, so a
+//! relying party can attach a [`VerificationCache`] to skip repeated work.
+//!
+//! Entries are keyed by the SHA-256 hash of the token. Successful results
+//! are kept until the earlier of the token's `exp` and the cache TTL.
+//! Signature failures are cached for a (usually shorter) negative TTL so a
+//! flood of forged tokens does not cost a signature check each time. Other
+//! errors are never cached.
+//!
+//! [`Verifier::verify`]: crate::Verifier::verify
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::claims::AttestationClaims;
+use crate::error::AttestationError;
+
+/// A bounded cache of verification results keyed by token hash.
+///
+/// The cache uses interior mutability so it can be consulted from
+/// [`Verifier::verify`], which takes `&self`. Cloning a cache copies its
+/// configuration but not its entries, because cached results are only
+/// meaningful for the trust store they were produced against.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri_attestation::{Issuer, SigningKey, VerificationCache, Verifier};
+/// use agent_uri::AgentUri;
+/// use std::time::Duration;
+///
+/// let signing_key = SigningKey::generate();
+/// let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(3600));
+/// let uri = AgentUri::parse(
+///     "agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q"
+/// ).unwrap();
+/// let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+///
+/// let mut verifier = Verifier::new();
+/// verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+/// verifier.enable_cache(VerificationCache::new(1024, Duration::from_secs(300)));
+///
+/// // The second call is served from the cache
+/// verifier.verify(&token).unwrap();
+/// verifier.verify(&token).unwrap();
+/// assert_eq!(verifier.cache().map(VerificationCache::len), Some(1));
+/// ```
+///
+/// [`Verifier::verify`]: crate::Verifier::verify
+#[derive(Debug)]
+pub struct VerificationCache {
+    capacity: usize,
+    ttl: Duration,
+    negative_ttl: Duration,
+    entries: Mutex<Entries>,
+}
+
+/// Cached results, indexed by token hash and by expiry.
+#[derive(Debug, Default)]
+struct Entries {
+    by_token: HashMap<[u8; 32], CacheEntry>,
+    by_expiry: BTreeSet<(DateTime<Utc>, [u8; 32])>,
+}
+
+impl Entries {
+    fn remove(&mut self, key: &[u8; 32]) {
+        if let Some(entry) = self.by_token.remove(key) {
+            self.by_expiry.remove(&(entry.expires_at, *key));
+        }
+    }
+
+    /// Removes the entry closest to expiring; returns false if empty.
+    fn pop_first(&mut self) -> bool {
+        let Some((_, key)) = self.by_expiry.pop_first() else {
+            return false;
+        };
+        self.by_token.remove(&key);
+        true
+    }
+}
+
+/// A cached verification outcome and the time it stops being valid.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    result: Result<AttestationClaims, AttestationError>,
+    expires_at: DateTime<Utc>,
+}
+
+impl VerificationCache {
+    /// Default TTL for cached signature failures.
+    pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_mins(1);
+
+    /// Creates a cache holding at most `capacity` entries.
+    ///
+    /// Successful results are kept for at most `ttl`, and never past the
+    /// token's own expiration. Signature failures are kept for
+    /// [`DEFAULT_NEGATIVE_TTL`](Self::DEFAULT_NEGATIVE_TTL).
+    #[must_use]
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            negative_ttl: Self::DEFAULT_NEGATIVE_TTL,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Sets how long signature failures are cached.
+    ///
+    /// A zero duration disables negative caching.
+    #[must_use]
+    pub fn with_negative_ttl(mut self, negative_ttl: Duration) -> Self {
+        self.negative_ttl = negative_ttl;
+        self
+    }
+
+    /// Returns the maximum number of entries.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the maximum lifetime of a successful entry.
+    #[must_use]
+    pub const fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the lifetime of a cached signature failure.
+    #[must_use]
+    pub const fn negative_ttl(&self) -> Duration {
+        self.negative_ttl
+    }
+
+    /// Returns the number of entries currently stored, including stale ones
+    /// that have not been evicted yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("lock poisoned").by_token.len()
+    }
+
+    /// Returns true if the cache holds no entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    pub fn clear(&self) {
+        *self.entries.lock().expect("lock poisoned") = Entries::default();
+    }
+
+    /// Looks up the cached result for `token` at time `now`.
+    ///
+    /// Stale entries are removed and reported as a miss.
+    pub(crate) fn get(
+        &self,
+        token: &str,
+        now: DateTime<Utc>,
+    ) -> Option<Result<AttestationClaims, AttestationError>> {
+        let key = token_hash(token);
+        let mut entries = self.entries.lock().expect("lock poisoned");
+        match entries.by_token.get(&key) {
+            Some(entry) if now < entry.expires_at => Some(entry.result.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Records the result of verifying `token` at time `now`.
+    ///
+    /// Only successes and `InvalidSignature` failures are stored.
+    pub(crate) fn insert(
+        &self,
+        token: &str,
+        result: &Result<AttestationClaims, AttestationError>,
+        now: DateTime<Utc>,
+    ) {
+        let expires_at = match result {
+            Ok(claims) => (now + to_chrono(self.ttl)).min(claims.exp),
+            Err(AttestationError::InvalidSignature) => now + to_chrono(self.negative_ttl),
+            Err(_) => return,
+        };
+        if self.capacity == 0 || expires_at <= now {
+            return;
+        }
+
+        let key = token_hash(token);
+        let mut entries = self.entries.lock().expect("lock poisoned");
+        entries.remove(&key);
+        // Stale entries, then the entry closest to expiring, which has the
+        // least value left, sit at the front of the expiry index
+        while entries.by_token.len() >= self.capacity && entries.pop_first() {}
+        entries.by_expiry.insert((expires_at, key));
+        entries.by_token.insert(
+            key,
+            CacheEntry {
+                result: result.clone(),
+                expires_at,
+            },
+        );
+    }
+}
+
+impl Clone for VerificationCache {
+    fn clone(&self) -> Self {
+        Self::new(self.capacity, self.ttl).with_negative_ttl(self.negative_ttl)
+    }
+}
+
+/// Hashes a token into a fixed-size cache key.
+fn token_hash(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+/// Converts a std duration to chrono, saturating on overflow.
+fn to_chrono(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claims::AttestationClaimsBuilder;
+
+    fn claims(ttl: Duration) -> AttestationClaims {
+        AttestationClaimsBuilder::new()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .ttl(ttl)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn hit_after_insert() {
+        let cache = VerificationCache::new(8, Duration::from_mins(1));
+        let now = Utc::now();
+        let result = Ok(claims(Duration::from_hours(1)));
+
+        cache.insert("token", &result, now);
+
+        assert_eq!(cache.get("token", now), Some(result));
+        assert_eq!(cache.get("other", now), None);
+    }
+
+    #[test]
+    fn positive_entry_expires_with_cache_ttl() {
+        let cache = VerificationCache::new(8, Duration::from_mins(1));
+        let now = Utc::now();
+
+        cache.insert("token", &Ok(claims(Duration::from_hours(1))), now);
+
+        assert!(cache.get("token", now + chrono::Duration::seconds(59)).is_some());
+        assert!(cache.get("token", now + chrono::Duration::seconds(60)).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn positive_entry_never_outlives_token() {
+        let cache = VerificationCache::new(8, Duration::from_hours(1));
+        let claims = claims(Duration::from_secs(30));
+        let exp = claims.exp;
+        let now = Utc::now();
+
+        cache.insert("token", &Ok(claims), now);
+
+        assert!(cache.get("token", exp).is_none());
+    }
+
+    #[test]
+    fn signature_failures_are_cached() {
+        let cache = VerificationCache::new(8, Duration::from_mins(1))
+            .with_negative_ttl(Duration::from_secs(5));
+        let now = Utc::now();
+
+        cache.insert("forged", &Err(AttestationError::InvalidSignature), now);
+
+        assert_eq!(
+            cache.get("forged", now),
+            Some(Err(AttestationError::InvalidSignature))
+        );
+        assert!(cache.get("forged", now + chrono::Duration::seconds(5)).is_none());
+    }
+
+    #[test]
+    fn other_failures_are_not_cached() {
+        let cache = VerificationCache::new(8, Duration::from_mins(1));
+        let now = Utc::now();
+
+        cache.insert(
+            "expired",
+            &Err(AttestationError::TokenExpired {
+                expired_at: "unknown".to_string(),
+            }),
+            now,
+        );
+
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn capacity_is_enforced() {
+        let cache = VerificationCache::new(2, Duration::from_mins(1));
+        let now = Utc::now();
+        let result = Ok(claims(Duration::from_hours(1)));
+
+        cache.insert("a", &result, now);
+        cache.insert("b", &result, now + chrono::Duration::seconds(1));
+        cache.insert("c", &result, now + chrono::Duration::seconds(2));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("a", now).is_none());
+        assert!(cache.get("c", now).is_some());
+    }
+
+    #[test]
+    fn eviction_prefers_stale_and_soonest_expiring_entries() {
+        let cache = VerificationCache::new(3, Duration::from_hours(1))
+            .with_negative_ttl(Duration::from_secs(5));
+        let now = Utc::now();
+        let result = Ok(claims(Duration::from_hours(1)));
+
+        cache.insert("long", &result, now);
+        cache.insert("forged", &Err(AttestationError::InvalidSignature), now);
+        cache.insert("short", &Ok(claims(Duration::from_mins(1))), now);
+        // Reinserting a cached token replaces it rather than evicting
+        cache.insert("long", &result, now + chrono::Duration::seconds(1));
+        assert_eq!(cache.len(), 3);
+
+        cache.insert("new", &result, now + chrono::Duration::seconds(10));
+        assert!(cache.get("forged", now).is_none());
+        cache.insert("newer", &result, now + chrono::Duration::seconds(10));
+        assert!(cache.get("short", now).is_none());
+        assert_eq!(cache.len(), 3);
+        assert!(cache.get("long", now).is_some());
+    }
+
+    #[test]
+    fn clone_does_not_share_entries() {
+        let cache = VerificationCache::new(8, Duration::from_mins(1));
+        cache.insert("token", &Ok(claims(Duration::from_hours(1))), Utc::now());
+
+        let cloned = cache.clone();
+
+        assert_eq!(cloned.capacity(), 8);
+        assert!(cloned.is_empty());
+        assert_eq!(cache.len(), 1);
+    }
+}
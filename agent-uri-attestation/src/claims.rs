@@ -2,12 +2,29 @@
 //!
 //! See `grammar.abnf` for the formal ABNF specification of claims structure.
 
+use std::collections::BTreeMap;
 use std::time::Duration;
 
+use agent_uri::{AgentUri, CapabilityPath};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::constraints::{CapabilityConstraints, CapabilityRequest};
+use crate::diff::ClaimsDiff;
 use crate::error::AttestationError;
+use crate::keys::VerifyingKey;
+use crate::limits;
+
+/// Current version of the claims schema, carried in the `ver` claim.
+///
+/// | Version | Changes |
+/// |---------|---------|
+/// | 1 | Original schema; tokens carry no `ver` claim |
+/// | 2 | Adds `ver`; capability lists are sorted and free of duplicates |
+pub const CLAIMS_VERSION: u32 = 2;
+
+/// Version assumed for tokens without a `ver` claim.
+pub(crate) const LEGACY_CLAIMS_VERSION: u32 = 1;
 
 /// Claims embedded in an attestation token.
 ///
@@ -22,11 +39,18 @@ use crate::error::AttestationError;
 /// | Field | Format | Max Length |
 /// |-------|--------|------------|
 /// | `agent_uri` | agent-uri ABNF | 512 chars |
+/// | `agent_uris` | JSON array | 32 items |
 /// | `capabilities` | JSON array | 64 items |
+/// | `denied_capabilities` | JSON array | 64 items |
 /// | `iss` | trust-root | 128 chars |
 /// | `iat` | ISO 8601 | 30 chars |
 /// | `exp` | ISO 8601 | 30 chars |
+/// | `nbf` | ISO 8601 | 30 chars |
+/// | `orig_iat` | ISO 8601 | 30 chars |
 /// | `aud` | alphanumeric | 128 chars |
+/// | `ver` | integer | — |
+/// | `cnf` | RFC 7800 JWK | — |
+/// | `nonce` | string | 128 |
 ///
 /// # Example
 ///
@@ -48,17 +72,54 @@ use crate::error::AttestationError;
 pub struct AttestationClaims {
     /// The full agent URI being attested
     pub agent_uri: String,
+    /// Further agent URIs attested by the same token, e.g. replicas of
+    /// `agent_uri`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agent_uris: Vec<String>,
     /// Capabilities granted to this agent
     pub capabilities: Vec<String>,
+    /// Optional constraints on individual capabilities, keyed by capability
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub capability_constraints: BTreeMap<String, CapabilityConstraints>,
+    /// Capabilities excluded from the granted coverage
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_capabilities: Vec<String>,
     /// Issuer (trust root) that created this attestation
     pub iss: String,
     /// When the token was issued
     pub iat: DateTime<Utc>,
     /// When the token expires
     pub exp: DateTime<Utc>,
+    /// Optional time before which the token must not be accepted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<DateTime<Utc>>,
     /// Optional audience restriction
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aud: Option<String>,
+    /// Optional index of this token in the issuer's status list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_idx: Option<u64>,
+    /// Version of the claims schema (see [`CLAIMS_VERSION`])
+    #[serde(default = "legacy_version")]
+    pub ver: u32,
+    /// Optional holder key the presenter must prove possession of
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::pop::serde_cnf"
+    )]
+    pub cnf: Option<VerifyingKey>,
+    /// Optional verifier-supplied challenge for single-use tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// When the first token in a renewal chain was issued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orig_iat: Option<DateTime<Utc>>,
+}
+
+/// Serde default for claims serialized without a `ver` field.
+const fn legacy_version() -> u32 {
+    LEGACY_CLAIMS_VERSION
 }
 
 impl AttestationClaims {
@@ -68,6 +129,97 @@ impl AttestationClaims {
         AttestationClaimsBuilder::new()
     }
 
+    /// Decodes the claims of a PASETO token **without verifying it**.
+    ///
+    /// Neither the signature, the issuer nor the validity period is checked,
+    /// so the returned claims are untrusted: anyone can mint a token with
+    /// arbitrary contents. Use this only to look inside a token, e.g. for
+    /// debugging, logging, or choosing which trust store to verify against,
+    /// and never to make authorization decisions. Use
+    /// [`Verifier::verify`](crate::Verifier::verify) for that.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidTokenFormat` if the token is not a
+    /// well-formed PASETO v4.public token, or
+    /// `AttestationError::InvalidClaims` if its payload lacks required claims.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri::AgentUri;
+    /// use agent_uri_attestation::{AttestationClaims, Issuer};
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let uri = AgentUri::parse(
+    ///     "agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q"
+    /// ).unwrap();
+    /// let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+    ///
+    /// // Pick a trust store by the (untrusted) issuer before verifying
+    /// let untrusted = AttestationClaims::decode_unverified(&token).unwrap();
+    /// assert_eq!(untrusted.iss, "acme.com");
+    /// ```
+    pub fn decode_unverified(token: &str) -> Result<Self, AttestationError> {
+        let json = crate::inspect::decode(token)?.json()?;
+        crate::verifier::extract_claims(&json)
+    }
+
+    /// Returns true if the claims use an older schema than [`CLAIMS_VERSION`].
+    ///
+    /// Claims decoded from tokens are migrated automatically, so this is
+    /// only true for claims constructed or deserialized directly.
+    #[must_use]
+    pub const fn needs_migration(&self) -> bool {
+        self.ver < CLAIMS_VERSION
+    }
+
+    /// Upgrades claims from an older schema version to [`CLAIMS_VERSION`].
+    ///
+    /// Each known version is upgraded one step at a time, so a version 1
+    /// claims set has its capability lists sorted and deduplicated. Current
+    /// claims are returned unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::UnsupportedClaimsVersion` if the claims
+    /// were produced by a newer schema than this library understands.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::{AttestationClaims, CLAIMS_VERSION};
+    ///
+    /// let mut legacy = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+    ///     .issuer("acme.com")
+    ///     .build()
+    ///     .unwrap();
+    /// legacy.ver = 1;
+    /// legacy.capabilities = vec!["write".into(), "read".into(), "write".into()];
+    ///
+    /// let claims = legacy.migrate().unwrap();
+    /// assert_eq!(claims.ver, CLAIMS_VERSION);
+    /// assert_eq!(claims.capabilities, ["read", "write"]);
+    /// ```
+    pub fn migrate(mut self) -> Result<Self, AttestationError> {
+        if self.ver > CLAIMS_VERSION {
+            return Err(AttestationError::UnsupportedClaimsVersion {
+                version: self.ver,
+                supported: CLAIMS_VERSION,
+            });
+        }
+        if self.ver <= LEGACY_CLAIMS_VERSION {
+            self.capabilities.sort_unstable();
+            self.capabilities.dedup();
+            self.denied_capabilities.sort_unstable();
+            self.denied_capabilities.dedup();
+            self.ver = LEGACY_CLAIMS_VERSION + 1;
+        }
+        Ok(self)
+    }
+
     /// Returns the trust root from the agent URI.
     ///
     /// This extracts the authority portion of the agent URI for trust root
@@ -95,6 +247,29 @@ impl AttestationClaims {
             .and_then(|rest| rest.split('/').next())
     }
 
+    /// Returns true if the token attests `uri`, either as its `agent_uri`
+    /// or as a member of its `agent_uris` fleet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::AttestationClaims;
+    ///
+    /// let claims = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/search/agent_01h455vb4pex5vsknk084sn02q")
+    ///     .add_agent_uri("agent://acme.com/search/agent_01h455vb4pex5vsknk084sn02r")
+    ///     .issuer("acme.com")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(claims.attests("agent://acme.com/search/agent_01h455vb4pex5vsknk084sn02r"));
+    /// assert!(!claims.attests("agent://acme.com/search/agent_01h455vb4pex5vsknk084sn02s"));
+    /// ```
+    #[must_use]
+    pub fn attests(&self, uri: &str) -> bool {
+        self.agent_uri == uri || self.agent_uris.iter().any(|member| member == uri)
+    }
+
     /// Returns true if the claims have expired.
     ///
     /// # Example
@@ -117,6 +292,83 @@ impl AttestationClaims {
         Utc::now() >= self.exp
     }
 
+    /// Returns how long until the token expires, or zero if it has.
+    ///
+    /// Long-running agents can use this to renew a token (see
+    /// [`Issuer::renew`](crate::Issuer::renew)) before it lapses.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::AttestationClaims;
+    /// use std::time::Duration;
+    ///
+    /// let claims = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+    ///     .issuer("acme.com")
+    ///     .ttl(Duration::from_secs(3600))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(claims.time_remaining() > Duration::from_secs(3500));
+    /// ```
+    #[must_use]
+    pub fn time_remaining(&self) -> Duration {
+        self.time_remaining_at(Utc::now())
+    }
+
+    /// Returns how long after `now` the token expires, or zero if it has
+    /// expired by then.
+    #[must_use]
+    pub fn time_remaining_at(&self, now: DateTime<Utc>) -> Duration {
+        (self.exp - now).to_std().unwrap_or(Duration::ZERO)
+    }
+
+    /// Returns when the token should be renewed: once `fraction` of its
+    /// lifetime from `iat` to `exp` has passed.
+    ///
+    /// `fraction` is clamped to `0.0..=1.0`, so `0.8` renews with a fifth
+    /// of the lifetime left and `1.0` at expiry. A `NaN` fraction renews at
+    /// expiry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::AttestationClaims;
+    /// use std::time::Duration;
+    ///
+    /// let claims = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+    ///     .issuer("acme.com")
+    ///     .ttl(Duration::from_secs(1000))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let renew_at = claims.renew_after(0.75);
+    /// assert_eq!(claims.time_remaining_at(renew_at), Duration::from_secs(250));
+    /// ```
+    #[must_use]
+    pub fn renew_after(&self, fraction: f64) -> DateTime<Utc> {
+        let fraction = if fraction.is_nan() {
+            1.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+        let lifetime = (self.exp - self.iat).to_std().unwrap_or(Duration::ZERO);
+        chrono::Duration::from_std(lifetime.mul_f64(fraction))
+            .ok()
+            .and_then(|elapsed| self.iat.checked_add_signed(elapsed))
+            .map_or(self.exp, |at| at.min(self.exp))
+    }
+
+    /// Returns when the first token in this token's renewal chain was issued.
+    ///
+    /// This is `orig_iat` for renewed tokens and `iat` otherwise.
+    #[must_use]
+    pub fn original_issued_at(&self) -> DateTime<Utc> {
+        self.orig_iat.unwrap_or(self.iat)
+    }
+
     /// Returns true if the token is not yet valid (before `iat`).
     #[must_use]
     pub fn is_not_yet_valid(&self) -> bool {
@@ -160,6 +412,161 @@ impl AttestationClaims {
     pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
         now >= self.exp
     }
+
+    /// Returns the granted capabilities as typed capability paths.
+    ///
+    /// Coverage checks such as
+    /// [`Verifier::verify_for_capability`](crate::Verifier::verify_for_capability)
+    /// treat capabilities as `/`-separated paths; this exposes them the same
+    /// way.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidClaims` if a capability is not a
+    /// valid capability path.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri::CapabilityPath;
+    /// use agent_uri_attestation::AttestationClaims;
+    ///
+    /// let claims = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02q")
+    ///     .issuer("acme.com")
+    ///     .capability_paths(vec![CapabilityPath::parse("workflow/approval").unwrap()])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let paths = claims.capability_paths().unwrap();
+    /// assert_eq!(paths[0].depth(), 2);
+    /// ```
+    pub fn capability_paths(&self) -> Result<Vec<CapabilityPath>, AttestationError> {
+        self.capabilities
+            .iter()
+            .map(|cap| {
+                CapabilityPath::parse(cap).map_err(|e| AttestationError::InvalidClaims {
+                    reason: format!("capability '{cap}' is not a capability path: {e}"),
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that a granted capability covers `request` within its
+    /// constraints.
+    ///
+    /// Delegates to [`check_constrained_coverage`](crate::check_constrained_coverage).
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InsufficientCapabilities` if no capability
+    /// covers the request, `AttestationError::CapabilityDenied` if the request
+    /// falls under a denied capability, or
+    /// `AttestationError::CapabilityConstraintViolated` if every covering
+    /// capability is constrained and none permits it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri::CapabilityPath;
+    /// use agent_uri_attestation::{AttestationClaims, CapabilityConstraints, CapabilityRequest};
+    /// use chrono::Utc;
+    ///
+    /// let claims = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02q")
+    ///     .issuer("acme.com")
+    ///     .add_constrained_capability(
+    ///         "workflow/approval",
+    ///         CapabilityConstraints::new().limit("max_amount", 1000),
+    ///     )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let path = CapabilityPath::parse("workflow/approval").unwrap();
+    /// let small = CapabilityRequest::new(path.clone(), Utc::now()).quantity("amount", 500);
+    /// let large = CapabilityRequest::new(path, Utc::now()).quantity("amount", 5000);
+    ///
+    /// assert!(claims.check_capability(&small).is_ok());
+    /// assert!(claims.check_capability(&large).is_err());
+    /// ```
+    pub fn check_capability(&self, request: &CapabilityRequest) -> Result<(), AttestationError> {
+        crate::verification::check_constrained_coverage(
+            &self.capabilities,
+            &self.denied_capabilities,
+            &self.capability_constraints,
+            request,
+        )
+    }
+
+    /// Compares these claims with `other`, typically a renewal of them.
+    ///
+    /// `self` is treated as the previous and `other` as the current claims;
+    /// see [`ClaimsDiff`] for what is reported and which changes count as
+    /// privilege escalation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::AttestationClaims;
+    /// use std::time::Duration;
+    ///
+    /// let previous = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+    ///     .issuer("acme.com")
+    ///     .add_capability("workflow/approval")
+    ///     .build()
+    ///     .unwrap();
+    /// let renewed = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+    ///     .issuer("acme.com")
+    ///     .add_capability("workflow")
+    ///     .ttl(Duration::from_secs(7200))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let diff = previous.diff(&renewed);
+    /// assert!(diff.expiry.is_some());
+    /// assert_eq!(diff.escalated_capabilities, ["workflow"]);
+    /// ```
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> ClaimsDiff {
+        ClaimsDiff::new(self, other)
+    }
+
+    /// Encodes these claims as an `EdDSA`-signed JWT.
+    ///
+    /// Intended for relying parties that only speak JOSE; PASETO via
+    /// [`Issuer`](crate::Issuer) remains the native format. The agent URI is
+    /// carried in `sub` and timestamps are truncated to whole seconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidClaims` if the claims cannot be
+    /// serialized.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::{AttestationClaims, SigningKey, Verifier};
+    ///
+    /// let signing_key = SigningKey::generate();
+    /// let claims = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02q")
+    ///     .issuer("acme.com")
+    ///     .add_capability("workflow/approval")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let jwt = claims.to_jwt(&signing_key).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+    /// assert_eq!(verifier.verify_jwt(&jwt).unwrap().agent_uri, claims.agent_uri);
+    /// ```
+    #[cfg(feature = "jwt")]
+    pub fn to_jwt(&self, signing_key: &crate::SigningKey) -> Result<String, AttestationError> {
+        crate::jwt::sign(self, signing_key)
+    }
 }
 
 /// Builder for constructing `AttestationClaims`.
@@ -186,10 +593,18 @@ impl AttestationClaims {
 #[derive(Debug, Clone)]
 pub struct AttestationClaimsBuilder {
     agent_uri: Option<String>,
+    agent_uris: Vec<String>,
     capabilities: Vec<String>,
+    capability_constraints: BTreeMap<String, CapabilityConstraints>,
+    denied_capabilities: Vec<String>,
     issuer: Option<String>,
     ttl: Duration,
     audience: Option<String>,
+    not_before: Option<DateTime<Utc>>,
+    status_index: Option<u64>,
+    holder_key: Option<VerifyingKey>,
+    nonce: Option<String>,
+    issued_at: Option<DateTime<Utc>>,
 }
 
 impl AttestationClaimsBuilder {
@@ -198,10 +613,18 @@ impl AttestationClaimsBuilder {
     pub fn new() -> Self {
         Self {
             agent_uri: None,
+            agent_uris: Vec::new(),
             capabilities: Vec::new(),
+            capability_constraints: BTreeMap::new(),
+            denied_capabilities: Vec::new(),
             issuer: None,
-            ttl: Duration::from_secs(86400), // 24 hours
+            ttl: Duration::from_hours(24), // 24 hours
             audience: None,
+            not_before: None,
+            status_index: None,
+            holder_key: None,
+            nonce: None,
+            issued_at: None,
         }
     }
 
@@ -212,6 +635,24 @@ impl AttestationClaimsBuilder {
         self
     }
 
+    /// Sets further agent URIs attested alongside `agent_uri`.
+    ///
+    /// One fleet token can then attest a set of related agents, such as the
+    /// replicas of a horizontally scaled service, instead of issuing one
+    /// near-identical token per agent. At most 32 URIs may be listed.
+    #[must_use]
+    pub fn agent_uris(mut self, uris: Vec<String>) -> Self {
+        self.agent_uris = uris;
+        self
+    }
+
+    /// Adds a single further agent URI; see [`agent_uris`](Self::agent_uris).
+    #[must_use]
+    pub fn add_agent_uri(mut self, uri: impl Into<String>) -> Self {
+        self.agent_uris.push(uri.into());
+        self
+    }
+
     /// Sets the capabilities granted.
     #[must_use]
     pub fn capabilities(mut self, caps: Vec<String>) -> Self {
@@ -219,6 +660,13 @@ impl AttestationClaimsBuilder {
         self
     }
 
+    /// Sets the capabilities granted from typed capability paths.
+    #[must_use]
+    pub fn capability_paths(mut self, paths: Vec<CapabilityPath>) -> Self {
+        self.capabilities = paths.into_iter().map(|path| path.to_string()).collect();
+        self
+    }
+
     /// Adds a single capability.
     #[must_use]
     pub fn add_capability(mut self, cap: impl Into<String>) -> Self {
@@ -226,6 +674,30 @@ impl AttestationClaimsBuilder {
         self
     }
 
+    /// Adds a single capability restricted by `constraints`.
+    #[must_use]
+    pub fn add_constrained_capability(
+        mut self,
+        cap: impl Into<String>,
+        constraints: CapabilityConstraints,
+    ) -> Self {
+        let cap = cap.into();
+        self.capabilities.push(cap.clone());
+        self.capability_constraints.insert(cap, constraints);
+        self
+    }
+
+    /// Excludes a capability subtree from the granted capabilities.
+    ///
+    /// Denials are evaluated after positive matches, so granting `workflow`
+    /// and denying `workflow/payments` covers everything under `workflow`
+    /// except the payments subtree.
+    #[must_use]
+    pub fn deny_capability(mut self, cap: impl Into<String>) -> Self {
+        self.denied_capabilities.push(cap.into());
+        self
+    }
+
     /// Sets the issuer (trust root).
     #[must_use]
     pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
@@ -247,30 +719,129 @@ impl AttestationClaimsBuilder {
         self
     }
 
-    /// Builds the claims.
+    /// Sets the optional not-before time.
+    ///
+    /// Verifiers reject the token until this time has been reached.
+    #[must_use]
+    pub fn not_before(mut self, nbf: DateTime<Utc>) -> Self {
+        self.not_before = Some(nbf);
+        self
+    }
+
+    /// Sets the token's index in the issuer's status list.
+    ///
+    /// Verifiers configured with a status list source reject the token once
+    /// the bit at this index is set.
+    #[must_use]
+    pub fn status_index(mut self, index: u64) -> Self {
+        self.status_index = Some(index);
+        self
+    }
+
+    /// Binds the token to a holder key through a `cnf` claim.
+    ///
+    /// Verifiers using [`Verifier::verify_with_pop`](crate::Verifier::verify_with_pop)
+    /// then require a signature by the holder's private key, so the token
+    /// alone is no longer sufficient.
+    #[must_use]
+    pub fn holder_key(mut self, key: VerifyingKey) -> Self {
+        self.holder_key = Some(key);
+        self
+    }
+
+    /// Embeds a verifier-supplied challenge nonce.
+    ///
+    /// [`Verifier::verify_with_nonce`](crate::Verifier::verify_with_nonce)
+    /// accepts the token only for this nonce, which lets a verifier that
+    /// remembers its outstanding challenges accept each token once.
+    #[must_use]
+    pub fn nonce(mut self, nonce: impl Into<String>) -> Self {
+        self.nonce = Some(nonce.into());
+        self
+    }
+
+    /// Sets the issuance time; `exp` is computed from it and the TTL.
+    ///
+    /// Defaults to the current system time when [`build`](Self::build) is
+    /// called. An [`Issuer`](crate::Issuer) sets it from its
+    /// [`Clock`](crate::Clock).
+    #[must_use]
+    pub fn issued_at(mut self, iat: DateTime<Utc>) -> Self {
+        self.issued_at = Some(iat);
+        self
+    }
+
+    /// Builds the claims in canonical form.
+    ///
+    /// The agent URI is normalized by parsing it, and capabilities and
+    /// denials are sorted with duplicates removed, so semantically identical
+    /// claims always serialize identically.
     ///
     /// # Errors
     ///
-    /// Returns `AttestationError::MissingField` if required fields are not set.
+    /// Returns `AttestationError::MissingField` if required fields are not set,
+    /// `AttestationError::InvalidClaims` if an agent URI does not parse, or
+    /// `AttestationError::LengthLimitExceeded` if the capabilities exceed the
+    /// grammar limits (64 entries of at most 128 characters each) or more
+    /// than 32 further agent URIs are listed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::AttestationClaimsBuilder;
+    ///
+    /// let claims = AttestationClaimsBuilder::new()
+    ///     .agent_uri("agent://acme.com/workflow/rule_01h455vb4pex5vsknk084sn02q")
+    ///     .add_capability("workflow/execute")
+    ///     .add_capability("workflow/approve")
+    ///     .add_capability("workflow/execute")
+    ///     .issuer("acme.com")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(claims.capabilities, ["workflow/approve", "workflow/execute"]);
+    /// ```
     pub fn build(self) -> Result<AttestationClaims, AttestationError> {
         let agent_uri = self.agent_uri.ok_or(AttestationError::MissingField {
             field: "agent_uri",
         })?;
+        let agent_uri = canonical_uri(&agent_uri, "agent_uri")?;
+        let mut agent_uris = self
+            .agent_uris
+            .iter()
+            .map(|uri| canonical_uri(uri, "agent_uris"))
+            .collect::<Result<Vec<_>, _>>()?;
+        agent_uris.sort_unstable();
+        agent_uris.dedup();
+        agent_uris.retain(|uri| *uri != agent_uri);
+        limits::check_agent_uris(&agent_uris)?;
         let issuer = self.issuer.ok_or(AttestationError::MissingField {
             field: "issuer",
         })?;
 
-        let now = Utc::now();
+        let now = self.issued_at.unwrap_or_else(Utc::now);
         let exp = now
             + chrono::Duration::from_std(self.ttl).map_err(|_| AttestationError::InvalidTtl)?;
 
         Ok(AttestationClaims {
             agent_uri,
-            capabilities: self.capabilities,
+            agent_uris,
+            capabilities: canonical_capabilities(self.capabilities, "capabilities")?,
+            capability_constraints: self.capability_constraints,
+            denied_capabilities: canonical_capabilities(
+                self.denied_capabilities,
+                "denied_capabilities",
+            )?,
             iss: issuer,
             iat: now,
             exp,
+            nbf: self.not_before,
             aud: self.audience,
+            status_idx: self.status_index,
+            ver: CLAIMS_VERSION,
+            cnf: self.holder_key,
+            nonce: self.nonce,
+            orig_iat: None,
         })
     }
 }
@@ -281,6 +852,27 @@ impl Default for AttestationClaimsBuilder {
     }
 }
 
+/// Normalizes the agent URI `uri` of claim `field` by parsing it.
+fn canonical_uri(uri: &str, field: &str) -> Result<String, AttestationError> {
+    AgentUri::parse(uri)
+        .map(|parsed| parsed.to_string())
+        .map_err(|e| AttestationError::InvalidClaims {
+            reason: format!("invalid {field} '{uri}': {e}"),
+        })
+}
+
+/// Sorts and deduplicates the capability list `field`, enforcing the
+/// grammar limits.
+fn canonical_capabilities(
+    mut caps: Vec<String>,
+    field: &'static str,
+) -> Result<Vec<String>, AttestationError> {
+    caps.sort_unstable();
+    caps.dedup();
+    limits::check_capabilities(field, &caps)?;
+    Ok(caps)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -302,6 +894,55 @@ mod tests {
         assert!(claims.aud.is_none());
     }
 
+    #[test]
+    fn builder_canonicalizes_capabilities_and_uri() {
+        let build = |caps: &[&str]| {
+            caps.iter()
+                .fold(AttestationClaimsBuilder::new(), |b, cap| b.add_capability(*cap))
+                .deny_capability("b/x")
+                .deny_capability("b/x")
+                .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+                .issuer("acme.com")
+                .build()
+                .unwrap()
+        };
+
+        let a = build(&["b", "a", "b"]);
+        let b = build(&["a", "b"]);
+        assert_eq!(a.capabilities, ["a", "b"]);
+        assert_eq!(a.capabilities, b.capabilities);
+        assert_eq!(a.denied_capabilities, ["b/x"]);
+    }
+
+    #[test]
+    fn builder_rejects_unparseable_uri() {
+        let result = AttestationClaimsBuilder::new()
+            .agent_uri("https://acme.com/test")
+            .issuer("acme.com")
+            .build();
+
+        assert!(matches!(result, Err(AttestationError::InvalidClaims { .. })));
+    }
+
+    #[test]
+    fn builder_enforces_capability_limits() {
+        let base = || {
+            AttestationClaimsBuilder::new()
+                .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+                .issuer("acme.com")
+        };
+
+        let too_long = base().add_capability("a".repeat(limits::MAX_CAPABILITY_LEN + 1)).build();
+        assert!(matches!(too_long, Err(AttestationError::LengthLimitExceeded { .. })));
+
+        let caps = (0..=limits::MAX_CAPABILITIES).map(|i| format!("cap{i}")).collect();
+        let too_many = base().capabilities(caps).build();
+        assert!(matches!(too_many, Err(AttestationError::LengthLimitExceeded { .. })));
+
+        let caps = (0..limits::MAX_CAPABILITIES).map(|i| format!("cap{i}")).collect();
+        assert!(base().capabilities(caps).build().is_ok());
+    }
+
     #[test]
     fn builder_requires_agent_uri() {
         let result = AttestationClaimsBuilder::new().issuer("acme.com").build();
@@ -337,6 +978,69 @@ mod tests {
         assert_eq!(claims.capabilities, vec!["read", "write"]);
     }
 
+    #[test]
+    fn capability_paths_roundtrip() {
+        let paths = vec![
+            CapabilityPath::parse("workflow/approval").unwrap(),
+            CapabilityPath::parse("assistant").unwrap(),
+        ];
+        let claims = AttestationClaimsBuilder::new()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .capability_paths(paths.clone())
+            .build()
+            .unwrap();
+
+        assert_eq!(claims.capabilities, vec!["assistant", "workflow/approval"]);
+        let mut expected = paths;
+        expected.sort();
+        assert_eq!(claims.capability_paths().unwrap(), expected);
+    }
+
+    #[test]
+    fn capability_paths_rejects_free_form_capabilities() {
+        let claims = AttestationClaimsBuilder::new()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .add_capability("Not A Path")
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            claims.capability_paths(),
+            Err(AttestationError::InvalidClaims { .. })
+        ));
+    }
+
+    #[test]
+    fn builder_with_constrained_capability() {
+        let claims = AttestationClaimsBuilder::new()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .add_capability("read")
+            .add_constrained_capability("write", CapabilityConstraints::new().max_uses(1))
+            .build()
+            .unwrap();
+
+        assert_eq!(claims.capabilities, vec!["read", "write"]);
+        assert_eq!(claims.capability_constraints.len(), 1);
+        assert_eq!(claims.capability_constraints["write"].max_uses, Some(1));
+    }
+
+    #[test]
+    fn builder_with_denied_capability() {
+        let claims = AttestationClaimsBuilder::new()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .add_capability("workflow")
+            .deny_capability("workflow/payments")
+            .build()
+            .unwrap();
+
+        assert_eq!(claims.capabilities, vec!["workflow"]);
+        assert_eq!(claims.denied_capabilities, vec!["workflow/payments"]);
+    }
+
     #[test]
     fn builder_with_audience() {
         let claims = AttestationClaimsBuilder::new()
@@ -349,12 +1053,53 @@ mod tests {
         assert_eq!(claims.aud, Some("api.acme.com".to_string()));
     }
 
+    #[test]
+    fn builder_with_not_before() {
+        let nbf = Utc::now() + chrono::Duration::minutes(5);
+        let claims = AttestationClaimsBuilder::new()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .not_before(nbf)
+            .build()
+            .unwrap();
+
+        assert_eq!(claims.nbf, Some(nbf));
+    }
+
+    #[test]
+    fn decode_unverified_ignores_signature() {
+        let issuer = crate::Issuer::generate("acme.com", Duration::from_hours(1));
+        let claims = AttestationClaimsBuilder::new()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .add_capability("read")
+            .build()
+            .unwrap();
+        let token = issuer.issue_claims(&claims).unwrap();
+
+        // Flip a signature byte; the claims are still readable
+        let mut tampered = token.into_bytes();
+        let sig_byte = tampered.len() - 10;
+        tampered[sig_byte] = if tampered[sig_byte] == b'A' { b'B' } else { b'A' };
+        let tampered = String::from_utf8(tampered).unwrap();
+
+        let decoded = AttestationClaims::decode_unverified(&tampered).unwrap();
+
+        assert_eq!(decoded.agent_uri, claims.agent_uri);
+        assert_eq!(decoded.capabilities, claims.capabilities);
+    }
+
+    #[test]
+    fn decode_unverified_rejects_garbage() {
+        assert!(AttestationClaims::decode_unverified("not a token").is_err());
+    }
+
     #[test]
     fn builder_with_custom_ttl() {
         let claims = AttestationClaimsBuilder::new()
             .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
             .issuer("acme.com")
-            .ttl(Duration::from_secs(3600))
+            .ttl(Duration::from_hours(1))
             .build()
             .unwrap();
 
@@ -390,13 +1135,49 @@ mod tests {
         let claims = AttestationClaimsBuilder::new()
             .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
             .issuer("acme.com")
-            .ttl(Duration::from_secs(3600))
+            .ttl(Duration::from_hours(1))
             .build()
             .unwrap();
 
         assert!(!claims.is_expired());
     }
 
+    #[test]
+    fn builder_canonicalizes_fleet_members() {
+        let primary = "agent://acme.com/fleet/drone_01h455vb4pex5vsknk084sn02q";
+        let member = "agent://acme.com/fleet/drone_01h455vb4pex5vsknk084sn02r";
+        let claims = AttestationClaimsBuilder::new()
+            .agent_uri(primary)
+            .agent_uris(vec![member.into(), primary.into(), member.into()])
+            .issuer("acme.com")
+            .build()
+            .unwrap();
+
+        assert_eq!(claims.agent_uris, [member]);
+        assert!(claims.attests(primary));
+        assert!(claims.attests(member));
+        assert!(!claims.attests("agent://acme.com/fleet/drone_01h455vb4pex5vsknk084sn02s"));
+    }
+
+    #[test]
+    fn builder_rejects_invalid_or_excess_fleet_members() {
+        let builder = AttestationClaimsBuilder::new()
+            .agent_uri("agent://acme.com/fleet/drone_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com");
+        assert!(matches!(
+            builder.clone().add_agent_uri("not a uri").build(),
+            Err(AttestationError::InvalidClaims { .. })
+        ));
+
+        let members = (0..=limits::MAX_AGENT_URIS)
+            .map(|i| format!("agent://acme.com/fleet/{i}/drone_01h455vb4pex5vsknk084sn02q"))
+            .collect();
+        assert!(matches!(
+            builder.agent_uris(members).build(),
+            Err(AttestationError::LengthLimitExceeded { .. })
+        ));
+    }
+
     #[test]
     fn claims_serialization_roundtrip() {
         let original = AttestationClaimsBuilder::new()
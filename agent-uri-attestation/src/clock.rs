@@ -0,0 +1,163 @@
+//! Time sources for issuance and verification.
+
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use chrono::{DateTime, Utc};
+
+/// Supplies the current time to an [`Issuer`](crate::Issuer) or
+/// [`Verifier`](crate::Verifier).
+///
+/// Every timestamp an issuer writes (`iat`, `exp`) and every validity check
+/// a verifier makes (`exp`, `nbf`, policy age limits, cache expiry) reads
+/// the time from its clock. The default is [`SystemClock`]; tests can use a
+/// [`ManualClock`] to move time forward without sleeping, and embedded
+/// deployments can plug in a trusted time source.
+///
+/// Any `Fn() -> DateTime<Utc>` closure is a clock.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use agent_uri_attestation::Verifier;
+///
+/// let verifier = Verifier::new().with_clock(|| Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap());
+/// # let _ = verifier;
+/// ```
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> DateTime<Utc> + Send + Sync,
+{
+    fn now(&self) -> DateTime<Utc> {
+        self()
+    }
+}
+
+/// The operating system's wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// Clones share the same time, so a test can hand one clone to an issuer
+/// and a verifier and advance both at once.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use agent_uri::AgentUri;
+/// use agent_uri_attestation::{AttestationError, Issuer, ManualClock, Verifier};
+///
+/// let clock = ManualClock::new(chrono::Utc::now());
+/// let issuer = Issuer::generate("acme.com", Duration::from_secs(60)).with_clock(clock.clone());
+/// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let token = issuer.issue(&uri, vec![]).unwrap();
+///
+/// let mut verifier = Verifier::new().with_clock(clock.clone());
+/// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+/// assert!(verifier.verify(&token).is_ok());
+///
+/// clock.advance(Duration::from_secs(61));
+/// assert!(matches!(
+///     verifier.verify(&token),
+///     Err(AttestationError::TokenExpired { .. })
+/// ));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl ManualClock {
+    /// Creates a clock stopped at `now`.
+    #[must_use]
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Sets the clock to `now`, which may be in the past.
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) = now;
+    }
+
+    /// Moves the clock forward by `by`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the resulting time is out of range for `DateTime<Utc>`.
+    pub fn advance(&self, by: std::time::Duration) {
+        let by = chrono::Duration::from_std(by).expect("duration out of range");
+        let mut now = self.now.lock().unwrap_or_else(PoisonError::into_inner);
+        *now = now.checked_add_signed(by).expect("time out of range");
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A shareable handle to the clock held by an issuer or verifier.
+#[derive(Clone)]
+pub(crate) struct SharedClock(pub(crate) Arc<dyn Clock>);
+
+impl Clock for SharedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0.now()
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self(Arc::new(SystemClock))
+    }
+}
+
+impl fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Clock")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn manual_clock_clones_share_time() {
+        let start = Utc::now();
+        let clock = ManualClock::new(start);
+        let other = clock.clone();
+
+        other.advance(Duration::from_secs(90));
+        assert_eq!(clock.now(), start + chrono::Duration::seconds(90));
+
+        clock.set(start);
+        assert_eq!(other.now(), start);
+    }
+
+    #[test]
+    fn closures_are_clocks() {
+        let fixed = Utc::now();
+        let clock = SharedClock(Arc::new(move || fixed));
+        assert_eq!(clock.now(), fixed);
+    }
+}
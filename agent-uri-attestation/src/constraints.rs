@@ -0,0 +1,431 @@
+//! Per-capability constraints.
+//!
+//! A token-wide `exp` is too coarse for attestations that mix routine and
+//! sensitive capabilities. Individual capabilities can therefore carry
+//! [`CapabilityConstraints`]: their own expiry, a usage limit, resource
+//! scopes, and numeric limits.
+//!
+//! # Wire Format
+//!
+//! Constraints travel inside the `capabilities` array. Unconstrained
+//! capabilities stay plain strings; constrained ones become objects with the
+//! capability under `cap` and the constraints alongside it:
+//!
+//! ```json
+//! "capabilities": [
+//!     "workflow/read",
+//!     {"cap": "workflow/approval", "max_amount": 1000, "resources": ["invoices"]}
+//! ]
+//! ```
+//!
+//! Verifiers that predate constraints cannot parse such an array and see no
+//! capabilities at all, so constrained grants fail closed rather than being
+//! silently widened.
+
+use std::collections::BTreeMap;
+
+use agent_uri::CapabilityPath;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AttestationError;
+use crate::verification::covers;
+
+/// Restrictions attached to a single granted capability.
+///
+/// All constraints are optional; an empty set restricts nothing.
+///
+/// Numeric limits are named `max_<quantity>` and bound the quantity
+/// `<quantity>` supplied in a [`CapabilityRequest`]. For example, the limit
+/// `max_amount` bounds the request quantity `amount`.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri_attestation::CapabilityConstraints;
+///
+/// let constraints = CapabilityConstraints::new()
+///     .max_uses(10)
+///     .resource("invoices")
+///     .limit("max_amount", 1000);
+///
+/// assert_eq!(constraints.limits.get("max_amount"), Some(&1000));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityConstraints {
+    /// When this capability stops being valid, independent of the token `exp`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<DateTime<Utc>>,
+    /// Maximum number of times the capability may be exercised
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_uses: Option<u64>,
+    /// Resource scopes the capability is restricted to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resources: Vec<String>,
+    /// Named numeric limits such as `max_amount`
+    #[serde(flatten)]
+    pub limits: BTreeMap<String, u64>,
+}
+
+impl CapabilityConstraints {
+    /// Creates an empty constraint set.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the capability-specific expiry.
+    #[must_use]
+    pub fn expires_at(mut self, exp: DateTime<Utc>) -> Self {
+        self.exp = Some(exp);
+        self
+    }
+
+    /// Sets the maximum number of uses.
+    #[must_use]
+    pub fn max_uses(mut self, max_uses: u64) -> Self {
+        self.max_uses = Some(max_uses);
+        self
+    }
+
+    /// Adds a resource scope.
+    ///
+    /// A scope covers the resource with the same name and every resource
+    /// below it (`invoices` covers `invoices/2024/17`).
+    #[must_use]
+    pub fn resource(mut self, scope: impl Into<String>) -> Self {
+        self.resources.push(scope.into());
+        self
+    }
+
+    /// Adds a numeric limit, conventionally named `max_<quantity>`.
+    #[must_use]
+    pub fn limit(mut self, name: impl Into<String>, value: u64) -> Self {
+        self.limits.insert(name.into(), value);
+        self
+    }
+
+    /// Returns true if no constraint is set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.exp.is_none()
+            && self.max_uses.is_none()
+            && self.resources.is_empty()
+            && self.limits.is_empty()
+    }
+
+    /// Checks a request against these constraints.
+    ///
+    /// Resource scopes fail closed: a request without a resource does not
+    /// satisfy a resource-scoped capability. Usage and numeric limits only
+    /// apply when the request supplies the corresponding value, since a
+    /// stateless verifier cannot know them otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::CapabilityConstraintViolated` naming
+    /// `capability` and the first violated constraint.
+    pub fn check(
+        &self,
+        capability: &str,
+        request: &CapabilityRequest,
+    ) -> Result<(), AttestationError> {
+        let violated = |reason: String| AttestationError::CapabilityConstraintViolated {
+            capability: capability.to_string(),
+            reason,
+        };
+
+        if let Some(exp) = self.exp
+            && request.now >= exp
+        {
+            return Err(violated(format!(
+                "capability expired at {}",
+                exp.to_rfc3339()
+            )));
+        }
+
+        if let (Some(max_uses), Some(uses)) = (self.max_uses, request.prior_uses)
+            && uses >= max_uses
+        {
+            return Err(violated(format!(
+                "usage limit of {max_uses} reached ({uses} prior uses)"
+            )));
+        }
+
+        if !self.resources.is_empty() {
+            let permitted = request.resource.as_deref().is_some_and(|resource| {
+                self.resources.iter().any(|scope| covers(scope, resource))
+            });
+            if !permitted {
+                return Err(violated(format!(
+                    "resource {:?} is outside the permitted scopes {:?}",
+                    request.resource, self.resources
+                )));
+            }
+        }
+
+        for (quantity, value) in &request.quantities {
+            if let Some(limit) = self.limits.get(&format!("max_{quantity}"))
+                && value > limit
+            {
+                return Err(violated(format!(
+                    "{quantity} of {value} exceeds the limit of {limit}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A request to exercise a capability, checked against constraints.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::CapabilityPath;
+/// use agent_uri_attestation::CapabilityRequest;
+/// use chrono::Utc;
+///
+/// let request = CapabilityRequest::new(CapabilityPath::parse("workflow/approval").unwrap(), Utc::now())
+///     .resource("invoices/2024/17")
+///     .quantity("amount", 250)
+///     .prior_uses(3);
+///
+/// assert_eq!(request.path().as_str(), "workflow/approval");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityRequest {
+    path: CapabilityPath,
+    now: DateTime<Utc>,
+    resource: Option<String>,
+    prior_uses: Option<u64>,
+    quantities: BTreeMap<String, u64>,
+}
+
+impl CapabilityRequest {
+    /// Creates a request for `path` evaluated at time `now`.
+    #[must_use]
+    pub fn new(path: CapabilityPath, now: DateTime<Utc>) -> Self {
+        Self {
+            path,
+            now,
+            resource: None,
+            prior_uses: None,
+            quantities: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the resource the capability is exercised on.
+    #[must_use]
+    pub fn resource(mut self, resource: impl Into<String>) -> Self {
+        self.resource = Some(resource.into());
+        self
+    }
+
+    /// Sets how many times the capability has already been used.
+    #[must_use]
+    pub fn prior_uses(mut self, uses: u64) -> Self {
+        self.prior_uses = Some(uses);
+        self
+    }
+
+    /// Sets a named quantity, checked against the `max_<name>` limit.
+    #[must_use]
+    pub fn quantity(mut self, name: impl Into<String>, value: u64) -> Self {
+        self.quantities.insert(name.into(), value);
+        self
+    }
+
+    /// Returns the requested capability path.
+    #[must_use]
+    pub fn path(&self) -> &CapabilityPath {
+        &self.path
+    }
+
+    /// Returns the time the request is evaluated at.
+    #[must_use]
+    pub fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+}
+
+/// A structured entry in the `capabilities` wire array.
+#[derive(Serialize, Deserialize)]
+struct ConstrainedEntry {
+    cap: String,
+    #[serde(flatten)]
+    constraints: CapabilityConstraints,
+}
+
+/// Either form an entry in the `capabilities` wire array may take.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum WireCapability {
+    Plain(String),
+    Constrained(ConstrainedEntry),
+}
+
+/// Encodes capabilities and their constraints as the `capabilities` claim.
+pub(crate) fn to_wire(
+    capabilities: &[String],
+    constraints: &BTreeMap<String, CapabilityConstraints>,
+) -> Result<serde_json::Value, AttestationError> {
+    let entries: Vec<WireCapability> = capabilities
+        .iter()
+        .map(|cap| match constraints.get(cap) {
+            Some(c) if !c.is_empty() => WireCapability::Constrained(ConstrainedEntry {
+                cap: cap.clone(),
+                constraints: c.clone(),
+            }),
+            _ => WireCapability::Plain(cap.clone()),
+        })
+        .collect();
+
+    serde_json::to_value(entries).map_err(|e| AttestationError::InvalidClaims {
+        reason: format!("invalid capabilities: {e}"),
+    })
+}
+
+/// Decodes the `capabilities` claim into capabilities and their constraints.
+pub(crate) fn from_wire(
+    value: &serde_json::Value,
+) -> Result<(Vec<String>, BTreeMap<String, CapabilityConstraints>), AttestationError> {
+    let entries: Vec<WireCapability> =
+        serde_json::from_value(value.clone()).map_err(|e| AttestationError::InvalidClaims {
+            reason: format!("invalid capabilities claim: {e}"),
+        })?;
+
+    let mut capabilities = Vec::with_capacity(entries.len());
+    let mut constraints = BTreeMap::new();
+    for entry in entries {
+        match entry {
+            WireCapability::Plain(cap) => capabilities.push(cap),
+            WireCapability::Constrained(ConstrainedEntry {
+                cap,
+                constraints: c,
+            }) => {
+                capabilities.push(cap.clone());
+                constraints.insert(cap, c);
+            }
+        }
+    }
+    Ok((capabilities, constraints))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn request() -> CapabilityRequest {
+        CapabilityRequest::new(
+            CapabilityPath::parse("workflow/approval").unwrap(),
+            Utc::now(),
+        )
+    }
+
+    #[test]
+    fn empty_constraints_allow_everything() {
+        assert!(
+            CapabilityConstraints::new()
+                .check("workflow", &request())
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn capability_expiry_is_enforced() {
+        let constraints =
+            CapabilityConstraints::new().expires_at(Utc::now() - Duration::seconds(1));
+
+        assert!(matches!(
+            constraints.check("workflow", &request()),
+            Err(AttestationError::CapabilityConstraintViolated { .. })
+        ));
+    }
+
+    #[test]
+    fn usage_limit_applies_only_when_uses_known() {
+        let constraints = CapabilityConstraints::new().max_uses(3);
+
+        assert!(constraints.check("workflow", &request()).is_ok());
+        assert!(
+            constraints
+                .check("workflow", &request().prior_uses(2))
+                .is_ok()
+        );
+        assert!(
+            constraints
+                .check("workflow", &request().prior_uses(3))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn resource_scopes_fail_closed() {
+        let constraints = CapabilityConstraints::new().resource("invoices");
+
+        assert!(constraints.check("workflow", &request()).is_err());
+        assert!(
+            constraints
+                .check("workflow", &request().resource("invoices/17"))
+                .is_ok()
+        );
+        assert!(
+            constraints
+                .check("workflow", &request().resource("invoicesx"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn numeric_limits_bound_matching_quantities() {
+        let constraints = CapabilityConstraints::new().limit("max_amount", 1000);
+
+        assert!(
+            constraints
+                .check("workflow", &request().quantity("amount", 1000))
+                .is_ok()
+        );
+        assert!(
+            constraints
+                .check("workflow", &request().quantity("amount", 1001))
+                .is_err()
+        );
+        assert!(
+            constraints
+                .check("workflow", &request().quantity("count", 5000))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn wire_format_roundtrip() {
+        let capabilities = vec!["workflow/read".to_string(), "workflow/approval".to_string()];
+        let mut constraints = BTreeMap::new();
+        constraints.insert(
+            "workflow/approval".to_string(),
+            CapabilityConstraints::new().limit("max_amount", 1000),
+        );
+
+        let wire = to_wire(&capabilities, &constraints).unwrap();
+        assert_eq!(
+            wire,
+            serde_json::json!(["workflow/read", {"cap": "workflow/approval", "max_amount": 1000}])
+        );
+
+        let (decoded_caps, decoded_constraints) = from_wire(&wire).unwrap();
+        assert_eq!(decoded_caps, capabilities);
+        assert_eq!(decoded_constraints, constraints);
+    }
+
+    #[test]
+    fn plain_string_array_decodes_without_constraints() {
+        let (caps, constraints) = from_wire(&serde_json::json!(["read", "write"])).unwrap();
+
+        assert_eq!(caps, vec!["read", "write"]);
+        assert!(constraints.is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+//! Co-signed attestations vouched for by several issuers.
+//!
+//! PASETO v4.public tokens carry exactly one signature. A co-signed
+//! attestation is therefore a bundle of tokens, one per issuer, that all
+//! attest the same subject: the same agent URI, capabilities and audience.
+//! The bundle is serialized by joining the tokens with `~`, a character that
+//! never appears in a PASETO token, and holds at most
+//! [`CoSignedAttestation::MAX_COSIGNERS`] tokens.
+//!
+//! Verification is done with [`Verifier::verify_cosigned`].
+//!
+//! [`Verifier::verify_cosigned`]: crate::Verifier::verify_cosigned
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::claims::AttestationClaims;
+use crate::error::AttestationError;
+use crate::issuer::Issuer;
+use crate::limits;
+
+/// A set of attestation tokens from different issuers over the same subject.
+///
+/// The first token is the primary attestation; its claims are the ones
+/// returned by verification.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri_attestation::{AttestationClaims, CoSignedAttestation, Issuer, Verifier};
+/// use std::time::Duration;
+///
+/// let platform = Issuer::generate("acme.com", Duration::from_secs(3600));
+/// let compliance = Issuer::generate("audit.example.org", Duration::from_secs(3600));
+///
+/// let claims = AttestationClaims::builder()
+///     .agent_uri("agent://acme.com/payments/settle/agent_01h455vb4pex5vsknk084sn02q")
+///     .issuer("acme.com")
+///     .add_capability("payments/settle")
+///     .ttl(Duration::from_secs(3600))
+///     .build()
+///     .unwrap();
+///
+/// let cosigned = CoSignedAttestation::issue(&claims, &[&platform, &compliance]).unwrap();
+/// let encoded = cosigned.to_string();
+///
+/// let mut verifier = Verifier::new();
+/// verifier.add_trusted_root("acme.com", platform.verifying_key());
+/// verifier.add_trusted_root("audit.example.org", compliance.verifying_key());
+///
+/// let verified = verifier
+///     .verify_cosigned(&encoded, &["acme.com", "audit.example.org"])
+///     .unwrap();
+/// assert_eq!(verified.iss, "acme.com");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoSignedAttestation {
+    tokens: Vec<String>,
+}
+
+impl CoSignedAttestation {
+    /// Separator placed between tokens in the serialized form.
+    pub const SEPARATOR: char = '~';
+
+    /// Maximum number of tokens in a bundle, bounding the signature checks
+    /// one bundle can cost a verifier.
+    pub const MAX_COSIGNERS: usize = 16;
+
+    /// Creates a co-signed attestation from already issued tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidTokenFormat` if `tokens` is empty,
+    /// holds more than [`MAX_COSIGNERS`](Self::MAX_COSIGNERS) tokens, or any
+    /// token contains the separator character, and
+    /// `AttestationError::LengthLimitExceeded` if a token is oversized.
+    pub fn from_tokens(tokens: Vec<String>) -> Result<Self, AttestationError> {
+        if tokens.is_empty() {
+            return Err(AttestationError::InvalidTokenFormat {
+                reason: "co-signed attestation contains no tokens".to_string(),
+            });
+        }
+        if tokens.len() > Self::MAX_COSIGNERS {
+            return Err(Self::too_many_tokens());
+        }
+        if tokens.iter().any(|t| t.is_empty() || t.contains(Self::SEPARATOR)) {
+            return Err(AttestationError::InvalidTokenFormat {
+                reason: format!(
+                    "co-signed tokens must be non-empty and must not contain '{}'",
+                    Self::SEPARATOR
+                ),
+            });
+        }
+        tokens.iter().try_for_each(|token| limits::check_token(token))?;
+        Ok(Self { tokens })
+    }
+
+    fn too_many_tokens() -> AttestationError {
+        AttestationError::InvalidTokenFormat {
+            reason: format!(
+                "co-signed attestation holds more than {} tokens",
+                Self::MAX_COSIGNERS
+            ),
+        }
+    }
+
+    /// Issues the same claims once per issuer.
+    ///
+    /// Each token carries the claims with `iss` replaced by the respective
+    /// issuer's trust root. The first issuer produces the primary token.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if `issuers` is empty or any issuer fails
+    /// to sign.
+    pub fn issue(
+        claims: &AttestationClaims,
+        issuers: &[&Issuer],
+    ) -> Result<Self, AttestationError> {
+        let tokens = issuers
+            .iter()
+            .map(|issuer| {
+                let mut claims = claims.clone();
+                claims.iss = issuer.trust_root().to_string();
+                issuer.issue_claims(&claims)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_tokens(tokens)
+    }
+
+    /// Returns the individual tokens, primary first.
+    #[must_use]
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// Returns the primary token.
+    #[must_use]
+    pub fn primary(&self) -> &str {
+        &self.tokens[0]
+    }
+}
+
+impl fmt::Display for CoSignedAttestation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, token) in self.tokens.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", Self::SEPARATOR)?;
+            }
+            f.write_str(token)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CoSignedAttestation {
+    type Err = AttestationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Count before allocating so an oversized bundle costs one scan
+        if s.split(Self::SEPARATOR).nth(Self::MAX_COSIGNERS).is_some() {
+            return Err(Self::too_many_tokens());
+        }
+        Self::from_tokens(s.split(Self::SEPARATOR).map(String::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn claims() -> AttestationClaims {
+        AttestationClaims::builder()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn issue_produces_one_token_per_issuer() {
+        let a = Issuer::generate("acme.com", Duration::from_hours(1));
+        let b = Issuer::generate("audit.example.org", Duration::from_hours(1));
+
+        let cosigned = CoSignedAttestation::issue(&claims(), &[&a, &b]).unwrap();
+
+        assert_eq!(cosigned.tokens().len(), 2);
+        assert!(cosigned.primary().starts_with("v4.public."));
+    }
+
+    #[test]
+    fn display_and_parse_roundtrip() {
+        let a = Issuer::generate("acme.com", Duration::from_hours(1));
+        let b = Issuer::generate("audit.example.org", Duration::from_hours(1));
+        let cosigned = CoSignedAttestation::issue(&claims(), &[&a, &b]).unwrap();
+
+        let parsed: CoSignedAttestation = cosigned.to_string().parse().unwrap();
+
+        assert_eq!(parsed, cosigned);
+    }
+
+    #[test]
+    fn empty_bundle_is_rejected() {
+        assert!(matches!(
+            CoSignedAttestation::issue(&claims(), &[]),
+            Err(AttestationError::InvalidTokenFormat { .. })
+        ));
+        assert!("".parse::<CoSignedAttestation>().is_err());
+        assert!("v4.public.a~~v4.public.b".parse::<CoSignedAttestation>().is_err());
+    }
+
+    #[test]
+    fn oversized_bundles_are_rejected() {
+        let max = CoSignedAttestation::MAX_COSIGNERS;
+        let token = Issuer::generate("acme.com", Duration::from_hours(1))
+            .issue_claims(&claims())
+            .unwrap();
+        assert!(CoSignedAttestation::from_tokens(vec![token.clone(); max]).is_ok());
+        assert!(matches!(
+            CoSignedAttestation::from_tokens(vec![token.clone(); max + 1]),
+            Err(AttestationError::InvalidTokenFormat { .. })
+        ));
+        assert!(matches!(
+            vec![token; max + 1].join("~").parse::<CoSignedAttestation>(),
+            Err(AttestationError::InvalidTokenFormat { .. })
+        ));
+        assert!(matches!(
+            "v4.public.x~".repeat(100_000).parse::<CoSignedAttestation>(),
+            Err(AttestationError::InvalidTokenFormat { .. })
+        ));
+
+        let oversized = format!("v4.public.{}", "A".repeat(limits::MAX_TOKEN_LEN));
+        assert!(matches!(
+            CoSignedAttestation::from_tokens(vec![oversized]),
+            Err(AttestationError::LengthLimitExceeded { .. })
+        ));
+    }
+}
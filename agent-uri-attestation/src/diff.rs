@@ -0,0 +1,254 @@
+//! Structured comparison of two sets of attestation claims.
+
+use std::fmt;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::claims::AttestationClaims;
+use crate::verification;
+
+/// The differences between two sets of attestation claims, as returned by
+/// [`AttestationClaims::diff`].
+///
+/// Capability lists are compared as sets. Changed single-valued fields are
+/// reported as `(previous, current)` pairs and are `None` when unchanged.
+///
+/// [`escalated_capabilities`](Self::escalated_capabilities) flags what review
+/// tooling usually cares about when comparing a renewed token against its
+/// predecessor: capabilities the current claims can exercise that the
+/// previous ones could not. A new grant already covered by a broader
+/// previous grant is listed as added but is not an escalation.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri_attestation::AttestationClaims;
+///
+/// let claims = |caps: &[&str]| {
+///     AttestationClaims::builder()
+///         .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+///         .issuer("acme.com")
+///         .capabilities(caps.iter().map(|c| c.to_string()).collect())
+///         .build()
+///         .unwrap()
+/// };
+/// let previous = claims(&["workflow"]);
+///
+/// let narrowed = previous.diff(&claims(&["workflow/approval"]));
+/// assert_eq!(narrowed.added_capabilities, ["workflow/approval"]);
+/// assert!(!narrowed.is_escalation());
+///
+/// let widened = previous.diff(&claims(&["payments", "workflow"]));
+/// assert_eq!(widened.escalated_capabilities, ["payments"]);
+/// assert!(widened.is_escalation());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClaimsDiff {
+    /// Change of the primary agent URI
+    pub agent_uri: Option<(String, String)>,
+    /// Further agent URIs attested only by the current claims
+    pub added_agent_uris: Vec<String>,
+    /// Further agent URIs attested only by the previous claims
+    pub removed_agent_uris: Vec<String>,
+    /// Change of issuer
+    pub issuer: Option<(String, String)>,
+    /// Change of expiration time
+    pub expiry: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Change of audience restriction
+    pub audience: Option<(Option<String>, Option<String>)>,
+    /// Capabilities granted only by the current claims
+    pub added_capabilities: Vec<String>,
+    /// Capabilities granted only by the previous claims
+    pub removed_capabilities: Vec<String>,
+    /// Capabilities denied only by the current claims
+    pub added_denied_capabilities: Vec<String>,
+    /// Capabilities denied only by the previous claims
+    pub removed_denied_capabilities: Vec<String>,
+    /// Capabilities whose constraints were added, removed or changed
+    pub changed_constraints: Vec<String>,
+    /// Capabilities the current claims can exercise and the previous could
+    /// not: uncovered or unconstrained grants, and dropped denials
+    pub escalated_capabilities: Vec<String>,
+}
+
+impl ClaimsDiff {
+    /// Compares `previous` with `current`.
+    pub(crate) fn new(previous: &AttestationClaims, current: &AttestationClaims) -> Self {
+        Self {
+            agent_uri: changed(&previous.agent_uri, &current.agent_uri),
+            added_agent_uris: difference(&current.agent_uris, &previous.agent_uris),
+            removed_agent_uris: difference(&previous.agent_uris, &current.agent_uris),
+            issuer: changed(&previous.iss, &current.iss),
+            expiry: changed(&previous.exp, &current.exp),
+            audience: changed(&previous.aud, &current.aud),
+            added_capabilities: difference(&current.capabilities, &previous.capabilities),
+            removed_capabilities: difference(&previous.capabilities, &current.capabilities),
+            added_denied_capabilities: difference(
+                &current.denied_capabilities,
+                &previous.denied_capabilities,
+            ),
+            removed_denied_capabilities: difference(
+                &previous.denied_capabilities,
+                &current.denied_capabilities,
+            ),
+            changed_constraints: previous
+                .capability_constraints
+                .keys()
+                .chain(current.capability_constraints.keys())
+                .filter(|cap| {
+                    previous.capability_constraints.get(*cap)
+                        != current.capability_constraints.get(*cap)
+                })
+                .collect::<std::collections::BTreeSet<_>>()
+                .into_iter()
+                .cloned()
+                .collect(),
+            escalated_capabilities: verification::widened_capabilities(previous, current)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Returns true if the claims are equal in every compared field.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns true if the current claims grant more than the previous ones:
+    /// an escalated capability, an additional agent URI, a different issuer,
+    /// or an audience restriction that was lifted or changed.
+    #[must_use]
+    pub fn is_escalation(&self) -> bool {
+        !self.escalated_capabilities.is_empty()
+            || !self.added_agent_uris.is_empty()
+            || self.agent_uri.is_some()
+            || self.issuer.is_some()
+            || self
+                .audience
+                .as_ref()
+                .is_some_and(|(previous, _)| previous.is_some())
+    }
+}
+
+/// Lists one change per line, prefixing additions with `+`, removals with
+/// `-` and changed fields with `~`.
+impl fmt::Display for ClaimsDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = |t: &DateTime<Utc>| t.to_rfc3339_opts(SecondsFormat::Millis, true);
+        let or_none = |value: &Option<String>| value.clone().unwrap_or_else(|| "(none)".into());
+
+        if let Some((previous, current)) = &self.agent_uri {
+            writeln!(f, "~ agent_uri: {previous} -> {current}")?;
+        }
+        if let Some((previous, current)) = &self.issuer {
+            writeln!(f, "~ iss: {previous} -> {current}")?;
+        }
+        if let Some((previous, current)) = &self.expiry {
+            writeln!(f, "~ exp: {} -> {}", time(previous), time(current))?;
+        }
+        if let Some((previous, current)) = &self.audience {
+            writeln!(f, "~ aud: {} -> {}", or_none(previous), or_none(current))?;
+        }
+        let lists = [
+            ('+', "agent_uri", &self.added_agent_uris),
+            ('-', "agent_uri", &self.removed_agent_uris),
+            ('+', "capability", &self.added_capabilities),
+            ('-', "capability", &self.removed_capabilities),
+            ('+', "denied", &self.added_denied_capabilities),
+            ('-', "denied", &self.removed_denied_capabilities),
+            ('~', "constraints", &self.changed_constraints),
+        ];
+        for (sign, label, items) in lists {
+            for item in items {
+                writeln!(f, "{sign} {label}: {item}")?;
+            }
+        }
+        for cap in &self.escalated_capabilities {
+            writeln!(f, "! escalated: {cap}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the `(previous, current)` pair if the values differ.
+fn changed<T: PartialEq + Clone>(previous: &T, current: &T) -> Option<(T, T)> {
+    (previous != current).then(|| (previous.clone(), current.clone()))
+}
+
+/// Returns the items of `a` missing from `b`.
+fn difference(a: &[String], b: &[String]) -> Vec<String> {
+    a.iter().filter(|item| !b.contains(item)).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constraints::CapabilityConstraints;
+
+    fn builder() -> crate::AttestationClaimsBuilder {
+        AttestationClaims::builder()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .add_capability("workflow")
+            .deny_capability("workflow/payments")
+    }
+
+    #[test]
+    fn identical_claims_have_an_empty_diff() {
+        let claims = builder().build().unwrap();
+        let diff = claims.diff(&claims);
+        assert!(diff.is_empty());
+        assert!(!diff.is_escalation());
+        assert_eq!(diff.to_string(), "");
+    }
+
+    #[test]
+    fn dropped_denial_is_an_escalation() {
+        let previous = builder().build().unwrap();
+        let current = AttestationClaims {
+            denied_capabilities: vec![],
+            ..previous.clone()
+        };
+
+        let diff = previous.diff(&current);
+        assert_eq!(diff.removed_denied_capabilities, ["workflow/payments"]);
+        assert_eq!(diff.escalated_capabilities, ["workflow/payments"]);
+        assert!(diff.to_string().contains("! escalated: workflow/payments\n"));
+    }
+
+    #[test]
+    fn lifted_constraints_are_an_escalation() {
+        let current = builder().build().unwrap();
+        let mut previous = current.clone();
+        previous
+            .capability_constraints
+            .insert("workflow".into(), CapabilityConstraints::new().max_uses(3));
+
+        let diff = previous.diff(&current);
+        assert_eq!(diff.changed_constraints, ["workflow"]);
+        assert_eq!(diff.escalated_capabilities, ["workflow"]);
+
+        // Adding constraints narrows the grant
+        assert!(previous.diff(&current).is_escalation());
+        assert!(!current.diff(&previous).is_escalation());
+    }
+
+    #[test]
+    fn field_changes_are_reported_as_pairs() {
+        let previous = builder().build().unwrap();
+        let current = AttestationClaims {
+            iss: "evil.com".into(),
+            exp: previous.exp + chrono::Duration::hours(1),
+            aud: Some("api.acme.com".into()),
+            ..previous.clone()
+        };
+
+        let diff = previous.diff(&current);
+        assert_eq!(diff.issuer, Some(("acme.com".into(), "evil.com".into())));
+        assert_eq!(diff.expiry, Some((previous.exp, current.exp)));
+        assert_eq!(diff.audience, Some((None, Some("api.acme.com".into()))));
+        assert!(diff.is_escalation(), "a different issuer is flagged");
+        assert!(diff.to_string().starts_with("~ iss: acme.com -> evil.com\n"));
+    }
+}
@@ -70,9 +70,255 @@ pub enum AttestationError {
         /// The capabilities that were attested in the token
         attested: Vec<String>,
     },
+    /// A required capability is covered but explicitly denied by the token.
+    CapabilityDenied {
+        /// The capability path that was required
+        required: String,
+        /// The denied capability that overlaps the required path
+        denied: String,
+    },
+    /// A covering capability exists but its constraints deny the request.
+    CapabilityConstraintViolated {
+        /// The granted capability whose constraints were violated
+        capability: String,
+        /// Which constraint denied the request
+        reason: String,
+    },
+    /// Token audience does not match the audience required by policy.
+    AudienceMismatch {
+        /// The audience in the token, if any
+        token_audience: Option<String>,
+        /// The audience required by the verification policy
+        expected_audience: String,
+    },
+    /// Token lifetime exceeds the maximum accepted by policy.
+    TtlExceedsMaximum {
+        /// Lifetime of the token (`exp - iat`) in seconds
+        ttl_secs: i64,
+        /// Maximum accepted lifetime in seconds
+        max_secs: u64,
+    },
+    /// Token was issued longer ago than the accepted freshness window.
+    TokenTooOld {
+        /// Time since the token's `iat` in seconds
+        age_secs: i64,
+        /// Maximum accepted age in seconds
+        max_age_secs: u64,
+    },
+    /// The issuer does not match any trust root pattern allowed by policy.
+    TrustRootNotAllowed {
+        /// The issuer that was rejected
+        issuer: String,
+    },
+    /// An attestation chain is empty or otherwise malformed.
+    InvalidChain {
+        /// Description of the chain error
+        reason: String,
+    },
+    /// A delegated token was not issued by the subject of its parent.
+    DelegationMismatch {
+        /// Index of the offending token in the chain
+        link: usize,
+        /// The issuer of the delegated token
+        issuer: String,
+        /// The trust root of the parent token's subject
+        expected_issuer: String,
+    },
+    /// A delegated token claims a capability its parent does not hold.
+    CapabilityEscalation {
+        /// Index of the offending token in the chain
+        link: usize,
+        /// The capability that is not covered by the parent
+        capability: String,
+    },
+    /// A co-signer attests different subject claims than the primary token.
+    CoSignatureMismatch {
+        /// The co-signer whose claims differ
+        issuer: String,
+    },
+    /// A required issuer has not co-signed the attestation.
+    MissingCoSignature {
+        /// The issuer whose signature is missing
+        issuer: String,
+    },
+    /// A transparency log is required but no inclusion proof was supplied.
+    InclusionProofRequired,
+    /// An inclusion proof or tree head failed to check out.
+    InvalidInclusionProof {
+        /// Why the proof was rejected
+        reason: String,
+    },
+    /// An issuance hook refused to issue the token.
+    IssuanceRejected {
+        /// Why issuance was refused
+        reason: String,
+    },
+    /// The token's status list entry marks it as revoked.
+    TokenRevoked {
+        /// The token's index in the issuer's status list
+        index: u64,
+    },
+    /// An online status responder reported the token as revoked.
+    TokenRevokedOnline {
+        /// The issuer of the revoked token
+        issuer: String,
+    },
+    /// The issuer's status list could not be obtained or checked.
+    StatusUnavailable {
+        /// The issuer whose status list was needed
+        issuer: String,
+        /// Why the status list could not be used
+        reason: String,
+    },
+    /// A threshold key set was configured with an unusable threshold.
+    InvalidThreshold {
+        /// The requested threshold
+        threshold: usize,
+        /// The number of keys in the set
+        keys: usize,
+    },
+    /// A claim or the encoded token exceeds a length limit of the grammar.
+    LengthLimitExceeded {
+        /// The claim or component that is too long, e.g. `"capabilities"`
+        field: &'static str,
+        /// Its actual length (characters, bytes or entries)
+        length: usize,
+        /// The maximum the grammar allows
+        max: usize,
+    },
+    /// The token uses a claims schema newer than this library supports.
+    UnsupportedClaimsVersion {
+        /// The token's `ver` claim
+        version: u32,
+        /// The newest version this library supports
+        supported: u32,
+    },
+    /// The presenter could not prove possession of the token's holder key.
+    ProofOfPossessionFailed {
+        /// Why the proof was rejected
+        reason: String,
+    },
+    /// The token's `nonce` claim does not match the verifier's challenge.
+    NonceMismatch {
+        /// The challenge the verifier issued
+        expected: String,
+        /// The nonce in the token, if any
+        actual: Option<String>,
+    },
+    /// A capability uses `.` separators where `/`-separated paths are
+    /// required.
+    MixedCapabilityNotation {
+        /// The dotted capability
+        capability: String,
+    },
+    /// Fewer distinct keys signed than the trust root's threshold requires.
+    ThresholdNotMet {
+        /// The trust root whose threshold was not met
+        trust_root: String,
+        /// The number of signatures required
+        required: usize,
+        /// The number of valid signatures from distinct keys
+        valid: usize,
+    },
+}
+
+impl AttestationError {
+    /// Returns the name of this error's variant, e.g. `"TokenExpired"`.
+    ///
+    /// Useful as a low-cardinality label for metrics and logs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::AttestationError;
+    ///
+    /// assert_eq!(AttestationError::InvalidSignature.kind(), "InvalidSignature");
+    /// ```
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::MissingField { .. } => "MissingField",
+            Self::InvalidTtl => "InvalidTtl",
+            Self::TokenExpired { .. } => "TokenExpired",
+            Self::TokenNotYetValid { .. } => "TokenNotYetValid",
+            Self::InvalidSignature => "InvalidSignature",
+            Self::InvalidTokenFormat { .. } => "InvalidTokenFormat",
+            Self::InvalidClaims { .. } => "InvalidClaims",
+            Self::TrustRootMismatch { .. } => "TrustRootMismatch",
+            Self::UntrustedIssuer { .. } => "UntrustedIssuer",
+            Self::UriMismatch { .. } => "UriMismatch",
+            Self::MissingPublicKey { .. } => "MissingPublicKey",
+            Self::InvalidKeyFormat { .. } => "InvalidKeyFormat",
+            Self::InsufficientCapabilities { .. } => "InsufficientCapabilities",
+            Self::CapabilityDenied { .. } => "CapabilityDenied",
+            Self::CapabilityConstraintViolated { .. } => "CapabilityConstraintViolated",
+            Self::AudienceMismatch { .. } => "AudienceMismatch",
+            Self::TtlExceedsMaximum { .. } => "TtlExceedsMaximum",
+            Self::TokenTooOld { .. } => "TokenTooOld",
+            Self::TrustRootNotAllowed { .. } => "TrustRootNotAllowed",
+            Self::InvalidChain { .. } => "InvalidChain",
+            Self::DelegationMismatch { .. } => "DelegationMismatch",
+            Self::CapabilityEscalation { .. } => "CapabilityEscalation",
+            Self::CoSignatureMismatch { .. } => "CoSignatureMismatch",
+            Self::MissingCoSignature { .. } => "MissingCoSignature",
+            Self::InclusionProofRequired => "InclusionProofRequired",
+            Self::InvalidInclusionProof { .. } => "InvalidInclusionProof",
+            Self::IssuanceRejected { .. } => "IssuanceRejected",
+            Self::TokenRevoked { .. } => "TokenRevoked",
+            Self::TokenRevokedOnline { .. } => "TokenRevokedOnline",
+            Self::StatusUnavailable { .. } => "StatusUnavailable",
+            Self::InvalidThreshold { .. } => "InvalidThreshold",
+            Self::LengthLimitExceeded { .. } => "LengthLimitExceeded",
+            Self::UnsupportedClaimsVersion { .. } => "UnsupportedClaimsVersion",
+            Self::ProofOfPossessionFailed { .. } => "ProofOfPossessionFailed",
+            Self::NonceMismatch { .. } => "NonceMismatch",
+            Self::MixedCapabilityNotation { .. } => "MixedCapabilityNotation",
+            Self::ThresholdNotMet { .. } => "ThresholdNotMet",
+        }
+    }
+}
+
+impl From<agent_uri_verify_core::VerifyError> for AttestationError {
+    fn from(error: agent_uri_verify_core::VerifyError) -> Self {
+        use agent_uri_verify_core::VerifyError;
+
+        let rfc3339 = |secs: i64| {
+            chrono::DateTime::from_timestamp(secs, 0)
+                .map_or_else(|| secs.to_string(), |dt| dt.to_rfc3339())
+        };
+        match error {
+            VerifyError::InvalidTokenFormat { reason } => Self::InvalidTokenFormat {
+                reason: reason.to_string(),
+            },
+            VerifyError::InvalidSignature => Self::InvalidSignature,
+            VerifyError::InvalidClaims { reason } => Self::InvalidClaims { reason },
+            VerifyError::UnsupportedClaimsVersion { version, supported } => {
+                Self::UnsupportedClaimsVersion {
+                    version: u32::try_from(version).unwrap_or(u32::MAX),
+                    supported,
+                }
+            }
+            VerifyError::TokenExpired { exp } => Self::TokenExpired {
+                expired_at: rfc3339(exp),
+            },
+            VerifyError::TokenNotYetValid { nbf } => Self::TokenNotYetValid {
+                valid_from: rfc3339(nbf),
+            },
+            VerifyError::InsufficientCapabilities { required } => {
+                Self::InsufficientCapabilities {
+                    required,
+                    attested: Vec::new(),
+                }
+            }
+            VerifyError::CapabilityDenied { required, denied } => {
+                Self::CapabilityDenied { required, denied }
+            }
+        }
+    }
 }
 
 impl fmt::Display for AttestationError {
+    #[allow(clippy::too_many_lines)]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::MissingField { field } => {
@@ -142,6 +388,177 @@ impl fmt::Display for AttestationError {
                      add a capability that is a prefix of or equals the required path"
                 )
             }
+            Self::CapabilityDenied { required, denied } => {
+                write!(
+                    f,
+                    "required capability '{required}' is excluded by denied capability '{denied}'; \
+                     request a token that does not deny this path"
+                )
+            }
+            Self::CapabilityConstraintViolated { capability, reason } => {
+                write!(
+                    f,
+                    "capability '{capability}' does not permit this request: {reason}; \
+                     request a capability issued without this constraint"
+                )
+            }
+            Self::AudienceMismatch {
+                token_audience,
+                expected_audience,
+            } => match token_audience {
+                Some(aud) => write!(
+                    f,
+                    "audience mismatch: token is for '{aud}' but expected '{expected_audience}'"
+                ),
+                None => write!(
+                    f,
+                    "token has no audience but policy requires '{expected_audience}'"
+                ),
+            },
+            Self::TtlExceedsMaximum { ttl_secs, max_secs } => {
+                write!(
+                    f,
+                    "token lifetime of {ttl_secs}s exceeds the maximum of {max_secs}s; \
+                     request a shorter-lived attestation"
+                )
+            }
+            Self::TokenTooOld {
+                age_secs,
+                max_age_secs,
+            } => {
+                write!(
+                    f,
+                    "token was issued {age_secs}s ago, beyond the freshness window of \
+                     {max_age_secs}s; request a fresh attestation"
+                )
+            }
+            Self::TrustRootNotAllowed { issuer } => {
+                write!(
+                    f,
+                    "issuer '{issuer}' does not match any trust root allowed by the verification policy"
+                )
+            }
+            Self::InvalidChain { reason } => {
+                write!(f, "invalid attestation chain: {reason}")
+            }
+            Self::DelegationMismatch {
+                link,
+                issuer,
+                expected_issuer,
+            } => {
+                write!(
+                    f,
+                    "chain link {link} issued by '{issuer}' but its parent attests '{expected_issuer}'"
+                )
+            }
+            Self::CapabilityEscalation { link, capability } => {
+                write!(
+                    f,
+                    "chain link {link} claims capability '{capability}' not held by its parent; \
+                     delegated capabilities may only narrow"
+                )
+            }
+            Self::CoSignatureMismatch { issuer } => {
+                write!(
+                    f,
+                    "co-signature from '{issuer}' attests different claims than the primary token"
+                )
+            }
+            Self::MissingCoSignature { issuer } => {
+                write!(f, "attestation is missing a required co-signature from '{issuer}'")
+            }
+            Self::InclusionProofRequired => {
+                write!(
+                    f,
+                    "verifier requires a transparency log inclusion proof; \
+                     use verify_logged with a proof and signed tree head"
+                )
+            }
+            Self::InvalidInclusionProof { reason } => {
+                write!(f, "invalid transparency log inclusion proof: {reason}")
+            }
+            Self::IssuanceRejected { reason } => {
+                write!(f, "issuance rejected by hook: {reason}")
+            }
+            Self::TokenRevoked { index } => {
+                write!(
+                    f,
+                    "token has been revoked (status list index {index}); \
+                     request a new attestation"
+                )
+            }
+            Self::TokenRevokedOnline { issuer } => {
+                write!(
+                    f,
+                    "status responder for issuer '{issuer}' reports the token as revoked; \
+                     request a new attestation"
+                )
+            }
+            Self::StatusUnavailable { issuer, reason } => {
+                write!(
+                    f,
+                    "status list for issuer '{issuer}' is unavailable: {reason}; \
+                     ensure the status list source returns a current token"
+                )
+            }
+            Self::InvalidThreshold { threshold, keys } => {
+                write!(
+                    f,
+                    "invalid threshold {threshold} for {keys} distinct keys; \
+                     threshold must be between 1 and the number of keys"
+                )
+            }
+            Self::LengthLimitExceeded { field, length, max } => {
+                write!(
+                    f,
+                    "{field} has length {length}, exceeding the grammar limit of {max}; \
+                     shorten it or split the grant across several attestations"
+                )
+            }
+            Self::UnsupportedClaimsVersion { version, supported } => {
+                write!(
+                    f,
+                    "claims schema version {version} is newer than the supported version \
+                     {supported}; upgrade agent-uri-attestation to verify this token"
+                )
+            }
+            Self::ProofOfPossessionFailed { reason } => {
+                write!(
+                    f,
+                    "proof of possession failed: {reason}; the presenter must sign the \
+                     challenge with the holder key bound to the token"
+                )
+            }
+            Self::NonceMismatch { expected, actual } => match actual {
+                Some(actual) => write!(
+                    f,
+                    "token nonce '{actual}' does not match challenge '{expected}'; request a \
+                     new token for the current challenge"
+                ),
+                None => write!(
+                    f,
+                    "token has no nonce but challenge '{expected}' was expected; issue the \
+                     token with the verifier's challenge"
+                ),
+            },
+            Self::MixedCapabilityNotation { capability } => {
+                write!(
+                    f,
+                    "capability '{capability}' uses '.' separators but capability paths use \
+                     '/'; grant '{}' instead",
+                    capability.replace('.', "/")
+                )
+            }
+            Self::ThresholdNotMet {
+                trust_root,
+                required,
+                valid,
+            } => {
+                write!(
+                    f,
+                    "trust root '{trust_root}' requires {required} signatures but only {valid} are valid"
+                )
+            }
         }
     }
 }
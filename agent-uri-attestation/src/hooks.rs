@@ -0,0 +1,86 @@
+//! Extension points around token issuance.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::claims::AttestationClaims;
+use crate::error::AttestationError;
+
+/// Observes and can veto token issuance.
+///
+/// Hooks registered with [`Issuer::with_hook`](crate::Issuer::with_hook) run
+/// for every attestation the issuer signs, in registration order. Both
+/// methods default to doing nothing, so a hook only implements the side it
+/// needs.
+///
+/// Returning an error from [`before_issue`](Self::before_issue) aborts
+/// issuance before anything is signed. Returning an error from
+/// [`after_issue`](Self::after_issue) discards the signed token, which lets
+/// audit hooks refuse to release tokens they could not record.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::time::Duration;
+///
+/// use agent_uri::AgentUri;
+/// use agent_uri_attestation::{AttestationClaims, AttestationError, IssuanceHook, Issuer};
+///
+/// /// Allows a fixed number of tokens.
+/// struct Quota {
+///     remaining: AtomicUsize,
+/// }
+///
+/// impl IssuanceHook for Quota {
+///     fn before_issue(&self, _claims: &AttestationClaims) -> Result<(), AttestationError> {
+///         self.remaining
+///             .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+///             .map(|_| ())
+///             .map_err(|_| AttestationError::IssuanceRejected {
+///                 reason: "issuance quota exhausted".to_string(),
+///             })
+///     }
+/// }
+///
+/// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600))
+///     .with_hook(Quota { remaining: AtomicUsize::new(1) });
+/// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+///
+/// assert!(issuer.issue(&uri, vec![]).is_ok());
+/// assert!(matches!(
+///     issuer.issue(&uri, vec![]),
+///     Err(AttestationError::IssuanceRejected { .. })
+/// ));
+/// ```
+pub trait IssuanceHook: Send + Sync {
+    /// Called with the claims about to be signed.
+    ///
+    /// # Errors
+    ///
+    /// Any error aborts issuance and is returned to the caller.
+    fn before_issue(&self, claims: &AttestationClaims) -> Result<(), AttestationError> {
+        let _ = claims;
+        Ok(())
+    }
+
+    /// Called with the claims of a token that has just been signed.
+    ///
+    /// # Errors
+    ///
+    /// Any error discards the token and is returned to the caller.
+    fn after_issue(&self, claims: &AttestationClaims) -> Result<(), AttestationError> {
+        let _ = claims;
+        Ok(())
+    }
+}
+
+/// A shareable handle to an issuance hook held by the issuer.
+#[derive(Clone)]
+pub(crate) struct SharedHook(pub(crate) Arc<dyn IssuanceHook>);
+
+impl fmt::Debug for SharedHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("IssuanceHook")
+    }
+}
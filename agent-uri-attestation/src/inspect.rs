@@ -0,0 +1,272 @@
+//! Introspection of tokens without verification.
+//!
+//! Everything in this module reads a token's contents without checking its
+//! signature, issuer or validity period. The results are untrusted and must
+//! never drive authorization decisions; use [`Verifier`](crate::Verifier)
+//! for that.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+
+use crate::error::AttestationError;
+use crate::limits;
+
+/// Header of PASETO v4.public tokens.
+pub(crate) const PASETO_HEADER: &str = "v4.public.";
+
+/// Length of the Ed25519 signature appended to the message.
+const SIGNATURE_LEN: usize = 64;
+
+/// The parts of a PASETO v4.public token, decoded but not verified.
+pub(crate) struct DecodedToken {
+    /// The JSON message carrying the claims
+    pub(crate) message: Vec<u8>,
+    /// The raw (unauthenticated) footer, if present
+    pub(crate) footer: Option<Vec<u8>>,
+}
+
+/// Splits and base64url-decodes a PASETO v4.public token.
+pub(crate) fn decode(token: &str) -> Result<DecodedToken, AttestationError> {
+    let invalid = |reason: String| AttestationError::InvalidTokenFormat { reason };
+
+    limits::check_token(token)?;
+    let body = token
+        .strip_prefix(PASETO_HEADER)
+        .ok_or_else(|| invalid(format!("token must start with '{PASETO_HEADER}'")))?;
+    let (payload, footer) = match body.split_once('.') {
+        Some((payload, footer)) => (payload, Some(footer)),
+        None => (body, None),
+    };
+
+    let mut message = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|e| invalid(format!("invalid base64url payload: {e}")))?;
+    if message.len() < SIGNATURE_LEN {
+        return Err(invalid("payload is shorter than an Ed25519 signature".to_string()));
+    }
+    message.truncate(message.len() - SIGNATURE_LEN);
+
+    let footer = footer
+        .map(|footer| {
+            URL_SAFE_NO_PAD
+                .decode(footer)
+                .map_err(|e| invalid(format!("invalid base64url footer: {e}")))
+        })
+        .transpose()?;
+
+    Ok(DecodedToken { message, footer })
+}
+
+impl DecodedToken {
+    /// Parses the message as JSON.
+    pub(crate) fn json(&self) -> Result<serde_json::Value, AttestationError> {
+        serde_json::from_slice(&self.message).map_err(|e| AttestationError::InvalidClaims {
+            reason: format!("payload is not valid JSON: {e}"),
+        })
+    }
+}
+
+/// Renders a human-readable breakdown of a token for debugging.
+///
+/// The output lists the header, payload and footer sizes, the decoded
+/// claims and the time left until expiry. The token is decoded locally and
+/// its signature is **not** verified, so the claims shown are untrusted.
+/// Signature bytes are never printed, and neither is the token itself, so
+/// the output is safe to paste into tickets and chat.
+///
+/// Malformed tokens produce a one-line description of the problem instead
+/// of an error.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_attestation::{format_token, Issuer};
+/// use std::time::Duration;
+///
+/// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+/// let uri = AgentUri::parse(
+///     "agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q"
+/// ).unwrap();
+/// let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+///
+/// let report = format_token(&token);
+/// assert!(report.contains("\"iss\": \"acme.com\""));
+/// assert!(report.contains("expires in"));
+/// ```
+#[must_use]
+pub fn format_token(token: &str) -> String {
+    format_token_at(token, Utc::now())
+}
+
+/// Renders a token breakdown relative to the time `now`.
+fn format_token_at(token: &str, now: DateTime<Utc>) -> String {
+    let decoded = match decode(token) {
+        Ok(decoded) => decoded,
+        Err(e) => return format!("invalid token ({} bytes): {e}", token.len()),
+    };
+
+    let mut lines = vec![
+        format!("PASETO token, {} bytes (signature NOT verified)", token.len()),
+        format!("  header:    {}", PASETO_HEADER.trim_end_matches('.')),
+        format!(
+            "  payload:   {} bytes ({}-byte message, {SIGNATURE_LEN}-byte Ed25519 signature)",
+            decoded.message.len() + SIGNATURE_LEN,
+            decoded.message.len()
+        ),
+    ];
+    lines.push(match &decoded.footer {
+        None => "  footer:    none".to_string(),
+        Some(footer) => match std::str::from_utf8(footer) {
+            Ok(text) => format!("  footer:    {} bytes: {text:?}", footer.len()),
+            Err(_) => format!("  footer:    {} bytes (binary)", footer.len()),
+        },
+    });
+
+    match decoded.json() {
+        Ok(json) => {
+            lines.push("claims (untrusted):".to_string());
+            let pretty = serde_json::to_string_pretty(&json).unwrap_or_default();
+            lines.extend(pretty.lines().map(|line| format!("  {line}")));
+            lines.extend(validity_lines(&json, now));
+        }
+        Err(e) => lines.push(format!("claims:      {e}")),
+    }
+
+    lines.join("\n")
+}
+
+/// Describes the validity window of decoded claims relative to `now`.
+fn validity_lines(json: &serde_json::Value, now: DateTime<Utc>) -> Vec<String> {
+    let timestamp = |claim: &str| {
+        json.get(claim)
+            .and_then(serde_json::Value::as_str)
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc))
+    };
+
+    let mut lines = vec!["validity:".to_string()];
+    if let Some(nbf) = timestamp("nbf")
+        && now < nbf
+    {
+        lines.push(format!("  not valid for another {}", human_duration(nbf - now)));
+    }
+    lines.push(match timestamp("exp") {
+        Some(exp) if now < exp => format!("  expires in {}", human_duration(exp - now)),
+        Some(exp) => format!("  expired {} ago", human_duration(now - exp)),
+        None => "  no readable exp claim".to_string(),
+    });
+    lines
+}
+
+/// Formats a duration as its non-zero day, hour, minute and second parts.
+fn human_duration(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds();
+    let parts: Vec<String> = [
+        (secs / 86_400, "d"),
+        (secs / 3_600 % 24, "h"),
+        (secs / 60 % 60, "m"),
+        (secs % 60, "s"),
+    ]
+    .iter()
+    .filter(|(value, _)| *value > 0)
+    .map(|(value, unit)| format!("{value}{unit}"))
+    .collect();
+
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use agent_uri::AgentUri;
+
+    use super::*;
+    use crate::issuer::Issuer;
+
+    #[test]
+    fn decodes_issued_token() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let uri =
+            AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+        let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+
+        let decoded = decode(&token).unwrap();
+        let json = decoded.json().unwrap();
+
+        assert_eq!(json["agent_uri"], uri.to_string());
+        assert!(decoded.footer.is_none());
+    }
+
+    #[test]
+    fn rejects_other_token_types() {
+        assert!(matches!(
+            decode("v4.local.abc"),
+            Err(AttestationError::InvalidTokenFormat { .. })
+        ));
+        assert!(matches!(
+            decode("v4.public.!!!"),
+            Err(AttestationError::InvalidTokenFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_payload() {
+        let token = format!("{PASETO_HEADER}{}", URL_SAFE_NO_PAD.encode([0u8; 10]));
+
+        assert!(matches!(
+            decode(&token),
+            Err(AttestationError::InvalidTokenFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn format_token_shows_claims_and_expiry() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let uri =
+            AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+        let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+        let signature = &token[token.len() - 40..];
+
+        let report = format_token_at(&token, Utc::now());
+
+        assert!(report.contains("signature NOT verified"));
+        assert!(report.contains("footer:    none"));
+        assert!(report.contains(&uri.to_string()));
+        assert!(report.contains("expires in 59m"));
+        assert!(!report.contains(signature));
+    }
+
+    #[test]
+    fn format_token_reports_expired_tokens() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let uri =
+            AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+        let token = issuer.issue(&uri, vec![]).unwrap();
+
+        let report = format_token_at(&token, Utc::now() + chrono::Duration::hours(3));
+
+        assert!(report.contains("expired 2h"));
+    }
+
+    #[test]
+    fn format_token_describes_malformed_tokens() {
+        assert_eq!(
+            format_token("garbage"),
+            "invalid token (7 bytes): invalid token format: token must start with 'v4.public.'"
+        );
+    }
+
+    #[test]
+    fn human_duration_drops_zero_parts() {
+        assert_eq!(human_duration(chrono::Duration::seconds(0)), "0s");
+        assert_eq!(human_duration(chrono::Duration::seconds(3_605)), "1h 5s");
+        assert_eq!(human_duration(chrono::Duration::seconds(90_061)), "1d 1h 1m 1s");
+    }
+}
@@ -1,13 +1,21 @@
 //! Token issuer for creating attestations.
 
+use std::sync::Arc;
 use std::time::Duration;
 
-use agent_uri::AgentUri;
+use agent_uri::{AgentUri, CapabilityPath};
 use rusty_paseto::prelude::*;
 
 use crate::claims::{AttestationClaims, AttestationClaimsBuilder};
+use crate::clock::{Clock, SharedClock};
+use crate::constraints;
 use crate::error::AttestationError;
+use crate::hooks::{IssuanceHook, SharedHook};
 use crate::keys::{SigningKey, VerifyingKey};
+use crate::limits;
+use crate::status::StatusList;
+use crate::telemetry::Step;
+use crate::verifier::Verifier;
 
 /// Creates attestation tokens for agent URIs.
 ///
@@ -31,11 +39,19 @@ use crate::keys::{SigningKey, VerifyingKey};
 ///
 /// assert!(token.starts_with("v4.public."));
 /// ```
+///
+/// Use [`Issuer::builder`] to configure a default audience, a token footer,
+/// the clock, strict mode and hooks in one place.
 #[derive(Debug, Clone)]
 pub struct Issuer {
     trust_root: String,
     signing_key: SigningKey,
     default_ttl: Duration,
+    audience: Option<String>,
+    footer: Option<String>,
+    hooks: Vec<SharedHook>,
+    strict: bool,
+    clock: SharedClock,
 }
 
 impl Issuer {
@@ -56,9 +72,36 @@ impl Issuer {
             trust_root: trust_root.into(),
             signing_key,
             default_ttl,
+            audience: None,
+            footer: None,
+            hooks: Vec::new(),
+            strict: false,
+            clock: SharedClock::default(),
         }
     }
 
+    /// Creates a builder for an issuer signing with `signing_key`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::{Issuer, SigningKey};
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::builder("acme.com", SigningKey::generate())
+    ///     .default_ttl(Duration::from_secs(600))
+    ///     .audience("api.acme.com")
+    ///     .strict()
+    ///     .build();
+    ///
+    /// assert_eq!(issuer.default_audience(), Some("api.acme.com"));
+    /// assert!(issuer.is_strict());
+    /// ```
+    #[must_use]
+    pub fn builder(trust_root: impl Into<String>, signing_key: SigningKey) -> IssuerBuilder {
+        IssuerBuilder::new(trust_root, signing_key)
+    }
+
     /// Generates a new issuer with a random signing key.
     ///
     /// # Arguments
@@ -70,6 +113,64 @@ impl Issuer {
         Self::new(trust_root, SigningKey::generate(), default_ttl)
     }
 
+    /// Registers a hook that runs around every issued attestation.
+    ///
+    /// Hooks run in registration order; see [`IssuanceHook`] for how their
+    /// errors abort issuance. Status list tokens are not attestations and do
+    /// not run hooks.
+    #[must_use]
+    pub fn with_hook(mut self, hook: impl IssuanceHook + 'static) -> Self {
+        self.hooks.push(SharedHook(Arc::new(hook)));
+        self
+    }
+
+    /// Replaces the issuer's time source.
+    ///
+    /// `iat` and `exp` of every token (attestations and status lists alike)
+    /// are computed from `clock` instead of the system time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = SharedClock(Arc::new(clock));
+        self
+    }
+
+    /// Enables strict grammar enforcement.
+    ///
+    /// A strict issuer checks every length limit documented in
+    /// `grammar.abnf` (agent URI, capability count and length, issuer,
+    /// audience, and the encoded token and payload) before releasing a token,
+    /// and returns `AttestationError::LengthLimitExceeded` instead of
+    /// producing a token that verifiers may reject. Claim limits are checked
+    /// before hooks run; token limits after signing, before `after_issue`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::{AttestationError, Issuer};
+    /// use agent_uri::AgentUri;
+    /// use std::time::Duration;
+    ///
+    /// let long_root = format!("{}.com", "a".repeat(130));
+    /// let issuer = Issuer::generate(long_root, Duration::from_secs(3600)).strict();
+    /// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+    ///
+    /// assert!(matches!(
+    ///     issuer.issue(&uri, vec![]),
+    ///     Err(AttestationError::LengthLimitExceeded { field: "iss", .. })
+    /// ));
+    /// ```
+    #[must_use]
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Returns true if the issuer enforces grammar limits before signing.
+    #[must_use]
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
     /// Returns the trust root this issuer represents.
     #[must_use]
     pub fn trust_root(&self) -> &str {
@@ -88,6 +189,32 @@ impl Issuer {
         self.default_ttl
     }
 
+    /// Returns the audience set on tokens this issuer builds, if any.
+    #[must_use]
+    pub fn default_audience(&self) -> Option<&str> {
+        self.audience.as_deref()
+    }
+
+    /// Returns the footer attached to PASETO tokens, if any.
+    #[must_use]
+    pub fn footer(&self) -> Option<&str> {
+        self.footer.as_deref()
+    }
+
+    /// Starts claims for `uri` issued now by this issuer, valid for `ttl`
+    /// and restricted to the default audience.
+    fn claims_for(&self, uri: &AgentUri, ttl: Duration) -> AttestationClaimsBuilder {
+        let builder = AttestationClaimsBuilder::new()
+            .agent_uri(uri.to_string())
+            .issuer(&self.trust_root)
+            .ttl(ttl)
+            .issued_at(self.clock.now());
+        match &self.audience {
+            Some(audience) => builder.audience(audience),
+            None => builder,
+        }
+    }
+
     /// Issues an attestation token for an agent URI.
     ///
     /// # Arguments
@@ -140,11 +267,97 @@ impl Issuer {
         capabilities: Vec<String>,
         ttl: Duration,
     ) -> Result<String, AttestationError> {
-        let claims = AttestationClaimsBuilder::new()
-            .agent_uri(uri.to_string())
+        let claims = self.claims_for(uri, ttl).capabilities(capabilities).build()?;
+
+        self.issue_claims(&claims)
+    }
+
+    /// Issues an attestation token granting typed capability paths.
+    ///
+    /// Equivalent to [`issue`](Self::issue) with each path converted to its
+    /// `/`-separated string form, the form coverage checks expect.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if token creation fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri::{AgentUri, CapabilityPath};
+    /// use agent_uri_attestation::{Issuer, Verifier};
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let uri = AgentUri::parse(
+    ///     "agent://acme.com/workflow/approval/agent_01h455vb4pex5vsknk084sn02q"
+    /// ).unwrap();
+    /// let workflow = CapabilityPath::parse("workflow").unwrap();
+    /// let token = issuer.issue_paths(&uri, vec![workflow]).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    /// let required = CapabilityPath::parse("workflow/approval").unwrap();
+    /// assert!(verifier.verify_for_capability(&token, &uri, &required).is_ok());
+    /// ```
+    pub fn issue_paths(
+        &self,
+        uri: &AgentUri,
+        capabilities: Vec<CapabilityPath>,
+    ) -> Result<String, AttestationError> {
+        let claims = self
+            .claims_for(uri, self.default_ttl)
+            .capability_paths(capabilities)
+            .build()?;
+
+        self.issue_claims(&claims)
+    }
+
+    /// Issues one fleet token attesting every URI in `uris`.
+    ///
+    /// The first URI becomes the token's `agent_uri` and the rest its
+    /// `agent_uris`, so [`Verifier::verify_for_uri`] accepts the token for
+    /// any of them. Use this for replicas of one service that share
+    /// capabilities; at most 33 URIs fit in one token.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::MissingField` if `uris` is empty,
+    /// `AttestationError::LengthLimitExceeded` if it is too long, or any
+    /// error from [`issue_claims`](Self::issue_claims).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::{Issuer, Verifier};
+    /// use agent_uri::AgentUri;
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let replicas: Vec<AgentUri> = ["agent_01h455vb4pex5vsknk084sn02q", "agent_01h455vb4pex5vsknk084sn02r"]
+    ///     .iter()
+    ///     .map(|id| AgentUri::parse(&format!("agent://acme.com/search/{id}")).unwrap())
+    ///     .collect();
+    /// let token = issuer.issue_fleet(&replicas, vec!["search".into()]).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    /// for replica in &replicas {
+    ///     assert!(verifier.verify_for_uri(&token, replica).is_ok());
+    /// }
+    /// ```
+    pub fn issue_fleet(
+        &self,
+        uris: &[AgentUri],
+        capabilities: Vec<String>,
+    ) -> Result<String, AttestationError> {
+        let (primary, members) = uris.split_first().ok_or(AttestationError::MissingField {
+            field: "agent_uri",
+        })?;
+        let claims = self
+            .claims_for(primary, self.default_ttl)
+            .agent_uris(members.iter().map(ToString::to_string).collect())
             .capabilities(capabilities)
-            .issuer(&self.trust_root)
-            .ttl(ttl)
             .build()?;
 
         self.issue_claims(&claims)
@@ -153,20 +366,141 @@ impl Issuer {
     /// Issues a token for pre-built claims.
     ///
     /// This is useful when you need full control over the claims structure.
+    /// The claims are signed as given; the default audience is not applied.
     ///
     /// # Errors
     ///
     /// Returns `AttestationError` if token creation fails.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(agent_uri = %claims.agent_uri))
+    )]
     pub fn issue_claims(&self, claims: &AttestationClaims) -> Result<String, AttestationError> {
+        let sign = |claims: &AttestationClaims| {
+            let token = self.sign_paseto(claims)?;
+            if self.strict {
+                limits::check_token(&token)?;
+            }
+            Ok(token)
+        };
+        Step::start("issue")
+            .finish(self.check_claims(claims).and_then(|()| self.run_hooks(claims, sign)))
+    }
+
+    /// Reissues `claims` with a fresh validity period.
+    ///
+    /// The new token keeps the agent URI, capabilities and every other claim,
+    /// is valid for the issuer's default TTL from now, and records the
+    /// original issuance time in `orig_iat` so audits can follow a
+    /// long-running agent across renewals.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::TrustRootMismatch` if `claims` were issued
+    /// by another trust root, or any error from
+    /// [`issue_claims`](Self::issue_claims).
+    pub fn reissue(&self, claims: &AttestationClaims) -> Result<String, AttestationError> {
+        if claims.iss != self.trust_root {
+            return Err(AttestationError::TrustRootMismatch {
+                token_root: claims.iss.clone(),
+                expected_root: self.trust_root.clone(),
+            });
+        }
+
+        let now = self.clock.now();
+        let ttl = chrono::Duration::from_std(self.default_ttl)
+            .map_err(|_| AttestationError::InvalidTtl)?;
+        let renewed = AttestationClaims {
+            iat: now,
+            exp: now + ttl,
+            orig_iat: Some(claims.original_issued_at()),
+            ..claims.clone()
+        };
+        self.issue_claims(&renewed)
+    }
+
+    /// Verifies one of this issuer's tokens and reissues it.
+    ///
+    /// Only tokens that are still valid can be renewed; see
+    /// [`reissue`](Self::reissue) for what the new token carries.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if `token` does not verify against this
+    /// issuer's key (including `TokenExpired` for lapsed tokens), or if
+    /// reissuing fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::{Issuer, Verifier};
+    /// use agent_uri::AgentUri;
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+    /// let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+    ///
+    /// let renewed = issuer.renew(&token).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    /// let original = verifier.verify(&token).unwrap();
+    /// let claims = verifier.verify(&renewed).unwrap();
+    /// assert_eq!(claims.capabilities, ["read"]);
+    /// assert_eq!(claims.original_issued_at(), original.iat);
+    /// ```
+    pub fn renew(&self, token: &str) -> Result<String, AttestationError> {
+        let mut verifier = Verifier::new().with_clock(self.clock.clone());
+        verifier.add_trusted_root(&self.trust_root, self.verifying_key());
+        self.reissue(&verifier.verify(token)?)
+    }
+
+    /// Checks the claim length limits if the issuer is strict.
+    fn check_claims(&self, claims: &AttestationClaims) -> Result<(), AttestationError> {
+        if self.strict {
+            limits::check_claims(claims)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Runs the registered hooks around `sign`.
+    fn run_hooks<T>(
+        &self,
+        claims: &AttestationClaims,
+        sign: impl FnOnce(&AttestationClaims) -> Result<T, AttestationError>,
+    ) -> Result<T, AttestationError> {
+        for hook in &self.hooks {
+            hook.0.before_issue(claims)?;
+        }
+        let token = sign(claims)?;
+        for hook in &self.hooks {
+            hook.0.after_issue(claims)?;
+        }
+        Ok(token)
+    }
+
+    /// Signs `claims` as a PASETO v4.public token.
+    fn sign_paseto(&self, claims: &AttestationClaims) -> Result<String, AttestationError> {
         // Build the PASETO key from the signing key
-        let dalek_key = self.signing_key.as_dalek();
-        let key_bytes = dalek_key.to_keypair_bytes();
+        let key_bytes = self.signing_key.as_dalek().to_keypair_bytes();
         let key_wrapper = Key::<64>::from(&key_bytes);
         let paseto_key = PasetoAsymmetricPrivateKey::<V4, Public>::from(&key_wrapper);
 
         // Format timestamps for PASETO
         let exp_str = claims.exp.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
         let iat_str = claims.iat.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+        // PASETO defaults a missing nbf to the system time; pin it to iat so
+        // tokens follow the issuer's clock
+        let nbf_str = claims
+            .nbf
+            .unwrap_or(claims.iat)
+            .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+            .to_string();
+        let orig_iat_str = claims
+            .orig_iat
+            .map(|orig| orig.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string());
 
         // Prepare claims
         let exp_claim =
@@ -178,21 +512,13 @@ impl Issuer {
                 reason: format!("invalid issued at: {e}"),
             })?;
         let iss_claim = IssuerClaim::from(claims.iss.as_str());
-        let agent_uri_claim = CustomClaim::try_from(("agent_uri", claims.agent_uri.as_str()))
-            .map_err(|e| AttestationError::InvalidClaims {
-                reason: format!("invalid agent_uri claim: {e}"),
-            })?;
+        let agent_uri_claim = custom_claim("agent_uri", claims.agent_uri.as_str())?;
 
-        // Serialize capabilities as JSON array
-        let capabilities_json = serde_json::to_value(&claims.capabilities).map_err(|e| {
-            AttestationError::InvalidClaims {
-                reason: format!("invalid capabilities: {e}"),
-            }
-        })?;
-        let capabilities_claim = CustomClaim::try_from(("capabilities", capabilities_json))
-            .map_err(|e| AttestationError::InvalidClaims {
-                reason: format!("invalid capabilities claim: {e}"),
-            })?;
+        // Serialize capabilities as JSON array, inlining any constraints
+        let capabilities_json =
+            constraints::to_wire(&claims.capabilities, &claims.capability_constraints)?;
+        let capabilities_claim = custom_claim("capabilities", capabilities_json)?;
+        let ver_claim = custom_claim("ver", claims.ver)?;
 
         // Build the token with standard and custom claims
         let mut builder = PasetoBuilder::<V4, Public>::default();
@@ -201,18 +527,346 @@ impl Issuer {
             .set_claim(iat_claim)
             .set_claim(iss_claim)
             .set_claim(agent_uri_claim)
-            .set_claim(capabilities_claim);
+            .set_claim(capabilities_claim)
+            .set_claim(ver_claim);
+
+        // Set optional fleet members
+        if !claims.agent_uris.is_empty() {
+            builder.set_claim(custom_claim("agent_uris", serde_json::json!(claims.agent_uris))?);
+        }
+
+        // Set optional denied capabilities
+        if !claims.denied_capabilities.is_empty() {
+            let denied = serde_json::json!(claims.denied_capabilities);
+            builder.set_claim(custom_claim("denied_capabilities", denied)?);
+        }
+
+        // Set optional status list index
+        if let Some(index) = claims.status_idx {
+            builder.set_claim(custom_claim("status_idx", index)?);
+        }
+
+        // Set optional holder key binding
+        if let Some(holder) = &claims.cnf {
+            builder.set_claim(custom_claim("cnf", crate::pop::to_wire(holder))?);
+        }
+
+        // Set optional renewal lineage
+        if let Some(orig_iat_str) = &orig_iat_str {
+            builder.set_claim(custom_claim("orig_iat", orig_iat_str.as_str())?);
+        }
 
-        // Set optional audience
+        // Set optional challenge nonce
+        if let Some(nonce) = &claims.nonce {
+            builder.set_claim(custom_claim("nonce", nonce.as_str())?);
+        }
+
+        // Set optional audience and footer
         if let Some(aud) = &claims.aud {
             builder.set_claim(AudienceClaim::from(aud.as_str()));
         }
+        if let Some(footer) = &self.footer {
+            builder.set_footer(Footer::from(footer.as_str()));
+        }
+
+        // Set not-before
+        let nbf_claim = NotBeforeClaim::try_from(nbf_str.as_str()).map_err(|e| {
+            AttestationError::InvalidClaims {
+                reason: format!("invalid not before: {e}"),
+            }
+        })?;
+        builder.set_claim(nbf_claim);
 
         // Build and sign the token
         builder.build(&paseto_key).map_err(|e| AttestationError::InvalidTokenFormat {
             reason: e.to_string(),
         })
     }
+
+    /// Issues a signed status list token.
+    ///
+    /// Attestations carrying a `status_idx` claim are revoked by setting the
+    /// corresponding bit in `list` and publishing a fresh token. Keep `ttl`
+    /// short and reissue frequently: verifiers reject expired status lists.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if token creation fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::{Issuer, StatusList};
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(86400));
+    /// let mut list = StatusList::new(4096);
+    /// list.revoke(7).unwrap();
+    ///
+    /// let token = issuer.issue_status_list(&list, Duration::from_secs(300)).unwrap();
+    /// assert!(token.starts_with("v4.public."));
+    /// ```
+    pub fn issue_status_list(
+        &self,
+        list: &StatusList,
+        ttl: Duration,
+    ) -> Result<String, AttestationError> {
+        let key_bytes = self.signing_key.as_dalek().to_keypair_bytes();
+        let key_wrapper = Key::<64>::from(&key_bytes);
+        let paseto_key = PasetoAsymmetricPrivateKey::<V4, Public>::from(&key_wrapper);
+
+        let now = self.clock.now();
+        let exp = now + chrono::Duration::from_std(ttl).map_err(|_| AttestationError::InvalidTtl)?;
+        let exp_str = exp.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+        let iat_str = now.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+
+        let exp_claim =
+            ExpirationClaim::try_from(exp_str.as_str()).map_err(|e| AttestationError::InvalidClaims {
+                reason: format!("invalid expiration: {e}"),
+            })?;
+        let iat_claim =
+            IssuedAtClaim::try_from(iat_str.as_str()).map_err(|e| AttestationError::InvalidClaims {
+                reason: format!("invalid issued at: {e}"),
+            })?;
+        let nbf_claim =
+            NotBeforeClaim::try_from(iat_str.as_str()).map_err(|e| AttestationError::InvalidClaims {
+                reason: format!("invalid not before: {e}"),
+            })?;
+        let list_json =
+            serde_json::to_value(list.to_claim()).map_err(|e| AttestationError::InvalidClaims {
+                reason: format!("invalid status list: {e}"),
+            })?;
+        let list_claim = CustomClaim::try_from(("status_list", list_json)).map_err(|e| {
+            AttestationError::InvalidClaims {
+                reason: format!("invalid status_list claim: {e}"),
+            }
+        })?;
+
+        let mut builder = PasetoBuilder::<V4, Public>::default();
+        builder
+            .set_claim(exp_claim)
+            .set_claim(iat_claim)
+            .set_claim(nbf_claim)
+            .set_claim(IssuerClaim::from(self.trust_root.as_str()))
+            .set_claim(list_claim);
+        if let Some(footer) = &self.footer {
+            builder.set_footer(Footer::from(footer.as_str()));
+        }
+        builder
+            .build(&paseto_key)
+            .map_err(|e| AttestationError::InvalidTokenFormat {
+                reason: e.to_string(),
+            })
+    }
+
+    /// Issues a CWT token (CBOR/`COSE_Sign1`) for pre-built claims.
+    ///
+    /// This is the compact alternative to [`issue_claims`](Self::issue_claims)
+    /// for constrained devices. Timestamps are truncated to whole seconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if encoding or signing fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::{AttestationClaims, Issuer, Verifier};
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let claims = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/actuator/valve/sensor_01h455vb4pex5vsknk084sn02q")
+    ///     .issuer("acme.com")
+    ///     .add_capability("actuator/valve")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let cwt = issuer.issue_cose(&claims).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    /// assert_eq!(verifier.verify_cose(&cwt).unwrap().capabilities, ["actuator/valve"]);
+    /// ```
+    #[cfg(feature = "cose")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(agent_uri = %claims.agent_uri))
+    )]
+    pub fn issue_cose(&self, claims: &AttestationClaims) -> Result<Vec<u8>, AttestationError> {
+        let sign = |claims: &AttestationClaims| crate::cose::sign(claims, &self.signing_key);
+        Step::start("issue")
+            .finish(self.check_claims(claims).and_then(|()| self.run_hooks(claims, sign)))
+    }
+
+    /// Issues an attestation and attaches it to a DHT registration.
+    ///
+    /// The registration's TTL is set to the issuer's default TTL so the
+    /// record never outlives the attestation it carries.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The agent URI to attest and register
+    /// * `capabilities` - Capabilities to grant
+    /// * `endpoints` - Network endpoints for contacting the agent
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if token creation fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri::AgentUri;
+    /// use agent_uri_attestation::Issuer;
+    /// use agent_uri_dht::{Dht, Endpoint, SimulatedDht, SimulationConfig};
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let uri = AgentUri::parse(
+    ///     "agent://acme.com/workflow/approval/agent_01h455vb4pex5vsknk084sn02q"
+    /// ).unwrap();
+    ///
+    /// let registration = issuer
+    ///     .issue_registration(
+    ///         &uri,
+    ///         vec!["workflow/approval".into()],
+    ///         vec![Endpoint::https("agent.acme.com:443")],
+    ///     )
+    ///     .unwrap();
+    /// assert!(registration.attestation().is_some());
+    ///
+    /// let dht = SimulatedDht::new(SimulationConfig::default());
+    /// dht.register(registration).unwrap();
+    /// ```
+    #[cfg(feature = "dht")]
+    pub fn issue_registration(
+        &self,
+        uri: &AgentUri,
+        capabilities: Vec<String>,
+        endpoints: Vec<agent_uri_dht::Endpoint>,
+    ) -> Result<agent_uri_dht::Registration, AttestationError> {
+        let token = self.issue(uri, capabilities)?;
+        Ok(agent_uri_dht::Registration::new(uri.clone(), endpoints)
+            .with_ttl(self.default_ttl)
+            .with_attestation(token))
+    }
+}
+
+/// Configures an [`Issuer`].
+///
+/// Every setting has a default: tokens live for
+/// [`DEFAULT_TTL`](Self::DEFAULT_TTL), carry no audience or footer, use the
+/// system clock, and are signed without grammar checks or hooks.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_attestation::{IssuerBuilder, SigningKey, Verifier};
+/// use std::time::Duration;
+///
+/// let key = SigningKey::generate();
+/// let issuer = IssuerBuilder::new("acme.com", key.clone())
+///     .default_ttl(Duration::from_secs(900))
+///     .audience("api.acme.com")
+///     .key_id(key.verifying_key().fingerprint())
+///     .build();
+///
+/// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+///
+/// let mut verifier = Verifier::new();
+/// verifier.add_trusted_root("acme.com", key.verifying_key());
+/// assert_eq!(verifier.verify(&token).unwrap().aud.as_deref(), Some("api.acme.com"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct IssuerBuilder {
+    issuer: Issuer,
+}
+
+impl IssuerBuilder {
+    /// TTL of issued tokens unless [`default_ttl`](Self::default_ttl) is set.
+    pub const DEFAULT_TTL: Duration = Duration::from_hours(1);
+
+    /// Creates a builder for an issuer for `trust_root` signing with
+    /// `signing_key`.
+    #[must_use]
+    pub fn new(trust_root: impl Into<String>, signing_key: SigningKey) -> Self {
+        Self {
+            issuer: Issuer::new(trust_root, signing_key, Self::DEFAULT_TTL),
+        }
+    }
+
+    /// Sets the time-to-live of tokens issued without an explicit TTL.
+    #[must_use]
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        self.issuer.default_ttl = ttl;
+        self
+    }
+
+    /// Restricts tokens the issuer builds to `audience`.
+    ///
+    /// Applies to [`Issuer::issue`], [`Issuer::issue_with_ttl`],
+    /// [`Issuer::issue_paths`] and [`Issuer::issue_fleet`]; pre-built claims
+    /// keep their own `aud`.
+    #[must_use]
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.issuer.audience = Some(audience.into());
+        self
+    }
+
+    /// Attaches `footer` to every PASETO token.
+    ///
+    /// The footer is signed but not encrypted; it typically carries a key
+    /// hint. CWT tokens have no footer and ignore it.
+    #[must_use]
+    pub fn footer(mut self, footer: impl Into<String>) -> Self {
+        self.issuer.footer = Some(footer.into());
+        self
+    }
+
+    /// Attaches the footer `{"kid":"<kid>"}` so verifiers can tell which key
+    /// signed a token, for example a [`VerifyingKey::fingerprint`].
+    #[must_use]
+    pub fn key_id(self, kid: impl Into<String>) -> Self {
+        let footer = serde_json::json!({ "kid": kid.into() }).to_string();
+        self.footer(footer)
+    }
+
+    /// Replaces the time source; see [`Issuer::with_clock`].
+    #[must_use]
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.issuer = self.issuer.with_clock(clock);
+        self
+    }
+
+    /// Enables strict grammar enforcement; see [`Issuer::strict`].
+    #[must_use]
+    pub fn strict(mut self) -> Self {
+        self.issuer.strict = true;
+        self
+    }
+
+    /// Registers a hook; see [`Issuer::with_hook`].
+    #[must_use]
+    pub fn hook(mut self, hook: impl IssuanceHook + 'static) -> Self {
+        self.issuer = self.issuer.with_hook(hook);
+        self
+    }
+
+    /// Builds the issuer.
+    #[must_use]
+    pub fn build(self) -> Issuer {
+        self.issuer
+    }
+}
+
+/// Builds the custom claim `name`, reporting failures as invalid claims.
+fn custom_claim<T>(name: &str, value: T) -> Result<CustomClaim<T>, AttestationError> {
+    CustomClaim::try_from((name, value)).map_err(|e| AttestationError::InvalidClaims {
+        reason: format!("invalid {name} claim: {e}"),
+    })
 }
 
 #[cfg(test)]
@@ -223,9 +877,34 @@ mod tests {
         AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap()
     }
 
+    #[cfg(feature = "dht")]
+    #[test]
+    fn issue_registration_attaches_verifiable_token() {
+        let issuer = Issuer::generate("acme.com", Duration::from_mins(10));
+        let uri = test_uri();
+
+        let registration = issuer
+            .issue_registration(
+                &uri,
+                vec!["read".into()],
+                vec![agent_uri_dht::Endpoint::https("agent.acme.com:443")],
+            )
+            .unwrap();
+
+        let mut verifier = crate::Verifier::new();
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
+        let claims = verifier
+            .verify_for_uri(registration.attestation().unwrap(), &uri)
+            .unwrap();
+
+        assert_eq!(registration.agent_uri(), &uri);
+        assert_eq!(claims.capabilities, vec!["read"]);
+        assert!(registration.remaining_ttl().unwrap() <= Duration::from_mins(10));
+    }
+
     #[test]
     fn issue_creates_valid_token() {
-        let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
         let uri = test_uri();
 
         let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
@@ -235,8 +914,8 @@ mod tests {
 
     #[test]
     fn generated_issuer_has_unique_key() {
-        let issuer1 = Issuer::generate("acme.com", Duration::from_secs(3600));
-        let issuer2 = Issuer::generate("acme.com", Duration::from_secs(3600));
+        let issuer1 = Issuer::generate("acme.com", Duration::from_hours(1));
+        let issuer2 = Issuer::generate("acme.com", Duration::from_hours(1));
 
         assert_ne!(
             issuer1.verifying_key().to_bytes(),
@@ -247,7 +926,7 @@ mod tests {
     #[test]
     fn issuer_trust_root_accessible() {
         let signing_key = SigningKey::generate();
-        let issuer = Issuer::new("acme.com", signing_key, Duration::from_secs(3600));
+        let issuer = Issuer::new("acme.com", signing_key, Duration::from_hours(1));
 
         assert_eq!(issuer.trust_root(), "acme.com");
     }
@@ -255,19 +934,19 @@ mod tests {
     #[test]
     fn issuer_default_ttl_accessible() {
         let signing_key = SigningKey::generate();
-        let issuer = Issuer::new("acme.com", signing_key, Duration::from_secs(7200));
+        let issuer = Issuer::new("acme.com", signing_key, Duration::from_hours(2));
 
-        assert_eq!(issuer.default_ttl(), Duration::from_secs(7200));
+        assert_eq!(issuer.default_ttl(), Duration::from_hours(2));
     }
 
     #[test]
     fn issue_with_custom_ttl() {
-        let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
         let uri = test_uri();
 
         // Should not error with different TTL
         let token = issuer
-            .issue_with_ttl(&uri, vec![], Duration::from_secs(60))
+            .issue_with_ttl(&uri, vec![], Duration::from_mins(1))
             .unwrap();
 
         assert!(token.starts_with("v4.public."));
@@ -275,7 +954,7 @@ mod tests {
 
     #[test]
     fn issue_with_multiple_capabilities() {
-        let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
         let uri = test_uri();
 
         let capabilities = vec![
@@ -291,7 +970,7 @@ mod tests {
 
     #[test]
     fn issue_claims_directly() {
-        let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
 
         let claims = AttestationClaimsBuilder::new()
             .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
@@ -305,4 +984,230 @@ mod tests {
 
         assert!(token.starts_with("v4.public."));
     }
+
+    /// Records hook calls into a shared log, optionally vetoing.
+    struct Recorder {
+        name: &'static str,
+        veto: bool,
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl IssuanceHook for Recorder {
+        fn before_issue(&self, claims: &AttestationClaims) -> Result<(), AttestationError> {
+            self.log.lock().unwrap().push(format!("before {} {}", self.name, claims.iss));
+            if self.veto {
+                return Err(AttestationError::IssuanceRejected {
+                    reason: format!("vetoed by {}", self.name),
+                });
+            }
+            Ok(())
+        }
+
+        fn after_issue(&self, _claims: &AttestationClaims) -> Result<(), AttestationError> {
+            self.log.lock().unwrap().push(format!("after {}", self.name));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn hooks_run_in_order_around_issuance() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let hook = |name, veto| Recorder {
+            name,
+            veto,
+            log: Arc::clone(&log),
+        };
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1))
+            .with_hook(hook("audit", false))
+            .with_hook(hook("quota", false));
+
+        issuer.issue(&test_uri(), vec![]).unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["before audit acme.com", "before quota acme.com", "after audit", "after quota"]
+        );
+    }
+
+    #[test]
+    fn hook_error_aborts_issuance() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1)).with_hook(Recorder {
+            name: "policy",
+            veto: true,
+            log: Arc::clone(&log),
+        });
+
+        let result = issuer.issue(&test_uri(), vec![]);
+
+        assert_eq!(
+            result,
+            Err(AttestationError::IssuanceRejected {
+                reason: "vetoed by policy".to_string()
+            })
+        );
+        assert_eq!(*log.lock().unwrap(), ["before policy acme.com"]);
+    }
+
+    #[test]
+    fn strict_issuer_checks_claims_before_hooks() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1))
+            .with_hook(Recorder {
+                name: "audit",
+                veto: false,
+                log: Arc::clone(&log),
+            })
+            .strict();
+        let claims = AttestationClaimsBuilder::new()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .audience("a".repeat(129))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            issuer.issue_claims(&claims),
+            Err(AttestationError::LengthLimitExceeded {
+                field: "aud",
+                length: 129,
+                max: 128,
+            })
+        );
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn strict_issuer_rejects_oversized_tokens() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let uri =
+            AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+        // Every claim is within its own limit, but together they overflow
+        let capabilities: Vec<String> =
+            (0..64).map(|i| format!("{i:02}{}", "c".repeat(126))).collect();
+
+        assert!(issuer.issue(&uri, capabilities.clone()).is_ok());
+        assert!(matches!(
+            issuer.strict().issue(&uri, capabilities),
+            Err(AttestationError::LengthLimitExceeded { field: "token", .. })
+        ));
+    }
+
+    #[test]
+    fn renew_preserves_claims_and_lineage() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
+        let token = issuer.issue(&test_uri(), vec!["read".into(), "write".into()]).unwrap();
+        let original = verifier.verify(&token).unwrap();
+        assert_eq!(original.orig_iat, None);
+
+        let renewed = verifier.verify(&issuer.renew(&token).unwrap()).unwrap();
+        assert_eq!(renewed.capabilities, original.capabilities);
+        assert_eq!(renewed.agent_uri, original.agent_uri);
+        assert_eq!(renewed.orig_iat, Some(original.iat));
+        assert!(renewed.exp >= original.exp);
+
+        // Renewing again keeps the first issue time
+        let token = issuer.reissue(&renewed).unwrap();
+        let twice = verifier.verify(&token).unwrap();
+        assert_eq!(twice.original_issued_at(), original.iat);
+    }
+
+    #[test]
+    fn renew_rejects_foreign_and_expired_tokens() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let other = Issuer::generate("other.com", Duration::from_hours(1));
+        let foreign = other.issue(&test_uri(), vec![]).unwrap();
+
+        assert!(matches!(
+            issuer.renew(&foreign),
+            Err(AttestationError::InvalidSignature | AttestationError::UntrustedIssuer { .. })
+        ));
+
+        let mut claims = AttestationClaimsBuilder::new()
+            .agent_uri(test_uri().to_string())
+            .issuer("other.com")
+            .build()
+            .unwrap();
+        assert_eq!(
+            issuer.reissue(&claims),
+            Err(AttestationError::TrustRootMismatch {
+                token_root: "other.com".to_string(),
+                expected_root: "acme.com".to_string(),
+            })
+        );
+
+        claims.iss = "acme.com".to_string();
+        claims.iat = chrono::Utc::now() - chrono::Duration::hours(2);
+        claims.exp = chrono::Utc::now() - chrono::Duration::hours(1);
+        let expired = issuer.sign_paseto(&claims).unwrap();
+        assert!(issuer.renew(&expired).is_err());
+    }
+
+    #[test]
+    fn builder_applies_defaults_to_issued_tokens() {
+        let key = SigningKey::generate();
+        let clock = crate::clock::ManualClock::new(chrono::Utc::now());
+        let issuer = Issuer::builder("acme.com", key.clone())
+            .default_ttl(Duration::from_mins(2))
+            .audience("api.acme.com")
+            .key_id("k1")
+            .clock(clock.clone())
+            .build();
+        assert_eq!(issuer.footer(), Some(r#"{"kid":"k1"}"#));
+
+        let token = issuer.issue(&test_uri(), vec!["read".into()]).unwrap();
+        let decoded = crate::inspect::decode(&token).unwrap();
+        assert_eq!(decoded.footer.as_deref(), Some(br#"{"kid":"k1"}"#.as_slice()));
+
+        let mut verifier = Verifier::new().with_clock(clock.clone());
+        verifier.add_trusted_root("acme.com", key.verifying_key());
+        let claims = verifier.verify(&token).unwrap();
+        assert_eq!(claims.aud.as_deref(), Some("api.acme.com"));
+        assert_eq!(claims.iat.timestamp(), clock.now().timestamp());
+        assert_eq!((claims.exp - claims.iat).num_seconds(), 120);
+
+        let status = issuer.issue_status_list(&StatusList::new(8), Duration::from_mins(1));
+        assert!(status.unwrap().ends_with(&token[token.rfind('.').unwrap()..]));
+    }
+
+    #[test]
+    fn builder_defaults_match_new() {
+        let issuer = IssuerBuilder::new("acme.com", SigningKey::generate()).build();
+        assert_eq!(issuer.default_ttl(), IssuerBuilder::DEFAULT_TTL);
+        assert_eq!(issuer.default_audience(), None);
+        assert_eq!(issuer.footer(), None);
+        assert!(!issuer.is_strict());
+    }
+
+    #[test]
+    fn time_remaining_saturates_at_zero() {
+        let mut claims = AttestationClaimsBuilder::new()
+            .agent_uri(test_uri().to_string())
+            .issuer("acme.com")
+            .ttl(Duration::from_mins(10))
+            .build()
+            .unwrap();
+        assert!(claims.time_remaining() > Duration::from_secs(590));
+
+        claims.exp = chrono::Utc::now() - chrono::Duration::seconds(1);
+        assert_eq!(claims.time_remaining(), Duration::ZERO);
+    }
+
+    #[test]
+    fn renew_after_clamps_the_fraction() {
+        let claims = AttestationClaimsBuilder::new()
+            .agent_uri(test_uri().to_string())
+            .issuer("acme.com")
+            .ttl(Duration::from_mins(10))
+            .build()
+            .unwrap();
+
+        assert_eq!(claims.renew_after(0.5), claims.iat + chrono::Duration::seconds(300));
+        assert_eq!(claims.renew_after(-1.0), claims.iat);
+        assert_eq!(claims.renew_after(2.0), claims.exp);
+        assert_eq!(claims.renew_after(f64::NAN), claims.exp);
+        assert_eq!(claims.time_remaining_at(claims.exp), Duration::ZERO);
+    }
 }
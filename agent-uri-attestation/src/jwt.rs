@@ -0,0 +1,315 @@
+//! JWT (JWS compact, `EdDSA`) interop for attestation claims.
+//!
+//! Enabled with the `jwt` feature. PASETO remains the native format; this
+//! module lets relying parties that only speak JOSE consume the same
+//! attestations. Claims map onto registered JWT claims (RFC 7519) where one
+//! exists:
+//!
+//! | Claim | JWT claim | Notes |
+//! |-------|-----------|-------|
+//! | `iss` | `iss` | Trust root |
+//! | `agent_uri` | `sub` | Full agent URI |
+//! | `agent_uris` | `agent_uris` | Private claim, optional array of strings |
+//! | `aud` | `aud` | Optional |
+//! | `iat` | `iat` | `NumericDate` (whole seconds) |
+//! | `exp` | `exp` | `NumericDate` (whole seconds) |
+//! | `nbf` | `nbf` | Optional `NumericDate` |
+//! | `capabilities` | `capabilities` | Private claim, same array as in PASETO tokens |
+//! | `denied_capabilities` | `denied_capabilities` | Private claim, optional array of strings |
+//! | `status_idx` | `status_idx` | Private claim, optional status list index |
+//! | `ver` | `ver` | Private claim, claims schema version; absent means version 1 |
+//! | `cnf` | `cnf` | Optional holder key (RFC 7800 JWK) |
+//! | `nonce` | `nonce` | Optional challenge nonce |
+//! | `orig_iat` | `orig_iat` | Private claim, optional renewal lineage in seconds |
+//!
+//! Only the `EdDSA` algorithm is accepted; the `alg` header is checked
+//! before any signature verification to rule out algorithm confusion.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, Verifier as _};
+use serde::{Deserialize, Serialize};
+
+use crate::claims::{AttestationClaims, LEGACY_CLAIMS_VERSION};
+use crate::constraints;
+use crate::error::AttestationError;
+use crate::keys::{SigningKey, VerifyingKey};
+
+/// JOSE header for Ed25519-signed JWTs.
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    alg: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    typ: Option<String>,
+}
+
+/// JWT claims set with registered claim names.
+#[derive(Debug, Serialize, Deserialize)]
+struct JwtClaims {
+    iss: String,
+    sub: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    agent_uris: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    aud: Option<String>,
+    iat: i64,
+    exp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nbf: Option<i64>,
+    #[serde(default = "empty_capabilities")]
+    capabilities: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    denied_capabilities: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status_idx: Option<u64>,
+    #[serde(default = "legacy_version")]
+    ver: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cnf: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nonce: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    orig_iat: Option<i64>,
+}
+
+/// Default for an absent `ver` claim.
+const fn legacy_version() -> u32 {
+    LEGACY_CLAIMS_VERSION
+}
+
+/// Default for an absent `capabilities` claim.
+fn empty_capabilities() -> serde_json::Value {
+    serde_json::Value::Array(Vec::new())
+}
+
+/// Encodes and signs `claims` as a compact JWS.
+pub(crate) fn sign(
+    claims: &AttestationClaims,
+    signing_key: &SigningKey,
+) -> Result<String, AttestationError> {
+    let header = Header {
+        alg: "EdDSA".to_string(),
+        typ: Some("JWT".to_string()),
+    };
+    let payload = JwtClaims {
+        iss: claims.iss.clone(),
+        sub: claims.agent_uri.clone(),
+        agent_uris: claims.agent_uris.clone(),
+        aud: claims.aud.clone(),
+        iat: claims.iat.timestamp(),
+        exp: claims.exp.timestamp(),
+        nbf: claims.nbf.map(|nbf| nbf.timestamp()),
+        capabilities: constraints::to_wire(&claims.capabilities, &claims.capability_constraints)?,
+        denied_capabilities: claims.denied_capabilities.clone(),
+        status_idx: claims.status_idx,
+        ver: claims.ver,
+        cnf: claims.cnf.as_ref().map(crate::pop::to_wire),
+        nonce: claims.nonce.clone(),
+        orig_iat: claims.orig_iat.map(|orig| orig.timestamp()),
+    };
+
+    let signing_input = format!("{}.{}", encode_part(&header)?, encode_part(&payload)?);
+    let signature = signing_key.as_dalek().sign(signing_input.as_bytes());
+
+    Ok(format!(
+        "{signing_input}.{}",
+        URL_SAFE_NO_PAD.encode(signature.to_bytes())
+    ))
+}
+
+/// A parsed, not yet verified JWT.
+pub(crate) struct UnverifiedJwt<'a> {
+    signing_input: &'a str,
+    signature: Signature,
+    claims: AttestationClaims,
+}
+
+impl UnverifiedJwt<'_> {
+    /// Returns the claims, which are untrusted until the signature is checked.
+    pub(crate) fn claims(&self) -> &AttestationClaims {
+        &self.claims
+    }
+
+    /// Checks the signature against `verifying_key`.
+    pub(crate) fn verify(&self, verifying_key: &VerifyingKey) -> Result<(), AttestationError> {
+        verifying_key
+            .as_dalek()
+            .verify(self.signing_input.as_bytes(), &self.signature)
+            .map_err(|_| AttestationError::InvalidSignature)
+    }
+
+    /// Returns the claims; only trusted after [`verify`](Self::verify) succeeds.
+    pub(crate) fn into_claims(self) -> AttestationClaims {
+        self.claims
+    }
+}
+
+/// Parses a compact JWS, checking the header but not the signature.
+pub(crate) fn decode(token: &str) -> Result<UnverifiedJwt<'_>, AttestationError> {
+    let invalid = |reason: &str| AttestationError::InvalidTokenFormat {
+        reason: reason.to_string(),
+    };
+
+    let (signing_input, signature) = token
+        .rsplit_once('.')
+        .ok_or_else(|| invalid("JWT must have three dot-separated parts"))?;
+    let (header, payload) = signing_input
+        .split_once('.')
+        .ok_or_else(|| invalid("JWT must have three dot-separated parts"))?;
+
+    let header: Header = decode_part(header)?;
+    if header.alg != "EdDSA" {
+        return Err(AttestationError::InvalidTokenFormat {
+            reason: format!("unsupported JWT algorithm '{}'; expected EdDSA", header.alg),
+        });
+    }
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or_else(|| invalid("JWT signature is not a valid Ed25519 signature"))?;
+
+    let payload: JwtClaims = decode_part(payload)?;
+    crate::limits::check_agent_uris(&payload.agent_uris)?;
+    let (capabilities, capability_constraints) = constraints::from_wire(&payload.capabilities)?;
+    let claims = AttestationClaims {
+        agent_uri: payload.sub,
+        agent_uris: payload.agent_uris,
+        capabilities,
+        capability_constraints,
+        denied_capabilities: payload.denied_capabilities,
+        iss: payload.iss,
+        iat: numeric_date(payload.iat)?,
+        exp: numeric_date(payload.exp)?,
+        nbf: payload.nbf.map(numeric_date).transpose()?,
+        aud: payload.aud,
+        status_idx: payload.status_idx,
+        ver: payload.ver,
+        cnf: payload.cnf.as_ref().map(crate::pop::from_wire).transpose()?,
+        nonce: payload.nonce,
+        orig_iat: payload.orig_iat.map(numeric_date).transpose()?,
+    }
+    .migrate()?;
+
+    Ok(UnverifiedJwt {
+        signing_input,
+        signature,
+        claims,
+    })
+}
+
+/// Serializes a JWT part as base64url-encoded JSON.
+fn encode_part<T: Serialize>(part: &T) -> Result<String, AttestationError> {
+    let json = serde_json::to_vec(part).map_err(|e| AttestationError::InvalidClaims {
+        reason: format!("failed to serialize JWT: {e}"),
+    })?;
+    Ok(URL_SAFE_NO_PAD.encode(json))
+}
+
+/// Deserializes a base64url-encoded JSON JWT part.
+fn decode_part<T: for<'de> Deserialize<'de>>(part: &str) -> Result<T, AttestationError> {
+    let json = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|e| AttestationError::InvalidTokenFormat {
+            reason: format!("invalid base64url in JWT: {e}"),
+        })?;
+    serde_json::from_slice(&json).map_err(|e| AttestationError::InvalidClaims {
+        reason: format!("invalid JWT JSON: {e}"),
+    })
+}
+
+/// Converts a JWT `NumericDate` to a UTC date-time.
+fn numeric_date(secs: i64) -> Result<DateTime<Utc>, AttestationError> {
+    DateTime::from_timestamp(secs, 0).ok_or_else(|| AttestationError::InvalidClaims {
+        reason: format!("JWT timestamp {secs} out of range"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::claims::AttestationClaimsBuilder;
+    use crate::constraints::CapabilityConstraints;
+
+    fn claims() -> AttestationClaims {
+        AttestationClaimsBuilder::new()
+            .agent_uri("agent://acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02q")
+            .add_agent_uri("agent://acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02r")
+            .issuer("acme.com")
+            .add_constrained_capability(
+                "workflow/approval",
+                CapabilityConstraints::new().limit("max_amount", 1000),
+            )
+            .deny_capability("workflow/approval/override")
+            .audience("partner.example.com")
+            .holder_key(SigningKey::generate().verifying_key())
+            .nonce("n0nce")
+            .ttl(Duration::from_hours(1))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn sign_and_decode_roundtrip() {
+        let key = SigningKey::generate();
+        let original = claims();
+
+        let token = sign(&original, &key).unwrap();
+        let jwt = decode(&token).unwrap();
+        jwt.verify(&key.verifying_key()).unwrap();
+        let decoded = jwt.into_claims();
+
+        assert_eq!(token.split('.').count(), 3);
+        assert_eq!(decoded.agent_uri, original.agent_uri);
+        assert_eq!(decoded.agent_uris, original.agent_uris);
+        assert_eq!(decoded.capabilities, original.capabilities);
+        assert_eq!(
+            decoded.capability_constraints,
+            original.capability_constraints
+        );
+        assert_eq!(decoded.denied_capabilities, original.denied_capabilities);
+        assert_eq!(decoded.aud, original.aud);
+        assert_eq!(decoded.cnf, original.cnf);
+        assert_eq!(decoded.nonce, original.nonce);
+        assert_eq!(decoded.exp.timestamp(), original.exp.timestamp());
+    }
+
+    #[test]
+    fn payload_uses_registered_claim_names() {
+        let token = sign(&claims(), &SigningKey::generate()).unwrap();
+        let payload = token.split('.').nth(1).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+
+        assert_eq!(json["sub"], claims().agent_uri);
+        assert!(json["exp"].is_i64());
+        assert!(json.get("agent_uri").is_none());
+    }
+
+    #[test]
+    fn rejects_other_algorithms() {
+        let token = sign(&claims(), &SigningKey::generate()).unwrap();
+        let forged_header = URL_SAFE_NO_PAD.encode(br#"{"alg":"none"}"#);
+        let (_, rest) = token.split_once('.').unwrap();
+        let forged = format!("{forged_header}.{rest}");
+
+        assert!(matches!(
+            decode(&forged),
+            Err(AttestationError::InvalidTokenFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn wrong_key_fails_signature() {
+        let token = sign(&claims(), &SigningKey::generate()).unwrap();
+        let result = decode(&token)
+            .unwrap()
+            .verify(&SigningKey::generate().verifying_key());
+
+        assert_eq!(result, Err(AttestationError::InvalidSignature));
+    }
+}
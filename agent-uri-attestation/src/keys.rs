@@ -1,6 +1,11 @@
 //! Key types for attestation signing and verification.
 
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
 use ed25519_dalek::{SigningKey as DalekSigningKey, VerifyingKey as DalekVerifyingKey};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 
 use crate::error::AttestationError;
 
@@ -60,6 +65,18 @@ impl SigningKey {
         }
     }
 
+    /// Signs a verifier's challenge to prove possession of this key.
+    ///
+    /// Holders of attestations bound to this key (see
+    /// [`AttestationClaimsBuilder::holder_key`](crate::AttestationClaimsBuilder::holder_key))
+    /// send the result to [`Verifier::verify_with_pop`](crate::Verifier::verify_with_pop).
+    /// The challenge is signed under a fixed context, so the proof cannot be
+    /// replayed as a signature over anything else.
+    #[must_use]
+    pub fn prove_possession(&self, challenge: &[u8]) -> [u8; 64] {
+        crate::pop::prove(self, challenge)
+    }
+
     /// Returns a reference to the inner dalek signing key.
     pub(crate) fn as_dalek(&self) -> &DalekSigningKey {
         &self.inner
@@ -92,8 +109,15 @@ impl std::fmt::Debug for SigningKey {
 ///
 /// // Deserialize later
 /// let recovered = VerifyingKey::from_bytes(&bytes).unwrap();
+///
+/// // Or name the key in configuration files and logs
+/// let text = verifying_key.to_string();
+/// assert_eq!(text.parse::<VerifyingKey>().unwrap(), verifying_key);
+/// println!("pinned key {}", verifying_key.fingerprint());
 /// ```
-#[derive(Clone, PartialEq, Eq)]
+///
+/// Equality comparisons run in constant time.
+#[derive(Clone)]
 pub struct VerifyingKey {
     inner: DalekVerifyingKey,
 }
@@ -119,6 +143,79 @@ impl VerifyingKey {
         self.inner.to_bytes()
     }
 
+    /// Returns a short, stable identifier for the key.
+    ///
+    /// The fingerprint is the first 8 bytes of the SHA-256 hash of the raw
+    /// key bytes, as 16 lowercase hex characters. It is meant for logs and
+    /// key selection, not for pinning: pin the full key instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::SigningKey;
+    ///
+    /// let key = SigningKey::generate().verifying_key();
+    /// assert_eq!(key.fingerprint().len(), 16);
+    /// ```
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        hex(&Sha256::digest(self.to_bytes())[..FINGERPRINT_BYTES])
+    }
+
+    /// Returns a reference to the inner dalek verifying key.
+    pub(crate) fn as_dalek(&self) -> &DalekVerifyingKey {
+        &self.inner
+    }
+}
+
+/// Number of hash bytes in a key fingerprint.
+const FINGERPRINT_BYTES: usize = 8;
+
+impl PartialEq for VerifyingKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.as_bytes().ct_eq(other.inner.as_bytes()).into()
+    }
+}
+
+impl Eq for VerifyingKey {}
+
+/// Formats the key in its canonical encoding: the 32 raw key bytes as 64
+/// lowercase hex characters.
+impl fmt::Display for VerifyingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex(&self.to_bytes()))
+    }
+}
+
+/// Parses a key from its canonical hex encoding.
+///
+/// Upper-case hex digits are accepted.
+impl FromStr for VerifyingKey {
+    type Err = AttestationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| AttestationError::InvalidKeyFormat { reason };
+        if s.len() != 64 {
+            return Err(invalid(format!("expected 64 hex characters, got {}", s.len())));
+        }
+        let mut bytes = [0u8; 32];
+        for (byte, pair) in bytes.iter_mut().zip(s.as_bytes().chunks_exact(2)) {
+            let pair = std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| invalid("key contains non-hex characters".to_string()))?;
+            *byte = pair;
+        }
+        Self::from_bytes(&bytes)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut out, byte| {
+        // Writing to a String cannot fail
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
 }
 
 impl std::fmt::Debug for VerifyingKey {
@@ -133,6 +230,64 @@ impl std::fmt::Debug for VerifyingKey {
     }
 }
 
+/// A set of verifying keys published by one trust root, of which at least
+/// `threshold` must sign an attestation.
+///
+/// Threshold key sets protect critical trust roots against the compromise of
+/// any single signing key: an attacker holding fewer than `threshold` keys
+/// cannot produce an attestation the verifier accepts.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri_attestation::{SigningKey, ThresholdKeySet};
+///
+/// let keys: Vec<_> = (0..3).map(|_| SigningKey::generate().verifying_key()).collect();
+/// let set = ThresholdKeySet::new(keys, 2).unwrap();
+///
+/// assert_eq!(set.threshold(), 2);
+/// assert_eq!(set.keys().len(), 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThresholdKeySet {
+    keys: Vec<VerifyingKey>,
+    threshold: usize,
+}
+
+impl ThresholdKeySet {
+    /// Creates a key set requiring `threshold` of `keys` to sign.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidThreshold` if `threshold` is zero,
+    /// exceeds the number of keys, or the same key appears more than once.
+    pub fn new(keys: Vec<VerifyingKey>, threshold: usize) -> Result<Self, AttestationError> {
+        let has_duplicates = keys
+            .iter()
+            .enumerate()
+            .any(|(i, key)| keys[..i].contains(key));
+        if threshold == 0 || threshold > keys.len() || has_duplicates {
+            return Err(AttestationError::InvalidThreshold {
+                threshold,
+                keys: keys.len(),
+            });
+        }
+        Ok(Self { keys, threshold })
+    }
+
+    /// Returns the published keys.
+    #[must_use]
+    pub fn keys(&self) -> &[VerifyingKey] {
+        &self.keys
+    }
+
+    /// Returns the minimum number of distinct keys that must sign.
+    #[must_use]
+    pub const fn threshold(&self) -> usize {
+        self.threshold
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,6 +356,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn threshold_key_set_validates_threshold() {
+        let keys: Vec<_> = (0..3)
+            .map(|_| SigningKey::generate().verifying_key())
+            .collect();
+
+        assert!(ThresholdKeySet::new(keys.clone(), 1).is_ok());
+        assert!(ThresholdKeySet::new(keys.clone(), 3).is_ok());
+        assert!(matches!(
+            ThresholdKeySet::new(keys.clone(), 0),
+            Err(AttestationError::InvalidThreshold { .. })
+        ));
+        assert!(matches!(
+            ThresholdKeySet::new(keys, 4),
+            Err(AttestationError::InvalidThreshold { threshold: 4, keys: 3 })
+        ));
+    }
+
+    #[test]
+    fn threshold_key_set_rejects_duplicate_keys() {
+        let key = SigningKey::generate().verifying_key();
+        let result = ThresholdKeySet::new(vec![key.clone(), key], 2);
+
+        assert!(matches!(
+            result,
+            Err(AttestationError::InvalidThreshold { .. })
+        ));
+    }
+
     #[test]
     fn signing_key_debug_shows_public_key() {
         let key = SigningKey::generate();
@@ -210,6 +394,39 @@ mod tests {
         assert!(debug_output.contains("VerifyingKey"));
     }
 
+    #[test]
+    fn verifying_key_text_roundtrip() {
+        let key = SigningKey::generate().verifying_key();
+        let text = key.to_string();
+
+        assert_eq!(text.len(), 64);
+        assert_eq!(text.parse::<VerifyingKey>().unwrap(), key);
+        assert_eq!(text.to_uppercase().parse::<VerifyingKey>().unwrap(), key);
+    }
+
+    #[test]
+    fn verifying_key_parse_rejects_malformed_text() {
+        let text = SigningKey::generate().verifying_key().to_string();
+
+        for bad in [&text[..62], "zz", &format!("{}zz", &text[..62]), "é".repeat(32).as_str()] {
+            assert!(matches!(
+                bad.parse::<VerifyingKey>(),
+                Err(AttestationError::InvalidKeyFormat { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn fingerprint_is_stable_and_distinct() {
+        let key = SigningKey::from_bytes(&[7u8; 32]).unwrap().verifying_key();
+        let other = SigningKey::from_bytes(&[8u8; 32]).unwrap().verifying_key();
+
+        assert_eq!(key.fingerprint(), key.clone().fingerprint());
+        assert_eq!(key.fingerprint().len(), 16);
+        assert!(key.to_string().len() > key.fingerprint().len());
+        assert_ne!(key.fingerprint(), other.fingerprint());
+    }
+
     #[test]
     fn verifying_key_debug_shows_partial_bytes() {
         let signing_key = SigningKey::generate();
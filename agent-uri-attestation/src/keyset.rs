@@ -0,0 +1,443 @@
+//! Signed key-set documents for key discovery.
+//!
+//! An [`AgentKeySet`] is the `agent-keys.json` document of the
+//! specification (section 7.2): a trust root's verification keys, each with
+//! a key ID and validity window, the next key announced ahead of rotation,
+//! and the IDs of revoked keys. It is the one artifact behind every way of
+//! learning a root's keys — served as plain JSON from
+//! `/.well-known/agent-keys.json`, signed with [`AgentKeySet::sign`] for
+//! distribution over untrusted channels such as DNS, or carried inside a
+//! [`TrustBundle`](crate::TrustBundle).
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rusty_paseto::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AttestationError;
+use crate::keys::{SigningKey, VerifyingKey};
+use crate::verifier::parse_with_key;
+
+/// Name of the claim carrying a signed key set.
+const KEY_SET_CLAIM: &str = "agent_key_set";
+
+/// The only signature algorithm keys are published for.
+const ALGORITHM: &str = "Ed25519";
+
+/// A verification key published in an [`AgentKeySet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishedKey {
+    /// Key ID, unique within the key set
+    pub kid: String,
+    /// The Ed25519 public key
+    pub key: VerifyingKey,
+    /// When the key starts signing
+    pub not_before: DateTime<Utc>,
+    /// When verifiers stop accepting the key
+    pub not_after: DateTime<Utc>,
+}
+
+impl PublishedKey {
+    /// Publishes `key` for `[not_before, not_after)`, identified by its
+    /// [`fingerprint`](VerifyingKey::fingerprint).
+    #[must_use]
+    pub fn new(key: VerifyingKey, not_before: DateTime<Utc>, not_after: DateTime<Utc>) -> Self {
+        Self {
+            kid: key.fingerprint(),
+            key,
+            not_before,
+            not_after,
+        }
+    }
+
+    /// Replaces the key ID.
+    #[must_use]
+    pub fn with_kid(mut self, kid: impl Into<String>) -> Self {
+        self.kid = kid.into();
+        self
+    }
+
+    /// Returns true if `now` lies within the key's validity window.
+    #[must_use]
+    pub fn is_valid_at(&self, now: DateTime<Utc>) -> bool {
+        self.not_before <= now && now < self.not_after
+    }
+}
+
+/// A trust root's published verification keys.
+///
+/// Serializes to the JSON layout of the specification's
+/// `agent-keys.json`, with public keys in standard base64. Deserializing
+/// rejects unknown algorithms, malformed keys and empty validity windows.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_attestation::{AgentKeySet, Issuer, PublishedKey, SigningKey, Verifier};
+/// use chrono::{Duration, Utc};
+///
+/// let signing_key = SigningKey::generate();
+/// let now = Utc::now();
+/// let mut key_set = AgentKeySet::new("acme.com");
+/// key_set.add_key(PublishedKey::new(signing_key.verifying_key(), now, now + Duration::days(30)));
+///
+/// // Publish as JSON, or sign it for untrusted channels
+/// let json = serde_json::to_string(&key_set).unwrap();
+/// assert_eq!(serde_json::from_str::<AgentKeySet>(&json).unwrap(), key_set);
+/// let document = key_set.sign(&signing_key, std::time::Duration::from_secs(3600)).unwrap();
+///
+/// let mut verifier = Verifier::new();
+/// verifier.load_key_set(&document, &signing_key.verifying_key()).unwrap();
+///
+/// let issuer = Issuer::new("acme.com", signing_key, std::time::Duration::from_secs(60));
+/// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+/// assert!(verifier.verify(&issuer.issue(&uri, vec![]).unwrap()).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "WireKeySet", try_from = "WireKeySet")]
+pub struct AgentKeySet {
+    trust_root: String,
+    keys: Vec<PublishedKey>,
+    next_key: Option<PublishedKey>,
+    revoked_keys: BTreeSet<String>,
+}
+
+impl AgentKeySet {
+    /// Creates an empty key set for `trust_root`.
+    #[must_use]
+    pub fn new(trust_root: impl Into<String>) -> Self {
+        Self {
+            trust_root: trust_root.into(),
+            keys: Vec::new(),
+            next_key: None,
+            revoked_keys: BTreeSet::new(),
+        }
+    }
+
+    /// Publishes `key`, replacing any key with the same ID.
+    pub fn add_key(&mut self, key: PublishedKey) {
+        self.keys.retain(|existing| existing.kid != key.kid);
+        self.keys.push(key);
+    }
+
+    /// Announces the key that will sign after the next rotation.
+    ///
+    /// The next key is trusted once its `not_before` passes, so verifiers
+    /// that cache the document keep accepting tokens across the rotation.
+    pub fn set_next_key(&mut self, key: PublishedKey) {
+        self.next_key = Some(key);
+    }
+
+    /// Revokes the key `kid`, removing it from the published keys and
+    /// listing it under `revoked_keys`.
+    ///
+    /// Returns true if the key was published.
+    pub fn revoke(&mut self, kid: &str) -> bool {
+        let published = self.keys.len();
+        self.keys.retain(|key| key.kid != kid);
+        let was_next = self.next_key.take_if(|key| key.kid == kid).is_some();
+        self.revoked_keys.insert(kid.to_string());
+        was_next || self.keys.len() < published
+    }
+
+    /// Returns the trust root the keys sign for.
+    #[must_use]
+    pub fn trust_root(&self) -> &str {
+        &self.trust_root
+    }
+
+    /// Returns the published keys, excluding the next key.
+    #[must_use]
+    pub fn keys(&self) -> &[PublishedKey] {
+        &self.keys
+    }
+
+    /// Returns the announced next key, if any.
+    #[must_use]
+    pub fn next_key(&self) -> Option<&PublishedKey> {
+        self.next_key.as_ref()
+    }
+
+    /// Returns the IDs of revoked keys.
+    #[must_use]
+    pub fn revoked_keys(&self) -> &BTreeSet<String> {
+        &self.revoked_keys
+    }
+
+    /// Looks up a published or next key by ID.
+    #[must_use]
+    pub fn get(&self, kid: &str) -> Option<&PublishedKey> {
+        self.keys
+            .iter()
+            .chain(self.next_key.as_ref())
+            .find(|key| key.kid == kid)
+    }
+
+    /// Returns the keys a verifier should trust at `now`: published and
+    /// next keys whose window contains `now` and that are not revoked.
+    #[must_use]
+    pub fn valid_keys_at(&self, now: DateTime<Utc>) -> Vec<VerifyingKey> {
+        self.keys
+            .iter()
+            .chain(self.next_key.as_ref())
+            .filter(|key| key.is_valid_at(now) && !self.revoked_keys.contains(&key.kid))
+            .map(|key| key.key.clone())
+            .collect()
+    }
+
+    /// Signs the key set as a PASETO v4.public token valid for `ttl`, issued
+    /// by the key set's trust root.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidTtl` if `ttl` is out of range, or
+    /// `AttestationError::InvalidTokenFormat` if signing fails.
+    pub fn sign(&self, signing_key: &SigningKey, ttl: Duration) -> Result<String, AttestationError> {
+        let key_bytes = signing_key.as_dalek().to_keypair_bytes();
+        let key_wrapper = Key::<64>::from(&key_bytes);
+        let paseto_key = PasetoAsymmetricPrivateKey::<V4, Public>::from(&key_wrapper);
+
+        let now = Utc::now();
+        let exp = now + chrono::Duration::from_std(ttl).map_err(|_| AttestationError::InvalidTtl)?;
+        let exp_str = exp.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+        let iat_str = now.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+
+        let exp_claim =
+            ExpirationClaim::try_from(exp_str.as_str()).map_err(|e| AttestationError::InvalidClaims {
+                reason: format!("invalid expiration: {e}"),
+            })?;
+        let iat_claim =
+            IssuedAtClaim::try_from(iat_str.as_str()).map_err(|e| AttestationError::InvalidClaims {
+                reason: format!("invalid issued at: {e}"),
+            })?;
+        let key_set_json =
+            serde_json::to_value(self).map_err(|e| AttestationError::InvalidClaims {
+                reason: format!("invalid key set: {e}"),
+            })?;
+        let key_set_claim = CustomClaim::try_from((KEY_SET_CLAIM, key_set_json)).map_err(|e| {
+            AttestationError::InvalidClaims {
+                reason: format!("invalid agent_key_set claim: {e}"),
+            }
+        })?;
+
+        PasetoBuilder::<V4, Public>::default()
+            .set_claim(exp_claim)
+            .set_claim(iat_claim)
+            .set_claim(IssuerClaim::from(self.trust_root.as_str()))
+            .set_claim(key_set_claim)
+            .build(&paseto_key)
+            .map_err(|e| AttestationError::InvalidTokenFormat {
+                reason: e.to_string(),
+            })
+    }
+
+    /// Verifies a signed key set and returns it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidSignature` if `token` was not signed
+    /// by `signer`, `AttestationError::TokenExpired` if the document has
+    /// expired, or `AttestationError::InvalidClaims` if it is malformed or
+    /// its `iss` differs from the key set's trust root.
+    pub fn open(token: &str, signer: &VerifyingKey) -> Result<Self, AttestationError> {
+        Self::open_at(token, signer, Utc::now())
+    }
+
+    /// Like [`open`](Self::open), checking expiry against `now`.
+    pub(crate) fn open_at(
+        token: &str,
+        signer: &VerifyingKey,
+        now: DateTime<Utc>,
+    ) -> Result<Self, AttestationError> {
+        let json = parse_with_key(token, signer, now)?;
+        let key_set: Self = json
+            .get(KEY_SET_CLAIM)
+            .map(|v| serde_json::from_value(v.clone()))
+            .transpose()
+            .map_err(|e| invalid(&e.to_string()))?
+            .ok_or_else(|| invalid("token has no agent_key_set claim"))?;
+        if json["iss"].as_str() != Some(key_set.trust_root.as_str()) {
+            return Err(invalid("iss does not match trust_root"));
+        }
+        Ok(key_set)
+    }
+}
+
+fn invalid(reason: &str) -> AttestationError {
+    AttestationError::InvalidClaims {
+        reason: format!("invalid key set: {reason}"),
+    }
+}
+
+/// Wire form of an [`AgentKeySet`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WireKeySet {
+    trust_root: String,
+    keys: Vec<WireKey>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next_key: Option<WireKey>,
+    #[serde(default)]
+    revoked_keys: Vec<String>,
+}
+
+/// Wire form of a [`PublishedKey`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WireKey {
+    kid: String,
+    algorithm: String,
+    public_key: String,
+    not_before: DateTime<Utc>,
+    not_after: DateTime<Utc>,
+}
+
+impl From<&PublishedKey> for WireKey {
+    fn from(key: &PublishedKey) -> Self {
+        Self {
+            kid: key.kid.clone(),
+            algorithm: ALGORITHM.to_string(),
+            public_key: STANDARD.encode(key.key.to_bytes()),
+            not_before: key.not_before,
+            not_after: key.not_after,
+        }
+    }
+}
+
+impl TryFrom<WireKey> for PublishedKey {
+    type Error = AttestationError;
+
+    fn try_from(wire: WireKey) -> Result<Self, Self::Error> {
+        if wire.algorithm != ALGORITHM {
+            return Err(invalid(&format!(
+                "key '{}' uses unsupported algorithm '{}'",
+                wire.kid, wire.algorithm
+            )));
+        }
+        if wire.not_before >= wire.not_after {
+            return Err(invalid(&format!("key '{}' has an empty validity window", wire.kid)));
+        }
+        let bytes: [u8; 32] = STANDARD
+            .decode(&wire.public_key)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| AttestationError::InvalidKeyFormat {
+                reason: format!("key '{}' is not a base64 Ed25519 public key", wire.kid),
+            })?;
+        Ok(Self {
+            kid: wire.kid,
+            key: VerifyingKey::from_bytes(&bytes)?,
+            not_before: wire.not_before,
+            not_after: wire.not_after,
+        })
+    }
+}
+
+impl From<AgentKeySet> for WireKeySet {
+    fn from(key_set: AgentKeySet) -> Self {
+        Self {
+            trust_root: key_set.trust_root,
+            keys: key_set.keys.iter().map(WireKey::from).collect(),
+            next_key: key_set.next_key.as_ref().map(WireKey::from),
+            revoked_keys: key_set.revoked_keys.into_iter().collect(),
+        }
+    }
+}
+
+impl TryFrom<WireKeySet> for AgentKeySet {
+    type Error = AttestationError;
+
+    fn try_from(wire: WireKeySet) -> Result<Self, Self::Error> {
+        Ok(Self {
+            trust_root: wire.trust_root,
+            keys: wire
+                .keys
+                .into_iter()
+                .map(PublishedKey::try_from)
+                .collect::<Result<_, _>>()?,
+            next_key: wire.next_key.map(PublishedKey::try_from).transpose()?,
+            revoked_keys: wire.revoked_keys.into_iter().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_set(now: DateTime<Utc>) -> (AgentKeySet, [SigningKey; 3]) {
+        let keys = [SigningKey::generate(), SigningKey::generate(), SigningKey::generate()];
+        let day = chrono::Duration::days(1);
+        let mut key_set = AgentKeySet::new("acme.com");
+        key_set.add_key(PublishedKey::new(keys[0].verifying_key(), now - day, now + day));
+        key_set.add_key(
+            PublishedKey::new(keys[1].verifying_key(), now - day * 3, now - day).with_kid("old"),
+        );
+        key_set.set_next_key(PublishedKey::new(keys[2].verifying_key(), now + day, now + day * 3));
+        (key_set, keys)
+    }
+
+    #[test]
+    fn json_follows_the_specification_layout() {
+        let (key_set, keys) = key_set(Utc::now());
+        let json = serde_json::to_value(&key_set).unwrap();
+
+        assert_eq!(json["trust_root"], "acme.com");
+        assert_eq!(json["keys"][0]["kid"], keys[0].verifying_key().fingerprint());
+        assert_eq!(json["keys"][0]["algorithm"], "Ed25519");
+        assert_eq!(
+            json["keys"][0]["public_key"],
+            STANDARD.encode(keys[0].verifying_key().to_bytes())
+        );
+        assert_eq!(json["keys"][1]["kid"], "old");
+        assert!(json["next_key"].is_object());
+        assert_eq!(json["revoked_keys"], serde_json::json!([]));
+        assert_eq!(serde_json::from_value::<AgentKeySet>(json).unwrap(), key_set);
+    }
+
+    #[test]
+    fn valid_keys_follow_windows_and_revocation() {
+        let now = Utc::now();
+        let (mut key_set, keys) = key_set(now);
+        assert_eq!(key_set.valid_keys_at(now), [keys[0].verifying_key()]);
+        assert_eq!(
+            key_set.valid_keys_at(now + chrono::Duration::hours(36)),
+            [keys[2].verifying_key()]
+        );
+
+        assert!(key_set.revoke(&keys[0].verifying_key().fingerprint()));
+        assert!(!key_set.revoke("unknown"));
+        assert!(key_set.valid_keys_at(now).is_empty());
+        assert_eq!(key_set.revoked_keys().len(), 2);
+    }
+
+    #[test]
+    fn sign_and_open_roundtrip() {
+        let (key_set, keys) = key_set(Utc::now());
+        let token = key_set.sign(&keys[0], Duration::from_mins(1)).unwrap();
+
+        assert_eq!(AgentKeySet::open(&token, &keys[0].verifying_key()).unwrap(), key_set);
+        assert_eq!(
+            AgentKeySet::open(&token, &keys[1].verifying_key()),
+            Err(AttestationError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn deserialize_rejects_malformed_keys() {
+        let (key_set, _) = key_set(Utc::now());
+        let json = serde_json::to_value(&key_set).unwrap();
+        let with = |pointer: &str, value: serde_json::Value| {
+            let mut json = json.clone();
+            *json.pointer_mut(pointer).unwrap() = value;
+            serde_json::from_value::<AgentKeySet>(json)
+        };
+
+        assert!(with("/keys/0/algorithm", "RS256".into()).is_err());
+        assert!(with("/keys/0/public_key", "AAAA".into()).is_err());
+        assert!(with("/keys/0/not_after", json["keys"][0]["not_before"].clone()).is_err());
+    }
+}
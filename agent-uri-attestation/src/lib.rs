@@ -41,11 +41,197 @@
 //! Attestation tokens are PASETO v4.public tokens containing:
 //!
 //! - `agent_uri`: The full agent URI being attested
-//! - `capabilities`: Array of capability strings granted
+//! - `agent_uris`: Optional further agent URIs attested by a fleet token
+//!   (see [`Issuer::issue_fleet`])
+//! - `capabilities`: Array of capabilities granted, each either a string or
+//!   an object carrying per-capability constraints
+//! - `denied_capabilities`: Optional array of capabilities excluded from the grant
 //! - `iss`: Issuer (trust root) that created the attestation
 //! - `iat`: Issued-at timestamp
 //! - `exp`: Expiration timestamp
+//! - `nbf`: Optional not-before timestamp
+//! - `orig_iat`: Issue time of the first token in a renewal chain (see
+//!   [`Issuer::renew`]; a [`TokenWatcher`] reports when held tokens are due)
 //! - `aud`: Optional audience restriction
+//! - `cnf`: Optional holder key for proof-of-possession
+//! - `nonce`: Optional verifier challenge for single-use tokens
+//! - `ver`: Claims schema version ([`CLAIMS_VERSION`]); tokens from older
+//!   known versions are migrated on decode with [`AttestationClaims::migrate`]
+//!
+//! # Verification Policies
+//!
+//! Relying parties that need more than signature and expiration checks can
+//! describe their requirements once as a [`VerificationPolicy`] and apply it
+//! with [`Verifier::verify_with_policy`]:
+//!
+//! ```rust
+//! use agent_uri_attestation::VerificationPolicy;
+//! use std::time::Duration;
+//!
+//! let policy = VerificationPolicy::builder()
+//!     .require_audience("api.acme.com")
+//!     .max_ttl(Duration::from_secs(3600))
+//!     .allow_trust_root("*.acme.com")
+//!     .build();
+//! # let _ = policy;
+//! ```
+//!
+//! Lifetime limits that should hold for every token, whichever method
+//! verifies it, can be set on the verifier itself with
+//! [`Verifier::with_max_ttl`] and [`Verifier::with_max_age`].
+//!
+//! # Capability Constraints
+//!
+//! Individual capabilities can carry [`CapabilityConstraints`] such as their
+//! own expiry, a usage limit, resource scopes or numeric limits like
+//! `max_amount`. Relying parties check them with
+//! [`AttestationClaims::check_capability`] and a [`CapabilityRequest`]
+//! describing the action being taken.
+//!
+//! Capabilities can also be carved out of a broader grant: a token granting
+//! `workflow` and denying `workflow/payments` covers every `workflow` path
+//! except the payments subtree.
+//!
+//! Coverage compares capabilities as `/`-separated paths, so a dotted grant
+//! such as `workflow.approval.read` covers no path by default. A
+//! [`CapabilityNotation`] set on the verifier or policy treats `.` as a
+//! separator too, or rejects dotted grants outright.
+//!
+//! Organizations migrating capability taxonomies can register
+//! [`CapabilityAliases`] mapping legacy names such as `email.send` to
+//! canonical paths such as `communication/email/send`, so tokens in either
+//! form validate during the transition.
+//!
+//! # Delegation Chains
+//!
+//! [`Verifier::verify_chain`] accepts a root-to-leaf sequence of tokens in
+//! which each token is issued by the trust root of the previous token's
+//! subject and may only narrow its capabilities.
+//! [`Verifier::verify_chain_with_nonce`] additionally requires the leaf to
+//! answer a challenge, as [`Verifier::verify_with_nonce`] does for a single
+//! token.
+//!
+//! [`AttestationClaims::diff`] compares a renewed token's claims with its
+//! predecessor's and flags any [`ClaimsDiff::escalated_capabilities`].
+//!
+//! # Feature Flags
+//!
+//! | Feature | Description |
+//! |---------|-------------|
+//! | `cose` | CWT (CBOR/`COSE_Sign1`) encoding via `Issuer::issue_cose` and `Verifier::verify_cose` |
+//! | `jwt` | `EdDSA` JWT interop via `AttestationClaims::to_jwt` and `Verifier::verify_jwt` |
+//! | `dht` | `Issuer::issue_registration` for attested `agent-uri-dht` registrations, and `Verifier` as a DHT `RegistrationValidator` |
+//! | `tracing` | Spans and per-step outcome/latency events for issuance and verification |
+//! | `status-http` | `HttpStatusChecker`, a reqwest-based [`StatusChecker`] |
+//! | `wasm` | `wasm-bindgen` exports of the verifier and browser-backed clock and randomness |
+//!
+//! # Co-signed Attestations
+//!
+//! High-assurance agents can be vouched for by several authorities at once.
+//! A [`CoSignedAttestation`] bundles one token per issuer over the same
+//! subject, and [`Verifier::verify_cosigned`] requires a chosen set of them.
+//!
+//! Critical trust roots can instead publish a [`ThresholdKeySet`] of `n` keys
+//! and require `k` of them to sign, via [`Verifier::add_threshold_root`] and
+//! [`Verifier::verify_threshold`].
+//!
+//! # Transparency Log
+//!
+//! An [`AttestationLog`] records every issued token in an append-only Merkle
+//! tree and signs its tree heads. A verifier configured with
+//! [`Verifier::require_transparency_log`] only accepts tokens presented with
+//! an [`InclusionProof`] against a [`SignedTreeHead`], via
+//! [`Verifier::verify_logged`].
+//!
+//! # Revocation
+//!
+//! Tokens issued with a `status_idx` claim (see
+//! [`AttestationClaimsBuilder::status_index`]) can be revoked by flipping
+//! their bit in a [`StatusList`]. The issuer republishes the list as a
+//! short-lived token with [`Issuer::issue_status_list`], and verifiers fetch
+//! it through a [`StatusListSource`] registered with
+//! [`Verifier::with_status_list_source`].
+//!
+//! Relying parties that need real-time answers for high-value operations
+//! can call [`Verifier::verify_online`] with an async [`StatusChecker`];
+//! the `status-http` feature provides an HTTP reference implementation.
+//!
+//! # Proof of Possession
+//!
+//! Tokens bound to a holder key with [`AttestationClaimsBuilder::holder_key`]
+//! carry a `cnf` claim. [`Verifier::verify_with_pop`] accepts them only with
+//! a signature over a fresh challenge from [`SigningKey::prove_possession`],
+//! so a leaked token cannot be replayed by anyone else.
+//!
+//! # Single-Use Tokens
+//!
+//! For handshakes such as DHT registration, a verifier hands out a challenge
+//! from [`Verifier::generate_nonce`], the issuer embeds it with
+//! [`AttestationClaimsBuilder::nonce`], and [`Verifier::verify_with_nonce`]
+//! accepts the token only for that challenge. Retiring each challenge after
+//! use makes the token single-use.
+//!
+//! # Offline Trust Bundles
+//!
+//! Air-gapped relying parties synchronize their trust state from a single
+//! file: a [`TrustBundle`] of trusted roots, threshold key sets and status
+//! list tokens, signed by a distribution key. Build one directly or with
+//! [`Verifier::export_bundle`], sign it with [`TrustBundle::sign`], and load
+//! it on the other side with [`Verifier::load_bundle`].
+//!
+//! # Issuance Hooks
+//!
+//! An [`IssuanceHook`] registered with [`Issuer::with_hook`] sees the claims
+//! of every attestation before and after it is signed, for audit logging
+//! and quota enforcement. A hook error vetoes issuance.
+//!
+//! # Caching
+//!
+//! A [`VerificationCache`] can be attached with [`Verifier::enable_cache`] so
+//! repeated presentations of the same token skip signature verification.
+//!
+//! # Time Sources
+//!
+//! [`Issuer::with_clock`] and [`Verifier::with_clock`] replace the system
+//! time with any [`Clock`]. A [`ManualClock`] makes expiry tests
+//! deterministic; embedded deployments can supply their own trusted time.
+//!
+//! # Metrics
+//!
+//! [`Verifier::metrics`] returns a [`VerifierMetrics`] snapshot of attempted
+//! verifications, failures by error kind, cache hits, average latency and
+//! signatures validated per key, which [`VerifierMetrics::to_prometheus`]
+//! renders for scraping.
+//!
+//! # Key Rotation
+//!
+//! A root can trust several keys at once with [`Verifier::add_trusted_key`].
+//! [`Verifier::verify_detailed`] reports which key validated a token, and
+//! the per-key counters show when an old key can be retired with
+//! [`Verifier::remove_trusted_key`].
+//!
+//! On the issuing side, [`KeyRollover`] rotates the signing key on a
+//! schedule, publishing the next key ahead of use and keeping the previous
+//! one published until its tokens have had time to expire.
+//!
+//! Keys are published as an [`AgentKeySet`]: the specification's
+//! `agent-keys.json` document with key IDs, validity windows, the next key
+//! and revoked key IDs. Serve it as JSON, or sign it and load it with
+//! [`Verifier::load_key_set`]; [`TrustBundle`]s carry key sets too.
+//!
+//! # WebAssembly
+//!
+//! The crate builds for `wasm32-unknown-unknown` with the `wasm` feature,
+//! which backs the clock and key generation with browser APIs and exports a
+//! JavaScript `Verifier` class (`WasmVerifier`) plus `decodeUnverified`.
+//! Claims are returned as JSON strings.
+//!
+//! # Debugging Tokens
+//!
+//! [`format_token`] renders a readable breakdown of a token's parts, claims
+//! and remaining lifetime without verifying it, and
+//! [`AttestationClaims::decode_unverified`] returns the untrusted claims.
+//! Neither requires pasting tokens into third-party decoders.
 //!
 //! # Security Properties
 //!
@@ -74,35 +260,88 @@
 //! | Component | Max Length |
 //! |-----------|------------|
 //! | Total token | 8192 chars |
-//! | agent_uri | 512 chars |
+//! | `agent_uri` | 512 chars |
+//! | `agent_uris` | 32 items |
 //! | capabilities | 64 items |
 //! | Each capability | 128 chars |
 //! | issuer | 128 chars |
 //! | audience | 128 chars |
+//!
+//! The claims builder always enforces the capability limits. An issuer in
+//! strict mode ([`Issuer::strict`]) enforces all of them before signing and
+//! reports violations as [`AttestationError::LengthLimitExceeded`].
 
 #![deny(missing_docs)]
 #![deny(clippy::all)]
 #![deny(clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
+mod bundle;
+mod cache;
 mod claims;
+mod clock;
+mod constraints;
+#[cfg(feature = "cose")]
+mod cose;
+mod cosign;
+mod diff;
 mod error;
+mod hooks;
+mod inspect;
 mod issuer;
+mod keyset;
+#[cfg(feature = "jwt")]
+mod jwt;
 mod keys;
+mod limits;
+mod metrics;
+mod online;
+mod policy;
+mod pop;
 #[cfg(kani)]
 mod proofs;
+mod rollover;
+mod status;
+mod telemetry;
+mod transparency;
 mod verification;
 mod verifier;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod watcher;
 
-pub use claims::{AttestationClaims, AttestationClaimsBuilder};
+pub use bundle::TrustBundle;
+pub use cache::VerificationCache;
+pub use claims::{AttestationClaims, AttestationClaimsBuilder, CLAIMS_VERSION};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use constraints::{CapabilityConstraints, CapabilityRequest};
+pub use cosign::CoSignedAttestation;
+pub use diff::ClaimsDiff;
 pub use error::AttestationError;
-pub use issuer::Issuer;
-pub use keys::{SigningKey, VerifyingKey};
+pub use hooks::IssuanceHook;
+pub use inspect::format_token;
+pub use issuer::{Issuer, IssuerBuilder};
+pub use keys::{SigningKey, ThresholdKeySet, VerifyingKey};
+pub use keyset::{AgentKeySet, PublishedKey};
+#[cfg(feature = "status-http")]
+pub use online::HttpStatusChecker;
+pub use metrics::VerifierMetrics;
+pub use online::StatusChecker;
+pub use policy::{VerificationPolicy, VerificationPolicyBuilder};
+pub use rollover::{KeyRollover, RolloverEvent};
+pub use status::{StatusList, StatusListSource};
+pub use transparency::{AttestationLog, InclusionProof, SignedTreeHead};
 pub use verification::{
-    capability_covers, check_capability_coverage, check_expiration, validate_issuer,
-    validate_subject,
+    capability_covers, capability_covers_with, check_capability_coverage,
+    check_capability_coverage_with, check_constrained_coverage, check_delegation,
+    check_expiration, check_freshness, check_max_ttl, check_not_before, trust_root_matches,
+    validate_audience, validate_issuer, validate_subject, CapabilityAliases,
+    CapabilityNotation,
 };
-pub use verifier::Verifier;
+pub use verifier::{VerifiedAttestation, Verifier};
+#[cfg(feature = "wasm")]
+pub use wasm::{decode_unverified, WasmVerifier};
+pub use watcher::{RenewalNotice, TokenWatcher};
 
 /// A prelude module for convenient imports.
 ///
@@ -113,8 +352,16 @@ pub use verifier::Verifier;
 /// ```
 pub mod prelude {
     pub use crate::{
-        capability_covers, check_capability_coverage, check_expiration, validate_issuer,
-        validate_subject, AttestationClaims, AttestationClaimsBuilder, AttestationError, Issuer,
-        SigningKey, Verifier, VerifyingKey,
+        capability_covers, capability_covers_with, check_capability_coverage,
+        check_capability_coverage_with, check_constrained_coverage, check_delegation,
+        check_expiration, check_freshness, check_max_ttl, check_not_before, format_token,
+        trust_root_matches, validate_audience, validate_issuer, validate_subject, AgentKeySet,
+        AttestationClaims, AttestationClaimsBuilder, AttestationError, AttestationLog,
+        CapabilityAliases, CapabilityConstraints, CapabilityNotation, CapabilityRequest,
+        ClaimsDiff, Clock, CoSignedAttestation, InclusionProof, IssuanceHook, Issuer,
+        IssuerBuilder, KeyRollover, ManualClock, PublishedKey, RenewalNotice, RolloverEvent,
+        SignedTreeHead, SigningKey, StatusChecker, StatusList, StatusListSource, SystemClock,
+        ThresholdKeySet, TokenWatcher, TrustBundle, VerificationCache, VerificationPolicy,
+        VerificationPolicyBuilder, VerifiedAttestation, Verifier, VerifierMetrics, VerifyingKey,
     };
 }
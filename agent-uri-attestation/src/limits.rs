@@ -0,0 +1,164 @@
+//! Length limits from the grammar specification.
+//!
+//! See the length constraints summary in `grammar.abnf`. The claims builder
+//! always enforces the capability limits; an [`Issuer`](crate::Issuer) in
+//! strict mode enforces the rest before releasing a token.
+
+use crate::claims::AttestationClaims;
+use crate::error::AttestationError;
+
+/// Maximum length of an encoded token.
+pub(crate) const MAX_TOKEN_LEN: usize = 8192;
+
+/// Maximum length of a token's decoded payload, in bytes.
+pub(crate) const MAX_PAYLOAD_LEN: usize = 4096;
+
+/// Maximum length of the `agent_uri` claim.
+pub(crate) const MAX_AGENT_URI_LEN: usize = 512;
+
+/// Maximum number of entries in the `agent_uris` claim.
+pub(crate) const MAX_AGENT_URIS: usize = 32;
+
+/// Maximum number of entries in a capability list.
+pub(crate) const MAX_CAPABILITIES: usize = 64;
+
+/// Maximum length of a single capability.
+pub(crate) const MAX_CAPABILITY_LEN: usize = 128;
+
+/// Maximum length of the `iss` claim.
+pub(crate) const MAX_ISSUER_LEN: usize = 128;
+
+/// Maximum length of the `aud` claim.
+pub(crate) const MAX_AUDIENCE_LEN: usize = 128;
+
+/// Maximum length of the `nonce` claim.
+pub(crate) const MAX_NONCE_LEN: usize = 128;
+
+/// Checks a capability list named `field` against the count and length limits.
+pub(crate) fn check_capabilities(
+    field: &'static str,
+    caps: &[String],
+) -> Result<(), AttestationError> {
+    check(field, caps.len(), MAX_CAPABILITIES)?;
+    caps.iter().try_for_each(|cap| check(field, cap.len(), MAX_CAPABILITY_LEN))
+}
+
+/// Checks the `agent_uris` claim against the count and length limits.
+pub(crate) fn check_agent_uris(uris: &[String]) -> Result<(), AttestationError> {
+    check("agent_uris", uris.len(), MAX_AGENT_URIS)?;
+    uris.iter().try_for_each(|uri| check("agent_uris", uri.len(), MAX_AGENT_URI_LEN))
+}
+
+/// Checks every length-limited claim.
+pub(crate) fn check_claims(claims: &AttestationClaims) -> Result<(), AttestationError> {
+    check("agent_uri", claims.agent_uri.len(), MAX_AGENT_URI_LEN)?;
+    check_agent_uris(&claims.agent_uris)?;
+    check_capabilities("capabilities", &claims.capabilities)?;
+    check_capabilities("denied_capabilities", &claims.denied_capabilities)?;
+    check("iss", claims.iss.len(), MAX_ISSUER_LEN)?;
+    if let Some(aud) = &claims.aud {
+        check("aud", aud.len(), MAX_AUDIENCE_LEN)?;
+    }
+    if let Some(nonce) = &claims.nonce {
+        check("nonce", nonce.len(), MAX_NONCE_LEN)?;
+    }
+    Ok(())
+}
+
+/// Checks an encoded PASETO token and its decoded payload.
+pub(crate) fn check_token(token: &str) -> Result<(), AttestationError> {
+    check("token", token.len(), MAX_TOKEN_LEN)?;
+    let payload = token
+        .strip_prefix("v4.public.")
+        .and_then(|rest| rest.split('.').next())
+        .unwrap_or_default();
+    // Unpadded base64url carries 6 bits per character
+    check("payload", payload.len() * 6 / 8, MAX_PAYLOAD_LEN)
+}
+
+/// Checks a compact JWT and its decoded payload.
+#[cfg(feature = "jwt")]
+pub(crate) fn check_jwt(token: &str) -> Result<(), AttestationError> {
+    check("token", token.len(), MAX_TOKEN_LEN)?;
+    let payload = token.split('.').nth(1).unwrap_or_default();
+    check("payload", payload.len() * 6 / 8, MAX_PAYLOAD_LEN)
+}
+
+/// Checks an encoded CWT token against the token length limit.
+#[cfg(feature = "cose")]
+pub(crate) fn check_cwt(token: &[u8]) -> Result<(), AttestationError> {
+    check("token", token.len(), MAX_TOKEN_LEN)
+}
+
+fn check(field: &'static str, length: usize, max: usize) -> Result<(), AttestationError> {
+    if length > max {
+        Err(AttestationError::LengthLimitExceeded { field, length, max })
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capability_limits() {
+        let caps: Vec<String> = (0..MAX_CAPABILITIES).map(|i| format!("cap{i}")).collect();
+        assert!(check_capabilities("capabilities", &caps).is_ok());
+
+        let mut too_many = caps;
+        too_many.push("one_more".to_string());
+        assert_eq!(
+            check_capabilities("capabilities", &too_many),
+            Err(AttestationError::LengthLimitExceeded {
+                field: "capabilities",
+                length: 65,
+                max: 64,
+            })
+        );
+
+        let too_long = vec!["a".repeat(MAX_CAPABILITY_LEN + 1)];
+        assert!(matches!(
+            check_capabilities("denied_capabilities", &too_long),
+            Err(AttestationError::LengthLimitExceeded { field: "denied_capabilities", .. })
+        ));
+    }
+
+    #[test]
+    fn token_limits() {
+        assert!(check_token(&format!("v4.public.{}", "A".repeat(5000))).is_ok());
+        assert!(matches!(
+            check_token(&format!("v4.public.{}", "A".repeat(6000))),
+            Err(AttestationError::LengthLimitExceeded { field: "payload", .. })
+        ));
+        assert!(matches!(
+            check_token(&format!("v4.public.A.{}", "A".repeat(MAX_TOKEN_LEN))),
+            Err(AttestationError::LengthLimitExceeded { field: "token", .. })
+        ));
+    }
+
+    #[cfg(feature = "jwt")]
+    #[test]
+    fn jwt_limits() {
+        assert!(check_jwt(&format!("e30.{}.sig", "A".repeat(5000))).is_ok());
+        assert!(matches!(
+            check_jwt(&format!("e30.{}.sig", "A".repeat(6000))),
+            Err(AttestationError::LengthLimitExceeded { field: "payload", .. })
+        ));
+        assert!(matches!(
+            check_jwt(&format!("e30.A.{}", "A".repeat(MAX_TOKEN_LEN))),
+            Err(AttestationError::LengthLimitExceeded { field: "token", .. })
+        ));
+    }
+
+    #[cfg(feature = "cose")]
+    #[test]
+    fn cwt_limits() {
+        assert!(check_cwt(&[0; MAX_TOKEN_LEN]).is_ok());
+        assert!(matches!(
+            check_cwt(&[0; MAX_TOKEN_LEN + 1]),
+            Err(AttestationError::LengthLimitExceeded { field: "token", .. })
+        ));
+    }
+}
@@ -0,0 +1,273 @@
+//! Verification counters for capacity planning and attack detection.
+//!
+//! Every [`Verifier`] counts the tokens it verifies, how they fail, how
+//! often the cache answers, and how long verification takes. A
+//! [`VerifierMetrics`] snapshot is available from [`Verifier::metrics`] and
+//! can be rendered in the Prometheus text exposition format.
+//!
+//! Counters cover token verification: signature, issuer, validity window
+//! and status checks, once per token (so a chain of three tokens counts as
+//! three). Follow-on URI, capability and policy checks are not counted.
+//!
+//! Successful signature checks are also counted per trusted key, keyed by
+//! [`VerifyingKey::fingerprint`], so operators can see when a retiring key
+//! stops being used.
+//!
+//! [`Verifier`]: crate::Verifier
+//! [`Verifier::metrics`]: crate::Verifier::metrics
+//! [`VerifyingKey::fingerprint`]: crate::VerifyingKey::fingerprint
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use web_time::Instant;
+
+use crate::error::AttestationError;
+use crate::keys::VerifyingKey;
+
+/// A point-in-time snapshot of a verifier's counters.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri_attestation::{Issuer, Verifier};
+/// use agent_uri::AgentUri;
+/// use std::time::Duration;
+///
+/// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+/// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let token = issuer.issue(&uri, vec![]).unwrap();
+///
+/// let mut verifier = Verifier::new();
+/// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+/// verifier.verify(&token).unwrap();
+/// assert!(verifier.verify("v4.public.garbage").is_err());
+///
+/// let metrics = verifier.metrics();
+/// assert_eq!(metrics.attempted, 2);
+/// assert_eq!(metrics.failed(), 1);
+/// assert!(metrics.to_prometheus().contains("agent_uri_verifications_total 2"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifierMetrics {
+    /// Number of token verifications attempted
+    pub attempted: u64,
+    /// Number of verifications that succeeded
+    pub succeeded: u64,
+    /// Failed verifications keyed by [`AttestationError::kind`]
+    pub failures: BTreeMap<&'static str, u64>,
+    /// Number of verifications answered by the cache
+    pub cache_hits: u64,
+    /// Signatures validated by each trusted key, keyed by key fingerprint
+    pub key_usage: BTreeMap<String, u64>,
+    /// Mean time spent per verification, including cache hits
+    pub average_latency: Duration,
+}
+
+impl VerifierMetrics {
+    /// Returns the total number of failed verifications.
+    #[must_use]
+    pub fn failed(&self) -> u64 {
+        self.failures.values().sum()
+    }
+
+    /// Renders the snapshot in the Prometheus text exposition format.
+    ///
+    /// Metric names are prefixed with `agent_uri_`; failures carry an
+    /// `error` label with the error kind.
+    #[must_use]
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        let _ = writeln!(
+            out,
+            "# HELP agent_uri_verifications_total Token verifications attempted.\n\
+             # TYPE agent_uri_verifications_total counter\n\
+             agent_uri_verifications_total {}",
+            self.attempted
+        );
+        let _ = writeln!(
+            out,
+            "# HELP agent_uri_verification_successes_total Token verifications that succeeded.\n\
+             # TYPE agent_uri_verification_successes_total counter\n\
+             agent_uri_verification_successes_total {}",
+            self.succeeded
+        );
+        let _ = writeln!(
+            out,
+            "# HELP agent_uri_verification_failures_total Token verifications that failed.\n\
+             # TYPE agent_uri_verification_failures_total counter"
+        );
+        for (kind, count) in &self.failures {
+            let _ = writeln!(
+                out,
+                "agent_uri_verification_failures_total{{error=\"{kind}\"}} {count}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP agent_uri_verification_cache_hits_total Verifications answered by the cache.\n\
+             # TYPE agent_uri_verification_cache_hits_total counter\n\
+             agent_uri_verification_cache_hits_total {}",
+            self.cache_hits
+        );
+        let _ = writeln!(
+            out,
+            "# HELP agent_uri_key_verifications_total Signatures validated by each trusted key.\n\
+             # TYPE agent_uri_key_verifications_total counter"
+        );
+        for (key, count) in &self.key_usage {
+            let _ = writeln!(out, "agent_uri_key_verifications_total{{key=\"{key}\"}} {count}");
+        }
+        let _ = writeln!(
+            out,
+            "# HELP agent_uri_verification_latency_seconds_avg Mean verification latency.\n\
+             # TYPE agent_uri_verification_latency_seconds_avg gauge\n\
+             agent_uri_verification_latency_seconds_avg {}",
+            self.average_latency.as_secs_f64()
+        );
+        out
+    }
+}
+
+/// Live counters updated by the verifier.
+///
+/// Cloning a recorder starts from zero, mirroring how cloning a
+/// [`VerificationCache`](crate::VerificationCache) does not copy entries.
+#[derive(Debug, Default)]
+pub(crate) struct MetricsRecorder {
+    attempted: AtomicU64,
+    succeeded: AtomicU64,
+    cache_hits: AtomicU64,
+    latency_nanos: AtomicU64,
+    failures: Mutex<BTreeMap<&'static str, u64>>,
+    key_usage: Mutex<BTreeMap<String, u64>>,
+}
+
+impl MetricsRecorder {
+    /// Records the outcome of one verification that began at `started`.
+    pub(crate) fn record<T>(&self, started: Instant, result: &Result<T, AttestationError>) {
+        let nanos = u64::try_from(started.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        self.latency_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.attempted.fetch_add(1, Ordering::Relaxed);
+        match result {
+            Ok(_) => {
+                self.succeeded.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(error) => {
+                let mut failures = self.failures.lock().expect("lock poisoned");
+                *failures.entry(error.kind()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Records that a verification was answered by the cache.
+    pub(crate) fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that `key` validated a token's signature.
+    pub(crate) fn record_key(&self, key: &VerifyingKey) {
+        let mut usage = self.key_usage.lock().expect("lock poisoned");
+        *usage.entry(key.fingerprint()).or_default() += 1;
+    }
+
+    /// Returns a snapshot of the counters.
+    pub(crate) fn snapshot(&self) -> VerifierMetrics {
+        let attempted = self.attempted.load(Ordering::Relaxed);
+        let latency_nanos = self.latency_nanos.load(Ordering::Relaxed);
+        VerifierMetrics {
+            attempted,
+            succeeded: self.succeeded.load(Ordering::Relaxed),
+            failures: self.failures.lock().expect("lock poisoned").clone(),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            key_usage: self.key_usage.lock().expect("lock poisoned").clone(),
+            average_latency: Duration::from_nanos(
+                latency_nanos.checked_div(attempted).unwrap_or_default(),
+            ),
+        }
+    }
+
+    /// Resets all counters to zero.
+    pub(crate) fn reset(&self) {
+        self.attempted.store(0, Ordering::Relaxed);
+        self.succeeded.store(0, Ordering::Relaxed);
+        self.cache_hits.store(0, Ordering::Relaxed);
+        self.latency_nanos.store(0, Ordering::Relaxed);
+        self.failures.lock().expect("lock poisoned").clear();
+        self.key_usage.lock().expect("lock poisoned").clear();
+    }
+}
+
+impl Clone for MetricsRecorder {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_outcomes_by_kind() {
+        let recorder = MetricsRecorder::default();
+        recorder.record(Instant::now(), &Ok(()));
+        recorder.record::<()>(Instant::now(), &Err(AttestationError::InvalidSignature));
+        recorder.record::<()>(Instant::now(), &Err(AttestationError::InvalidSignature));
+        recorder.record_cache_hit();
+
+        let metrics = recorder.snapshot();
+        assert_eq!(metrics.attempted, 3);
+        assert_eq!(metrics.succeeded, 1);
+        assert_eq!(metrics.failures.get("InvalidSignature"), Some(&2));
+        assert_eq!(metrics.cache_hits, 1);
+    }
+
+    #[test]
+    fn records_usage_per_key() {
+        let recorder = MetricsRecorder::default();
+        let old = crate::SigningKey::generate().verifying_key();
+        let new = crate::SigningKey::generate().verifying_key();
+        recorder.record_key(&old);
+        recorder.record_key(&new);
+        recorder.record_key(&new);
+
+        let metrics = recorder.snapshot();
+        assert_eq!(metrics.key_usage.get(&old.fingerprint()), Some(&1));
+        assert_eq!(metrics.key_usage.get(&new.fingerprint()), Some(&2));
+        assert!(metrics.to_prometheus().contains(&format!(
+            "agent_uri_key_verifications_total{{key=\"{}\"}} 2\n",
+            new.fingerprint()
+        )));
+    }
+
+    #[test]
+    fn reset_clears_counters() {
+        let recorder = MetricsRecorder::default();
+        recorder.record(Instant::now(), &Ok(()));
+        recorder.reset();
+
+        assert_eq!(recorder.snapshot(), VerifierMetrics::default());
+    }
+
+    #[test]
+    fn prometheus_output_labels_failures() {
+        let mut metrics = VerifierMetrics {
+            attempted: 5,
+            succeeded: 4,
+            ..VerifierMetrics::default()
+        };
+        metrics.failures.insert("TokenExpired", 1);
+
+        let text = metrics.to_prometheus();
+        assert!(text.contains("agent_uri_verifications_total 5\n"));
+        assert!(
+            text.contains("agent_uri_verification_failures_total{error=\"TokenExpired\"} 1\n")
+        );
+        assert!(text.contains("# TYPE agent_uri_verification_cache_hits_total counter\n"));
+    }
+}
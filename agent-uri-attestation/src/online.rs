@@ -0,0 +1,277 @@
+//! Online (OCSP-like) status checking.
+//!
+//! Status lists bound revocation latency by the list's lifetime. Relying
+//! parties guarding high-value operations can instead ask the issuer
+//! whether a token is still good at the moment of use, through a
+//! [`StatusChecker`] passed to [`Verifier::verify_online`].
+//!
+//! With the `status-http` feature, [`HttpStatusChecker`] implements the
+//! check against an HTTP status responder.
+//!
+//! [`Verifier::verify_online`]: crate::Verifier::verify_online
+
+use std::future::Future;
+
+use crate::claims::AttestationClaims;
+use crate::error::AttestationError;
+
+/// Confirms in real time that a verified token has not been revoked.
+///
+/// The verifier only calls the checker after the token's signature and
+/// validity window have been verified, so implementations can trust
+/// `claims`. Returning an error rejects the token; implementations should
+/// fail closed when the responder cannot be reached.
+///
+/// # Example
+///
+/// ```
+/// use std::future::Future;
+///
+/// use agent_uri_attestation::{AttestationClaims, AttestationError, StatusChecker};
+///
+/// struct DenyIssuer(&'static str);
+///
+/// impl StatusChecker for DenyIssuer {
+///     fn check_status(
+///         &self,
+///         _token: &str,
+///         claims: &AttestationClaims,
+///     ) -> impl Future<Output = Result<(), AttestationError>> + Send {
+///         let revoked = claims.iss == self.0;
+///         let issuer = claims.iss.clone();
+///         async move {
+///             if revoked {
+///                 Err(AttestationError::TokenRevokedOnline { issuer })
+///             } else {
+///                 Ok(())
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub trait StatusChecker: Send + Sync {
+    /// Checks whether the token with the given verified claims is still good.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenRevokedOnline` if the token has been revoked, or
+    /// `StatusUnavailable` if its status could not be determined.
+    fn check_status(
+        &self,
+        token: &str,
+        claims: &AttestationClaims,
+    ) -> impl Future<Output = Result<(), AttestationError>> + Send;
+}
+
+#[cfg(feature = "status-http")]
+pub use http::HttpStatusChecker;
+
+#[cfg(feature = "status-http")]
+mod http {
+    use std::future::Future;
+
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use serde::{Deserialize, Serialize};
+    use sha2::{Digest, Sha256};
+
+    use super::StatusChecker;
+    use crate::claims::AttestationClaims;
+    use crate::error::AttestationError;
+
+    /// Status query sent to the responder.
+    #[derive(Debug, Serialize)]
+    struct StatusRequest<'a> {
+        iss: &'a str,
+        agent_uri: &'a str,
+        token_hash: String,
+    }
+
+    /// Status reported by the responder.
+    #[derive(Debug, Deserialize)]
+    struct StatusResponse {
+        status: String,
+    }
+
+    /// A [`StatusChecker`] that queries an HTTP status responder.
+    ///
+    /// Each check POSTs a JSON object with the token's `iss`, `agent_uri`
+    /// and `token_hash` (the base64url SHA-256 of the token, so the bearer
+    /// token itself never leaves the relying party). The responder answers
+    /// with `{"status": "good"}` or `{"status": "revoked"}`; any other
+    /// answer, HTTP error or transport failure is reported as
+    /// `StatusUnavailable`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::HttpStatusChecker;
+    ///
+    /// let checker = HttpStatusChecker::new("https://status.acme.com/v1/check");
+    /// assert_eq!(checker.endpoint(), "https://status.acme.com/v1/check");
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct HttpStatusChecker {
+        client: reqwest::Client,
+        endpoint: String,
+    }
+
+    impl HttpStatusChecker {
+        /// Creates a checker for the responder at `endpoint`.
+        #[must_use]
+        pub fn new(endpoint: impl Into<String>) -> Self {
+            Self::with_client(reqwest::Client::new(), endpoint)
+        }
+
+        /// Creates a checker that sends requests with `client`.
+        ///
+        /// Use this to configure timeouts, proxies or client certificates.
+        #[must_use]
+        pub fn with_client(client: reqwest::Client, endpoint: impl Into<String>) -> Self {
+            Self {
+                client,
+                endpoint: endpoint.into(),
+            }
+        }
+
+        /// Returns the responder endpoint.
+        #[must_use]
+        pub fn endpoint(&self) -> &str {
+            &self.endpoint
+        }
+    }
+
+    impl StatusChecker for HttpStatusChecker {
+        fn check_status(
+            &self,
+            token: &str,
+            claims: &AttestationClaims,
+        ) -> impl Future<Output = Result<(), AttestationError>> + Send {
+            let issuer = claims.iss.clone();
+            let request = self.client.post(&self.endpoint).json(&StatusRequest {
+                iss: &claims.iss,
+                agent_uri: &claims.agent_uri,
+                token_hash: URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes())),
+            });
+
+            async move {
+                let unavailable = |reason: String| AttestationError::StatusUnavailable {
+                    issuer: issuer.clone(),
+                    reason,
+                };
+
+                let response = request
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status)
+                    .map_err(|e| unavailable(format!("status responder request failed: {e}")))?;
+                let body: StatusResponse = response
+                    .json()
+                    .await
+                    .map_err(|e| unavailable(format!("invalid status response: {e}")))?;
+
+                match body.status.as_str() {
+                    "good" => Ok(()),
+                    "revoked" => Err(AttestationError::TokenRevokedOnline {
+                        issuer: issuer.clone(),
+                    }),
+                    other => Err(unavailable(format!("unknown token status '{other}'"))),
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        use std::thread;
+
+        use super::*;
+
+        /// Serves one HTTP request with a JSON `body` and returns the
+        /// responder URL plus a handle yielding the raw request.
+        fn responder(
+            status: &'static str,
+            body: &'static str,
+        ) -> (String, thread::JoinHandle<String>) {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}/check", listener.local_addr().unwrap());
+            let handle = thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some((head, rest)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|line| {
+                                let line = line.to_lowercase();
+                                let value = line.strip_prefix("content-length:")?;
+                                value.trim().parse::<usize>().ok()
+                            })
+                            .unwrap_or(0);
+                        if rest.len() >= length {
+                            break;
+                        }
+                    }
+                }
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\ncontent-type: application/json\r\n\
+                     content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+                String::from_utf8(request).unwrap()
+            });
+            (url, handle)
+        }
+
+        fn claims() -> AttestationClaims {
+            AttestationClaims::builder()
+                .agent_uri("agent://acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02q")
+                .issuer("acme.com")
+                .build()
+                .unwrap()
+        }
+
+        #[tokio::test]
+        async fn good_status_accepts_and_hashes_token() {
+            let (url, handle) = responder("200 OK", r#"{"status":"good"}"#);
+            let checker = HttpStatusChecker::new(url);
+
+            assert_eq!(checker.check_status("v4.public.token", &claims()).await, Ok(()));
+
+            let request = handle.join().unwrap();
+            assert!(request.starts_with("POST /check "));
+            assert!(request.contains(r#""iss":"acme.com""#));
+            assert!(!request.contains("v4.public.token"));
+        }
+
+        #[tokio::test]
+        async fn revoked_status_rejects() {
+            let (url, _) = responder("200 OK", r#"{"status":"revoked"}"#);
+            let checker = HttpStatusChecker::new(url);
+
+            assert!(matches!(
+                checker.check_status("token", &claims()).await,
+                Err(AttestationError::TokenRevokedOnline { ref issuer }) if issuer == "acme.com"
+            ));
+        }
+
+        #[tokio::test]
+        async fn responder_error_fails_closed() {
+            let (url, _) = responder("503 Service Unavailable", "{}");
+            let checker = HttpStatusChecker::new(url);
+
+            assert!(matches!(
+                checker.check_status("token", &claims()).await,
+                Err(AttestationError::StatusUnavailable { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,534 @@
+//! Composable verification policies.
+//!
+//! A [`VerificationPolicy`] bundles the relying-party checks that sit on top
+//! of signature and expiration verification: audience restriction, maximum
+//! token lifetime, required capabilities, allowed trust roots and so on.
+//! Policies are built once and applied with [`Verifier::verify_with_policy`],
+//! so every relying party enforces the same rules in the same order.
+//!
+//! [`Verifier::verify_with_policy`]: crate::Verifier::verify_with_policy
+
+use std::time::Duration;
+
+use agent_uri::CapabilityPath;
+use chrono::{DateTime, Utc};
+
+use crate::claims::AttestationClaims;
+use crate::constraints::CapabilityRequest;
+use crate::error::AttestationError;
+use crate::verification::{self, CapabilityAliases, CapabilityNotation};
+
+/// A set of additional checks applied to verified attestation claims.
+///
+/// The default policy performs no checks beyond what [`Verifier::verify`]
+/// already does. Each builder method adds one requirement.
+///
+/// Checks are evaluated in a fixed order and the first failure is returned:
+///
+/// 1. Allowed trust roots
+/// 2. Issuer binding to the attested URI
+/// 3. Not-before presence and validity
+/// 4. Maximum token lifetime
+/// 5. Maximum token age
+/// 6. Audience
+/// 7. Required capabilities
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::CapabilityPath;
+/// use agent_uri_attestation::VerificationPolicy;
+/// use std::time::Duration;
+///
+/// let policy = VerificationPolicy::builder()
+///     .require_audience("api.acme.com")
+///     .max_ttl(Duration::from_secs(3600))
+///     .require_capability(CapabilityPath::parse("workflow/approval").unwrap())
+///     .allow_trust_root("*.acme.com")
+///     .build();
+///
+/// assert_eq!(policy.audience(), Some("api.acme.com"));
+/// ```
+///
+/// [`Verifier::verify`]: crate::Verifier::verify
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationPolicy {
+    audience: Option<String>,
+    max_ttl: Option<Duration>,
+    max_age: Option<Duration>,
+    required_capabilities: Vec<CapabilityPath>,
+    capability_notation: CapabilityNotation,
+    capability_aliases: CapabilityAliases,
+    allowed_trust_roots: Vec<String>,
+    require_not_before: bool,
+    require_issuer_binding: bool,
+}
+
+impl VerificationPolicy {
+    /// Creates a new builder for a verification policy.
+    #[must_use]
+    pub fn builder() -> VerificationPolicyBuilder {
+        VerificationPolicyBuilder::new()
+    }
+
+    /// Returns the required audience, if any.
+    #[must_use]
+    pub fn audience(&self) -> Option<&str> {
+        self.audience.as_deref()
+    }
+
+    /// Returns the maximum accepted token lifetime, if any.
+    #[must_use]
+    pub fn max_ttl(&self) -> Option<Duration> {
+        self.max_ttl
+    }
+
+    /// Returns the maximum accepted token age, if any.
+    #[must_use]
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    /// Returns the capabilities the token must cover.
+    #[must_use]
+    pub fn required_capabilities(&self) -> &[CapabilityPath] {
+        &self.required_capabilities
+    }
+
+    /// Returns how dotted capabilities are compared with required paths.
+    #[must_use]
+    pub fn capability_notation(&self) -> CapabilityNotation {
+        self.capability_notation
+    }
+
+    /// Returns the legacy capability aliases consulted by coverage checks.
+    #[must_use]
+    pub fn capability_aliases(&self) -> &CapabilityAliases {
+        &self.capability_aliases
+    }
+
+    /// Returns the allowed trust root patterns.
+    ///
+    /// An empty slice means any trusted issuer is accepted.
+    #[must_use]
+    pub fn allowed_trust_roots(&self) -> &[String] {
+        &self.allowed_trust_roots
+    }
+
+    /// Checks verified claims against this policy at the given time.
+    ///
+    /// Signature and expiration are not re-checked here; this method is
+    /// meant to run on claims returned by the verifier. Taking `now` as a
+    /// parameter keeps the time-dependent checks testable.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failing check:
+    /// - `TrustRootNotAllowed` - Issuer matches no allowed trust root pattern
+    /// - `TrustRootMismatch` - Issuer differs from the attested URI's trust root
+    /// - `MissingField` - `nbf` is required but absent
+    /// - `TokenNotYetValid` - `nbf` is in the future
+    /// - `TtlExceedsMaximum` - Token lifetime exceeds `max_ttl`
+    /// - `TokenTooOld` - Token was issued longer than `max_age` ago
+    /// - `AudienceMismatch` - Token audience differs from the required audience
+    /// - `MixedCapabilityNotation` - A capability is dotted under
+    ///   [`CapabilityNotation::RejectDotted`]
+    /// - `InsufficientCapabilities` - A required capability is not covered
+    /// - `CapabilityConstraintViolated` - Every covering capability is
+    ///   expired or scoped to specific resources at `now`
+    /// - `CapabilityDenied` - A required capability is explicitly denied
+    pub fn check(
+        &self,
+        claims: &AttestationClaims,
+        now: DateTime<Utc>,
+    ) -> Result<(), AttestationError> {
+        if !self.allowed_trust_roots.is_empty()
+            && !self
+                .allowed_trust_roots
+                .iter()
+                .any(|pattern| verification::trust_root_matches(pattern, &claims.iss))
+        {
+            return Err(AttestationError::TrustRootNotAllowed {
+                issuer: claims.iss.clone(),
+            });
+        }
+
+        if self.require_issuer_binding {
+            verification::validate_issuer(claims.trust_root().unwrap_or_default(), &claims.iss)?;
+        }
+
+        match claims.nbf {
+            Some(nbf) => verification::check_not_before(nbf, now)?,
+            None if self.require_not_before => {
+                return Err(AttestationError::MissingField { field: "nbf" });
+            }
+            None => {}
+        }
+
+        if let Some(max_ttl) = self.max_ttl {
+            verification::check_max_ttl(claims.iat, claims.exp, max_ttl)?;
+        }
+
+        if let Some(max_age) = self.max_age {
+            verification::check_freshness(claims.iat, now, max_age)?;
+        }
+
+        if let Some(audience) = &self.audience {
+            verification::validate_audience(audience, claims.aud.as_deref())?;
+        }
+
+        if !self.required_capabilities.is_empty() {
+            let aliased = self.capability_aliases.expand_claims(claims);
+            let normalized = self.capability_notation.normalize_claims(&aliased)?;
+            for required in &self.required_capabilities {
+                let request = CapabilityRequest::new(required.clone(), now);
+                normalized.check_capability(&request)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Builder for constructing a [`VerificationPolicy`].
+///
+/// # Example
+///
+/// ```
+/// use agent_uri_attestation::VerificationPolicyBuilder;
+///
+/// let policy = VerificationPolicyBuilder::new()
+///     .allow_trust_root("acme.com")
+///     .require_issuer_binding()
+///     .require_not_before()
+///     .build();
+///
+/// assert_eq!(policy.allowed_trust_roots(), ["acme.com"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VerificationPolicyBuilder {
+    policy: VerificationPolicy,
+}
+
+impl VerificationPolicyBuilder {
+    /// Creates a new builder with no requirements.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires the token's `aud` claim to equal `audience`.
+    #[must_use]
+    pub fn require_audience(mut self, audience: impl Into<String>) -> Self {
+        self.policy.audience = Some(audience.into());
+        self
+    }
+
+    /// Rejects tokens whose lifetime (`exp - iat`) exceeds `max_ttl`.
+    #[must_use]
+    pub fn max_ttl(mut self, max_ttl: Duration) -> Self {
+        self.policy.max_ttl = Some(max_ttl);
+        self
+    }
+
+    /// Rejects tokens issued more than `max_age` ago (`now - iat`), however
+    /// long they remain valid.
+    #[must_use]
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.policy.max_age = Some(max_age);
+        self
+    }
+
+    /// Requires the token's capabilities to cover `capability`.
+    ///
+    /// May be called multiple times; every capability must be covered.
+    #[must_use]
+    pub fn require_capability(mut self, capability: CapabilityPath) -> Self {
+        self.policy.required_capabilities.push(capability);
+        self
+    }
+
+    /// Sets how granted capabilities written with `.` separators are
+    /// matched against required capabilities; see [`CapabilityNotation`].
+    #[must_use]
+    pub fn capability_notation(mut self, notation: CapabilityNotation) -> Self {
+        self.policy.capability_notation = notation;
+        self
+    }
+
+    /// Treats granted or denied `legacy` capabilities as also granting or
+    /// denying `canonical`; see [`CapabilityAliases`].
+    ///
+    /// May be called multiple times; a later alias for the same legacy
+    /// capability replaces the earlier one.
+    #[must_use]
+    pub fn capability_alias(
+        mut self,
+        legacy: impl Into<String>,
+        canonical: CapabilityPath,
+    ) -> Self {
+        self.policy.capability_aliases.insert(legacy, canonical);
+        self
+    }
+
+    /// Allows tokens from issuers matching `pattern`.
+    ///
+    /// Patterns are exact trust roots (`acme.com`) or subdomain wildcards
+    /// (`*.acme.com`). Once any pattern is added, issuers matching none of
+    /// them are rejected.
+    #[must_use]
+    pub fn allow_trust_root(mut self, pattern: impl Into<String>) -> Self {
+        self.policy.allowed_trust_roots.push(pattern.into());
+        self
+    }
+
+    /// Requires the token to carry an `nbf` claim.
+    ///
+    /// An `nbf` claim that is present is always enforced; this only rejects
+    /// tokens that omit it.
+    #[must_use]
+    pub fn require_not_before(mut self) -> Self {
+        self.policy.require_not_before = true;
+        self
+    }
+
+    /// Requires the issuer to equal the trust root of the attested URI.
+    #[must_use]
+    pub fn require_issuer_binding(mut self) -> Self {
+        self.policy.require_issuer_binding = true;
+        self
+    }
+
+    /// Builds the policy.
+    #[must_use]
+    pub fn build(self) -> VerificationPolicy {
+        self.policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration as ChronoDuration;
+
+    use super::*;
+    use crate::claims::AttestationClaimsBuilder;
+    use crate::constraints::CapabilityConstraints;
+
+    fn claims() -> AttestationClaimsBuilder {
+        AttestationClaimsBuilder::new()
+            .agent_uri("agent://acme.com/workflow/approval/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .add_capability("workflow")
+            .ttl(Duration::from_hours(1))
+    }
+
+    #[test]
+    fn default_policy_accepts_anything() {
+        let claims = claims().build().unwrap();
+        assert!(VerificationPolicy::default().check(&claims, Utc::now()).is_ok());
+    }
+
+    #[test]
+    fn audience_required_and_matching() {
+        let policy = VerificationPolicy::builder()
+            .require_audience("api.acme.com")
+            .build();
+        let claims = claims().audience("api.acme.com").build().unwrap();
+
+        assert!(policy.check(&claims, Utc::now()).is_ok());
+    }
+
+    #[test]
+    fn audience_required_but_missing() {
+        let policy = VerificationPolicy::builder()
+            .require_audience("api.acme.com")
+            .build();
+        let claims = claims().build().unwrap();
+
+        let result = policy.check(&claims, Utc::now());
+        assert!(matches!(
+            result,
+            Err(AttestationError::AudienceMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn max_ttl_rejects_long_lived_tokens() {
+        let policy = VerificationPolicy::builder()
+            .max_ttl(Duration::from_mins(10))
+            .build();
+        let claims = claims().build().unwrap();
+
+        let result = policy.check(&claims, Utc::now());
+        assert!(matches!(
+            result,
+            Err(AttestationError::TtlExceedsMaximum { max_secs: 600, .. })
+        ));
+    }
+
+    #[test]
+    fn max_age_rejects_stale_tokens() {
+        let policy = VerificationPolicy::builder()
+            .max_age(Duration::from_mins(10))
+            .build();
+        let claims = claims().build().unwrap();
+
+        assert!(policy.check(&claims, claims.iat + chrono::Duration::minutes(10)).is_ok());
+        assert!(matches!(
+            policy.check(&claims, claims.iat + chrono::Duration::minutes(11)),
+            Err(AttestationError::TokenTooOld { max_age_secs: 600, .. })
+        ));
+    }
+
+    #[test]
+    fn capability_notation_controls_dotted_grants() {
+        let required = CapabilityPath::parse("workflow/approval").unwrap();
+        let claims = claims()
+            .capabilities(vec!["workflow.approval".into()])
+            .build()
+            .unwrap();
+        let check = |notation| {
+            VerificationPolicy::builder()
+                .require_capability(required.clone())
+                .capability_notation(notation)
+                .build()
+                .check(&claims, Utc::now())
+        };
+
+        assert!(matches!(
+            check(CapabilityNotation::Exact),
+            Err(AttestationError::InsufficientCapabilities { .. })
+        ));
+        assert!(check(CapabilityNotation::Unified).is_ok());
+        assert!(matches!(
+            check(CapabilityNotation::RejectDotted),
+            Err(AttestationError::MixedCapabilityNotation { .. })
+        ));
+    }
+
+    #[test]
+    fn capability_aliases_cover_canonical_paths() {
+        let canonical = CapabilityPath::parse("communication/email/send").unwrap();
+        let policy = VerificationPolicy::builder()
+            .require_capability(canonical.clone())
+            .capability_alias("email.send", canonical)
+            .build();
+        let legacy = claims()
+            .capabilities(vec!["email.send".into()])
+            .build()
+            .unwrap();
+        let current = claims()
+            .capabilities(vec!["communication/email".into()])
+            .build()
+            .unwrap();
+
+        assert_eq!(policy.capability_aliases().len(), 1);
+        assert!(policy.check(&legacy, Utc::now()).is_ok());
+        assert!(policy.check(&current, Utc::now()).is_ok());
+    }
+
+    #[test]
+    fn required_capabilities_must_all_be_covered() {
+        let policy = VerificationPolicy::builder()
+            .require_capability(CapabilityPath::parse("workflow/approval").unwrap())
+            .require_capability(CapabilityPath::parse("assistant/chat").unwrap())
+            .build();
+        let claims = claims().build().unwrap();
+
+        let result = policy.check(&claims, Utc::now());
+        assert!(matches!(
+            result,
+            Err(AttestationError::InsufficientCapabilities { ref required, .. })
+                if required == "assistant/chat"
+        ));
+    }
+
+    #[test]
+    fn required_capabilities_respect_constraints() {
+        let now = Utc::now();
+        let policy = VerificationPolicy::builder()
+            .require_capability(CapabilityPath::parse("workflow/approval").unwrap())
+            .build();
+        let expired = claims()
+            .capabilities(vec![])
+            .add_constrained_capability(
+                "workflow",
+                CapabilityConstraints::new().expires_at(now - ChronoDuration::minutes(1)),
+            )
+            .build()
+            .unwrap();
+        let current = claims()
+            .capabilities(vec![])
+            .add_constrained_capability(
+                "workflow",
+                CapabilityConstraints::new().expires_at(now + ChronoDuration::minutes(1)),
+            )
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            policy.check(&expired, now),
+            Err(AttestationError::CapabilityConstraintViolated { ref capability, .. })
+                if capability == "workflow"
+        ));
+        assert!(policy.check(&current, now).is_ok());
+    }
+
+    #[test]
+    fn allowed_trust_roots_filter_issuers() {
+        let claims = claims().build().unwrap();
+
+        let allowed = VerificationPolicy::builder()
+            .allow_trust_root("other.com")
+            .allow_trust_root("acme.com")
+            .build();
+        assert!(allowed.check(&claims, Utc::now()).is_ok());
+
+        let denied = VerificationPolicy::builder()
+            .allow_trust_root("*.acme.com")
+            .build();
+        assert!(matches!(
+            denied.check(&claims, Utc::now()),
+            Err(AttestationError::TrustRootNotAllowed { .. })
+        ));
+    }
+
+    #[test]
+    fn issuer_binding_rejects_foreign_issuer() {
+        let policy = VerificationPolicy::builder()
+            .require_issuer_binding()
+            .build();
+        let claims = claims().issuer("other.com").build().unwrap();
+
+        let result = policy.check(&claims, Utc::now());
+        assert!(matches!(
+            result,
+            Err(AttestationError::TrustRootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn require_not_before_rejects_missing_claim() {
+        let policy = VerificationPolicy::builder().require_not_before().build();
+        let claims = claims().build().unwrap();
+
+        let result = policy.check(&claims, Utc::now());
+        assert!(matches!(
+            result,
+            Err(AttestationError::MissingField { field: "nbf" })
+        ));
+    }
+
+    #[test]
+    fn not_before_is_enforced_at_given_time() {
+        let policy = VerificationPolicy::builder().require_not_before().build();
+        let nbf = Utc::now() + ChronoDuration::minutes(5);
+        let claims = claims().not_before(nbf).build().unwrap();
+
+        assert!(matches!(
+            policy.check(&claims, nbf - ChronoDuration::seconds(1)),
+            Err(AttestationError::TokenNotYetValid { .. })
+        ));
+        assert!(policy.check(&claims, nbf).is_ok());
+    }
+}
@@ -0,0 +1,170 @@
+//! Proof-of-possession binding through the `cnf` claim.
+//!
+//! A plain attestation is a bearer token: whoever presents it is trusted.
+//! An attestation issued with a holder key (see
+//! [`AttestationClaimsBuilder::holder_key`]) carries that key in a `cnf`
+//! (confirmation) claim, and [`Verifier::verify_with_pop`] additionally
+//! requires a signature by the holder over a verifier-chosen challenge, so a
+//! stolen token is useless without the holder's private key.
+//!
+//! # Wire Format
+//!
+//! The claim follows RFC 7800, with the key as an Ed25519 JWK (RFC 8037):
+//!
+//! ```json
+//! "cnf": {"jwk": {"kty": "OKP", "crv": "Ed25519", "x": "<base64url key>"}}
+//! ```
+//!
+//! Holders sign the challenge prefixed with a fixed context string, so a
+//! proof can never double as a signature over a token or any other message.
+//!
+//! [`AttestationClaimsBuilder::holder_key`]: crate::AttestationClaimsBuilder::holder_key
+//! [`Verifier::verify_with_pop`]: crate::Verifier::verify_with_pop
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, Verifier as _};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AttestationError;
+use crate::keys::{SigningKey, VerifyingKey};
+
+/// Context prepended to every challenge before signing.
+const POP_CONTEXT: &[u8] = b"agent-uri-attestation/pop/v1\0";
+
+/// The `cnf` claim.
+#[derive(Debug, Serialize, Deserialize)]
+struct Confirmation {
+    jwk: Jwk,
+}
+
+/// An Ed25519 public key as an OKP JSON Web Key.
+#[derive(Debug, Serialize, Deserialize)]
+struct Jwk {
+    kty: String,
+    crv: String,
+    x: String,
+}
+
+/// Encodes `key` as a `cnf` claim value.
+pub(crate) fn to_wire(key: &VerifyingKey) -> serde_json::Value {
+    serde_json::json!(Confirmation {
+        jwk: Jwk {
+            kty: "OKP".to_string(),
+            crv: "Ed25519".to_string(),
+            x: URL_SAFE_NO_PAD.encode(key.to_bytes()),
+        },
+    })
+}
+
+/// Decodes the holder key from a `cnf` claim value.
+pub(crate) fn from_wire(value: &serde_json::Value) -> Result<VerifyingKey, AttestationError> {
+    let invalid = |reason: String| AttestationError::InvalidClaims {
+        reason: format!("invalid cnf claim: {reason}"),
+    };
+
+    let cnf: Confirmation =
+        serde_json::from_value(value.clone()).map_err(|e| invalid(e.to_string()))?;
+    if cnf.jwk.kty != "OKP" || cnf.jwk.crv != "Ed25519" {
+        return Err(invalid(format!(
+            "unsupported key type {}/{}; expected OKP/Ed25519",
+            cnf.jwk.kty, cnf.jwk.crv
+        )));
+    }
+    let bytes: [u8; 32] = URL_SAFE_NO_PAD
+        .decode(&cnf.jwk.x)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid("x must be a base64url 32-byte key".to_string()))?;
+    VerifyingKey::from_bytes(&bytes)
+}
+
+/// Serde adapter for the optional `cnf` field of the claims.
+pub(crate) mod serde_cnf {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::keys::VerifyingKey;
+
+    #[allow(clippy::ref_option)] // signature required by `serialize_with`
+    pub(crate) fn serialize<S: Serializer>(
+        key: &Option<VerifyingKey>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match key {
+            Some(key) => serializer.serialize_some(&super::to_wire(key)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<VerifyingKey>, D::Error> {
+        Option::<serde_json::Value>::deserialize(deserializer)?
+            .map(|value| super::from_wire(&value).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+/// Returns the message a holder signs to prove possession for `challenge`.
+fn message(challenge: &[u8]) -> Vec<u8> {
+    [POP_CONTEXT, challenge].concat()
+}
+
+/// Signs `challenge` with the holder's key.
+pub(crate) fn prove(holder: &SigningKey, challenge: &[u8]) -> [u8; 64] {
+    holder.as_dalek().sign(&message(challenge)).to_bytes()
+}
+
+/// Checks a holder's signature over `challenge`.
+pub(crate) fn verify(
+    holder: &VerifyingKey,
+    challenge: &[u8],
+    signature: &[u8],
+) -> Result<(), AttestationError> {
+    let failed = |reason: &str| AttestationError::ProofOfPossessionFailed {
+        reason: reason.to_string(),
+    };
+    let signature = Signature::from_slice(signature)
+        .map_err(|_| failed("signature is not a 64-byte Ed25519 signature"))?;
+    holder
+        .as_dalek()
+        .verify(&message(challenge), &signature)
+        .map_err(|_| failed("signature does not match the token's holder key"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cnf_roundtrip() {
+        let key = SigningKey::generate().verifying_key();
+        let wire = to_wire(&key);
+
+        assert_eq!(wire["jwk"]["crv"], "Ed25519");
+        assert_eq!(from_wire(&wire).unwrap(), key);
+    }
+
+    #[test]
+    fn cnf_rejects_other_key_types() {
+        let wire = serde_json::json!({"jwk": {"kty": "EC", "crv": "P-256", "x": "AAAA"}});
+
+        assert!(matches!(
+            from_wire(&wire),
+            Err(AttestationError::InvalidClaims { .. })
+        ));
+    }
+
+    #[test]
+    fn proof_is_bound_to_challenge_and_context() {
+        let holder = SigningKey::generate();
+        let proof = prove(&holder, b"nonce-1");
+
+        assert!(verify(&holder.verifying_key(), b"nonce-1", &proof).is_ok());
+        assert!(verify(&holder.verifying_key(), b"nonce-2", &proof).is_err());
+
+        // A plain signature over the challenge is not a proof
+        let raw = holder.as_dalek().sign(b"nonce-1").to_bytes();
+        assert!(verify(&holder.verifying_key(), b"nonce-1", &raw).is_err());
+    }
+}
@@ -26,6 +26,10 @@
 //! | Capability | Exact match works | `exact_match_is_covered` |
 //! | Capability | Deterministic | `capability_covers_deterministic` |
 //! | Capability | Prefix coverage | `prefix_covers_child` |
+//! | Capability | Coverage is transitive | `covers_is_transitive` |
+//! | Capability | Monotone in granted set | `coverage_is_monotone` |
+//! | Delegation | Never gains capabilities | `attenuation_never_gains_capabilities` |
+//! | Delegation | Expiry only shrinks | `delegated_expiry_only_shrinks` |
 //! | Claims | Missing URI errors | `builder_missing_uri_errors` |
 //! | Claims | Missing issuer errors | `builder_missing_issuer_errors` |
 //! | Claims | Construction safe | `builder_construction_never_panics` |
@@ -33,8 +37,11 @@
 // Proofs module is conditionally compiled only when running Kani
 #![cfg(kani)]
 
-use crate::claims::AttestationClaimsBuilder;
-use crate::verification::{capability_covers, validate_issuer, validate_subject};
+use crate::claims::{AttestationClaims, AttestationClaimsBuilder};
+use crate::verification::{
+    capability_covers, check_delegation, covers, delegated_expiry, validate_issuer,
+    validate_subject,
+};
 
 /// Proof harnesses for issuer validation (agent-uri specific: trust root binding).
 mod issuer_proofs {
@@ -68,12 +75,14 @@ mod issuer_proofs {
         }
     }
 
-    /// Prove validation is symmetric in its error case.
+    /// Prove validation is symmetric in its error case for plain trust
+    /// roots (wildcard patterns are directional by design).
     #[kani::proof]
     #[kani::unwind(12)]
     fn issuer_validation_symmetry() {
         let a: [u8; 8] = kani::any();
         let b: [u8; 8] = kani::any();
+        kani::assume(a[0] != b'*' && b[0] != b'*');
 
         if let (Ok(str_a), Ok(str_b)) = (std::str::from_utf8(&a), std::str::from_utf8(&b)) {
             let result_ab = validate_issuer(str_a, str_b);
@@ -199,6 +208,127 @@ mod capability_proofs {
     }
 }
 
+/// Proof harnesses for delegation (attenuation) invariants.
+///
+/// Capabilities are drawn symbolically from a small set of paths that
+/// covers every prefix relation (ancestor, descendant, sibling, unrelated,
+/// and a string prefix that is not a path prefix).
+mod delegation_proofs {
+    use super::*;
+    use agent_uri::CapabilityPath;
+    use chrono::{DateTime, Utc};
+
+    const PATHS: [&str; 5] = [
+        "workflow",
+        "workflow/approval",
+        "workflow/approval/urgent",
+        "workflow/payments",
+        "workflowx",
+    ];
+
+    /// Returns a symbolic choice among [`PATHS`].
+    fn any_path() -> &'static str {
+        let index: usize = kani::any();
+        kani::assume(index < PATHS.len());
+        PATHS[index]
+    }
+
+    /// Builds claims for `agent://eu.acme.com/...` without the builder,
+    /// whose use of the system clock Kani cannot model.
+    fn claims(iss: &str, capabilities: Vec<String>) -> AttestationClaims {
+        AttestationClaims {
+            agent_uri: "agent://eu.acme.com/workflow/agent_01h455vb4pex5vsknk084sn02q"
+                .to_string(),
+            agent_uris: Vec::new(),
+            capabilities,
+            capability_constraints: std::collections::BTreeMap::new(),
+            denied_capabilities: Vec::new(),
+            iss: iss.to_string(),
+            iat: DateTime::<Utc>::default(),
+            exp: DateTime::<Utc>::default(),
+            nbf: None,
+            aud: None,
+            status_idx: None,
+            ver: crate::CLAIMS_VERSION,
+            cnf: None,
+            nonce: None,
+            orig_iat: None,
+        }
+    }
+
+    /// Prove `covers` is transitive on symbolic strings, the lemma that
+    /// makes multi-hop delegation sound.
+    #[kani::proof]
+    #[kani::unwind(6)]
+    fn covers_is_transitive() {
+        let a: [u8; 4] = kani::any();
+        let b: [u8; 4] = kani::any();
+        let c: [u8; 4] = kani::any();
+
+        if let (Ok(a), Ok(b), Ok(c)) = (
+            std::str::from_utf8(&a),
+            std::str::from_utf8(&b),
+            std::str::from_utf8(&c),
+        ) {
+            if covers(a, b) && covers(b, c) {
+                assert!(covers(a, c));
+            }
+        }
+    }
+
+    /// Prove granting an extra capability never removes coverage.
+    #[kani::proof]
+    #[kani::unwind(30)]
+    fn coverage_is_monotone() {
+        let mut granted = vec![any_path().to_string()];
+        let extra = any_path();
+
+        if let Ok(required) = CapabilityPath::parse(any_path()) {
+            if capability_covers(&granted, &required) {
+                granted.push(extra.to_string());
+                assert!(capability_covers(&granted, &required));
+            }
+        }
+    }
+
+    /// Prove an accepted delegation covers nothing its parent does not.
+    #[kani::proof]
+    #[kani::unwind(30)]
+    fn attenuation_never_gains_capabilities() {
+        let parent = claims("acme.com", vec![any_path().to_string()]);
+        let child = claims(
+            "eu.acme.com",
+            vec![any_path().to_string(), any_path().to_string()],
+        );
+
+        if check_delegation(1, &parent, &child).is_ok() {
+            if let Ok(required) = CapabilityPath::parse(any_path()) {
+                if capability_covers(&child.capabilities, &required) {
+                    assert!(capability_covers(&parent.capabilities, &required));
+                }
+            }
+        }
+    }
+
+    /// Prove appending a link never extends a chain's expiry.
+    #[kani::proof]
+    fn delegated_expiry_only_shrinks() {
+        let chain: i64 = kani::any();
+        let link: i64 = kani::any();
+        kani::assume((0..=4_102_444_800).contains(&chain));
+        kani::assume((0..=4_102_444_800).contains(&link));
+
+        if let (Some(chain), Some(link)) = (
+            DateTime::<Utc>::from_timestamp(chain, 0),
+            DateTime::<Utc>::from_timestamp(link, 0),
+        ) {
+            let exp = delegated_expiry(chain, link);
+            assert!(exp <= chain && exp <= link);
+            assert!(exp == chain || exp == link);
+        }
+    }
+}
+
 /// Proof harnesses for claims builder validation.
 ///
 /// Note: Full builder verification is limited by Kani's handling of time-based
@@ -0,0 +1,334 @@
+//! Scheduled signing key rotation.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::clock::{Clock, SharedClock};
+use crate::issuer::Issuer;
+use crate::keys::{SigningKey, VerifyingKey};
+use crate::keyset::{AgentKeySet, PublishedKey};
+
+/// A change made by [`KeyRollover::tick`].
+///
+/// Keys are identified by their [`VerifyingKey::fingerprint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RolloverEvent {
+    /// A next key was generated and added to the key-set document.
+    NextKeyPublished {
+        /// Fingerprint of the new key
+        kid: String,
+    },
+    /// The next key became the active signing key.
+    KeyActivated {
+        /// Fingerprint of the newly active key
+        kid: String,
+    },
+    /// A previously active key was removed from the key-set document.
+    KeyRetired {
+        /// Fingerprint of the retired key
+        kid: String,
+    },
+}
+
+/// Rotates an issuer's signing key on a fixed schedule.
+///
+/// Each key signs for `interval`. `overlap` before the end of that period a
+/// next key is generated and published alongside the active one, so
+/// verifiers that refresh the key-set document learn it before any token
+/// uses it. When the period ends the next key becomes active, and the old
+/// key stays published for another `overlap` so tokens it signed keep
+/// verifying. An overlap longer than the interval is shortened to it.
+///
+/// Nothing happens on its own: call [`tick`](Self::tick) periodically (at
+/// least once per `overlap`) and republish
+/// [`key_set`](Self::key_set) whenever it returns events.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use agent_uri_attestation::{KeyRollover, ManualClock, RolloverEvent, SigningKey};
+///
+/// let clock = ManualClock::new(chrono::Utc::now());
+/// let day = Duration::from_secs(86400);
+/// let mut rollover = KeyRollover::new("acme.com", SigningKey::generate(), 30 * day, 2 * day)
+///     .with_clock(clock.clone());
+/// assert!(rollover.tick().is_empty());
+///
+/// clock.advance(28 * day);
+/// assert!(matches!(rollover.tick()[..], [RolloverEvent::NextKeyPublished { .. }]));
+/// assert_eq!(rollover.published_keys().len(), 2);
+///
+/// clock.advance(2 * day);
+/// assert!(matches!(rollover.tick()[..], [RolloverEvent::KeyActivated { .. }]));
+///
+/// clock.advance(2 * day);
+/// assert!(matches!(rollover.tick()[..], [RolloverEvent::KeyRetired { .. }]));
+/// assert_eq!(rollover.published_keys().len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeyRollover {
+    trust_root: String,
+    active: SigningKey,
+    activated_at: DateTime<Utc>,
+    next: Option<SigningKey>,
+    /// The previous key, when it was activated, and when it is retired
+    retiring: Option<(VerifyingKey, DateTime<Utc>, DateTime<Utc>)>,
+    interval: chrono::Duration,
+    overlap: chrono::Duration,
+    clock: SharedClock,
+}
+
+impl KeyRollover {
+    /// Starts a rotation schedule with `active` as the current signing key,
+    /// activated now.
+    ///
+    /// # Arguments
+    ///
+    /// * `trust_root` - The trust root the keys sign for
+    /// * `active` - The key to sign with until the first rotation
+    /// * `interval` - How long each key signs before it is replaced
+    /// * `overlap` - How long the next key is published before it signs,
+    ///   and the old key after it stops
+    #[must_use]
+    pub fn new(
+        trust_root: impl Into<String>,
+        active: SigningKey,
+        interval: Duration,
+        overlap: Duration,
+    ) -> Self {
+        let interval = to_chrono(interval);
+        let clock = SharedClock::default();
+        Self {
+            trust_root: trust_root.into(),
+            active,
+            activated_at: clock.now(),
+            next: None,
+            retiring: None,
+            interval,
+            overlap: to_chrono(overlap).min(interval),
+            clock,
+        }
+    }
+
+    /// Replaces the time source and restarts the current key's period at
+    /// the clock's time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = SharedClock(Arc::new(clock));
+        self.activated_at = self.clock.now();
+        self
+    }
+
+    /// Advances the schedule to the current time and returns what changed.
+    pub fn tick(&mut self) -> Vec<RolloverEvent> {
+        let now = self.clock.now();
+        let mut events = Vec::new();
+        let rotates_at = after(self.activated_at, self.interval);
+
+        if self.retiring.as_ref().is_some_and(|(_, _, until)| now >= *until) {
+            events.extend(self.retire());
+        }
+        if self.next.is_none() && now >= rotates_at - self.overlap {
+            let next = SigningKey::generate();
+            events.push(RolloverEvent::NextKeyPublished {
+                kid: next.verifying_key().fingerprint(),
+            });
+            self.next = Some(next);
+        }
+        if now >= rotates_at
+            && let Some(next) = self.next.take()
+        {
+            events.extend(self.retire());
+            let previous = std::mem::replace(&mut self.active, next);
+            self.retiring = Some((
+                previous.verifying_key(),
+                self.activated_at,
+                after(now, self.overlap),
+            ));
+            self.activated_at = now;
+            events.push(RolloverEvent::KeyActivated {
+                kid: self.active.verifying_key().fingerprint(),
+            });
+        }
+        events
+    }
+
+    /// Drops the retiring key, if any, and reports it.
+    fn retire(&mut self) -> Option<RolloverEvent> {
+        self.retiring.take().map(|(key, _, _)| RolloverEvent::KeyRetired {
+            kid: key.fingerprint(),
+        })
+    }
+
+    /// Returns the key currently used for signing.
+    #[must_use]
+    pub fn active_key(&self) -> &SigningKey {
+        &self.active
+    }
+
+    /// Returns an issuer that signs with the active key.
+    ///
+    /// The issuer holds a copy of the key, so obtain a fresh one after a
+    /// [`KeyActivated`](RolloverEvent::KeyActivated) event.
+    #[must_use]
+    pub fn issuer(&self, default_ttl: Duration) -> Issuer {
+        Issuer::new(&self.trust_root, self.active.clone(), default_ttl)
+            .with_clock(self.clock.clone())
+    }
+
+    /// Returns every key verifiers should currently trust: the retiring
+    /// key, the active key and the next key, as far as they exist.
+    #[must_use]
+    pub fn published_keys(&self) -> Vec<VerifyingKey> {
+        self.retiring
+            .iter()
+            .map(|(key, _, _)| key.clone())
+            .chain(std::iter::once(self.active.verifying_key()))
+            .chain(self.next.iter().map(SigningKey::verifying_key))
+            .collect()
+    }
+
+    /// Returns the published keys as an [`AgentKeySet`], the
+    /// `.well-known/agent-keys.json` document of the specification.
+    ///
+    /// The retiring and active keys are listed under `keys`, the next key
+    /// as `next_key`. Each key's window runs from its scheduled activation
+    /// to the end of its overlap after retirement; `kid` is the key
+    /// fingerprint.
+    #[must_use]
+    pub fn key_set(&self) -> AgentKeySet {
+        let lifetime = self
+            .interval
+            .checked_add(&self.overlap)
+            .unwrap_or(chrono::Duration::MAX);
+        let mut key_set = AgentKeySet::new(&self.trust_root);
+        if let Some((key, activated, retires)) = &self.retiring {
+            key_set.add_key(PublishedKey::new(key.clone(), *activated, *retires));
+        }
+        key_set.add_key(PublishedKey::new(
+            self.active.verifying_key(),
+            self.activated_at,
+            after(self.activated_at, lifetime),
+        ));
+        if let Some(next) = &self.next {
+            let activates = after(self.activated_at, self.interval);
+            key_set.set_next_key(PublishedKey::new(
+                next.verifying_key(),
+                activates,
+                after(activates, lifetime),
+            ));
+        }
+        key_set
+    }
+}
+
+/// Converts `duration`, saturating at the largest representable span.
+fn to_chrono(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX)
+}
+
+/// Returns `time + span`, saturating at the latest representable time.
+fn after(time: DateTime<Utc>, span: chrono::Duration) -> DateTime<Utc> {
+    time.checked_add_signed(span).unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::verifier::Verifier;
+    use agent_uri::AgentUri;
+
+    const HOUR: Duration = Duration::from_hours(1);
+
+    fn rollover(clock: &ManualClock) -> KeyRollover {
+        KeyRollover::new("acme.com", SigningKey::generate(), 10 * HOUR, 2 * HOUR)
+            .with_clock(clock.clone())
+    }
+
+    fn trusting(rollover: &KeyRollover, clock: &ManualClock) -> Verifier {
+        let mut verifier = Verifier::new().with_clock(clock.clone());
+        for key in rollover.published_keys() {
+            verifier.add_trusted_key("acme.com", key);
+        }
+        verifier
+    }
+
+    #[test]
+    fn tokens_from_the_old_key_verify_during_the_overlap() {
+        let clock = ManualClock::new(Utc::now());
+        let mut rollover = rollover(&clock);
+        let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+        let old_token = rollover.issuer(12 * HOUR).issue(&uri, vec![]).unwrap();
+
+        clock.advance(10 * HOUR);
+        let events = rollover.tick();
+        assert_eq!(events.len(), 2, "publishes and activates in one late tick");
+
+        let new_token = rollover.issuer(12 * HOUR).issue(&uri, vec![]).unwrap();
+        let verifier = trusting(&rollover, &clock);
+        assert_eq!(verifier.trusted_keys("acme.com").len(), 2);
+        assert!(verifier.verify(&old_token).is_ok());
+        assert!(verifier.verify(&new_token).is_ok());
+
+        clock.advance(HOUR);
+        assert!(rollover.tick().is_empty());
+        clock.advance(HOUR);
+        assert!(matches!(rollover.tick()[..], [RolloverEvent::KeyRetired { .. }]));
+        let verifier = trusting(&rollover, &clock);
+        assert!(verifier.verify(&old_token).is_err());
+        assert!(verifier.verify(&new_token).is_ok());
+    }
+
+    #[test]
+    fn late_tick_retires_before_activating() {
+        let clock = ManualClock::new(Utc::now());
+        let mut rollover = rollover(&clock);
+        clock.advance(10 * HOUR);
+        rollover.tick();
+
+        // A late tick catches up on every step that fell due
+        clock.advance(20 * HOUR);
+        let events = rollover.tick();
+        assert!(matches!(
+            events[..],
+            [
+                RolloverEvent::KeyRetired { .. },
+                RolloverEvent::NextKeyPublished { .. },
+                RolloverEvent::KeyActivated { .. },
+            ]
+        ));
+        assert_eq!(rollover.published_keys().len(), 2);
+    }
+
+    #[test]
+    fn key_set_lists_published_keys() {
+        let clock = ManualClock::new(Utc::now());
+        let mut rollover = rollover(&clock);
+        clock.advance(8 * HOUR);
+        rollover.tick();
+
+        let key_set = rollover.key_set();
+        assert_eq!(key_set.trust_root(), "acme.com");
+        assert_eq!(key_set.keys().len(), 1);
+        assert_eq!(
+            key_set.keys()[0].kid,
+            rollover.active_key().verifying_key().fingerprint()
+        );
+        let next = key_set.next_key().unwrap();
+        assert_eq!(next.not_before, clock.now() + chrono::Duration::hours(2));
+
+        // Verifiers pick up the next key once it activates
+        let mut verifier = Verifier::new().with_clock(clock.clone());
+        verifier.trust_key_set(&key_set);
+        assert_eq!(verifier.trusted_keys("acme.com").len(), 1);
+        clock.advance(2 * HOUR);
+        verifier.trust_key_set(&key_set);
+        assert_eq!(verifier.trusted_keys("acme.com").len(), 2);
+        assert!(verifier.trusted_keys("acme.com").contains(&next.key));
+    }
+}
@@ -0,0 +1,251 @@
+//! Status-list based revocation.
+//!
+//! High-volume issuers cannot afford to distribute a full revocation list
+//! for every token. Instead, each attestation can carry a `status_idx`
+//! claim pointing at a bit in a [`StatusList`]. The issuer publishes the
+//! list as a short-lived, signed status list token via
+//! [`Issuer::issue_status_list`] and refreshes it frequently; a set bit
+//! means the token at that index is revoked.
+//!
+//! Verifiers obtain current status list tokens through a
+//! [`StatusListSource`] registered with
+//! [`Verifier::with_status_list_source`]. Tokens without a `status_idx`
+//! claim are not subject to status checks.
+//!
+//! [`Issuer::issue_status_list`]: crate::Issuer::issue_status_list
+//! [`Verifier::with_status_list_source`]: crate::Verifier::with_status_list_source
+
+use std::fmt;
+use std::sync::Arc;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AttestationError;
+
+/// A bitfield of revocation flags indexed by `status_idx`.
+///
+/// Bit `i` is stored in byte `i / 8` at position `i % 8`, least
+/// significant bit first.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri_attestation::StatusList;
+///
+/// let mut list = StatusList::new(1024);
+/// list.revoke(42).unwrap();
+///
+/// assert_eq!(list.is_revoked(42), Some(true));
+/// assert_eq!(list.is_revoked(43), Some(false));
+/// assert_eq!(list.is_revoked(4096), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusList {
+    size: u64,
+    bits: Vec<u8>,
+}
+
+impl StatusList {
+    /// Creates a list of `size` entries, none of them revoked.
+    #[must_use]
+    pub fn new(size: u64) -> Self {
+        let bytes = usize::try_from(size.div_ceil(8)).unwrap_or(usize::MAX);
+        Self {
+            size,
+            bits: vec![0; bytes],
+        }
+    }
+
+    /// Returns the number of entries.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns true if the list has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Marks the token at `index` as revoked.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidClaims` if `index` is out of range.
+    pub fn revoke(&mut self, index: u64) -> Result<(), AttestationError> {
+        self.set(index, true)
+    }
+
+    /// Clears the revocation flag of the token at `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidClaims` if `index` is out of range.
+    pub fn reinstate(&mut self, index: u64) -> Result<(), AttestationError> {
+        self.set(index, false)
+    }
+
+    /// Returns whether the token at `index` is revoked, or `None` if the
+    /// index is out of range.
+    #[must_use]
+    pub fn is_revoked(&self, index: u64) -> Option<bool> {
+        let (byte, mask) = self.locate(index)?;
+        Some(self.bits[byte] & mask != 0)
+    }
+
+    fn set(&mut self, index: u64, revoked: bool) -> Result<(), AttestationError> {
+        let (byte, mask) = self
+            .locate(index)
+            .ok_or_else(|| AttestationError::InvalidClaims {
+                reason: format!("status index {index} is beyond the list size {}", self.size),
+            })?;
+        if revoked {
+            self.bits[byte] |= mask;
+        } else {
+            self.bits[byte] &= !mask;
+        }
+        Ok(())
+    }
+
+    /// Returns the byte offset and bit mask for `index`.
+    fn locate(&self, index: u64) -> Option<(usize, u8)> {
+        if index >= self.size {
+            return None;
+        }
+        let byte = usize::try_from(index / 8).ok()?;
+        Some((byte, 1 << (index % 8)))
+    }
+
+    /// Encodes the list as the `status_list` claim.
+    pub(crate) fn to_claim(&self) -> StatusListClaim {
+        StatusListClaim {
+            size: self.size,
+            lst: URL_SAFE_NO_PAD.encode(&self.bits),
+        }
+    }
+
+    /// Decodes the `status_list` claim.
+    pub(crate) fn from_claim(claim: &StatusListClaim) -> Result<Self, AttestationError> {
+        let bits = URL_SAFE_NO_PAD
+            .decode(&claim.lst)
+            .map_err(|e| AttestationError::InvalidClaims {
+                reason: format!("invalid status list encoding: {e}"),
+            })?;
+        if (bits.len() as u64) < claim.size.div_ceil(8) {
+            return Err(AttestationError::InvalidClaims {
+                reason: format!("status list is too short for {} entries", claim.size),
+            });
+        }
+        Ok(Self {
+            size: claim.size,
+            bits,
+        })
+    }
+}
+
+/// Wire form of a status list inside a status list token.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct StatusListClaim {
+    /// Number of entries
+    pub(crate) size: u64,
+    /// Base64url-encoded bitfield
+    pub(crate) lst: String,
+}
+
+/// Supplies the current status list token of an issuer.
+///
+/// Implementations typically fetch tokens over the network and cache them
+/// until shortly before they expire. Returning `None` makes verification of
+/// tokens carrying a `status_idx` fail closed.
+///
+/// Any `Fn(&str) -> Option<String>` closure is a source.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri_attestation::StatusListSource;
+///
+/// let source = |issuer: &str| (issuer == "acme.com").then(|| "v4.public...".to_string());
+/// assert!(source.status_list("other.com").is_none());
+/// ```
+pub trait StatusListSource: Send + Sync {
+    /// Returns the current status list token published by `issuer`.
+    fn status_list(&self, issuer: &str) -> Option<String>;
+}
+
+impl<F> StatusListSource for F
+where
+    F: Fn(&str) -> Option<String> + Send + Sync,
+{
+    fn status_list(&self, issuer: &str) -> Option<String> {
+        self(issuer)
+    }
+}
+
+/// A shareable handle to a status list source held by the verifier.
+#[derive(Clone)]
+pub(crate) struct SharedStatusSource(pub(crate) Arc<dyn StatusListSource>);
+
+impl fmt::Debug for SharedStatusSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StatusListSource")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revoke_and_reinstate() {
+        let mut list = StatusList::new(16);
+
+        list.revoke(9).unwrap();
+        assert_eq!(list.is_revoked(9), Some(true));
+        assert_eq!(list.is_revoked(8), Some(false));
+
+        list.reinstate(9).unwrap();
+        assert_eq!(list.is_revoked(9), Some(false));
+    }
+
+    #[test]
+    fn out_of_range_index_is_rejected() {
+        let mut list = StatusList::new(10);
+
+        assert!(list.revoke(10).is_err());
+        assert_eq!(list.is_revoked(10), None);
+    }
+
+    #[test]
+    fn uses_lsb_first_bit_order() {
+        let mut list = StatusList::new(16);
+        list.revoke(0).unwrap();
+        list.revoke(9).unwrap();
+
+        assert_eq!(list.bits, vec![0b0000_0001, 0b0000_0010]);
+    }
+
+    #[test]
+    fn claim_roundtrip() {
+        let mut list = StatusList::new(100);
+        list.revoke(3).unwrap();
+        list.revoke(99).unwrap();
+
+        let decoded = StatusList::from_claim(&list.to_claim()).unwrap();
+
+        assert_eq!(decoded, list);
+    }
+
+    #[test]
+    fn truncated_claim_is_rejected() {
+        let claim = StatusListClaim {
+            size: 64,
+            lst: URL_SAFE_NO_PAD.encode([0u8; 2]),
+        };
+
+        assert!(StatusList::from_claim(&claim).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+//! Structured tracing of issuance and verification steps.
+//!
+//! With the `tracing` feature, each [`Step`] emits one event when it
+//! finishes, carrying the step name, outcome, latency and any error.
+//! Successful steps are logged at `DEBUG` and failures at `WARN`, so
+//! operators can see why tokens are rejected without enabling debug output.
+//! Without the feature, steps compile to nothing.
+
+#[cfg(feature = "tracing")]
+use web_time::Instant;
+
+use crate::error::AttestationError;
+
+/// A timed issuance or verification step.
+pub(crate) struct Step {
+    #[cfg(feature = "tracing")]
+    name: &'static str,
+    #[cfg(feature = "tracing")]
+    start: Instant,
+}
+
+impl Step {
+    /// Starts timing the step called `name`.
+    pub(crate) fn start(name: &'static str) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = name;
+        Self {
+            #[cfg(feature = "tracing")]
+            name,
+            #[cfg(feature = "tracing")]
+            start: Instant::now(),
+        }
+    }
+
+    /// Records the step's outcome and passes `result` through.
+    #[cfg_attr(not(feature = "tracing"), allow(clippy::unused_self))]
+    pub(crate) fn finish<T>(
+        self,
+        result: Result<T, AttestationError>,
+    ) -> Result<T, AttestationError> {
+        #[cfg(feature = "tracing")]
+        {
+            let latency_us = u64::try_from(self.start.elapsed().as_micros()).unwrap_or(u64::MAX);
+            match &result {
+                Ok(_) => tracing::debug!(step = self.name, outcome = "ok", latency_us),
+                Err(error) => tracing::warn!(
+                    step = self.name,
+                    outcome = "failed",
+                    latency_us,
+                    error = %error,
+                ),
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finish_passes_result_through() {
+        assert_eq!(Step::start("ok").finish(Ok(7)), Ok(7));
+        assert_eq!(
+            Step::start("failed").finish::<()>(Err(AttestationError::InvalidSignature)),
+            Err(AttestationError::InvalidSignature)
+        );
+    }
+}
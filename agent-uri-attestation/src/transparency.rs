@@ -0,0 +1,401 @@
+//! Append-only transparency log of issued attestations.
+//!
+//! An [`AttestationLog`] records the SHA-256 digest of every token a trust
+//! root issues in a Merkle tree (RFC 9162 hashing), and periodically
+//! publishes a [`SignedTreeHead`] committing to all entries so far. For any
+//! entry it can produce an [`InclusionProof`] showing that the token is part
+//! of the published tree.
+//!
+//! Auditors who track tree heads get evidence of every attestation the
+//! trust root has issued, so mis-issuance cannot stay hidden. Relying parties
+//! can demand that evidence with [`Verifier::require_transparency_log`] and
+//! [`Verifier::verify_logged`].
+//!
+//! [`Verifier::require_transparency_log`]: crate::Verifier::require_transparency_log
+//! [`Verifier::verify_logged`]: crate::Verifier::verify_logged
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, Verifier as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::AttestationError;
+use crate::keys::{SigningKey, VerifyingKey};
+
+/// Domain separator for signed tree head signatures.
+const TREE_HEAD_CONTEXT: &[u8] = b"agent-uri-attestation/tree-head/v1";
+
+/// A SHA-256 hash in the Merkle tree.
+type Hash = [u8; 32];
+
+/// An append-only Merkle log of issued token digests.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_attestation::{AttestationLog, Issuer, SigningKey, Verifier};
+/// use std::time::Duration;
+///
+/// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+/// let mut log = AttestationLog::new(SigningKey::generate());
+///
+/// let uri = AgentUri::parse(
+///     "agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q"
+/// ).unwrap();
+/// let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+/// let index = log.append(&token);
+///
+/// let head = log.tree_head();
+/// let proof = log.inclusion_proof(index).unwrap();
+///
+/// let mut verifier = Verifier::new();
+/// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+/// verifier.require_transparency_log(log.verifying_key());
+///
+/// assert!(verifier.verify(&token).is_err());
+/// assert!(verifier.verify_logged(&token, &proof, &head).is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct AttestationLog {
+    signing_key: SigningKey,
+    leaves: Vec<Hash>,
+}
+
+impl AttestationLog {
+    /// Creates an empty log that signs tree heads with `signing_key`.
+    #[must_use]
+    pub fn new(signing_key: SigningKey) -> Self {
+        Self {
+            signing_key,
+            leaves: Vec::new(),
+        }
+    }
+
+    /// Returns the key verifiers use to check tree heads.
+    #[must_use]
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+
+    /// Records a token and returns its leaf index.
+    pub fn append(&mut self, token: &str) -> u64 {
+        self.leaves.push(leaf_hash(token));
+        self.leaves.len() as u64 - 1
+    }
+
+    /// Returns the number of logged tokens.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.leaves.len() as u64
+    }
+
+    /// Returns true if no token has been logged.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Signs and returns the head of the tree in its current state.
+    #[must_use]
+    pub fn tree_head(&self) -> SignedTreeHead {
+        let tree_size = self.len();
+        let root_hash = root(&self.leaves);
+        let timestamp = Utc::now();
+        let signature = self
+            .signing_key
+            .as_dalek()
+            .sign(&tree_head_message(tree_size, &root_hash, timestamp))
+            .to_bytes()
+            .to_vec();
+
+        SignedTreeHead {
+            tree_size,
+            root_hash,
+            timestamp,
+            signature,
+        }
+    }
+
+    /// Returns a proof that the entry at `index` is in the current tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidInclusionProof` if `index` is not a
+    /// logged entry.
+    pub fn inclusion_proof(&self, index: u64) -> Result<InclusionProof, AttestationError> {
+        let leaf = usize::try_from(index)
+            .ok()
+            .filter(|&leaf| leaf < self.leaves.len())
+            .ok_or_else(|| AttestationError::InvalidInclusionProof {
+                reason: format!("index {index} is beyond the log size {}", self.len()),
+            })?;
+
+        Ok(InclusionProof {
+            leaf_index: index,
+            tree_size: self.len(),
+            path: path(leaf, &self.leaves),
+        })
+    }
+}
+
+/// A log's signed commitment to its first `tree_size` entries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedTreeHead {
+    /// Number of entries covered by this head
+    pub tree_size: u64,
+    /// Merkle root hash over those entries
+    pub root_hash: [u8; 32],
+    /// When the head was signed
+    pub timestamp: DateTime<Utc>,
+    /// Ed25519 signature of the log over the fields above
+    pub signature: Vec<u8>,
+}
+
+impl SignedTreeHead {
+    /// Checks the log's signature over this tree head.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidSignature` if the signature does not
+    /// verify under `log_key`.
+    pub fn verify(&self, log_key: &VerifyingKey) -> Result<(), AttestationError> {
+        let signature = Signature::from_slice(&self.signature)
+            .map_err(|_| AttestationError::InvalidSignature)?;
+        log_key
+            .as_dalek()
+            .verify(
+                &tree_head_message(self.tree_size, &self.root_hash, self.timestamp),
+                &signature,
+            )
+            .map_err(|_| AttestationError::InvalidSignature)
+    }
+}
+
+/// Evidence that a token is included in a tree of a given size.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    /// Index of the token's entry in the log
+    pub leaf_index: u64,
+    /// Size of the tree the proof was computed against
+    pub tree_size: u64,
+    /// Sibling hashes from the leaf up to the root
+    pub path: Vec<[u8; 32]>,
+}
+
+impl InclusionProof {
+    /// Checks that `token` is included in the tree described by `head`.
+    ///
+    /// Only the proof is checked here; verify the head's signature with
+    /// [`SignedTreeHead::verify`] before trusting the result.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError::InvalidInclusionProof` if the proof was
+    /// computed for another tree size or does not lead to the head's root.
+    pub fn verify(&self, token: &str, head: &SignedTreeHead) -> Result<(), AttestationError> {
+        if self.tree_size != head.tree_size {
+            return Err(AttestationError::InvalidInclusionProof {
+                reason: format!(
+                    "proof is for tree size {} but the tree head covers {}",
+                    self.tree_size, head.tree_size
+                ),
+            });
+        }
+        if self.root_for(token) != Some(head.root_hash) {
+            return Err(AttestationError::InvalidInclusionProof {
+                reason: "proof does not lead to the tree head's root hash".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Recomputes the root hash from the token and audit path (RFC 9162,
+    /// section 2.1.3.2), or `None` if the path has the wrong shape.
+    fn root_for(&self, token: &str) -> Option<Hash> {
+        if self.leaf_index >= self.tree_size {
+            return None;
+        }
+        let mut fnode = self.leaf_index;
+        let mut snode = self.tree_size - 1;
+        let mut hash = leaf_hash(token);
+
+        for sibling in &self.path {
+            if snode == 0 {
+                return None;
+            }
+            if fnode & 1 == 1 || fnode == snode {
+                hash = node_hash(sibling, &hash);
+                while fnode & 1 == 0 && fnode != 0 {
+                    fnode >>= 1;
+                    snode >>= 1;
+                }
+            } else {
+                hash = node_hash(&hash, sibling);
+            }
+            fnode >>= 1;
+            snode >>= 1;
+        }
+
+        (snode == 0).then_some(hash)
+    }
+}
+
+/// Hashes a token into its leaf: `SHA-256(0x00 || SHA-256(token))`.
+fn leaf_hash(token: &str) -> Hash {
+    let digest = Sha256::digest(token.as_bytes());
+    Sha256::new()
+        .chain_update([0x00])
+        .chain_update(digest)
+        .finalize()
+        .into()
+}
+
+/// Hashes two children into their parent: `SHA-256(0x01 || left || right)`.
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    Sha256::new()
+        .chain_update([0x01])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Returns the largest power of two strictly less than `n` (for `n > 1`).
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+/// Computes the Merkle tree hash over `leaves`.
+fn root(leaves: &[Hash]) -> Hash {
+    match leaves {
+        [] => Sha256::digest([]).into(),
+        [leaf] => *leaf,
+        _ => {
+            let k = split_point(leaves.len());
+            node_hash(&root(&leaves[..k]), &root(&leaves[k..]))
+        }
+    }
+}
+
+/// Computes the audit path for the leaf at `index`.
+fn path(index: usize, leaves: &[Hash]) -> Vec<Hash> {
+    if leaves.len() <= 1 {
+        return Vec::new();
+    }
+    let k = split_point(leaves.len());
+    let (mut path, sibling) = if index < k {
+        (path(index, &leaves[..k]), root(&leaves[k..]))
+    } else {
+        (path(index - k, &leaves[k..]), root(&leaves[..k]))
+    };
+    path.push(sibling);
+    path
+}
+
+/// Builds the byte string a tree head signature covers.
+fn tree_head_message(tree_size: u64, root_hash: &Hash, timestamp: DateTime<Utc>) -> Vec<u8> {
+    let mut message = TREE_HEAD_CONTEXT.to_vec();
+    message.extend_from_slice(&tree_size.to_be_bytes());
+    message.extend_from_slice(&timestamp.timestamp_millis().to_be_bytes());
+    message.extend_from_slice(root_hash);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_with(n: usize) -> (AttestationLog, Vec<String>) {
+        let mut log = AttestationLog::new(SigningKey::generate());
+        let tokens: Vec<String> = (0..n).map(|i| format!("v4.public.token-{i}")).collect();
+        for token in &tokens {
+            log.append(token);
+        }
+        (log, tokens)
+    }
+
+    #[test]
+    fn every_entry_has_a_valid_proof() {
+        for size in 1..=17 {
+            let (log, tokens) = log_with(size);
+            let head = log.tree_head();
+
+            for (index, token) in tokens.iter().enumerate() {
+                let proof = log.inclusion_proof(index as u64).unwrap();
+                assert!(proof.verify(token, &head).is_ok(), "size {size}, index {index}");
+            }
+        }
+    }
+
+    #[test]
+    fn proof_rejects_other_tokens() {
+        let (log, _) = log_with(5);
+        let head = log.tree_head();
+        let proof = log.inclusion_proof(2).unwrap();
+
+        assert!(matches!(
+            proof.verify("v4.public.unlogged", &head),
+            Err(AttestationError::InvalidInclusionProof { .. })
+        ));
+    }
+
+    #[test]
+    fn proof_rejects_mismatched_tree_size() {
+        let (mut log, tokens) = log_with(3);
+        let proof = log.inclusion_proof(0).unwrap();
+        log.append("v4.public.later");
+
+        assert!(proof.verify(&tokens[0], &log.tree_head()).is_err());
+    }
+
+    #[test]
+    fn tampered_path_is_rejected() {
+        let (log, tokens) = log_with(8);
+        let head = log.tree_head();
+        let mut proof = log.inclusion_proof(3).unwrap();
+        proof.path[1][0] ^= 0xff;
+
+        assert!(proof.verify(&tokens[3], &head).is_err());
+    }
+
+    #[test]
+    fn out_of_range_index_is_rejected() {
+        let (log, _) = log_with(2);
+
+        assert!(log.inclusion_proof(2).is_err());
+    }
+
+    #[test]
+    fn tree_head_signature_verifies() {
+        let (log, _) = log_with(4);
+        let head = log.tree_head();
+
+        assert!(head.verify(&log.verifying_key()).is_ok());
+        assert_eq!(
+            head.verify(&SigningKey::generate().verifying_key()),
+            Err(AttestationError::InvalidSignature)
+        );
+
+        let mut forged = head.clone();
+        forged.tree_size += 1;
+        assert!(forged.verify(&log.verifying_key()).is_err());
+    }
+
+    #[test]
+    fn matches_rfc_9162_structure_for_three_leaves() {
+        let (log, tokens) = log_with(3);
+        let leaves: Vec<Hash> = tokens.iter().map(|t| leaf_hash(t)).collect();
+        let expected = node_hash(&node_hash(&leaves[0], &leaves[1]), &leaves[2]);
+
+        assert_eq!(log.tree_head().root_hash, expected);
+        assert_eq!(
+            log.inclusion_proof(2).unwrap().path,
+            vec![node_hash(&leaves[0], &leaves[1])]
+        );
+    }
+}
@@ -18,15 +18,30 @@
 //!
 //! | Function | Property Verified |
 //! |----------|-------------------|
-//! | [`validate_issuer`] | Token issuer equals URI trust root (exact match) |
+//! | [`validate_issuer`] | Token issuer equals the trust root or falls under its `*.` wildcard |
 //! | [`validate_subject`] | Token subject equals presented URI (exact match) |
 //! | [`check_expiration`] | Current time is strictly less than expiration |
 //! | [`capability_covers`] | Attested capability is prefix of or equals required |
+//! | [`check_capability_coverage`] | Required path is covered and not denied |
+//! | [`check_capability_coverage_with`] | As above, under a [`CapabilityNotation`] |
+//! | [`check_constrained_coverage`] | A covering capability's constraints permit the request |
+//! | [`check_not_before`] | Current time is not earlier than `nbf` |
+//! | [`check_max_ttl`] | Token lifetime (`exp - iat`) does not exceed a maximum |
+//! | [`check_freshness`] | Token age (`now - iat`) does not exceed a maximum |
+//! | [`validate_audience`] | Token audience equals the expected audience |
+//! | [`trust_root_matches`] | Trust root equals a pattern or falls under a `*.` wildcard |
+//! | [`check_delegation`] | Delegated token is bound to its parent and never widens it |
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 
 use agent_uri::CapabilityPath;
 
+use crate::claims::AttestationClaims;
+use crate::constraints::{CapabilityConstraints, CapabilityRequest};
 use crate::error::AttestationError;
 
 /// Pure function: checks if any attested capability covers the required path.
@@ -62,20 +77,241 @@ use crate::error::AttestationError;
 /// ```
 #[must_use]
 pub fn capability_covers(attested_capabilities: &[String], required: &CapabilityPath) -> bool {
-    attested_capabilities.iter().any(|cap| {
-        let required_str = required.as_str();
-        // Capability covers required if:
-        // 1. They are exactly equal, OR
-        // 2. Capability is a proper prefix (required starts with cap + "/")
-        required_str == cap || required_str.starts_with(&format!("{cap}/"))
-    })
+    attested_capabilities
+        .iter()
+        .any(|cap| covers(cap, required.as_str()))
+}
+
+/// Returns true if capability `cap` covers the capability string `required`.
+pub(crate) use agent_uri_verify_core::covers;
+
+/// How attested capability strings written with `.` separators relate to
+/// `/`-separated capability paths.
+///
+/// Capability paths always use `/`, but tokens may grant capabilities in
+/// dotted form such as `workflow.approval.read`. Under the default
+/// [`Exact`](Self::Exact) notation such a grant covers no path, which is
+/// easy to miss.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::CapabilityPath;
+/// use agent_uri_attestation::{capability_covers_with, CapabilityNotation};
+///
+/// let attested = vec!["workflow.approval".to_string()];
+/// let required = CapabilityPath::parse("workflow/approval/read").unwrap();
+///
+/// assert!(!capability_covers_with(&attested, &required, CapabilityNotation::Exact));
+/// assert!(capability_covers_with(&attested, &required, CapabilityNotation::Unified));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CapabilityNotation {
+    /// Capabilities are compared as written; `.` is an ordinary character.
+    #[default]
+    Exact,
+    /// `.` and `/` are equivalent separators in attested and denied
+    /// capabilities.
+    Unified,
+    /// Capabilities containing `.` are rejected with
+    /// `AttestationError::MixedCapabilityNotation` instead of silently
+    /// covering nothing.
+    RejectDotted,
+}
+
+impl CapabilityNotation {
+    /// Rewrites `capabilities` into `/`-separated form under this notation.
+    fn normalize(self, capabilities: &[String]) -> Result<Cow<'_, [String]>, AttestationError> {
+        match self {
+            Self::Exact => Ok(Cow::Borrowed(capabilities)),
+            Self::Unified => Ok(Cow::Owned(
+                capabilities.iter().map(|cap| cap.replace('.', "/")).collect(),
+            )),
+            Self::RejectDotted => match capabilities.iter().find(|cap| cap.contains('.')) {
+                Some(cap) => Err(AttestationError::MixedCapabilityNotation {
+                    capability: cap.clone(),
+                }),
+                None => Ok(Cow::Borrowed(capabilities)),
+            },
+        }
+    }
+
+    /// Rewrites the granted, denied and constrained capabilities of
+    /// `claims` into `/`-separated form under this notation.
+    pub(crate) fn normalize_claims(
+        self,
+        claims: &AttestationClaims,
+    ) -> Result<Cow<'_, AttestationClaims>, AttestationError> {
+        let (Cow::Owned(capabilities), Cow::Owned(denied_capabilities)) = (
+            self.normalize(&claims.capabilities)?,
+            self.normalize(&claims.denied_capabilities)?,
+        ) else {
+            return Ok(Cow::Borrowed(claims));
+        };
+        Ok(Cow::Owned(AttestationClaims {
+            capabilities,
+            denied_capabilities,
+            capability_constraints: claims
+                .capability_constraints
+                .iter()
+                .map(|(cap, constraints)| (cap.replace('.', "/"), constraints.clone()))
+                .collect(),
+            ..claims.clone()
+        }))
+    }
+}
+
+/// A verifier-side table mapping legacy capability strings to canonical
+/// capability paths.
+///
+/// Organizations migrating to a new capability taxonomy can register each
+/// legacy name, such as `email.send`, with its canonical path, such as
+/// `communication/email/send`. Wherever a token grants or denies a legacy
+/// name, coverage checks also treat it as granting or denying the
+/// canonical path, so tokens in either form validate during the
+/// transition. Constraints on a legacy name apply to its canonical path
+/// unless that path carries its own.
+///
+/// Aliases match whole capability strings; `email.send.bulk` is not
+/// rewritten by an alias for `email.send`. They are resolved before any
+/// [`CapabilityNotation`] is applied.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::CapabilityPath;
+/// use agent_uri_attestation::{capability_covers, CapabilityAliases};
+///
+/// let aliases = CapabilityAliases::new().with_alias(
+///     "email.send",
+///     CapabilityPath::parse("communication/email/send").unwrap(),
+/// );
+/// let attested = vec!["email.send".to_string()];
+/// let required = CapabilityPath::parse("communication/email/send").unwrap();
+///
+/// assert!(!capability_covers(&attested, &required));
+/// assert!(capability_covers(&aliases.expand(&attested), &required));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapabilityAliases {
+    aliases: BTreeMap<String, CapabilityPath>,
+}
+
+impl CapabilityAliases {
+    /// Creates an empty alias table.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an alias from `legacy` to `canonical`, returning the table.
+    #[must_use]
+    pub fn with_alias(mut self, legacy: impl Into<String>, canonical: CapabilityPath) -> Self {
+        self.insert(legacy, canonical);
+        self
+    }
+
+    /// Maps `legacy` to `canonical`, returning the canonical path it
+    /// previously mapped to, if any.
+    pub fn insert(
+        &mut self,
+        legacy: impl Into<String>,
+        canonical: CapabilityPath,
+    ) -> Option<CapabilityPath> {
+        self.aliases.insert(legacy.into(), canonical)
+    }
+
+    /// Returns the canonical path registered for `legacy`, if any.
+    #[must_use]
+    pub fn resolve(&self, legacy: &str) -> Option<&CapabilityPath> {
+        self.aliases.get(legacy)
+    }
+
+    /// Returns the number of aliases.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    /// Returns true if no aliases are registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// Returns `capabilities` followed by the canonical path of every
+    /// aliased entry that is not already listed.
+    #[must_use]
+    pub fn expand<'a>(&self, capabilities: &'a [String]) -> Cow<'a, [String]> {
+        let mut expanded = Cow::Borrowed(capabilities);
+        for cap in capabilities {
+            if let Some(canonical) = self.resolve(cap)
+                && !expanded.iter().any(|c| c == canonical.as_str())
+            {
+                expanded.to_mut().push(canonical.to_string());
+            }
+        }
+        expanded
+    }
+
+    /// Extends the granted, denied and constrained capabilities of `claims`
+    /// with the canonical paths of their aliased entries.
+    pub(crate) fn expand_claims<'a>(
+        &self,
+        claims: &'a AttestationClaims,
+    ) -> Cow<'a, AttestationClaims> {
+        let (capabilities, denied_capabilities) = (
+            self.expand(&claims.capabilities),
+            self.expand(&claims.denied_capabilities),
+        );
+        if matches!(
+            (&capabilities, &denied_capabilities),
+            (Cow::Borrowed(_), Cow::Borrowed(_))
+        ) {
+            return Cow::Borrowed(claims);
+        }
+        let mut capability_constraints = claims.capability_constraints.clone();
+        for (cap, constraints) in &claims.capability_constraints {
+            if let Some(canonical) = self.resolve(cap) {
+                capability_constraints
+                    .entry(canonical.to_string())
+                    .or_insert_with(|| constraints.clone());
+            }
+        }
+        Cow::Owned(AttestationClaims {
+            capabilities: capabilities.into_owned(),
+            denied_capabilities: denied_capabilities.into_owned(),
+            capability_constraints,
+            ..claims.clone()
+        })
+    }
+}
+
+/// Pure function: [`capability_covers`] under a [`CapabilityNotation`].
+///
+/// With [`CapabilityNotation::RejectDotted`], any dotted capability makes
+/// the result `false`; use [`check_capability_coverage_with`] to learn why.
+#[must_use]
+pub fn capability_covers_with(
+    attested_capabilities: &[String],
+    required: &CapabilityPath,
+    notation: CapabilityNotation,
+) -> bool {
+    notation
+        .normalize(attested_capabilities)
+        .is_ok_and(|caps| capability_covers(&caps, required))
 }
 
 /// Pure function: validates that the token issuer matches the URI trust root.
 ///
+/// The trust root may be a `*.` wildcard pattern, matched on label
+/// boundaries as in [`trust_root_matches`]. Plain trust roots require an
+/// exact match.
+///
 /// # Arguments
 ///
-/// * `uri_trust_root` - The trust root extracted from the agent URI
+/// * `uri_trust_root` - The trust root extracted from the agent URI, or a
+///   trusted root pattern such as `*.acme.com`
 /// * `token_issuer` - The issuer claim from the token
 ///
 /// # Returns
@@ -84,7 +320,8 @@ pub fn capability_covers(attested_capabilities: &[String], required: &Capability
 ///
 /// # Errors
 ///
-/// Returns `AttestationError::TrustRootMismatch` if `uri_trust_root` does not equal `token_issuer`.
+/// Returns `AttestationError::TrustRootMismatch` if `token_issuer` does not
+/// match `uri_trust_root`.
 ///
 /// # Examples
 ///
@@ -93,12 +330,14 @@ pub fn capability_covers(attested_capabilities: &[String], required: &Capability
 ///
 /// assert!(validate_issuer("acme.com", "acme.com").is_ok());
 /// assert!(validate_issuer("acme.com", "evil.com").is_err());
+/// assert!(validate_issuer("*.acme.com", "eu.acme.com").is_ok());
+/// assert!(validate_issuer("*.acme.com", "evilacme.com").is_err());
 /// ```
 pub fn validate_issuer(
     uri_trust_root: &str,
     token_issuer: &str,
 ) -> Result<(), AttestationError> {
-    if uri_trust_root == token_issuer {
+    if trust_root_matches(uri_trust_root, token_issuer) {
         Ok(())
     } else {
         Err(AttestationError::TrustRootMismatch {
@@ -188,20 +427,24 @@ pub fn check_expiration(exp: DateTime<Utc>, now: DateTime<Utc>) -> Result<(), At
 
 /// Pure function: checks capability coverage and returns a structured error if insufficient.
 ///
+/// Denied capabilities are evaluated after positive matches: a required path
+/// that is covered by an attested capability is still rejected if it overlaps
+/// a denied capability, i.e. lies under it or contains it.
+///
 /// # Arguments
 ///
 /// * `attested_capabilities` - The capabilities from the token
+/// * `denied_capabilities` - The denied capabilities from the token
 /// * `required` - The required capability path
 ///
 /// # Returns
 ///
-/// `Ok(())` if capabilities are sufficient, or
-/// `Err(AttestationError::InsufficientCapabilities)` if not
+/// `Ok(())` if capabilities are sufficient, or an error if not
 ///
 /// # Errors
 ///
 /// Returns `AttestationError::InsufficientCapabilities` if no attested capability covers
-/// the required path.
+/// the required path, or `AttestationError::CapabilityDenied` if it is covered but denied.
 ///
 /// # Examples
 ///
@@ -210,26 +453,448 @@ pub fn check_expiration(exp: DateTime<Utc>, now: DateTime<Utc>) -> Result<(), At
 /// use agent_uri_attestation::check_capability_coverage;
 ///
 /// let attested = vec!["workflow".to_string()];
+/// let denied = vec!["workflow/payments".to_string()];
+///
 /// let required = CapabilityPath::parse("workflow/approval").unwrap();
-/// assert!(check_capability_coverage(&attested, &required).is_ok());
+/// assert!(check_capability_coverage(&attested, &denied, &required).is_ok());
+///
+/// let payments = CapabilityPath::parse("workflow/payments/refund").unwrap();
+/// assert!(check_capability_coverage(&attested, &denied, &payments).is_err());
 ///
 /// let unrelated = CapabilityPath::parse("assistant/chat").unwrap();
-/// assert!(check_capability_coverage(&attested, &unrelated).is_err());
+/// assert!(check_capability_coverage(&attested, &denied, &unrelated).is_err());
 /// ```
 pub fn check_capability_coverage(
     attested_capabilities: &[String],
+    denied_capabilities: &[String],
     required: &CapabilityPath,
 ) -> Result<(), AttestationError> {
-    if capability_covers(attested_capabilities, required) {
-        Ok(())
-    } else {
-        Err(AttestationError::InsufficientCapabilities {
+    if !capability_covers(attested_capabilities, required) {
+        return Err(AttestationError::InsufficientCapabilities {
             required: required.to_string(),
             attested: attested_capabilities.to_vec(),
+        });
+    }
+    check_not_denied(denied_capabilities, required.as_str())
+}
+
+/// Pure function: [`check_capability_coverage`] under a
+/// [`CapabilityNotation`].
+///
+/// # Errors
+///
+/// Returns `AttestationError::MixedCapabilityNotation` if `notation` is
+/// [`CapabilityNotation::RejectDotted`] and an attested or denied capability
+/// contains `.`, otherwise the errors of [`check_capability_coverage`].
+///
+/// # Examples
+///
+/// ```
+/// use agent_uri::CapabilityPath;
+/// use agent_uri_attestation::{check_capability_coverage_with, AttestationError, CapabilityNotation};
+///
+/// let attested = vec!["workflow.approval.read".to_string()];
+/// let required = CapabilityPath::parse("workflow/approval/read").unwrap();
+///
+/// assert!(check_capability_coverage_with(&attested, &[], &required, CapabilityNotation::Unified).is_ok());
+/// assert!(matches!(
+///     check_capability_coverage_with(&attested, &[], &required, CapabilityNotation::RejectDotted),
+///     Err(AttestationError::MixedCapabilityNotation { .. })
+/// ));
+/// ```
+pub fn check_capability_coverage_with(
+    attested_capabilities: &[String],
+    denied_capabilities: &[String],
+    required: &CapabilityPath,
+    notation: CapabilityNotation,
+) -> Result<(), AttestationError> {
+    check_capability_coverage(
+        &notation.normalize(attested_capabilities)?,
+        &notation.normalize(denied_capabilities)?,
+        required,
+    )
+}
+
+/// Rejects a required path that overlaps any denied capability.
+fn check_not_denied(
+    denied_capabilities: &[String],
+    required: &str,
+) -> Result<(), AttestationError> {
+    match denied_capabilities
+        .iter()
+        .find(|denied| covers(denied, required) || covers(required, denied))
+    {
+        Some(denied) => Err(AttestationError::CapabilityDenied {
+            required: required.to_string(),
+            denied: denied.clone(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Pure function: checks capability coverage, honouring per-capability constraints.
+///
+/// The request is permitted if any attested capability covers its path and
+/// either carries no constraints or has constraints that permit it, and the
+/// path does not overlap a denied capability. This is the constraint-aware
+/// counterpart of [`check_capability_coverage`].
+///
+/// # Arguments
+///
+/// * `attested_capabilities` - The capabilities from the token
+/// * `denied_capabilities` - The denied capabilities from the token
+/// * `constraints` - Constraints from the token, keyed by capability
+/// * `request` - The requested capability and its context
+///
+/// # Errors
+///
+/// Returns `AttestationError::InsufficientCapabilities` if no attested
+/// capability covers the requested path,
+/// `AttestationError::CapabilityConstraintViolated` for the first covering
+/// capability if all covering capabilities deny the request, or
+/// `AttestationError::CapabilityDenied` if the path is explicitly denied.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use agent_uri::CapabilityPath;
+/// use agent_uri_attestation::{check_constrained_coverage, CapabilityConstraints, CapabilityRequest};
+/// use chrono::Utc;
+///
+/// let attested = vec!["workflow/approval".to_string()];
+/// let mut constraints = BTreeMap::new();
+/// constraints.insert(
+///     "workflow/approval".to_string(),
+///     CapabilityConstraints::new().resource("invoices"),
+/// );
+///
+/// let path = CapabilityPath::parse("workflow/approval").unwrap();
+/// let invoice = CapabilityRequest::new(path.clone(), Utc::now()).resource("invoices/17");
+/// let payroll = CapabilityRequest::new(path, Utc::now()).resource("payroll/3");
+///
+/// assert!(check_constrained_coverage(&attested, &[], &constraints, &invoice).is_ok());
+/// assert!(check_constrained_coverage(&attested, &[], &constraints, &payroll).is_err());
+/// ```
+pub fn check_constrained_coverage(
+    attested_capabilities: &[String],
+    denied_capabilities: &[String],
+    constraints: &BTreeMap<String, CapabilityConstraints>,
+    request: &CapabilityRequest,
+) -> Result<(), AttestationError> {
+    let required = request.path().as_str();
+    let mut violation = None;
+
+    for cap in attested_capabilities.iter().filter(|cap| covers(cap, required)) {
+        match constraints.get(cap).map(|c| c.check(cap, request)) {
+            None | Some(Ok(())) => return check_not_denied(denied_capabilities, required),
+            Some(Err(e)) => {
+                violation.get_or_insert(e);
+            }
+        }
+    }
+
+    Err(violation.unwrap_or_else(|| AttestationError::InsufficientCapabilities {
+        required: required.to_string(),
+        attested: attested_capabilities.to_vec(),
+    }))
+}
+
+/// Pure function: checks that a token is already valid at a given time.
+///
+/// # Arguments
+///
+/// * `nbf` - The not-before time from the token
+/// * `now` - The current time to check against
+///
+/// # Returns
+///
+/// `Ok(())` if the token is usable (now >= nbf), or
+/// `Err(AttestationError::TokenNotYetValid)` if it is used too early
+///
+/// # Errors
+///
+/// Returns `AttestationError::TokenNotYetValid` if `now < nbf`.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{Utc, Duration};
+/// use agent_uri_attestation::check_not_before;
+///
+/// let now = Utc::now();
+///
+/// assert!(check_not_before(now - Duration::minutes(5), now).is_ok());
+/// assert!(check_not_before(now, now).is_ok()); // Boundary: now == nbf is valid
+/// assert!(check_not_before(now + Duration::minutes(5), now).is_err());
+/// ```
+pub fn check_not_before(nbf: DateTime<Utc>, now: DateTime<Utc>) -> Result<(), AttestationError> {
+    if now >= nbf {
+        Ok(())
+    } else {
+        Err(AttestationError::TokenNotYetValid {
+            valid_from: nbf.to_rfc3339(),
+        })
+    }
+}
+
+/// Pure function: checks that a token's lifetime does not exceed a maximum.
+///
+/// The lifetime is measured from `iat` to `exp`, so a long-lived token is
+/// rejected even when presented shortly after issuance.
+///
+/// # Arguments
+///
+/// * `iat` - The issued-at time from the token
+/// * `exp` - The expiration time from the token
+/// * `max_ttl` - The longest lifetime the relying party accepts
+///
+/// # Errors
+///
+/// Returns `AttestationError::TtlExceedsMaximum` if `exp - iat > max_ttl`.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{Utc, Duration};
+/// use agent_uri_attestation::check_max_ttl;
+///
+/// let iat = Utc::now();
+/// let max = std::time::Duration::from_secs(3600);
+///
+/// assert!(check_max_ttl(iat, iat + Duration::minutes(30), max).is_ok());
+/// assert!(check_max_ttl(iat, iat + Duration::hours(1), max).is_ok());
+/// assert!(check_max_ttl(iat, iat + Duration::hours(2), max).is_err());
+/// ```
+pub fn check_max_ttl(
+    iat: DateTime<Utc>,
+    exp: DateTime<Utc>,
+    max_ttl: Duration,
+) -> Result<(), AttestationError> {
+    let ttl = exp - iat;
+    let within = chrono::Duration::from_std(max_ttl).map_or(true, |max| ttl <= max);
+    if within {
+        Ok(())
+    } else {
+        Err(AttestationError::TtlExceedsMaximum {
+            ttl_secs: ttl.num_seconds(),
+            max_secs: max_ttl.as_secs(),
+        })
+    }
+}
+
+/// Pure function: checks that a token was issued recently enough.
+///
+/// The age is measured from `iat` to `now`, regardless of how long the
+/// issuer let the token live. Tokens issued in the future have age zero.
+///
+/// # Arguments
+///
+/// * `iat` - The issued-at time from the token
+/// * `now` - The current time
+/// * `max_age` - The oldest token the relying party accepts
+///
+/// # Errors
+///
+/// Returns `AttestationError::TokenTooOld` if `now - iat > max_age`.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{Utc, Duration};
+/// use agent_uri_attestation::check_freshness;
+///
+/// let now = Utc::now();
+/// let max = std::time::Duration::from_secs(300);
+///
+/// assert!(check_freshness(now - Duration::minutes(5), now, max).is_ok());
+/// assert!(check_freshness(now - Duration::minutes(6), now, max).is_err());
+/// ```
+pub fn check_freshness(
+    iat: DateTime<Utc>,
+    now: DateTime<Utc>,
+    max_age: Duration,
+) -> Result<(), AttestationError> {
+    let age = now - iat;
+    let within = chrono::Duration::from_std(max_age).map_or(true, |max| age <= max);
+    if within {
+        Ok(())
+    } else {
+        Err(AttestationError::TokenTooOld {
+            age_secs: age.num_seconds(),
+            max_age_secs: max_age.as_secs(),
         })
     }
 }
 
+/// Pure function: validates that the token audience matches the expected audience.
+///
+/// A token without an `aud` claim never satisfies an audience requirement.
+///
+/// # Arguments
+///
+/// * `expected` - The audience the relying party identifies as
+/// * `token_audience` - The `aud` claim from the token, if any
+///
+/// # Errors
+///
+/// Returns `AttestationError::AudienceMismatch` if the token has no audience
+/// or its audience differs from `expected`.
+///
+/// # Examples
+///
+/// ```
+/// use agent_uri_attestation::validate_audience;
+///
+/// assert!(validate_audience("api.acme.com", Some("api.acme.com")).is_ok());
+/// assert!(validate_audience("api.acme.com", Some("other.com")).is_err());
+/// assert!(validate_audience("api.acme.com", None).is_err());
+/// ```
+pub fn validate_audience(
+    expected: &str,
+    token_audience: Option<&str>,
+) -> Result<(), AttestationError> {
+    if token_audience == Some(expected) {
+        Ok(())
+    } else {
+        Err(AttestationError::AudienceMismatch {
+            token_audience: token_audience.map(String::from),
+            expected_audience: expected.to_string(),
+        })
+    }
+}
+
+/// Pure function: checks whether a trust root matches a pattern.
+///
+/// A pattern is either an exact trust root (`acme.com`) or a wildcard of the
+/// form `*.acme.com`, which matches any subdomain of `acme.com` at any depth
+/// but not `acme.com` itself.
+///
+/// # Examples
+///
+/// ```
+/// use agent_uri_attestation::trust_root_matches;
+///
+/// assert!(trust_root_matches("acme.com", "acme.com"));
+/// assert!(trust_root_matches("*.acme.com", "agents.acme.com"));
+/// assert!(trust_root_matches("*.acme.com", "eu.agents.acme.com"));
+/// assert!(!trust_root_matches("*.acme.com", "acme.com"));
+/// assert!(!trust_root_matches("*.acme.com", "evilacme.com"));
+/// ```
+#[must_use]
+pub fn trust_root_matches(pattern: &str, trust_root: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(suffix) => trust_root
+            .strip_suffix(suffix)
+            .and_then(|head| head.strip_suffix('.'))
+            .is_some_and(|label| !label.is_empty()),
+        None => pattern == trust_root,
+    }
+}
+
+/// Pure function: validates one link of a delegation chain.
+///
+/// A child attestation is a valid delegation of its parent when:
+/// - The child's issuer equals the trust root of the parent's subject URI
+/// - Every child capability is covered by some parent capability
+///
+/// # Arguments
+///
+/// * `link` - Index of the child token in the chain, used for error reporting
+/// * `parent` - Claims of the delegating (parent) token
+/// * `child` - Claims of the delegated (child) token
+///
+/// # Errors
+///
+/// Returns `AttestationError::DelegationMismatch` if the issuer binding fails,
+/// or `AttestationError::CapabilityEscalation` if the child claims a capability
+/// its parent does not hold.
+///
+/// # Examples
+///
+/// ```
+/// use agent_uri_attestation::{check_delegation, AttestationClaims};
+///
+/// let parent = AttestationClaims::builder()
+///     .agent_uri("agent://eu.acme.com/workflow/agent_01h455vb4pex5vsknk084sn02q")
+///     .issuer("acme.com")
+///     .add_capability("workflow")
+///     .build()
+///     .unwrap();
+/// let child = AttestationClaims::builder()
+///     .agent_uri("agent://eu.acme.com/workflow/approval/agent_01h455vb4pex5vsknk084sn02q")
+///     .issuer("eu.acme.com")
+///     .add_capability("workflow/approval")
+///     .build()
+///     .unwrap();
+///
+/// assert!(check_delegation(1, &parent, &child).is_ok());
+/// assert!(check_delegation(1, &child, &parent).is_err());
+/// ```
+pub fn check_delegation(
+    link: usize,
+    parent: &AttestationClaims,
+    child: &AttestationClaims,
+) -> Result<(), AttestationError> {
+    let expected_issuer = parent.trust_root().unwrap_or_default();
+    if child.iss != expected_issuer {
+        return Err(AttestationError::DelegationMismatch {
+            link,
+            issuer: child.iss.clone(),
+            expected_issuer: expected_issuer.to_string(),
+        });
+    }
+
+    match widened_capabilities(parent, child).next() {
+        Some(escalated) => Err(AttestationError::CapabilityEscalation {
+            link,
+            capability: escalated.clone(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Returns the capabilities through which `wider` grants more than
+/// `narrower`: grants `narrower` does not cover, followed by denials of
+/// `narrower` that `wider` drops.
+pub(crate) fn widened_capabilities<'a>(
+    narrower: &'a AttestationClaims,
+    wider: &'a AttestationClaims,
+) -> impl Iterator<Item = &'a String> {
+    // A constrained capability is only covered by a grant with the same
+    // constraints; otherwise the wider claims could shed them
+    let covered = |cap: &&String| {
+        narrower.capabilities.iter().any(|p| {
+            covers(p, cap)
+                && narrower.capability_constraints.get(p).is_none_or(|c| {
+                    c.is_empty() || wider.capability_constraints.get(*cap) == Some(c)
+                })
+        })
+    };
+    // Every denial that touches a granted capability must carry over
+    let dropped = |denied: &&String| {
+        wider
+            .capabilities
+            .iter()
+            .any(|cap| covers(cap, denied) || covers(denied, cap))
+            && !wider.denied_capabilities.iter().any(|d| covers(d, denied))
+    };
+    wider
+        .capabilities
+        .iter()
+        .filter(move |cap| !covered(cap))
+        .chain(narrower.denied_capabilities.iter().filter(move |denied| dropped(denied)))
+}
+
+/// Returns the expiration of a delegation whose chain so far expires at
+/// `chain_exp` after appending a link expiring at `link_exp`.
+///
+/// A delegation is only valid while every link is, so attenuation can only
+/// shorten the chain's lifetime.
+pub(crate) fn delegated_expiry(chain_exp: DateTime<Utc>, link_exp: DateTime<Utc>) -> DateTime<Utc> {
+    chain_exp.min(link_exp)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -338,6 +1003,17 @@ mod tests {
         fn with_port_exact_match() {
             assert!(validate_issuer("localhost:8472", "localhost:8472").is_ok());
         }
+        #[test]
+        fn wildcard_matches_on_label_boundary() {
+            assert!(validate_issuer("*.acme.com", "eu.acme.com").is_ok());
+            assert!(validate_issuer("*.acme.com", "a.b.acme.com").is_ok());
+            for issuer in ["acme.com", "evilacme.com", "eu.acme.com.evil.com"] {
+                assert!(matches!(
+                    validate_issuer("*.acme.com", issuer),
+                    Err(AttestationError::TrustRootMismatch { .. })
+                ));
+            }
+        }
     }
 
     mod subject_validation_tests {
@@ -399,14 +1075,14 @@ mod tests {
         fn returns_ok_when_covered() {
             let attested = vec!["workflow".to_string()];
             let required = CapabilityPath::parse("workflow/approval").unwrap();
-            assert!(check_capability_coverage(&attested, &required).is_ok());
+            assert!(check_capability_coverage(&attested, &[], &required).is_ok());
         }
 
         #[test]
         fn returns_err_with_details_when_not_covered() {
             let attested = vec!["assistant/chat".to_string()];
             let required = CapabilityPath::parse("workflow/approval").unwrap();
-            let result = check_capability_coverage(&attested, &required);
+            let result = check_capability_coverage(&attested, &[], &required);
             match result {
                 Err(AttestationError::InsufficientCapabilities {
                     required: req,
@@ -419,4 +1095,462 @@ mod tests {
             }
         }
     }
+
+    mod denied_capability_tests {
+        use super::*;
+
+        fn check(required: &str) -> Result<(), AttestationError> {
+            let attested = vec!["workflow".to_string()];
+            let denied = vec!["workflow/payments".to_string()];
+            let required = CapabilityPath::parse(required).unwrap();
+            check_capability_coverage(&attested, &denied, &required)
+        }
+
+        #[test]
+        fn sibling_of_denied_path_is_covered() {
+            assert!(check("workflow/approval").is_ok());
+        }
+
+        #[test]
+        fn denied_path_and_descendants_are_rejected() {
+            assert!(matches!(
+                check("workflow/payments"),
+                Err(AttestationError::CapabilityDenied { ref denied, .. })
+                    if denied == "workflow/payments"
+            ));
+            assert!(check("workflow/payments/refund").is_err());
+        }
+
+        #[test]
+        fn ancestor_of_denied_path_is_rejected() {
+            assert!(matches!(
+                check("workflow"),
+                Err(AttestationError::CapabilityDenied { .. })
+            ));
+        }
+
+        #[test]
+        fn uncovered_path_reports_insufficient_capabilities() {
+            assert!(matches!(
+                check("assistant/chat"),
+                Err(AttestationError::InsufficientCapabilities { .. })
+            ));
+        }
+
+        #[test]
+        fn denial_applies_to_constrained_coverage() {
+            let attested = vec!["workflow".to_string()];
+            let denied = vec!["workflow/payments".to_string()];
+            let request = CapabilityRequest::new(
+                CapabilityPath::parse("workflow/payments").unwrap(),
+                Utc::now(),
+            );
+
+            assert!(matches!(
+                check_constrained_coverage(&attested, &denied, &BTreeMap::new(), &request),
+                Err(AttestationError::CapabilityDenied { .. })
+            ));
+        }
+    }
+
+    mod constrained_coverage_tests {
+        use super::*;
+
+        fn request(path: &str) -> CapabilityRequest {
+            CapabilityRequest::new(CapabilityPath::parse(path).unwrap(), Utc::now())
+        }
+
+        #[test]
+        fn unconstrained_capability_behaves_like_plain_coverage() {
+            let attested = vec!["workflow".to_string()];
+            let constraints = BTreeMap::new();
+
+            assert!(
+                check_constrained_coverage(&attested, &[], &constraints, &request("workflow/approval"))
+                    .is_ok()
+            );
+            assert!(matches!(
+                check_constrained_coverage(&attested, &[], &constraints, &request("assistant/chat")),
+                Err(AttestationError::InsufficientCapabilities { .. })
+            ));
+        }
+
+        #[test]
+        fn violated_constraint_is_reported() {
+            let attested = vec!["workflow/approval".to_string()];
+            let mut constraints = BTreeMap::new();
+            constraints.insert(
+                "workflow/approval".to_string(),
+                CapabilityConstraints::new().limit("max_amount", 100),
+            );
+
+            let result = check_constrained_coverage(
+                &attested,
+                &[],
+                &constraints,
+                &request("workflow/approval").quantity("amount", 101),
+            );
+
+            assert!(matches!(
+                result,
+                Err(AttestationError::CapabilityConstraintViolated { ref capability, .. })
+                    if capability == "workflow/approval"
+            ));
+        }
+
+        #[test]
+        fn any_permitting_capability_suffices() {
+            let attested = vec!["workflow/approval".to_string(), "workflow".to_string()];
+            let mut constraints = BTreeMap::new();
+            constraints.insert(
+                "workflow/approval".to_string(),
+                CapabilityConstraints::new().max_uses(1),
+            );
+
+            let request = request("workflow/approval").prior_uses(5);
+
+            assert!(check_constrained_coverage(&attested, &[], &constraints, &request).is_ok());
+        }
+    }
+
+    mod not_before_tests {
+        use super::*;
+        use chrono::Duration;
+
+        #[test]
+        fn past_not_before_is_valid() {
+            let now = Utc::now();
+            assert!(check_not_before(now - Duration::hours(1), now).is_ok());
+        }
+
+        #[test]
+        fn exact_not_before_is_valid() {
+            let now = Utc::now();
+            assert!(check_not_before(now, now).is_ok());
+        }
+
+        #[test]
+        fn future_not_before_is_rejected() {
+            let now = Utc::now();
+            let result = check_not_before(now + Duration::seconds(1), now);
+            assert!(matches!(
+                result,
+                Err(AttestationError::TokenNotYetValid { .. })
+            ));
+        }
+    }
+
+    mod max_ttl_tests {
+        use super::*;
+
+        #[test]
+        fn shorter_ttl_is_accepted() {
+            let iat = Utc::now();
+            let exp = iat + chrono::Duration::minutes(10);
+            assert!(check_max_ttl(iat, exp, Duration::from_hours(1)).is_ok());
+        }
+
+        #[test]
+        fn equal_ttl_is_accepted() {
+            let iat = Utc::now();
+            let exp = iat + chrono::Duration::seconds(3600);
+            assert!(check_max_ttl(iat, exp, Duration::from_hours(1)).is_ok());
+        }
+
+        #[test]
+        fn longer_ttl_is_rejected() {
+            let iat = Utc::now();
+            let exp = iat + chrono::Duration::seconds(3601);
+            let result = check_max_ttl(iat, exp, Duration::from_hours(1));
+            assert!(matches!(
+                result,
+                Err(AttestationError::TtlExceedsMaximum {
+                    ttl_secs: 3601,
+                    max_secs: 3600
+                })
+            ));
+        }
+    }
+
+    mod notation_tests {
+        use super::*;
+
+        fn path(s: &str) -> CapabilityPath {
+            CapabilityPath::parse(s).unwrap()
+        }
+
+        #[test]
+        fn unified_notation_treats_dots_as_separators() {
+            let attested = vec!["workflow.approval".to_string()];
+            let denied = vec!["workflow.approval.delete".to_string()];
+            let unified = CapabilityNotation::Unified;
+
+            assert!(capability_covers_with(&attested, &path("workflow/approval/read"), unified));
+            assert!(!capability_covers_with(&attested, &path("workflow/payments"), unified));
+            assert!(matches!(
+                check_capability_coverage_with(
+                    &attested,
+                    &denied,
+                    &path("workflow/approval/delete"),
+                    unified
+                ),
+                Err(AttestationError::CapabilityDenied { .. })
+            ));
+        }
+
+        #[test]
+        fn reject_dotted_names_the_offending_capability() {
+            let attested = vec!["assistant/chat".to_string(), "workflow.approval".to_string()];
+            let result = check_capability_coverage_with(
+                &attested,
+                &[],
+                &path("assistant/chat"),
+                CapabilityNotation::RejectDotted,
+            );
+
+            assert_eq!(
+                result,
+                Err(AttestationError::MixedCapabilityNotation {
+                    capability: "workflow.approval".to_string()
+                })
+            );
+        }
+    }
+
+    mod alias_tests {
+        use super::*;
+
+        fn aliases() -> CapabilityAliases {
+            CapabilityAliases::new().with_alias(
+                "email.send",
+                CapabilityPath::parse("communication/email/send").unwrap(),
+            )
+        }
+
+        #[test]
+        fn expand_appends_canonical_paths_once() {
+            let aliases = aliases();
+            let plain = vec!["assistant/chat".to_string()];
+            assert!(matches!(aliases.expand(&plain), Cow::Borrowed(_)));
+
+            let legacy = vec![
+                "communication/email/send".to_string(),
+                "email.send".to_string(),
+            ];
+            assert_eq!(*aliases.expand(&legacy), legacy[..]);
+
+            let legacy = vec!["email.send".to_string(), "email.send.bulk".to_string()];
+            assert_eq!(
+                *aliases.expand(&legacy),
+                ["email.send", "email.send.bulk", "communication/email/send"]
+            );
+        }
+
+        #[test]
+        fn legacy_constraints_apply_to_the_canonical_path() {
+            let expired = CapabilityConstraints::new().expires_at(Utc::now());
+            let claims = AttestationClaims::builder()
+                .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+                .issuer("acme.com")
+                .add_constrained_capability("email.send", expired)
+                .deny_capability("email.send")
+                .build()
+                .unwrap();
+
+            let expanded = aliases().expand_claims(&claims);
+            assert!(expanded.capabilities.contains(&"communication/email/send".to_string()));
+            assert!(expanded
+                .denied_capabilities
+                .contains(&"communication/email/send".to_string()));
+            assert_eq!(
+                expanded.capability_constraints.get("communication/email/send"),
+                claims.capability_constraints.get("email.send")
+            );
+        }
+    }
+
+    mod freshness_tests {
+        use super::*;
+
+        #[test]
+        fn recent_token_is_accepted() {
+            let now = Utc::now();
+            let iat = now - chrono::Duration::seconds(300);
+            assert!(check_freshness(iat, now, Duration::from_mins(5)).is_ok());
+            assert!(check_freshness(now + chrono::Duration::seconds(5), now, Duration::ZERO).is_ok());
+        }
+
+        #[test]
+        fn old_token_is_rejected() {
+            let now = Utc::now();
+            let iat = now - chrono::Duration::seconds(301);
+            assert!(matches!(
+                check_freshness(iat, now, Duration::from_mins(5)),
+                Err(AttestationError::TokenTooOld {
+                    age_secs: 301,
+                    max_age_secs: 300
+                })
+            ));
+        }
+    }
+
+    mod audience_tests {
+        use super::*;
+
+        #[test]
+        fn matching_audience_succeeds() {
+            assert!(validate_audience("api.acme.com", Some("api.acme.com")).is_ok());
+        }
+
+        #[test]
+        fn different_audience_fails() {
+            let result = validate_audience("api.acme.com", Some("api.evil.com"));
+            assert!(matches!(
+                result,
+                Err(AttestationError::AudienceMismatch { .. })
+            ));
+        }
+
+        #[test]
+        fn missing_audience_fails() {
+            let result = validate_audience("api.acme.com", None);
+            assert!(matches!(
+                result,
+                Err(AttestationError::AudienceMismatch {
+                    token_audience: None,
+                    ..
+                })
+            ));
+        }
+    }
+
+    mod trust_root_pattern_tests {
+        use super::*;
+
+        #[test]
+        fn exact_pattern_matches_only_itself() {
+            assert!(trust_root_matches("acme.com", "acme.com"));
+            assert!(!trust_root_matches("acme.com", "agents.acme.com"));
+        }
+
+        #[test]
+        fn wildcard_matches_subdomains() {
+            assert!(trust_root_matches("*.acme.com", "agents.acme.com"));
+            assert!(trust_root_matches("*.acme.com", "eu.agents.acme.com"));
+        }
+
+        #[test]
+        fn wildcard_does_not_match_apex() {
+            assert!(!trust_root_matches("*.acme.com", "acme.com"));
+        }
+
+        #[test]
+        fn wildcard_requires_label_boundary() {
+            assert!(!trust_root_matches("*.acme.com", "evilacme.com"));
+            assert!(!trust_root_matches("*.acme.com", ".acme.com"));
+        }
+    }
+
+    mod delegation_tests {
+        use super::*;
+        use crate::claims::AttestationClaimsBuilder;
+
+        fn claims(uri_root: &str, iss: &str, caps: &[&str]) -> AttestationClaims {
+            AttestationClaimsBuilder::new()
+                .agent_uri(format!(
+                    "agent://{uri_root}/workflow/agent_01h455vb4pex5vsknk084sn02q"
+                ))
+                .issuer(iss)
+                .capabilities(caps.iter().map(ToString::to_string).collect())
+                .build()
+                .unwrap()
+        }
+
+        #[test]
+        fn narrowed_child_is_valid() {
+            let parent = claims("eu.acme.com", "acme.com", &["workflow"]);
+            let child = claims("eu.acme.com", "eu.acme.com", &["workflow/approval"]);
+            assert!(check_delegation(1, &parent, &child).is_ok());
+        }
+
+        #[test]
+        fn equal_capabilities_are_valid() {
+            let parent = claims("eu.acme.com", "acme.com", &["workflow"]);
+            let child = claims("eu.acme.com", "eu.acme.com", &["workflow"]);
+            assert!(check_delegation(1, &parent, &child).is_ok());
+        }
+
+        #[test]
+        fn wrong_issuer_is_rejected() {
+            let parent = claims("eu.acme.com", "acme.com", &["workflow"]);
+            let child = claims("eu.acme.com", "us.acme.com", &["workflow"]);
+            assert!(matches!(
+                check_delegation(2, &parent, &child),
+                Err(AttestationError::DelegationMismatch { link: 2, .. })
+            ));
+        }
+
+        #[test]
+        fn constrained_capability_must_keep_constraints() {
+            let constraints = CapabilityConstraints::new().limit("max_amount", 1000);
+            let parent = AttestationClaimsBuilder::new()
+                .agent_uri("agent://eu.acme.com/workflow/agent_01h455vb4pex5vsknk084sn02q")
+                .issuer("acme.com")
+                .add_constrained_capability("workflow/approval", constraints.clone())
+                .build()
+                .unwrap();
+            let stripped = claims("eu.acme.com", "eu.acme.com", &["workflow/approval"]);
+            let kept = AttestationClaimsBuilder::new()
+                .agent_uri("agent://eu.acme.com/workflow/agent_01h455vb4pex5vsknk084sn02q")
+                .issuer("eu.acme.com")
+                .add_constrained_capability("workflow/approval", constraints)
+                .build()
+                .unwrap();
+
+            assert!(matches!(
+                check_delegation(1, &parent, &stripped),
+                Err(AttestationError::CapabilityEscalation { .. })
+            ));
+            assert!(check_delegation(1, &parent, &kept).is_ok());
+        }
+
+        #[test]
+        fn parent_denials_must_carry_over() {
+            let parent = AttestationClaimsBuilder::new()
+                .agent_uri("agent://eu.acme.com/workflow/agent_01h455vb4pex5vsknk084sn02q")
+                .issuer("acme.com")
+                .add_capability("workflow")
+                .deny_capability("workflow/payments")
+                .build()
+                .unwrap();
+            let dropped = claims("eu.acme.com", "eu.acme.com", &["workflow"]);
+            let narrowed = claims("eu.acme.com", "eu.acme.com", &["workflow/approval"]);
+            let kept = AttestationClaimsBuilder::new()
+                .agent_uri("agent://eu.acme.com/workflow/agent_01h455vb4pex5vsknk084sn02q")
+                .issuer("eu.acme.com")
+                .add_capability("workflow")
+                .deny_capability("workflow/payments")
+                .build()
+                .unwrap();
+
+            assert!(matches!(
+                check_delegation(1, &parent, &dropped),
+                Err(AttestationError::CapabilityEscalation { ref capability, .. })
+                    if capability == "workflow/payments"
+            ));
+            assert!(check_delegation(1, &parent, &narrowed).is_ok());
+            assert!(check_delegation(1, &parent, &kept).is_ok());
+        }
+
+        #[test]
+        fn widened_capability_is_rejected() {
+            let parent = claims("eu.acme.com", "acme.com", &["workflow/approval"]);
+            let child = claims("eu.acme.com", "eu.acme.com", &["workflow"]);
+            assert!(matches!(
+                check_delegation(1, &parent, &child),
+                Err(AttestationError::CapabilityEscalation { link: 1, ref capability })
+                    if capability == "workflow"
+            ));
+        }
+    }
 }
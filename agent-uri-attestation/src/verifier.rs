@@ -1,17 +1,35 @@
 //! Token verifier for validating attestations.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
 
 use agent_uri::AgentUri;
-use chrono::Utc;
-use rusty_paseto::prelude::*;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use web_time::Instant;
 
 use agent_uri::CapabilityPath;
 
-use crate::claims::AttestationClaims;
+use crate::bundle::{BundleStatusSource, TrustBundle};
+use crate::cache::VerificationCache;
+use crate::claims::{AttestationClaims, LEGACY_CLAIMS_VERSION};
+use crate::clock::{Clock, SharedClock};
+use crate::constraints::{self, CapabilityRequest};
+use crate::cosign::CoSignedAttestation;
 use crate::error::AttestationError;
-use crate::keys::VerifyingKey;
-use crate::verification;
+use crate::keys::{ThresholdKeySet, VerifyingKey};
+use crate::keyset::AgentKeySet;
+use crate::limits;
+use crate::metrics::{MetricsRecorder, VerifierMetrics};
+use crate::online::StatusChecker;
+use crate::policy::VerificationPolicy;
+use crate::telemetry::Step;
+use crate::status::{SharedStatusSource, StatusList, StatusListClaim, StatusListSource};
+use crate::transparency::{InclusionProof, SignedTreeHead};
+use crate::verification::{self, CapabilityAliases, CapabilityNotation};
 
 /// Verifies attestation tokens for agent URIs.
 ///
@@ -45,7 +63,19 @@ use crate::verification;
 /// ```
 #[derive(Debug, Clone, Default)]
 pub struct Verifier {
-    trusted_roots: HashMap<String, VerifyingKey>,
+    trusted_roots: HashMap<String, Vec<VerifyingKey>>,
+    trusted_patterns: HashMap<String, VerifyingKey>,
+    parent_domain_issuers: HashSet<String>,
+    threshold_roots: HashMap<String, ThresholdKeySet>,
+    cache: Option<VerificationCache>,
+    transparency_log: Option<VerifyingKey>,
+    status_source: Option<SharedStatusSource>,
+    metrics: MetricsRecorder,
+    clock: SharedClock,
+    max_ttl: Option<Duration>,
+    max_age: Option<Duration>,
+    capability_notation: CapabilityNotation,
+    capability_aliases: CapabilityAliases,
 }
 
 impl Verifier {
@@ -57,458 +87,3224 @@ impl Verifier {
 
     /// Adds a trusted root and its public key.
     ///
+    /// Replaces any keys previously registered for `trust_root`; use
+    /// [`add_trusted_key`](Self::add_trusted_key) to trust several keys for
+    /// the same root.
+    ///
     /// # Arguments
     ///
     /// * `trust_root` - The trust root identifier (e.g., "acme.com")
     /// * `public_key` - The Ed25519 public key for this trust root
     pub fn add_trusted_root(&mut self, trust_root: impl Into<String>, public_key: VerifyingKey) {
-        self.trusted_roots.insert(trust_root.into(), public_key);
+        self.trusted_roots.insert(trust_root.into(), vec![public_key]);
+        // Cached results were computed against the previous trust store
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
     }
 
-    /// Returns true if the given trust root is registered.
-    #[must_use]
-    pub fn has_trusted_root(&self, trust_root: &str) -> bool {
-        self.trusted_roots.contains_key(trust_root)
+    /// Trusts an additional key for `trust_root`, keeping the keys already
+    /// registered for it.
+    ///
+    /// Tokens are accepted if any of the root's keys validates them, which
+    /// lets an issuer rotate keys without a flag day.
+    /// [`verify_detailed`](Self::verify_detailed) reports which key matched,
+    /// and [`metrics`](Self::metrics) counts matches per key, so an old key
+    /// can be retired with [`remove_trusted_key`](Self::remove_trusted_key)
+    /// once it stops being used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::{Issuer, SigningKey, Verifier};
+    /// use agent_uri::AgentUri;
+    /// use std::time::Duration;
+    ///
+    /// let old = SigningKey::generate();
+    /// let new = SigningKey::generate();
+    /// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+    /// let token = Issuer::new("acme.com", new.clone(), Duration::from_secs(3600))
+    ///     .issue(&uri, vec![])
+    ///     .unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", old.verifying_key());
+    /// verifier.add_trusted_key("acme.com", new.verifying_key());
+    ///
+    /// let verified = verifier.verify_detailed(&token).unwrap();
+    /// assert_eq!(verified.key_id(), new.verifying_key().fingerprint());
+    ///
+    /// let usage = verifier.metrics().key_usage;
+    /// assert_eq!(usage.get(&new.verifying_key().fingerprint()), Some(&1));
+    /// assert_eq!(usage.get(&old.verifying_key().fingerprint()), None);
+    /// ```
+    pub fn add_trusted_key(&mut self, trust_root: impl Into<String>, public_key: VerifyingKey) {
+        let keys = self.trusted_roots.entry(trust_root.into()).or_default();
+        if !keys.contains(&public_key) {
+            keys.push(public_key);
+        }
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Stops trusting `public_key` for `trust_root`.
+    ///
+    /// The root itself is removed along with its last key. Returns true if
+    /// the key was registered.
+    pub fn remove_trusted_key(&mut self, trust_root: &str, public_key: &VerifyingKey) -> bool {
+        let Some(keys) = self.trusted_roots.get_mut(trust_root) else {
+            return false;
+        };
+        let before = keys.len();
+        keys.retain(|key| key != public_key);
+        let removed = keys.len() != before;
+        if keys.is_empty() {
+            self.trusted_roots.remove(trust_root);
+        }
+        if removed && let Some(cache) = &self.cache {
+            cache.clear();
+        }
+        removed
     }
 
-    /// Returns the number of registered trusted roots.
+    /// Returns the keys registered for `trust_root` with
+    /// [`add_trusted_root`](Self::add_trusted_root) and
+    /// [`add_trusted_key`](Self::add_trusted_key), oldest first.
     #[must_use]
-    pub fn trusted_root_count(&self) -> usize {
-        self.trusted_roots.len()
+    pub fn trusted_keys(&self, trust_root: &str) -> &[VerifyingKey] {
+        self.trusted_roots.get(trust_root).map_or(&[], Vec::as_slice)
     }
 
-    /// Verifies an attestation token and returns its claims.
+    /// Adds a key trusted for every trust root matching `pattern`.
     ///
-    /// This method:
-    /// 1. Parses the PASETO token
-    /// 2. Verifies the signature using the issuer's public key
-    /// 3. Checks expiration
-    /// 4. Validates the issuer is trusted
+    /// A pattern of the form `*.acme.com` matches any subdomain of
+    /// `acme.com` on a label boundary (`eu.acme.com`, `a.b.acme.com`), but
+    /// neither `acme.com` itself nor `evilacme.com`; see
+    /// [`trust_root_matches`](crate::trust_root_matches). A pattern without
+    /// the `*.` prefix behaves like [`add_trusted_root`](Self::add_trusted_root).
     ///
-    /// # Errors
+    /// A root registered with `add_trusted_root` takes precedence over any
+    /// pattern, and among patterns the longest match wins, so individual
+    /// subdomains can still pin their own keys.
     ///
-    /// Returns `AttestationError` if verification fails for any reason:
-    /// - `InvalidSignature` - Signature doesn't match any trusted key
-    /// - `TokenExpired` - Token has passed its expiration time
-    /// - `UntrustedIssuer` - Issuer is not in the trusted roots set
-    /// - `InvalidTokenFormat` - Token is malformed
-    /// - `InvalidClaims` - Claims cannot be parsed
-    pub fn verify(&self, token: &str) -> Result<AttestationClaims, AttestationError> {
-        if self.trusted_roots.is_empty() {
-            return Err(AttestationError::UntrustedIssuer {
-                issuer: "unknown".to_string(),
-            });
-        }
-
-        // Try each trusted key until one works
-        let (issuer, claims) = self.extract_and_verify(token)?;
-
-        // Validate issuer is trusted (already verified by finding the key)
-        if !self.trusted_roots.contains_key(&issuer) {
-            return Err(AttestationError::UntrustedIssuer { issuer });
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri_attestation::{Issuer, SigningKey, Verifier};
+    /// use agent_uri::AgentUri;
+    /// use std::time::Duration;
+    ///
+    /// let corporate = SigningKey::generate();
+    /// let issuer = Issuer::new("eu.acme.com", corporate.clone(), Duration::from_secs(3600));
+    /// let uri = AgentUri::parse("agent://eu.acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+    ///     .unwrap();
+    /// let token = issuer.issue(&uri, vec![]).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root_pattern("*.acme.com", corporate.verifying_key());
+    /// assert_eq!(verifier.verify(&token).unwrap().iss, "eu.acme.com");
+    /// ```
+    pub fn add_trusted_root_pattern(
+        &mut self,
+        pattern: impl Into<String>,
+        public_key: VerifyingKey,
+    ) {
+        self.trusted_patterns.insert(pattern.into(), public_key);
+        if let Some(cache) = &self.cache {
+            cache.clear();
         }
-
-        Ok(claims)
     }
 
-    /// Verifies a token and checks it matches the expected agent URI.
+    /// Lets attestations issued by `issuer` cover agent URIs under its
+    /// subdomains in [`verify_for_uri`](Self::verify_for_uri).
     ///
-    /// # Arguments
+    /// By default the token's `iss` must equal the attested URI's trust
+    /// root. After this call, a token from `acme.com` may also attest
+    /// `agent://eu.acme.com/...` or `agent://a.b.acme.com/...`, matched on
+    /// label boundaries, so `evilacme.com` is never covered. The allowance
+    /// is per issuer; other issuers keep the exact binding.
     ///
-    /// * `token` - The PASETO token to verify
-    /// * `expected_uri` - The agent URI that should be attested
+    /// # Example
     ///
-    /// # Errors
+    /// ```
+    /// use agent_uri_attestation::{Issuer, Verifier};
+    /// use agent_uri::AgentUri;
+    /// use std::time::Duration;
     ///
-    /// Returns `AttestationError` if:
-    /// - Token verification fails
-    /// - The token's `agent_uri` doesn't match `expected_uri`
-    /// - The trust root in the token doesn't match the URI's trust root
-    pub fn verify_for_uri(
-        &self,
-        token: &str,
-        expected_uri: &AgentUri,
-    ) -> Result<AttestationClaims, AttestationError> {
-        let claims = self.verify(token)?;
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let uri = AgentUri::parse("agent://billing.acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+    ///     .unwrap();
+    /// let token = issuer.issue(&uri, vec![]).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    /// assert!(verifier.verify_for_uri(&token, &uri).is_err());
+    ///
+    /// verifier.allow_parent_domain_issuer("acme.com");
+    /// assert!(verifier.verify_for_uri(&token, &uri).is_ok());
+    /// ```
+    pub fn allow_parent_domain_issuer(&mut self, issuer: impl Into<String>) {
+        self.parent_domain_issuers.insert(issuer.into());
+    }
 
-        let expected_str = expected_uri.to_string();
-        if claims.agent_uri != expected_str {
-            return Err(AttestationError::UriMismatch {
-                token_uri: claims.agent_uri.clone(),
-                expected_uri: expected_str,
-            });
+    /// Adds a trust root that requires `k` of `n` signatures.
+    ///
+    /// Tokens for this trust root are only accepted through
+    /// [`verify_threshold`](Self::verify_threshold); a single signature is
+    /// never sufficient.
+    ///
+    /// # Arguments
+    ///
+    /// * `trust_root` - The trust root identifier (e.g., "acme.com")
+    /// * `key_set` - The published keys and required threshold
+    pub fn add_threshold_root(&mut self, trust_root: impl Into<String>, key_set: ThresholdKeySet) {
+        self.threshold_roots.insert(trust_root.into(), key_set);
+        if let Some(cache) = &self.cache {
+            cache.clear();
         }
+    }
 
-        // Also verify trust root matches
-        if let Some(token_root) = claims.trust_root() {
-            let expected_root = expected_uri.trust_root().as_str();
-            if token_root != expected_root {
-                return Err(AttestationError::TrustRootMismatch {
-                    token_root: token_root.to_string(),
-                    expected_root: expected_root.to_string(),
-                });
-            }
-        }
+    /// Returns true if the given trust root is registered with a threshold key set.
+    #[must_use]
+    pub fn has_threshold_root(&self, trust_root: &str) -> bool {
+        self.threshold_roots.contains_key(trust_root)
+    }
 
-        Ok(claims)
+    /// Enables result caching with the given cache.
+    ///
+    /// Replaces any previously configured cache. The cache is cleared
+    /// whenever the trust store changes.
+    pub fn enable_cache(&mut self, cache: VerificationCache) {
+        self.cache = Some(cache);
     }
 
-    /// Verifies a token, checks URI match, AND validates capability coverage.
+    /// Disables result caching and drops all cached entries.
+    pub fn disable_cache(&mut self) {
+        self.cache = None;
+    }
+
+    /// Returns the configured cache, if caching is enabled.
+    #[must_use]
+    pub fn cache(&self) -> Option<&VerificationCache> {
+        self.cache.as_ref()
+    }
+
+    /// Requires every token to be proven present in a transparency log.
     ///
-    /// This is the most comprehensive verification method, performing:
-    /// 1. Signature verification using trusted keys
-    /// 2. Expiration check
-    /// 3. Issuer validation against trusted roots
-    /// 4. URI match verification
-    /// 5. Trust root consistency check
-    /// 6. Capability coverage verification
+    /// Once set, [`verify`](Self::verify) and every method built on it, as
+    /// well as [`verify_threshold`](Self::verify_threshold) and the CWT and
+    /// JWT paths, reject tokens with `InclusionProofRequired`; tokens are
+    /// only accepted through [`verify_logged`](Self::verify_logged) with
+    /// tree heads signed by `log_key`.
+    pub fn require_transparency_log(&mut self, log_key: VerifyingKey) {
+        self.transparency_log = Some(log_key);
+    }
+
+    /// Returns the transparency log key, if inclusion proofs are required.
+    #[must_use]
+    pub fn transparency_log(&self) -> Option<&VerifyingKey> {
+        self.transparency_log.as_ref()
+    }
+
+    /// Checks tokens carrying a `status_idx` claim against status lists.
     ///
-    /// # Arguments
+    /// For each such token the verifier asks `source` for the issuer's
+    /// current status list token, verifies it against the issuer's trusted
+    /// key, and rejects the attestation with `TokenRevoked` if its bit is
+    /// set. A missing, expired or unverifiable status list fails closed
+    /// with `StatusUnavailable`.
     ///
-    /// * `token` - The PASETO token to verify
-    /// * `uri` - The agent URI that should be attested
-    /// * `required_capability` - The capability path required for the operation
+    /// Status is checked after the cache, so a cached token is still
+    /// rejected once its issuer publishes a list revoking it.
     ///
-    /// # Returns
+    /// # Example
     ///
-    /// The verified `AttestationClaims` if all checks pass
+    /// ```
+    /// use agent_uri_attestation::{AttestationClaims, AttestationError, Issuer, StatusList, Verifier};
+    /// use std::time::Duration;
     ///
-    /// # Errors
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let claims = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02q")
+    ///     .issuer("acme.com")
+    ///     .status_index(7)
+    ///     .build()
+    ///     .unwrap();
+    /// let token = issuer.issue_claims(&claims).unwrap();
     ///
-    /// Returns `AttestationError` if any verification step fails:
-    /// - `InvalidSignature` - Signature doesn't match any trusted key
-    /// - `TokenExpired` - Token has passed its expiration time
-    /// - `UntrustedIssuer` - Issuer is not in the trusted roots set
-    /// - `UriMismatch` - Token's `agent_uri` doesn't match expected URI
-    /// - `TrustRootMismatch` - Trust root in token doesn't match URI's trust root
-    /// - `InsufficientCapabilities` - Token capabilities don't cover required path
+    /// let mut list = StatusList::new(1024);
+    /// list.revoke(7).unwrap();
+    /// let status = issuer.issue_status_list(&list, Duration::from_secs(300)).unwrap();
     ///
-    /// # Examples
+    /// let mut verifier = Verifier::new()
+    ///     .with_status_list_source(move |_: &str| Some(status.clone()));
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    ///
+    /// assert!(matches!(
+    ///     verifier.verify(&token),
+    ///     Err(AttestationError::TokenRevoked { index: 7 })
+    /// ));
+    /// ```
+    #[must_use]
+    pub fn with_status_list_source(mut self, source: impl StatusListSource + 'static) -> Self {
+        self.status_source = Some(SharedStatusSource(Arc::new(source)));
+        self
+    }
+
+    /// Replaces the verifier's time source.
+    ///
+    /// Expiry, not-before, policy age limits and cache lifetimes are all
+    /// evaluated against `clock` instead of the system time. See
+    /// [`ManualClock`](crate::ManualClock) for testing expiry without
+    /// sleeping.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = SharedClock(Arc::new(clock));
+        self
+    }
+
+    /// Rejects tokens whose total lifetime (`exp - iat`) exceeds `max_ttl`,
+    /// whatever TTL their issuer chose.
+    ///
+    /// Applies to every verification method, unlike the per-call limit of
+    /// a [`VerificationPolicy`]. Violations are reported as
+    /// `AttestationError::TtlExceedsMaximum`.
+    ///
+    /// # Example
     ///
     /// ```
     /// use agent_uri::AgentUri;
-    /// use agent_uri::CapabilityPath;
-    /// use agent_uri_attestation::{Issuer, Verifier, SigningKey};
+    /// use agent_uri_attestation::{AttestationError, Issuer, Verifier};
     /// use std::time::Duration;
     ///
-    /// let signing_key = SigningKey::generate();
-    /// let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(3600));
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(86400));
+    /// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+    /// let token = issuer.issue(&uri, vec![]).unwrap();
     ///
-    /// let uri = AgentUri::parse(
-    ///     "agent://acme.com/workflow/approval/agent_01h455vb4pex5vsknk084sn02q"
-    /// ).unwrap();
-    /// let token = issuer.issue(&uri, vec!["workflow".into()]).unwrap();
+    /// let mut verifier = Verifier::new().with_max_ttl(Duration::from_secs(3600));
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    /// assert!(matches!(
+    ///     verifier.verify(&token),
+    ///     Err(AttestationError::TtlExceedsMaximum { .. })
+    /// ));
+    /// ```
+    #[must_use]
+    pub fn with_max_ttl(mut self, max_ttl: Duration) -> Self {
+        self.max_ttl = Some(max_ttl);
+        self
+    }
+
+    /// Rejects tokens issued more than `max_age` ago (`now - iat`), even if
+    /// they have not expired.
     ///
-    /// let mut verifier = Verifier::new();
-    /// verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+    /// Applies to every verification method, including cached results.
+    /// Violations are reported as `AttestationError::TokenTooOld`.
     ///
-    /// // "workflow" capability covers "workflow/approval"
-    /// let required = CapabilityPath::parse("workflow/approval").unwrap();
-    /// let claims = verifier.verify_for_capability(&token, &uri, &required).unwrap();
+    /// # Example
     ///
-    /// assert_eq!(claims.iss, "acme.com");
     /// ```
-    pub fn verify_for_capability(
-        &self,
-        token: &str,
-        uri: &AgentUri,
-        required_capability: &CapabilityPath,
-    ) -> Result<AttestationClaims, AttestationError> {
-        // First verify the token and URI match
-        let claims = self.verify_for_uri(token, uri)?;
+    /// use agent_uri::AgentUri;
+    /// use agent_uri_attestation::{AttestationError, Issuer, ManualClock, Verifier};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new(chrono::Utc::now());
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(86400))
+    ///     .with_clock(clock.clone());
+    /// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+    /// let token = issuer.issue(&uri, vec![]).unwrap();
+    ///
+    /// let mut verifier = Verifier::new()
+    ///     .with_max_age(Duration::from_secs(300))
+    ///     .with_clock(clock.clone());
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    /// assert!(verifier.verify(&token).is_ok());
+    ///
+    /// clock.advance(Duration::from_secs(301));
+    /// assert!(matches!(
+    ///     verifier.verify(&token),
+    ///     Err(AttestationError::TokenTooOld { .. })
+    /// ));
+    /// ```
+    #[must_use]
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
 
-        // Then check capability coverage using pure function
-        verification::check_capability_coverage(&claims.capabilities, required_capability)?;
+    /// Sets how granted capabilities written with `.` separators are matched
+    /// by [`verify_for_capability`](Self::verify_for_capability); see
+    /// [`CapabilityNotation`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri::{AgentUri, CapabilityPath};
+    /// use agent_uri_attestation::{CapabilityNotation, Issuer, Verifier};
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+    /// let token = issuer.issue(&uri, vec!["workflow.approval.read".into()]).unwrap();
+    /// let required = CapabilityPath::parse("workflow/approval/read").unwrap();
+    ///
+    /// let mut verifier = Verifier::new().with_capability_notation(CapabilityNotation::Unified);
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    /// assert!(verifier.verify_for_capability(&token, &uri, &required).is_ok());
+    /// ```
+    #[must_use]
+    pub fn with_capability_notation(mut self, notation: CapabilityNotation) -> Self {
+        self.capability_notation = notation;
+        self
+    }
 
-        Ok(claims)
+    /// Sets the legacy capability aliases consulted by
+    /// [`verify_for_capability`](Self::verify_for_capability); see
+    /// [`CapabilityAliases`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri::{AgentUri, CapabilityPath};
+    /// use agent_uri_attestation::{CapabilityAliases, Issuer, Verifier};
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+    /// let token = issuer.issue(&uri, vec!["email.send".into()]).unwrap();
+    /// let canonical = CapabilityPath::parse("communication/email/send").unwrap();
+    ///
+    /// let aliases = CapabilityAliases::new().with_alias("email.send", canonical.clone());
+    /// let mut verifier = Verifier::new().with_capability_aliases(aliases);
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    /// assert!(verifier.verify_for_capability(&token, &uri, &canonical).is_ok());
+    /// ```
+    #[must_use]
+    pub fn with_capability_aliases(mut self, aliases: CapabilityAliases) -> Self {
+        self.capability_aliases = aliases;
+        self
     }
 
-    /// Internal method to extract issuer and verify signature.
-    fn extract_and_verify(
-        &self,
+    /// Merges a signed [`TrustBundle`] into this verifier's trust store.
+    ///
+    /// The bundle's roots, patterns and threshold roots are added as if
+    /// registered individually, replacing existing entries for the same
+    /// names. Its status lists take precedence over any configured
+    /// [`StatusListSource`], which still serves the issuers the bundle does
+    /// not cover. Nothing is changed if the bundle fails verification.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if the bundle was not signed by
+    /// `distribution_key`, has expired, or is malformed; see
+    /// [`TrustBundle::open`].
+    pub fn load_bundle(
+        &mut self,
         token: &str,
-    ) -> Result<(String, AttestationClaims), AttestationError> {
-        // Try each trusted key until one works
-        let mut last_error = None;
+        distribution_key: &VerifyingKey,
+    ) -> Result<(), AttestationError> {
+        let bundle = TrustBundle::open_at(token, distribution_key, self.clock.now())?;
 
-        for (trust_root, verifying_key) in &self.trusted_roots {
-            match try_verify_with_key(token, verifying_key) {
-                Ok(claims) => {
-                    // Verify the issuer matches the key we used
-                    if claims.iss == *trust_root {
-                        return Ok((trust_root.clone(), claims));
-                    }
-                    // Issuer mismatch - this key signed it but claims different issuer
-                    last_error = Some(AttestationError::TrustRootMismatch {
-                        token_root: claims.iss.clone(),
-                        expected_root: trust_root.clone(),
-                    });
-                }
-                Err(e) => {
-                    last_error = Some(e);
-                }
-            }
+        for (root, keys) in bundle.trusted_roots() {
+            self.trusted_roots.insert(root.clone(), keys.clone());
+        }
+        for (pattern, key) in bundle.trusted_patterns() {
+            self.trusted_patterns.insert(pattern.clone(), key.clone());
+        }
+        for (root, key_set) in bundle.threshold_roots() {
+            self.threshold_roots.insert(root.clone(), key_set.clone());
+        }
+        for key_set in bundle.agent_key_sets().values() {
+            self.trust_key_set(key_set);
+        }
+        if !bundle.status_lists().is_empty() {
+            let source = BundleStatusSource {
+                lists: bundle.status_lists().clone(),
+                fallback: self.status_source.take(),
+            };
+            self.status_source = Some(SharedStatusSource(Arc::new(source)));
         }
 
-        Err(last_error.unwrap_or(AttestationError::UntrustedIssuer {
-            issuer: "unknown".to_string(),
-        }))
+        // Cached results were computed against the previous trust store
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+        Ok(())
     }
 
-}
+    /// Trusts the keys of `key_set` that are valid now for its trust root.
+    ///
+    /// Replaces the keys previously registered for the root, leaving it
+    /// untrusted if no key is currently valid. Keys are taken at this
+    /// moment: call again with a fresh document after a rotation, or once
+    /// an announced next key's `not_before` passes.
+    pub fn trust_key_set(&mut self, key_set: &AgentKeySet) {
+        let keys = key_set.valid_keys_at(self.clock.now());
+        if keys.is_empty() {
+            self.trusted_roots.remove(key_set.trust_root());
+        } else {
+            self.trusted_roots.insert(key_set.trust_root().to_string(), keys);
+        }
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
 
-/// Try to verify a token with a specific key.
-fn try_verify_with_key(
-    token: &str,
-    verifying_key: &VerifyingKey,
-) -> Result<AttestationClaims, AttestationError> {
-    let key_bytes = verifying_key.to_bytes();
-    let key_wrapper = Key::<32>::from(&key_bytes);
-    let paseto_key = PasetoAsymmetricPublicKey::<V4, Public>::from(&key_wrapper);
-
-    let json_value = PasetoParser::<V4, Public>::default()
-        .parse(token, &paseto_key)
-        .map_err(|e| {
-            let err_str = e.to_string();
-            if err_str.to_lowercase().contains("signature") {
-                AttestationError::InvalidSignature
-            } else if err_str.to_lowercase().contains("expired")
-                || err_str.to_lowercase().contains("exp")
-            {
-                AttestationError::TokenExpired {
-                    expired_at: "unknown".to_string(),
-                }
-            } else {
-                AttestationError::InvalidTokenFormat { reason: err_str }
+    /// Verifies a signed [`AgentKeySet`] and trusts its currently valid keys;
+    /// see [`trust_key_set`](Self::trust_key_set).
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if the document was not signed by
+    /// `signer`, has expired, or is malformed; see [`AgentKeySet::open`].
+    pub fn load_key_set(
+        &mut self,
+        token: &str,
+        signer: &VerifyingKey,
+    ) -> Result<(), AttestationError> {
+        let key_set = AgentKeySet::open_at(token, signer, self.clock.now())?;
+        self.trust_key_set(&key_set);
+        Ok(())
+    }
+
+    /// Exports this verifier's roots, patterns and threshold roots as a
+    /// [`TrustBundle`].
+    ///
+    /// Every key of a root with several (see
+    /// [`add_trusted_key`](Self::add_trusted_key)) is exported, so a bundle
+    /// taken mid-rotation carries both the outgoing and the incoming key.
+    ///
+    /// Status lists come from a [`StatusListSource`] on demand and are not
+    /// exported; add current ones with [`TrustBundle::add_status_list`]
+    /// before signing.
+    #[must_use]
+    pub fn export_bundle(&self) -> TrustBundle {
+        let mut bundle = TrustBundle::new();
+        for (root, keys) in &self.trusted_roots {
+            for key in keys {
+                bundle.add_trusted_key(root.clone(), key.clone());
             }
-        })?;
+        }
+        for (pattern, key) in &self.trusted_patterns {
+            bundle.add_trusted_root_pattern(pattern.clone(), key.clone());
+        }
+        for (root, key_set) in &self.threshold_roots {
+            bundle.add_threshold_root(root.clone(), key_set.clone());
+        }
+        bundle
+    }
 
-    // Extract claims from JSON
-    extract_claims(&json_value)
-}
+    /// Returns a snapshot of this verifier's counters.
+    ///
+    /// Cloning a verifier starts its clone's counters from zero.
+    #[must_use]
+    pub fn metrics(&self) -> VerifierMetrics {
+        self.metrics.snapshot()
+    }
 
-/// Extract `AttestationClaims` from parsed JSON value.
-fn extract_claims(json: &serde_json::Value) -> Result<AttestationClaims, AttestationError> {
-    let agent_uri = json["agent_uri"]
+    /// Resets all counters to zero.
+    pub fn reset_metrics(&self) {
+        self.metrics.reset();
+    }
+
+    /// Returns true if the given trust root is registered.
+    #[must_use]
+    pub fn has_trusted_root(&self, trust_root: &str) -> bool {
+        self.trusted_roots.contains_key(trust_root)
+    }
+
+    /// Returns the number of registered trusted roots, including patterns.
+    #[must_use]
+    pub fn trusted_root_count(&self) -> usize {
+        self.trusted_roots.len() + self.trusted_patterns.len()
+    }
+
+    /// Returns the keys trusted for `issuer`: the exact registrations if
+    /// there are any, otherwise the key of the longest matching pattern.
+    fn keys_for_issuer(&self, issuer: &str) -> &[VerifyingKey] {
+        if let Some(keys) = self.trusted_roots.get(issuer) {
+            return keys;
+        }
+        self.trusted_patterns
+            .iter()
+            .filter(|(pattern, _)| verification::trust_root_matches(pattern, issuer))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map_or(&[], |(_, key)| std::slice::from_ref(key))
+    }
+
+    /// Runs `verify` with each key trusted for `issuer` until one succeeds,
+    /// returning its result and the key.
+    #[cfg(any(feature = "cose", feature = "jwt"))]
+    fn with_issuer_key<T>(
+        &self,
+        issuer: &str,
+        verify: impl Fn(&VerifyingKey) -> Result<T, AttestationError>,
+    ) -> Result<(T, &VerifyingKey), AttestationError> {
+        let mut last_error = AttestationError::UntrustedIssuer {
+            issuer: issuer.to_string(),
+        };
+        for key in self.keys_for_issuer(issuer) {
+            match verify(key) {
+                Ok(value) => {
+                    self.metrics.record_key(key);
+                    return Ok((value, key));
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Verifies an attestation token and returns its claims.
+    ///
+    /// If a [`VerificationCache`] is enabled, a cached result for the same
+    /// token is returned without repeating the checks below.
+    ///
+    /// This method:
+    /// 1. Checks the token against the grammar's length limits, then parses it
+    /// 2. Verifies the signature using the issuer's public key
+    /// 3. Checks expiration
+    /// 4. Validates the issuer is trusted
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if verification fails for any reason:
+    /// - `InvalidSignature` - Signature doesn't match any trusted key
+    /// - `TokenExpired` - Token has passed its expiration time
+    /// - `UntrustedIssuer` - Issuer is not in the trusted roots set
+    /// - `InvalidTokenFormat` - Token is malformed
+    /// - `LengthLimitExceeded` - Token or payload exceeds the grammar limits
+    /// - `InvalidClaims` - Claims cannot be parsed
+    /// - `InclusionProofRequired` - A transparency log is required; see
+    ///   [`verify_logged`](Self::verify_logged)
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn verify(&self, token: &str) -> Result<AttestationClaims, AttestationError> {
+        if self.transparency_log.is_some() {
+            return Err(AttestationError::InclusionProofRequired);
+        }
+        self.verify_cached(token)
+    }
+
+    /// Verifies a token like [`verify`](Self::verify) and reports which
+    /// trusted key validated it.
+    ///
+    /// Useful when a root has several keys (see
+    /// [`add_trusted_key`](Self::add_trusted_key)). The cache is bypassed,
+    /// since cached results do not record the key.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` under the same conditions as
+    /// [`verify`](Self::verify).
+    pub fn verify_detailed(&self, token: &str) -> Result<VerifiedAttestation, AttestationError> {
+        if self.transparency_log.is_some() {
+            return Err(AttestationError::InclusionProofRequired);
+        }
+        self.measured(|| {
+            let (claims, key) = self.verify_signed(token)?;
+            self.check_status(&claims)?;
+            Ok(VerifiedAttestation { claims, key })
+        })
+    }
+
+    /// Verifies a token together with evidence that it was logged.
+    ///
+    /// Checks the tree head's signature against the log key configured with
+    /// [`require_transparency_log`](Self::require_transparency_log), checks
+    /// that `proof` places the token in that tree, then runs the checks of
+    /// [`verify`](Self::verify).
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidInclusionProof` if no log is configured or the proof
+    /// does not match the tree head, `InvalidSignature` if the tree head is
+    /// not signed by the log, or any error from token verification.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn verify_logged(
+        &self,
+        token: &str,
+        proof: &InclusionProof,
+        head: &SignedTreeHead,
+    ) -> Result<AttestationClaims, AttestationError> {
+        let log_key = self.transparency_log.as_ref().ok_or_else(|| {
+            AttestationError::InvalidInclusionProof {
+                reason: "no transparency log is configured".to_string(),
+            }
+        })?;
+        head.verify(log_key)?;
+        proof.verify(token, head)?;
+        self.verify_cached(token)
+    }
+
+    /// Verifies a token and then confirms its status with an online checker.
+    ///
+    /// Runs the checks of [`verify`](Self::verify), then asks `checker`
+    /// whether the token is still good. Use this for high-value operations
+    /// that cannot tolerate the revocation delay of status lists. The online
+    /// result is never cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from [`verify`](Self::verify), or the checker's
+    /// error (typically `TokenRevokedOnline` or `StatusUnavailable`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::future::Future;
+    ///
+    /// use agent_uri::AgentUri;
+    /// use agent_uri_attestation::{
+    ///     AttestationClaims, AttestationError, Issuer, StatusChecker, Verifier,
+    /// };
+    /// use std::time::Duration;
+    ///
+    /// struct AlwaysGood;
+    ///
+    /// impl StatusChecker for AlwaysGood {
+    ///     fn check_status(
+    ///         &self,
+    ///         _token: &str,
+    ///         _claims: &AttestationClaims,
+    ///     ) -> impl Future<Output = Result<(), AttestationError>> + Send {
+    ///         async { Ok(()) }
+    ///     }
+    /// }
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let uri = AgentUri::parse(
+    ///     "agent://acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02q"
+    /// ).unwrap();
+    /// let token = issuer.issue(&uri, vec!["workflow/approval".into()]).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    ///
+    /// let claims = verifier.verify_online(&token, &AlwaysGood).await.unwrap();
+    /// assert_eq!(claims.iss, "acme.com");
+    /// # });
+    /// ```
+    pub async fn verify_online<C: StatusChecker>(
+        &self,
+        token: &str,
+        checker: &C,
+    ) -> Result<AttestationClaims, AttestationError> {
+        let claims = self.verify(token)?;
+        checker.check_status(token, &claims).await?;
+        Ok(claims)
+    }
+
+    /// Verifies a token, consulting the cache if one is enabled.
+    ///
+    /// Revocation status is checked on every call, cached or not.
+    fn verify_cached(&self, token: &str) -> Result<AttestationClaims, AttestationError> {
+        self.measured(|| {
+            let claims = match &self.cache {
+                None => self.verify_uncached(token)?,
+                Some(cache) => {
+                    let now = self.clock.now();
+                    if let Some(result) = cache.get(token, now) {
+                        self.metrics.record_cache_hit();
+                        let claims = result?;
+                        // Cached claims age while they sit in the cache
+                        Step::start("expiry").finish(self.check_lifetime(&claims, now))?;
+                        claims
+                    } else {
+                        let result = self.verify_uncached(token);
+                        cache.insert(token, &result, now);
+                        result?
+                    }
+                }
+            };
+            self.check_status(&claims)?;
+            Ok(claims)
+        })
+    }
+
+    /// Runs one token verification and records it in the metrics.
+    fn measured<T>(
+        &self,
+        verify: impl FnOnce() -> Result<T, AttestationError>,
+    ) -> Result<T, AttestationError> {
+        let started = Instant::now();
+        let result = verify();
+        self.metrics.record(started, &result);
+        result
+    }
+
+    /// Rejects `claims` if the issuer's status list marks them revoked.
+    fn check_status(&self, claims: &AttestationClaims) -> Result<(), AttestationError> {
+        let (Some(source), Some(index)) = (&self.status_source, claims.status_idx) else {
+            return Ok(());
+        };
+        Step::start("status").finish(self.check_status_list(source, claims, index))
+    }
+
+    /// Looks up `index` in the issuer's current status list.
+    fn check_status_list(
+        &self,
+        source: &SharedStatusSource,
+        claims: &AttestationClaims,
+        index: u64,
+    ) -> Result<(), AttestationError> {
+        let unavailable = |reason: String| AttestationError::StatusUnavailable {
+            issuer: claims.iss.clone(),
+            reason,
+        };
+
+        let token = source
+            .0
+            .status_list(&claims.iss)
+            .ok_or_else(|| unavailable("no status list token was returned".to_string()))?;
+
+        let keys = match self.keys_for_issuer(&claims.iss) {
+            [] => self
+                .threshold_roots
+                .get(&claims.iss)
+                .map_or(&[][..], ThresholdKeySet::keys),
+            keys => keys,
+        };
+        let json = keys
+            .iter()
+            .find_map(|key| parse_with_key(&token, key, self.clock.now()).ok())
+            .ok_or_else(|| unavailable("status list token failed verification".to_string()))?;
+
+        if json.get("iss").and_then(|v| v.as_str()) != Some(claims.iss.as_str()) {
+            return Err(unavailable("status list token has a different issuer".to_string()));
+        }
+        let claim: StatusListClaim = json
+            .get("status_list")
+            .map(|v| serde_json::from_value(v.clone()))
+            .transpose()
+            .map_err(|e| unavailable(format!("invalid status_list claim: {e}")))?
+            .ok_or_else(|| unavailable("status list token has no status_list claim".to_string()))?;
+        let list = StatusList::from_claim(&claim).map_err(|e| unavailable(e.to_string()))?;
+
+        match list.is_revoked(index) {
+            Some(false) => Ok(()),
+            Some(true) => Err(AttestationError::TokenRevoked { index }),
+            None => Err(unavailable(format!(
+                "status index {index} is beyond the list size {}",
+                list.len()
+            ))),
+        }
+    }
+
+    /// Verifies a token without consulting the cache.
+    fn verify_uncached(&self, token: &str) -> Result<AttestationClaims, AttestationError> {
+        self.verify_signed(token).map(|(claims, _)| claims)
+    }
+
+    /// Verifies a token without consulting the cache and returns the key
+    /// that validated it.
+    fn verify_signed(
+        &self,
+        token: &str,
+    ) -> Result<(AttestationClaims, VerifyingKey), AttestationError> {
+        if self.trusted_roots.is_empty() && self.trusted_patterns.is_empty() {
+            return Err(AttestationError::UntrustedIssuer {
+                issuer: "unknown".to_string(),
+            });
+        }
+
+        // Bound the work done on untrusted input before decoding it
+        Step::start("length").finish(limits::check_token(token))?;
+
+        // Try each trusted key until one works
+        let (issuer, claims, key) =
+            Step::start("signature").finish(self.extract_and_verify(token))?;
+
+        // Validate issuer is trusted (already verified by finding the key)
+        Step::start("issuer").finish(if self.keys_for_issuer(&claims.iss).is_empty() {
+            Err(AttestationError::UntrustedIssuer { issuer })
+        } else {
+            Ok(())
+        })?;
+
+        // The parser enforces exp/nbf; repeat the check as an explicit step
+        Step::start("expiry").finish(self.check_validity(&claims))?;
+
+        Ok((claims, key))
+    }
+
+    /// Verifies a token and checks it matches the expected agent URI.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The PASETO token to verify
+    /// * `expected_uri` - The agent URI that should be attested
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if:
+    /// - Token verification fails
+    /// - The token attests neither `expected_uri` as its `agent_uri` nor as
+    ///   a member of its `agent_uris` fleet
+    /// - The token's issuer doesn't match the URI's trust root, unless the
+    ///   issuer is an allowed parent domain (see
+    ///   [`allow_parent_domain_issuer`](Self::allow_parent_domain_issuer))
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(uri = %expected_uri))
+    )]
+    pub fn verify_for_uri(
+        &self,
+        token: &str,
+        expected_uri: &AgentUri,
+    ) -> Result<AttestationClaims, AttestationError> {
+        let claims = self.verify(token)?;
+        Step::start("uri").finish(self.check_uri(&claims, expected_uri))?;
+        Ok(claims)
+    }
+
+    /// Verifies a holder-bound token and the presenter's proof of possession.
+    ///
+    /// The token must carry a `cnf` claim (see
+    /// [`AttestationClaimsBuilder::holder_key`](crate::AttestationClaimsBuilder::holder_key)),
+    /// and `signature` must be the holder's
+    /// [`SigningKey::prove_possession`](crate::SigningKey::prove_possession)
+    /// over `challenge`. Challenges should be fresh and unpredictable per
+    /// request; this method does not track which ones were already used.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if:
+    /// - Token verification fails
+    /// - `ProofOfPossessionFailed` - The token has no `cnf` claim, or
+    ///   `signature` is not the holder's signature over `challenge`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use agent_uri_attestation::{AttestationClaims, Issuer, SigningKey, Verifier};
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let holder = SigningKey::generate();
+    ///
+    /// let claims = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/assistant/agent_01h455vb4pex5vsknk084sn02q")
+    ///     .add_capability("read")
+    ///     .issuer("acme.com")
+    ///     .holder_key(holder.verifying_key())
+    ///     .build()
+    ///     .unwrap();
+    /// let token = issuer.issue_claims(&claims).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    ///
+    /// let proof = holder.prove_possession(b"challenge-42");
+    /// assert!(verifier.verify_with_pop(&token, b"challenge-42", &proof).is_ok());
+    /// assert!(verifier.verify_with_pop(&token, b"challenge-43", &proof).is_err());
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn verify_with_pop(
+        &self,
+        token: &str,
+        challenge: &[u8],
+        signature: &[u8],
+    ) -> Result<AttestationClaims, AttestationError> {
+        let claims = self.verify(token)?;
+        Step::start("pop").finish(match &claims.cnf {
+            Some(holder) => crate::pop::verify(holder, challenge, signature),
+            None => Err(AttestationError::ProofOfPossessionFailed {
+                reason: "token is not bound to a holder key".to_string(),
+            }),
+        })?;
+        Ok(claims)
+    }
+
+    /// Generates a random challenge nonce for [`verify_with_nonce`](Self::verify_with_nonce).
+    ///
+    /// The nonce is 128 random bits as unpadded base64url, which fits the
+    /// grammar's `nonce-claim`.
+    #[must_use]
+    pub fn generate_nonce() -> String {
+        let mut bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    /// Verifies a token issued for the challenge `nonce`.
+    ///
+    /// Use this for single-use flows such as registration handshakes: the
+    /// verifier hands out a fresh nonce (see
+    /// [`generate_nonce`](Self::generate_nonce)), the issuer embeds it with
+    /// [`AttestationClaimsBuilder::nonce`](crate::AttestationClaimsBuilder::nonce),
+    /// and the verifier retires the nonce once this call succeeds. The
+    /// verifier itself keeps no record of consumed nonces.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if:
+    /// - Token verification fails
+    /// - `NonceMismatch` - The token's `nonce` claim is absent or differs
+    ///   from `nonce`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use agent_uri_attestation::{AttestationClaims, Issuer, Verifier};
+    /// use std::time::Duration;
+    ///
+    /// let issuer = Issuer::generate("acme.com", Duration::from_secs(60));
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    ///
+    /// let challenge = Verifier::generate_nonce();
+    /// let claims = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/registrar/agent_01h455vb4pex5vsknk084sn02q")
+    ///     .add_capability("dht/register")
+    ///     .issuer("acme.com")
+    ///     .nonce(challenge.clone())
+    ///     .build()
+    ///     .unwrap();
+    /// let token = issuer.issue_claims(&claims).unwrap();
+    ///
+    /// assert!(verifier.verify_with_nonce(&token, &challenge).is_ok());
+    /// assert!(verifier.verify_with_nonce(&token, &Verifier::generate_nonce()).is_err());
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn verify_with_nonce(
+        &self,
+        token: &str,
+        nonce: &str,
+    ) -> Result<AttestationClaims, AttestationError> {
+        let claims = self.verify(token)?;
+        Step::start("nonce").finish(check_nonce(&claims, nonce))?;
+        Ok(claims)
+    }
+
+    /// Verifies a token, checks URI match, AND validates capability coverage.
+    ///
+    /// This is the most comprehensive verification method, performing:
+    /// 1. Signature verification using trusted keys
+    /// 2. Expiration check
+    /// 3. Issuer validation against trusted roots
+    /// 4. URI match verification
+    /// 5. Trust root consistency check
+    /// 6. Capability coverage verification
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The PASETO token to verify
+    /// * `uri` - The agent URI that should be attested
+    /// * `required_capability` - The capability path required for the operation
+    ///
+    /// # Returns
+    ///
+    /// The verified `AttestationClaims` if all checks pass
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if any verification step fails:
+    /// - `InvalidSignature` - Signature doesn't match any trusted key
+    /// - `TokenExpired` - Token has passed its expiration time
+    /// - `UntrustedIssuer` - Issuer is not in the trusted roots set
+    /// - `UriMismatch` - Token's `agent_uri` doesn't match expected URI
+    /// - `TrustRootMismatch` - Trust root in token doesn't match URI's trust root
+    /// - `InsufficientCapabilities` - Token capabilities don't cover required path
+    /// - `CapabilityDenied` - The required path is explicitly denied by the token
+    /// - `CapabilityConstraintViolated` - The covering capability has expired
+    ///   or is restricted to specific resources; use
+    ///   [`AttestationClaims::check_capability`] to supply request context
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use agent_uri::AgentUri;
+    /// use agent_uri::CapabilityPath;
+    /// use agent_uri_attestation::{Issuer, Verifier, SigningKey};
+    /// use std::time::Duration;
+    ///
+    /// let signing_key = SigningKey::generate();
+    /// let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(3600));
+    ///
+    /// let uri = AgentUri::parse(
+    ///     "agent://acme.com/workflow/approval/agent_01h455vb4pex5vsknk084sn02q"
+    /// ).unwrap();
+    /// let token = issuer.issue(&uri, vec!["workflow".into()]).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+    ///
+    /// // "workflow" capability covers "workflow/approval"
+    /// let required = CapabilityPath::parse("workflow/approval").unwrap();
+    /// let claims = verifier.verify_for_capability(&token, &uri, &required).unwrap();
+    ///
+    /// assert_eq!(claims.iss, "acme.com");
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(capability = %required_capability))
+    )]
+    pub fn verify_for_capability(
+        &self,
+        token: &str,
+        uri: &AgentUri,
+        required_capability: &CapabilityPath,
+    ) -> Result<AttestationClaims, AttestationError> {
+        // First verify the token and URI match
+        let claims = self.verify_for_uri(token, uri)?;
+
+        // Then check capability coverage, including any per-capability
+        // constraints that can be evaluated without request context
+        let request = CapabilityRequest::new(required_capability.clone(), self.clock.now());
+        let aliased = self.capability_aliases.expand_claims(&claims);
+        let coverage = self
+            .capability_notation
+            .normalize_claims(&aliased)
+            .and_then(|normalized| normalized.check_capability(&request));
+        Step::start("capabilities").finish(coverage)?;
+
+        Ok(claims)
+    }
+
+    /// Verifies a token and applies a [`VerificationPolicy`] to its claims.
+    ///
+    /// Runs the same checks as [`verify`](Self::verify), then evaluates the
+    /// policy against the current time.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if verification fails or the claims
+    /// violate the policy. See [`VerificationPolicy::check`] for the
+    /// policy-specific errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use agent_uri::AgentUri;
+    /// use agent_uri_attestation::{
+    ///     AttestationClaims, Issuer, SigningKey, VerificationPolicy, Verifier,
+    /// };
+    /// use std::time::Duration;
+    ///
+    /// let signing_key = SigningKey::generate();
+    /// let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(3600));
+    ///
+    /// let claims = AttestationClaims::builder()
+    ///     .agent_uri("agent://acme.com/workflow/approval/agent_01h455vb4pex5vsknk084sn02q")
+    ///     .issuer("acme.com")
+    ///     .audience("api.acme.com")
+    ///     .ttl(Duration::from_secs(3600))
+    ///     .build()
+    ///     .unwrap();
+    /// let token = issuer.issue_claims(&claims).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+    ///
+    /// let policy = VerificationPolicy::builder()
+    ///     .require_audience("api.acme.com")
+    ///     .max_ttl(Duration::from_secs(3600))
+    ///     .build();
+    ///
+    /// assert!(verifier.verify_with_policy(&token, &policy).is_ok());
+    /// ```
+    pub fn verify_with_policy(
+        &self,
+        token: &str,
+        policy: &VerificationPolicy,
+    ) -> Result<AttestationClaims, AttestationError> {
+        let claims = self.verify(token)?;
+        policy.check(&claims, self.clock.now())?;
+        Ok(claims)
+    }
+
+    /// Verifies a delegation chain and returns the effective leaf claims.
+    ///
+    /// `tokens` is ordered root first, leaf last. Every token must verify on
+    /// its own against the trusted roots. In addition, each token after the
+    /// first must be a valid delegation of the one before it (see
+    /// [`check_delegation`](crate::check_delegation)): issued by the trust
+    /// root of its parent's subject, and claiming only capabilities that its
+    /// parent covers.
+    ///
+    /// The returned claims are the leaf's, with `exp` clamped to the earliest
+    /// expiration in the chain, since a delegation is only valid while every
+    /// link is.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if:
+    /// - `InvalidChain` - The chain is empty
+    /// - Any token fails [`verify`](Self::verify)
+    /// - `DelegationMismatch` - A token was not issued by its parent's subject
+    /// - `CapabilityEscalation` - A token widens its parent's capabilities
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use agent_uri::AgentUri;
+    /// use agent_uri_attestation::{Issuer, SigningKey, Verifier};
+    /// use std::time::Duration;
+    ///
+    /// let root = Issuer::generate("acme.com", Duration::from_secs(3600));
+    /// let regional = Issuer::generate("eu.acme.com", Duration::from_secs(600));
+    ///
+    /// let intermediate = AgentUri::parse(
+    ///     "agent://eu.acme.com/workflow/broker_01h455vb4pex5vsknk084sn02q"
+    /// ).unwrap();
+    /// let leaf = AgentUri::parse(
+    ///     "agent://eu.acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02q"
+    /// ).unwrap();
+    ///
+    /// let root_token = root.issue(&intermediate, vec!["workflow".into()]).unwrap();
+    /// let leaf_token = regional.issue(&leaf, vec!["workflow/approval".into()]).unwrap();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// verifier.add_trusted_root("acme.com", root.verifying_key());
+    /// verifier.add_trusted_root("eu.acme.com", regional.verifying_key());
+    ///
+    /// let claims = verifier.verify_chain(&[&root_token, &leaf_token]).unwrap();
+    /// assert_eq!(claims.agent_uri, leaf.to_string());
+    /// ```
+    pub fn verify_chain(&self, tokens: &[&str]) -> Result<AttestationClaims, AttestationError> {
+        let (root, rest) = tokens
+            .split_first()
+            .ok_or_else(|| AttestationError::InvalidChain {
+                reason: "chain contains no tokens".to_string(),
+            })?;
+
+        let mut parent = self.verify(root)?;
+        let mut exp = parent.exp;
+
+        for (offset, token) in rest.iter().enumerate() {
+            let child = self.verify(token)?;
+            verification::check_delegation(offset + 1, &parent, &child)?;
+            exp = verification::delegated_expiry(exp, child.exp);
+            parent = child;
+        }
+
+        parent.exp = exp;
+        Ok(parent)
+    }
+
+    /// Verifies a delegation chain whose leaf token answers a challenge.
+    ///
+    /// Combines [`verify_chain`](Self::verify_chain) with the check of
+    /// [`verify_with_nonce`](Self::verify_with_nonce): the leaf is the token
+    /// presented for the handshake, so its `nonce` claim must equal `nonce`.
+    /// Earlier links are long-lived grants and need not carry one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if:
+    /// - The chain fails [`verify_chain`](Self::verify_chain)
+    /// - `NonceMismatch` - The leaf's `nonce` claim is absent or differs
+    ///   from `nonce`
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn verify_chain_with_nonce(
+        &self,
+        tokens: &[&str],
+        nonce: &str,
+    ) -> Result<AttestationClaims, AttestationError> {
+        let claims = self.verify_chain(tokens)?;
+        Step::start("nonce").finish(check_nonce(&claims, nonce))?;
+        Ok(claims)
+    }
+
+    /// Verifies a co-signed attestation and returns the primary claims.
+    ///
+    /// Every token in the bundle must verify against the trusted roots and
+    /// attest the same agent URI, capabilities, audience, revocation index,
+    /// holder binding and nonce as the primary token. Each issuer in
+    /// `required_issuers` must be among the signers; pass an empty slice to
+    /// only require that all included signatures are valid.
+    ///
+    /// The returned claims are the primary token's, with `exp` clamped to
+    /// the earliest expiration among all signatures.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if:
+    /// - `InvalidTokenFormat` - The bundle cannot be parsed
+    /// - Any token fails [`verify`](Self::verify)
+    /// - `CoSignatureMismatch` - A co-signer attests different subject claims
+    /// - `MissingCoSignature` - A required issuer did not sign
+    ///
+    /// # Examples
+    ///
+    /// See [`CoSignedAttestation`].
+    pub fn verify_cosigned(
+        &self,
+        cosigned: &str,
+        required_issuers: &[&str],
+    ) -> Result<AttestationClaims, AttestationError> {
+        let bundle: CoSignedAttestation = cosigned.parse()?;
+        let mut claims = self.verify(bundle.primary())?;
+        let mut signers = vec![claims.iss.clone()];
+
+        for token in &bundle.tokens()[1..] {
+            let cosigned_claims = self.verify(token)?;
+            if !same_attested_claims(&cosigned_claims, &claims) {
+                return Err(AttestationError::CoSignatureMismatch {
+                    issuer: cosigned_claims.iss,
+                });
+            }
+            claims.exp = claims.exp.min(cosigned_claims.exp);
+            signers.push(cosigned_claims.iss);
+        }
+
+        if let Some(missing) = required_issuers
+            .iter()
+            .find(|required| !signers.iter().any(|signer| signer == *required))
+        {
+            return Err(AttestationError::MissingCoSignature {
+                issuer: (*missing).to_string(),
+            });
+        }
+
+        Ok(claims)
+    }
+
+    /// Verifies a threshold-signed attestation and returns its claims.
+    ///
+    /// `bundle` is a [`CoSignedAttestation`] in which every token carries the
+    /// same trust root as issuer and is signed by a different key of that
+    /// trust root's [`ThresholdKeySet`]. Tokens that fail to verify are
+    /// ignored; the attestation is accepted once at least `threshold`
+    /// distinct keys have produced valid signatures over the same claims.
+    ///
+    /// The returned claims are those of the first valid token, with `exp`
+    /// clamped to the earliest expiration among the valid tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if:
+    /// - `InvalidTokenFormat` - The bundle cannot be parsed
+    /// - No token verifies against any threshold key set
+    /// - `CoSignatureMismatch` - Valid tokens disagree on their claims
+    /// - `ThresholdNotMet` - Too few distinct keys signed
+    /// - `InclusionProofRequired` - A transparency log is required
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use agent_uri::AgentUri;
+    /// use agent_uri_attestation::{
+    ///     CoSignedAttestation, Issuer, SigningKey, ThresholdKeySet, Verifier,
+    /// };
+    /// use std::time::Duration;
+    ///
+    /// let keys: Vec<_> = (0..3).map(|_| SigningKey::generate()).collect();
+    /// let issuers: Vec<_> = keys
+    ///     .iter()
+    ///     .map(|k| Issuer::new("acme.com", k.clone(), Duration::from_secs(3600)))
+    ///     .collect();
+    ///
+    /// let mut verifier = Verifier::new();
+    /// let key_set = ThresholdKeySet::new(keys.iter().map(SigningKey::verifying_key).collect(), 2)
+    ///     .unwrap();
+    /// verifier.add_threshold_root("acme.com", key_set);
+    ///
+    /// let uri = AgentUri::parse("agent://acme.com/root/ca_01h455vb4pex5vsknk084sn02q").unwrap();
+    /// let claims = agent_uri_attestation::AttestationClaims::builder()
+    ///     .agent_uri(uri.to_string())
+    ///     .issuer("acme.com")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let bundle = CoSignedAttestation::issue(&claims, &[&issuers[0], &issuers[2]]).unwrap();
+    /// assert!(verifier.verify_threshold(&bundle.to_string()).is_ok());
+    ///
+    /// let single = CoSignedAttestation::issue(&claims, &[&issuers[1]]).unwrap();
+    /// assert!(verifier.verify_threshold(&single.to_string()).is_err());
+    /// ```
+    pub fn verify_threshold(&self, bundle: &str) -> Result<AttestationClaims, AttestationError> {
+        if self.transparency_log.is_some() {
+            return Err(AttestationError::InclusionProofRequired);
+        }
+        self.measured(|| {
+            let bundle: CoSignedAttestation = bundle.parse()?;
+
+            // No valid bundle holds more signatures than its key set has keys
+            let max_keys = self.threshold_roots.values().map(|set| set.keys().len()).max();
+            if let Some(max_keys) = max_keys
+                && bundle.tokens().len() > max_keys
+            {
+                let count = bundle.tokens().len();
+                return Err(AttestationError::InvalidTokenFormat {
+                    reason: format!(
+                        "bundle has {count} signatures; key sets have at most {max_keys} keys"
+                    ),
+                });
+            }
+
+            let mut accepted: Option<(&str, &ThresholdKeySet, AttestationClaims)> = None;
+            let mut signers: Vec<usize> = Vec::new();
+            let mut last_error = None;
+
+            for (position, token) in bundle.tokens().iter().enumerate() {
+                if bundle.tokens()[..position].contains(token) {
+                    continue;
+                }
+                let accepted_root = accepted.as_ref().map(|(root, _, _)| *root);
+                let found = self.find_threshold_signer(token, accepted_root, &signers);
+                let (root, key_set, key_index, claims) = match found {
+                    Ok(found) => found,
+                    Err(e) => {
+                        last_error = Some(e);
+                        continue;
+                    }
+                };
+
+                match &mut accepted {
+                    None => accepted = Some((root, key_set, claims)),
+                    Some((accepted_root, _, accepted_claims)) => {
+                        if root != *accepted_root
+                            || !same_attested_claims(&claims, accepted_claims)
+                        {
+                            return Err(AttestationError::CoSignatureMismatch {
+                                issuer: claims.iss,
+                            });
+                        }
+                        accepted_claims.exp = accepted_claims.exp.min(claims.exp);
+                    }
+                }
+
+                signers.push(key_index);
+            }
+
+            let Some((root, key_set, claims)) = accepted else {
+                return Err(last_error.unwrap_or(AttestationError::UntrustedIssuer {
+                    issuer: "unknown".to_string(),
+                }));
+            };
+
+            if signers.len() < key_set.threshold() {
+                return Err(AttestationError::ThresholdNotMet {
+                    trust_root: root.to_string(),
+                    required: key_set.threshold(),
+                    valid: signers.len(),
+                });
+            }
+
+            Step::start("expiry").finish(self.check_lifetime(&claims, self.clock.now()))?;
+            self.check_status(&claims)?;
+            Ok(claims)
+        })
+    }
+
+    /// Finds the threshold key that signed `token`.
+    ///
+    /// Once a root has been accepted only its key set is searched, and the
+    /// keys in `counted` are skipped: a token they signed adds no signer.
+    /// Returns the trust root, its key set, the index of the signing key
+    /// within the set, and the verified claims.
+    fn find_threshold_signer(
+        &self,
+        token: &str,
+        accepted_root: Option<&str>,
+        counted: &[usize],
+    ) -> Result<(&str, &ThresholdKeySet, usize, AttestationClaims), AttestationError> {
+        let mut last_error = None;
+
+        let roots = self
+            .threshold_roots
+            .iter()
+            .filter(|(root, _)| accepted_root.is_none_or(|accepted| accepted == root.as_str()));
+        for (trust_root, key_set) in roots {
+            let keys = key_set.keys().iter().enumerate();
+            for (index, verifying_key) in keys.filter(|(index, _)| !counted.contains(index)) {
+                match try_verify_with_key(token, verifying_key, self.clock.now()) {
+                    Ok(claims) if claims.iss == *trust_root => {
+                        return Ok((trust_root, key_set, index, claims));
+                    }
+                    Ok(claims) => {
+                        last_error = Some(AttestationError::TrustRootMismatch {
+                            token_root: claims.iss,
+                            expected_root: trust_root.clone(),
+                        });
+                    }
+                    Err(e) => last_error = Some(e),
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or(AttestationError::UntrustedIssuer {
+            issuer: "unknown".to_string(),
+        }))
+    }
+
+    /// Verifies a CWT token (CBOR/`COSE_Sign1`) and returns its claims.
+    ///
+    /// Applies the same checks as [`verify`](Self::verify): the signature
+    /// must match the key registered for the token's issuer, and the token
+    /// must be within its `nbf`/`exp` validity window. Threshold roots are
+    /// not consulted. The result is not cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if:
+    /// - `InvalidTokenFormat` - The bytes are not an `EdDSA` `COSE_Sign1`
+    /// - `LengthLimitExceeded` - The token exceeds the grammar limits
+    /// - `InvalidClaims` - The CWT claims set is incomplete
+    /// - `UntrustedIssuer` - No key is registered for the issuer
+    /// - `InvalidSignature` - The signature does not verify
+    /// - `TokenNotYetValid` / `TokenExpired` - Outside the validity window
+    /// - `InclusionProofRequired` - A transparency log is required, which
+    ///   CWT tokens cannot satisfy
+    #[cfg(feature = "cose")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn verify_cose(&self, token: &[u8]) -> Result<AttestationClaims, AttestationError> {
+        if self.transparency_log.is_some() {
+            return Err(AttestationError::InclusionProofRequired);
+        }
+        self.measured(|| {
+            // Bound the work done on untrusted input before decoding it
+            Step::start("length").finish(limits::check_cwt(token))?;
+            let (sign1, claims) = crate::cose::decode(token)?;
+
+            let signature = self.with_issuer_key(&claims.iss, |key| {
+                crate::cose::verify_signature(&sign1, key)
+            });
+            Step::start("signature").finish(signature)?;
+            Step::start("expiry").finish(self.check_validity(&claims))?;
+            self.check_status(&claims)?;
+
+            Ok(claims)
+        })
+    }
+
+    /// Verifies a CWT token and applies a [`VerificationPolicy`] to its claims.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if [`verify_cose`](Self::verify_cose)
+    /// fails or the claims violate the policy.
+    #[cfg(feature = "cose")]
+    pub fn verify_cose_with_policy(
+        &self,
+        token: &[u8],
+        policy: &VerificationPolicy,
+    ) -> Result<AttestationClaims, AttestationError> {
+        let claims = self.verify_cose(token)?;
+        policy.check(&claims, self.clock.now())?;
+        Ok(claims)
+    }
+
+    /// Verifies an `EdDSA` JWT produced by
+    /// [`AttestationClaims::to_jwt`] and returns its claims.
+    ///
+    /// Applies the same checks as [`verify`](Self::verify): the `alg` header
+    /// must be `EdDSA`, the signature must match the key registered for the
+    /// token's issuer, and the token must be within its `nbf`/`exp`
+    /// validity window. Threshold roots are not consulted. The result is not
+    /// cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if:
+    /// - `InvalidTokenFormat` - The token is not a compact `EdDSA` JWS
+    /// - `LengthLimitExceeded` - The token or payload exceeds the grammar
+    ///   limits
+    /// - `InvalidClaims` - The claims set is incomplete
+    /// - `UntrustedIssuer` - No key is registered for the issuer
+    /// - `InvalidSignature` - The signature does not verify
+    /// - `TokenNotYetValid` / `TokenExpired` - Outside the validity window
+    /// - `InclusionProofRequired` - A transparency log is required, which
+    ///   JWTs cannot satisfy
+    #[cfg(feature = "jwt")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn verify_jwt(&self, token: &str) -> Result<AttestationClaims, AttestationError> {
+        if self.transparency_log.is_some() {
+            return Err(AttestationError::InclusionProofRequired);
+        }
+        self.measured(|| {
+            // Bound the work done on untrusted input before decoding it
+            Step::start("length").finish(limits::check_jwt(token))?;
+            let jwt = crate::jwt::decode(token)?;
+
+            let signature = self.with_issuer_key(&jwt.claims().iss, |key| jwt.verify(key));
+            Step::start("signature").finish(signature)?;
+            let claims = jwt.into_claims();
+            Step::start("expiry").finish(self.check_validity(&claims))?;
+            self.check_status(&claims)?;
+
+            Ok(claims)
+        })
+    }
+
+    /// Checks that `claims` are within their `nbf`/`exp` validity window
+    /// and the verifier's lifetime limits.
+    fn check_validity(&self, claims: &AttestationClaims) -> Result<(), AttestationError> {
+        let now = self.clock.now();
+        if let Some(nbf) = claims.nbf {
+            verification::check_not_before(nbf, now)?;
+        }
+        verification::check_expiration(claims.exp, now)?;
+        self.check_lifetime(claims, now)
+    }
+
+    /// Checks `claims` against the configured maximum lifetime and age.
+    fn check_lifetime(
+        &self,
+        claims: &AttestationClaims,
+        now: DateTime<Utc>,
+    ) -> Result<(), AttestationError> {
+        if let Some(max_ttl) = self.max_ttl {
+            verification::check_max_ttl(claims.iat, claims.exp, max_ttl)?;
+        }
+        if let Some(max_age) = self.max_age {
+            verification::check_freshness(claims.iat, now, max_age)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that `claims` attest `expected_uri` and were issued by its
+    /// trust root or an allowed parent domain.
+    fn check_uri(
+        &self,
+        claims: &AttestationClaims,
+        expected_uri: &AgentUri,
+    ) -> Result<(), AttestationError> {
+        let expected_str = expected_uri.to_string();
+        if !claims.attests(&expected_str) {
+            return Err(AttestationError::UriMismatch {
+                token_uri: claims.agent_uri.clone(),
+                expected_uri: expected_str,
+            });
+        }
+
+        let expected_root = expected_uri.trust_root().as_str();
+        if self.parent_domain_issuers.contains(&claims.iss)
+            && verification::trust_root_matches(&format!("*.{}", claims.iss), expected_root)
+        {
+            return Ok(());
+        }
+        verification::validate_issuer(expected_root, &claims.iss)
+    }
+
+    /// Internal method to extract issuer and verify signature.
+    fn extract_and_verify(
+        &self,
+        token: &str,
+    ) -> Result<(String, AttestationClaims, VerifyingKey), AttestationError> {
+        // Try each trusted key until one works
+        let mut last_error = None;
+
+        let candidates = self
+            .trusted_roots
+            .iter()
+            .flat_map(|(root, keys)| keys.iter().map(move |key| (root, key)))
+            .chain(&self.trusted_patterns);
+        for (trust_root, verifying_key) in candidates {
+            match try_verify_with_key(token, verifying_key, self.clock.now()) {
+                Ok(claims) => {
+                    // Verify the issuer is trusted with the key we used; exact
+                    // roots override patterns for the same issuer
+                    if self.keys_for_issuer(&claims.iss).contains(verifying_key) {
+                        self.metrics.record_key(verifying_key);
+                        return Ok((claims.iss.clone(), claims, verifying_key.clone()));
+                    }
+                    // Issuer mismatch - this key signed it but claims different issuer
+                    last_error = Some(AttestationError::TrustRootMismatch {
+                        token_root: claims.iss.clone(),
+                        expected_root: trust_root.clone(),
+                    });
+                }
+                Err(e) => {
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or(AttestationError::UntrustedIssuer {
+            issuer: "unknown".to_string(),
+        }))
+    }
+}
+
+/// The result of [`Verifier::verify_detailed`]: verified claims and the key
+/// that validated them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedAttestation {
+    /// The verified claims
+    pub claims: AttestationClaims,
+    /// The trusted key whose signature matched
+    pub key: VerifyingKey,
+}
+
+impl VerifiedAttestation {
+    /// Returns the fingerprint of the matching key, the identifier used in
+    /// [`VerifierMetrics::key_usage`].
+    #[must_use]
+    pub fn key_id(&self) -> String {
+        self.key.fingerprint()
+    }
+}
+
+/// Admits DHT registrations whose attestation token verifies for the
+/// registered agent URI and covers its capability path, see
+/// [`Verifier::verify_for_capability`].
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_attestation::{Issuer, Verifier};
+/// use agent_uri_dht::{Dht, DhtError, Endpoint, Registration, SimulatedDht};
+/// use std::time::Duration;
+///
+/// let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+/// let mut verifier = Verifier::new();
+/// verifier.add_trusted_root("acme.com", issuer.verifying_key());
+/// let dht = SimulatedDht::with_defaults().with_validator(verifier);
+///
+/// let uri = AgentUri::parse(
+///     "agent://acme.com/workflow/approval/agent_01h455vb4pex5vsknk084sn02q"
+/// ).unwrap();
+/// let endpoints = vec![Endpoint::https("agent.acme.com:443")];
+///
+/// let narrow = issuer.issue(&uri, vec!["workflow/review".into()]).unwrap();
+/// let poisoned = Registration::new(uri.clone(), endpoints.clone()).with_attestation(narrow);
+/// assert!(matches!(dht.register(poisoned), Err(DhtError::CapabilityMismatch { .. })));
+///
+/// let registration = issuer
+///     .issue_registration(&uri, vec!["workflow".into()], endpoints)
+///     .unwrap();
+/// dht.register(registration).unwrap();
+/// ```
+#[cfg(feature = "dht")]
+impl agent_uri_dht::RegistrationValidator for Verifier {
+    fn validate(
+        &self,
+        registration: &agent_uri_dht::Registration,
+    ) -> Result<(), agent_uri_dht::DhtError> {
+        self.attested_until(registration).map(|_| ())
+    }
+
+    /// Returns the token's expiry, so verified registrations can be ranked
+    /// by remaining lifetime.
+    fn attested_until(
+        &self,
+        registration: &agent_uri_dht::Registration,
+    ) -> Result<Option<std::time::SystemTime>, agent_uri_dht::DhtError> {
+        let uri = registration.agent_uri();
+        let token = registration.attestation().ok_or_else(|| {
+            agent_uri_dht::DhtError::invalid_attestation(uri.as_str(), "missing attestation token")
+        })?;
+        match self.verify_for_capability(token, uri, uri.capability_path()) {
+            Ok(claims) => Ok(Some(claims.exp.into())),
+            Err(AttestationError::InsufficientCapabilities { required, attested }) => Err(
+                agent_uri_dht::DhtError::capability_mismatch(required, attested.join(", ")),
+            ),
+            Err(e) => Err(agent_uri_dht::DhtError::invalid_attestation(
+                uri.as_str(),
+                e.to_string(),
+            )),
+        }
+    }
+}
+
+/// Try to verify a token with a specific key.
+fn try_verify_with_key(
+    token: &str,
+    verifying_key: &VerifyingKey,
+    now: DateTime<Utc>,
+) -> Result<AttestationClaims, AttestationError> {
+    extract_claims(&parse_with_key(token, verifying_key, now)?)
+}
+
+/// Verifies a PASETO token's signature and time claims at `now` with a
+/// specific key and returns its raw claims.
+pub(crate) fn parse_with_key(
+    token: &str,
+    verifying_key: &VerifyingKey,
+    now: DateTime<Utc>,
+) -> Result<serde_json::Value, AttestationError> {
+    let message = agent_uri_verify_core::open(token, &verifying_key.to_bytes())?;
+    let json: serde_json::Value =
+        serde_json::from_slice(&message).map_err(|e| AttestationError::InvalidClaims {
+            reason: format!("claims are not valid JSON: {e}"),
+        })?;
+
+    if let Some(nbf) = time_claim(&json, "nbf")? {
+        verification::check_not_before(nbf, now)?;
+    }
+    let exp = time_claim(&json, "exp")?.ok_or_else(|| AttestationError::InvalidClaims {
+        reason: "missing exp claim".to_string(),
+    })?;
+    verification::check_expiration(exp, now)?;
+
+    Ok(json)
+}
+
+/// Returns true if two signatures of a co-signed or threshold attestation
+/// attest the same claims: subject, capabilities, audience, revocation
+/// index, holder binding and nonce. Issuer and validity period may differ.
+fn same_attested_claims(a: &AttestationClaims, b: &AttestationClaims) -> bool {
+    a.agent_uri == b.agent_uri
+        && a.agent_uris == b.agent_uris
+        && a.capabilities == b.capabilities
+        && a.capability_constraints == b.capability_constraints
+        && a.denied_capabilities == b.denied_capabilities
+        && a.aud == b.aud
+        && a.status_idx == b.status_idx
+        && a.cnf == b.cnf
+        && a.nonce == b.nonce
+}
+
+/// Checks that `claims` carry the verifier's challenge `nonce`.
+fn check_nonce(claims: &AttestationClaims, nonce: &str) -> Result<(), AttestationError> {
+    if claims.nonce.as_deref() == Some(nonce) {
+        Ok(())
+    } else {
+        Err(AttestationError::NonceMismatch {
+            expected: nonce.to_string(),
+            actual: claims.nonce.clone(),
+        })
+    }
+}
+
+/// Parses the optional RFC 3339 timestamp claim `name`.
+fn time_claim(
+    json: &serde_json::Value,
+    name: &str,
+) -> Result<Option<DateTime<Utc>>, AttestationError> {
+    let Some(value) = json.get(name) else {
+        return Ok(None);
+    };
+    let parsed = value
+        .as_str()
+        .ok_or_else(|| format!("{name} claim must be a string"))
+        .and_then(|s| DateTime::parse_from_rfc3339(s).map_err(|e| e.to_string()));
+    parsed
+        .map(|dt| Some(dt.with_timezone(&Utc)))
+        .map_err(|e| AttestationError::InvalidClaims {
+            reason: format!("invalid {name} format: {e}"),
+        })
+}
+
+/// Extract `AttestationClaims` from parsed JSON value.
+pub(crate) fn extract_claims(json: &serde_json::Value) -> Result<AttestationClaims, AttestationError> {
+    let agent_uri = json["agent_uri"]
         .as_str()
         .ok_or_else(|| AttestationError::InvalidClaims {
             reason: "missing agent_uri claim".to_string(),
         })?
         .to_string();
 
-    let capabilities: Vec<String> = json
-        .get("capabilities")
-        .and_then(|v| serde_json::from_value(v.clone()).ok())
-        .unwrap_or_default();
+    // A malformed fleet must not be read as empty either
+    let agent_uris: Vec<String> = json
+        .get("agent_uris")
+        .map(|v| serde_json::from_value(v.clone()))
+        .transpose()
+        .map_err(|e| AttestationError::InvalidClaims {
+            reason: format!("invalid agent_uris claim: {e}"),
+        })?
+        .unwrap_or_default();
+    limits::check_agent_uris(&agent_uris)?;
+
+    let (capabilities, capability_constraints) = json
+        .get("capabilities")
+        .and_then(|v| constraints::from_wire(v).ok())
+        .unwrap_or_default();
+
+    // Unlike grants, a malformed deny-list must not be read as empty
+    let denied_capabilities: Vec<String> = json
+        .get("denied_capabilities")
+        .map(|v| serde_json::from_value(v.clone()))
+        .transpose()
+        .map_err(|e| AttestationError::InvalidClaims {
+            reason: format!("invalid denied_capabilities claim: {e}"),
+        })?
+        .unwrap_or_default();
+
+    let iss = json["iss"]
+        .as_str()
+        .ok_or_else(|| AttestationError::InvalidClaims {
+            reason: "missing iss claim".to_string(),
+        })?
+        .to_string();
+
+    let iat = time_claim(json, "iat")?.ok_or_else(|| AttestationError::InvalidClaims {
+        reason: "missing iat claim".to_string(),
+    })?;
+    let exp = time_claim(json, "exp")?.ok_or_else(|| AttestationError::InvalidClaims {
+        reason: "missing exp claim".to_string(),
+    })?;
+    let nbf = time_claim(json, "nbf")?;
+    let orig_iat = time_claim(json, "orig_iat")?;
+
+    let aud = json.get("aud").and_then(|v| v.as_str()).map(String::from);
+
+    // A malformed index must not silently skip the status check
+    let status_idx = json
+        .get("status_idx")
+        .map(|v| {
+            v.as_u64().ok_or_else(|| AttestationError::InvalidClaims {
+                reason: "status_idx claim must be an unsigned integer".to_string(),
+            })
+        })
+        .transpose()?;
+
+    let ver = json
+        .get("ver")
+        .map(|v| {
+            v.as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| AttestationError::InvalidClaims {
+                    reason: "ver claim must be an unsigned integer".to_string(),
+                })
+        })
+        .transpose()?
+        .unwrap_or(LEGACY_CLAIMS_VERSION);
+
+    let cnf = json.get("cnf").map(crate::pop::from_wire).transpose()?;
+
+    let nonce = json
+        .get("nonce")
+        .map(|v| {
+            v.as_str().map(String::from).ok_or_else(|| AttestationError::InvalidClaims {
+                reason: "nonce claim must be a string".to_string(),
+            })
+        })
+        .transpose()?;
+
+    AttestationClaims {
+        agent_uri,
+        agent_uris,
+        capabilities,
+        capability_constraints,
+        denied_capabilities,
+        iss,
+        iat,
+        exp,
+        nbf,
+        aud,
+        status_idx,
+        ver,
+        cnf,
+        nonce,
+        orig_iat,
+    }
+    .migrate()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::issuer::Issuer;
+    use crate::bundle::TrustBundle;
+    use crate::keys::SigningKey;
+
+    fn test_uri() -> AgentUri {
+        AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap()
+    }
+
+    #[cfg(feature = "dht")]
+    #[test]
+    fn registration_validator_rejects_unverifiable_tokens() {
+        use agent_uri_dht::{DhtError, Endpoint, Registration, RegistrationValidator};
+
+        let trusted = Issuer::generate("acme.com", Duration::from_mins(10));
+        let impostor = Issuer::generate("acme.com", Duration::from_mins(10));
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", trusted.verifying_key());
+        let registration =
+            Registration::new(test_uri(), vec![Endpoint::https("agent.acme.com:443")]);
+
+        let missing = verifier.validate(&registration).unwrap_err();
+        assert!(matches!(missing, DhtError::InvalidAttestation { .. }));
+
+        let forged = impostor.issue(&test_uri(), vec!["test".into()]).unwrap();
+        let forged = verifier.validate(&registration.clone().with_attestation(forged));
+        assert!(matches!(forged, Err(DhtError::InvalidAttestation { .. })));
+
+        let token = trusted.issue(&test_uri(), vec!["test".into()]).unwrap();
+        assert!(verifier.validate(&registration.with_attestation(token)).is_ok());
+    }
+
+    #[cfg(feature = "dht")]
+    #[test]
+    fn registration_validator_ranks_by_token_lifetime() {
+        use agent_uri_dht::{AttestationRanking, Endpoint, Registration, RegistrationValidator};
+
+        let signing_key = SigningKey::generate();
+        let short = Issuer::new("acme.com", signing_key.clone(), Duration::from_mins(10));
+        let long = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        let unattested =
+            Registration::new(test_uri(), vec![Endpoint::https("agent.acme.com:443")]);
+        let attested = |issuer: &Issuer| {
+            let token = issuer.issue(&test_uri(), vec!["test".into()]).unwrap();
+            unattested.clone().with_attestation(token)
+        };
+        let (short, long) = (attested(&short), attested(&long));
+
+        let expiry = verifier.attested_until(&long).unwrap().unwrap();
+        let remaining = expiry.duration_since(std::time::SystemTime::now()).unwrap();
+        assert!(remaining > Duration::from_secs(3500));
+
+        let ranking = AttestationRanking::new(verifier);
+        let ranked = ranking.rank(vec![unattested.clone(), short.clone(), long.clone()]);
+        assert_eq!(ranked, vec![long, short, unattested]);
+    }
+
+    #[test]
+    fn verifier_starts_empty() {
+        let verifier = Verifier::new();
+        assert_eq!(verifier.trusted_root_count(), 0);
+        assert!(!verifier.has_trusted_root("acme.com"));
+    }
+
+    #[test]
+    fn add_trusted_root() {
+        let mut verifier = Verifier::new();
+        let signing_key = SigningKey::generate();
+
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+        assert_eq!(verifier.trusted_root_count(), 1);
+        assert!(verifier.has_trusted_root("acme.com"));
+        assert!(!verifier.has_trusted_root("other.com"));
+    }
+
+    #[test]
+    fn verify_valid_token() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let uri = test_uri();
+
+        let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+        let claims = verifier.verify(&token).unwrap();
+
+        assert_eq!(claims.agent_uri, uri.to_string());
+        assert_eq!(claims.iss, "acme.com");
+        assert_eq!(claims.capabilities, vec!["read"]);
+    }
+
+    #[test]
+    fn verify_rejects_empty_footer_spelling() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let token = issuer.issue(&test_uri(), vec![]).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+        assert!(verifier.verify(&token).is_ok());
+        assert!(matches!(
+            verifier.verify(&format!("{token}.")),
+            Err(AttestationError::InvalidTokenFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn verify_rejects_untrusted_issuer() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("evil.com", signing_key.clone(), Duration::from_hours(1));
+        let uri =
+            AgentUri::parse("agent://evil.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+
+        let token = issuer.issue(&uri, vec![]).unwrap();
+
+        let mut verifier = Verifier::new();
+        // Register key under different trust root
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+        let result = verifier.verify(&token);
+
+        // Token signed by key we know, but issuer claim doesn't match
+        assert!(matches!(
+            result,
+            Err(AttestationError::TrustRootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn verify_rejects_wrong_key() {
+        let signing_key1 = SigningKey::generate();
+        let signing_key2 = SigningKey::generate();
+
+        let issuer = Issuer::new("acme.com", signing_key1, Duration::from_hours(1));
+        let uri = test_uri();
+
+        let token = issuer.issue(&uri, vec![]).unwrap();
+
+        let mut verifier = Verifier::new();
+        // Register different key
+        verifier.add_trusted_root("acme.com", signing_key2.verifying_key());
+
+        let result = verifier.verify(&token);
+
+        // Wrong key should result in either InvalidSignature or InvalidTokenFormat
+        // (depending on how PASETO reports the error)
+        assert!(
+            matches!(
+                result,
+                Err(
+                    AttestationError::InvalidSignature
+                        | AttestationError::InvalidTokenFormat { .. }
+                )
+            ),
+            "Expected InvalidSignature or InvalidTokenFormat, got {result:?}",
+        );
+    }
+
+    #[test]
+    fn verify_for_uri_matches() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let uri = test_uri();
+
+        let token = issuer.issue(&uri, vec![]).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+        let claims = verifier.verify_for_uri(&token, &uri).unwrap();
+
+        assert_eq!(claims.agent_uri, uri.to_string());
+    }
+
+    #[test]
+    fn verify_for_uri_rejects_mismatch() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let uri1 = test_uri();
+        let uri2 =
+            AgentUri::parse("agent://acme.com/other/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+
+        let token = issuer.issue(&uri1, vec![]).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+        let result = verifier.verify_for_uri(&token, &uri2);
+
+        assert!(matches!(result, Err(AttestationError::UriMismatch { .. })));
+    }
+
+    #[test]
+    fn verify_empty_verifier_returns_untrusted() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key, Duration::from_hours(1));
+        let uri = test_uri();
+
+        let token = issuer.issue(&uri, vec![]).unwrap();
+
+        let verifier = Verifier::new();
+
+        let result = verifier.verify(&token);
+
+        assert!(matches!(
+            result,
+            Err(AttestationError::UntrustedIssuer { .. })
+        ));
+    }
+
+    #[test]
+    fn verify_with_policy_enforces_audience() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let uri = test_uri();
+
+        let token = issuer.issue(&uri, vec!["test".into()]).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+        let policy = VerificationPolicy::builder()
+            .require_audience("api.acme.com")
+            .build();
+        let result = verifier.verify_with_policy(&token, &policy);
+
+        assert!(matches!(
+            result,
+            Err(AttestationError::AudienceMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn verify_rejects_token_before_not_before() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+
+        let claims = crate::claims::AttestationClaimsBuilder::new()
+            .agent_uri(test_uri().to_string())
+            .issuer("acme.com")
+            .not_before(Utc::now() + chrono::Duration::minutes(10))
+            .build()
+            .unwrap();
+        let token = issuer.issue_claims(&claims).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+        let result = verifier.verify(&token);
+
+        assert!(matches!(
+            result,
+            Err(AttestationError::TokenNotYetValid { .. })
+        ));
+    }
+
+    #[test]
+    fn lifetime_limits_apply_to_cached_results() {
+        let clock = crate::clock::ManualClock::new(Utc::now());
+        let issuer =
+            Issuer::generate("acme.com", Duration::from_hours(1)).with_clock(clock.clone());
+        let token = issuer.issue(&test_uri(), vec![]).unwrap();
+
+        let mut verifier = Verifier::new()
+            .with_max_age(Duration::from_mins(10))
+            .with_clock(clock.clone());
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
+        verifier.enable_cache(VerificationCache::new(16, Duration::from_hours(1)));
+        verifier.verify(&token).unwrap();
+
+        clock.advance(Duration::from_secs(601));
+        assert!(matches!(
+            verifier.verify(&token),
+            Err(AttestationError::TokenTooOld { max_age_secs: 600, .. })
+        ));
+        assert_eq!(verifier.metrics().cache_hits, 1);
+
+        let strict = verifier.with_max_ttl(Duration::from_mins(30));
+        assert!(matches!(
+            strict.verify_detailed(&token),
+            Err(AttestationError::TtlExceedsMaximum { max_secs: 1800, .. })
+        ));
+    }
+
+    #[test]
+    fn metrics_count_cache_hits_and_failures() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let token = issuer.issue(&test_uri(), vec![]).unwrap();
+        let foreign = Issuer::generate("acme.com", Duration::from_hours(1))
+            .issue(&test_uri(), vec![])
+            .unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
+        verifier.enable_cache(VerificationCache::new(16, Duration::from_mins(1)));
+        verifier.verify(&token).unwrap();
+        verifier.verify(&token).unwrap();
+        assert!(verifier.verify(&foreign).is_err());
+
+        let metrics = verifier.metrics();
+        assert_eq!(metrics.attempted, 3);
+        assert_eq!(metrics.succeeded, 2);
+        assert_eq!(metrics.cache_hits, 1);
+        assert_eq!(metrics.failed(), 1);
+
+        verifier.reset_metrics();
+        assert_eq!(verifier.metrics().attempted, 0);
+    }
+
+    #[test]
+    fn cached_verify_returns_same_claims() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let token = issuer.issue(&test_uri(), vec!["read".into()]).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        verifier.enable_cache(VerificationCache::new(16, Duration::from_mins(1)));
+
+        let first = verifier.verify(&token).unwrap();
+        let second = verifier.verify(&token).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(verifier.cache().map(VerificationCache::len), Some(1));
+    }
+
+    #[test]
+    fn adding_trusted_root_clears_cache() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let token = issuer.issue(&test_uri(), vec![]).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        verifier.enable_cache(VerificationCache::new(16, Duration::from_mins(1)));
+        verifier.verify(&token).unwrap();
+
+        verifier.add_trusted_root("other.com", SigningKey::generate().verifying_key());
+
+        assert_eq!(verifier.cache().map(VerificationCache::len), Some(0));
+    }
+
+    #[test]
+    fn verify_chain_rejects_empty_chain() {
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", SigningKey::generate().verifying_key());
+
+        let result = verifier.verify_chain(&[]);
+
+        assert!(matches!(result, Err(AttestationError::InvalidChain { .. })));
+    }
+
+    #[test]
+    fn verify_chain_clamps_expiration_to_shortest_link() {
+        let root = Issuer::generate("acme.com", Duration::from_mins(1));
+        let regional = Issuer::generate("eu.acme.com", Duration::from_hours(1));
+        let intermediate =
+            AgentUri::parse("agent://eu.acme.com/workflow/broker_01h455vb4pex5vsknk084sn02q")
+                .unwrap();
+        let leaf = AgentUri::parse(
+            "agent://eu.acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02q",
+        )
+        .unwrap();
+
+        let root_token = root.issue(&intermediate, vec!["workflow".into()]).unwrap();
+        let leaf_token = regional
+            .issue(&leaf, vec!["workflow/approval".into()])
+            .unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", root.verifying_key());
+        verifier.add_trusted_root("eu.acme.com", regional.verifying_key());
+
+        let root_claims = verifier.verify(&root_token).unwrap();
+        let claims = verifier.verify_chain(&[&root_token, &leaf_token]).unwrap();
+
+        assert_eq!(claims.exp, root_claims.exp);
+        assert_eq!(claims.capabilities, vec!["workflow/approval"]);
+    }
+
+    #[test]
+    fn verify_chain_rejects_capability_escalation() {
+        let root = Issuer::generate("acme.com", Duration::from_hours(1));
+        let regional = Issuer::generate("eu.acme.com", Duration::from_hours(1));
+        let intermediate =
+            AgentUri::parse("agent://eu.acme.com/workflow/broker_01h455vb4pex5vsknk084sn02q")
+                .unwrap();
+        let leaf = AgentUri::parse(
+            "agent://eu.acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02q",
+        )
+        .unwrap();
+
+        let root_token = root
+            .issue(&intermediate, vec!["workflow/approval".into()])
+            .unwrap();
+        let leaf_token = regional.issue(&leaf, vec!["workflow".into()]).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", root.verifying_key());
+        verifier.add_trusted_root("eu.acme.com", regional.verifying_key());
+
+        let result = verifier.verify_chain(&[&root_token, &leaf_token]);
+
+        assert!(matches!(
+            result,
+            Err(AttestationError::CapabilityEscalation { link: 1, .. })
+        ));
+    }
+
+    fn cosigning_setup() -> (Issuer, Issuer, Verifier) {
+        let platform = Issuer::generate("acme.com", Duration::from_hours(1));
+        let compliance = Issuer::generate("audit.example.org", Duration::from_hours(1));
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", platform.verifying_key());
+        verifier.add_trusted_root("audit.example.org", compliance.verifying_key());
+
+        (platform, compliance, verifier)
+    }
+
+    #[test]
+    fn verify_cosigned_requires_listed_issuers() {
+        let (platform, compliance, verifier) = cosigning_setup();
+        let claims = crate::claims::AttestationClaimsBuilder::new()
+            .agent_uri(test_uri().to_string())
+            .issuer("acme.com")
+            .build()
+            .unwrap();
+
+        let both = CoSignedAttestation::issue(&claims, &[&platform, &compliance]).unwrap();
+        let only_platform = CoSignedAttestation::issue(&claims, &[&platform]).unwrap();
+        let required = ["acme.com", "audit.example.org"];
+
+        assert!(verifier.verify_cosigned(&both.to_string(), &required).is_ok());
+        assert!(matches!(
+            verifier.verify_cosigned(&only_platform.to_string(), &required),
+            Err(AttestationError::MissingCoSignature { ref issuer }) if issuer == "audit.example.org"
+        ));
+    }
+
+    #[test]
+    fn verify_cosigned_rejects_divergent_claims() {
+        let (platform, compliance, verifier) = cosigning_setup();
+        let primary = platform.issue(&test_uri(), vec!["read".into()]).unwrap();
+        let other = compliance.issue(&test_uri(), vec!["write".into()]).unwrap();
+        let bundle = CoSignedAttestation::from_tokens(vec![primary, other]).unwrap();
+
+        let result = verifier.verify_cosigned(&bundle.to_string(), &[]);
+
+        assert!(matches!(
+            result,
+            Err(AttestationError::CoSignatureMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn verify_cosigned_rejects_divergent_nonce() {
+        let (platform, compliance, verifier) = cosigning_setup();
+        let claims = |issuer: &str, nonce: &str| {
+            crate::claims::AttestationClaimsBuilder::new()
+                .agent_uri(test_uri().to_string())
+                .issuer(issuer)
+                .nonce(nonce)
+                .build()
+                .unwrap()
+        };
+        let primary = platform.issue_claims(&claims("acme.com", "n-1")).unwrap();
+        let other = compliance
+            .issue_claims(&claims("audit.example.org", "n-2"))
+            .unwrap();
+        let bundle = CoSignedAttestation::from_tokens(vec![primary, other]).unwrap();
+
+        assert!(matches!(
+            verifier.verify_cosigned(&bundle.to_string(), &[]),
+            Err(AttestationError::CoSignatureMismatch { ref issuer }) if issuer == "audit.example.org"
+        ));
+    }
+
+    fn threshold_setup(n: usize, k: usize) -> (Vec<Issuer>, Verifier) {
+        let issuers: Vec<_> = (0..n)
+            .map(|_| Issuer::generate("acme.com", Duration::from_hours(1)))
+            .collect();
+        let key_set =
+            ThresholdKeySet::new(issuers.iter().map(Issuer::verifying_key).collect(), k).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_threshold_root("acme.com", key_set);
+
+        (issuers, verifier)
+    }
+
+    #[test]
+    fn verify_threshold_accepts_k_of_n() {
+        let (issuers, verifier) = threshold_setup(3, 2);
+        let claims = crate::claims::AttestationClaimsBuilder::new()
+            .agent_uri(test_uri().to_string())
+            .issuer("acme.com")
+            .build()
+            .unwrap();
+
+        let bundle = CoSignedAttestation::issue(&claims, &[&issuers[1], &issuers[2]]).unwrap();
+
+        let result = verifier.verify_threshold(&bundle.to_string()).unwrap();
+        assert_eq!(result.iss, "acme.com");
+        assert!(verifier.has_threshold_root("acme.com"));
+        assert!(!verifier.has_trusted_root("acme.com"));
+    }
+
+    #[test]
+    fn verify_threshold_counts_each_key_once() {
+        let (issuers, verifier) = threshold_setup(3, 2);
+        let token = issuers[0].issue(&test_uri(), vec![]).unwrap();
+        let bundle = CoSignedAttestation::from_tokens(vec![token.clone(), token]).unwrap();
+
+        let result = verifier.verify_threshold(&bundle.to_string());
+
+        assert!(matches!(
+            result,
+            Err(AttestationError::ThresholdNotMet {
+                required: 2,
+                valid: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn verify_threshold_ignores_foreign_signatures() {
+        let (issuers, verifier) = threshold_setup(3, 2);
+        let outsider = Issuer::generate("acme.com", Duration::from_hours(1));
+        let bundle = CoSignedAttestation::from_tokens(vec![
+            issuers[0].issue(&test_uri(), vec![]).unwrap(),
+            outsider.issue(&test_uri(), vec![]).unwrap(),
+        ])
+        .unwrap();
+
+        let result = verifier.verify_threshold(&bundle.to_string());
+
+        assert!(matches!(
+            result,
+            Err(AttestationError::ThresholdNotMet { valid: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn verify_threshold_rejects_oversized_bundles() {
+        let (issuers, verifier) = threshold_setup(3, 2);
+        let claims = crate::claims::AttestationClaimsBuilder::new()
+            .agent_uri(test_uri().to_string())
+            .issuer("acme.com")
+            .build()
+            .unwrap();
+        let signers: Vec<&Issuer> = issuers.iter().collect();
+        let full = CoSignedAttestation::issue(&claims, &signers).unwrap();
+        assert!(verifier.verify_threshold(&full.to_string()).is_ok());
+
+        let mut tokens = full.tokens().to_vec();
+        tokens.push("v4.public.junk".to_string());
+        let bundle = CoSignedAttestation::from_tokens(tokens).unwrap();
+        assert!(matches!(
+            verifier.verify_threshold(&bundle.to_string()),
+            Err(AttestationError::InvalidTokenFormat { ref reason })
+                if reason.contains("at most 3 keys")
+        ));
+    }
+
+    #[test]
+    fn single_signature_not_enough_for_threshold_root() {
+        let (issuers, verifier) = threshold_setup(2, 1);
+        let token = issuers[0].issue(&test_uri(), vec![]).unwrap();
+
+        assert!(verifier.verify(&token).is_err());
+        assert!(verifier.verify_threshold(&token).is_ok());
+    }
+
+    #[test]
+    fn verify_multiple_capabilities() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let uri = test_uri();
+
+        let capabilities = vec!["read".to_string(), "write".to_string(), "admin".to_string()];
+        let token = issuer.issue(&uri, capabilities).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+        let claims = verifier.verify(&token).unwrap();
+
+        // Capabilities are issued in canonical (sorted) order
+        assert_eq!(claims.capabilities, ["admin", "read", "write"]);
+    }
+
+    #[test]
+    fn capability_aliases_apply_to_grants_and_denials() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let uri = test_uri();
+        let send = CapabilityPath::parse("communication/email/send").unwrap();
+        let aliases = CapabilityAliases::new()
+            .with_alias("email.send", send.clone())
+            .with_alias("email", CapabilityPath::parse("communication/email").unwrap());
+
+        let granted = issuer.issue(&uri, vec!["email.send".into()]).unwrap();
+        let claims = AttestationClaims::builder()
+            .agent_uri(uri.to_string())
+            .issuer("acme.com")
+            .add_capability("communication")
+            .deny_capability("email")
+            .build()
+            .unwrap();
+        let denied = issuer.issue_claims(&claims).unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        assert!(verifier.verify_for_capability(&granted, &uri, &send).is_err());
+        assert!(verifier.verify_for_capability(&denied, &uri, &send).is_ok());
 
-    let iss = json["iss"]
-        .as_str()
-        .ok_or_else(|| AttestationError::InvalidClaims {
-            reason: "missing iss claim".to_string(),
-        })?
-        .to_string();
+        let mut verifier = Verifier::new().with_capability_aliases(aliases);
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        assert!(verifier.verify_for_capability(&granted, &uri, &send).is_ok());
+        assert!(matches!(
+            verifier.verify_for_capability(&denied, &uri, &send),
+            Err(AttestationError::CapabilityDenied { .. })
+        ));
+    }
 
-    let iat = json["iat"]
-        .as_str()
-        .ok_or_else(|| AttestationError::InvalidClaims {
-            reason: "missing iat claim".to_string(),
-        })?;
-    let iat = chrono::DateTime::parse_from_rfc3339(iat)
-        .map_err(|e| AttestationError::InvalidClaims {
-            reason: format!("invalid iat format: {e}"),
-        })?
-        .with_timezone(&Utc);
+    #[test]
+    fn transparency_log_requires_inclusion_proof() {
+        let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let token = issuer.issue(&test_uri(), vec!["read".into()]).unwrap();
+        let mut log = crate::AttestationLog::new(SigningKey::generate());
+        let index = log.append(&token);
+        let head = log.tree_head();
+        let proof = log.inclusion_proof(index).unwrap();
 
-    let exp = json["exp"]
-        .as_str()
-        .ok_or_else(|| AttestationError::InvalidClaims {
-            reason: "missing exp claim".to_string(),
-        })?;
-    let exp = chrono::DateTime::parse_from_rfc3339(exp)
-        .map_err(|e| AttestationError::InvalidClaims {
-            reason: format!("invalid exp format: {e}"),
-        })?
-        .with_timezone(&Utc);
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        assert!(matches!(
+            verifier.verify_logged(&token, &proof, &head),
+            Err(AttestationError::InvalidInclusionProof { .. })
+        ));
 
-    let aud = json.get("aud").and_then(|v| v.as_str()).map(String::from);
+        verifier.require_transparency_log(log.verifying_key());
 
-    Ok(AttestationClaims {
-        agent_uri,
-        capabilities,
-        iss,
-        iat,
-        exp,
-        aud,
-    })
-}
+        assert_eq!(
+            verifier.verify(&token),
+            Err(AttestationError::InclusionProofRequired)
+        );
+        assert!(verifier.verify_logged(&token, &proof, &head).is_ok());
+    }
 
-#[cfg(test)]
-mod tests {
-    use std::time::Duration;
+    #[test]
+    fn transparency_log_is_required_for_threshold_bundles() {
+        let (issuers, mut verifier) = threshold_setup(2, 1);
+        let token = issuers[0].issue(&test_uri(), vec![]).unwrap();
+        assert!(verifier.verify_threshold(&token).is_ok());
+
+        verifier.require_transparency_log(SigningKey::generate().verifying_key());
+        assert_eq!(
+            verifier.verify_threshold(&token),
+            Err(AttestationError::InclusionProofRequired)
+        );
+    }
 
-    use super::*;
-    use crate::issuer::Issuer;
-    use crate::keys::SigningKey;
+    #[cfg(feature = "cose")]
+    #[test]
+    fn transparency_log_is_required_for_cwt_tokens() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let claims = AttestationClaims::builder()
+            .agent_uri(test_uri().to_string())
+            .issuer("acme.com")
+            .build()
+            .unwrap();
+        let token = issuer.issue_cose(&claims).unwrap();
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
+        assert!(verifier.verify_cose(&token).is_ok());
 
-    fn test_uri() -> AgentUri {
-        AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap()
+        verifier.require_transparency_log(SigningKey::generate().verifying_key());
+        assert_eq!(
+            verifier.verify_cose(&token),
+            Err(AttestationError::InclusionProofRequired)
+        );
     }
 
+    #[cfg(feature = "cose")]
     #[test]
-    fn verifier_starts_empty() {
+    fn verify_cose_rejects_oversized_tokens_before_decoding() {
         let verifier = Verifier::new();
-        assert_eq!(verifier.trusted_root_count(), 0);
-        assert!(!verifier.has_trusted_root("acme.com"));
+        let oversized = vec![0xff; crate::limits::MAX_TOKEN_LEN + 1];
+
+        assert!(matches!(
+            verifier.verify_cose(&oversized),
+            Err(AttestationError::LengthLimitExceeded { field: "token", .. })
+        ));
     }
 
+    #[cfg(feature = "jwt")]
     #[test]
-    fn add_trusted_root() {
+    fn verify_jwt_rejects_oversized_payloads_before_decoding() {
+        let verifier = Verifier::new();
+        let oversized = format!("e30.{}.sig", "A".repeat(6000));
+
+        assert!(matches!(
+            verifier.verify_jwt(&oversized),
+            Err(AttestationError::LengthLimitExceeded { field: "payload", .. })
+        ));
+    }
+
+    #[cfg(feature = "jwt")]
+    #[test]
+    fn transparency_log_is_required_for_jwts() {
+        let signing_key = SigningKey::generate();
+        let claims = AttestationClaims::builder()
+            .agent_uri(test_uri().to_string())
+            .issuer("acme.com")
+            .build()
+            .unwrap();
+        let token = claims.to_jwt(&signing_key).unwrap();
         let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        assert!(verifier.verify_jwt(&token).is_ok());
+
+        verifier.require_transparency_log(SigningKey::generate().verifying_key());
+        assert_eq!(
+            verifier.verify_jwt(&token),
+            Err(AttestationError::InclusionProofRequired)
+        );
+    }
+
+    #[test]
+    fn transparency_log_rejects_foreign_tree_head() {
         let signing_key = SigningKey::generate();
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_hours(1));
+        let token = issuer.issue(&test_uri(), vec![]).unwrap();
+        let mut rogue_log = crate::AttestationLog::new(SigningKey::generate());
+        let index = rogue_log.append(&token);
 
+        let mut verifier = Verifier::new();
         verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        verifier.require_transparency_log(SigningKey::generate().verifying_key());
 
-        assert_eq!(verifier.trusted_root_count(), 1);
-        assert!(verifier.has_trusted_root("acme.com"));
-        assert!(!verifier.has_trusted_root("other.com"));
+        let result = verifier.verify_logged(
+            &token,
+            &rogue_log.inclusion_proof(index).unwrap(),
+            &rogue_log.tree_head(),
+        );
+        assert_eq!(result, Err(AttestationError::InvalidSignature));
+    }
+
+    fn status_setup(revoked: &[u64]) -> (Issuer, String, String) {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let claims = AttestationClaims::builder()
+            .agent_uri(test_uri().to_string())
+            .issuer("acme.com")
+            .status_index(5)
+            .build()
+            .unwrap();
+        let token = issuer.issue_claims(&claims).unwrap();
+
+        let mut list = StatusList::new(64);
+        for &index in revoked {
+            list.revoke(index).unwrap();
+        }
+        let status = issuer
+            .issue_status_list(&list, Duration::from_mins(5))
+            .unwrap();
+        (issuer, token, status)
     }
 
     #[test]
-    fn verify_valid_token() {
-        let signing_key = SigningKey::generate();
-        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(3600));
-        let uri = test_uri();
+    fn status_list_accepts_unrevoked_token() {
+        let (issuer, token, status) = status_setup(&[4, 6]);
+        let mut verifier =
+            Verifier::new().with_status_list_source(move |_: &str| Some(status.clone()));
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
 
-        let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+        assert_eq!(verifier.verify(&token).unwrap().status_idx, Some(5));
+    }
+
+    #[test]
+    fn status_list_revocation_bypasses_cache() {
+        let (issuer, token, status) = status_setup(&[]);
+        let (_, _, foreign) = status_setup(&[]);
+        let current = std::sync::Arc::new(std::sync::Mutex::new(status));
+        let source = std::sync::Arc::clone(&current);
+
+        let mut verifier = Verifier::new()
+            .with_status_list_source(move |_: &str| Some(source.lock().unwrap().clone()));
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
+        verifier.enable_cache(VerificationCache::new(16, Duration::from_mins(1)));
+        assert!(verifier.verify(&token).is_ok());
+
+        // Reissue the list with the same issuer key, revoking index 5
+        let mut list = StatusList::new(64);
+        list.revoke(5).unwrap();
+        *current.lock().unwrap() = issuer
+            .issue_status_list(&list, Duration::from_mins(5))
+            .unwrap();
+        assert_eq!(
+            verifier.verify(&token),
+            Err(AttestationError::TokenRevoked { index: 5 })
+        );
+
+        // A list signed by a different issuer key is not trusted
+        *current.lock().unwrap() = foreign;
+        assert!(matches!(
+            verifier.verify(&token),
+            Err(AttestationError::StatusUnavailable { .. })
+        ));
+    }
+
+    #[test]
+    fn status_list_fails_closed_without_list() {
+        let (issuer, token, _) = status_setup(&[]);
+        let mut verifier = Verifier::new().with_status_list_source(|_: &str| None);
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
+
+        assert!(matches!(
+            verifier.verify(&token),
+            Err(AttestationError::StatusUnavailable { ref issuer, .. }) if issuer == "acme.com"
+        ));
+    }
 
+    #[test]
+    fn status_list_token_is_not_an_attestation() {
+        let (issuer, _, status) = status_setup(&[]);
         let mut verifier = Verifier::new();
-        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
 
-        let claims = verifier.verify(&token).unwrap();
+        assert!(verifier.verify(&status).is_err());
+    }
 
-        assert_eq!(claims.agent_uri, uri.to_string());
-        assert_eq!(claims.iss, "acme.com");
-        assert_eq!(claims.capabilities, vec!["read"]);
+    /// Reports every token from `issuer` as revoked.
+    struct RevokeIssuer(&'static str);
+
+    impl StatusChecker for RevokeIssuer {
+        fn check_status(
+            &self,
+            _token: &str,
+            claims: &AttestationClaims,
+        ) -> impl std::future::Future<Output = Result<(), AttestationError>> + Send {
+            let result = if claims.iss == self.0 {
+                Err(AttestationError::TokenRevokedOnline {
+                    issuer: claims.iss.clone(),
+                })
+            } else {
+                Ok(())
+            };
+            async move { result }
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_online_consults_checker() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let token = issuer.issue(&test_uri(), vec![]).unwrap();
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
+
+        assert!(verifier.verify_online(&token, &RevokeIssuer("other.com")).await.is_ok());
+        assert_eq!(
+            verifier.verify_online(&token, &RevokeIssuer("acme.com")).await,
+            Err(AttestationError::TokenRevokedOnline {
+                issuer: "acme.com".to_string()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn verify_online_skips_checker_for_invalid_token() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let token = issuer.issue(&test_uri(), vec![]).unwrap();
+        let verifier = Verifier::new();
+
+        assert!(matches!(
+            verifier.verify_online(&token, &RevokeIssuer("acme.com")).await,
+            Err(AttestationError::UntrustedIssuer { .. })
+        ));
+    }
+
+    fn subdomain_token(signing_key: &SigningKey, trust_root: &str) -> String {
+        let issuer = Issuer::new(trust_root, signing_key.clone(), Duration::from_hours(1));
+        let uri = AgentUri::parse(&format!(
+            "agent://{trust_root}/test/agent_01h455vb4pex5vsknk084sn02q"
+        ))
+        .unwrap();
+        issuer.issue(&uri, vec![]).unwrap()
     }
 
     #[test]
-    fn verify_rejects_untrusted_issuer() {
-        let signing_key = SigningKey::generate();
-        let issuer = Issuer::new("evil.com", signing_key.clone(), Duration::from_secs(3600));
-        let uri =
-            AgentUri::parse("agent://evil.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+    fn pattern_root_attests_subdomains_on_label_boundary() {
+        let corporate = SigningKey::generate();
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root_pattern("*.acme.com", corporate.verifying_key());
 
-        let token = issuer.issue(&uri, vec![]).unwrap();
+        for trust_root in ["eu.acme.com", "a.b.acme.com"] {
+            let token = subdomain_token(&corporate, trust_root);
+            assert_eq!(verifier.verify(&token).unwrap().iss, trust_root);
+        }
+        for trust_root in ["acme.com", "evilacme.com"] {
+            let token = subdomain_token(&corporate, trust_root);
+            assert!(verifier.verify(&token).is_err());
+        }
+    }
 
+    #[test]
+    fn exact_root_overrides_pattern() {
+        let corporate = SigningKey::generate();
+        let pinned = SigningKey::generate();
         let mut verifier = Verifier::new();
-        // Register key under different trust root
-        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        verifier.add_trusted_root_pattern("*.acme.com", corporate.verifying_key());
+        verifier.add_trusted_root("eu.acme.com", pinned.verifying_key());
 
-        let result = verifier.verify(&token);
+        assert!(verifier.verify(&subdomain_token(&pinned, "eu.acme.com")).is_ok());
+        assert!(verifier.verify(&subdomain_token(&corporate, "eu.acme.com")).is_err());
+        assert!(verifier.verify(&subdomain_token(&corporate, "us.acme.com")).is_ok());
+        assert_eq!(verifier.trusted_root_count(), 2);
+    }
 
-        // Token signed by key we know, but issuer claim doesn't match
+    #[test]
+    fn parent_domain_issuer_is_opt_in_and_label_safe() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
+        let token_for = |trust_root: &str| {
+            let uri = AgentUri::parse(&format!(
+                "agent://{trust_root}/test/agent_01h455vb4pex5vsknk084sn02q"
+            ))
+            .unwrap();
+            (issuer.issue(&uri, vec![]).unwrap(), uri)
+        };
+
+        let (team_token, team_uri) = token_for("team.acme.com");
         assert!(matches!(
-            result,
+            verifier.verify_for_uri(&team_token, &team_uri),
+            Err(AttestationError::TrustRootMismatch { .. })
+        ));
+
+        verifier.allow_parent_domain_issuer("acme.com");
+        assert!(verifier.verify_for_uri(&team_token, &team_uri).is_ok());
+        let (own_token, own_uri) = token_for("acme.com");
+        assert!(verifier.verify_for_uri(&own_token, &own_uri).is_ok());
+        let (evil_token, evil_uri) = token_for("evilacme.com");
+        assert!(matches!(
+            verifier.verify_for_uri(&evil_token, &evil_uri),
             Err(AttestationError::TrustRootMismatch { .. })
         ));
     }
 
     #[test]
-    fn verify_rejects_wrong_key() {
-        let signing_key1 = SigningKey::generate();
-        let signing_key2 = SigningKey::generate();
+    fn legacy_claims_are_migrated_on_decode() {
+        let json = serde_json::json!({
+            "agent_uri": "agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q",
+            "capabilities": ["write", "read", "write"],
+            "iss": "acme.com",
+            "iat": "2024-01-15T14:30:00.000Z",
+            "exp": "2024-01-15T15:30:00.000Z",
+        });
+
+        let claims = extract_claims(&json).unwrap();
+        assert_eq!(claims.ver, crate::CLAIMS_VERSION);
+        assert_eq!(claims.capabilities, ["read", "write"]);
+    }
 
-        let issuer = Issuer::new("acme.com", signing_key1, Duration::from_secs(3600));
-        let uri = test_uri();
+    #[test]
+    fn future_claims_versions_are_rejected() {
+        let json = serde_json::json!({
+            "agent_uri": "agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q",
+            "iss": "acme.com",
+            "iat": "2024-01-15T14:30:00.000Z",
+            "exp": "2024-01-15T15:30:00.000Z",
+            "ver": 99,
+        });
+
+        assert_eq!(
+            extract_claims(&json),
+            Err(AttestationError::UnsupportedClaimsVersion {
+                version: 99,
+                supported: crate::CLAIMS_VERSION,
+            })
+        );
+    }
 
-        let token = issuer.issue(&uri, vec![]).unwrap();
+    fn holder_bound_token(holder: &SigningKey) -> (Verifier, String) {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let claims = AttestationClaims::builder()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .add_capability("read")
+            .issuer("acme.com")
+            .holder_key(holder.verifying_key())
+            .build()
+            .unwrap();
+        let token = issuer.issue_claims(&claims).unwrap();
 
         let mut verifier = Verifier::new();
-        // Register different key
-        verifier.add_trusted_root("acme.com", signing_key2.verifying_key());
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
+        (verifier, token)
+    }
 
-        let result = verifier.verify(&token);
+    #[test]
+    fn verify_with_pop_accepts_holder_proof() {
+        let holder = SigningKey::generate();
+        let (verifier, token) = holder_bound_token(&holder);
 
-        // Wrong key should result in either InvalidSignature or InvalidTokenFormat
-        // (depending on how PASETO reports the error)
-        assert!(
-            matches!(
-                result,
-                Err(
-                    AttestationError::InvalidSignature
-                        | AttestationError::InvalidTokenFormat { .. }
-                )
-            ),
-            "Expected InvalidSignature or InvalidTokenFormat, got {result:?}",
-        );
+        let proof = holder.prove_possession(b"nonce");
+        let claims = verifier.verify_with_pop(&token, b"nonce", &proof).unwrap();
+        assert_eq!(claims.cnf, Some(holder.verifying_key()));
     }
 
     #[test]
-    fn verify_for_uri_matches() {
-        let signing_key = SigningKey::generate();
-        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(3600));
-        let uri = test_uri();
+    fn verify_with_pop_rejects_other_keys_and_challenges() {
+        let holder = SigningKey::generate();
+        let (verifier, token) = holder_bound_token(&holder);
 
-        let token = issuer.issue(&uri, vec![]).unwrap();
+        let thief = SigningKey::generate().prove_possession(b"nonce");
+        assert!(matches!(
+            verifier.verify_with_pop(&token, b"nonce", &thief),
+            Err(AttestationError::ProofOfPossessionFailed { .. })
+        ));
 
-        let mut verifier = Verifier::new();
-        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        let replayed = holder.prove_possession(b"old-nonce");
+        assert!(matches!(
+            verifier.verify_with_pop(&token, b"nonce", &replayed),
+            Err(AttestationError::ProofOfPossessionFailed { .. })
+        ));
+    }
 
-        let claims = verifier.verify_for_uri(&token, &uri).unwrap();
+    #[test]
+    fn verify_with_pop_rejects_bearer_tokens() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+        let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
 
-        assert_eq!(claims.agent_uri, uri.to_string());
+        let proof = SigningKey::generate().prove_possession(b"nonce");
+        assert!(matches!(
+            verifier.verify_with_pop(&token, b"nonce", &proof),
+            Err(AttestationError::ProofOfPossessionFailed { .. })
+        ));
+        // Bearer verification still works for holder-bound tokens
+        let holder = SigningKey::generate();
+        let (verifier, token) = holder_bound_token(&holder);
+        assert!(verifier.verify(&token).is_ok());
     }
 
     #[test]
-    fn verify_for_uri_rejects_mismatch() {
-        let signing_key = SigningKey::generate();
-        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(3600));
-        let uri1 = test_uri();
-        let uri2 =
-            AgentUri::parse("agent://acme.com/other/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+    fn verify_with_nonce_requires_matching_challenge() {
+        let issuer = Issuer::generate("acme.com", Duration::from_mins(1));
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", issuer.verifying_key());
+        let builder = AttestationClaims::builder()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com");
+
+        let challenge = Verifier::generate_nonce();
+        assert_eq!(challenge.len(), 22);
+        assert_ne!(challenge, Verifier::generate_nonce());
+
+        let claims = builder.clone().nonce(challenge.clone()).build().unwrap();
+        let token = issuer.issue_claims(&claims).unwrap();
+        let decoded = verifier.verify_with_nonce(&token, &challenge).unwrap();
+        assert_eq!(decoded.nonce.as_deref(), Some(challenge.as_str()));
+        assert_eq!(
+            verifier.verify_with_nonce(&token, "other"),
+            Err(AttestationError::NonceMismatch {
+                expected: "other".to_string(),
+                actual: Some(challenge),
+            })
+        );
 
-        let token = issuer.issue(&uri1, vec![]).unwrap();
+        let token = issuer.issue_claims(&builder.build().unwrap()).unwrap();
+        assert_eq!(
+            verifier.verify_with_nonce(&token, "other"),
+            Err(AttestationError::NonceMismatch {
+                expected: "other".to_string(),
+                actual: None,
+            })
+        );
+    }
+
+    #[test]
+    fn verify_chain_with_nonce_checks_the_leaf() {
+        let root = Issuer::generate("acme.com", Duration::from_hours(1));
+        let regional = Issuer::generate("eu.acme.com", Duration::from_mins(1));
+        let intermediate =
+            AgentUri::parse("agent://eu.acme.com/workflow/broker_01h455vb4pex5vsknk084sn02q")
+                .unwrap();
+        let root_token = root.issue(&intermediate, vec!["workflow".into()]).unwrap();
+        let leaf = AttestationClaims::builder()
+            .agent_uri("agent://eu.acme.com/workflow/approval/rule_01h455vb4pex5vsknk084sn02q")
+            .add_capability("workflow/approval")
+            .issuer("eu.acme.com");
 
         let mut verifier = Verifier::new();
-        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        verifier.add_trusted_root("acme.com", root.verifying_key());
+        verifier.add_trusted_root("eu.acme.com", regional.verifying_key());
+
+        let challenge = Verifier::generate_nonce();
+        let claims = leaf.clone().nonce(challenge.clone()).build().unwrap();
+        let leaf_token = regional.issue_claims(&claims).unwrap();
+        let chain = [root_token.as_str(), leaf_token.as_str()];
+        let decoded = verifier.verify_chain_with_nonce(&chain, &challenge).unwrap();
+        assert_eq!(decoded.nonce.as_deref(), Some(challenge.as_str()));
+        assert_eq!(
+            verifier.verify_chain_with_nonce(&chain, "other"),
+            Err(AttestationError::NonceMismatch {
+                expected: "other".to_string(),
+                actual: Some(challenge.clone()),
+            })
+        );
 
-        let result = verifier.verify_for_uri(&token, &uri2);
+        // A valid chain whose leaf carries no challenge is rejected
+        let leaf_token = regional.issue_claims(&leaf.build().unwrap()).unwrap();
+        assert!(matches!(
+            verifier.verify_chain_with_nonce(&[&root_token, &leaf_token], &challenge),
+            Err(AttestationError::NonceMismatch { actual: None, .. })
+        ));
+        assert!(matches!(
+            verifier.verify_chain_with_nonce(&[], &challenge),
+            Err(AttestationError::InvalidChain { .. })
+        ));
+    }
 
-        assert!(matches!(result, Err(AttestationError::UriMismatch { .. })));
+    #[test]
+    fn load_bundle_installs_roots_and_status_lists() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let distribution = SigningKey::generate();
+        let claims = AttestationClaims::builder()
+            .agent_uri("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("acme.com")
+            .status_index(3)
+            .build()
+            .unwrap();
+        let token = issuer.issue_claims(&claims).unwrap();
+
+        let mut list = StatusList::new(16);
+        list.revoke(3).unwrap();
+        let mut bundle = TrustBundle::new();
+        bundle.add_trusted_root("acme.com", issuer.verifying_key());
+        bundle.add_status_list(
+            "acme.com",
+            issuer.issue_status_list(&list, Duration::from_mins(5)).unwrap(),
+        );
+        let file = bundle.sign(&distribution, Duration::from_mins(1)).unwrap();
+
+        let mut verifier = Verifier::new();
+        assert!(verifier.load_bundle(&file, &SigningKey::generate().verifying_key()).is_err());
+        assert_eq!(verifier.trusted_root_count(), 0);
+
+        verifier.load_bundle(&file, &distribution.verifying_key()).unwrap();
+        assert!(verifier.has_trusted_root("acme.com"));
+        assert_eq!(
+            verifier.verify(&token),
+            Err(AttestationError::TokenRevoked { index: 3 })
+        );
     }
 
     #[test]
-    fn verify_empty_verifier_returns_untrusted() {
-        let signing_key = SigningKey::generate();
-        let issuer = Issuer::new("acme.com", signing_key, Duration::from_secs(3600));
-        let uri = test_uri();
+    fn exported_bundle_keeps_every_key_of_a_rotating_root() {
+        let old = SigningKey::generate();
+        let new = SigningKey::generate();
+        let distribution = SigningKey::generate();
+        let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .unwrap();
+        let old_token = Issuer::new("acme.com", old.clone(), Duration::from_hours(1))
+            .issue(&uri, vec!["read".into()])
+            .unwrap();
+        let new_token = Issuer::new("acme.com", new.clone(), Duration::from_hours(1))
+            .issue(&uri, vec!["read".into()])
+            .unwrap();
+
+        let mut source = Verifier::new();
+        source.add_trusted_root("acme.com", old.verifying_key());
+        source.add_trusted_key("acme.com", new.verifying_key());
+        let bundle = source.export_bundle();
+        assert_eq!(
+            bundle.trusted_roots()["acme.com"],
+            vec![old.verifying_key(), new.verifying_key()]
+        );
 
-        let token = issuer.issue(&uri, vec![]).unwrap();
+        let file = bundle.sign(&distribution, Duration::from_mins(1)).unwrap();
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", SigningKey::generate().verifying_key());
+        verifier.load_bundle(&file, &distribution.verifying_key()).unwrap();
+        assert!(verifier.verify(&old_token).is_ok());
+        assert!(verifier.verify(&new_token).is_ok());
+        assert_eq!(verifier.export_bundle(), bundle);
+    }
 
-        let verifier = Verifier::new();
+    #[test]
+    fn load_bundle_falls_back_to_configured_status_source() {
+        let issuer = Issuer::generate("other.com", Duration::from_hours(1));
+        let status = issuer
+            .issue_status_list(&StatusList::new(16), Duration::from_mins(5))
+            .unwrap();
+        let mut verifier = Verifier::new().with_status_list_source(move |iss: &str| {
+            (iss == "other.com").then(|| status.clone())
+        });
+        verifier.add_trusted_root("other.com", issuer.verifying_key());
+
+        let distribution = SigningKey::generate();
+        let mut bundle = TrustBundle::new();
+        bundle.add_status_list("acme.com", "v4.public.unused");
+        let file = bundle.sign(&distribution, Duration::from_mins(1)).unwrap();
+        verifier.load_bundle(&file, &distribution.verifying_key()).unwrap();
+
+        let claims = AttestationClaims::builder()
+            .agent_uri("agent://other.com/test/agent_01h455vb4pex5vsknk084sn02q")
+            .issuer("other.com")
+            .status_index(1)
+            .build()
+            .unwrap();
+        let token = issuer.issue_claims(&claims).unwrap();
+        assert!(verifier.verify(&token).is_ok());
+    }
 
-        let result = verifier.verify(&token);
+    #[test]
+    fn export_bundle_roundtrips_trust_store() {
+        let keys: Vec<VerifyingKey> =
+            (0..2).map(|_| SigningKey::generate().verifying_key()).collect();
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", keys[0].clone());
+        verifier.add_trusted_root_pattern("*.acme.com", keys[1].clone());
+        verifier.add_threshold_root("bank.com", ThresholdKeySet::new(keys, 1).unwrap());
+
+        let distribution = SigningKey::generate();
+        let file = verifier
+            .export_bundle()
+            .sign(&distribution, Duration::from_mins(1))
+            .unwrap();
+        let mut imported = Verifier::new();
+        imported.load_bundle(&file, &distribution.verifying_key()).unwrap();
+
+        assert_eq!(imported.export_bundle(), verifier.export_bundle());
+        assert_eq!(imported.trusted_root_count(), 2);
+        assert!(imported.has_threshold_root("bank.com"));
+    }
+
+    #[test]
+    fn oversized_tokens_are_rejected_before_decoding() {
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", SigningKey::generate().verifying_key());
+        let token = format!("v4.public.{}", "A".repeat(limits::MAX_TOKEN_LEN));
 
         assert!(matches!(
-            result,
-            Err(AttestationError::UntrustedIssuer { .. })
+            verifier.verify(&token),
+            Err(AttestationError::LengthLimitExceeded { field: "token", .. })
+        ));
+        assert!(matches!(
+            AttestationClaims::decode_unverified(&token),
+            Err(AttestationError::LengthLimitExceeded { field: "token", .. })
         ));
     }
 
     #[test]
-    fn verify_multiple_capabilities() {
-        let signing_key = SigningKey::generate();
-        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(3600));
-        let uri = test_uri();
-
-        let capabilities = vec!["read".to_string(), "write".to_string(), "admin".to_string()];
-        let token = issuer.issue(&uri, capabilities.clone()).unwrap();
+    fn additional_keys_verify_and_report_usage() {
+        let old = Issuer::generate("acme.com", Duration::from_hours(1));
+        let new = Issuer::generate("acme.com", Duration::from_hours(1));
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", old.verifying_key());
+        verifier.add_trusted_key("acme.com", new.verifying_key());
+        verifier.add_trusted_key("acme.com", new.verifying_key());
+        assert_eq!(verifier.trusted_keys("acme.com").len(), 2);
+
+        let old_token = old.issue(&test_uri(), vec![]).unwrap();
+        let new_token = new.issue(&test_uri(), vec![]).unwrap();
+        assert_eq!(verifier.verify_detailed(&old_token).unwrap().key, old.verifying_key());
+        assert_eq!(verifier.verify_detailed(&new_token).unwrap().key, new.verifying_key());
+        verifier.verify(&new_token).unwrap();
+
+        let usage = verifier.metrics().key_usage;
+        assert_eq!(usage.get(&old.verifying_key().fingerprint()), Some(&1));
+        assert_eq!(usage.get(&new.verifying_key().fingerprint()), Some(&2));
+    }
 
+    #[test]
+    fn retired_keys_are_no_longer_trusted() {
+        let old = Issuer::generate("acme.com", Duration::from_hours(1));
+        let new = Issuer::generate("acme.com", Duration::from_hours(1));
         let mut verifier = Verifier::new();
-        verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+        verifier.enable_cache(VerificationCache::new(16, Duration::from_mins(1)));
+        verifier.add_trusted_root("acme.com", old.verifying_key());
+        verifier.add_trusted_key("acme.com", new.verifying_key());
 
-        let claims = verifier.verify(&token).unwrap();
+        let old_token = old.issue(&test_uri(), vec![]).unwrap();
+        assert!(verifier.verify(&old_token).is_ok());
+
+        assert!(verifier.remove_trusted_key("acme.com", &old.verifying_key()));
+        assert!(!verifier.remove_trusted_key("acme.com", &old.verifying_key()));
+        assert!(verifier.verify(&old_token).is_err());
+        assert!(verifier.verify(&new.issue(&test_uri(), vec![]).unwrap()).is_ok());
+
+        assert!(verifier.remove_trusted_key("acme.com", &new.verifying_key()));
+        assert!(!verifier.has_trusted_root("acme.com"));
+    }
+
+    #[test]
+    fn add_trusted_root_replaces_additional_keys() {
+        let old = SigningKey::generate();
+        let new = SigningKey::generate();
+        let mut verifier = Verifier::new();
+        verifier.add_trusted_root("acme.com", old.verifying_key());
+        verifier.add_trusted_key("acme.com", new.verifying_key());
+        verifier.add_trusted_root("acme.com", new.verifying_key());
 
-        assert_eq!(claims.capabilities, capabilities);
+        assert_eq!(verifier.trusted_keys("acme.com"), [new.verifying_key()]);
     }
 }
@@ -0,0 +1,158 @@
+//! JavaScript bindings for browser-side verification.
+//!
+//! With the `wasm` feature, this module exports a `Verifier` class and a
+//! `decodeUnverified` function through `wasm-bindgen`, so agent consoles can
+//! check attestations locally after building the crate for
+//! `wasm32-unknown-unknown`. The feature also routes the clock and random
+//! number generation through the browser's `Date` and `crypto` APIs.
+//!
+//! Claims cross the boundary as JSON strings in the token's own claim
+//! format; errors are thrown as JavaScript `Error`s carrying the same
+//! message as [`AttestationError`].
+//!
+//! ```js
+//! import { Verifier, decodeUnverified } from "agent-uri-attestation";
+//!
+//! const verifier = new Verifier();
+//! verifier.addTrustedRoot("acme.com", publicKeyBytes);
+//! const claims = JSON.parse(verifier.verify(token));
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::claims::AttestationClaims;
+use crate::error::AttestationError;
+use crate::keys::VerifyingKey;
+use crate::verifier::Verifier;
+
+/// A [`Verifier`] exported to JavaScript as `Verifier`.
+#[wasm_bindgen(js_name = Verifier)]
+#[derive(Debug, Default)]
+pub struct WasmVerifier {
+    inner: Verifier,
+}
+
+#[wasm_bindgen(js_class = Verifier)]
+impl WasmVerifier {
+    /// Creates a verifier with no trusted roots.
+    #[wasm_bindgen(constructor)]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts `public_key`, a 32-byte Ed25519 key, for `trust_root`.
+    ///
+    /// # Errors
+    ///
+    /// Throws if the key is not a valid Ed25519 public key.
+    #[wasm_bindgen(js_name = addTrustedRoot)]
+    pub fn add_trusted_root(
+        &mut self,
+        trust_root: &str,
+        public_key: &[u8],
+    ) -> Result<(), JsError> {
+        self.try_add_trusted_root(trust_root, public_key).map_err(to_js)
+    }
+
+    /// Verifies `token` and returns its claims as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Throws if the token fails verification.
+    pub fn verify(&self, token: &str) -> Result<String, JsError> {
+        self.inner
+            .verify(token)
+            .and_then(|claims| to_json(&claims))
+            .map_err(to_js)
+    }
+
+    /// Returns true if a key is trusted for `trust_root`.
+    #[wasm_bindgen(js_name = hasTrustedRoot)]
+    #[must_use]
+    pub fn has_trusted_root(&self, trust_root: &str) -> bool {
+        self.inner.has_trusted_root(trust_root)
+    }
+
+    fn try_add_trusted_root(
+        &mut self,
+        trust_root: &str,
+        public_key: &[u8],
+    ) -> Result<(), AttestationError> {
+        let bytes: &[u8; 32] =
+            public_key.try_into().map_err(|_| AttestationError::InvalidKeyFormat {
+                reason: format!("expected 32 key bytes, got {}", public_key.len()),
+            })?;
+        self.inner.add_trusted_root(trust_root, VerifyingKey::from_bytes(bytes)?);
+        Ok(())
+    }
+}
+
+/// Decodes a token's claims as JSON without verifying its signature.
+///
+/// See [`AttestationClaims::decode_unverified`]; the result must not be
+/// trusted.
+///
+/// # Errors
+///
+/// Throws if the token is malformed.
+#[wasm_bindgen(js_name = decodeUnverified)]
+pub fn decode_unverified(token: &str) -> Result<String, JsError> {
+    AttestationClaims::decode_unverified(token)
+        .and_then(|claims| to_json(&claims))
+        .map_err(to_js)
+}
+
+fn to_json(claims: &AttestationClaims) -> Result<String, AttestationError> {
+    serde_json::to_string(claims).map_err(|e| AttestationError::InvalidClaims {
+        reason: e.to_string(),
+    })
+}
+
+// Takes the error by value to fit `map_err`
+#[allow(clippy::needless_pass_by_value)]
+fn to_js(error: AttestationError) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use agent_uri::AgentUri;
+
+    use super::*;
+    use crate::issuer::Issuer;
+
+    // Only success paths run natively: building a `JsError` needs a
+    // JavaScript host.
+
+    #[test]
+    fn verifies_with_raw_key_bytes() {
+        let issuer = Issuer::generate("acme.com", Duration::from_hours(1));
+        let uri =
+            AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+        let token = issuer.issue(&uri, vec!["read".into()]).unwrap();
+
+        let mut verifier = WasmVerifier::new();
+        verifier
+            .add_trusted_root("acme.com", &issuer.verifying_key().to_bytes())
+            .unwrap();
+        assert!(verifier.has_trusted_root("acme.com"));
+
+        let json = verifier.verify(&token).unwrap();
+        assert_eq!(json, decode_unverified(&token).unwrap());
+        let claims: AttestationClaims = serde_json::from_str(&json).unwrap();
+        assert_eq!(claims.capabilities, vec!["read"]);
+    }
+
+    #[test]
+    fn rejects_short_keys() {
+        let mut verifier = WasmVerifier::new();
+
+        assert!(matches!(
+            verifier.try_add_trusted_root("acme.com", &[0u8; 31]),
+            Err(AttestationError::InvalidKeyFormat { .. })
+        ));
+    }
+}
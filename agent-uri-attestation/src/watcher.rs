@@ -0,0 +1,238 @@
+//! Renewal scheduling for tokens held by long-running agents.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+
+use crate::claims::AttestationClaims;
+use crate::clock::{Clock, SharedClock};
+use crate::error::AttestationError;
+
+/// A held token that is due for renewal, as reported by
+/// [`TokenWatcher::due`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenewalNotice {
+    /// The name the token was watched under
+    pub name: String,
+    /// When the token became due for renewal
+    pub renew_after: DateTime<Utc>,
+    /// When the token expires
+    pub expires_at: DateTime<Utc>,
+    /// Whether the token has already expired and can no longer be renewed
+    /// with [`Issuer::renew`](crate::Issuer::renew)
+    pub expired: bool,
+}
+
+/// Tracks a set of held tokens and reports which ones should be renewed.
+///
+/// Each token is due once [`renew_fraction`](Self::renew_fraction) of its
+/// lifetime has passed, see [`AttestationClaims::renew_after`]. The watcher
+/// never renews anything itself: poll [`due`](Self::due), or sleep until
+/// [`next_renewal`](Self::next_renewal), then renew the reported tokens and
+/// [`watch`](Self::watch) the replacements under the same names.
+///
+/// Tokens are decoded without verification, so only watch tokens this
+/// agent obtained from a trusted issuer.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use agent_uri::AgentUri;
+/// use agent_uri_attestation::{Issuer, ManualClock, TokenWatcher};
+///
+/// let clock = ManualClock::new(chrono::Utc::now());
+/// let issuer = Issuer::generate("acme.com", Duration::from_secs(1000)).with_clock(clock.clone());
+/// let uri = AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap();
+///
+/// let mut watcher = TokenWatcher::new().with_clock(clock.clone());
+/// watcher.watch("primary", issuer.issue(&uri, vec![]).unwrap()).unwrap();
+/// assert!(watcher.due().is_empty());
+///
+/// clock.advance(Duration::from_secs(800));
+/// let due = watcher.due();
+/// assert_eq!(due[0].name, "primary");
+///
+/// let renewed = issuer.renew(watcher.token("primary").unwrap()).unwrap();
+/// watcher.watch("primary", renewed).unwrap();
+/// assert!(watcher.due().is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct TokenWatcher {
+    tokens: BTreeMap<String, (String, AttestationClaims)>,
+    renew_fraction: f64,
+    clock: SharedClock,
+}
+
+impl TokenWatcher {
+    /// Share of a token's lifetime after which it is due for renewal,
+    /// unless [`with_renew_fraction`](Self::with_renew_fraction) is used.
+    pub const DEFAULT_RENEW_FRACTION: f64 = 0.8;
+
+    /// Creates a watcher holding no tokens.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            tokens: BTreeMap::new(),
+            renew_fraction: Self::DEFAULT_RENEW_FRACTION,
+            clock: SharedClock::default(),
+        }
+    }
+
+    /// Sets the share of each token's lifetime after which it is due for
+    /// renewal; see [`AttestationClaims::renew_after`].
+    #[must_use]
+    pub fn with_renew_fraction(mut self, fraction: f64) -> Self {
+        self.renew_fraction = fraction;
+        self
+    }
+
+    /// Replaces the time source used to decide which tokens are due.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = SharedClock(Arc::new(clock));
+        self
+    }
+
+    /// Returns the share of a token's lifetime after which it is due.
+    #[must_use]
+    pub fn renew_fraction(&self) -> f64 {
+        self.renew_fraction
+    }
+
+    /// Watches `token` under `name`, replacing any token previously
+    /// watched under that name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AttestationError` if `token` is not a well-formed PASETO
+    /// attestation token; see [`AttestationClaims::decode_unverified`].
+    pub fn watch(
+        &mut self,
+        name: impl Into<String>,
+        token: impl Into<String>,
+    ) -> Result<(), AttestationError> {
+        let token = token.into();
+        let claims = AttestationClaims::decode_unverified(&token)?;
+        self.tokens.insert(name.into(), (token, claims));
+        Ok(())
+    }
+
+    /// Stops watching the token under `name`, returning it if there was one.
+    pub fn unwatch(&mut self, name: &str) -> Option<String> {
+        self.tokens.remove(name).map(|(token, _)| token)
+    }
+
+    /// Returns the token watched under `name`.
+    #[must_use]
+    pub fn token(&self, name: &str) -> Option<&str> {
+        self.tokens.get(name).map(|(token, _)| token.as_str())
+    }
+
+    /// Returns the claims of the token watched under `name`.
+    #[must_use]
+    pub fn claims(&self, name: &str) -> Option<&AttestationClaims> {
+        self.tokens.get(name).map(|(_, claims)| claims)
+    }
+
+    /// Returns the number of watched tokens.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Returns true if no tokens are watched.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Returns the tokens due for renewal now, soonest expiry first.
+    #[must_use]
+    pub fn due(&self) -> Vec<RenewalNotice> {
+        let now = self.clock.now();
+        let mut due: Vec<_> = self
+            .tokens
+            .iter()
+            .map(|(name, (_, claims))| RenewalNotice {
+                name: name.clone(),
+                renew_after: claims.renew_after(self.renew_fraction),
+                expires_at: claims.exp,
+                expired: claims.is_expired_at(now),
+            })
+            .filter(|notice| notice.renew_after <= now)
+            .collect();
+        due.sort_by_key(|notice| notice.expires_at);
+        due
+    }
+
+    /// Returns the earliest time any watched token is due for renewal,
+    /// which may already have passed.
+    #[must_use]
+    pub fn next_renewal(&self) -> Option<DateTime<Utc>> {
+        self.tokens
+            .values()
+            .map(|(_, claims)| claims.renew_after(self.renew_fraction))
+            .min()
+    }
+}
+
+impl Default for TokenWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::issuer::Issuer;
+    use agent_uri::AgentUri;
+    use std::time::Duration;
+
+    const MINUTE: Duration = Duration::from_mins(1);
+
+    fn uri() -> AgentUri {
+        AgentUri::parse("agent://acme.com/test/agent_01h455vb4pex5vsknk084sn02q").unwrap()
+    }
+
+    #[test]
+    fn due_tokens_are_ordered_by_expiry() {
+        let clock = ManualClock::new(Utc::now());
+        let issuer = Issuer::generate("acme.com", 10 * MINUTE).with_clock(clock.clone());
+        let mut watcher = TokenWatcher::new()
+            .with_renew_fraction(0.5)
+            .with_clock(clock.clone());
+        watcher
+            .watch("long", issuer.issue_with_ttl(&uri(), vec![], 20 * MINUTE).unwrap())
+            .unwrap();
+        watcher
+            .watch("short", issuer.issue(&uri(), vec![]).unwrap())
+            .unwrap();
+        let expected = clock.now() + chrono::Duration::minutes(5);
+        assert_eq!(watcher.next_renewal().map(|t| t.timestamp()), Some(expected.timestamp()));
+
+        clock.advance(5 * MINUTE);
+        let names = |w: &TokenWatcher| w.due().into_iter().map(|n| n.name).collect::<Vec<_>>();
+        assert_eq!(names(&watcher), ["short"]);
+
+        clock.advance(10 * MINUTE);
+        let due = watcher.due();
+        assert_eq!(names(&watcher), ["short", "long"]);
+        assert!(due[0].expired);
+        assert!(!due[1].expired);
+
+        assert!(watcher.unwatch("short").is_some());
+        assert_eq!(names(&watcher), ["long"]);
+    }
+
+    #[test]
+    fn malformed_tokens_are_rejected() {
+        let mut watcher = TokenWatcher::new();
+        assert!(watcher.watch("bad", "not-a-token").is_err());
+        assert!(watcher.is_empty());
+    }
+}
@@ -133,6 +133,14 @@ fn build_covering_capabilities(
     caps
 }
 
+/// Returns capabilities in the canonical form the claims builder issues:
+/// sorted, without duplicates.
+fn canonical(mut caps: Vec<String>) -> Vec<String> {
+    caps.sort();
+    caps.dedup();
+    caps
+}
+
 // ============================================================================
 // PROPTEST TESTS
 // ============================================================================
@@ -161,7 +169,7 @@ proptest! {
 
         // Verify round-trip
         let claims = verifier.verify(&token).unwrap();
-        prop_assert_eq!(claims.capabilities, caps);
+        prop_assert_eq!(claims.capabilities, canonical(caps));
     }
 
     /// Capability strings match grammar format
@@ -272,7 +280,7 @@ proptest! {
         let token = issuer.issue(&uri, caps.clone()).unwrap();
         let claims = verifier.verify(&token).unwrap();
 
-        prop_assert_eq!(claims.capabilities, caps);
+        prop_assert_eq!(claims.capabilities, canonical(caps));
     }
 }
 
@@ -404,7 +412,7 @@ fn capability_string_edge_cases() {
     let token = issuer.issue(&uri, caps.clone()).unwrap();
     let claims = verifier.verify(&token).unwrap();
 
-    assert_eq!(claims.capabilities, caps);
+    assert_eq!(claims.capabilities, canonical(caps));
 }
 
 /// Validates payload uses base64url encoding per grammar
@@ -549,7 +557,7 @@ proptest! {
 
         // Basic verify succeeds
         let claims = verifier.verify(&token).unwrap();
-        prop_assert_eq!(claims.capabilities, capabilities);
+        prop_assert_eq!(claims.capabilities, canonical(capabilities));
 
         // verify_for_uri succeeds
         let uri_claims = verifier.verify_for_uri(&token, &uri).unwrap();
@@ -3,8 +3,10 @@
 use std::time::Duration;
 
 use agent_uri::AgentUri;
+use agent_uri::CapabilityPath;
 use agent_uri_attestation::{
-    AttestationClaimsBuilder, AttestationError, Issuer, SigningKey, Verifier, VerifyingKey,
+    AttestationClaimsBuilder, AttestationError, CapabilityConstraints, CapabilityRequest, Issuer,
+    ManualClock, SigningKey, StatusList, VerificationPolicy, Verifier, VerifyingKey,
 };
 
 fn test_uri() -> AgentUri {
@@ -49,6 +51,42 @@ fn verify_for_uri_matches() {
     assert_eq!(claims.agent_uri, uri.to_string());
 }
 
+#[test]
+fn fleet_token_verifies_for_every_member() {
+    let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    let mut verifier = Verifier::new();
+    verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    let fleet: Vec<AgentUri> = ["q", "r", "s"]
+        .iter()
+        .map(|suffix| {
+            AgentUri::parse(&format!(
+                "agent://acme.com/fleet/drone_01h455vb4pex5vsknk084sn02{suffix}"
+            ))
+            .unwrap()
+        })
+        .collect();
+    let outsider =
+        AgentUri::parse("agent://acme.com/fleet/drone_01h455vb4pex5vsknk084sn02t").unwrap();
+
+    let token = issuer
+        .issue_fleet(&fleet, vec!["fleet.telemetry".into()])
+        .unwrap();
+
+    for uri in &fleet {
+        let claims = verifier.verify_for_uri(&token, uri).unwrap();
+        assert_eq!(claims.agent_uri, fleet[0].to_string());
+        assert_eq!(claims.agent_uris.len(), 2);
+    }
+    assert!(matches!(
+        verifier.verify_for_uri(&token, &outsider),
+        Err(AttestationError::UriMismatch { .. })
+    ));
+    assert!(matches!(
+        issuer.issue_fleet(&[], vec![]),
+        Err(AttestationError::MissingField { field: "agent_uri" })
+    ));
+}
+
 #[test]
 fn verify_for_uri_rejects_mismatch() {
     let signing_key = SigningKey::generate();
@@ -116,17 +154,18 @@ fn rejects_invalid_signature() {
 #[test]
 fn expired_token_rejected() {
     let signing_key = SigningKey::generate();
-    // Issue with very short TTL
-    let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_millis(1));
+    let clock = ManualClock::new(chrono::Utc::now());
+    let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(60))
+        .with_clock(clock.clone());
     let uri = test_uri();
 
-    let mut verifier = Verifier::new();
+    let mut verifier = Verifier::new().with_clock(clock.clone());
     verifier.add_trusted_root("acme.com", signing_key.verifying_key());
 
     let token = issuer.issue(&uri, vec![]).unwrap();
+    assert!(verifier.verify(&token).is_ok());
 
-    // Small delay to ensure expiration
-    std::thread::sleep(Duration::from_millis(50));
+    clock.advance(Duration::from_secs(61));
 
     let result = verifier.verify(&token);
 
@@ -137,6 +176,47 @@ fn expired_token_rejected() {
     );
 }
 
+#[test]
+fn not_yet_valid_token_accepted_once_clock_reaches_nbf() {
+    let start = chrono::Utc::now();
+    let clock = ManualClock::new(start);
+    let issuer = Issuer::generate("acme.com", Duration::from_secs(3600)).with_clock(clock.clone());
+    let claims = AttestationClaimsBuilder::new()
+        .agent_uri(test_uri().to_string())
+        .issuer("acme.com")
+        .not_before(start + chrono::Duration::minutes(5))
+        .build()
+        .unwrap();
+    let token = issuer.issue_claims(&claims).unwrap();
+
+    let mut verifier = Verifier::new().with_clock(clock.clone());
+    verifier.add_trusted_root("acme.com", issuer.verifying_key());
+    assert!(matches!(
+        verifier.verify(&token),
+        Err(AttestationError::TokenNotYetValid { .. })
+    ));
+
+    clock.advance(Duration::from_secs(300));
+    assert!(verifier.verify(&token).is_ok());
+}
+
+#[test]
+fn renewal_uses_issuer_clock() {
+    let clock = ManualClock::new(chrono::Utc::now());
+    let issuer = Issuer::generate("acme.com", Duration::from_secs(60)).with_clock(clock.clone());
+    let token = issuer.issue(&test_uri(), vec![]).unwrap();
+
+    clock.advance(Duration::from_secs(45));
+    let renewed = issuer.renew(&token).unwrap();
+
+    clock.advance(Duration::from_secs(45));
+    assert!(matches!(
+        issuer.renew(&token),
+        Err(AttestationError::TokenExpired { .. })
+    ));
+    assert!(issuer.renew(&renewed).is_ok());
+}
+
 #[test]
 fn claims_builder_requires_agent_uri() {
     let result = AttestationClaimsBuilder::new().issuer("acme.com").build();
@@ -174,10 +254,18 @@ fn multiple_capabilities() {
         "workflow.approval.admin".to_string(),
     ];
 
-    let token = issuer.issue(&uri, capabilities.clone()).unwrap();
+    let token = issuer.issue(&uri, capabilities).unwrap();
     let claims = verifier.verify(&token).unwrap();
 
-    assert_eq!(claims.capabilities, capabilities);
+    // Capabilities are issued in canonical (sorted) order
+    assert_eq!(
+        claims.capabilities,
+        [
+            "workflow.approval.admin",
+            "workflow.approval.execute",
+            "workflow.approval.read",
+        ]
+    );
 }
 
 #[test]
@@ -297,3 +385,173 @@ fn verifier_checks_trusted_root_existence() {
     assert!(verifier.has_trusted_root("acme.com"));
     assert!(!verifier.has_trusted_root("other.com"));
 }
+
+#[test]
+fn verify_with_policy_accepts_compliant_token() {
+    let signing_key = SigningKey::generate();
+    let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(3600));
+
+    let claims = AttestationClaimsBuilder::new()
+        .agent_uri(test_uri().to_string())
+        .issuer("acme.com")
+        .add_capability("workflow")
+        .audience("api.acme.com")
+        .not_before(chrono::Utc::now() - chrono::Duration::seconds(5))
+        .ttl(Duration::from_secs(600))
+        .build()
+        .unwrap();
+    let token = issuer.issue_claims(&claims).unwrap();
+
+    let mut verifier = Verifier::new();
+    verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+    let policy = VerificationPolicy::builder()
+        .require_audience("api.acme.com")
+        .max_ttl(Duration::from_secs(900))
+        .require_capability(CapabilityPath::parse("workflow/approval").unwrap())
+        .allow_trust_root("acme.com")
+        .require_not_before()
+        .require_issuer_binding()
+        .build();
+
+    let verified = verifier.verify_with_policy(&token, &policy).unwrap();
+    assert_eq!(verified.aud, Some("api.acme.com".to_string()));
+    assert!(verified.nbf.is_some());
+}
+
+#[test]
+fn verify_with_policy_rejects_excessive_ttl() {
+    let signing_key = SigningKey::generate();
+    let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(86400));
+    let uri = test_uri();
+
+    let mut verifier = Verifier::new();
+    verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+    let token = issuer.issue(&uri, vec![]).unwrap();
+    let policy = VerificationPolicy::builder()
+        .max_ttl(Duration::from_secs(3600))
+        .build();
+    let result = verifier.verify_with_policy(&token, &policy);
+
+    assert!(matches!(
+        result,
+        Err(AttestationError::TtlExceedsMaximum { .. })
+    ));
+}
+
+#[test]
+fn constrained_capabilities_survive_round_trip() {
+    let signing_key = SigningKey::generate();
+    let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(3600));
+    let uri = test_uri();
+
+    let claims = AttestationClaimsBuilder::new()
+        .agent_uri(uri.to_string())
+        .issuer("acme.com")
+        .add_capability("workflow/read")
+        .add_constrained_capability(
+            "workflow/approval",
+            CapabilityConstraints::new()
+                .limit("max_amount", 1000)
+                .resource("invoices"),
+        )
+        .build()
+        .unwrap();
+    let token = issuer.issue_claims(&claims).unwrap();
+
+    let mut verifier = Verifier::new();
+    verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+    let verified = verifier.verify(&token).unwrap();
+
+    assert_eq!(verified.capability_constraints, claims.capability_constraints);
+
+    let path = CapabilityPath::parse("workflow/approval").unwrap();
+    let within = CapabilityRequest::new(path.clone(), chrono::Utc::now())
+        .resource("invoices/2024/17")
+        .quantity("amount", 999);
+    let over = CapabilityRequest::new(path.clone(), chrono::Utc::now())
+        .resource("invoices/2024/17")
+        .quantity("amount", 1001);
+
+    assert!(verified.check_capability(&within).is_ok());
+    assert!(matches!(
+        verified.check_capability(&over),
+        Err(AttestationError::CapabilityConstraintViolated { .. })
+    ));
+
+    // Resource-scoped capabilities fail closed without request context
+    assert!(matches!(
+        verifier.verify_for_capability(&token, &uri, &path),
+        Err(AttestationError::CapabilityConstraintViolated { .. })
+    ));
+}
+
+#[test]
+fn denied_capabilities_exclude_subtree() {
+    let signing_key = SigningKey::generate();
+    let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(3600));
+    let uri = test_uri();
+
+    let claims = AttestationClaimsBuilder::new()
+        .agent_uri(uri.to_string())
+        .issuer("acme.com")
+        .add_capability("workflow")
+        .deny_capability("workflow/payments")
+        .build()
+        .unwrap();
+    let token = issuer.issue_claims(&claims).unwrap();
+
+    let mut verifier = Verifier::new();
+    verifier.add_trusted_root("acme.com", signing_key.verifying_key());
+
+    let approval = CapabilityPath::parse("workflow/approval").unwrap();
+    let refund = CapabilityPath::parse("workflow/payments/refund").unwrap();
+
+    let verified = verifier
+        .verify_for_capability(&token, &uri, &approval)
+        .unwrap();
+    assert_eq!(verified.denied_capabilities, vec!["workflow/payments"]);
+    assert!(matches!(
+        verifier.verify_for_capability(&token, &uri, &refund),
+        Err(AttestationError::CapabilityDenied { .. })
+    ));
+}
+
+#[test]
+fn status_list_revokes_indexed_token() {
+    let issuer = Issuer::generate("acme.com", Duration::from_secs(3600));
+    let uri = test_uri();
+
+    let issue = |index| {
+        let claims = AttestationClaimsBuilder::new()
+            .agent_uri(uri.to_string())
+            .issuer("acme.com")
+            .add_capability("workflow.approval.read")
+            .status_index(index)
+            .build()
+            .unwrap();
+        issuer.issue_claims(&claims).unwrap()
+    };
+    let kept = issue(1);
+    let revoked = issue(2);
+    let unindexed = issuer.issue(&uri, vec!["workflow.approval.read".into()]).unwrap();
+
+    let mut list = StatusList::new(128);
+    list.revoke(2).unwrap();
+    let status = issuer
+        .issue_status_list(&list, Duration::from_secs(300))
+        .unwrap();
+
+    let mut verifier = Verifier::new().with_status_list_source(move |iss: &str| {
+        (iss == "acme.com").then(|| status.clone())
+    });
+    verifier.add_trusted_root("acme.com", issuer.verifying_key());
+
+    assert!(verifier.verify_for_uri(&kept, &uri).is_ok());
+    assert!(verifier.verify(&unindexed).is_ok());
+    assert_eq!(
+        verifier.verify(&revoked),
+        Err(AttestationError::TokenRevoked { index: 2 })
+    );
+}
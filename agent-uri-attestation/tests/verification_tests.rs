@@ -9,7 +9,8 @@ use agent_uri::AgentUri;
 use agent_uri::CapabilityPath;
 use agent_uri_attestation::{
     capability_covers, check_capability_coverage, check_expiration, validate_issuer,
-    validate_subject, AttestationClaimsBuilder, AttestationError, Issuer, SigningKey, Verifier,
+    validate_subject, AttestationClaimsBuilder, AttestationError, Issuer, ManualClock, SigningKey,
+    Verifier,
 };
 use chrono::{Duration as ChronoDuration, Utc};
 
@@ -115,14 +116,14 @@ mod check_capability_coverage_tests {
     fn returns_ok_when_covered() {
         let attested = vec!["workflow".to_string()];
         let required = CapabilityPath::parse("workflow/approval").unwrap();
-        assert!(check_capability_coverage(&attested, &required).is_ok());
+        assert!(check_capability_coverage(&attested, &[], &required).is_ok());
     }
 
     #[test]
     fn returns_err_with_details_when_not_covered() {
         let attested = vec!["assistant/chat".to_string()];
         let required = CapabilityPath::parse("workflow/approval").unwrap();
-        let result = check_capability_coverage(&attested, &required);
+        let result = check_capability_coverage(&attested, &[], &required);
         match result {
             Err(AttestationError::InsufficientCapabilities {
                 required: req,
@@ -139,7 +140,7 @@ mod check_capability_coverage_tests {
     fn empty_capabilities_returns_err() {
         let attested: Vec<String> = vec![];
         let required = CapabilityPath::parse("workflow").unwrap();
-        let result = check_capability_coverage(&attested, &required);
+        let result = check_capability_coverage(&attested, &[], &required);
         assert!(matches!(
             result,
             Err(AttestationError::InsufficientCapabilities { .. })
@@ -477,16 +478,16 @@ mod verify_for_capability_tests {
     #[test]
     fn expired_token_fails_before_capability_check() {
         let signing_key = SigningKey::generate();
-        // Very short TTL
-        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_millis(1));
+        let clock = ManualClock::new(Utc::now());
+        let issuer = Issuer::new("acme.com", signing_key.clone(), Duration::from_secs(60))
+            .with_clock(clock.clone());
         let uri = test_uri();
 
         let token = issuer.issue(&uri, vec!["workflow".into()]).unwrap();
 
-        // Wait for expiration
-        std::thread::sleep(Duration::from_millis(50));
+        clock.advance(Duration::from_secs(61));
 
-        let mut verifier = Verifier::new();
+        let mut verifier = Verifier::new().with_clock(clock);
         verifier.add_trusted_root("acme.com", signing_key.verifying_key());
 
         let required = CapabilityPath::parse("workflow").unwrap();
@@ -14,11 +14,14 @@
 //! | SimulatedDht register | Fast | Single agent registration |
 //! | SimulatedDht lookup_exact | Fast | From populated DHT |
 //! | SimulatedDht lookup_prefix | Scales | With result count |
+//! | SimulatedDht concurrent lookup | Flat | More shards keep writers off readers |
+
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
 
 use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
-use agent_uri_dht::{Dht, DhtKey, Endpoint, PathTrie, Registration, SimulatedDht};
+use agent_uri_dht::{Dht, DhtKey, Endpoint, PathTrie, Registration, SimulatedDht, SimulationConfig};
 
 // ============================================================================
 // DhtKey Benchmarks
@@ -332,6 +335,56 @@ fn bench_simulated_dht_lookup_prefix(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmarks `SimulatedDht::lookup_exact()` while two writer threads keep
+/// registering and deregistering agents, with one shard and with sixteen.
+///
+/// Expected: With sixteen shards lookups rarely wait on the writers.
+fn bench_simulated_dht_concurrent_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simulated_dht/concurrent_lookup");
+    let trust_root = TrustRoot::parse("anthropic.com").expect("valid");
+    let path = CapabilityPath::parse("cat0/sub0").expect("valid path");
+
+    for shards in [1, 16] {
+        let dht = SimulatedDht::new(SimulationConfig::new().with_shards(shards));
+        for i in 0..1000 {
+            let registration = Registration::new(
+                make_agent_uri(i),
+                vec![Endpoint::https(format!("agent{i}.anthropic.com"))],
+            );
+            let _ = dht.register(registration);
+        }
+
+        let stop = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            for writer in 0..2 {
+                let (dht, stop) = (&dht, &stop);
+                scope.spawn(move || {
+                    let mut i = 0;
+                    while !stop.load(Ordering::Relaxed) {
+                        let uri = AgentUri::parse(&format!(
+                            "agent://writer{writer}.com/churn/llm_01h455vb4pex5vsknk08{:04x}qq",
+                            i % 0x1000
+                        ))
+                        .expect("valid URI");
+                        let registration =
+                            Registration::new(uri.clone(), vec![Endpoint::https("w.example.com")]);
+                        let _ = dht.register(registration);
+                        let _ = dht.deregister(&uri);
+                        i += 1;
+                    }
+                });
+            }
+
+            group.bench_with_input(BenchmarkId::new("shards", shards), &shards, |b, _| {
+                b.iter(|| dht.lookup_exact(&trust_root, &path).expect("lookup succeeds"));
+            });
+            stop.store(true, Ordering::Relaxed);
+        });
+    }
+
+    group.finish();
+}
+
 // ============================================================================
 // Memory Benchmarks
 // ============================================================================
@@ -412,6 +465,7 @@ criterion_group!(
     bench_simulated_dht_register,
     bench_simulated_dht_lookup_exact,
     bench_simulated_dht_lookup_prefix,
+    bench_simulated_dht_concurrent_lookup,
     bench_memory_per_registration,
     bench_dht_memory_scaling,
 );
@@ -0,0 +1,13 @@
+//! Generates the gRPC registry protocol (feature `grpc`).
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        // A vendored protoc, so building needs no system install
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("no vendored protoc");
+        // SAFETY: build scripts are single-threaded
+        unsafe { std::env::set_var("PROTOC", protoc) };
+        tonic_prost_build::compile_protos("proto/registry.proto")
+            .expect("failed to compile proto/registry.proto");
+    }
+}
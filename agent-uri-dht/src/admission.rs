@@ -0,0 +1,345 @@
+//! Spam-resistant admission: proof of work or attestation.
+
+use std::sync::{Arc, RwLock};
+
+use agent_uri::AgentUri;
+use sha2::{Digest, Sha256};
+
+use crate::{DhtError, DhtKey, Registration, RegistrationValidator};
+
+/// A hashcash-style proof-of-work challenge bound to a registry-issued
+/// salt and to a registration's [`DhtKey`] and agent URI.
+///
+/// A nonce solves the challenge when SHA-256 over a domain tag, the salt,
+/// the key, the agent URI and the nonce has at least `difficulty` leading
+/// zero bits. Solving takes about 2^`difficulty` hashes; checking takes
+/// one. Because the agent URI is hashed in, a proof cannot be reused for
+/// another agent, even one registered under the same key. Because the salt
+/// is hashed in, a proof stops verifying once the registry issues a new
+/// salt; under a fixed salt a solution can be replayed indefinitely.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{Endpoint, ProofOfWork, Registration};
+///
+/// let pow = ProofOfWork::new(8).with_salt([7; 32]);
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let nonce = pow.solve(&uri).unwrap();
+/// assert!(pow.verify(&uri, nonce));
+///
+/// let registration = Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+///     .with_proof_of_work(nonce);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProofOfWork {
+    difficulty: u32,
+    salt: [u8; 32],
+}
+
+impl ProofOfWork {
+    /// Domain separation tag hashed ahead of every challenge.
+    const DOMAIN: &'static [u8] = b"agent-uri-dht/pow/v1";
+
+    /// Largest satisfiable difficulty: every bit of the SHA-256 digest.
+    pub const MAX_DIFFICULTY: u32 = 256;
+
+    /// Creates a challenge requiring `difficulty` leading zero bits, with an
+    /// all-zero salt.
+    ///
+    /// Each extra bit doubles the expected work; 20 takes about a second
+    /// on one core.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `difficulty` exceeds [`Self::MAX_DIFFICULTY`], which no
+    /// nonce could ever meet.
+    #[must_use]
+    pub const fn new(difficulty: u32) -> Self {
+        assert!(
+            difficulty <= Self::MAX_DIFFICULTY,
+            "proof-of-work difficulty cannot exceed 256 bits"
+        );
+        Self {
+            difficulty,
+            salt: [0; 32],
+        }
+    }
+
+    /// Sets the registry-issued salt proofs are bound to.
+    #[must_use]
+    pub const fn with_salt(mut self, salt: [u8; 32]) -> Self {
+        self.salt = salt;
+        self
+    }
+
+    /// Returns the required leading zero bits.
+    #[must_use]
+    pub const fn difficulty(&self) -> u32 {
+        self.difficulty
+    }
+
+    /// Returns the salt proofs are bound to.
+    #[must_use]
+    pub const fn salt(&self) -> &[u8; 32] {
+        &self.salt
+    }
+
+    /// Finds the smallest nonce solving the challenge for `agent_uri`, or
+    /// `None` if no 64-bit nonce does.
+    ///
+    /// Below the top difficulties a solution exists with overwhelming
+    /// probability, long before the search space runs out.
+    #[must_use]
+    pub fn solve(&self, agent_uri: &AgentUri) -> Option<u64> {
+        let prefix = self.prefix(agent_uri);
+        (0..=u64::MAX).find(|&nonce| Self::hash(&prefix, nonce).leading_zeros() >= self.difficulty)
+    }
+
+    /// Returns true if `nonce` solves the challenge for `agent_uri`.
+    #[must_use]
+    pub fn verify(&self, agent_uri: &AgentUri, nonce: u64) -> bool {
+        Self::hash(&self.prefix(agent_uri), nonce).leading_zeros() >= self.difficulty
+    }
+
+    /// Returns the hasher state shared by every nonce for `agent_uri`.
+    fn prefix(&self, agent_uri: &AgentUri) -> Sha256 {
+        let key = DhtKey::derive(agent_uri.trust_root(), agent_uri.capability_path());
+        let mut hasher = Sha256::new();
+        hasher.update(Self::DOMAIN);
+        hasher.update(self.salt);
+        hasher.update(key.as_bytes());
+        hasher.update(agent_uri.as_str().as_bytes());
+        hasher
+    }
+
+    fn hash(prefix: &Sha256, nonce: u64) -> DhtKey {
+        let digest = prefix.clone().chain_update(nonce.to_le_bytes()).finalize();
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&digest);
+        DhtKey::from_bytes(bytes)
+    }
+}
+
+/// Admission control for open registries: each registration must carry a
+/// valid attestation or solve a [`ProofOfWork`].
+///
+/// The difficulty is set per deployment: enough to make registering
+/// thousands of spam agents expensive, while a legitimate agent pays once
+/// per salt. The registry publishes the current challenge from
+/// [`proof_of_work`](Self::proof_of_work) and replaces the salt with
+/// [`rotate_salt`](Self::rotate_salt) on its own schedule; proofs solved
+/// under an earlier salt are then rejected, so a solution cannot be
+/// replayed past one rotation period. Registrations whose attestation the
+/// configured validator accepts skip the work entirely.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{AdmissionPolicy, Dht, Endpoint, ProofOfWork, Registration, SimulatedDht};
+///
+/// let policy = AdmissionPolicy::new(8);
+/// policy.rotate_salt([7; 32]);
+/// let dht = SimulatedDht::with_defaults().with_validator(policy.clone());
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let registration = Registration::new(uri.clone(), vec![Endpoint::https("agent.acme.com")]);
+/// assert!(dht.register(registration.clone()).is_err());
+///
+/// let challenge: ProofOfWork = policy.proof_of_work();
+/// let nonce = challenge.solve(&uri).unwrap();
+/// assert!(dht.register(registration.with_proof_of_work(nonce)).is_ok());
+/// ```
+#[derive(Clone)]
+pub struct AdmissionPolicy {
+    proof_of_work: Arc<RwLock<ProofOfWork>>,
+    attestation: Option<Arc<dyn RegistrationValidator>>,
+}
+
+impl AdmissionPolicy {
+    /// Creates a policy requiring a proof of work of `difficulty` leading
+    /// zero bits, with an all-zero salt until the first
+    /// [`rotate_salt`](Self::rotate_salt).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `difficulty` exceeds [`ProofOfWork::MAX_DIFFICULTY`].
+    #[must_use]
+    pub fn new(difficulty: u32) -> Self {
+        Self {
+            proof_of_work: Arc::new(RwLock::new(ProofOfWork::new(difficulty))),
+            attestation: None,
+        }
+    }
+
+    /// Also admits registrations that carry an attestation `validator`
+    /// accepts, without a proof of work.
+    ///
+    /// Use `agent_uri_attestation::Verifier` as the validator.
+    #[must_use]
+    pub fn or_attestation(mut self, validator: impl RegistrationValidator + 'static) -> Self {
+        self.attestation = Some(Arc::new(validator));
+        self
+    }
+
+    /// Returns the current proof-of-work challenge, salt included.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the challenge lock is poisoned.
+    #[must_use]
+    pub fn proof_of_work(&self) -> ProofOfWork {
+        *self.proof_of_work.read().expect("lock poisoned")
+    }
+
+    /// Replaces the salt proofs must be bound to, invalidating every proof
+    /// solved under the previous one.
+    ///
+    /// Clones of the policy share the salt, so a registry can keep a clone
+    /// to rotate after handing the policy to a DHT.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the challenge lock is poisoned.
+    pub fn rotate_salt(&self, salt: [u8; 32]) {
+        let mut proof_of_work = self.proof_of_work.write().expect("lock poisoned");
+        *proof_of_work = proof_of_work.with_salt(salt);
+    }
+}
+
+impl std::fmt::Debug for AdmissionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdmissionPolicy")
+            .field("proof_of_work", &self.proof_of_work())
+            .field("attestation", &self.attestation.is_some())
+            .finish()
+    }
+}
+
+impl RegistrationValidator for AdmissionPolicy {
+    /// Admits `registration` if its proof of work verifies, or if it is
+    /// attested and the attestation validator accepts it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the attestation validator's error if the registration is
+    /// attested but neither check passes, and
+    /// `DhtError::InsufficientProofOfWork` otherwise.
+    fn validate(&self, registration: &Registration) -> Result<(), DhtError> {
+        let agent_uri = registration.agent_uri();
+        let proof_of_work = self.proof_of_work();
+        if registration
+            .proof_of_work()
+            .is_some_and(|nonce| proof_of_work.verify(agent_uri, nonce))
+        {
+            return Ok(());
+        }
+
+        match &self.attestation {
+            Some(validator) if registration.attestation().is_some() => {
+                validator.validate(registration)
+            }
+            _ => Err(DhtError::insufficient_proof_of_work(
+                agent_uri.as_str(),
+                proof_of_work.difficulty(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Endpoint;
+
+    fn uri(suffix: &str) -> AgentUri {
+        AgentUri::parse(&format!(
+            "agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn0{suffix}"
+        ))
+        .unwrap()
+    }
+
+    fn registration(suffix: &str) -> Registration {
+        Registration::new(uri(suffix), vec![Endpoint::https("agent.acme.com")])
+    }
+
+    fn attested(registration: &Registration) -> Result<(), DhtError> {
+        match registration.attestation() {
+            Some("valid") => Ok(()),
+            _ => Err(DhtError::invalid_attestation(
+                registration.agent_uri().as_str(),
+                "bad token",
+            )),
+        }
+    }
+
+    #[test]
+    fn proofs_are_bound_to_the_agent() {
+        let pow = ProofOfWork::new(10);
+        let nonce = pow.solve(&uri("2q")).unwrap();
+        assert!(pow.verify(&uri("2q"), nonce));
+        assert_ne!(pow.solve(&uri("3q")), Some(nonce));
+        assert!(ProofOfWork::new(0).verify(&uri("2q"), 12345));
+    }
+
+    #[test]
+    fn proofs_are_bound_to_the_salt() {
+        let pow = ProofOfWork::new(12).with_salt([1; 32]);
+        let nonce = pow.solve(&uri("2q")).unwrap();
+        assert!(pow.verify(&uri("2q"), nonce));
+        assert!(!pow.with_salt([2; 32]).verify(&uri("2q"), nonce));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot exceed 256 bits")]
+    fn unsatisfiable_difficulty_is_rejected() {
+        let _ = ProofOfWork::new(ProofOfWork::MAX_DIFFICULTY + 1);
+    }
+
+    #[test]
+    fn rotating_the_salt_rejects_replayed_proofs() {
+        let policy = AdmissionPolicy::new(12);
+        let registry = policy.clone();
+        let nonce = policy.proof_of_work().solve(&uri("2q")).unwrap();
+        assert!(policy.validate(&registration("2q").with_proof_of_work(nonce)).is_ok());
+
+        registry.rotate_salt([9; 32]);
+        assert_eq!(policy.proof_of_work().salt(), &[9; 32]);
+        let replayed = registration("2q").with_proof_of_work(nonce);
+        assert!(matches!(
+            policy.validate(&replayed),
+            Err(DhtError::InsufficientProofOfWork { difficulty: 12, .. })
+        ));
+    }
+
+    #[test]
+    fn policy_requires_work_without_attestation() {
+        let policy = AdmissionPolicy::new(8);
+        let err = policy.validate(&registration("2q")).unwrap_err();
+        assert_eq!(err, DhtError::insufficient_proof_of_work(uri("2q").as_str(), 8));
+
+        let nonce = policy.proof_of_work().solve(&uri("2q")).unwrap();
+        assert!(policy.validate(&registration("2q").with_proof_of_work(nonce)).is_ok());
+        let stolen = registration("3q").with_proof_of_work(nonce);
+        assert_eq!(
+            policy.validate(&stolen).is_ok(),
+            policy.proof_of_work().verify(&uri("3q"), nonce)
+        );
+    }
+
+    #[test]
+    fn valid_attestation_skips_the_work() {
+        let policy = AdmissionPolicy::new(24).or_attestation(attested);
+        assert!(policy.validate(&registration("2q").with_attestation("valid")).is_ok());
+
+        let err = policy
+            .validate(&registration("2q").with_attestation("forged"))
+            .unwrap_err();
+        assert!(matches!(err, DhtError::InvalidAttestation { .. }));
+        assert!(matches!(
+            policy.validate(&registration("2q")),
+            Err(DhtError::InsufficientProofOfWork { difficulty: 24, .. })
+        ));
+    }
+}
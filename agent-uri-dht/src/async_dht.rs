@@ -0,0 +1,860 @@
+//! Async DHT trait for network-backed implementations.
+
+use std::future::Future;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+
+use crate::{
+    Dht, DhtError, DhtEvent, DhtHealth, DhtStats, Endpoint, LookupCursor, LookupFilter,
+    LookupPage, PathPattern, Registration, TrustRootGroup,
+};
+
+/// Async counterpart of [`Dht`].
+///
+/// Network backends such as a libp2p Kademlia node or a remote registry
+/// should implement this trait rather than blocking inside [`Dht`]. Every
+/// [`Dht`] is also an `AsyncDht` whose futures run the synchronous
+/// operation when first polled, so code written against `AsyncDht` accepts
+/// in-memory implementations unchanged. With the `tokio` feature,
+/// [`TokioDht`] instead moves each operation onto tokio's blocking thread
+/// pool, for synchronous implementations that may block.
+///
+/// The methods mirror those of [`Dht`] and fail with the same errors.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+/// use agent_uri_dht::{AsyncDht, Endpoint, Registration, SimulatedDht};
+///
+/// async fn announce(dht: &impl AsyncDht, uri: AgentUri) -> usize {
+///     let registration = Registration::new(uri, vec![Endpoint::https("agent.acme.com")]);
+///     dht.register(registration).await.unwrap();
+///     dht.lookup_prefix(
+///         &TrustRoot::parse("acme.com").unwrap(),
+///         &CapabilityPath::parse("assistant").unwrap(),
+///     )
+///     .await
+///     .unwrap()
+///     .len()
+/// }
+///
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let dht = SimulatedDht::with_defaults();
+/// # let found = tokio::runtime::Builder::new_current_thread()
+/// #     .build()
+/// #     .unwrap()
+/// #     .block_on(announce(&dht, uri));
+/// # assert_eq!(found, 1);
+/// ```
+pub trait AsyncDht: Send + Sync {
+    /// Registers an agent at its capability path; see [`Dht::register`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the registration is rejected.
+    fn register(
+        &self,
+        registration: Registration,
+    ) -> impl Future<Output = Result<(), DhtError>> + Send;
+
+    /// Replaces an existing registration's endpoints; see
+    /// [`Dht::update_endpoint`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the agent is not registered, its registration
+    /// has expired, or `new_endpoints` is empty.
+    fn update_endpoint(
+        &self,
+        agent_uri: &AgentUri,
+        new_endpoints: Vec<Endpoint>,
+    ) -> impl Future<Output = Result<(), DhtError>> + Send;
+
+    /// Replaces an existing registration with a newer version of it; see
+    /// [`Dht::update_registration`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the agent is not registered, either version
+    /// has expired, or the update's sequence number is stale or conflicts
+    /// with the stored one.
+    fn update_registration(
+        &self,
+        registration: Registration,
+    ) -> impl Future<Output = Result<(), DhtError>> + Send;
+
+    /// Extends a registration so it expires `ttl` from now; see
+    /// [`Dht::renew`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the agent is not registered or its
+    /// registration has already expired.
+    fn renew(
+        &self,
+        agent_uri: &AgentUri,
+        ttl: Duration,
+    ) -> impl Future<Output = Result<(), DhtError>> + Send;
+
+    /// Removes a registration; see [`Dht::deregister`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::NotFound` if the agent is not registered.
+    fn deregister(&self, agent_uri: &AgentUri) -> impl Future<Output = Result<(), DhtError>> + Send;
+
+    /// Looks up agents at an exact capability path; see
+    /// [`Dht::lookup_exact`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_exact(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send;
+
+    /// Looks up agents at several exact capability paths in one call; see
+    /// [`Dht::lookup_many`].
+    fn lookup_many(
+        &self,
+        queries: &[(TrustRoot, CapabilityPath)],
+    ) -> impl Future<Output = Vec<Result<Vec<Registration>, DhtError>>> + Send;
+
+    /// Looks up agents at a capability path and all child paths; see
+    /// [`Dht::lookup_prefix`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send;
+
+    /// Looks up one page of agents at a capability path and all child
+    /// paths; see [`Dht::lookup_prefix_paged`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_prefix_paged(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> impl Future<Output = Result<LookupPage, DhtError>> + Send;
+
+    /// Looks up agents at a capability path across all trust roots; see
+    /// [`Dht::lookup_global`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_global(
+        &self,
+        capability_path: &CapabilityPath,
+    ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send;
+
+    /// Looks up agents matching a path pattern within a trust root; see
+    /// [`Dht::lookup_pattern`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_pattern(
+        &self,
+        trust_root: &TrustRoot,
+        pattern: &PathPattern,
+    ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send;
+
+    /// Looks up agents matching a path pattern across all trust roots; see
+    /// [`Dht::lookup_pattern_global`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_pattern_global(
+        &self,
+        pattern: &PathPattern,
+    ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send;
+
+    /// Looks up every agent registered under a trust root; see
+    /// [`Dht::lookup_trust_root`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_trust_root(
+        &self,
+        trust_root: &TrustRoot,
+    ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send;
+
+    /// Looks up one page of the agents registered under a trust root; see
+    /// [`Dht::lookup_trust_root_paged`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_trust_root_paged(
+        &self,
+        trust_root: &TrustRoot,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> impl Future<Output = Result<LookupPage, DhtError>> + Send;
+
+    /// Subscribes to changes of registrations under a capability prefix;
+    /// see [`Dht::watch_prefix`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if change notifications are unavailable.
+    fn watch_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> impl Future<Output = Result<Receiver<DhtEvent>, DhtError>> + Send;
+
+    /// Looks up agents at an exact capability path that match `filter`;
+    /// see [`Dht::lookup_exact_filtered`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_exact_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send;
+
+    /// Looks up agents at a capability path and all child paths that match
+    /// `filter`; see [`Dht::lookup_prefix_filtered`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_prefix_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send;
+
+    /// Looks up one page of agents at a capability path and all child paths
+    /// that match `filter`; see [`Dht::lookup_prefix_paged_filtered`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_prefix_paged_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+        filter: &LookupFilter,
+    ) -> impl Future<Output = Result<LookupPage, DhtError>> + Send;
+
+    /// Looks up agents at a capability path across all trust roots that
+    /// match `filter`; see [`Dht::lookup_global_filtered`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_global_filtered(
+        &self,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send;
+
+    /// Looks up agents at a capability path across all trust roots, grouped
+    /// by trust root; see [`Dht::lookup_global_grouped`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the lookup fails.
+    fn lookup_global_grouped(
+        &self,
+        capability_path: &CapabilityPath,
+        per_root_limit: Option<usize>,
+    ) -> impl Future<Output = Result<Vec<TrustRootGroup>, DhtError>> + Send;
+
+    /// Checks that the backend is reachable; see [`Dht::ping`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::Backend` if the backend cannot be reached.
+    fn ping(&self) -> impl Future<Output = Result<(), DhtError>> + Send;
+
+    /// Reports whether the backend is reachable; see [`Dht::health`].
+    fn health(&self) -> impl Future<Output = DhtHealth> + Send;
+
+    /// Returns statistics about the registrations stored; see
+    /// [`Dht::stats`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the statistics cannot be gathered.
+    fn stats(&self) -> impl Future<Output = Result<DhtStats, DhtError>> + Send;
+}
+
+impl<D: Dht> AsyncDht for D {
+    async fn register(&self, registration: Registration) -> Result<(), DhtError> {
+        Dht::register(self, registration)
+    }
+
+    async fn update_endpoint(
+        &self,
+        agent_uri: &AgentUri,
+        new_endpoints: Vec<Endpoint>,
+    ) -> Result<(), DhtError> {
+        Dht::update_endpoint(self, agent_uri, new_endpoints)
+    }
+
+    async fn update_registration(&self, registration: Registration) -> Result<(), DhtError> {
+        Dht::update_registration(self, registration)
+    }
+
+    async fn renew(&self, agent_uri: &AgentUri, ttl: Duration) -> Result<(), DhtError> {
+        Dht::renew(self, agent_uri, ttl)
+    }
+
+    async fn deregister(&self, agent_uri: &AgentUri) -> Result<(), DhtError> {
+        Dht::deregister(self, agent_uri)
+    }
+
+    async fn lookup_exact(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Dht::lookup_exact(self, trust_root, capability_path)
+    }
+
+    async fn lookup_many(
+        &self,
+        queries: &[(TrustRoot, CapabilityPath)],
+    ) -> Vec<Result<Vec<Registration>, DhtError>> {
+        Dht::lookup_many(self, queries)
+    }
+
+    async fn lookup_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Dht::lookup_prefix(self, trust_root, capability_path)
+    }
+
+    async fn lookup_prefix_paged(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        Dht::lookup_prefix_paged(self, trust_root, capability_path, limit, cursor)
+    }
+
+    async fn lookup_global(
+        &self,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Dht::lookup_global(self, capability_path)
+    }
+
+    async fn lookup_pattern(
+        &self,
+        trust_root: &TrustRoot,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Dht::lookup_pattern(self, trust_root, pattern)
+    }
+
+    async fn lookup_pattern_global(
+        &self,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Dht::lookup_pattern_global(self, pattern)
+    }
+
+    async fn lookup_trust_root(
+        &self,
+        trust_root: &TrustRoot,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Dht::lookup_trust_root(self, trust_root)
+    }
+
+    async fn lookup_trust_root_paged(
+        &self,
+        trust_root: &TrustRoot,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        Dht::lookup_trust_root_paged(self, trust_root, limit, cursor)
+    }
+
+    async fn watch_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Receiver<DhtEvent>, DhtError> {
+        Dht::watch_prefix(self, trust_root, capability_path)
+    }
+
+    async fn lookup_exact_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Dht::lookup_exact_filtered(self, trust_root, capability_path, filter)
+    }
+
+    async fn lookup_prefix_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Dht::lookup_prefix_filtered(self, trust_root, capability_path, filter)
+    }
+
+    async fn lookup_prefix_paged_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+        filter: &LookupFilter,
+    ) -> Result<LookupPage, DhtError> {
+        Dht::lookup_prefix_paged_filtered(self, trust_root, capability_path, limit, cursor, filter)
+    }
+
+    async fn lookup_global_filtered(
+        &self,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Dht::lookup_global_filtered(self, capability_path, filter)
+    }
+
+    async fn lookup_global_grouped(
+        &self,
+        capability_path: &CapabilityPath,
+        per_root_limit: Option<usize>,
+    ) -> Result<Vec<TrustRootGroup>, DhtError> {
+        Dht::lookup_global_grouped(self, capability_path, per_root_limit)
+    }
+
+    async fn ping(&self) -> Result<(), DhtError> {
+        Dht::ping(self)
+    }
+
+    async fn health(&self) -> DhtHealth {
+        Dht::health(self)
+    }
+
+    async fn stats(&self) -> Result<DhtStats, DhtError> {
+        Dht::stats(self)
+    }
+}
+
+#[cfg(feature = "tokio")]
+pub use blocking::TokioDht;
+
+#[cfg(feature = "tokio")]
+mod blocking {
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::sync::mpsc::Receiver;
+    use std::time::Duration;
+
+    use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+
+    use super::AsyncDht;
+    use crate::{
+        Dht, DhtError, DhtEvent, DhtHealth, DhtStats, Endpoint, LookupCursor, LookupFilter,
+        LookupPage, PathPattern, Registration, SimulatedDht, TrustRootGroup,
+    };
+
+    /// Runs a synchronous [`Dht`] on tokio's blocking thread pool.
+    ///
+    /// Each operation is moved to [`tokio::task::spawn_blocking`], so a
+    /// slow implementation, such as a [`SimulatedDht`] configured with a
+    /// simulated delay, never stalls the async runtime. Clones share the
+    /// same underlying DHT.
+    ///
+    /// Operations must be awaited inside a tokio runtime. If the blocking
+    /// task panics or is cancelled, the operation fails with
+    /// `DhtError::Internal`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri::{AgentUri, CapabilityPath};
+    /// use agent_uri_dht::{AsyncDht, Endpoint, Registration, TokioDht};
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let dht = TokioDht::with_defaults();
+    /// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+    /// dht.register(Registration::new(uri, vec![Endpoint::https("agent.acme.com")]))
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let found = dht
+    ///     .lookup_global(&CapabilityPath::parse("assistant/chat").unwrap())
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(found.len(), 1);
+    /// # });
+    /// ```
+    #[derive(Debug)]
+    pub struct TokioDht<D = SimulatedDht> {
+        inner: Arc<D>,
+    }
+
+    impl TokioDht<SimulatedDht> {
+        /// Wraps a new [`SimulatedDht`] with the default configuration.
+        #[must_use]
+        pub fn with_defaults() -> Self {
+            Self::new(SimulatedDht::with_defaults())
+        }
+    }
+
+    impl<D: Dht + 'static> TokioDht<D> {
+        /// Wraps `dht`.
+        #[must_use]
+        pub fn new(dht: D) -> Self {
+            Self::from_arc(Arc::new(dht))
+        }
+
+        /// Wraps a DHT that is also shared elsewhere.
+        #[must_use]
+        pub fn from_arc(dht: Arc<D>) -> Self {
+            Self { inner: dht }
+        }
+
+        /// Returns the wrapped DHT, for calling it synchronously.
+        #[must_use]
+        pub fn inner(&self) -> &Arc<D> {
+            &self.inner
+        }
+
+        /// Runs `op` against the wrapped DHT on the blocking thread pool.
+        async fn spawn<T: Send + 'static>(
+            &self,
+            op: impl FnOnce(&D) -> Result<T, DhtError> + Send + 'static,
+        ) -> Result<T, DhtError> {
+            let dht = Arc::clone(&self.inner);
+            tokio::task::spawn_blocking(move || op(&dht))
+                .await
+                .map_err(|e| DhtError::internal(format!("blocking DHT task failed: {e}")))?
+        }
+    }
+
+    impl<D> Clone for TokioDht<D> {
+        fn clone(&self) -> Self {
+            Self {
+                inner: Arc::clone(&self.inner),
+            }
+        }
+    }
+
+    impl<D: Dht + 'static> AsyncDht for TokioDht<D> {
+        fn register(
+            &self,
+            registration: Registration,
+        ) -> impl Future<Output = Result<(), DhtError>> + Send {
+            self.spawn(move |dht| dht.register(registration))
+        }
+
+        fn update_endpoint(
+            &self,
+            agent_uri: &AgentUri,
+            new_endpoints: Vec<Endpoint>,
+        ) -> impl Future<Output = Result<(), DhtError>> + Send {
+            let agent_uri = agent_uri.clone();
+            self.spawn(move |dht| dht.update_endpoint(&agent_uri, new_endpoints))
+        }
+
+        fn update_registration(
+            &self,
+            registration: Registration,
+        ) -> impl Future<Output = Result<(), DhtError>> + Send {
+            self.spawn(move |dht| dht.update_registration(registration))
+        }
+
+        fn renew(
+            &self,
+            agent_uri: &AgentUri,
+            ttl: Duration,
+        ) -> impl Future<Output = Result<(), DhtError>> + Send {
+            let agent_uri = agent_uri.clone();
+            self.spawn(move |dht| dht.renew(&agent_uri, ttl))
+        }
+
+        fn deregister(
+            &self,
+            agent_uri: &AgentUri,
+        ) -> impl Future<Output = Result<(), DhtError>> + Send {
+            let agent_uri = agent_uri.clone();
+            self.spawn(move |dht| dht.deregister(&agent_uri))
+        }
+
+        fn lookup_exact(
+            &self,
+            trust_root: &TrustRoot,
+            capability_path: &CapabilityPath,
+        ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send {
+            let (trust_root, capability_path) = (trust_root.clone(), capability_path.clone());
+            self.spawn(move |dht| dht.lookup_exact(&trust_root, &capability_path))
+        }
+
+        fn lookup_many(
+            &self,
+            queries: &[(TrustRoot, CapabilityPath)],
+        ) -> impl Future<Output = Vec<Result<Vec<Registration>, DhtError>>> + Send {
+            let queries = queries.to_vec();
+            let count = queries.len();
+            let batch = self.spawn(move |dht| Ok(dht.lookup_many(&queries)));
+            // A failed task fails every query in the batch
+            async move { batch.await.unwrap_or_else(|e| vec![Err(e); count]) }
+        }
+
+        fn lookup_prefix(
+            &self,
+            trust_root: &TrustRoot,
+            capability_path: &CapabilityPath,
+        ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send {
+            let (trust_root, capability_path) = (trust_root.clone(), capability_path.clone());
+            self.spawn(move |dht| dht.lookup_prefix(&trust_root, &capability_path))
+        }
+
+        fn lookup_prefix_paged(
+            &self,
+            trust_root: &TrustRoot,
+            capability_path: &CapabilityPath,
+            limit: usize,
+            cursor: Option<&LookupCursor>,
+        ) -> impl Future<Output = Result<LookupPage, DhtError>> + Send {
+            let (trust_root, capability_path) = (trust_root.clone(), capability_path.clone());
+            let cursor = cursor.cloned();
+            self.spawn(move |dht| {
+                dht.lookup_prefix_paged(&trust_root, &capability_path, limit, cursor.as_ref())
+            })
+        }
+
+        fn lookup_global(
+            &self,
+            capability_path: &CapabilityPath,
+        ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send {
+            let capability_path = capability_path.clone();
+            self.spawn(move |dht| dht.lookup_global(&capability_path))
+        }
+
+        fn lookup_pattern(
+            &self,
+            trust_root: &TrustRoot,
+            pattern: &PathPattern,
+        ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send {
+            let (trust_root, pattern) = (trust_root.clone(), pattern.clone());
+            self.spawn(move |dht| dht.lookup_pattern(&trust_root, &pattern))
+        }
+
+        fn lookup_pattern_global(
+            &self,
+            pattern: &PathPattern,
+        ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send {
+            let pattern = pattern.clone();
+            self.spawn(move |dht| dht.lookup_pattern_global(&pattern))
+        }
+
+        fn lookup_trust_root(
+            &self,
+            trust_root: &TrustRoot,
+        ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send {
+            let trust_root = trust_root.clone();
+            self.spawn(move |dht| dht.lookup_trust_root(&trust_root))
+        }
+
+        fn lookup_trust_root_paged(
+            &self,
+            trust_root: &TrustRoot,
+            limit: usize,
+            cursor: Option<&LookupCursor>,
+        ) -> impl Future<Output = Result<LookupPage, DhtError>> + Send {
+            let trust_root = trust_root.clone();
+            let cursor = cursor.cloned();
+            self.spawn(move |dht| dht.lookup_trust_root_paged(&trust_root, limit, cursor.as_ref()))
+        }
+
+        fn watch_prefix(
+            &self,
+            trust_root: &TrustRoot,
+            capability_path: &CapabilityPath,
+        ) -> impl Future<Output = Result<Receiver<DhtEvent>, DhtError>> + Send {
+            // Subscribing does not block
+            std::future::ready(Dht::watch_prefix(&*self.inner, trust_root, capability_path))
+        }
+
+        fn lookup_exact_filtered(
+            &self,
+            trust_root: &TrustRoot,
+            capability_path: &CapabilityPath,
+            filter: &LookupFilter,
+        ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send {
+            let (trust_root, capability_path) = (trust_root.clone(), capability_path.clone());
+            let filter = filter.clone();
+            self.spawn(move |dht| dht.lookup_exact_filtered(&trust_root, &capability_path, &filter))
+        }
+
+        fn lookup_prefix_filtered(
+            &self,
+            trust_root: &TrustRoot,
+            capability_path: &CapabilityPath,
+            filter: &LookupFilter,
+        ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send {
+            let (trust_root, capability_path) = (trust_root.clone(), capability_path.clone());
+            let filter = filter.clone();
+            self.spawn(move |dht| {
+                dht.lookup_prefix_filtered(&trust_root, &capability_path, &filter)
+            })
+        }
+
+        fn lookup_prefix_paged_filtered(
+            &self,
+            trust_root: &TrustRoot,
+            capability_path: &CapabilityPath,
+            limit: usize,
+            cursor: Option<&LookupCursor>,
+            filter: &LookupFilter,
+        ) -> impl Future<Output = Result<LookupPage, DhtError>> + Send {
+            let (trust_root, capability_path) = (trust_root.clone(), capability_path.clone());
+            let (cursor, filter) = (cursor.cloned(), filter.clone());
+            self.spawn(move |dht| {
+                dht.lookup_prefix_paged_filtered(
+                    &trust_root,
+                    &capability_path,
+                    limit,
+                    cursor.as_ref(),
+                    &filter,
+                )
+            })
+        }
+
+        fn lookup_global_filtered(
+            &self,
+            capability_path: &CapabilityPath,
+            filter: &LookupFilter,
+        ) -> impl Future<Output = Result<Vec<Registration>, DhtError>> + Send {
+            let (capability_path, filter) = (capability_path.clone(), filter.clone());
+            self.spawn(move |dht| dht.lookup_global_filtered(&capability_path, &filter))
+        }
+
+        fn lookup_global_grouped(
+            &self,
+            capability_path: &CapabilityPath,
+            per_root_limit: Option<usize>,
+        ) -> impl Future<Output = Result<Vec<TrustRootGroup>, DhtError>> + Send {
+            let capability_path = capability_path.clone();
+            self.spawn(move |dht| dht.lookup_global_grouped(&capability_path, per_root_limit))
+        }
+
+        fn ping(&self) -> impl Future<Output = Result<(), DhtError>> + Send {
+            self.spawn(Dht::ping)
+        }
+
+        fn health(&self) -> impl Future<Output = DhtHealth> + Send {
+            let health = self.spawn(|dht| Ok(dht.health()));
+            async move {
+                health
+                    .await
+                    .unwrap_or_else(|e| DhtHealth::from_ping(Err(e), Duration::ZERO))
+            }
+        }
+
+        fn stats(&self) -> impl Future<Output = Result<DhtStats, DhtError>> + Send {
+            self.spawn(Dht::stats)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimulatedDht;
+
+    fn registration() -> Registration {
+        let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q")
+            .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+    }
+
+    /// Runs a full register/lookup/deregister cycle through `AsyncDht`.
+    async fn round_trip(dht: &impl AsyncDht) {
+        let registration = registration();
+        let uri = registration.agent_uri().clone();
+        let trust_root = TrustRoot::parse("acme.com").unwrap();
+        let path = CapabilityPath::parse("assistant/chat").unwrap();
+
+        let events = AsyncDht::watch_prefix(dht, &trust_root, &path).await.unwrap();
+        AsyncDht::register(dht, registration).await.unwrap();
+        let moved = vec![Endpoint::https("eu.agent.acme.com")];
+        AsyncDht::update_endpoint(dht, &uri, moved.clone()).await.unwrap();
+        AsyncDht::renew(dht, &uri, Duration::from_mins(1)).await.unwrap();
+        let found = AsyncDht::lookup_exact(dht, &trust_root, &path).await.unwrap();
+        assert_eq!(found[0].endpoints(), moved);
+        assert_eq!(AsyncDht::lookup_prefix(dht, &trust_root, &path).await.unwrap().len(), 1);
+        let parent = CapabilityPath::parse("assistant").unwrap();
+        let queries = [(trust_root.clone(), path.clone()), (trust_root.clone(), parent)];
+        let batch = AsyncDht::lookup_many(dht, &queries).await;
+        assert_eq!((batch[0].as_ref().unwrap().len(), batch[1].as_ref().unwrap().len()), (1, 0));
+        let page = AsyncDht::lookup_prefix_paged(dht, &trust_root, &path, 10, None)
+            .await
+            .unwrap();
+        assert_eq!((page.registrations.len(), page.next_cursor), (1, None));
+        assert_eq!(AsyncDht::lookup_global(dht, &path).await.unwrap().len(), 1);
+        let grpc = LookupFilter::new().with_protocol("grpc");
+        assert!(
+            AsyncDht::lookup_prefix_filtered(dht, &trust_root, &path, &grpc)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        assert!(AsyncDht::health(dht).await.is_healthy());
+        assert_eq!(AsyncDht::stats(dht).await.unwrap().total_registrations, 1);
+
+        AsyncDht::deregister(dht, &uri).await.unwrap();
+        assert_eq!(events.try_iter().count(), 4);
+        assert!(
+            AsyncDht::deregister(dht, &uri)
+                .await
+                .is_err_and(|e| e.is_not_found())
+        );
+    }
+
+    #[tokio::test]
+    async fn sync_dht_is_async_dht() {
+        round_trip(&SimulatedDht::with_defaults()).await;
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn tokio_dht_runs_on_blocking_pool() {
+        let dht = TokioDht::with_defaults();
+        round_trip(&dht).await;
+
+        // Clones share the wrapped DHT
+        AsyncDht::register(&dht.clone(), registration()).await.unwrap();
+        assert_eq!(SimulatedDht::stats(dht.inner()).total_registrations, 1);
+    }
+}
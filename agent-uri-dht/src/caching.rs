@@ -0,0 +1,802 @@
+//! Lookup result caching.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+
+use crate::churn::ratio;
+use crate::sharded::ShardedMap;
+use crate::{
+    Dht, DhtError, DhtEvent, DhtStats, Endpoint, LookupCursor, LookupPage, PathPattern,
+    Registration,
+};
+
+/// Counters of a [`CachingDht`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Hits that returned a cached empty result
+    pub negative_hits: u64,
+    /// Lookups that went to the wrapped DHT
+    pub misses: u64,
+    /// Lookups that waited for another caller's identical lookup instead of
+    /// repeating it
+    pub coalesced: u64,
+    /// Lookup results currently cached
+    pub entries: usize,
+}
+
+impl CacheStats {
+    /// Returns the fraction of lookups answered without a lookup of their
+    /// own, or 0.0 before the first lookup.
+    #[must_use]
+    pub fn hit_ratio(&self) -> f64 {
+        let served = self.hits + self.coalesced;
+        let total = served + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        ratio(
+            usize::try_from(served).unwrap_or(usize::MAX),
+            usize::try_from(total).unwrap_or(usize::MAX),
+        )
+    }
+}
+
+/// A lookup, identified by trust root, path and mode.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum CacheKey {
+    Exact(TrustRoot, CapabilityPath),
+    Prefix(Option<TrustRoot>, CapabilityPath),
+    Pattern(Option<TrustRoot>, PathPattern),
+    TrustRoot(TrustRoot),
+}
+
+impl CacheKey {
+    /// Returns true if a registration of `agent_uri` could be among the
+    /// results of this lookup.
+    fn covers(&self, agent_uri: &AgentUri) -> bool {
+        let same_root = |root: &Option<TrustRoot>| {
+            root.as_ref().is_none_or(|root| root == agent_uri.trust_root())
+        };
+        let path = agent_uri.capability_path();
+        match self {
+            Self::Exact(root, exact) => root == agent_uri.trust_root() && exact == path,
+            Self::Prefix(root, prefix) => same_root(root) && path.starts_with(prefix),
+            Self::Pattern(root, pattern) => same_root(root) && pattern.matches(path),
+            Self::TrustRoot(root) => root == agent_uri.trust_root(),
+        }
+    }
+
+    /// Returns the trust root and path to watch for registrations this
+    /// lookup would return, if it is confined to one trust root and prefix.
+    const fn watched(&self) -> Option<(&TrustRoot, &CapabilityPath)> {
+        match self {
+            Self::Exact(root, path) | Self::Prefix(Some(root), path) => Some((root, path)),
+            _ => None,
+        }
+    }
+}
+
+/// A cached lookup, or one in progress.
+enum Slot {
+    Ready {
+        registrations: Vec<Registration>,
+        expires_at: Instant,
+        watch: Option<Watch>,
+    },
+    Loading(Arc<Flight>),
+}
+
+/// Change notifications that end a cached empty result early.
+struct Watch {
+    events: Mutex<Receiver<DhtEvent>>,
+    fired: AtomicBool,
+}
+
+impl Watch {
+    fn new(events: Receiver<DhtEvent>) -> Self {
+        Self {
+            events: Mutex::new(events),
+            fired: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns true once an agent `key` would return has been registered,
+    /// or the notifications have stopped.
+    fn fired(&self, key: &CacheKey) -> bool {
+        if self.fired.load(Ordering::Relaxed) {
+            return true;
+        }
+        let events = self.events.lock().expect("lock poisoned");
+        loop {
+            match events.try_recv() {
+                Ok(DhtEvent::Registered(registration) | DhtEvent::Updated(registration))
+                    if key.covers(registration.agent_uri()) =>
+                {
+                    break;
+                }
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        self.fired.store(true, Ordering::Relaxed);
+        true
+    }
+}
+
+/// A lookup in progress, which callers of the same lookup wait for.
+#[derive(Default)]
+struct Flight {
+    result: Mutex<Option<Result<Vec<Registration>, DhtError>>>,
+    done: Condvar,
+}
+
+/// Ends a flight whose lookup unwound, so its waiters and later lookups of
+/// the key do not block forever.
+struct FlightGuard<'a> {
+    entries: &'a ShardedMap<CacheKey, Slot>,
+    key: &'a CacheKey,
+    flight: &'a Arc<Flight>,
+}
+
+impl Drop for FlightGuard<'_> {
+    fn drop(&mut self) {
+        let mut shard = self.entries.write(self.key);
+        if matches!(shard.get(self.key), Some(Slot::Loading(f)) if Arc::ptr_eq(f, self.flight)) {
+            shard.remove(self.key);
+        }
+        drop(shard);
+        let mut result = self.flight.result.lock().unwrap_or_else(PoisonError::into_inner);
+        *result = Some(Err(DhtError::internal("lookup panicked")));
+        self.flight.done.notify_all();
+    }
+}
+
+/// A [`Dht`] wrapper that caches lookup results.
+///
+/// Routing layers look up the same hot capability prefixes thousands of
+/// times per second; this answers repeats from memory. Results of exact,
+/// prefix, global, pattern and trust root lookups are cached per trust
+/// root, path and mode, for the shortest of:
+///
+/// - [`max_ttl`](Self::with_max_ttl)
+/// - the `ttl` query parameter, in seconds, of each returned agent's URI
+///   (e.g. `agent://acme.com/assistant/chat/llm_...?ttl=30`)
+/// - the time until the first returned registration expires
+///
+/// Empty results are cached for the shorter
+/// [`negative_ttl`](Self::with_negative_ttl), so lookups of capabilities
+/// nobody offers stop reaching the backend too. Exact and prefix lookups
+/// under a trust root also [watch](Dht::watch_prefix) their path while
+/// empty, and go back to the wrapped DHT as soon as a matching agent
+/// registers anywhere. Errors are not cached, and paged lookups always go to
+/// the wrapped DHT.
+///
+/// Expired results are dropped from a cache shard whenever a new result is
+/// stored in it, so lookups of many distinct capabilities do not grow the
+/// cache, or the watches it holds, without bound.
+///
+/// Concurrent misses on the same lookup are coalesced: one caller runs it
+/// while the others wait for its result, so an expiring hot entry causes
+/// one lookup rather than a stampede.
+///
+/// Writes made through the wrapper drop every cached result that could
+/// include the agent written. Writes made directly to the wrapped DHT, or
+/// by other nodes, show up once the cached results expire.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+/// use agent_uri_dht::{CachingDht, Dht, Endpoint, Registration, SimulatedDht};
+///
+/// let dht = CachingDht::new(SimulatedDht::with_defaults());
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q?ttl=30").unwrap();
+/// dht.register(Registration::new(uri, vec![Endpoint::https("agent.acme.com")]))?;
+///
+/// let trust_root = TrustRoot::parse("acme.com").unwrap();
+/// let prefix = CapabilityPath::parse("assistant").unwrap();
+/// for _ in 0..100 {
+///     assert_eq!(dht.lookup_prefix(&trust_root, &prefix)?.len(), 1);
+/// }
+/// assert_eq!(dht.cache_stats().misses, 1);
+/// assert_eq!(dht.cache_stats().hits, 99);
+/// # Ok::<(), agent_uri_dht::DhtError>(())
+/// ```
+pub struct CachingDht<D> {
+    inner: D,
+    entries: ShardedMap<CacheKey, Slot>,
+    max_ttl: Duration,
+    negative_ttl: Duration,
+    hits: AtomicU64,
+    negative_hits: AtomicU64,
+    misses: AtomicU64,
+    coalesced: AtomicU64,
+}
+
+impl<D: Dht> CachingDht<D> {
+    /// Longest time a result is cached, unless
+    /// [`with_max_ttl`](Self::with_max_ttl) is used.
+    pub const DEFAULT_MAX_TTL: Duration = Duration::from_secs(30);
+
+    /// Time an empty result is cached, unless
+    /// [`with_negative_ttl`](Self::with_negative_ttl) is used.
+    pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(5);
+
+    /// Wraps `inner`.
+    #[must_use]
+    pub fn new(inner: D) -> Self {
+        Self {
+            inner,
+            entries: ShardedMap::new(16),
+            max_ttl: Self::DEFAULT_MAX_TTL,
+            negative_ttl: Self::DEFAULT_NEGATIVE_TTL,
+            hits: AtomicU64::new(0),
+            negative_hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            coalesced: AtomicU64::new(0),
+        }
+    }
+
+    /// Sets the longest time a result is cached.
+    #[must_use]
+    pub const fn with_max_ttl(mut self, ttl: Duration) -> Self {
+        self.max_ttl = ttl;
+        self
+    }
+
+    /// Sets how long an empty result is cached; zero disables negative
+    /// caching.
+    #[must_use]
+    pub const fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = ttl;
+        self
+    }
+
+    /// Returns the wrapped DHT.
+    #[must_use]
+    pub const fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Returns the cache's counters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    #[must_use]
+    pub fn cache_stats(&self) -> CacheStats {
+        let now = Instant::now();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            negative_hits: self.negative_hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            coalesced: self.coalesced.load(Ordering::Relaxed),
+            entries: self
+                .entries
+                .read_each()
+                .map(|shard| {
+                    shard
+                        .values()
+                        .filter(|slot| {
+                            matches!(slot, Slot::Ready { expires_at, .. } if *expires_at > now)
+                        })
+                        .count()
+                })
+                .sum(),
+        }
+    }
+
+    /// Drops every cached result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn clear(&self) {
+        for mut shard in self.entries.write_each() {
+            shard.clear();
+        }
+    }
+
+    /// Drops every cached result that could include `agent_uri`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn invalidate(&self, agent_uri: &AgentUri) {
+        for mut shard in self.entries.write_each() {
+            shard.retain(|key, _| !key.covers(agent_uri));
+        }
+    }
+
+    /// Returns the cached result of `key`, or runs `load` for it, once
+    /// across concurrent callers.
+    fn cached(
+        &self,
+        key: CacheKey,
+        load: impl FnOnce(&D) -> Result<Vec<Registration>, DhtError>,
+    ) -> Result<Vec<Registration>, DhtError> {
+        if let Some(registrations) = fresh(&self.entries.read(&key), &key) {
+            return Ok(self.hit(registrations));
+        }
+
+        let flight = {
+            let mut shard = self.entries.write(&key);
+            if let Some(registrations) = fresh(&shard, &key) {
+                return Ok(self.hit(registrations));
+            }
+            if let Some(Slot::Loading(flight)) = shard.get(&key) {
+                let flight = Arc::clone(flight);
+                drop(shard);
+                self.coalesced.fetch_add(1, Ordering::Relaxed);
+                return wait(&flight);
+            }
+            let flight = Arc::new(Flight::default());
+            shard.insert(key.clone(), Slot::Loading(Arc::clone(&flight)));
+            flight
+        };
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let guard = FlightGuard {
+            entries: &self.entries,
+            key: &key,
+            flight: &flight,
+        };
+        let result = load(&self.inner);
+        let watch = match (&result, key.watched()) {
+            (Ok(registrations), Some((trust_root, path)))
+                if registrations.is_empty() && !self.negative_ttl.is_zero() =>
+            {
+                self.inner.watch_prefix(trust_root, path).ok().map(Watch::new)
+            }
+            _ => None,
+        };
+        std::mem::forget(guard);
+        {
+            let mut shard = self.entries.write(&key);
+            // A write may have invalidated the lookup while it ran
+            if matches!(shard.get(&key), Some(Slot::Loading(f)) if Arc::ptr_eq(f, &flight)) {
+                match &result {
+                    Ok(registrations) => {
+                        let ttl = self.ttl(registrations);
+                        if ttl.is_zero() {
+                            shard.remove(&key);
+                        } else {
+                            evict_stale(&mut shard);
+                            shard.insert(key, Slot::Ready {
+                                registrations: registrations.clone(),
+                                expires_at: Instant::now() + ttl,
+                                watch,
+                            });
+                        }
+                    }
+                    Err(_) => {
+                        shard.remove(&key);
+                    }
+                }
+            }
+        }
+        *flight.result.lock().expect("lock poisoned") = Some(result.clone());
+        flight.done.notify_all();
+        result
+    }
+
+    /// Counts a cache hit returning `registrations`.
+    fn hit(&self, registrations: Vec<Registration>) -> Vec<Registration> {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        if registrations.is_empty() {
+            self.negative_hits.fetch_add(1, Ordering::Relaxed);
+        }
+        registrations
+    }
+
+    /// Returns how long `registrations` may be cached.
+    fn ttl(&self, registrations: &[Registration]) -> Duration {
+        if registrations.is_empty() {
+            return self.negative_ttl;
+        }
+        let now = SystemTime::now();
+        registrations.iter().fold(self.max_ttl, |ttl, registration| {
+            let hint = registration
+                .agent_uri()
+                .query()
+                .ttl()
+                .map_or(ttl, Duration::from_secs);
+            let remaining = registration
+                .expires_at()
+                .duration_since(now)
+                .unwrap_or(Duration::ZERO);
+            ttl.min(hint).min(remaining)
+        })
+    }
+
+    /// Drops the results a write about `agent_uri` affects, and passes the
+    /// write's result on.
+    fn write<T>(&self, agent_uri: &AgentUri, result: Result<T, DhtError>) -> Result<T, DhtError> {
+        self.invalidate(agent_uri);
+        result
+    }
+}
+
+/// Returns the cached result of `key` in `shard`, if it has not expired.
+fn fresh(shard: &HashMap<CacheKey, Slot>, key: &CacheKey) -> Option<Vec<Registration>> {
+    match shard.get(key) {
+        Some(Slot::Ready {
+            registrations,
+            expires_at,
+            watch,
+        }) if *expires_at > Instant::now()
+            && watch.as_ref().is_none_or(|watch| !watch.fired(key)) =>
+        {
+            Some(registrations.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Drops the expired results in `shard`, and empty results whose watch has
+/// fired, releasing their watches.
+///
+/// Keys that are never looked up again would otherwise stay cached, each
+/// empty one holding a subscription on the wrapped DHT.
+fn evict_stale(shard: &mut HashMap<CacheKey, Slot>) {
+    let now = Instant::now();
+    shard.retain(|_, slot| match slot {
+        Slot::Ready {
+            expires_at, watch, ..
+        } => {
+            *expires_at > now
+                && watch
+                    .as_ref()
+                    .is_none_or(|watch| !watch.fired.load(Ordering::Relaxed))
+        }
+        Slot::Loading(_) => true,
+    });
+}
+
+/// Waits for another caller's lookup to finish and returns its result.
+fn wait(flight: &Flight) -> Result<Vec<Registration>, DhtError> {
+    let mut result = flight.result.lock().expect("lock poisoned");
+    while result.is_none() {
+        result = flight.done.wait(result).expect("lock poisoned");
+    }
+    result.clone().expect("result is set")
+}
+
+impl<D> std::fmt::Debug for CachingDht<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachingDht")
+            .field("max_ttl", &self.max_ttl)
+            .field("negative_ttl", &self.negative_ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<D: Dht> Dht for CachingDht<D> {
+    fn register(&self, registration: Registration) -> Result<(), DhtError> {
+        let agent_uri = registration.agent_uri().clone();
+        self.write(&agent_uri, self.inner.register(registration))
+    }
+
+    fn update_endpoint(
+        &self,
+        agent_uri: &AgentUri,
+        new_endpoints: Vec<Endpoint>,
+    ) -> Result<(), DhtError> {
+        self.write(agent_uri, self.inner.update_endpoint(agent_uri, new_endpoints))
+    }
+
+    fn update_registration(&self, registration: Registration) -> Result<(), DhtError> {
+        let agent_uri = registration.agent_uri().clone();
+        self.write(&agent_uri, self.inner.update_registration(registration))
+    }
+
+    fn renew(&self, agent_uri: &AgentUri, ttl: Duration) -> Result<(), DhtError> {
+        self.write(agent_uri, self.inner.renew(agent_uri, ttl))
+    }
+
+    fn deregister(&self, agent_uri: &AgentUri) -> Result<(), DhtError> {
+        self.write(agent_uri, self.inner.deregister(agent_uri))
+    }
+
+    fn lookup_exact(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let key = CacheKey::Exact(trust_root.clone(), capability_path.clone());
+        self.cached(key, |dht| dht.lookup_exact(trust_root, capability_path))
+    }
+
+    fn lookup_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let key = CacheKey::Prefix(Some(trust_root.clone()), capability_path.clone());
+        self.cached(key, |dht| dht.lookup_prefix(trust_root, capability_path))
+    }
+
+    fn lookup_prefix_paged(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        self.inner
+            .lookup_prefix_paged(trust_root, capability_path, limit, cursor)
+    }
+
+    fn lookup_global(
+        &self,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let key = CacheKey::Prefix(None, capability_path.clone());
+        self.cached(key, |dht| dht.lookup_global(capability_path))
+    }
+
+    fn lookup_pattern(
+        &self,
+        trust_root: &TrustRoot,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let key = CacheKey::Pattern(Some(trust_root.clone()), pattern.clone());
+        self.cached(key, |dht| dht.lookup_pattern(trust_root, pattern))
+    }
+
+    fn lookup_pattern_global(
+        &self,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let key = CacheKey::Pattern(None, pattern.clone());
+        self.cached(key, |dht| dht.lookup_pattern_global(pattern))
+    }
+
+    fn lookup_trust_root(&self, trust_root: &TrustRoot) -> Result<Vec<Registration>, DhtError> {
+        let key = CacheKey::TrustRoot(trust_root.clone());
+        self.cached(key, |dht| dht.lookup_trust_root(trust_root))
+    }
+
+    fn lookup_trust_root_paged(
+        &self,
+        trust_root: &TrustRoot,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        self.inner.lookup_trust_root_paged(trust_root, limit, cursor)
+    }
+
+    fn watch_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Receiver<DhtEvent>, DhtError> {
+        self.inner.watch_prefix(trust_root, capability_path)
+    }
+
+    fn ping(&self) -> Result<(), DhtError> {
+        self.inner.ping()
+    }
+
+    fn stats(&self) -> Result<DhtStats, DhtError> {
+        self.inner.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DhtOperation, SimulatedDht, SimulationConfig};
+
+    fn uri(query: &str) -> AgentUri {
+        AgentUri::parse(&format!(
+            "agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q{query}"
+        ))
+        .unwrap()
+    }
+
+    fn trust_root() -> TrustRoot {
+        TrustRoot::parse("acme.com").unwrap()
+    }
+
+    fn prefix() -> CapabilityPath {
+        CapabilityPath::parse("assistant").unwrap()
+    }
+
+    #[test]
+    fn repeated_lookups_are_served_from_the_cache() {
+        let dht = CachingDht::new(SimulatedDht::with_defaults());
+        dht.inner()
+            .register(Registration::new(uri(""), vec![Endpoint::https("agent.acme.com")]))
+            .unwrap();
+
+        for _ in 0..3 {
+            assert_eq!(dht.lookup_prefix(&trust_root(), &prefix()).unwrap().len(), 1);
+        }
+        // A different mode is a different lookup
+        assert_eq!(dht.lookup_global(&prefix()).unwrap().len(), 1);
+
+        // Writes to the wrapped DHT are not seen until the result expires
+        dht.inner().deregister(&uri("")).unwrap();
+        assert_eq!(dht.lookup_prefix(&trust_root(), &prefix()).unwrap().len(), 1);
+
+        let stats = dht.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (3, 2, 2));
+        assert!((stats.hit_ratio() - 0.6).abs() < f64::EPSILON);
+
+        dht.clear();
+        assert!(dht.lookup_prefix(&trust_root(), &prefix()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn ttl_hint_and_expiry_bound_the_cache_time() {
+        let dht = CachingDht::new(SimulatedDht::with_defaults());
+        let hinted = Registration::new(uri("?ttl=0"), vec![Endpoint::https("agent.acme.com")]);
+        dht.register(hinted).unwrap();
+
+        // `ttl=0` forbids caching
+        dht.lookup_prefix(&trust_root(), &prefix()).unwrap();
+        dht.lookup_prefix(&trust_root(), &prefix()).unwrap();
+        assert_eq!(dht.cache_stats().misses, 2);
+        assert_eq!(dht.cache_stats().entries, 0);
+
+        let dht = CachingDht::new(SimulatedDht::with_defaults())
+            .with_max_ttl(Duration::from_hours(1));
+        let registration =
+            Registration::new(uri("?ttl=300"), vec![Endpoint::https("agent.acme.com")]);
+        dht.register(registration.clone()).unwrap();
+        let registrations = [registration];
+        let ttl = dht.ttl(&registrations);
+        assert!(ttl <= Duration::from_mins(5));
+        assert!(ttl > Duration::from_secs(290));
+
+        let expiring = registrations[0].clone().with_ttl(Duration::from_secs(10));
+        assert!(dht.ttl(&[expiring]) <= Duration::from_secs(10));
+        assert_eq!(dht.ttl(&[]), CachingDht::<SimulatedDht>::DEFAULT_NEGATIVE_TTL);
+    }
+
+    #[test]
+    fn writes_invalidate_affected_lookups() {
+        let dht = CachingDht::new(SimulatedDht::with_defaults());
+        let exact = CapabilityPath::parse("assistant/chat").unwrap();
+        let other = CapabilityPath::parse("billing").unwrap();
+        let pattern = PathPattern::parse("*/chat").unwrap();
+
+        // Cache empty results for every lookup
+        assert!(dht.lookup_exact(&trust_root(), &exact).unwrap().is_empty());
+        assert!(dht.lookup_prefix(&trust_root(), &other).unwrap().is_empty());
+        assert!(dht.lookup_pattern_global(&pattern).unwrap().is_empty());
+        assert!(dht.lookup_trust_root(&trust_root()).unwrap().is_empty());
+        assert_eq!(dht.cache_stats().entries, 4);
+
+        dht.register(Registration::new(uri(""), vec![Endpoint::https("agent.acme.com")]))
+            .unwrap();
+        assert_eq!(dht.cache_stats().entries, 1);
+        assert_eq!(dht.lookup_exact(&trust_root(), &exact).unwrap().len(), 1);
+        assert_eq!(dht.lookup_pattern_global(&pattern).unwrap().len(), 1);
+        assert_eq!(dht.lookup_trust_root(&trust_root()).unwrap().len(), 1);
+
+        dht.update_endpoint(&uri(""), vec![Endpoint::https("new.acme.com")]).unwrap();
+        let registrations = dht.lookup_exact(&trust_root(), &exact).unwrap();
+        assert_eq!(registrations[0].endpoints()[0], Endpoint::https("new.acme.com"));
+
+        dht.deregister(&uri("")).unwrap();
+        assert!(dht.lookup_trust_root(&trust_root()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn empty_results_are_cached_until_a_matching_agent_registers() {
+        let dht = CachingDht::new(SimulatedDht::with_defaults())
+            .with_negative_ttl(Duration::from_mins(1));
+        let exact = CapabilityPath::parse("assistant/chat").unwrap();
+        let pattern = PathPattern::parse("*/chat").unwrap();
+
+        for _ in 0..3 {
+            assert!(dht.lookup_exact(&trust_root(), &exact).unwrap().is_empty());
+            assert!(dht.lookup_pattern(&trust_root(), &pattern).unwrap().is_empty());
+        }
+        let stats = dht.cache_stats();
+        assert_eq!((stats.negative_hits, stats.misses), (4, 2));
+
+        // Registrations elsewhere do not end the cached empty result
+        let other = AgentUri::parse("agent://acme.com/billing/llm_01h455vb4pex5vsknk084sn02q")
+            .unwrap();
+        dht.inner()
+            .register(Registration::new(other, vec![Endpoint::https("agent.acme.com")]))
+            .unwrap();
+        assert!(dht.lookup_exact(&trust_root(), &exact).unwrap().is_empty());
+        assert_eq!(dht.cache_stats().negative_hits, 5);
+
+        // A matching registration made past the cache is seen through the
+        // watch; the pattern lookup has no watch and waits out its TTL
+        dht.inner()
+            .register(Registration::new(uri(""), vec![Endpoint::https("agent.acme.com")]))
+            .unwrap();
+        assert_eq!(dht.lookup_exact(&trust_root(), &exact).unwrap().len(), 1);
+        assert!(dht.lookup_pattern(&trust_root(), &pattern).unwrap().is_empty());
+        let stats = dht.cache_stats();
+        assert_eq!((stats.negative_hits, stats.misses), (6, 3));
+    }
+
+    #[test]
+    fn panicking_lookup_releases_its_waiters() {
+        let config = SimulationConfig::default().with_simulated_delay(Duration::from_millis(50));
+        let dht = CachingDht::new(SimulatedDht::new(config));
+        let key = CacheKey::Prefix(Some(trust_root()), prefix());
+
+        std::thread::scope(|scope| {
+            let loader = scope.spawn(|| {
+                dht.cached(key.clone(), |_| {
+                    std::thread::sleep(Duration::from_millis(100));
+                    panic!("backend bug");
+                })
+            });
+            std::thread::sleep(Duration::from_millis(20));
+            let waiter = scope.spawn(|| dht.lookup_prefix(&trust_root(), &prefix()));
+
+            assert!(loader.join().is_err());
+            assert!(matches!(waiter.join().unwrap(), Err(DhtError::Internal { .. })));
+        });
+        assert_eq!(dht.cache_stats().coalesced, 1);
+
+        // The key is not stuck loading
+        assert!(dht.lookup_prefix(&trust_root(), &prefix()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn expired_results_are_evicted_with_their_watches() {
+        let dht = CachingDht::new(SimulatedDht::with_defaults())
+            .with_negative_ttl(Duration::from_millis(20));
+
+        for name in ["alpha", "beta", "gamma", "delta"] {
+            let path = CapabilityPath::parse(name).unwrap();
+            assert!(dht.lookup_exact(&trust_root(), &path).unwrap().is_empty());
+        }
+        let slots = |dht: &CachingDht<SimulatedDht>| -> usize {
+            dht.entries.read_each().map(|shard| shard.len()).sum()
+        };
+        assert_eq!(slots(&dht), 4);
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(dht.cache_stats().entries, 0);
+        // Store a result in every shard; each store sweeps its shard
+        for i in 0..256 {
+            let path = CapabilityPath::parse(&format!("fresh/p{i}")).unwrap();
+            dht.lookup_exact(&trust_root(), &path).unwrap();
+        }
+        let stale = ["alpha", "beta", "gamma", "delta"].map(|name| {
+            CacheKey::Exact(trust_root(), CapabilityPath::parse(name).unwrap())
+        });
+        assert!(stale.iter().all(|key| !dht.entries.read(key).contains_key(key)));
+    }
+
+    #[test]
+    fn concurrent_misses_run_one_lookup() {
+        let config = SimulationConfig::default().with_simulated_delay(Duration::from_millis(50));
+        let dht = CachingDht::new(SimulatedDht::new(config));
+        dht.register(Registration::new(uri(""), vec![Endpoint::https("agent.acme.com")]))
+            .unwrap();
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    assert_eq!(dht.lookup_prefix(&trust_root(), &prefix()).unwrap().len(), 1);
+                });
+            }
+        });
+
+        let stats = dht.cache_stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits + stats.coalesced, 7);
+        let inner = dht.stats().unwrap();
+        assert_eq!(inner.latency(DhtOperation::PrefixLookup).unwrap().samples, 1);
+    }
+}
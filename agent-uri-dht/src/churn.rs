@@ -0,0 +1,386 @@
+//! Churn schedules for measuring a [`NetworkSimulation`] over time.
+
+use crate::{DhtKey, NetworkSimulation};
+
+/// A change in network membership.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChurnEvent {
+    /// A new node joins
+    Join,
+    /// The given node leaves gracefully, handing off its records
+    Leave(usize),
+    /// The given node crashes, losing its records
+    Fail(usize),
+    /// A random online node leaves gracefully
+    LeaveRandom,
+    /// A random online node crashes
+    FailRandom,
+}
+
+/// Membership changes to apply round by round, and how to measure them.
+///
+/// Each round applies its events in order, runs a
+/// [`repair`](NetworkSimulation::repair) if one is due, then measures record
+/// availability and runs lookups between random online nodes for random
+/// registered keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChurnSchedule {
+    rounds: Vec<Vec<ChurnEvent>>,
+    repair_interval: usize,
+    lookups_per_round: usize,
+    seed: u64,
+}
+
+impl Default for ChurnSchedule {
+    fn default() -> Self {
+        Self {
+            rounds: Vec::new(),
+            repair_interval: 1,
+            lookups_per_round: 20,
+            seed: 0,
+        }
+    }
+}
+
+impl ChurnSchedule {
+    /// Creates an empty schedule that repairs every round and runs 20
+    /// lookups per round.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a schedule of `rounds` identical rounds, each with `joins`
+    /// joins, then `leaves` random graceful departures, then `failures`
+    /// random crashes.
+    #[must_use]
+    pub fn steady(rounds: usize, joins: usize, leaves: usize, failures: usize) -> Self {
+        let mut events = vec![ChurnEvent::Join; joins];
+        events.extend(std::iter::repeat_n(ChurnEvent::LeaveRandom, leaves));
+        events.extend(std::iter::repeat_n(ChurnEvent::FailRandom, failures));
+        Self {
+            rounds: vec![events; rounds],
+            ..Self::default()
+        }
+    }
+
+    /// Appends a round with the given events.
+    #[must_use]
+    pub fn with_round(mut self, events: Vec<ChurnEvent>) -> Self {
+        self.rounds.push(events);
+        self
+    }
+
+    /// Sets how many rounds pass between repairs; 0 disables repair.
+    #[must_use]
+    pub const fn with_repair_interval(mut self, rounds: usize) -> Self {
+        self.repair_interval = rounds;
+        self
+    }
+
+    /// Sets the number of lookups measured each round.
+    #[must_use]
+    pub const fn with_lookups_per_round(mut self, lookups: usize) -> Self {
+        self.lookups_per_round = lookups;
+        self
+    }
+
+    /// Sets the seed for random events and lookups.
+    #[must_use]
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the events of each round.
+    #[must_use]
+    pub fn rounds(&self) -> &[Vec<ChurnEvent>] {
+        &self.rounds
+    }
+}
+
+/// Measurements taken at the end of one churn round.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChurnRound {
+    /// Round number, starting at 0
+    pub round: usize,
+    /// Nodes online after the round's events
+    pub online: usize,
+    /// Nodes that joined this round
+    pub joined: usize,
+    /// Nodes that left gracefully this round
+    pub departed: usize,
+    /// Nodes that crashed this round
+    pub failed: usize,
+    /// Keys registered in the network
+    pub keys: usize,
+    /// Keys with at least one live replica on an online node
+    pub available_keys: usize,
+    /// Lookups run
+    pub lookups: usize,
+    /// Lookups that found their key
+    pub successful_lookups: usize,
+    /// Total hops over all lookups run
+    pub lookup_hops: usize,
+    /// Records copied by hand-offs and repair
+    pub repair_traffic: usize,
+}
+
+impl ChurnRound {
+    /// Returns the fraction of keys still available, or 1.0 if none are
+    /// registered.
+    #[must_use]
+    pub fn availability(&self) -> f64 {
+        ratio(self.available_keys, self.keys)
+    }
+
+    /// Returns the fraction of lookups that succeeded, or 1.0 if none ran.
+    #[must_use]
+    pub fn lookup_success_rate(&self) -> f64 {
+        ratio(self.successful_lookups, self.lookups)
+    }
+
+    /// Returns the mean hops per lookup, or 0.0 if none ran.
+    #[must_use]
+    pub fn mean_hops(&self) -> f64 {
+        if self.lookups == 0 {
+            0.0
+        } else {
+            ratio(self.lookup_hops, self.lookups)
+        }
+    }
+}
+
+/// Per-round results of [`NetworkSimulation::run_churn`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChurnReport {
+    /// Measurements for each round, in order
+    pub rounds: Vec<ChurnRound>,
+}
+
+impl ChurnReport {
+    /// Returns the lowest availability seen in any round, or 1.0 if there
+    /// were no rounds.
+    #[must_use]
+    pub fn min_availability(&self) -> f64 {
+        self.rounds
+            .iter()
+            .map(ChurnRound::availability)
+            .fold(1.0, f64::min)
+    }
+
+    /// Returns the fraction of all lookups that succeeded.
+    #[must_use]
+    pub fn lookup_success_rate(&self) -> f64 {
+        ratio(
+            self.rounds.iter().map(|round| round.successful_lookups).sum(),
+            self.rounds.iter().map(|round| round.lookups).sum(),
+        )
+    }
+
+    /// Returns the records copied by hand-offs and repair over all rounds.
+    #[must_use]
+    pub fn repair_traffic(&self) -> usize {
+        self.rounds.iter().map(|round| round.repair_traffic).sum()
+    }
+}
+
+impl NetworkSimulation {
+    /// Runs a churn schedule against the network, measuring each round.
+    ///
+    /// Random departures and crashes always leave at least one node
+    /// online; explicit [`ChurnEvent::Leave`] and [`ChurnEvent::Fail`]
+    /// events do not, and rounds that end with no node online measure no
+    /// lookups.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an explicit event names a node index that does not exist.
+    pub fn run_churn(&mut self, schedule: &ChurnSchedule) -> ChurnReport {
+        let mut rng = SplitMix(schedule.seed);
+        let mut report = ChurnReport::default();
+
+        for (round, events) in schedule.rounds.iter().enumerate() {
+            let mut measured = ChurnRound {
+                round,
+                ..ChurnRound::default()
+            };
+            for event in events {
+                match *event {
+                    ChurnEvent::Join => {
+                        self.join();
+                        measured.joined += 1;
+                    }
+                    ChurnEvent::Leave(index) => {
+                        if self.is_online(index) {
+                            measured.departed += 1;
+                        }
+                        measured.repair_traffic += self.leave(index);
+                    }
+                    ChurnEvent::Fail(index) => {
+                        if self.is_online(index) {
+                            measured.failed += 1;
+                        }
+                        self.fail(index);
+                    }
+                    ChurnEvent::LeaveRandom => {
+                        if self.online_count() > 1 {
+                            let index = self.random_online(&mut rng);
+                            measured.departed += 1;
+                            measured.repair_traffic += self.leave(index);
+                        }
+                    }
+                    ChurnEvent::FailRandom => {
+                        if self.online_count() > 1 {
+                            let index = self.random_online(&mut rng);
+                            measured.failed += 1;
+                            self.fail(index);
+                        }
+                    }
+                }
+            }
+
+            if schedule.repair_interval > 0 && (round + 1) % schedule.repair_interval == 0 {
+                measured.repair_traffic += self.repair();
+            }
+
+            let keys: Vec<DhtKey> = self.published().iter().copied().collect();
+            measured.online = self.online_count();
+            measured.keys = keys.len();
+            measured.available_keys = keys.iter().filter(|key| self.is_available(key)).count();
+            if measured.online > 0 && !keys.is_empty() {
+                for _ in 0..schedule.lookups_per_round {
+                    let origin = self.random_online(&mut rng);
+                    let key = &keys[rng.below(keys.len())];
+                    let lookup = self.lookup_key(origin, key);
+                    measured.lookups += 1;
+                    measured.lookup_hops += lookup.metrics.hops;
+                    if !lookup.registrations.is_empty() {
+                        measured.successful_lookups += 1;
+                    }
+                }
+            }
+            report.rounds.push(measured);
+        }
+        report
+    }
+
+    /// Picks a uniformly random online node; there must be one.
+    pub(crate) fn random_online(&self, rng: &mut SplitMix) -> usize {
+        let online: Vec<usize> = (0..self.node_count())
+            .filter(|&index| self.is_online(index))
+            .collect();
+        online[rng.below(online.len())]
+    }
+}
+
+/// Small seeded generator, so simulation runs are reproducible.
+pub(crate) struct SplitMix(pub(crate) u64);
+
+impl SplitMix {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..bound`; `bound` must be non-zero.
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        usize::try_from(self.next() % bound as u64).unwrap_or(0)
+    }
+}
+
+/// Returns `numerator / denominator`, or 1.0 if the denominator is 0.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        1.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Endpoint, NetworkConfig, Registration};
+    use agent_uri::AgentUri;
+
+    fn populated(nodes: usize, k: usize, agents: usize) -> NetworkSimulation {
+        let mut network = NetworkSimulation::new(NetworkConfig::new().with_nodes(nodes).with_k(k));
+        for i in 0..agents {
+            let uri = AgentUri::parse(&format!(
+                "agent://acme.com/assistant/skill{i}/llm_01h455vb4pex5vsknk084sn02q"
+            ))
+            .unwrap();
+            let registration = Registration::new(uri, vec![Endpoint::https("agent.acme.com")]);
+            network.register(i % nodes, registration).unwrap();
+        }
+        network
+    }
+
+    #[test]
+    fn repair_keeps_records_available_under_churn() {
+        let mut network = populated(100, 8, 20);
+        let report = network.run_churn(&ChurnSchedule::steady(10, 2, 1, 3).with_seed(1));
+
+        assert_eq!(report.rounds.len(), 10);
+        assert_eq!(report.rounds[9].online, 100 + 20 - 10 - 30);
+        assert!((report.min_availability() - 1.0).abs() < f64::EPSILON);
+        assert!((report.lookup_success_rate() - 1.0).abs() < f64::EPSILON);
+        assert!(report.repair_traffic() > 0);
+        assert!(report.rounds.iter().all(|round| round.mean_hops() > 0.0));
+    }
+
+    #[test]
+    fn records_are_lost_without_repair() {
+        let mut network = populated(100, 4, 20);
+        let schedule = ChurnSchedule::steady(10, 0, 0, 9).with_repair_interval(0);
+        let report = network.run_churn(&schedule);
+
+        assert_eq!(report.repair_traffic(), 0);
+        assert!(report.min_availability() < 1.0);
+        let last = report.rounds.last().unwrap();
+        assert_eq!(last.online, 10);
+        assert!(last.available_keys < last.keys);
+    }
+
+    #[test]
+    fn graceful_leave_hands_off_records() {
+        let mut network = populated(50, 4, 1);
+        let key = *network.published().iter().next().unwrap();
+        let replica = network.replicas(&key)[0];
+
+        let schedule = ChurnSchedule::new()
+            .with_round(vec![ChurnEvent::Leave(replica)])
+            .with_repair_interval(0);
+        let report = network.run_churn(&schedule);
+
+        assert_eq!(report.rounds[0].departed, 1);
+        assert_eq!(report.repair_traffic(), 1);
+        assert_eq!(network.replicas(&key).len(), 4);
+        assert!(!network.replicas(&key).contains(&replica));
+    }
+
+    #[test]
+    fn joined_nodes_can_look_up_and_receive_records() {
+        let mut network = populated(30, 3, 5);
+        let newcomer = network.join();
+        assert!(network.is_online(newcomer));
+        for key in network.published().clone() {
+            assert!(!network.lookup_key(newcomer, &key).registrations.is_empty());
+        }
+
+        network.repair();
+        for key in network.published().clone() {
+            let mut replicas = network.replicas(&key);
+            let mut expected = network.closest_nodes(&key, 3);
+            replicas.retain(|index| expected.contains(index));
+            replicas.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(replicas, expected);
+        }
+    }
+}
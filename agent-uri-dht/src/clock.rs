@@ -0,0 +1,183 @@
+//! Time sources for the simulated DHT.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Where a [`SimulatedDht`](crate::SimulatedDht) reads the time from and
+/// how it waits.
+///
+/// The simulation checks expiry against [`now`](Self::now) and waits out
+/// simulated latency with [`sleep`](Self::sleep), so swapping in a
+/// [`VirtualClock`] makes latency-modeled runs finish as fast as the CPU
+/// allows, with identical results on every run.
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+
+    /// Waits for `duration` to pass.
+    fn sleep(&self, duration: Duration);
+}
+
+/// The operating system's clock; sleeping blocks the thread.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// Sleeping returns at once, moving the clock forward by the time slept;
+/// time can also be moved with [`advance`](Self::advance). Clones share
+/// the same time, so a test can keep one and hand another to the DHT.
+///
+/// Sleeps from concurrent threads each move the shared time, so they add
+/// up rather than overlap as they would in real time.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{
+///     Dht, Endpoint, Registration, SimulatedDht, SimulationConfig, VirtualClock,
+/// };
+///
+/// let clock = VirtualClock::new();
+/// let config = SimulationConfig::new().with_simulated_delay(Duration::from_secs(1));
+/// let dht = SimulatedDht::new(config).with_clock(clock.clone());
+///
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let registration = Registration::new(uri.clone(), vec![Endpoint::https("agent.acme.com")])
+///     .with_registered_at(clock.now())
+///     .with_ttl(Duration::from_secs(60));
+/// dht.register(registration)?;
+/// assert_eq!(clock.elapsed(), Duration::from_secs(1));
+///
+/// // An hour passes instantly, and the registration expires
+/// clock.advance(Duration::from_secs(3600));
+/// assert!(dht.lookup_exact(uri.trust_root(), uri.capability_path())?.is_empty());
+/// # Ok::<(), agent_uri_dht::DhtError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct VirtualClock {
+    start: SystemTime,
+    now: Arc<Mutex<SystemTime>>,
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VirtualClock {
+    /// Creates a clock starting at the current system time.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::starting_at(SystemTime::now())
+    }
+
+    /// Creates a clock starting at `start`, for runs that must not depend
+    /// on when they happen.
+    #[must_use]
+    pub fn starting_at(start: SystemTime) -> Self {
+        Self {
+            start,
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    /// Returns the current virtual time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    #[must_use]
+    pub fn now(&self) -> SystemTime {
+        *self.now.lock().expect("lock poisoned")
+    }
+
+    /// Moves the clock forward by `by`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().expect("lock poisoned") += by;
+    }
+
+    /// Returns the virtual time passed since the clock started.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.now().duration_since(self.start).unwrap_or_default()
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> SystemTime {
+        Self::now(self)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dht, DhtOperation, Endpoint, Registration, SimulatedDht, SimulationConfig};
+    use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+
+    #[test]
+    fn virtual_clock_moves_only_when_told() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = VirtualClock::starting_at(start);
+        let shared = clock.clone();
+        assert_eq!(clock.now(), start);
+
+        shared.advance(Duration::from_secs(5));
+        Clock::sleep(&clock, Duration::from_secs(10));
+        assert_eq!(clock.now(), start + Duration::from_secs(15));
+        assert_eq!(shared.elapsed(), Duration::from_secs(15));
+    }
+
+    #[test]
+    fn latency_modeled_runs_take_no_wall_clock_time() {
+        let clock = VirtualClock::new();
+        let config = SimulationConfig::new().with_simulated_delay(Duration::from_secs(1));
+        let dht = SimulatedDht::new(config).with_clock(clock.clone());
+        let trust_root = TrustRoot::parse("acme.com").unwrap();
+        let path = CapabilityPath::parse("assistant").unwrap();
+
+        let wall = std::time::Instant::now();
+        for i in 0..1000 {
+            let uri = AgentUri::parse(&format!(
+                "agent://acme.com/assistant/skill{i}/llm_01h455vb4pex5vsknk084sn02q"
+            ))
+            .unwrap();
+            let registration = Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+                .with_registered_at(clock.now())
+                .with_ttl(Duration::from_secs(500));
+            dht.register(registration).unwrap();
+        }
+        assert!(wall.elapsed() < Duration::from_secs(5));
+        assert_eq!(clock.elapsed(), Duration::from_secs(1000));
+        let register = dht.stats().latency(DhtOperation::Register).cloned().unwrap();
+        assert_eq!(register.total, Duration::from_secs(1000));
+
+        // Agent i expired at i + 500s; the lookup itself takes the clock
+        // to 1001s
+        let live = dht.lookup_prefix(&trust_root, &path).unwrap();
+        assert_eq!(live.len(), 498);
+        assert_eq!(dht.expire_stale(), 502);
+    }
+}
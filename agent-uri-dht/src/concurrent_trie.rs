@@ -0,0 +1,323 @@
+//! Path trie sharded by first segment for concurrent access.
+
+use agent_uri::CapabilityPath;
+
+use crate::pattern::PatternSegment;
+use crate::sharded::ShardedMap;
+use crate::{PathPattern, PathTrie};
+
+/// A [`PathTrie`] safe to share between threads, split by first path
+/// segment into independently locked shards.
+///
+/// Every operation takes `&self`. Inserts and removals lock only the shard
+/// holding their path's first segment, so writers under `assistant/...`
+/// never block readers or writers under `workflow/...`. Reads that span
+/// every first segment, such as [`with_all`](Self::with_all), hold each
+/// shard's read lock for the duration of the callback.
+///
+/// Reads hand matching values to a callback instead of returning them, as
+/// the references are only valid while the shard locks are held.
+///
+/// # Examples
+///
+/// ```
+/// use agent_uri::CapabilityPath;
+/// use agent_uri_dht::ConcurrentPathTrie;
+///
+/// let trie: ConcurrentPathTrie<String> = ConcurrentPathTrie::new(8);
+/// let chat = CapabilityPath::parse("assistant/chat").unwrap();
+/// let code = CapabilityPath::parse("assistant/code").unwrap();
+///
+/// std::thread::scope(|s| {
+///     s.spawn(|| trie.insert(&chat, "Chat Agent".to_string()));
+///     s.spawn(|| trie.insert(&code, "Code Agent".to_string()));
+/// });
+///
+/// let prefix = CapabilityPath::parse("assistant").unwrap();
+/// assert_eq!(trie.with_prefix(&prefix, |agents| agents.len()), 2);
+/// ```
+pub struct ConcurrentPathTrie<V> {
+    /// Tries holding the paths under each first segment
+    shards: ShardedMap<String, PathTrie<V>>,
+}
+
+impl<V: Clone> Default for ConcurrentPathTrie<V> {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+impl<V> std::fmt::Debug for ConcurrentPathTrie<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConcurrentPathTrie")
+            .field("shards", &self.shards.shard_count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<V: Clone> ConcurrentPathTrie<V> {
+    /// Creates an empty trie split into `shards` shards, at least one.
+    #[must_use]
+    pub fn new(shards: usize) -> Self {
+        Self {
+            shards: ShardedMap::new(shards),
+        }
+    }
+
+    /// Returns the number of values in the trie, at every path.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shards
+            .read_each()
+            .map(|shard| shard.values().map(PathTrie::len).sum::<usize>())
+            .sum()
+    }
+
+    /// Returns true if the trie holds no values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.shards
+            .read_each()
+            .all(|shard| shard.values().all(PathTrie::is_empty))
+    }
+
+    /// Returns an independent copy of the trie, one shard at a time.
+    pub(crate) fn fork(&self) -> Self {
+        Self {
+            shards: self.shards.fork(PathTrie::clone),
+        }
+    }
+
+    /// Inserts a value at the given path.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn insert(&self, path: &CapabilityPath, value: V) {
+        let first = first_segment(path);
+        let mut shard = self.shards.write(first);
+        shard.entry(first.to_string()).or_default().insert(path, value);
+    }
+
+    /// Removes values at the path that match the predicate, pruning nodes
+    /// left empty.
+    ///
+    /// Returns the number of values removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn remove<F>(&self, path: &CapabilityPath, predicate: F) -> usize
+    where
+        F: Fn(&V) -> bool,
+    {
+        self.with_trie_mut(path, |trie| trie.remove(path, predicate))
+            .unwrap_or(0)
+    }
+
+    /// Replaces the values at the path that match the predicate with
+    /// `value`, in one step so readers never see the path without it.
+    ///
+    /// Returns the number of values replaced; `value` is inserted only if
+    /// it is not 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn replace<F>(&self, path: &CapabilityPath, predicate: F, value: V) -> usize
+    where
+        F: Fn(&V) -> bool,
+    {
+        self.with_trie_mut(path, |trie| {
+            let removed = trie.remove(path, predicate);
+            if removed > 0 {
+                trie.insert(path, value);
+            }
+            removed
+        })
+        .unwrap_or(0)
+    }
+
+    /// Removes `prefix` and every path below it, returning their values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    #[must_use = "returns the removed values"]
+    pub fn remove_subtree(&self, prefix: &CapabilityPath) -> Vec<V> {
+        self.with_trie_mut(prefix, |trie| trie.remove_subtree(prefix))
+            .unwrap_or_default()
+    }
+
+    /// Clears all values from the trie.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn clear(&self) {
+        for mut shard in self.shards.write_each() {
+            shard.clear();
+        }
+    }
+
+    /// Calls `f` with the values at the exact path (not including
+    /// descendants).
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn with_exact<R>(&self, path: &CapabilityPath, f: impl FnOnce(Vec<&V>) -> R) -> R {
+        let first = first_segment(path);
+        let shard = self.shards.read(first);
+        f(shard.get(first).map(|trie| trie.get_exact(path)).unwrap_or_default())
+    }
+
+    /// Calls `f` with the values at the path and all descendant paths.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn with_prefix<R>(&self, path: &CapabilityPath, f: impl FnOnce(Vec<&V>) -> R) -> R {
+        let first = first_segment(path);
+        let shard = self.shards.read(first);
+        f(shard.get(first).map(|trie| trie.get_prefix(path)).unwrap_or_default())
+    }
+
+    /// Calls `f` with the values at every path matching `pattern` (not
+    /// including descendants).
+    ///
+    /// A pattern starting with a literal segment reads one shard; one
+    /// starting with a wildcard reads them all.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn with_matching<R>(&self, pattern: &PathPattern, f: impl FnOnce(Vec<&V>) -> R) -> R {
+        if let Some(PatternSegment::Literal(first)) = pattern.segments().first() {
+            let shard = self.shards.read(first.as_str());
+            return f(shard
+                .get(first.as_str())
+                .map(|trie| trie.get_matching(pattern))
+                .unwrap_or_default());
+        }
+        let shards: Vec<_> = self.shards.read_each().collect();
+        f(shards
+            .iter()
+            .flat_map(|shard| shard.values())
+            .flat_map(|trie| trie.get_matching(pattern))
+            .collect())
+    }
+
+    /// Calls `f` with every value in the trie.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn with_all<R>(&self, f: impl FnOnce(Vec<&V>) -> R) -> R {
+        let shards: Vec<_> = self.shards.read_each().collect();
+        f(shards
+            .iter()
+            .flat_map(|shard| shard.values())
+            .flat_map(PathTrie::get_all)
+            .collect())
+    }
+
+    /// Runs `f` on the trie holding `path`'s first segment, dropping the
+    /// trie if `f` leaves it empty. Returns None if there is no such trie.
+    fn with_trie_mut<R>(
+        &self,
+        path: &CapabilityPath,
+        f: impl FnOnce(&mut PathTrie<V>) -> R,
+    ) -> Option<R> {
+        let first = first_segment(path);
+        let mut shard = self.shards.write(first);
+        let trie = shard.get_mut(first)?;
+        let result = f(trie);
+        if trie.is_empty() {
+            shard.remove(first);
+        }
+        Some(result)
+    }
+}
+
+/// Returns the segment a path is sharded by.
+fn first_segment(path: &CapabilityPath) -> &str {
+    path.segments().first().map_or("", |segment| segment.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn path(s: &str) -> CapabilityPath {
+        CapabilityPath::parse(s).unwrap()
+    }
+
+    #[test]
+    fn reads_match_a_plain_trie() {
+        let trie: ConcurrentPathTrie<&str> = ConcurrentPathTrie::new(4);
+        for p in ["assistant/chat", "assistant/code", "assistant", "workflow/run"] {
+            trie.insert(&path(p), p);
+        }
+
+        assert_eq!(trie.len(), 4);
+        assert_eq!(trie.with_exact(&path("assistant"), |v| v.len()), 1);
+        assert_eq!(trie.with_prefix(&path("assistant"), |v| v.len()), 3);
+        assert_eq!(trie.with_prefix(&path("missing"), |v| v.len()), 0);
+        assert_eq!(trie.with_all(|v| v.len()), 4);
+
+        let pattern = PathPattern::parse("*/run").unwrap();
+        assert_eq!(trie.with_matching(&pattern, |v| v.into_iter().copied().collect::<Vec<_>>()), [
+            "workflow/run"
+        ]);
+        let pattern = PathPattern::parse("assistant/*").unwrap();
+        assert_eq!(trie.with_matching(&pattern, |v| v.len()), 2);
+    }
+
+    #[test]
+    fn removals_prune_and_replace_is_atomic() {
+        let trie: ConcurrentPathTrie<(u32, &str)> = ConcurrentPathTrie::new(4);
+        trie.insert(&path("team/a/b"), (1, "old"));
+        trie.insert(&path("team/c"), (2, "c"));
+
+        assert_eq!(trie.replace(&path("team/a/b"), |v| v.0 == 1, (1, "new")), 1);
+        assert_eq!(trie.replace(&path("team/a/b"), |v| v.0 == 9, (9, "none")), 0);
+        assert_eq!(trie.with_exact(&path("team/a/b"), |v| v[0].1), "new");
+
+        assert_eq!(trie.remove(&path("team/c"), |_| true), 1);
+        assert_eq!(trie.remove_subtree(&path("team")), [(1, "new")]);
+        assert!(trie.is_empty());
+        assert_eq!(trie.shards.read_each().map(|shard| shard.len()).sum::<usize>(), 0);
+    }
+
+    #[test]
+    fn concurrent_writers_under_different_segments() {
+        let trie: Arc<ConcurrentPathTrie<usize>> = Arc::new(ConcurrentPathTrie::new(8));
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let trie = Arc::clone(&trie);
+                std::thread::spawn(move || {
+                    for i in 0..100 {
+                        trie.insert(&path(&format!("segment{t}/leaf{}", i % 10)), i);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(trie.len(), 800);
+        assert_eq!(trie.with_prefix(&path("segment3"), |v| v.len()), 100);
+    }
+}
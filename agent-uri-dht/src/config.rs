@@ -2,6 +2,24 @@
 
 use std::time::Duration;
 
+use crate::{
+    ExpiryPolicy, FaultModel, KeyAlgorithm, KeyVersion, LatencyDistribution, LatencyModel,
+};
+
+/// What a [`SimulatedDht`](crate::SimulatedDht) at capacity does with a
+/// new registration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EvictionPolicy {
+    /// Refuse the registration with `CapacityExceeded`.
+    #[default]
+    Reject,
+    /// Evict the registration least recently registered, updated or
+    /// returned by a lookup.
+    LeastRecentlyUsed,
+    /// Evict the registration closest to expiring.
+    SoonestExpiry,
+}
+
 /// Configuration for the simulated DHT.
 ///
 /// Controls behavior such as replication factor, TTL, and verification.
@@ -18,32 +36,136 @@ pub struct SimulationConfig {
     /// Default: 1 hour
     pub default_ttl: Duration,
 
-    /// Whether to verify attestations on registration.
+    /// Shortest TTL a registration or renewal may ask for.
+    ///
+    /// Shorter requests fail with `TtlOutOfBounds`.
+    /// Default: None
+    pub min_ttl: Option<Duration>,
+
+    /// Longest TTL a registration or renewal may ask for.
     ///
+    /// Longer requests fail with `TtlOutOfBounds`.
+    /// Default: None
+    pub max_ttl: Option<Duration>,
+
+    /// Whether to reject registrations that carry no attestation token.
+    ///
+    /// The token itself is checked by the validator set with
+    /// [`SimulatedDht::with_validator`](crate::SimulatedDht::with_validator).
     /// Set to false for evaluation without attestation infrastructure.
     /// Default: false
     pub verify_attestations: bool,
 
     /// Simulated network delay for operations.
     ///
-    /// Used for latency experiments. None means no delay.
+    /// Used for latency experiments. Each operation draws a delay from its
+    /// distribution; see [`LatencyModel`].
+    /// Default: no delay
+    pub latency: LatencyModel,
+
+    /// Number of recent operations whose latency is kept in a
+    /// [`LatencyLog`](crate::LatencyLog).
+    ///
+    /// Read it with
+    /// [`SimulatedDht::latency_log`](crate::SimulatedDht::latency_log).
+    /// None keeps no log.
     /// Default: None
-    pub simulated_delay: Option<Duration>,
+    pub latency_log: Option<usize>,
+
+    /// Failures injected into operations.
+    ///
+    /// Used for testing retry logic and robustness; see [`FaultModel`].
+    /// Default: no faults
+    pub faults: FaultModel,
 
     /// Whether to automatically remove expired registrations.
     ///
     /// Default: true
     pub auto_expire: bool,
+
+    /// Jitter applied to the expiry of stored registrations.
+    ///
+    /// Registrations and renewals have their TTLs shortened by the policy's
+    /// jitter, so a fleet registered together does not expire together.
+    /// Default: no jitter
+    pub expiry: ExpiryPolicy,
+
+    /// Maximum registrations a single lookup may return.
+    ///
+    /// Pages are capped at this size, and unpaged lookups matching more
+    /// fail with `ResultLimitExceeded`. None means no cap.
+    /// Default: None
+    pub max_results_per_query: Option<usize>,
+
+    /// Number of independently locked shards each index is split into.
+    ///
+    /// Writers only block lookups that hash to the same shard, so more
+    /// shards let concurrent registrations and lookups proceed in parallel.
+    /// Default: 16
+    pub shards: usize,
+
+    /// Maximum registrations stored across all trust roots.
+    ///
+    /// None means no limit.
+    /// Default: None
+    pub max_registrations: Option<usize>,
+
+    /// Maximum registrations stored under a single trust root.
+    ///
+    /// None means no limit.
+    /// Default: None
+    pub max_registrations_per_trust_root: Option<usize>,
+
+    /// What to do with a registration that would exceed a capacity limit.
+    ///
+    /// Evicted registrations are announced to watchers as
+    /// [`DhtEvent::Evicted`](crate::DhtEvent::Evicted).
+    /// Default: [`EvictionPolicy::Reject`]
+    pub eviction: EvictionPolicy,
+
+    /// Hash function DHT keys are derived with.
+    ///
+    /// Must match the keyspace of the network being modeled.
+    /// Default: [`KeyAlgorithm::Sha256`]
+    pub key_algorithm: KeyAlgorithm,
+
+    /// Key derivation input DHT keys are derived from.
+    ///
+    /// Must match the keyspace of the network being modeled.
+    /// Default: [`KeyVersion::Legacy`]
+    pub key_version: KeyVersion,
+
+    /// Whether to order everything the DHT hands out by canonical agent
+    /// URI: lookup results, snapshots, and the events announced by bulk
+    /// removals.
+    ///
+    /// Otherwise order follows the internal hash maps and differs between
+    /// runs. Paged lookups are always ordered by path and URI.
+    /// Default: false
+    pub deterministic: bool,
 }
 
 impl Default for SimulationConfig {
     fn default() -> Self {
         Self {
             max_registrations_per_key: 1000,
-            default_ttl: Duration::from_secs(3600),
+            default_ttl: Duration::from_hours(1),
+            min_ttl: None,
+            max_ttl: None,
             verify_attestations: false,
-            simulated_delay: None,
+            latency: LatencyModel::none(),
+            latency_log: None,
+            faults: FaultModel::none(),
             auto_expire: true,
+            expiry: ExpiryPolicy::new(),
+            max_results_per_query: None,
+            shards: 16,
+            max_registrations: None,
+            max_registrations_per_trust_root: None,
+            eviction: EvictionPolicy::Reject,
+            key_algorithm: KeyAlgorithm::Sha256,
+            key_version: KeyVersion::Legacy,
+            deterministic: false,
         }
     }
 }
@@ -69,6 +191,20 @@ impl SimulationConfig {
         self
     }
 
+    /// Sets the shortest TTL a registration or renewal may ask for.
+    #[must_use]
+    pub const fn with_min_ttl(mut self, ttl: Duration) -> Self {
+        self.min_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the longest TTL a registration or renewal may ask for.
+    #[must_use]
+    pub const fn with_max_ttl(mut self, ttl: Duration) -> Self {
+        self.max_ttl = Some(ttl);
+        self
+    }
+
     /// Enables or disables attestation verification.
     #[must_use]
     pub const fn with_verify_attestations(mut self, verify: bool) -> Self {
@@ -76,10 +212,30 @@ impl SimulationConfig {
         self
     }
 
-    /// Sets the simulated network delay.
+    /// Delays every operation by exactly `delay`.
+    #[must_use]
+    pub fn with_simulated_delay(self, delay: Duration) -> Self {
+        self.with_latency(LatencyModel::new(LatencyDistribution::Constant(delay)))
+    }
+
+    /// Sets the latency model operations are delayed by.
+    #[must_use]
+    pub fn with_latency(mut self, latency: LatencyModel) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Keeps the latency of the latest `capacity` operations.
+    #[must_use]
+    pub const fn with_latency_log(mut self, capacity: usize) -> Self {
+        self.latency_log = Some(capacity);
+        self
+    }
+
+    /// Sets the failures injected into operations.
     #[must_use]
-    pub const fn with_simulated_delay(mut self, delay: Duration) -> Self {
-        self.simulated_delay = Some(delay);
+    pub fn with_faults(mut self, faults: FaultModel) -> Self {
+        self.faults = faults;
         self
     }
 
@@ -89,35 +245,113 @@ impl SimulationConfig {
         self.auto_expire = auto_expire;
         self
     }
+
+    /// Sets the jitter applied to the expiry of stored registrations.
+    #[must_use]
+    pub const fn with_expiry_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.expiry = policy;
+        self
+    }
+
+    /// Sets the maximum registrations a single lookup may return.
+    #[must_use]
+    pub const fn with_max_results_per_query(mut self, max: usize) -> Self {
+        self.max_results_per_query = Some(max);
+        self
+    }
+
+    /// Sets the number of shards each index is split into.
+    #[must_use]
+    pub const fn with_shards(mut self, shards: usize) -> Self {
+        self.shards = shards;
+        self
+    }
+
+    /// Sets the maximum registrations stored across all trust roots.
+    #[must_use]
+    pub const fn with_max_registrations(mut self, max: usize) -> Self {
+        self.max_registrations = Some(max);
+        self
+    }
+
+    /// Sets the maximum registrations stored under a single trust root.
+    #[must_use]
+    pub const fn with_max_registrations_per_trust_root(mut self, max: usize) -> Self {
+        self.max_registrations_per_trust_root = Some(max);
+        self
+    }
+
+    /// Sets what happens to a registration that would exceed a capacity
+    /// limit.
+    #[must_use]
+    pub const fn with_eviction(mut self, eviction: EvictionPolicy) -> Self {
+        self.eviction = eviction;
+        self
+    }
+
+    /// Sets the hash function DHT keys are derived with.
+    #[must_use]
+    pub const fn with_key_algorithm(mut self, algorithm: KeyAlgorithm) -> Self {
+        self.key_algorithm = algorithm;
+        self
+    }
+
+    /// Sets the key derivation input DHT keys are derived from.
+    #[must_use]
+    pub const fn with_key_version(mut self, version: KeyVersion) -> Self {
+        self.key_version = version;
+        self
+    }
+
+    /// Sets whether results are ordered by canonical agent URI, for golden
+    /// files and reproducible reports.
+    #[must_use]
+    pub const fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::DhtOperation;
 
     #[test]
     fn default_config() {
         let config = SimulationConfig::default();
         assert_eq!(config.max_registrations_per_key, 1000);
-        assert_eq!(config.default_ttl, Duration::from_secs(3600));
+        assert_eq!(config.default_ttl, Duration::from_hours(1));
+        assert!(config.min_ttl.is_none() && config.max_ttl.is_none());
         assert!(!config.verify_attestations);
-        assert!(config.simulated_delay.is_none());
+        assert!(config.latency.distribution(DhtOperation::Register).is_none());
+        assert!(config.latency_log.is_none());
         assert!(config.auto_expire);
+        assert!(config.max_results_per_query.is_none());
+        assert_eq!(config.shards, 16);
+        assert!(config.max_registrations.is_none());
+        assert!(config.max_registrations_per_trust_root.is_none());
+        assert_eq!(config.eviction, EvictionPolicy::Reject);
+        assert_eq!(config.key_version, KeyVersion::Legacy);
+        assert!(!config.deterministic);
     }
 
     #[test]
     fn builder_pattern() {
         let config = SimulationConfig::new()
             .with_max_registrations_per_key(10)
-            .with_default_ttl(Duration::from_secs(1800))
+            .with_default_ttl(Duration::from_mins(30))
             .with_verify_attestations(true)
             .with_simulated_delay(Duration::from_millis(50))
             .with_auto_expire(false);
 
         assert_eq!(config.max_registrations_per_key, 10);
-        assert_eq!(config.default_ttl, Duration::from_secs(1800));
+        assert_eq!(config.default_ttl, Duration::from_mins(30));
         assert!(config.verify_attestations);
-        assert_eq!(config.simulated_delay, Some(Duration::from_millis(50)));
+        assert_eq!(
+            config.latency.sample(DhtOperation::ExactLookup),
+            Some(Duration::from_millis(50))
+        );
         assert!(!config.auto_expire);
     }
 }
@@ -0,0 +1,263 @@
+//! Keyspace distribution analysis.
+
+use std::collections::HashMap;
+
+use crate::churn::ratio;
+use crate::{DhtKey, KeyAlgorithm, KeyVersion, Registration};
+
+/// Registrations stored under one [`DhtKey`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyLoad {
+    /// The key
+    pub key: DhtKey,
+    /// Trust root and capability path the key was derived from, as
+    /// `trust_root/capability_path`
+    pub path: String,
+    /// Registrations under the key
+    pub registrations: usize,
+}
+
+/// How a set of registrations spreads over the DHT keyspace.
+///
+/// Keys are grouped into 2^`prefix_bits` buckets by their leading bits, a
+/// stand-in for the nodes responsible for each region of the keyspace.
+/// Since keys are derived from the capability path alone, every agent
+/// offering a popular capability lands on the same key, and thus on the
+/// same few nodes; [`hot_keys`](Self::hot_keys) names those paths and
+/// [`gini`](Self::gini) summarizes how uneven the bucket load is.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{Endpoint, KeyspaceDistribution, Registration};
+///
+/// let registrations: Vec<_> = ["2q", "3q", "4q"]
+///     .iter()
+///     .map(|suffix| {
+///         let uri = AgentUri::parse(&format!(
+///             "agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn0{suffix}"
+///         ))
+///         .unwrap();
+///         Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+///     })
+///     .collect();
+///
+/// let distribution = KeyspaceDistribution::analyze(&registrations, 4);
+/// assert_eq!(distribution.unique_keys(), 1);
+/// assert_eq!(distribution.hot_keys(1)[0].registrations, 3);
+/// assert!(distribution.gini() > 0.9);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyspaceDistribution {
+    prefix_bits: u32,
+    buckets: Vec<usize>,
+    keys: Vec<KeyLoad>,
+    total_registrations: usize,
+}
+
+impl KeyspaceDistribution {
+    /// Largest supported bucket prefix; 2^16 buckets.
+    pub const MAX_PREFIX_BITS: u32 = 16;
+
+    /// Analyzes `registrations` under SHA-256 keys, bucketing by the
+    /// leading `prefix_bits` bits (capped at
+    /// [`MAX_PREFIX_BITS`](Self::MAX_PREFIX_BITS)).
+    #[must_use]
+    pub fn analyze(registrations: &[Registration], prefix_bits: u32) -> Self {
+        Self::analyze_with(KeyAlgorithm::Sha256, registrations, prefix_bits)
+    }
+
+    /// Analyzes `registrations` under keys derived with `algorithm`.
+    #[must_use]
+    pub fn analyze_with(
+        algorithm: KeyAlgorithm,
+        registrations: &[Registration],
+        prefix_bits: u32,
+    ) -> Self {
+        Self::analyze_versioned(algorithm, KeyVersion::default(), registrations, prefix_bits)
+    }
+
+    /// Analyzes `registrations` under keys derived with `algorithm` from
+    /// the input of key derivation `version`.
+    #[must_use]
+    pub fn analyze_versioned(
+        algorithm: KeyAlgorithm,
+        version: KeyVersion,
+        registrations: &[Registration],
+        prefix_bits: u32,
+    ) -> Self {
+        let prefix_bits = prefix_bits.min(Self::MAX_PREFIX_BITS);
+        let mut by_key: HashMap<DhtKey, KeyLoad> = HashMap::new();
+        for registration in registrations {
+            let agent_uri = registration.agent_uri();
+            let key = DhtKey::derive_versioned(
+                algorithm,
+                version,
+                agent_uri.trust_root(),
+                agent_uri.capability_path(),
+            );
+            by_key
+                .entry(key)
+                .or_insert_with(|| KeyLoad {
+                    key,
+                    path: format!("{}/{}", agent_uri.trust_root(), agent_uri.capability_path()),
+                    registrations: 0,
+                })
+                .registrations += 1;
+        }
+
+        let mut buckets = vec![0; 1 << prefix_bits];
+        for load in by_key.values() {
+            buckets[bucket(&load.key, prefix_bits)] += load.registrations;
+        }
+
+        let mut keys: Vec<KeyLoad> = by_key.into_values().collect();
+        keys.sort_by(|a, b| b.registrations.cmp(&a.registrations).then(a.key.cmp(&b.key)));
+
+        Self {
+            prefix_bits,
+            buckets,
+            keys,
+            total_registrations: registrations.len(),
+        }
+    }
+
+    /// Returns the number of leading key bits buckets are grouped by.
+    #[must_use]
+    pub const fn prefix_bits(&self) -> u32 {
+        self.prefix_bits
+    }
+
+    /// Returns the registrations in each bucket, indexed by key prefix.
+    #[must_use]
+    pub fn buckets(&self) -> &[usize] {
+        &self.buckets
+    }
+
+    /// Returns the number of registrations analyzed.
+    #[must_use]
+    pub const fn total_registrations(&self) -> usize {
+        self.total_registrations
+    }
+
+    /// Returns the number of distinct keys.
+    #[must_use]
+    pub fn unique_keys(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns the number of buckets holding no registrations.
+    #[must_use]
+    pub fn empty_buckets(&self) -> usize {
+        self.buckets.iter().filter(|&&count| count == 0).count()
+    }
+
+    /// Returns the `count` most loaded keys, most loaded first.
+    #[must_use]
+    pub fn hot_keys(&self, count: usize) -> &[KeyLoad] {
+        &self.keys[..count.min(self.keys.len())]
+    }
+
+    /// Returns the fraction of registrations in the fullest bucket, or 0.0
+    /// if there are none.
+    #[must_use]
+    pub fn max_bucket_share(&self) -> f64 {
+        if self.total_registrations == 0 {
+            return 0.0;
+        }
+        let max = self.buckets.iter().copied().max().unwrap_or(0);
+        ratio(max, self.total_registrations)
+    }
+
+    /// Returns the Gini coefficient of the bucket loads: 0.0 when every
+    /// bucket holds the same number of registrations, approaching 1.0 as
+    /// they concentrate in a single bucket. Returns 0.0 if there are no
+    /// registrations.
+    #[must_use]
+    pub fn gini(&self) -> f64 {
+        let mut loads = self.buckets.clone();
+        loads.sort_unstable();
+        let n = loads.len();
+        let total: usize = loads.iter().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        // G = (2 * sum(i * x_i) - (n + 1) * sum(x_i)) / (n * sum(x_i)),
+        // for loads sorted ascending and i counted from 1
+        let weighted: usize = loads.iter().enumerate().map(|(i, &x)| (i + 1) * x).sum();
+        ratio(2 * weighted - (n + 1) * total, n * total)
+    }
+}
+
+/// Returns the bucket of `key`: its leading `prefix_bits` bits.
+fn bucket(key: &DhtKey, prefix_bits: u32) -> usize {
+    if prefix_bits == 0 {
+        return 0;
+    }
+    let bytes = key.as_bytes();
+    let leading = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    usize::try_from(leading >> (32 - prefix_bits)).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_uri::AgentUri;
+
+    use super::*;
+    use crate::Endpoint;
+
+    fn registration(path: &str, suffix: usize) -> Registration {
+        let uri = AgentUri::parse(&format!(
+            "agent://acme.com/{path}/llm_01h455vb4pex5vsknk084s{suffix:04x}"
+        ))
+        .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+    }
+
+    #[test]
+    fn popular_paths_are_reported_as_hot_keys() {
+        let mut registrations: Vec<_> = (0..10).map(|i| registration("chat", i)).collect();
+        registrations.push(registration("code", 10));
+        registrations.push(registration("search", 11));
+
+        let distribution = KeyspaceDistribution::analyze(&registrations, 8);
+        assert_eq!(distribution.total_registrations(), 12);
+        assert_eq!(distribution.unique_keys(), 3);
+        assert_eq!(distribution.buckets().len(), 256);
+        assert_eq!(distribution.buckets().iter().sum::<usize>(), 12);
+
+        let hot = distribution.hot_keys(2);
+        assert_eq!(hot.len(), 2);
+        assert_eq!(hot[0].path, "acme.com/chat");
+        assert_eq!(hot[0].registrations, 10);
+        assert_eq!(distribution.hot_keys(10).len(), 3);
+        assert!(distribution.max_bucket_share() >= 10.0 / 12.0);
+    }
+
+    #[test]
+    fn gini_measures_concentration() {
+        let spread: Vec<_> = (0..64).map(|i| registration(&format!("cap{i}"), i)).collect();
+        let spread = KeyspaceDistribution::analyze(&spread, 2);
+        let concentrated: Vec<_> = (0..64).map(|i| registration("chat", i)).collect();
+        let concentrated = KeyspaceDistribution::analyze(&concentrated, 2);
+
+        assert!(spread.gini() < 0.3, "{}", spread.gini());
+        assert_eq!(spread.empty_buckets(), 0);
+        assert_eq!(concentrated.empty_buckets(), 3);
+        assert!((concentrated.gini() - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn empty_input_and_prefix_bounds() {
+        let empty = KeyspaceDistribution::analyze(&[], 0);
+        assert_eq!(empty.buckets(), &[0]);
+        assert!(empty.gini().abs() < f64::EPSILON);
+        assert!(empty.max_bucket_share().abs() < f64::EPSILON);
+        assert!(empty.hot_keys(5).is_empty());
+
+        let capped = KeyspaceDistribution::analyze(&[registration("chat", 0)], 40);
+        assert_eq!(capped.prefix_bits(), KeyspaceDistribution::MAX_PREFIX_BITS);
+        assert_eq!(capped.buckets().len(), 1 << 16);
+    }
+}
@@ -0,0 +1,475 @@
+//! Mirroring registrations into DNS-SD records.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use agent_uri::{AgentUri, TrustRoot};
+
+use crate::txt::{self, instance_name};
+use crate::{Dht, DhtError, LookupCursor, Registration};
+
+/// A DNS resource record published by a [`DnsBridge`].
+///
+/// Names are absolute, ending in a dot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DnsRecord {
+    /// Points the service type at an agent's instance name
+    Ptr {
+        /// Owner name, the service type
+        name: String,
+        /// The agent's instance name
+        target: String,
+    },
+    /// Locates one of an agent's endpoints
+    Srv {
+        /// Owner name, the agent's instance name
+        name: String,
+        /// Priority; lower is tried first
+        priority: u16,
+        /// Relative weight among endpoints of the same priority
+        weight: u16,
+        /// Port the endpoint listens on
+        port: u16,
+        /// Host name of the endpoint
+        target: String,
+    },
+    /// Carries an agent's registration as `key=value` strings
+    Txt {
+        /// Owner name, the agent's instance name
+        name: String,
+        /// The TXT strings, in order
+        entries: Vec<String>,
+    },
+}
+
+impl DnsRecord {
+    /// Returns the owner name of the record.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Ptr { name, .. } | Self::Srv { name, .. } | Self::Txt { name, .. } => name,
+        }
+    }
+
+    /// Decodes the registration carried by a TXT record, for clients
+    /// reading the zone back. Returns None for other records, or TXT
+    /// records without an agent URI.
+    #[must_use]
+    pub fn registration(&self) -> Option<Registration> {
+        let Self::Txt { entries, .. } = self else {
+            return None;
+        };
+        txt::decode(entries.iter().filter_map(|entry| entry.split_once('=')))
+    }
+}
+
+/// Applies record changes to a DNS zone, e.g. through RFC 2136 dynamic
+/// updates or a DNS provider's API.
+///
+/// Updates are made per owner name: every record at a name is replaced at
+/// once, so implementations need not diff individual records.
+pub trait DnsUpdater: Send + Sync {
+    /// Replaces every record at `name` with `records`, published with
+    /// time-to-live `ttl`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::Backend` if the zone could not be updated.
+    fn replace(&self, name: &str, records: &[DnsRecord], ttl: Duration) -> Result<(), DhtError>;
+
+    /// Removes every record at `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::Backend` if the zone could not be updated.
+    fn remove(&self, name: &str) -> Result<(), DhtError>;
+}
+
+/// The outcome of a [`DnsBridge::sync`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DnsSyncReport {
+    /// Registrations mirrored into the zone
+    pub published: usize,
+    /// Names whose records were replaced
+    pub replaced: usize,
+    /// Names whose records were removed
+    pub removed: usize,
+    /// Registrations left out of the zone, as they cannot be encoded in
+    /// TXT strings
+    pub skipped: Vec<(AgentUri, DhtError)>,
+    /// Names the updater failed to change; they are retried on the next
+    /// sync
+    pub failed: Vec<(String, DhtError)>,
+}
+
+/// Mirrors the registrations under a trust root into DNS-SD records, so
+/// clients that only speak DNS can discover agents.
+///
+/// The DHT stays authoritative: each [`sync`](Self::sync) reads every
+/// registration under the trust root and brings the zone in line with it
+/// through a [`DnsUpdater`], changing only names whose records differ
+/// from the last sync. Agents are published as instances of
+/// `_agent._tcp.{trust_root}.`, as in RFC 6763:
+///
+/// - a PTR record at the service type for every agent
+/// - a SRV record at the agent's instance name for each endpoint with a
+///   host name and a port, explicit or the protocol's well-known one
+/// - a TXT record at the instance name carrying the registration, in the
+///   same properties as `MdnsDht` advertises
+///
+/// Instance names hash the agent URI, since URIs may exceed DNS's 63-byte
+/// label limit; clients read the URI from the `uri` TXT property.
+///
+/// If the DHT cannot be read, the sync fails without touching the zone.
+/// Call `sync` periodically, e.g. on the interval records are published
+/// for.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Mutex;
+/// use std::time::Duration;
+///
+/// use agent_uri::{AgentUri, TrustRoot};
+/// use agent_uri_dht::{
+///     Dht, DhtError, DnsBridge, DnsRecord, DnsUpdater, Endpoint, Registration, SimulatedDht,
+/// };
+///
+/// #[derive(Default)]
+/// struct Log(Mutex<Vec<String>>);
+///
+/// impl DnsUpdater for Log {
+///     fn replace(&self, name: &str, _: &[DnsRecord], _: Duration) -> Result<(), DhtError> {
+///         self.0.lock().unwrap().push(format!("replace {name}"));
+///         Ok(())
+///     }
+///
+///     fn remove(&self, name: &str) -> Result<(), DhtError> {
+///         self.0.lock().unwrap().push(format!("remove {name}"));
+///         Ok(())
+///     }
+/// }
+///
+/// let dht = SimulatedDht::with_defaults();
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// dht.register(Registration::new(uri, vec![Endpoint::https("agent.acme.com")])).unwrap();
+///
+/// let mut bridge = DnsBridge::new(TrustRoot::parse("acme.com").unwrap(), Log::default());
+/// let report = bridge.sync(&dht)?;
+/// assert_eq!(report.published, 1);
+/// assert_eq!(report.replaced, 2); // the service type and the agent's instance
+///
+/// // Nothing changed, so nothing is sent
+/// assert_eq!(bridge.sync(&dht)?.replaced, 0);
+/// # Ok::<(), DhtError>(())
+/// ```
+#[derive(Debug)]
+pub struct DnsBridge<U> {
+    trust_root: TrustRoot,
+    updater: U,
+    ttl: Duration,
+    published: BTreeMap<String, Vec<DnsRecord>>,
+}
+
+impl<U: DnsUpdater> DnsBridge<U> {
+    /// Time-to-live of published records, unless
+    /// [`with_ttl`](Self::with_ttl) is used.
+    pub const DEFAULT_TTL: Duration = Duration::from_mins(1);
+
+    /// Registrations read from the DHT per page.
+    const PAGE_SIZE: usize = 256;
+
+    /// Creates a bridge publishing the agents under `trust_root` through
+    /// `updater`.
+    #[must_use]
+    pub const fn new(trust_root: TrustRoot, updater: U) -> Self {
+        Self {
+            trust_root,
+            updater,
+            ttl: Self::DEFAULT_TTL,
+            published: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the time-to-live of published records.
+    #[must_use]
+    pub const fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Returns the trust root whose agents are published.
+    #[must_use]
+    pub const fn trust_root(&self) -> &TrustRoot {
+        &self.trust_root
+    }
+
+    /// Returns the updater.
+    #[must_use]
+    pub const fn updater(&self) -> &U {
+        &self.updater
+    }
+
+    /// Returns the service type agents are published under, e.g.
+    /// `_agent._tcp.acme.com.`.
+    #[must_use]
+    pub fn service_name(&self) -> String {
+        format!("_agent._tcp.{}.", self.trust_root.host_str())
+    }
+
+    /// Returns the records currently published, by owner name.
+    #[must_use]
+    pub const fn published(&self) -> &BTreeMap<String, Vec<DnsRecord>> {
+        &self.published
+    }
+
+    /// Brings the zone in line with the registrations under the trust root
+    /// in `dht`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the registrations could not be read from `dht`;
+    /// the zone is left unchanged. Failures to update individual names are
+    /// reported in [`DnsSyncReport::failed`] instead.
+    pub fn sync<D: Dht + ?Sized>(&mut self, dht: &D) -> Result<DnsSyncReport, DhtError> {
+        let mut registrations = Vec::new();
+        let mut cursor: Option<LookupCursor> = None;
+        loop {
+            let page =
+                dht.lookup_trust_root_paged(&self.trust_root, Self::PAGE_SIZE, cursor.as_ref())?;
+            registrations.extend(page.registrations);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let mut report = DnsSyncReport::default();
+        let zone = self.zone(registrations, &mut report);
+
+        for (name, records) in &zone {
+            if self.published.get(name) == Some(records) {
+                continue;
+            }
+            match self.updater.replace(name, records, self.ttl) {
+                Ok(()) => {
+                    self.published.insert(name.clone(), records.clone());
+                    report.replaced += 1;
+                }
+                Err(e) => report.failed.push((name.clone(), e)),
+            }
+        }
+        let stale: Vec<String> = self
+            .published
+            .keys()
+            .filter(|name| !zone.contains_key(*name))
+            .cloned()
+            .collect();
+        for name in stale {
+            match self.updater.remove(&name) {
+                Ok(()) => {
+                    self.published.remove(&name);
+                    report.removed += 1;
+                }
+                Err(e) => report.failed.push((name, e)),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Builds the records for `registrations`, by owner name.
+    fn zone(
+        &self,
+        registrations: Vec<Registration>,
+        report: &mut DnsSyncReport,
+    ) -> BTreeMap<String, Vec<DnsRecord>> {
+        let service = self.service_name();
+        let mut zone = BTreeMap::new();
+        let mut pointers = Vec::new();
+        for registration in registrations {
+            let properties = match txt::encode(&registration) {
+                Ok(properties) => properties,
+                Err(e) => {
+                    report.skipped.push((registration.agent_uri().clone(), e));
+                    continue;
+                }
+            };
+            let instance = format!("{}.{service}", instance_name(registration.agent_uri()));
+            let mut records = srv_records(&instance, &registration);
+            records.push(DnsRecord::Txt {
+                name: instance.clone(),
+                entries: properties
+                    .into_iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect(),
+            });
+            pointers.push(DnsRecord::Ptr {
+                name: service.clone(),
+                target: instance.clone(),
+            });
+            zone.insert(instance, records);
+            report.published += 1;
+        }
+        if !pointers.is_empty() {
+            zone.insert(service, pointers);
+        }
+        zone
+    }
+}
+
+/// Returns a SRV record for each of `registration`'s endpoints that has a
+/// host name and a port, in endpoint order.
+fn srv_records(instance: &str, registration: &Registration) -> Vec<DnsRecord> {
+    registration
+        .endpoints()
+        .iter()
+        .filter_map(|endpoint| {
+            let host = endpoint.host()?;
+            if host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().is_ok() {
+                return None;
+            }
+            let port = endpoint.port().or_else(|| endpoint.default_port())?;
+            Some(DnsRecord::Srv {
+                name: instance.to_string(),
+                priority: endpoint
+                    .priority()
+                    .map_or(0, |priority| u16::try_from(priority).unwrap_or(u16::MAX)),
+                weight: endpoint
+                    .weight()
+                    .map_or(1, |weight| u16::try_from(weight).unwrap_or(u16::MAX)),
+                port,
+                target: format!("{}.", host.trim_end_matches('.')),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{Endpoint, SimulatedDht};
+
+    /// An in-memory zone.
+    #[derive(Default)]
+    struct Zone {
+        records: Mutex<BTreeMap<String, Vec<DnsRecord>>>,
+        fail: Mutex<Option<String>>,
+    }
+
+    impl DnsUpdater for Zone {
+        fn replace(&self, name: &str, records: &[DnsRecord], _: Duration) -> Result<(), DhtError> {
+            if self.fail.lock().unwrap().as_deref() == Some(name) {
+                return Err(DhtError::backend("update refused"));
+            }
+            self.records.lock().unwrap().insert(name.to_string(), records.to_vec());
+            Ok(())
+        }
+
+        fn remove(&self, name: &str) -> Result<(), DhtError> {
+            self.records.lock().unwrap().remove(name);
+            Ok(())
+        }
+    }
+
+    fn uri(suffix: &str) -> AgentUri {
+        AgentUri::parse(&format!(
+            "agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn0{suffix}"
+        ))
+        .unwrap()
+    }
+
+    fn bridge() -> DnsBridge<Zone> {
+        DnsBridge::new(TrustRoot::parse("acme.com").unwrap(), Zone::default())
+    }
+
+    #[test]
+    fn publishes_ptr_srv_and_txt_records() {
+        let dht = SimulatedDht::with_defaults();
+        dht.register(Registration::new(uri("2q"), vec![
+            Endpoint::https("agent.acme.com"),
+            Endpoint::grpc("rpc.acme.com:50051").with_priority(1).with_weight(3),
+            Endpoint::https("10.0.0.1:8443"),
+            Endpoint::multiaddr("/ip4/10.0.0.1/tcp/4001"),
+        ]))
+        .unwrap();
+
+        let mut bridge = bridge();
+        let report = bridge.sync(&dht).unwrap();
+        assert_eq!(report.published, 1);
+        assert_eq!(report.replaced, 2);
+
+        let instance = format!("{}._agent._tcp.acme.com.", instance_name(&uri("2q")));
+        let zone = bridge.updater().records.lock().unwrap().clone();
+        assert_eq!(zone["_agent._tcp.acme.com."], [DnsRecord::Ptr {
+            name: "_agent._tcp.acme.com.".to_string(),
+            target: instance.clone(),
+        }]);
+        let records = &zone[&instance];
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0], DnsRecord::Srv {
+            name: instance.clone(),
+            priority: 0,
+            weight: 1,
+            port: 443,
+            target: "agent.acme.com.".to_string(),
+        });
+        assert!(matches!(records[1], DnsRecord::Srv { priority: 1, weight: 3, port: 50051, .. }));
+        assert_eq!(records[2].registration().unwrap().endpoints().len(), 4);
+        assert!(records[0].registration().is_none());
+        assert_eq!(&zone, bridge.published());
+    }
+
+    #[test]
+    fn syncs_only_changes() {
+        let dht = SimulatedDht::with_defaults();
+        for suffix in ["2q", "3q"] {
+            dht.register(Registration::new(uri(suffix), vec![Endpoint::https("agent.acme.com")]))
+                .unwrap();
+        }
+        let mut bridge = bridge();
+        assert_eq!(bridge.sync(&dht).unwrap().replaced, 3);
+        assert_eq!(bridge.sync(&dht).unwrap(), DnsSyncReport {
+            published: 2,
+            ..DnsSyncReport::default()
+        });
+
+        dht.update_endpoint(&uri("2q"), vec![Endpoint::https("eu.acme.com")]).unwrap();
+        assert_eq!(bridge.sync(&dht).unwrap().replaced, 1);
+
+        dht.deregister(&uri("3q")).unwrap();
+        let report = bridge.sync(&dht).unwrap();
+        assert_eq!((report.replaced, report.removed), (1, 1));
+
+        dht.deregister(&uri("2q")).unwrap();
+        let report = bridge.sync(&dht).unwrap();
+        assert_eq!((report.replaced, report.removed), (0, 2));
+        assert!(bridge.updater().records.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn failed_updates_are_retried_and_oversized_records_skipped() {
+        let dht = SimulatedDht::with_defaults();
+        dht.register(Registration::new(uri("2q"), vec![Endpoint::https("agent.acme.com")]))
+            .unwrap();
+        dht.register(
+            Registration::new(uri("3q"), vec![Endpoint::https("agent.acme.com")])
+                .with_metadata("notes", "x".repeat(300)),
+        )
+        .unwrap();
+
+        let mut bridge = bridge();
+        *bridge.updater().fail.lock().unwrap() = Some("_agent._tcp.acme.com.".to_string());
+        let report = bridge.sync(&dht).unwrap();
+        assert_eq!(report.published, 1);
+        assert_eq!(report.skipped[0].0, uri("3q"));
+        assert_eq!(report.failed[0].0, "_agent._tcp.acme.com.");
+
+        *bridge.updater().fail.lock().unwrap() = None;
+        let report = bridge.sync(&dht).unwrap();
+        assert_eq!(report.replaced, 1);
+        assert!(report.failed.is_empty());
+    }
+}
@@ -0,0 +1,337 @@
+//! Using [`Dht`] implementations behind pointers and trait objects.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+
+use crate::{
+    Dht, DhtError, DhtEvent, DhtHealth, DhtStats, Endpoint, LookupCursor, LookupFilter,
+    LookupPage, PathPattern, Registration, TrustRootGroup,
+};
+
+/// Implements [`Dht`] for a wrapper by calling through to the `Dht` it
+/// dereferences to, including the provided methods, so overrides of them
+/// are kept.
+macro_rules! forward_dht {
+    ($wrapper:ty $(, $param:ident)?) => {
+        impl$(<$param: Dht + ?Sized>)? Dht for $wrapper {
+            fn register(&self, registration: Registration) -> Result<(), DhtError> {
+                (**self).register(registration)
+            }
+
+            fn update_endpoint(
+                &self,
+                agent_uri: &AgentUri,
+                new_endpoints: Vec<Endpoint>,
+            ) -> Result<(), DhtError> {
+                (**self).update_endpoint(agent_uri, new_endpoints)
+            }
+
+            fn update_registration(&self, registration: Registration) -> Result<(), DhtError> {
+                (**self).update_registration(registration)
+            }
+
+            fn renew(&self, agent_uri: &AgentUri, ttl: Duration) -> Result<(), DhtError> {
+                (**self).renew(agent_uri, ttl)
+            }
+
+            fn deregister(&self, agent_uri: &AgentUri) -> Result<(), DhtError> {
+                (**self).deregister(agent_uri)
+            }
+
+            fn lookup_exact(
+                &self,
+                trust_root: &TrustRoot,
+                capability_path: &CapabilityPath,
+            ) -> Result<Vec<Registration>, DhtError> {
+                (**self).lookup_exact(trust_root, capability_path)
+            }
+
+            fn lookup_many(
+                &self,
+                queries: &[(TrustRoot, CapabilityPath)],
+            ) -> Vec<Result<Vec<Registration>, DhtError>> {
+                (**self).lookup_many(queries)
+            }
+
+            fn lookup_prefix(
+                &self,
+                trust_root: &TrustRoot,
+                capability_path: &CapabilityPath,
+            ) -> Result<Vec<Registration>, DhtError> {
+                (**self).lookup_prefix(trust_root, capability_path)
+            }
+
+            fn lookup_prefix_paged(
+                &self,
+                trust_root: &TrustRoot,
+                capability_path: &CapabilityPath,
+                limit: usize,
+                cursor: Option<&LookupCursor>,
+            ) -> Result<LookupPage, DhtError> {
+                (**self).lookup_prefix_paged(trust_root, capability_path, limit, cursor)
+            }
+
+            fn lookup_global(
+                &self,
+                capability_path: &CapabilityPath,
+            ) -> Result<Vec<Registration>, DhtError> {
+                (**self).lookup_global(capability_path)
+            }
+
+            fn lookup_global_grouped(
+                &self,
+                capability_path: &CapabilityPath,
+                per_root_limit: Option<usize>,
+            ) -> Result<Vec<TrustRootGroup>, DhtError> {
+                (**self).lookup_global_grouped(capability_path, per_root_limit)
+            }
+
+            fn lookup_pattern(
+                &self,
+                trust_root: &TrustRoot,
+                pattern: &PathPattern,
+            ) -> Result<Vec<Registration>, DhtError> {
+                (**self).lookup_pattern(trust_root, pattern)
+            }
+
+            fn lookup_pattern_global(
+                &self,
+                pattern: &PathPattern,
+            ) -> Result<Vec<Registration>, DhtError> {
+                (**self).lookup_pattern_global(pattern)
+            }
+
+            fn lookup_trust_root(
+                &self,
+                trust_root: &TrustRoot,
+            ) -> Result<Vec<Registration>, DhtError> {
+                (**self).lookup_trust_root(trust_root)
+            }
+
+            fn lookup_trust_root_paged(
+                &self,
+                trust_root: &TrustRoot,
+                limit: usize,
+                cursor: Option<&LookupCursor>,
+            ) -> Result<LookupPage, DhtError> {
+                (**self).lookup_trust_root_paged(trust_root, limit, cursor)
+            }
+
+            fn watch_prefix(
+                &self,
+                trust_root: &TrustRoot,
+                capability_path: &CapabilityPath,
+            ) -> Result<Receiver<DhtEvent>, DhtError> {
+                (**self).watch_prefix(trust_root, capability_path)
+            }
+
+            fn lookup_exact_filtered(
+                &self,
+                trust_root: &TrustRoot,
+                capability_path: &CapabilityPath,
+                filter: &LookupFilter,
+            ) -> Result<Vec<Registration>, DhtError> {
+                (**self).lookup_exact_filtered(trust_root, capability_path, filter)
+            }
+
+            fn lookup_prefix_filtered(
+                &self,
+                trust_root: &TrustRoot,
+                capability_path: &CapabilityPath,
+                filter: &LookupFilter,
+            ) -> Result<Vec<Registration>, DhtError> {
+                (**self).lookup_prefix_filtered(trust_root, capability_path, filter)
+            }
+
+            fn lookup_prefix_paged_filtered(
+                &self,
+                trust_root: &TrustRoot,
+                capability_path: &CapabilityPath,
+                limit: usize,
+                cursor: Option<&LookupCursor>,
+                filter: &LookupFilter,
+            ) -> Result<LookupPage, DhtError> {
+                (**self).lookup_prefix_paged_filtered(
+                    trust_root,
+                    capability_path,
+                    limit,
+                    cursor,
+                    filter,
+                )
+            }
+
+            fn lookup_global_filtered(
+                &self,
+                capability_path: &CapabilityPath,
+                filter: &LookupFilter,
+            ) -> Result<Vec<Registration>, DhtError> {
+                (**self).lookup_global_filtered(capability_path, filter)
+            }
+
+            fn ping(&self) -> Result<(), DhtError> {
+                (**self).ping()
+            }
+
+            fn health(&self) -> DhtHealth {
+                (**self).health()
+            }
+
+            fn stats(&self) -> Result<DhtStats, DhtError> {
+                (**self).stats()
+            }
+        }
+    };
+}
+
+forward_dht!(&D, D);
+forward_dht!(Box<D>, D);
+forward_dht!(Arc<D>, D);
+forward_dht!(DynDht);
+
+/// A shared, type-erased [`Dht`].
+///
+/// Wrappers such as [`CachingDht`](crate::CachingDht) are generic over the
+/// backend they wrap, so a stack chosen at run time (say, a cache over
+/// Redis in production and over a [`SimulatedDht`](crate::SimulatedDht) in
+/// tests) would otherwise need a type per combination. `DynDht` erases the
+/// backend to one type that is cheap to clone and can itself be wrapped.
+/// Every [`Dht`] method, provided ones included, is called through to the
+/// backend.
+///
+/// `Arc<D>`, `Box<D>` and `&D` implement [`Dht`] as well, for `D` either a
+/// concrete backend or `dyn Dht`.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+/// use agent_uri_dht::{CachingDht, Dht, DynDht, Endpoint, Registration, SimulatedDht};
+///
+/// fn backend(redis_url: Option<&str>) -> DynDht {
+///     match redis_url {
+///         // e.g. DynDht::new(RedisDht::connect(url)?) with the `redis` feature
+///         Some(_) => unimplemented!(),
+///         None => DynDht::new(SimulatedDht::with_defaults()),
+///     }
+/// }
+///
+/// let dht = CachingDht::new(backend(None));
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// dht.register(Registration::new(uri, vec![Endpoint::https("agent.acme.com")]))?;
+///
+/// // Stacks erase again, so they can be passed around as one type
+/// let stack = DynDht::new(dht);
+/// let found = stack.lookup_exact(
+///     &TrustRoot::parse("acme.com").unwrap(),
+///     &CapabilityPath::parse("assistant/chat").unwrap(),
+/// )?;
+/// assert_eq!(found.len(), 1);
+/// # Ok::<(), agent_uri_dht::DhtError>(())
+/// ```
+#[derive(Clone)]
+pub struct DynDht {
+    inner: Arc<dyn Dht>,
+}
+
+impl DynDht {
+    /// Erases `dht`'s type.
+    #[must_use]
+    pub fn new(dht: impl Dht + 'static) -> Self {
+        Self {
+            inner: Arc::new(dht),
+        }
+    }
+
+    /// Wraps an already shared backend, so the caller can keep using it
+    /// directly.
+    #[must_use]
+    pub fn from_arc(dht: Arc<dyn Dht>) -> Self {
+        Self { inner: dht }
+    }
+
+    /// Returns the backend.
+    #[must_use]
+    pub fn inner(&self) -> &dyn Dht {
+        &*self.inner
+    }
+
+    /// Returns the shared backend.
+    #[must_use]
+    pub fn into_arc(self) -> Arc<dyn Dht> {
+        self.inner
+    }
+}
+
+impl std::ops::Deref for DynDht {
+    type Target = dyn Dht;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.inner
+    }
+}
+
+impl fmt::Debug for DynDht {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynDht").finish_non_exhaustive()
+    }
+}
+
+impl<D: Dht + 'static> From<Arc<D>> for DynDht {
+    fn from(dht: Arc<D>) -> Self {
+        Self { inner: dht }
+    }
+}
+
+impl From<Box<dyn Dht>> for DynDht {
+    fn from(dht: Box<dyn Dht>) -> Self {
+        Self { inner: dht.into() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CachingDht, SimulatedDht};
+
+    fn registration() -> Registration {
+        let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q")
+            .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+    }
+
+    fn exact(dht: &impl Dht) -> usize {
+        let uri = registration().agent_uri().clone();
+        dht.lookup_exact(uri.trust_root(), uri.capability_path()).unwrap().len()
+    }
+
+    #[test]
+    fn pointers_to_backends_are_backends() {
+        let shared = Arc::new(SimulatedDht::with_defaults());
+        let boxed: Box<dyn Dht> = Box::new(CachingDht::new(Arc::clone(&shared)));
+        boxed.register(registration()).unwrap();
+
+        assert_eq!(exact(&shared), 1);
+        assert_eq!(exact(&boxed), 1);
+        assert_eq!(exact(&&*boxed), 1);
+        assert_eq!(SimulatedDht::stats(&shared).total_registrations, 1);
+    }
+
+    #[test]
+    fn dyn_dht_composes_and_forwards_overrides() {
+        let simulated = Arc::new(SimulatedDht::with_defaults());
+        let stack = DynDht::new(CachingDht::new(DynDht::from(Arc::clone(&simulated))));
+        let erased: Box<dyn Dht> = Box::new(stack.clone());
+        erased.register(registration()).unwrap();
+
+        assert_eq!(exact(&stack), 1);
+        assert_eq!(SimulatedDht::stats(&simulated).total_registrations, 1);
+        // The backend's own stats, not the trait's empty default
+        assert_eq!(stack.inner().stats().unwrap().total_registrations, 1);
+        assert_eq!(DynDht::from(erased).stats().unwrap().total_registrations, 1);
+        assert!(format!("{stack:?}").starts_with("DynDht"));
+    }
+}
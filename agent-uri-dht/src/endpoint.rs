@@ -1,6 +1,10 @@
 //! Network endpoint type for agent discovery.
 
+use std::collections::BTreeMap;
 use std::fmt;
+use std::str::FromStr;
+
+use crate::DhtError;
 
 /// Network endpoint for contacting an agent.
 ///
@@ -14,6 +18,24 @@ use std::fmt;
 ///
 /// let https = Endpoint::new("https", "agent.example.com:443", Some("/v1/agent"));
 /// let grpc = Endpoint::new("grpc", "agent.example.com:50051", None::<&str>);
+///
+/// // Parsed from configuration strings
+/// let parsed = Endpoint::parse("grpc://agent.example.com:50051").unwrap();
+/// assert_eq!(parsed, grpc);
+///
+/// // Canonical forms for newer transports
+/// let wt = Endpoint::webtransport("Agent.Example.com:443", "session");
+/// assert_eq!(wt.to_uri(), "webtransport://agent.example.com/session");
+/// assert!(Endpoint::quic("agent.example.com").validate().is_err());
+///
+/// // Hints for endpoint selection
+/// let eu = Endpoint::https("eu.agent.example.com:443")
+///     .with_region("eu-west-1")
+///     .with_priority(1)
+///     .with_weight(3)
+///     .with_protocol_version("a2a/1.0")
+///     .with_metadata("gpu", "a100");
+/// assert_eq!(eu.region(), Some("eu-west-1"));
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Endpoint {
@@ -23,9 +45,22 @@ pub struct Endpoint {
     address: String,
     /// Optional path prefix for routing
     path: Option<String>,
+    /// Deployment region (e.g., "eu-west-1")
+    region: Option<String>,
+    /// Preference order among an agent's endpoints; lower is tried first
+    priority: Option<u32>,
+    /// Relative share of traffic among endpoints of the same priority
+    weight: Option<u32>,
+    /// Application protocol versions served (e.g., "a2a/1.0")
+    protocol_versions: Vec<String>,
+    /// Free-form key/value annotations
+    metadata: BTreeMap<String, String>,
 }
 
 impl Endpoint {
+    /// Protocol name of libp2p multiaddr endpoints.
+    pub const MULTIADDR: &'static str = "multiaddr";
+
     /// Creates a new endpoint.
     ///
     /// # Arguments
@@ -43,9 +78,46 @@ impl Endpoint {
             protocol: protocol.into(),
             address: address.into(),
             path: path.map(Into::into),
+            region: None,
+            priority: None,
+            weight: None,
+            protocol_versions: Vec::new(),
+            metadata: BTreeMap::new(),
         }
     }
 
+    /// Parses an endpoint URI such as `grpc://agent.example.com:50051` or
+    /// `https://agent.example.com/v1/agent`, as written by
+    /// [`to_uri`](Self::to_uri).
+    ///
+    /// The scheme picks the constructor, so `wss`, `quic` and
+    /// `webtransport` endpoints come out in the same canonical form as from
+    /// [`wss`](Self::wss), [`quic`](Self::quic) and
+    /// [`webtransport`](Self::webtransport); the scheme itself is
+    /// lowercased. A string starting with `/` is taken as a
+    /// [multiaddr](Self::multiaddr). The result must pass
+    /// [`validate`](Self::validate).
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::InvalidEndpoint` if `uri` has no scheme or the
+    /// endpoint breaks a rule of its protocol.
+    pub fn parse(uri: &str) -> Result<Self, DhtError> {
+        let Some((protocol, address, path)) = split_uri(uri) else {
+            return Err(DhtError::invalid_endpoint(uri, "missing scheme, e.g. 'https://'"));
+        };
+        let protocol = protocol.to_ascii_lowercase();
+        let endpoint = match (protocol.as_str(), path) {
+            ("wss", None) => Self::wss(address),
+            ("quic", None) => Self::quic(address),
+            ("webtransport", Some(path)) => Self::webtransport(address, path),
+            (Self::MULTIADDR, _) => Self::multiaddr(address),
+            _ => Self::new(protocol, address, path),
+        };
+        endpoint.validate()?;
+        Ok(endpoint)
+    }
+
     /// Creates an HTTPS endpoint.
     #[must_use]
     pub fn https(address: impl Into<String>) -> Self {
@@ -70,6 +142,99 @@ impl Endpoint {
         Self::new("ws", address, None::<String>)
     }
 
+    /// Creates a secure WebSocket endpoint.
+    ///
+    /// The address is lowercased and the default port 443 dropped.
+    #[must_use]
+    pub fn wss(address: impl Into<String>) -> Self {
+        Self::new("wss", canonical_address(&address.into(), 443), None::<String>)
+    }
+
+    /// Creates a raw QUIC endpoint.
+    ///
+    /// The address is lowercased. QUIC has no default port, so `address`
+    /// must include one to pass [`validate`](Self::validate).
+    #[must_use]
+    pub fn quic(address: impl Into<String>) -> Self {
+        Self::new("quic", address.into().to_ascii_lowercase(), None::<String>)
+    }
+
+    /// Creates a WebTransport endpoint for the session at `path`.
+    ///
+    /// The address is lowercased, the default port 443 dropped, and `path`
+    /// given a leading `/` if it lacks one.
+    #[must_use]
+    pub fn webtransport(address: impl Into<String>, path: impl Into<String>) -> Self {
+        let path = path.into();
+        let path = if path.starts_with('/') { path } else { format!("/{path}") };
+        Self::new("webtransport", canonical_address(&address.into(), 443), Some(path))
+    }
+
+    /// Creates a libp2p endpoint from a multiaddr such as
+    /// `/ip4/203.0.113.7/tcp/4001/p2p/12D3KooW...`, which p2p backends can
+    /// dial directly.
+    ///
+    /// The address is not checked; enable the `multiaddr` feature and use
+    /// `parse_multiaddr` to validate it.
+    #[must_use]
+    pub fn multiaddr(address: impl Into<String>) -> Self {
+        Self::new(Self::MULTIADDR, address, None::<String>)
+    }
+
+    /// Creates a libp2p endpoint, checking that `address` is a well-formed
+    /// multiaddr.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::InvalidEndpoint` if `address` does not parse as a
+    /// multiaddr.
+    #[cfg(feature = "multiaddr")]
+    pub fn parse_multiaddr(address: impl Into<String>) -> Result<Self, DhtError> {
+        let address = address.into();
+        match address.parse::<multiaddr::Multiaddr>() {
+            Ok(_) => Ok(Self::multiaddr(address)),
+            Err(e) => Err(DhtError::invalid_endpoint(address, e.to_string())),
+        }
+    }
+
+    /// Sets the deployment region.
+    #[must_use]
+    pub fn with_region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// Sets the preference order among the agent's endpoints, as in a DNS
+    /// SRV record: selectors only pick from the endpoints with the lowest
+    /// priority they can use. Endpoints without one count as priority 0.
+    #[must_use]
+    pub const fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Sets the relative share of traffic this endpoint should receive
+    /// among the agent's endpoints of the same priority.
+    #[must_use]
+    pub const fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+
+    /// Adds an application protocol version served at this endpoint.
+    #[must_use]
+    pub fn with_protocol_version(mut self, version: impl Into<String>) -> Self {
+        self.protocol_versions.push(version.into());
+        self
+    }
+
+    /// Adds a metadata entry, replacing any previous value for `key`.
+    #[must_use]
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
     /// Returns the protocol.
     #[must_use]
     pub fn protocol(&self) -> &str {
@@ -88,9 +253,149 @@ impl Endpoint {
         self.path.as_deref()
     }
 
+    /// Returns the deployment region, if any.
+    #[must_use]
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    /// Returns the priority, if any.
+    #[must_use]
+    pub const fn priority(&self) -> Option<u32> {
+        self.priority
+    }
+
+    /// Returns the traffic weight, if any.
+    #[must_use]
+    pub const fn weight(&self) -> Option<u32> {
+        self.weight
+    }
+
+    /// Returns the application protocol versions served.
+    #[must_use]
+    pub fn protocol_versions(&self) -> &[String] {
+        &self.protocol_versions
+    }
+
+    /// Returns true if this endpoint serves protocol version `version`.
+    #[must_use]
+    pub fn supports_version(&self, version: &str) -> bool {
+        self.protocol_versions.iter().any(|v| v == version)
+    }
+
+    /// Returns the metadata entries.
+    #[must_use]
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    /// Returns the host in the address, without its port, or `None` for
+    /// multiaddr endpoints.
+    #[must_use]
+    pub fn host(&self) -> Option<&str> {
+        if self.is_multiaddr() {
+            return None;
+        }
+        Some(split_port(&self.address).0)
+    }
+
+    /// Returns the explicit port in the address, if any.
+    #[must_use]
+    pub fn port(&self) -> Option<u16> {
+        if self.is_multiaddr() {
+            return None;
+        }
+        split_port(&self.address).1.and_then(|port| port.parse().ok())
+    }
+
+    /// Returns the well-known port of the endpoint's protocol: 443 for
+    /// `https` and `wss`, 80 for `http` and `ws`.
+    pub(crate) fn default_port(&self) -> Option<u16> {
+        match self.protocol.as_str() {
+            "https" | "wss" => Some(443),
+            "http" | "ws" => Some(80),
+            _ => None,
+        }
+    }
+
+    /// Checks the endpoint against the rules of its protocol.
+    ///
+    /// Every address must be a bare `host[:port]` with a port in range,
+    /// and every path must start with `/`. `quic` endpoints must also name
+    /// a port. Multiaddr endpoints must start with `/`, and parse as a
+    /// multiaddr with the `multiaddr` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::InvalidEndpoint` describing the first broken
+    /// rule.
+    pub fn validate(&self) -> Result<(), DhtError> {
+        let invalid = |reason: &str| Err(DhtError::invalid_endpoint(self.to_uri(), reason));
+        if self.address.is_empty() {
+            return invalid("address is empty");
+        }
+        if self.address.chars().any(char::is_whitespace) {
+            return invalid("address contains whitespace");
+        }
+        if self.is_multiaddr() {
+            #[cfg(feature = "multiaddr")]
+            if let Err(e) = self.address.parse::<multiaddr::Multiaddr>() {
+                return invalid(&e.to_string());
+            }
+            if !self.address.starts_with('/') {
+                return invalid("multiaddr must start with '/'");
+            }
+            return Ok(());
+        }
+        if self.address.contains('/') {
+            return invalid("address must be host[:port] without a scheme or path");
+        }
+        match split_port(&self.address) {
+            (_, Some(port)) if !port.parse::<u16>().is_ok_and(|port| port > 0) => {
+                return invalid("port must be between 1 and 65535");
+            }
+            (_, None) if self.protocol == "quic" => {
+                return invalid("quic endpoints require an explicit port");
+            }
+            _ => {}
+        }
+        if self.path.as_deref().is_some_and(|path| !path.starts_with('/')) {
+            return invalid("path must start with '/'");
+        }
+        Ok(())
+    }
+
+    /// Validates a registration's endpoint list: it must be non-empty and
+    /// every endpoint must pass [`validate`](Self::validate).
+    pub(crate) fn validate_all(endpoints: &[Self]) -> Result<(), DhtError> {
+        if endpoints.is_empty() {
+            return Err(DhtError::NoEndpoints);
+        }
+        endpoints.iter().try_for_each(Self::validate)
+    }
+
+    /// Returns true if this is a libp2p multiaddr endpoint.
+    #[must_use]
+    pub fn is_multiaddr(&self) -> bool {
+        self.protocol == Self::MULTIADDR
+    }
+
+    /// Returns the parsed multiaddr, or `None` if this is not a multiaddr
+    /// endpoint or its address is malformed.
+    #[cfg(feature = "multiaddr")]
+    #[must_use]
+    pub fn to_multiaddr(&self) -> Option<multiaddr::Multiaddr> {
+        self.is_multiaddr().then(|| self.address.parse().ok()).flatten()
+    }
+
     /// Returns the full URI representation.
+    ///
+    /// Multiaddr endpoints are represented by the multiaddr itself.
     #[must_use]
     pub fn to_uri(&self) -> String {
+        if self.is_multiaddr() {
+            return self.address.clone();
+        }
         match &self.path {
             Some(p) => format!("{}://{}{}", self.protocol, self.address, p),
             None => format!("{}://{}", self.protocol, self.address),
@@ -104,6 +409,51 @@ impl fmt::Display for Endpoint {
     }
 }
 
+impl FromStr for Endpoint {
+    type Err = DhtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Splits an endpoint URI into protocol, address and path, without
+/// checking them. Multiaddrs, which start with `/`, are all address.
+pub(crate) fn split_uri(uri: &str) -> Option<(&str, &str, Option<&str>)> {
+    if uri.starts_with('/') {
+        return Some((Endpoint::MULTIADDR, uri, None));
+    }
+    let (protocol, rest) = uri.split_once("://")?;
+    Some(match rest.find('/') {
+        Some(i) => (protocol, &rest[..i], Some(&rest[i..])),
+        None => (protocol, rest, None),
+    })
+}
+
+/// Splits `address` into host and port, if it ends in a numeric port that
+/// is not part of an unbracketed IPv6 literal.
+fn split_port(address: &str) -> (&str, Option<&str>) {
+    match address.rsplit_once(':') {
+        Some((host, port))
+            if !port.is_empty()
+                && port.bytes().all(|b| b.is_ascii_digit())
+                && (!host.contains(':') || host.ends_with(']')) =>
+        {
+            (host, Some(port))
+        }
+        _ => (address, None),
+    }
+}
+
+/// Lowercases `address` and drops `default_port` from it.
+fn canonical_address(address: &str, default_port: u16) -> String {
+    let address = address.to_ascii_lowercase();
+    match split_port(&address) {
+        (host, Some(port)) if port.parse() == Ok(default_port) => host.to_string(),
+        _ => address,
+    }
+}
+
 #[cfg(feature = "serde")]
 impl serde::Serialize for Endpoint {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -111,10 +461,15 @@ impl serde::Serialize for Endpoint {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("Endpoint", 3)?;
+        let mut state = serializer.serialize_struct("Endpoint", 8)?;
         state.serialize_field("protocol", &self.protocol)?;
         state.serialize_field("address", &self.address)?;
         state.serialize_field("path", &self.path)?;
+        state.serialize_field("region", &self.region)?;
+        state.serialize_field("priority", &self.priority)?;
+        state.serialize_field("weight", &self.weight)?;
+        state.serialize_field("protocol_versions", &self.protocol_versions)?;
+        state.serialize_field("metadata", &self.metadata)?;
         state.end()
     }
 }
@@ -125,13 +480,22 @@ impl<'de> serde::Deserialize<'de> for Endpoint {
     where
         D: serde::Deserializer<'de>,
     {
-        use serde::de::Deserialize;
-
         #[derive(serde::Deserialize)]
         struct EndpointData {
             protocol: String,
             address: String,
             path: Option<String>,
+            // Absent from records written before endpoint metadata existed
+            #[serde(default)]
+            region: Option<String>,
+            #[serde(default)]
+            priority: Option<u32>,
+            #[serde(default)]
+            weight: Option<u32>,
+            #[serde(default)]
+            protocol_versions: Vec<String>,
+            #[serde(default)]
+            metadata: BTreeMap<String, String>,
         }
 
         let data = EndpointData::deserialize(deserializer)?;
@@ -139,6 +503,11 @@ impl<'de> serde::Deserialize<'de> for Endpoint {
             protocol: data.protocol,
             address: data.address,
             path: data.path,
+            region: data.region,
+            priority: data.priority,
+            weight: data.weight,
+            protocol_versions: data.protocol_versions,
+            metadata: data.metadata,
         })
     }
 }
@@ -188,6 +557,116 @@ mod tests {
         assert_eq!(endpoint.to_uri(), "mqtt://broker.example.com:1883/agents");
     }
 
+    #[test]
+    fn selection_hints_are_kept_off_the_uri() {
+        let endpoint = Endpoint::grpc("agent.example.com:50051")
+            .with_region("us-east-1")
+            .with_priority(2)
+            .with_weight(10)
+            .with_protocol_version("a2a/1.0")
+            .with_protocol_version("a2a/1.1")
+            .with_metadata("zone", "b");
+        assert_eq!(endpoint.region(), Some("us-east-1"));
+        assert_eq!(endpoint.priority(), Some(2));
+        assert_eq!(endpoint.weight(), Some(10));
+        assert!(endpoint.supports_version("a2a/1.1"));
+        assert!(!endpoint.supports_version("a2a/2.0"));
+        assert_eq!(endpoint.metadata().get("zone").map(String::as_str), Some("b"));
+        assert_eq!(endpoint.to_uri(), "grpc://agent.example.com:50051");
+        assert_ne!(endpoint, Endpoint::grpc("agent.example.com:50051"));
+    }
+
+    #[test]
+    fn parse_detects_the_scheme() {
+        let grpc = Endpoint::parse("grpc://agent.example.com:50051").unwrap();
+        assert_eq!(grpc, Endpoint::grpc("agent.example.com:50051"));
+        let https: Endpoint = "HTTPS://agent.example.com/v1/agent".parse().unwrap();
+        assert_eq!(https, Endpoint::https_with_path("agent.example.com", "/v1/agent"));
+        assert_eq!(
+            Endpoint::parse("wss://Agent.Example.com:443").unwrap(),
+            Endpoint::wss("agent.example.com")
+        );
+        assert_eq!(
+            Endpoint::parse("webtransport://agent.example.com:443/session").unwrap(),
+            Endpoint::webtransport("agent.example.com", "/session")
+        );
+        assert!(Endpoint::parse("/ip4/127.0.0.1/tcp/4001").unwrap().is_multiaddr());
+
+        for uri in [Endpoint::quic("agent.example.com:4433"), https, grpc].map(|e| e.to_uri()) {
+            assert_eq!(Endpoint::parse(&uri).unwrap().to_uri(), uri);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_malformed_uris() {
+        for uri in [
+            "agent.example.com:443",
+            "https://",
+            "https://agent.example.com:99999",
+            "quic://agent.example.com",
+            "",
+        ] {
+            let err = Endpoint::parse(uri).unwrap_err();
+            assert!(matches!(err, DhtError::InvalidEndpoint { .. }), "{uri}");
+        }
+    }
+
+    #[test]
+    fn multiaddr_uri_is_the_address() {
+        let endpoint = Endpoint::multiaddr("/ip4/127.0.0.1/tcp/4001");
+        assert!(endpoint.is_multiaddr());
+        assert!(!Endpoint::https("agent.example.com").is_multiaddr());
+        assert_eq!(endpoint.to_uri(), "/ip4/127.0.0.1/tcp/4001");
+    }
+
+    #[cfg(feature = "multiaddr")]
+    #[test]
+    fn parse_multiaddr_validates() {
+        let endpoint = Endpoint::parse_multiaddr("/ip4/127.0.0.1/tcp/4001").unwrap();
+        assert_eq!(endpoint.to_multiaddr().unwrap().iter().count(), 2);
+
+        let err = Endpoint::parse_multiaddr("/ip4/not-an-ip/tcp/4001").unwrap_err();
+        assert!(matches!(err, DhtError::InvalidEndpoint { .. }));
+        assert!(Endpoint::multiaddr("tcp:4001").to_multiaddr().is_none());
+    }
+
+    #[test]
+    fn transport_constructors_canonicalize() {
+        assert_eq!(Endpoint::wss("Agent.Example.com:443").to_uri(), "wss://agent.example.com");
+        assert_eq!(
+            Endpoint::wss("agent.example.com:8443").to_uri(),
+            "wss://agent.example.com:8443"
+        );
+        assert_eq!(Endpoint::quic("AGENT.example.com:4433").address(), "agent.example.com:4433");
+        assert_eq!(
+            Endpoint::webtransport("agent.example.com", "/session").to_uri(),
+            "webtransport://agent.example.com/session"
+        );
+        assert_eq!(
+            Endpoint::webtransport("[::1]:443", "wt").to_uri(),
+            "webtransport://[::1]/wt"
+        );
+    }
+
+    #[test]
+    fn validate_applies_scheme_rules() {
+        assert!(Endpoint::quic("agent.example.com:4433").validate().is_ok());
+        assert!(Endpoint::quic("agent.example.com").validate().is_err());
+        assert!(Endpoint::wss("agent.example.com").validate().is_ok());
+        assert!(Endpoint::https("https://agent.example.com").validate().is_err());
+        assert!(Endpoint::https("agent.example.com:0").validate().is_err());
+        assert!(Endpoint::https("agent.example.com:70000").validate().is_err());
+        assert!(Endpoint::new("https", "agent.example.com", Some("v1")).validate().is_err());
+        assert!(Endpoint::multiaddr("/ip4/127.0.0.1/tcp/4001").validate().is_ok());
+        assert!(Endpoint::multiaddr("ip4/127.0.0.1").validate().is_err());
+        assert!(Endpoint::grpc("").validate().is_err());
+        assert_eq!(Endpoint::grpc("[::1]:50051").port(), Some(50051));
+        assert_eq!(Endpoint::grpc("::1").port(), None);
+        assert_eq!(Endpoint::grpc("[::1]:50051").host(), Some("[::1]"));
+        assert_eq!(Endpoint::https("agent.example.com").host(), Some("agent.example.com"));
+        assert_eq!(Endpoint::multiaddr("/ip4/127.0.0.1/tcp/4001").host(), None);
+    }
+
     #[test]
     fn display_matches_to_uri() {
         let endpoint = Endpoint::https("agent.example.com:443");
@@ -1,6 +1,7 @@
 //! Custom error types for DHT operations.
 
 use std::fmt;
+use std::time::Duration;
 
 /// Errors that can occur during DHT operations.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -20,6 +21,25 @@ pub enum DhtError {
         /// The agent URI whose registration expired
         agent_uri: String,
     },
+    /// An update carried an older sequence number than the stored
+    /// registration.
+    StaleWrite {
+        /// The agent URI whose update was rejected
+        agent_uri: String,
+        /// Sequence number of the stored registration
+        current: u64,
+        /// Sequence number of the rejected update
+        attempted: u64,
+    },
+    /// An update carried the stored registration's sequence number but
+    /// different contents, e.g. from two replicas of the agent writing at
+    /// once.
+    SequenceConflict {
+        /// The agent URI whose update was rejected
+        agent_uri: String,
+        /// The sequence number both versions claim
+        seq: u64,
+    },
     /// The attestation is invalid or missing.
     InvalidAttestation {
         /// The agent URI with invalid attestation
@@ -27,6 +47,14 @@ pub enum DhtError {
         /// Reason the attestation is invalid
         reason: String,
     },
+    /// The registration carries neither a valid attestation nor a proof of
+    /// work meeting the registry's difficulty.
+    InsufficientProofOfWork {
+        /// The agent URI that was rejected
+        agent_uri: String,
+        /// Leading zero bits the proof of work must have
+        difficulty: u32,
+    },
     /// The capability path does not match the attestation.
     CapabilityMismatch {
         /// The claimed capability path
@@ -41,8 +69,51 @@ pub enum DhtError {
         /// Maximum allowed registrations
         max: usize,
     },
+    /// The registry, or one of its trust roots, is full.
+    CapacityExceeded {
+        /// The full trust root, or None if the whole registry is full
+        trust_root: Option<String>,
+        /// Maximum allowed registrations
+        max: usize,
+    },
     /// The endpoints list is empty.
     NoEndpoints,
+    /// The registration or renewal asked for a TTL outside the registry's
+    /// bounds.
+    TtlOutOfBounds {
+        /// The agent URI that was rejected
+        agent_uri: String,
+        /// The TTL asked for
+        ttl: Duration,
+        /// Shortest TTL the registry grants
+        min: Duration,
+        /// Longest TTL the registry grants
+        max: Duration,
+    },
+    /// An endpoint address is malformed.
+    InvalidEndpoint {
+        /// The rejected address
+        address: String,
+        /// Reason the address is invalid
+        reason: String,
+    },
+    /// A lookup matched more registrations than a single query may return.
+    ResultLimitExceeded {
+        /// Maximum results per query
+        max: usize,
+    },
+    /// A snapshot was written in a format this release cannot read.
+    UnsupportedSnapshotVersion {
+        /// The snapshot's format version
+        version: u32,
+        /// The format version this release reads
+        supported: u32,
+    },
+    /// The storage backend failed or could not be reached.
+    Backend {
+        /// Error message
+        message: String,
+    },
     /// Internal error (should not happen in production).
     Internal {
         /// Error message
@@ -51,6 +122,7 @@ pub enum DhtError {
 }
 
 impl fmt::Display for DhtError {
+    #[allow(clippy::too_many_lines)]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::NotFound { agent_uri } => {
@@ -71,9 +143,34 @@ impl fmt::Display for DhtError {
                     "registration for agent '{agent_uri}' has expired; re-register to restore"
                 )
             }
+            Self::StaleWrite {
+                agent_uri,
+                current,
+                attempted,
+            } => {
+                write!(
+                    f,
+                    "stale update for agent '{agent_uri}': sequence {attempted} is older than stored sequence {current}"
+                )
+            }
+            Self::SequenceConflict { agent_uri, seq } => {
+                write!(
+                    f,
+                    "conflicting update for agent '{agent_uri}': sequence {seq} is already stored with different contents; retry with a higher sequence"
+                )
+            }
             Self::InvalidAttestation { agent_uri, reason } => {
                 write!(f, "invalid attestation for agent '{agent_uri}': {reason}")
             }
+            Self::InsufficientProofOfWork {
+                agent_uri,
+                difficulty,
+            } => {
+                write!(
+                    f,
+                    "registration for agent '{agent_uri}' needs a valid attestation or a proof of work of difficulty {difficulty}"
+                )
+            }
             Self::CapabilityMismatch { claimed, attested } => {
                 write!(
                     f,
@@ -86,9 +183,53 @@ impl fmt::Display for DhtError {
                     "DHT key '{key}' has reached maximum capacity of {max} registrations"
                 )
             }
+            Self::CapacityExceeded {
+                trust_root: Some(trust_root),
+                max,
+            } => {
+                write!(
+                    f,
+                    "trust root '{trust_root}' has reached maximum capacity of {max} registrations"
+                )
+            }
+            Self::CapacityExceeded {
+                trust_root: None,
+                max,
+            } => {
+                write!(f, "registry has reached maximum capacity of {max} registrations")
+            }
             Self::NoEndpoints => {
                 write!(f, "registration must have at least one endpoint")
             }
+            Self::TtlOutOfBounds {
+                agent_uri,
+                ttl,
+                min,
+                max,
+            } => {
+                write!(
+                    f,
+                    "TTL of {ttl:?} for agent '{agent_uri}' is outside the registry's bounds of {min:?} to {max:?}"
+                )
+            }
+            Self::InvalidEndpoint { address, reason } => {
+                write!(f, "invalid endpoint address '{address}': {reason}")
+            }
+            Self::ResultLimitExceeded { max } => {
+                write!(
+                    f,
+                    "lookup matched more than {max} registrations; use lookup_prefix_paged to page through them"
+                )
+            }
+            Self::Backend { message } => {
+                write!(f, "DHT backend error: {message}")
+            }
+            Self::UnsupportedSnapshotVersion { version, supported } => {
+                write!(
+                    f,
+                    "snapshot format version {version} is not supported (expected {supported})"
+                )
+            }
             Self::Internal { message } => {
                 write!(f, "internal DHT error: {message}")
             }
@@ -123,6 +264,25 @@ impl DhtError {
         }
     }
 
+    /// Creates a `StaleWrite` error.
+    #[must_use]
+    pub fn stale_write(agent_uri: impl Into<String>, current: u64, attempted: u64) -> Self {
+        Self::StaleWrite {
+            agent_uri: agent_uri.into(),
+            current,
+            attempted,
+        }
+    }
+
+    /// Creates a `SequenceConflict` error.
+    #[must_use]
+    pub fn sequence_conflict(agent_uri: impl Into<String>, seq: u64) -> Self {
+        Self::SequenceConflict {
+            agent_uri: agent_uri.into(),
+            seq,
+        }
+    }
+
     /// Creates an `InvalidAttestation` error.
     #[must_use]
     pub fn invalid_attestation(agent_uri: impl Into<String>, reason: impl Into<String>) -> Self {
@@ -132,6 +292,31 @@ impl DhtError {
         }
     }
 
+    /// Creates a `TtlOutOfBounds` error.
+    #[must_use]
+    pub fn ttl_out_of_bounds(
+        agent_uri: impl Into<String>,
+        ttl: Duration,
+        min: Duration,
+        max: Duration,
+    ) -> Self {
+        Self::TtlOutOfBounds {
+            agent_uri: agent_uri.into(),
+            ttl,
+            min,
+            max,
+        }
+    }
+
+    /// Creates an `InsufficientProofOfWork` error.
+    #[must_use]
+    pub fn insufficient_proof_of_work(agent_uri: impl Into<String>, difficulty: u32) -> Self {
+        Self::InsufficientProofOfWork {
+            agent_uri: agent_uri.into(),
+            difficulty,
+        }
+    }
+
     /// Creates a `CapabilityMismatch` error.
     #[must_use]
     pub fn capability_mismatch(claimed: impl Into<String>, attested: impl Into<String>) -> Self {
@@ -150,6 +335,45 @@ impl DhtError {
         }
     }
 
+    /// Creates a `CapacityExceeded` error for `trust_root`, or for the whole
+    /// registry if None.
+    #[must_use]
+    pub fn capacity_exceeded(trust_root: Option<&str>, max: usize) -> Self {
+        Self::CapacityExceeded {
+            trust_root: trust_root.map(str::to_string),
+            max,
+        }
+    }
+
+    /// Creates an `InvalidEndpoint` error.
+    #[must_use]
+    pub fn invalid_endpoint(address: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::InvalidEndpoint {
+            address: address.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Creates a `ResultLimitExceeded` error.
+    #[must_use]
+    pub const fn result_limit_exceeded(max: usize) -> Self {
+        Self::ResultLimitExceeded { max }
+    }
+
+    /// Creates an `UnsupportedSnapshotVersion` error.
+    #[must_use]
+    pub const fn unsupported_snapshot_version(version: u32, supported: u32) -> Self {
+        Self::UnsupportedSnapshotVersion { version, supported }
+    }
+
+    /// Creates a `Backend` error.
+    #[must_use]
+    pub fn backend(message: impl Into<String>) -> Self {
+        Self::Backend {
+            message: message.into(),
+        }
+    }
+
     /// Creates an `Internal` error.
     #[must_use]
     pub fn internal(message: impl Into<String>) -> Self {
@@ -195,16 +419,39 @@ mod tests {
         assert!(err.is_expired());
     }
 
+    #[test]
+    fn stale_write_and_conflict_error_display() {
+        let err = DhtError::stale_write("agent://example.com/test/agent_123", 5, 3);
+        assert!(err.to_string().contains("sequence 3 is older than stored sequence 5"));
+        let err = DhtError::sequence_conflict("agent://example.com/test/agent_123", 5);
+        assert!(err.to_string().contains("higher sequence"));
+    }
+
     #[test]
     fn no_endpoints_error_display() {
         let err = DhtError::NoEndpoints;
         assert!(err.to_string().contains("at least one endpoint"));
     }
 
+    #[test]
+    fn result_limit_exceeded_error_display() {
+        let err = DhtError::result_limit_exceeded(500);
+        assert!(err.to_string().contains("more than 500"));
+        assert!(err.to_string().contains("lookup_prefix_paged"));
+    }
+
     #[test]
     fn key_capacity_exceeded_error_display() {
         let err = DhtError::key_capacity_exceeded("abc123...", 20);
         assert!(err.to_string().contains("maximum capacity"));
         assert!(err.to_string().contains("20"));
     }
+
+    #[test]
+    fn capacity_exceeded_error_display() {
+        let err = DhtError::capacity_exceeded(Some("acme.com"), 5);
+        assert!(err.to_string().contains("trust root 'acme.com'"));
+        let err = DhtError::capacity_exceeded(None, 5);
+        assert_eq!(err.to_string(), "registry has reached maximum capacity of 5 registrations");
+    }
 }
@@ -0,0 +1,196 @@
+//! Expiry jitter and refresh-ahead policies for registrations.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::{Duration, SystemTime};
+
+use agent_uri::AgentUri;
+
+use crate::Registration;
+use crate::heartbeat::clamp_fraction;
+use crate::registration::system_time_to_millis;
+
+/// How registrations' lifetimes are spread out and when they are renewed.
+///
+/// A fleet registered together with one TTL expires together, and lookups
+/// under it fall off a cliff if renewals lag. Jitter shortens each TTL by
+/// a share of up to [`jitter`](Self::with_jitter), so expiries spread over
+/// a window instead. The share is derived from the agent URI, the time and
+/// the [seed](Self::with_seed): repeatable, but different across agents.
+/// Jitter only ever shortens a TTL, so a registration never outlives what
+/// was asked for.
+///
+/// The refresh-ahead window, a share of the (jittered) TTL, sets when a
+/// registration becomes due for renewal; see
+/// [`Registration::should_refresh`].
+///
+/// Set on [`SimulationConfig`](crate::SimulationConfig) to jitter the
+/// expiries a [`SimulatedDht`](crate::SimulatedDht) stores, or on a
+/// [`HeartbeatScheduler`](crate::HeartbeatScheduler) to jitter the TTLs it
+/// renews for.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{Endpoint, ExpiryPolicy, Registration};
+///
+/// let policy = ExpiryPolicy::new().with_jitter(0.2).with_refresh_ahead(0.25);
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let registration = policy.apply(
+///     Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+///         .with_ttl(Duration::from_secs(100)),
+/// );
+///
+/// let lifetime = registration.expires_at().duration_since(registration.registered_at()).unwrap();
+/// assert!(lifetime > Duration::from_secs(80) && lifetime <= Duration::from_secs(100));
+/// assert_eq!(registration.refresh_ahead(), lifetime.mul_f64(0.25));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExpiryPolicy {
+    jitter: f64,
+    refresh_ahead: Option<f64>,
+    seed: u64,
+}
+
+impl ExpiryPolicy {
+    /// Creates a policy that leaves TTLs and refresh windows unchanged.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the largest share of a TTL it may be shortened by, clamped to
+    /// `0.0..=1.0`. Zero disables jitter.
+    #[must_use]
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = clamp_fraction(jitter);
+        self
+    }
+
+    /// Sets the refresh-ahead window as a share of the TTL, clamped to
+    /// `0.0..=1.0`.
+    #[must_use]
+    pub fn with_refresh_ahead(mut self, fraction: f64) -> Self {
+        self.refresh_ahead = Some(clamp_fraction(fraction));
+        self
+    }
+
+    /// Sets the seed jitter is derived with.
+    #[must_use]
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the largest share of a TTL it may be shortened by.
+    #[must_use]
+    pub const fn jitter(&self) -> f64 {
+        self.jitter
+    }
+
+    /// Returns the refresh-ahead window as a share of the TTL, if set.
+    #[must_use]
+    pub const fn refresh_ahead(&self) -> Option<f64> {
+        self.refresh_ahead
+    }
+
+    /// Returns `ttl` for `agent_uri`, registered or renewed at `at`, with
+    /// jitter applied.
+    #[must_use]
+    pub fn ttl(&self, agent_uri: &AgentUri, ttl: Duration, at: SystemTime) -> Duration {
+        if self.jitter <= 0.0 {
+            return ttl;
+        }
+        let mut hasher = DefaultHasher::new();
+        self.seed.hash(&mut hasher);
+        agent_uri.as_str().hash(&mut hasher);
+        system_time_to_millis(at).hash(&mut hasher);
+        // Uniform in [0, 1)
+        #[allow(clippy::cast_precision_loss)]
+        let unit = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+        ttl.mul_f64(1.0 - self.jitter * unit)
+    }
+
+    /// Returns the refresh-ahead window for a registration living `ttl`, if
+    /// the policy sets one.
+    #[must_use]
+    pub fn refresh_window(&self, ttl: Duration) -> Option<Duration> {
+        self.refresh_ahead.map(|fraction| ttl.mul_f64(fraction))
+    }
+
+    /// Returns `registration` with its lifetime jittered and its
+    /// refresh-ahead window set by this policy, unless the registration
+    /// sets its own.
+    #[must_use]
+    pub fn apply(&self, registration: Registration) -> Registration {
+        let registered_at = registration.registered_at();
+        let ttl = self.ttl(registration.agent_uri(), registration.ttl(), registered_at);
+        let registration = registration.with_ttl(ttl);
+        if registration.refresh_policy().is_some() {
+            return registration;
+        }
+        match self.refresh_window(ttl) {
+            Some(window) => registration.with_refresh_ahead(window),
+            None => registration,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dht, Endpoint, SimulatedDht, SimulationConfig};
+    use agent_uri::TrustRoot;
+
+    const TTL: Duration = Duration::from_secs(100);
+
+    fn registration(i: usize) -> Registration {
+        let uri = AgentUri::parse(&format!(
+            "agent://acme.com/assistant/skill{i}/llm_01h455vb4pex5vsknk084sn02q"
+        ))
+        .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.acme.com")]).with_ttl(TTL)
+    }
+
+    #[test]
+    fn jitter_spreads_expiries_without_extending_them() {
+        let policy = ExpiryPolicy::new().with_jitter(0.3).with_seed(1);
+        let at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let ttls: Vec<Duration> = (0..50)
+            .map(|i| policy.ttl(registration(i).agent_uri(), TTL, at))
+            .collect();
+
+        assert!(ttls.iter().all(|ttl| *ttl > Duration::from_secs(70) && *ttl <= TTL));
+        let shortest = ttls.iter().min().unwrap();
+        let longest = ttls.iter().max().unwrap();
+        assert!(longest.saturating_sub(*shortest) > Duration::from_secs(15), "spread out");
+
+        // Repeatable for the same agent, seed and time
+        assert_eq!(policy.ttl(registration(0).agent_uri(), TTL, at), ttls[0]);
+        assert_eq!(ExpiryPolicy::new().ttl(registration(0).agent_uri(), TTL, at), TTL);
+    }
+
+    #[test]
+    fn simulated_dht_stores_jittered_expiries() {
+        let policy = ExpiryPolicy::new().with_jitter(0.5);
+        let dht = SimulatedDht::new(SimulationConfig::new().with_expiry_policy(policy));
+        for i in 0..20 {
+            dht.register(registration(i)).unwrap();
+        }
+
+        let trust_root = TrustRoot::parse("acme.com").unwrap();
+        let stored = dht.lookup_trust_root(&trust_root).unwrap();
+        let mut expiries: Vec<SystemTime> = stored.iter().map(Registration::expires_at).collect();
+        expiries.sort();
+        expiries.dedup();
+        assert!(expiries.len() > 10);
+        assert!(stored.iter().all(|r| r.expires_at() <= r.registered_at() + TTL));
+
+        // Renewals are jittered too
+        let uri = stored[0].agent_uri().clone();
+        dht.renew(&uri, TTL).unwrap();
+        let renewed = dht.lookup_exact(uri.trust_root(), uri.capability_path()).unwrap();
+        assert!(renewed[0].expires_at() <= renewed[0].registered_at() + TTL);
+    }
+}
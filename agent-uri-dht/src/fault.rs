@@ -0,0 +1,359 @@
+//! Fault injection for the simulated DHT.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use agent_uri::TrustRoot;
+
+use crate::{DhtError, DhtOperation};
+
+/// A network partition cutting clients off from the nodes that hold some
+/// trust roots' records for a while.
+///
+/// While it lasts, operations on agents under those trust roots fail, and
+/// lookups across every trust root leave their registrations out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    /// Trust roots whose records are unreachable
+    pub trust_roots: Vec<TrustRoot>,
+    /// Time after the DHT is created the partition begins
+    pub start: Duration,
+    /// Time after the DHT is created the partition heals
+    pub end: Duration,
+}
+
+impl Partition {
+    /// Creates a partition isolating `trust_roots` from `start` until `end`
+    /// after the DHT is created.
+    #[must_use]
+    pub fn new(
+        trust_roots: impl IntoIterator<Item = TrustRoot>,
+        start: Duration,
+        end: Duration,
+    ) -> Self {
+        Self {
+            trust_roots: trust_roots.into_iter().collect(),
+            start,
+            end,
+        }
+    }
+
+    /// Returns true if `trust_root` is unreachable at `elapsed` after the
+    /// DHT is created.
+    #[must_use]
+    pub fn isolates(&self, trust_root: &TrustRoot, elapsed: Duration) -> bool {
+        (self.start..self.end).contains(&elapsed) && self.trust_roots.contains(trust_root)
+    }
+}
+
+/// Failures injected into a [`SimulatedDht`](crate::SimulatedDht)'s
+/// operations, for testing how clients retry and how evaluations hold up.
+///
+/// Three failure modes are modeled, each failing with
+/// `DhtError::Backend`:
+///
+/// - **Operation failure**: the operation is refused before it takes
+///   effect, with a probability set overall or per [`DhtOperation`].
+/// - **Message drop**: the request is lost before it takes effect, or the
+///   reply is lost after it did, each with the drop rate. A client that
+///   retries a write whose reply was lost sees the effect of its first
+///   attempt, e.g. `AlreadyRegistered`.
+/// - **Partition**: a [`Partition`] makes some trust roots unreachable for
+///   a window of time.
+///
+/// Draws are seeded, so a run is repeatable when operations are issued in
+/// the same order.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use agent_uri::TrustRoot;
+/// use agent_uri_dht::{DhtOperation, FaultModel, Partition, SimulationConfig};
+///
+/// let faults = FaultModel::none()
+///     .with_failure_rate(0.01)
+///     .with_operation_failure_rate(DhtOperation::Register, 0.05)
+///     .with_drop_rate(0.02)
+///     .with_partition(Partition::new(
+///         [TrustRoot::parse("acme.com").unwrap()],
+///         Duration::from_secs(10),
+///         Duration::from_secs(20),
+///     ))
+///     .with_seed(42);
+///
+/// let config = SimulationConfig::new().with_faults(faults);
+/// ```
+#[derive(Debug)]
+pub struct FaultModel {
+    failure_rate: f64,
+    operations: BTreeMap<DhtOperation, f64>,
+    drop_rate: f64,
+    partitions: Vec<Partition>,
+    seed: u64,
+    draws: AtomicU64,
+}
+
+impl Default for FaultModel {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl Clone for FaultModel {
+    fn clone(&self) -> Self {
+        Self {
+            failure_rate: self.failure_rate,
+            operations: self.operations.clone(),
+            drop_rate: self.drop_rate,
+            partitions: self.partitions.clone(),
+            seed: self.seed,
+            draws: AtomicU64::new(self.draws.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl FaultModel {
+    /// Creates a model that injects no faults.
+    #[must_use]
+    pub const fn none() -> Self {
+        Self {
+            failure_rate: 0.0,
+            operations: BTreeMap::new(),
+            drop_rate: 0.0,
+            partitions: Vec::new(),
+            seed: 0,
+            draws: AtomicU64::new(0),
+        }
+    }
+
+    /// Fails every operation with probability `rate`, clamped to
+    /// `0.0..=1.0`.
+    #[must_use]
+    pub const fn with_failure_rate(mut self, rate: f64) -> Self {
+        self.failure_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Fails `operation` with probability `rate` instead of the overall
+    /// rate.
+    #[must_use]
+    pub fn with_operation_failure_rate(mut self, operation: DhtOperation, rate: f64) -> Self {
+        self.operations.insert(operation, rate.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Drops each request and each reply with probability `rate`, clamped
+    /// to `0.0..=1.0`.
+    #[must_use]
+    pub const fn with_drop_rate(mut self, rate: f64) -> Self {
+        self.drop_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Adds a scheduled partition.
+    #[must_use]
+    pub fn with_partition(mut self, partition: Partition) -> Self {
+        self.partitions.push(partition);
+        self
+    }
+
+    /// Sets the seed faults are drawn with.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.draws = AtomicU64::new(0);
+        self
+    }
+
+    /// Returns the probability `operation` fails.
+    #[must_use]
+    pub fn failure_rate(&self, operation: DhtOperation) -> f64 {
+        self.operations
+            .get(&operation)
+            .copied()
+            .unwrap_or(self.failure_rate)
+    }
+
+    /// Returns the probability a request or reply is dropped.
+    #[must_use]
+    pub const fn drop_rate(&self) -> f64 {
+        self.drop_rate
+    }
+
+    /// Returns the scheduled partitions.
+    #[must_use]
+    pub fn partitions(&self) -> &[Partition] {
+        &self.partitions
+    }
+
+    /// Returns true if a partition makes `trust_root` unreachable at
+    /// `elapsed` after the DHT is created.
+    #[must_use]
+    pub fn isolates(&self, trust_root: &TrustRoot, elapsed: Duration) -> bool {
+        self.partitions
+            .iter()
+            .any(|partition| partition.isolates(trust_root, elapsed))
+    }
+
+    /// Returns true if the model never injects a fault.
+    #[must_use]
+    pub fn is_none(&self) -> bool {
+        self.failure_rate <= 0.0
+            && self.operations.values().all(|rate| *rate <= 0.0)
+            && self.drop_rate <= 0.0
+            && self.partitions.is_empty()
+    }
+
+    /// Decides the fate of a request for `operation` on `trust_root`'s
+    /// records, if it concerns one, before it takes effect.
+    pub(crate) fn request(
+        &self,
+        operation: DhtOperation,
+        trust_root: Option<&TrustRoot>,
+        elapsed: Duration,
+    ) -> Result<(), DhtError> {
+        if let Some(trust_root) = trust_root {
+            self.reach(trust_root, elapsed)?;
+        }
+        if self.happens(self.failure_rate(operation)) {
+            return Err(DhtError::backend(format!("simulated {operation:?} failure")));
+        }
+        if self.happens(self.drop_rate) {
+            return Err(DhtError::backend("simulated timeout: request dropped"));
+        }
+        Ok(())
+    }
+
+    /// Fails if a partition makes `trust_root` unreachable at `elapsed`.
+    pub(crate) fn reach(&self, trust_root: &TrustRoot, elapsed: Duration) -> Result<(), DhtError> {
+        if self.isolates(trust_root, elapsed) {
+            return Err(DhtError::backend(format!(
+                "simulated partition: {trust_root} is unreachable"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Decides whether the reply to an operation that took effect is lost.
+    pub(crate) fn reply(&self) -> Result<(), DhtError> {
+        if self.happens(self.drop_rate) {
+            return Err(DhtError::backend("simulated timeout: reply dropped"));
+        }
+        Ok(())
+    }
+
+    /// Returns true with probability `rate`.
+    fn happens(&self, rate: f64) -> bool {
+        rate > 0.0 && self.unit() < rate
+    }
+
+    /// Returns a uniform value in [0, 1) (`SplitMix64`).
+    fn unit(&self) -> f64 {
+        let n = self.draws.fetch_add(1, Ordering::Relaxed);
+        let mut z = self
+            .seed
+            .wrapping_add(n.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        #[allow(clippy::cast_precision_loss)]
+        let unit = (z >> 11) as f64 / (1u64 << 53) as f64;
+        unit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dht, Endpoint, Registration, SimulatedDht, SimulationConfig};
+    use agent_uri::{AgentUri, CapabilityPath};
+
+    fn registration(trust_root: &str) -> Registration {
+        let uri = AgentUri::parse(&format!(
+            "agent://{trust_root}/assistant/chat/llm_01h455vb4pex5vsknk084sn02q"
+        ))
+        .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+    }
+
+    fn dht(faults: FaultModel) -> SimulatedDht {
+        SimulatedDht::new(SimulationConfig::new().with_faults(faults))
+    }
+
+    #[test]
+    fn operation_failures_are_refused_before_taking_effect() {
+        let faults = FaultModel::none().with_operation_failure_rate(DhtOperation::Register, 1.0);
+        assert!(!faults.is_none());
+        assert!(FaultModel::none().is_none());
+        let dht = dht(faults);
+
+        let error = dht.register(registration("acme.com")).unwrap_err();
+        assert!(error.to_string().contains("simulated Register failure"));
+        assert_eq!(dht.stats().total_registrations, 0);
+
+        // Other operations are unaffected
+        let path = CapabilityPath::parse("assistant").unwrap();
+        assert!(dht.lookup_global(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn retries_see_the_effect_of_writes_whose_reply_was_lost() {
+        let dht = dht(FaultModel::none().with_drop_rate(0.3).with_seed(7));
+
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match dht.register(registration("acme.com")) {
+                Ok(()) | Err(DhtError::AlreadyRegistered { .. }) => break,
+                Err(error) => assert!(error.to_string().contains("simulated timeout")),
+            }
+            assert!(attempts < 100);
+        }
+        assert_eq!(dht.stats().total_registrations, 1);
+
+        // Each lookup survives both its request and its reply
+        let trust_root = TrustRoot::parse("acme.com").unwrap();
+        let path = CapabilityPath::parse("assistant/chat").unwrap();
+        let failures = (0..1000)
+            .filter(|_| dht.lookup_exact(&trust_root, &path).is_err())
+            .count();
+        assert!((400..600).contains(&failures), "{failures} of 1000 lookups failed");
+    }
+
+    #[test]
+    fn partitions_isolate_trust_roots_while_they_last() {
+        let acme = TrustRoot::parse("acme.com").unwrap();
+        let partition = Partition::new(
+            [acme.clone()],
+            Duration::from_millis(50),
+            Duration::from_hours(1),
+        );
+        assert!(!partition.isolates(&acme, Duration::from_millis(10)));
+        assert!(partition.isolates(&acme, Duration::from_mins(1)));
+        let dht = dht(FaultModel::none().with_partition(partition));
+
+        dht.register(registration("acme.com")).unwrap();
+        dht.register(registration("example.com")).unwrap();
+        std::thread::sleep(Duration::from_millis(60));
+
+        let error = dht.register(registration("acme.com")).unwrap_err();
+        assert!(error.to_string().contains("acme.com is unreachable"));
+        assert!(dht.lookup_trust_root(&acme).is_err());
+
+        // Lookups across trust roots return what is reachable
+        let path = CapabilityPath::parse("assistant").unwrap();
+        let found = dht.lookup_global(&path).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].agent_uri().trust_root().as_str(), "example.com");
+
+        let path = CapabilityPath::parse("assistant/chat").unwrap();
+        let batch = dht.lookup_many(&[
+            (acme, path.clone()),
+            (TrustRoot::parse("example.com").unwrap(), path),
+        ]);
+        assert!(batch[0].is_err());
+        assert_eq!(batch[1].as_ref().unwrap().len(), 1);
+    }
+}
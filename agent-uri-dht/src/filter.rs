@@ -0,0 +1,168 @@
+//! Server-side filtering of lookup results.
+
+use std::time::{Duration, SystemTime};
+
+use crate::Registration;
+
+/// Conditions registrations must meet to be returned by the `_filtered`
+/// lookups of [`Dht`](crate::Dht).
+///
+/// The default filter matches every registration; each setting narrows it
+/// further. Matching registrations are returned whole, with all their
+/// endpoints.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+/// use agent_uri_dht::{Dht, Endpoint, LookupFilter, Registration, SimulatedDht};
+///
+/// let dht = SimulatedDht::with_defaults();
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// dht.register(
+///     Registration::new(uri, vec![Endpoint::grpc("agent.acme.com:50051")])
+///         .with_metadata("tier", "stable"),
+/// )
+/// .unwrap();
+///
+/// let filter = LookupFilter::new()
+///     .with_protocol("grpc")
+///     .with_required_metadata("tier")
+///     .with_max_staleness(Duration::from_secs(300));
+/// let found = dht
+///     .lookup_prefix_filtered(
+///         &TrustRoot::parse("acme.com").unwrap(),
+///         &CapabilityPath::parse("assistant").unwrap(),
+///         &filter,
+///     )
+///     .unwrap();
+/// assert_eq!(found.len(), 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LookupFilter {
+    protocol: Option<String>,
+    max_staleness: Option<Duration>,
+    required_metadata: Vec<String>,
+    exclude_expired: bool,
+}
+
+impl LookupFilter {
+    /// Creates a filter that matches every registration.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires at least one endpoint using `protocol`, e.g. `"grpc"`.
+    #[must_use]
+    pub fn with_protocol(mut self, protocol: impl Into<String>) -> Self {
+        self.protocol = Some(protocol.into());
+        self
+    }
+
+    /// Requires the registration to have been registered or renewed within
+    /// `max_staleness`.
+    #[must_use]
+    pub const fn with_max_staleness(mut self, max_staleness: Duration) -> Self {
+        self.max_staleness = Some(max_staleness);
+        self
+    }
+
+    /// Requires the registration to carry the metadata key `key`.
+    #[must_use]
+    pub fn with_required_metadata(mut self, key: impl Into<String>) -> Self {
+        self.required_metadata.push(key.into());
+        self
+    }
+
+    /// Excludes expired registrations, even from a DHT configured to keep
+    /// returning them.
+    #[must_use]
+    pub const fn with_exclude_expired(mut self, exclude: bool) -> Self {
+        self.exclude_expired = exclude;
+        self
+    }
+
+    /// Returns true if `registration` meets every condition now.
+    #[must_use]
+    pub fn matches(&self, registration: &Registration) -> bool {
+        self.matches_at(registration, SystemTime::now())
+    }
+
+    /// Returns true if `registration` meets every condition at `now`.
+    #[must_use]
+    pub fn matches_at(&self, registration: &Registration, now: SystemTime) -> bool {
+        if self.exclude_expired && registration.expires_at() <= now {
+            return false;
+        }
+        if let Some(max) = self.max_staleness
+            && now
+                .duration_since(registration.registered_at())
+                .is_ok_and(|age| age > max)
+        {
+            return false;
+        }
+        if let Some(protocol) = &self.protocol
+            && !registration
+                .endpoints()
+                .iter()
+                .any(|endpoint| endpoint.protocol() == protocol)
+        {
+            return false;
+        }
+        self.required_metadata
+            .iter()
+            .all(|key| registration.metadata().contains_key(key))
+    }
+
+    /// Keeps the registrations in `registrations` that match now.
+    #[must_use]
+    pub fn apply(&self, mut registrations: Vec<Registration>) -> Vec<Registration> {
+        let now = SystemTime::now();
+        registrations.retain(|registration| self.matches_at(registration, now));
+        registrations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Endpoint;
+    use agent_uri::AgentUri;
+
+    fn registration() -> Registration {
+        let uri =
+            AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q")
+                .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+    }
+
+    #[test]
+    fn default_filter_matches_everything() {
+        let expired = registration().with_expires_at(SystemTime::UNIX_EPOCH);
+        assert!(LookupFilter::new().matches(&expired));
+        assert!(!LookupFilter::new().with_exclude_expired(true).matches(&expired));
+    }
+
+    #[test]
+    fn each_condition_narrows_the_filter() {
+        let now = SystemTime::now();
+        let fresh = registration()
+            .with_registered_at(now - Duration::from_secs(10))
+            .with_metadata("region", "eu");
+
+        let https = LookupFilter::new().with_protocol("https");
+        assert!(https.matches_at(&fresh, now));
+        assert!(!LookupFilter::new().with_protocol("grpc").matches_at(&fresh, now));
+
+        let recent = LookupFilter::new().with_max_staleness(Duration::from_mins(1));
+        assert!(recent.matches_at(&fresh, now));
+        assert!(!recent.matches_at(&fresh, now + Duration::from_mins(2)));
+
+        let region = LookupFilter::new().with_required_metadata("region");
+        assert!(region.matches_at(&fresh, now));
+        assert!(!region.with_required_metadata("tier").matches_at(&fresh, now));
+    }
+}
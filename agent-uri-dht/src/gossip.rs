@@ -0,0 +1,347 @@
+//! Epidemic dissemination for a [`NetworkSimulation`], as an alternative to
+//! storing records only at the nodes closest to their key.
+
+use std::time::Duration;
+
+use crate::churn::{SplitMix, ratio};
+use crate::{DhtError, NetworkSimulation, Registration};
+
+/// How a registration spreads by gossip.
+///
+/// Each round, every node that learned of the record at most
+/// [`rounds`](Self::rounds) rounds ago pushes it to
+/// [`fanout`](Self::fanout) random contacts from its routing table. Pushes
+/// alone can leave a few nodes uninformed; anti-entropy rounds, in which
+/// every node reconciles with one random contact, pick those up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GossipConfig {
+    /// Contacts an informed node pushes the record to per round
+    ///
+    /// Default: 3
+    pub fanout: usize,
+    /// Rounds a node keeps pushing the record after learning of it
+    ///
+    /// Default: 4
+    pub rounds: usize,
+    /// Every this many rounds, each online node reconciles with one random
+    /// contact and whichever lacks the record takes it from the other; 0
+    /// disables anti-entropy
+    ///
+    /// Default: 0
+    pub anti_entropy_interval: usize,
+    /// Simulated time one round takes
+    ///
+    /// Default: 100ms
+    pub round_interval: Duration,
+    /// Rounds to run before giving up on convergence
+    ///
+    /// Default: 100
+    pub max_rounds: usize,
+    /// Seed for choosing contacts
+    ///
+    /// Default: 0
+    pub seed: u64,
+}
+
+impl Default for GossipConfig {
+    fn default() -> Self {
+        Self {
+            fanout: 3,
+            rounds: 4,
+            anti_entropy_interval: 0,
+            round_interval: Duration::from_millis(100),
+            max_rounds: 100,
+            seed: 0,
+        }
+    }
+}
+
+impl GossipConfig {
+    /// Creates a configuration with the defaults.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the contacts pushed to per round.
+    #[must_use]
+    pub const fn with_fanout(mut self, fanout: usize) -> Self {
+        self.fanout = fanout;
+        self
+    }
+
+    /// Sets the rounds a node keeps pushing the record.
+    #[must_use]
+    pub const fn with_rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Runs an anti-entropy round every `interval` rounds; 0 disables them.
+    #[must_use]
+    pub const fn with_anti_entropy(mut self, interval: usize) -> Self {
+        self.anti_entropy_interval = interval;
+        self
+    }
+
+    /// Sets the simulated time one round takes.
+    #[must_use]
+    pub const fn with_round_interval(mut self, interval: Duration) -> Self {
+        self.round_interval = interval;
+        self
+    }
+
+    /// Sets the rounds run before giving up on convergence.
+    #[must_use]
+    pub const fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Sets the seed for choosing contacts.
+    #[must_use]
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// How far and how fast a gossiped registration spread.
+///
+/// Returned by [`NetworkSimulation::gossip`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GossipReport {
+    /// Rounds run
+    pub rounds: usize,
+    /// Online nodes in the network
+    pub online: usize,
+    /// Online nodes holding the record before the first round (just the
+    /// origin) and after each round
+    pub informed_by_round: Vec<usize>,
+    /// Round after which every online node held the record, if any
+    pub converged_round: Option<usize>,
+    /// Simulated time from the write until every online node held the
+    /// record, if it got that far
+    pub convergence_time: Option<Duration>,
+    /// Pushes sent
+    pub messages: usize,
+    /// Pushes to nodes that already held the record or were offline
+    pub redundant_messages: usize,
+    /// Anti-entropy reconciliations run
+    pub anti_entropy_exchanges: usize,
+}
+
+impl GossipReport {
+    /// Returns the number of online nodes holding the record at the end.
+    #[must_use]
+    pub fn informed(&self) -> usize {
+        self.informed_by_round.last().copied().unwrap_or_default()
+    }
+
+    /// Returns the fraction of online nodes holding the record at the end.
+    #[must_use]
+    pub fn coverage(&self) -> f64 {
+        ratio(self.informed(), self.online)
+    }
+
+    /// Returns the first round after which at least `fraction` of the
+    /// online nodes held the record, with 0 meaning before the first round.
+    #[must_use]
+    pub fn rounds_to(&self, fraction: f64) -> Option<usize> {
+        self.informed_by_round
+            .iter()
+            .position(|&informed| ratio(informed, self.online) >= fraction)
+    }
+
+    /// Returns the redundant share of pushes, or 0.0 if none were sent.
+    #[must_use]
+    pub fn redundancy(&self) -> f64 {
+        if self.messages == 0 {
+            return 0.0;
+        }
+        ratio(self.redundant_messages, self.messages)
+    }
+}
+
+impl NetworkSimulation {
+    /// Registers an agent at node `origin` and spreads the record to every
+    /// node by gossip, instead of storing it at the closest nodes as
+    /// [`register`](Self::register) does.
+    ///
+    /// Each round advances simulated time by
+    /// [`round_interval`](GossipConfig::round_interval). Gossip stops once
+    /// every online node holds the record, once no node is pushing it and
+    /// anti-entropy is disabled, or after
+    /// [`max_rounds`](GossipConfig::max_rounds). Lookups afterwards find the
+    /// record wherever it reached, so comparing the report with the
+    /// [`WriteOutcome`](crate::WriteOutcome) of a Kademlia write shows what
+    /// each strategy costs in messages and time.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::NoEndpoints` if the registration has no
+    /// endpoints, or `DhtError::InvalidEndpoint` if one is malformed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `origin` is not an online node.
+    pub fn gossip(
+        &mut self,
+        origin: usize,
+        registration: Registration,
+        config: &GossipConfig,
+    ) -> Result<GossipReport, DhtError> {
+        assert!(self.is_online(origin), "origin is not an online node");
+        let (key, stored) = self.version(registration)?;
+        let start = self.now();
+        let nodes = self.node_count();
+
+        // Round each node learned of the record in
+        let mut informed_at: Vec<Option<usize>> = vec![None; nodes];
+        self.store(origin, key, stored.clone());
+        informed_at[origin] = Some(0);
+
+        let mut report = GossipReport {
+            online: self.online_count(),
+            informed_by_round: vec![1],
+            ..GossipReport::default()
+        };
+        if report.online == 1 {
+            report.converged_round = Some(0);
+            report.convergence_time = Some(Duration::ZERO);
+            return Ok(report);
+        }
+
+        let mut rng = SplitMix(config.seed);
+        for round in 1..=config.max_rounds {
+            let senders: Vec<usize> = (0..nodes)
+                .filter(|&index| {
+                    self.is_online(index)
+                        && informed_at[index].is_some_and(|at| round - at <= config.rounds)
+                })
+                .collect();
+            let anti_entropy =
+                config.anti_entropy_interval > 0 && round % config.anti_entropy_interval == 0;
+            if senders.is_empty() && config.anti_entropy_interval == 0 {
+                break;
+            }
+
+            // Nodes informed this round only pass the record on from the next
+            let mut newly = vec![false; nodes];
+            for sender in senders {
+                for peer in self.pick_contacts(sender, config.fanout, &mut rng) {
+                    report.messages += 1;
+                    if self.is_online(peer) && informed_at[peer].is_none() && !newly[peer] {
+                        newly[peer] = true;
+                    } else {
+                        report.redundant_messages += 1;
+                    }
+                }
+            }
+            if anti_entropy {
+                for node in (0..nodes).filter(|&index| self.is_online(index)) {
+                    let Some(&peer) = self.pick_contacts(node, 1, &mut rng).first() else {
+                        continue;
+                    };
+                    report.anti_entropy_exchanges += 1;
+                    let differ = informed_at[node].is_some() != informed_at[peer].is_some();
+                    if self.is_online(peer) && differ {
+                        let lacking = if informed_at[node].is_some() { peer } else { node };
+                        newly[lacking] = true;
+                    }
+                }
+            }
+
+            for (index, _) in newly.iter().enumerate().filter(|(_, newly)| **newly) {
+                self.store(index, key, stored.clone());
+                informed_at[index] = Some(round);
+            }
+            self.advance(config.round_interval);
+            report.rounds = round;
+
+            let informed = (0..nodes)
+                .filter(|&index| self.is_online(index) && informed_at[index].is_some())
+                .count();
+            report.informed_by_round.push(informed);
+            if informed == report.online {
+                report.converged_round = Some(round);
+                report.convergence_time = Some(self.now().saturating_sub(start));
+                break;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Returns up to `count` distinct random contacts of node `index`.
+    fn pick_contacts(&self, index: usize, count: usize, rng: &mut SplitMix) -> Vec<usize> {
+        let mut contacts = self.contacts(index).to_vec();
+        let count = count.min(contacts.len());
+        for i in 0..count {
+            let j = i + rng.below(contacts.len() - i);
+            contacts.swap(i, j);
+        }
+        contacts.truncate(count);
+        contacts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Endpoint, NetworkConfig};
+    use agent_uri::AgentUri;
+
+    fn registration() -> Registration {
+        let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q")
+            .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+    }
+
+    fn network() -> NetworkSimulation {
+        NetworkSimulation::new(NetworkConfig::new().with_nodes(200).with_k(8).with_seed(7))
+    }
+
+    #[test]
+    fn gossip_reaches_every_node() {
+        let mut network = network();
+        let config = GossipConfig::new().with_anti_entropy(5);
+        let report = network.gossip(0, registration(), &config).unwrap();
+
+        assert_eq!(report.informed(), 200);
+        assert!((report.coverage() - 1.0).abs() < f64::EPSILON);
+        let rounds = report.converged_round.unwrap();
+        assert_eq!(report.rounds, rounds);
+        let elapsed = Duration::from_millis(100) * u32::try_from(rounds).unwrap();
+        assert_eq!(report.convergence_time, Some(elapsed));
+        assert!(report.rounds_to(0.5).unwrap() <= rounds);
+        assert!(report.messages >= 199);
+        assert!(report.redundancy() > 0.0);
+
+        let uri = registration().agent_uri().clone();
+        let lookup = network.lookup_exact(150, uri.trust_root(), uri.capability_path());
+        assert_eq!(lookup.registrations.len(), 1);
+        let key = network.published().first().copied().unwrap();
+        assert_eq!(network.replicas(&key).len(), 200);
+    }
+
+    #[test]
+    fn anti_entropy_completes_a_stalled_rumor() {
+        let config = GossipConfig::new().with_fanout(1).with_rounds(1);
+        let report = network().gossip(0, registration(), &config).unwrap();
+        assert!(report.converged_round.is_none());
+        assert!(report.informed() < 200);
+        assert_eq!(report.anti_entropy_exchanges, 0);
+
+        let config = config.with_anti_entropy(2);
+        let report = network().gossip(0, registration(), &config).unwrap();
+        assert!(report.converged_round.is_some());
+        assert!(report.anti_entropy_exchanges > 0);
+    }
+
+    #[test]
+    fn gossip_rejects_registrations_without_endpoints() {
+        let uri = registration().agent_uri().clone();
+        let result = network().gossip(0, Registration::new(uri, vec![]), &GossipConfig::new());
+        assert!(matches!(result, Err(DhtError::NoEndpoints)));
+    }
+}
@@ -0,0 +1,101 @@
+//! Cross-trust-root lookup results grouped by authority.
+
+use std::collections::BTreeMap;
+
+use agent_uri::TrustRoot;
+
+use crate::Registration;
+
+/// The registrations one trust root contributed to a global lookup.
+///
+/// Returned by [`Dht::lookup_global_grouped`](crate::Dht::lookup_global_grouped).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustRootGroup {
+    /// The authority the registrations belong to
+    pub trust_root: TrustRoot,
+    /// Number of registrations that matched under this trust root, before
+    /// any per-root limit
+    pub total: usize,
+    /// Matching registrations ordered by agent URI, at most the per-root
+    /// limit of them
+    pub registrations: Vec<Registration>,
+}
+
+impl TrustRootGroup {
+    /// Returns true if the per-root limit left out some matches.
+    #[must_use]
+    pub fn is_truncated(&self) -> bool {
+        self.registrations.len() < self.total
+    }
+
+    /// Buckets `registrations` by trust root, ordered by trust root, keeping
+    /// at most `per_root_limit` per group.
+    pub(crate) fn group(
+        registrations: Vec<Registration>,
+        per_root_limit: Option<usize>,
+    ) -> Vec<Self> {
+        let mut by_root: BTreeMap<String, Self> = BTreeMap::new();
+        for registration in registrations {
+            let trust_root = registration.agent_uri().trust_root();
+            by_root
+                .entry(trust_root.as_str().to_string())
+                .or_insert_with(|| Self {
+                    trust_root: trust_root.clone(),
+                    total: 0,
+                    registrations: Vec::new(),
+                })
+                .registrations
+                .push(registration);
+        }
+
+        by_root
+            .into_values()
+            .map(|mut group| {
+                group.total = group.registrations.len();
+                group
+                    .registrations
+                    .sort_unstable_by(|a, b| a.agent_uri().as_str().cmp(b.agent_uri().as_str()));
+                if let Some(limit) = per_root_limit {
+                    group.registrations.truncate(limit);
+                }
+                group
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Endpoint;
+    use agent_uri::AgentUri;
+
+    fn registration(trust_root: &str, suffix: &str) -> Registration {
+        let uri = AgentUri::parse(&format!(
+            "agent://{trust_root}/assistant/llm_01h455vb4pex5vsknk084sn0{suffix}"
+        ))
+        .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.example.com")])
+    }
+
+    #[test]
+    fn group_counts_before_limiting() {
+        let groups = TrustRootGroup::group(
+            vec![
+                registration("b.com", "3q"),
+                registration("a.com", "2q"),
+                registration("b.com", "2q"),
+                registration("b.com", "4q"),
+            ],
+            Some(2),
+        );
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].trust_root.as_str(), "a.com");
+        assert!(!groups[0].is_truncated());
+        assert_eq!(groups[1].total, 3);
+        assert!(groups[1].is_truncated());
+        let expected = [registration("b.com", "2q"), registration("b.com", "3q")];
+        assert_eq!(groups[1].registrations, expected);
+    }
+}
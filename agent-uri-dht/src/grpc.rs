@@ -0,0 +1,565 @@
+//! gRPC registry protocol.
+
+use std::pin::Pin;
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+
+use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+use tokio_stream::Stream;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use crate::registration::{millis_to_system_time, system_time_to_millis};
+use crate::{
+    AsyncDht, Dht, DhtError, DhtEvent, Endpoint, LookupCursor, LookupPage, PathPattern,
+    Registration, TokioDht,
+};
+
+/// Messages, client and server generated from `proto/registry.proto`
+/// (feature `grpc`).
+///
+/// Messages convert to and from the crate's types; converting a
+/// [`proto::Registration`] into a [`Registration`] validates its agent URI
+/// and endpoints.
+#[allow(missing_docs, clippy::all, clippy::pedantic)]
+pub mod proto {
+    tonic::include_proto!("agent_uri_dht.v1");
+}
+
+use proto::registry_server::{Registry, RegistryServer};
+use proto::watch_event::Event;
+
+/// Serves the gRPC registry protocol over a [`Dht`] (feature `grpc`).
+///
+/// Gives clients in any language with gRPC support access to a registry
+/// node. Requests are checked at the boundary with the crate's types:
+/// agent URIs, trust roots, capability paths and patterns are parsed and
+/// endpoints validated before `dht` sees them, and invalid ones fail with
+/// `INVALID_ARGUMENT`. Operations run on tokio's blocking thread pool, as
+/// with [`TokioDht`], and [`DhtError`]s map to the closest status code.
+///
+/// Each `Watch` stream holds a blocking thread until the client goes away.
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_uri_dht::{RegistryService, SimulatedDht};
+///
+/// # async fn serve() -> Result<(), Box<dyn std::error::Error>> {
+/// tonic::transport::Server::builder()
+///     .add_service(RegistryService::new(SimulatedDht::with_defaults()).into_server())
+///     .serve("127.0.0.1:50051".parse()?)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Clients use the generated [`RegistryClient`](proto::registry_client::RegistryClient):
+///
+/// ```no_run
+/// use agent_uri_dht::proto::registry_client::RegistryClient;
+/// use agent_uri_dht::proto::{LookupMode, LookupRequest};
+/// use agent_uri_dht::Registration;
+///
+/// # async fn lookup() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut client = RegistryClient::connect("http://127.0.0.1:50051").await?;
+/// let response = client
+///     .lookup(LookupRequest {
+///         trust_root: "acme.com".to_string(),
+///         path: "assistant".to_string(),
+///         mode: LookupMode::Prefix.into(),
+///         ..LookupRequest::default()
+///     })
+///     .await?;
+/// for registration in response.into_inner().registrations {
+///     let registration = Registration::try_from(registration)?;
+///     println!("{}", registration.agent_uri());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct RegistryService<D> {
+    dht: TokioDht<D>,
+}
+
+impl<D: Dht + 'static> RegistryService<D> {
+    /// How often a `Watch` stream's thread checks whether the client has
+    /// gone away while no events arrive.
+    const WATCH_POLL: Duration = Duration::from_secs(1);
+
+    /// Serves `dht`.
+    #[must_use]
+    pub fn new(dht: D) -> Self {
+        Self {
+            dht: TokioDht::new(dht),
+        }
+    }
+
+    /// Serves a DHT shared with other users.
+    #[must_use]
+    pub fn from_tokio(dht: TokioDht<D>) -> Self {
+        Self { dht }
+    }
+
+    /// Returns the served DHT.
+    #[must_use]
+    pub fn dht(&self) -> &TokioDht<D> {
+        &self.dht
+    }
+
+    /// Wraps the service for a `tonic` server.
+    #[must_use]
+    pub fn into_server(self) -> RegistryServer<Self> {
+        RegistryServer::new(self)
+    }
+}
+
+impl<D> std::fmt::Debug for RegistryService<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistryService").finish_non_exhaustive()
+    }
+}
+
+#[tonic::async_trait]
+impl<D: Dht + 'static> Registry for RegistryService<D> {
+    async fn register(
+        &self,
+        request: Request<proto::RegisterRequest>,
+    ) -> Result<Response<proto::RegisterResponse>, Status> {
+        let registration = required(request.into_inner().registration)?;
+        self.dht.register(registration).await?;
+        Ok(Response::new(proto::RegisterResponse {}))
+    }
+
+    async fn update_registration(
+        &self,
+        request: Request<proto::UpdateRegistrationRequest>,
+    ) -> Result<Response<proto::UpdateRegistrationResponse>, Status> {
+        let registration = required(request.into_inner().registration)?;
+        self.dht.update_registration(registration).await?;
+        Ok(Response::new(proto::UpdateRegistrationResponse {}))
+    }
+
+    async fn renew(
+        &self,
+        request: Request<proto::RenewRequest>,
+    ) -> Result<Response<proto::RenewResponse>, Status> {
+        let request = request.into_inner();
+        let agent_uri = parse_agent_uri(&request.agent_uri)?;
+        let ttl = Duration::from_millis(request.ttl_ms);
+        self.dht.renew(&agent_uri, ttl).await?;
+        Ok(Response::new(proto::RenewResponse {}))
+    }
+
+    async fn deregister(
+        &self,
+        request: Request<proto::DeregisterRequest>,
+    ) -> Result<Response<proto::DeregisterResponse>, Status> {
+        let agent_uri = parse_agent_uri(&request.into_inner().agent_uri)?;
+        self.dht.deregister(&agent_uri).await?;
+        Ok(Response::new(proto::DeregisterResponse {}))
+    }
+
+    async fn lookup(
+        &self,
+        request: Request<proto::LookupRequest>,
+    ) -> Result<Response<proto::LookupResponse>, Status> {
+        use proto::LookupMode;
+
+        let request = request.into_inner();
+        let trust_root = match request.trust_root.as_str() {
+            "" => None,
+            trust_root => Some(
+                TrustRoot::parse(trust_root)
+                    .map_err(|e| Status::invalid_argument(format!("invalid trust root: {e}")))?,
+            ),
+        };
+        let limit = usize::try_from(request.limit).unwrap_or(usize::MAX);
+        let cursor = (!request.cursor.is_empty()).then(|| LookupCursor::new(request.cursor));
+        let mode = LookupMode::try_from(request.mode).unwrap_or(LookupMode::Unspecified);
+        let dht = &self.dht;
+
+        let page = match (mode, trust_root) {
+            (LookupMode::Exact, Some(trust_root)) => {
+                let path = parse_path(&request.path)?;
+                all(dht.lookup_exact(&trust_root, &path).await?)
+            }
+            (LookupMode::Prefix, Some(trust_root)) if limit > 0 => {
+                let path = parse_path(&request.path)?;
+                dht.lookup_prefix_paged(&trust_root, &path, limit, cursor.as_ref())
+                    .await?
+            }
+            (LookupMode::Prefix, Some(trust_root)) => {
+                let path = parse_path(&request.path)?;
+                all(dht.lookup_prefix(&trust_root, &path).await?)
+            }
+            (LookupMode::Prefix, None) => {
+                let path = parse_path(&request.path)?;
+                all(dht.lookup_global(&path).await?)
+            }
+            (LookupMode::Pattern, Some(trust_root)) => {
+                let pattern = parse_pattern(&request.path)?;
+                all(dht.lookup_pattern(&trust_root, &pattern).await?)
+            }
+            (LookupMode::Pattern, None) => {
+                let pattern = parse_pattern(&request.path)?;
+                all(dht.lookup_pattern_global(&pattern).await?)
+            }
+            (LookupMode::TrustRoot, Some(trust_root)) if limit > 0 => {
+                dht.lookup_trust_root_paged(&trust_root, limit, cursor.as_ref())
+                    .await?
+            }
+            (LookupMode::TrustRoot, Some(trust_root)) => {
+                all(dht.lookup_trust_root(&trust_root).await?)
+            }
+            (LookupMode::Exact | LookupMode::TrustRoot, None) => {
+                return Err(Status::invalid_argument(format!(
+                    "{} lookups need a trust root",
+                    mode.as_str_name()
+                )));
+            }
+            (LookupMode::Unspecified, _) => {
+                return Err(Status::invalid_argument("lookup mode is not set"));
+            }
+        };
+
+        Ok(Response::new(proto::LookupResponse {
+            registrations: page.registrations.iter().map(Into::into).collect(),
+            next_cursor: page
+                .next_cursor
+                .map(|cursor| cursor.as_str().to_string())
+                .unwrap_or_default(),
+        }))
+    }
+
+    type WatchStream = Pin<Box<dyn Stream<Item = Result<proto::WatchEvent, Status>> + Send>>;
+
+    async fn watch(
+        &self,
+        request: Request<proto::WatchRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let request = request.into_inner();
+        let trust_root = TrustRoot::parse(&request.trust_root)
+            .map_err(|e| Status::invalid_argument(format!("invalid trust root: {e}")))?;
+        let path = parse_path(&request.path)?;
+        let events = self.dht.watch_prefix(&trust_root, &path).await?;
+
+        let (sender, receiver) = tokio::sync::mpsc::channel(16);
+        tokio::task::spawn_blocking(move || {
+            loop {
+                match events.recv_timeout(Self::WATCH_POLL) {
+                    Ok(event) => {
+                        if sender.blocking_send(Ok(event.into())).is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) if !sender.is_closed() => {}
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+}
+
+impl From<DhtError> for Status {
+    fn from(error: DhtError) -> Self {
+        let message = error.to_string();
+        match error {
+            DhtError::NotFound { .. } | DhtError::Expired { .. } => Self::not_found(message),
+            DhtError::AlreadyRegistered { .. } => Self::already_exists(message),
+            DhtError::StaleWrite { .. } | DhtError::SequenceConflict { .. } => {
+                Self::aborted(message)
+            }
+            DhtError::InvalidAttestation { .. }
+            | DhtError::InsufficientProofOfWork { .. }
+            | DhtError::CapabilityMismatch { .. } => Self::permission_denied(message),
+            DhtError::KeyCapacityExceeded { .. }
+            | DhtError::CapacityExceeded { .. }
+            | DhtError::ResultLimitExceeded { .. } => Self::resource_exhausted(message),
+            DhtError::NoEndpoints
+            | DhtError::InvalidEndpoint { .. }
+            | DhtError::TtlOutOfBounds { .. } => Self::invalid_argument(message),
+            DhtError::Backend { .. } => Self::unavailable(message),
+            DhtError::UnsupportedSnapshotVersion { .. } | DhtError::Internal { .. } => {
+                Self::internal(message)
+            }
+        }
+    }
+}
+
+impl From<&Endpoint> for proto::Endpoint {
+    fn from(endpoint: &Endpoint) -> Self {
+        Self {
+            protocol: endpoint.protocol().to_string(),
+            address: endpoint.address().to_string(),
+            path: endpoint.path().map(str::to_string),
+            region: endpoint.region().map(str::to_string),
+            priority: endpoint.priority(),
+            weight: endpoint.weight(),
+            protocol_versions: endpoint.protocol_versions().to_vec(),
+            metadata: endpoint
+                .metadata()
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl From<proto::Endpoint> for Endpoint {
+    fn from(endpoint: proto::Endpoint) -> Self {
+        let mut converted = Self::new(endpoint.protocol, endpoint.address, endpoint.path);
+        if let Some(region) = endpoint.region {
+            converted = converted.with_region(region);
+        }
+        if let Some(priority) = endpoint.priority {
+            converted = converted.with_priority(priority);
+        }
+        if let Some(weight) = endpoint.weight {
+            converted = converted.with_weight(weight);
+        }
+        for version in endpoint.protocol_versions {
+            converted = converted.with_protocol_version(version);
+        }
+        for (key, value) in endpoint.metadata {
+            converted = converted.with_metadata(key, value);
+        }
+        converted
+    }
+}
+
+impl From<&Registration> for proto::Registration {
+    fn from(registration: &Registration) -> Self {
+        Self {
+            agent_uri: registration.agent_uri().to_string(),
+            endpoints: registration.endpoints().iter().map(Into::into).collect(),
+            attestation: registration.attestation().map(str::to_string),
+            proof_of_work: registration.proof_of_work(),
+            metadata: registration
+                .metadata()
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            expires_at_ms: system_time_to_millis(registration.expires_at()),
+            registered_at_ms: system_time_to_millis(registration.registered_at()),
+            seq: registration.seq(),
+            signed_at_ms: system_time_to_millis(registration.signed_at()),
+            refresh_ahead_ms: registration
+                .refresh_policy()
+                .map(|window| u64::try_from(window.as_millis()).unwrap_or(u64::MAX)),
+        }
+    }
+}
+
+impl TryFrom<proto::Registration> for Registration {
+    type Error = Status;
+
+    /// Converts a registration received over gRPC, rejecting malformed
+    /// agent URIs and endpoints with `INVALID_ARGUMENT`.
+    fn try_from(registration: proto::Registration) -> Result<Self, Status> {
+        let agent_uri = parse_agent_uri(&registration.agent_uri)?;
+        let endpoints: Vec<Endpoint> =
+            registration.endpoints.into_iter().map(Into::into).collect();
+        Endpoint::validate_all(&endpoints)?;
+
+        let mut converted = Self::new(agent_uri, endpoints)
+            .with_expires_at(millis_to_system_time(registration.expires_at_ms))
+            .with_registered_at(millis_to_system_time(registration.registered_at_ms))
+            .with_seq(registration.seq)
+            .with_signed_at(millis_to_system_time(registration.signed_at_ms));
+        if let Some(attestation) = registration.attestation {
+            converted = converted.with_attestation(attestation);
+        }
+        if let Some(nonce) = registration.proof_of_work {
+            converted = converted.with_proof_of_work(nonce);
+        }
+        if let Some(window) = registration.refresh_ahead_ms {
+            converted = converted.with_refresh_ahead(Duration::from_millis(window));
+        }
+        for (key, value) in registration.metadata {
+            converted = converted.with_metadata(key, value);
+        }
+        Ok(converted)
+    }
+}
+
+impl From<DhtEvent> for proto::WatchEvent {
+    fn from(event: DhtEvent) -> Self {
+        let event = match event {
+            DhtEvent::Registered(registration) => Event::Registered((&registration).into()),
+            DhtEvent::Updated(registration) => Event::Updated((&registration).into()),
+            DhtEvent::Deregistered(agent_uri) => Event::Deregistered(agent_uri.to_string()),
+            DhtEvent::Expired(agent_uri) => Event::Expired(agent_uri.to_string()),
+            DhtEvent::Evicted(agent_uri) => Event::Evicted(agent_uri.to_string()),
+        };
+        Self { event: Some(event) }
+    }
+}
+
+/// Converts a registration a request must carry.
+fn required(registration: Option<proto::Registration>) -> Result<Registration, Status> {
+    registration
+        .ok_or_else(|| Status::invalid_argument("registration is missing"))?
+        .try_into()
+}
+
+/// Wraps every match of an unpaged lookup as a single page.
+fn all(registrations: Vec<Registration>) -> LookupPage {
+    LookupPage {
+        registrations,
+        next_cursor: None,
+    }
+}
+
+fn parse_agent_uri(agent_uri: &str) -> Result<AgentUri, Status> {
+    AgentUri::parse(agent_uri)
+        .map_err(|e| Status::invalid_argument(format!("invalid agent URI: {e}")))
+}
+
+fn parse_path(path: &str) -> Result<CapabilityPath, Status> {
+    CapabilityPath::parse(path)
+        .map_err(|e| Status::invalid_argument(format!("invalid capability path: {e}")))
+}
+
+fn parse_pattern(pattern: &str) -> Result<PathPattern, Status> {
+    PathPattern::parse(pattern)
+        .map_err(|e| Status::invalid_argument(format!("invalid path pattern: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use tokio_stream::StreamExt;
+    use tonic::Code;
+
+    use super::*;
+    use crate::SimulatedDht;
+
+    fn registration(suffix: &str) -> Registration {
+        let uri = AgentUri::parse(&format!(
+            "agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn0{suffix}"
+        ))
+        .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+    }
+
+    fn lookup(mode: proto::LookupMode, trust_root: &str, path: &str) -> proto::LookupRequest {
+        proto::LookupRequest {
+            trust_root: trust_root.to_string(),
+            path: path.to_string(),
+            mode: mode.into(),
+            ..proto::LookupRequest::default()
+        }
+    }
+
+    #[test]
+    fn registrations_convert_both_ways() {
+        let expires_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_900_000_000);
+        let registration = Registration::new(registration("2q").agent_uri().clone(), vec![
+            Endpoint::https_with_path("eu.acme.com", "/v1")
+                .with_region("eu-west-1")
+                .with_priority(2)
+                .with_weight(3)
+                .with_protocol_version("a2a/1.0")
+                .with_metadata("zone", "b"),
+        ])
+        .with_expires_at(expires_at)
+        .with_seq(4)
+        .with_attestation("v4.public.token")
+        .with_proof_of_work(42)
+        .with_refresh_ahead(Duration::from_secs(90))
+        .with_metadata("tier", "gold");
+
+        let message = proto::Registration::from(&registration);
+        let converted = Registration::try_from(message.clone()).unwrap();
+        assert_eq!(converted.endpoints(), registration.endpoints());
+        assert_eq!(converted.metadata(), registration.metadata());
+        assert_eq!(converted.attestation(), registration.attestation());
+        assert_eq!(converted.proof_of_work(), Some(42));
+        assert_eq!(converted.seq(), 4);
+        assert_eq!(converted.expires_at(), expires_at);
+        assert_eq!(converted.refresh_policy(), Some(Duration::from_secs(90)));
+
+        let mut invalid = message.clone();
+        invalid.agent_uri = "https://acme.com".to_string();
+        assert_eq!(Registration::try_from(invalid).unwrap_err().code(), Code::InvalidArgument);
+        let mut invalid = message;
+        invalid.endpoints[0].path = Some("v1".to_string());
+        assert_eq!(Registration::try_from(invalid).unwrap_err().code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn serves_registrations_and_lookups() {
+        use proto::LookupMode;
+
+        let service = RegistryService::new(SimulatedDht::with_defaults());
+        for suffix in ["2q", "3q"] {
+            let request = proto::RegisterRequest {
+                registration: Some((&registration(suffix)).into()),
+            };
+            service.register(Request::new(request)).await.unwrap();
+        }
+        let duplicate = proto::RegisterRequest {
+            registration: Some((&registration("2q")).into()),
+        };
+        let status = service.register(Request::new(duplicate)).await.unwrap_err();
+        assert_eq!(status.code(), Code::AlreadyExists);
+
+        let found = service
+            .lookup(Request::new(lookup(LookupMode::Prefix, "acme.com", "assistant")))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(found.registrations.len(), 2);
+        assert!(found.next_cursor.is_empty());
+
+        let mut paged = lookup(LookupMode::TrustRoot, "acme.com", "");
+        paged.limit = 1;
+        let first = service.lookup(Request::new(paged.clone())).await.unwrap().into_inner();
+        paged.cursor = first.next_cursor;
+        let second = service.lookup(Request::new(paged)).await.unwrap().into_inner();
+        assert_ne!(first.registrations, second.registrations);
+
+        let global = lookup(LookupMode::Pattern, "", "assistant/*");
+        let found = service.lookup(Request::new(global)).await.unwrap().into_inner();
+        assert_eq!(found.registrations.len(), 2);
+
+        for invalid in [
+            lookup(LookupMode::Exact, "", "assistant/chat"),
+            lookup(LookupMode::Unspecified, "acme.com", "assistant"),
+            lookup(LookupMode::Prefix, "acme.com", "Not A Path"),
+        ] {
+            let status = service.lookup(Request::new(invalid)).await.unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+        }
+
+        let uri = registration("2q").agent_uri().to_string();
+        let request = proto::DeregisterRequest { agent_uri: uri.clone() };
+        service.deregister(Request::new(request)).await.unwrap();
+        let request = proto::RenewRequest { agent_uri: uri, ttl_ms: 60_000 };
+        let status = service.renew(Request::new(request)).await.unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn watch_streams_changes() {
+        let service = RegistryService::new(SimulatedDht::with_defaults());
+        let request = proto::WatchRequest {
+            trust_root: "acme.com".to_string(),
+            path: "assistant".to_string(),
+        };
+        let mut events = service.watch(Request::new(request)).await.unwrap().into_inner();
+
+        let registration = registration("2q");
+        let dht: &SimulatedDht = service.dht().inner();
+        Dht::register(dht, registration.clone()).unwrap();
+        Dht::deregister(dht, registration.agent_uri()).unwrap();
+
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(event.event, Some(Event::Registered((&registration).into())));
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(event.event, Some(Event::Deregistered(registration.agent_uri().to_string())));
+    }
+}
@@ -0,0 +1,563 @@
+//! Endpoint liveness probing.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use agent_uri::{CapabilityPath, TrustRoot};
+
+use crate::{Dht, DhtError, Endpoint, Registration};
+
+/// Checks whether an endpoint is reachable.
+///
+/// [`TcpProbe`] and [`HttpProbe`] cover the common cases. Closures taking an
+/// `&Endpoint` implement this trait too, e.g. to speak a protocol-specific
+/// health check.
+pub trait HealthProbe: Send + Sync {
+    /// Probes `endpoint` once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error if the endpoint could not be reached or
+    /// reported itself unhealthy.
+    fn probe(&self, endpoint: &Endpoint) -> io::Result<()>;
+}
+
+impl<F> HealthProbe for F
+where
+    F: Fn(&Endpoint) -> io::Result<()> + Send + Sync,
+{
+    fn probe(&self, endpoint: &Endpoint) -> io::Result<()> {
+        self(endpoint)
+    }
+}
+
+/// Probes an endpoint by opening a TCP connection to its address.
+///
+/// Addresses without a port use the protocol's well-known port (443 for
+/// `https` and `wss`, 80 for `http` and `ws`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpProbe {
+    timeout: Duration,
+}
+
+impl TcpProbe {
+    /// Default connect timeout.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Creates a probe that gives up connecting after `timeout`.
+    #[must_use]
+    pub const fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl Default for TcpProbe {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_TIMEOUT)
+    }
+}
+
+impl HealthProbe for TcpProbe {
+    fn probe(&self, endpoint: &Endpoint) -> io::Result<()> {
+        connect(endpoint, self.timeout).map(drop)
+    }
+}
+
+/// Probes plain `http` endpoints with a `GET` request.
+///
+/// The endpoint is healthy if it answers with a status below 500. Other
+/// protocols fail with `ErrorKind::Unsupported`; TLS is out of scope, so
+/// combine this with a [`TcpProbe`] for `https` endpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpProbe {
+    timeout: Duration,
+    path: Option<String>,
+}
+
+impl HttpProbe {
+    /// Creates a probe that gives up after `timeout` for each of
+    /// connecting, sending and receiving.
+    #[must_use]
+    pub const fn new(timeout: Duration) -> Self {
+        Self { timeout, path: None }
+    }
+
+    /// Requests `path` (e.g. `"/healthz"`) instead of the endpoint's own
+    /// path.
+    #[must_use]
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+}
+
+impl Default for HttpProbe {
+    fn default() -> Self {
+        Self::new(TcpProbe::DEFAULT_TIMEOUT)
+    }
+}
+
+impl HealthProbe for HttpProbe {
+    fn probe(&self, endpoint: &Endpoint) -> io::Result<()> {
+        if endpoint.protocol() != "http" {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("cannot probe {} endpoints over HTTP", endpoint.protocol()),
+            ));
+        }
+        let mut stream = connect(endpoint, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        let path = self.path.as_deref().or(endpoint.path()).unwrap_or("/");
+        let request = format!(
+            "GET {path} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            endpoint.address()
+        );
+        stream.write_all(request.as_bytes())?;
+
+        let mut status_line = String::new();
+        BufReader::new(stream).read_line(&mut status_line)?;
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "malformed HTTP status line")
+            })?;
+        if status >= 500 {
+            return Err(io::Error::other(format!("HTTP status {status}")));
+        }
+        Ok(())
+    }
+}
+
+/// Opens a TCP connection to `endpoint`, trying each resolved address.
+fn connect(endpoint: &Endpoint, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for addr in socket_addrs(endpoint)? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "address did not resolve")
+    }))
+}
+
+/// Resolves `endpoint`'s address, filling in the protocol's default port.
+fn socket_addrs(endpoint: &Endpoint) -> io::Result<Vec<SocketAddr>> {
+    let address = endpoint.address();
+    if endpoint.port().is_some() {
+        return Ok(address.to_socket_addrs()?.collect());
+    }
+    let Some(port) = endpoint.default_port() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("no port in address and no default port for {}", endpoint.protocol()),
+        ));
+    };
+    let host = address.trim_start_matches('[').trim_end_matches(']');
+    Ok((host, port).to_socket_addrs()?.collect())
+}
+
+/// Whether an endpoint is considered reachable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HealthStatus {
+    /// The last check succeeded, or failures are still below the threshold
+    Healthy,
+    /// The failure threshold was reached and no check has succeeded since
+    Unhealthy,
+}
+
+/// The recorded health of one endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointHealth {
+    status: HealthStatus,
+    latency: Option<Duration>,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    checked_at: SystemTime,
+}
+
+impl EndpointHealth {
+    /// Returns the endpoint's status.
+    #[must_use]
+    pub const fn status(&self) -> HealthStatus {
+        self.status
+    }
+
+    /// Returns true if the endpoint is considered reachable.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.status == HealthStatus::Healthy
+    }
+
+    /// Returns the latency of the last successful check, if any.
+    #[must_use]
+    pub const fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// Returns the number of failed checks since the last success.
+    #[must_use]
+    pub const fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// Returns the error of the last check, if it failed.
+    #[must_use]
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Returns when the endpoint was last checked.
+    #[must_use]
+    pub const fn checked_at(&self) -> SystemTime {
+        self.checked_at
+    }
+}
+
+/// Tracks endpoint liveness and latency, and filters lookups by it.
+///
+/// Registrations outlive the processes behind them: after a crash, an
+/// agent's endpoints stay in the DHT until its TTL runs out. The checker
+/// probes endpoints with a [`HealthProbe`] and remembers the outcome, so
+/// the health-aware lookups can drop endpoints that stopped answering.
+///
+/// Nothing is probed on its own: call [`check`](Self::check) or
+/// [`check_registrations`](Self::check_registrations) periodically, and
+/// report failures seen in regular traffic with
+/// [`record_failure`](Self::record_failure). An endpoint turns unhealthy
+/// after [`failure_threshold`](Self::with_failure_threshold) consecutive
+/// failures and healthy again on its next success. Endpoints that were
+/// never checked count as healthy.
+///
+/// # Example
+///
+/// ```
+/// use std::io;
+///
+/// use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+/// use agent_uri_dht::{Dht, Endpoint, HealthChecker, Registration, SimulatedDht};
+///
+/// let dht = SimulatedDht::with_defaults();
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let up = Endpoint::https("us.agent.acme.com");
+/// let down = Endpoint::https("eu.agent.acme.com");
+/// dht.register(Registration::new(uri, vec![up.clone(), down.clone()])).unwrap();
+///
+/// let checker = HealthChecker::new(|endpoint: &Endpoint| {
+///     if endpoint.address().starts_with("eu.") {
+///         Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+///     } else {
+///         Ok(())
+///     }
+/// });
+/// checker.check(&up);
+/// checker.check(&down);
+///
+/// let found = checker
+///     .lookup_prefix(
+///         &dht,
+///         &TrustRoot::parse("acme.com").unwrap(),
+///         &CapabilityPath::parse("assistant").unwrap(),
+///     )
+///     .unwrap();
+/// assert_eq!(found[0].endpoints(), &[up]);
+/// ```
+pub struct HealthChecker {
+    probe: Arc<dyn HealthProbe>,
+    failure_threshold: u32,
+    records: Mutex<HashMap<Endpoint, EndpointHealth>>,
+}
+
+impl HealthChecker {
+    /// Consecutive failures after which an endpoint is unhealthy, unless
+    /// [`with_failure_threshold`](Self::with_failure_threshold) is used.
+    pub const DEFAULT_FAILURE_THRESHOLD: u32 = 1;
+
+    /// Creates a checker that probes endpoints with `probe`.
+    #[must_use]
+    pub fn new(probe: impl HealthProbe + 'static) -> Self {
+        Self {
+            probe: Arc::new(probe),
+            failure_threshold: Self::DEFAULT_FAILURE_THRESHOLD,
+            records: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the number of consecutive failures after which an endpoint is
+    /// unhealthy. Zero is treated as one.
+    #[must_use]
+    pub fn with_failure_threshold(mut self, threshold: u32) -> Self {
+        self.failure_threshold = threshold.max(1);
+        self
+    }
+
+    /// Probes `endpoint` now and records the outcome.
+    pub fn check(&self, endpoint: &Endpoint) -> EndpointHealth {
+        let started = Instant::now();
+        match self.probe.probe(endpoint) {
+            Ok(()) => self.record_success(endpoint, started.elapsed()),
+            Err(e) => self.record_failure(endpoint, e.to_string()),
+        }
+    }
+
+    /// Probes every distinct endpoint of `registrations` and records the
+    /// outcomes.
+    pub fn check_registrations(&self, registrations: &[Registration]) {
+        let mut seen = HashSet::new();
+        for endpoint in registrations.iter().flat_map(Registration::endpoints) {
+            if seen.insert(endpoint) {
+                self.check(endpoint);
+            }
+        }
+    }
+
+    /// Records a successful contact with `endpoint` that took `latency`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    pub fn record_success(&self, endpoint: &Endpoint, latency: Duration) -> EndpointHealth {
+        let health = EndpointHealth {
+            status: HealthStatus::Healthy,
+            latency: Some(latency),
+            consecutive_failures: 0,
+            last_error: None,
+            checked_at: SystemTime::now(),
+        };
+        self.records
+            .lock()
+            .expect("lock poisoned")
+            .insert(endpoint.clone(), health.clone());
+        health
+    }
+
+    /// Records a failed contact with `endpoint`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    pub fn record_failure(&self, endpoint: &Endpoint, error: impl Into<String>) -> EndpointHealth {
+        let mut records = self.records.lock().expect("lock poisoned");
+        let failures = records
+            .get(endpoint)
+            .map_or(0, |health| health.consecutive_failures)
+            .saturating_add(1);
+        let health = EndpointHealth {
+            status: if failures >= self.failure_threshold {
+                HealthStatus::Unhealthy
+            } else {
+                HealthStatus::Healthy
+            },
+            latency: None,
+            consecutive_failures: failures,
+            last_error: Some(error.into()),
+            checked_at: SystemTime::now(),
+        };
+        records.insert(endpoint.clone(), health.clone());
+        health
+    }
+
+    /// Returns the recorded health of `endpoint`, or `None` if it was never
+    /// checked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    #[must_use]
+    pub fn health(&self, endpoint: &Endpoint) -> Option<EndpointHealth> {
+        self.records.lock().expect("lock poisoned").get(endpoint).cloned()
+    }
+
+    /// Returns true unless `endpoint` is recorded as unhealthy.
+    #[must_use]
+    pub fn is_healthy(&self, endpoint: &Endpoint) -> bool {
+        self.health(endpoint).is_none_or(|health| health.is_healthy())
+    }
+
+    /// Forgets everything recorded about `endpoint`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    pub fn forget(&self, endpoint: &Endpoint) {
+        self.records.lock().expect("lock poisoned").remove(endpoint);
+    }
+
+    /// Removes unhealthy endpoints from `registrations`, dropping
+    /// registrations left without any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    #[must_use]
+    pub fn retain_healthy(&self, registrations: Vec<Registration>) -> Vec<Registration> {
+        let records = self.records.lock().expect("lock poisoned");
+        let healthy =
+            |endpoint: &Endpoint| records.get(endpoint).is_none_or(EndpointHealth::is_healthy);
+        registrations
+            .into_iter()
+            .filter_map(|mut registration| {
+                if registration.endpoints().iter().all(healthy) {
+                    return Some(registration);
+                }
+                let endpoints: Vec<_> = registration
+                    .endpoints()
+                    .iter()
+                    .filter(|endpoint| healthy(endpoint))
+                    .cloned()
+                    .collect();
+                if endpoints.is_empty() {
+                    return None;
+                }
+                registration.update_endpoints(endpoints);
+                Some(registration)
+            })
+            .collect()
+    }
+
+    /// Like [`Dht::lookup_exact`], keeping only healthy endpoints.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` as [`Dht::lookup_exact`] does.
+    pub fn lookup_exact<D: Dht + ?Sized>(
+        &self,
+        dht: &D,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Ok(self.retain_healthy(dht.lookup_exact(trust_root, capability_path)?))
+    }
+
+    /// Like [`Dht::lookup_prefix`], keeping only healthy endpoints.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` as [`Dht::lookup_prefix`] does.
+    pub fn lookup_prefix<D: Dht + ?Sized>(
+        &self,
+        dht: &D,
+        trust_root: &TrustRoot,
+        prefix: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Ok(self.retain_healthy(dht.lookup_prefix(trust_root, prefix)?))
+    }
+
+    /// Like [`Dht::lookup_global`], keeping only healthy endpoints.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` as [`Dht::lookup_global`] does.
+    pub fn lookup_global<D: Dht + ?Sized>(
+        &self,
+        dht: &D,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        Ok(self.retain_healthy(dht.lookup_global(capability_path)?))
+    }
+}
+
+impl Default for HealthChecker {
+    fn default() -> Self {
+        Self::new(TcpProbe::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    use agent_uri::AgentUri;
+
+    fn registration(endpoints: Vec<Endpoint>) -> Registration {
+        let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q")
+            .unwrap();
+        Registration::new(uri, endpoints)
+    }
+
+    #[test]
+    fn failures_below_threshold_stay_healthy() {
+        let endpoint = Endpoint::https("agent.acme.com");
+        let checker = HealthChecker::new(|_: &Endpoint| Ok(())).with_failure_threshold(2);
+
+        assert!(checker.record_failure(&endpoint, "timeout").is_healthy());
+        let health = checker.record_failure(&endpoint, "timeout");
+        assert_eq!(health.status(), HealthStatus::Unhealthy);
+        assert_eq!(health.consecutive_failures(), 2);
+        assert_eq!(health.last_error(), Some("timeout"));
+
+        let health = checker.check(&endpoint);
+        assert!(health.is_healthy());
+        assert!(health.latency().is_some());
+    }
+
+    #[test]
+    fn retain_healthy_drops_dead_endpoints_and_registrations() {
+        let up = Endpoint::https("up.acme.com");
+        let down = Endpoint::https("down.acme.com");
+        let checker = HealthChecker::default();
+        checker.record_failure(&down, "connection refused");
+
+        let kept = checker.retain_healthy(vec![registration(vec![up.clone(), down.clone()])]);
+        assert_eq!(kept[0].endpoints(), &[up]);
+        assert!(checker.retain_healthy(vec![registration(vec![down.clone()])]).is_empty());
+
+        checker.forget(&down);
+        assert!(checker.is_healthy(&down));
+    }
+
+    #[test]
+    fn tcp_probe_connects_to_listener() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        let probe = TcpProbe::new(Duration::from_millis(500));
+        assert!(probe.probe(&Endpoint::grpc(address)).is_err());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        assert!(probe.probe(&Endpoint::grpc(address)).is_ok());
+    }
+
+    #[test]
+    fn tcp_probe_requires_port_for_unknown_protocols() {
+        let err = TcpProbe::default()
+            .probe(&Endpoint::grpc("agent.acme.com"))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn http_probe_checks_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            for status in ["200 OK", "503 Service Unavailable"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request_line = String::new();
+                BufReader::new(&stream).read_line(&mut request_line).unwrap();
+                assert_eq!(request_line, "GET /healthz HTTP/1.1\r\n");
+                write!(stream, "HTTP/1.1 {status}\r\nContent-Length: 0\r\n\r\n").unwrap();
+            }
+        });
+
+        let probe = HttpProbe::default().with_path("/healthz");
+        let endpoint = Endpoint::new("http", address, None::<&str>);
+        assert!(probe.probe(&endpoint).is_ok());
+        assert!(probe.probe(&endpoint).is_err());
+        server.join().unwrap();
+
+        let err = probe.probe(&Endpoint::https("agent.acme.com")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+}
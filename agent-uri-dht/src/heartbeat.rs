@@ -0,0 +1,330 @@
+//! Periodic registration renewal.
+
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::{Duration, SystemTime};
+
+use agent_uri::AgentUri;
+
+use crate::{Dht, DhtError, ExpiryPolicy, Registration};
+
+/// Renews registrations before they expire.
+///
+/// Each scheduled agent is renewed with [`Dht::renew`] once
+/// [`refresh_fraction`](Self::with_refresh_fraction) of its TTL has passed,
+/// brought forward by a random share of up to
+/// [`jitter`](Self::with_jitter) of that interval so a fleet started
+/// together does not renew in lockstep. Jitter only ever renews earlier,
+/// never after the registration would expire.
+///
+/// With an [`ExpiryPolicy`](Self::with_expiry_policy), each renewal asks
+/// for a jittered TTL so the fleet's expiries drift apart too, and is
+/// scheduled at the policy's refresh-ahead window before expiry instead of
+/// at the refresh fraction. Registrations scheduled with
+/// [`schedule_registration`](Self::schedule_registration) are first renewed
+/// once [`Registration::should_refresh`] says so.
+///
+/// Nothing happens on its own: call [`tick`](Self::tick) periodically, e.g.
+/// after sleeping until [`next_due`](Self::next_due). Agents whose renewal
+/// fails because they are no longer registered or already expired are
+/// dropped from the schedule and must be registered again; other failures
+/// are retried on the next tick.
+///
+/// # Example
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+///
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{Dht, Endpoint, HeartbeatScheduler, Registration, SimulatedDht};
+///
+/// let ttl = Duration::from_secs(60);
+/// let dht = SimulatedDht::with_defaults();
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// dht.register(Registration::new(uri.clone(), vec![Endpoint::https("agent.acme.com")]).with_ttl(ttl))
+///     .unwrap();
+///
+/// let mut heartbeat = HeartbeatScheduler::new(ttl);
+/// heartbeat.schedule(uri);
+/// assert!(heartbeat.tick(&dht).is_empty());
+///
+/// // Half the TTL later, the registration is renewed
+/// let later = SystemTime::now() + Duration::from_secs(30);
+/// let renewed = heartbeat.tick_at(&dht, later);
+/// assert!(renewed[0].1.is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct HeartbeatScheduler {
+    ttl: Duration,
+    refresh_fraction: f64,
+    jitter: f64,
+    policy: ExpiryPolicy,
+    due: HashMap<String, (AgentUri, SystemTime)>,
+    random: RandomState,
+    renewals: u64,
+}
+
+impl HeartbeatScheduler {
+    /// Share of the TTL after which a registration is renewed, unless
+    /// [`with_refresh_fraction`](Self::with_refresh_fraction) is used.
+    pub const DEFAULT_REFRESH_FRACTION: f64 = 0.5;
+
+    /// Largest share of the renewal interval by which a renewal is brought
+    /// forward, unless [`with_jitter`](Self::with_jitter) is used.
+    pub const DEFAULT_JITTER: f64 = 0.1;
+
+    /// Creates a scheduler that renews registrations for `ttl` at a time.
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            refresh_fraction: Self::DEFAULT_REFRESH_FRACTION,
+            jitter: Self::DEFAULT_JITTER,
+            policy: ExpiryPolicy::new(),
+            due: HashMap::new(),
+            random: RandomState::new(),
+            renewals: 0,
+        }
+    }
+
+    /// Sets the share of the TTL after which a registration is renewed,
+    /// clamped to `0.0..=1.0`.
+    #[must_use]
+    pub fn with_refresh_fraction(mut self, fraction: f64) -> Self {
+        self.refresh_fraction = clamp_fraction(fraction);
+        self
+    }
+
+    /// Sets the largest share of the renewal interval by which a renewal
+    /// may be brought forward, clamped to `0.0..=1.0`. Zero disables
+    /// jitter.
+    #[must_use]
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = clamp_fraction(jitter);
+        self
+    }
+
+    /// Sets the policy renewal TTLs are jittered and refresh-ahead windows
+    /// are taken from.
+    #[must_use]
+    pub const fn with_expiry_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the TTL registrations are renewed for.
+    #[must_use]
+    pub const fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Schedules `agent_uri`, registered with this scheduler's TTL just
+    /// now, for renewal.
+    pub fn schedule(&mut self, agent_uri: AgentUri) {
+        self.schedule_at(agent_uri, SystemTime::now());
+    }
+
+    /// Schedules `agent_uri`, last registered or renewed at `since`, for
+    /// renewal.
+    pub fn schedule_at(&mut self, agent_uri: AgentUri, since: SystemTime) {
+        let due = since + self.next_interval(self.ttl);
+        self.due.insert(agent_uri.to_string(), (agent_uri, due));
+    }
+
+    /// Schedules `registration`'s agent for renewal when the registration
+    /// [should be refreshed](Registration::should_refresh), brought forward
+    /// by jitter, or right away if it already should be.
+    pub fn schedule_registration(&mut self, registration: &Registration) {
+        let agent_uri = registration.agent_uri().clone();
+        let registered_at = registration.registered_at();
+        let due = if registration.should_refresh(registered_at) {
+            registered_at
+        } else {
+            let interval = registration
+                .refresh_at()
+                .duration_since(registered_at)
+                .unwrap_or_default();
+            registered_at + self.jittered(interval)
+        };
+        self.due.insert(agent_uri.to_string(), (agent_uri, due));
+    }
+
+    /// Removes `agent_uri` from the schedule, returning true if it was
+    /// scheduled.
+    pub fn unschedule(&mut self, agent_uri: &AgentUri) -> bool {
+        self.due.remove(&agent_uri.to_string()).is_some()
+    }
+
+    /// Returns the number of scheduled agents.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.due.len()
+    }
+
+    /// Returns true if no agents are scheduled.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.due.is_empty()
+    }
+
+    /// Returns when the next renewal is due, which may already have passed.
+    #[must_use]
+    pub fn next_due(&self) -> Option<SystemTime> {
+        self.due.values().map(|(_, due)| *due).min()
+    }
+
+    /// Renews every registration that is due now.
+    ///
+    /// Returns each agent renewed or attempted with the outcome.
+    pub fn tick(&mut self, dht: &impl Dht) -> Vec<(AgentUri, Result<(), DhtError>)> {
+        self.tick_at(dht, SystemTime::now())
+    }
+
+    /// Renews every registration due at `now`.
+    ///
+    /// Renewed registrations are rescheduled relative to `now`.
+    pub fn tick_at(
+        &mut self,
+        dht: &impl Dht,
+        now: SystemTime,
+    ) -> Vec<(AgentUri, Result<(), DhtError>)> {
+        let due: Vec<AgentUri> = self
+            .due
+            .values()
+            .filter(|(_, due)| *due <= now)
+            .map(|(uri, _)| uri.clone())
+            .collect();
+
+        let mut outcomes = Vec::with_capacity(due.len());
+        for uri in due {
+            let ttl = self.policy.ttl(&uri, self.ttl, now);
+            let result = dht.renew(&uri, ttl);
+            match &result {
+                Ok(()) => {
+                    let due = now + self.next_interval(ttl);
+                    self.due.insert(uri.to_string(), (uri.clone(), due));
+                }
+                Err(DhtError::NotFound { .. } | DhtError::Expired { .. }) => {
+                    self.due.remove(&uri.to_string());
+                }
+                Err(_) => {}
+            }
+            outcomes.push((uri, result));
+        }
+        outcomes
+    }
+
+    /// Returns the time until a registration living `ttl` is next renewed,
+    /// jittered.
+    fn next_interval(&mut self, ttl: Duration) -> Duration {
+        let interval = self.policy.refresh_window(ttl).map_or_else(
+            || ttl.mul_f64(self.refresh_fraction),
+            |window| ttl.saturating_sub(window),
+        );
+        self.jittered(interval)
+    }
+
+    /// Returns `interval` shortened by a random share of up to the jitter.
+    fn jittered(&mut self, interval: Duration) -> Duration {
+        self.renewals = self.renewals.wrapping_add(1);
+        // Uniform in [0, 1) from a randomly keyed hash of a counter
+        #[allow(clippy::cast_precision_loss)]
+        let unit = (self.random.hash_one(self.renewals) >> 11) as f64 / (1u64 << 53) as f64;
+        interval.mul_f64(1.0 - self.jitter * unit)
+    }
+}
+
+/// Clamps `fraction` to `0.0..=1.0`, mapping `NaN` to zero.
+pub(crate) fn clamp_fraction(fraction: f64) -> f64 {
+    if fraction.is_nan() {
+        0.0
+    } else {
+        fraction.clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Endpoint, Registration, SimulatedDht};
+
+    const TTL: Duration = Duration::from_secs(100);
+
+    fn register(dht: &SimulatedDht, suffix: &str) -> AgentUri {
+        let uri = AgentUri::parse(&format!(
+            "agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn0{suffix}"
+        ))
+        .unwrap();
+        dht.register(Registration::new(uri.clone(), vec![Endpoint::https("agent.acme.com")]))
+            .unwrap();
+        uri
+    }
+
+    #[test]
+    fn renewals_are_jittered_before_the_refresh_point() {
+        let mut heartbeat = HeartbeatScheduler::new(TTL).with_jitter(0.5);
+        let now = SystemTime::now();
+        let dht = SimulatedDht::with_defaults();
+        for suffix in ["2q", "3q", "4q", "5q", "6q", "7q", "8q", "9q"] {
+            heartbeat.schedule_at(register(&dht, suffix), now);
+        }
+
+        let offsets: Vec<Duration> = heartbeat
+            .due
+            .values()
+            .map(|(_, due)| due.duration_since(now).unwrap())
+            .collect();
+        assert!(
+            offsets
+                .iter()
+                .all(|d| *d > Duration::from_secs(25) && *d <= Duration::from_secs(50))
+        );
+        assert!(offsets.iter().any(|d| *d != offsets[0]), "renewals are spread out");
+    }
+
+    #[test]
+    fn unregistered_agents_are_dropped() {
+        let dht = SimulatedDht::with_defaults();
+        let kept = register(&dht, "2q");
+        let gone = register(&dht, "3q");
+        let mut heartbeat = HeartbeatScheduler::new(TTL).with_jitter(0.0);
+        let now = SystemTime::now();
+        heartbeat.schedule_at(kept.clone(), now);
+        heartbeat.schedule_at(gone.clone(), now);
+        dht.deregister(&gone).unwrap();
+
+        let later = now + Duration::from_secs(50);
+        let mut outcomes = heartbeat.tick_at(&dht, later);
+        outcomes.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        assert!(outcomes[0].1.is_ok());
+        assert!(outcomes[1].1.as_ref().unwrap_err().is_not_found());
+
+        assert_eq!(heartbeat.len(), 1);
+        assert_eq!(heartbeat.next_due(), Some(later + Duration::from_secs(50)));
+    }
+
+    #[test]
+    fn registrations_are_renewed_in_their_refresh_ahead_window() {
+        let dht = SimulatedDht::with_defaults();
+        let uri = register(&dht, "2q");
+        let registration = Registration::new(uri.clone(), vec![Endpoint::https("agent.acme.com")])
+            .with_ttl(TTL)
+            .with_refresh_ahead(Duration::from_secs(20));
+        let registered_at = registration.registered_at();
+
+        let policy = ExpiryPolicy::new().with_refresh_ahead(0.1);
+        let mut heartbeat = HeartbeatScheduler::new(TTL)
+            .with_jitter(0.0)
+            .with_expiry_policy(policy);
+        heartbeat.schedule_registration(&registration);
+        let due = heartbeat.next_due().unwrap();
+        assert_eq!(due, registration.refresh_at());
+        assert!(registration.should_refresh(due));
+
+        // Later renewals use the policy's window of the TTL
+        assert!(heartbeat.tick_at(&dht, registered_at + Duration::from_secs(79)).is_empty());
+        assert!(heartbeat.tick_at(&dht, due)[0].1.is_ok());
+        assert_eq!(heartbeat.next_due(), Some(due + Duration::from_secs(90)));
+    }
+}
@@ -5,6 +5,101 @@ use std::fmt;
 use agent_uri::{CapabilityPath, TrustRoot};
 use sha2::{Digest, Sha256};
 
+/// Hash function a [`DhtKey`] is derived with.
+///
+/// Keys derived with different algorithms live in different keyspaces:
+/// a registry must use the algorithm of the network it interoperates with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum KeyAlgorithm {
+    /// SHA-256, the default.
+    #[default]
+    Sha256,
+    /// BLAKE3 with its default 256-bit output (feature `blake3`).
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+impl KeyAlgorithm {
+    /// Returns the algorithm's lowercase name, as shown in a key's `Debug`
+    /// form.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            #[cfg(feature = "blake3")]
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    /// Hashes the concatenation of `parts`.
+    fn hash(self, parts: &[&[u8]]) -> [u8; 32] {
+        match self {
+            Self::Sha256 => {
+                let mut hasher = Sha256::new();
+                for part in parts {
+                    hasher.update(part);
+                }
+                hasher.finalize().into()
+            }
+            #[cfg(feature = "blake3")]
+            Self::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                for part in parts {
+                    hasher.update(part);
+                }
+                hasher.finalize().into()
+            }
+        }
+    }
+}
+
+impl fmt::Display for KeyAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Version of the input a [`DhtKey`] is derived from.
+///
+/// Versioned keys hash a domain-separation tag ahead of the trust root and
+/// path, so they can never collide with hashes another protocol computes
+/// over the same strings, and a future change to the derivation can get a
+/// new tag and live alongside keys of the old one. Keys derived with
+/// different versions live in different keyspaces, as with
+/// [`KeyAlgorithm`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum KeyVersion {
+    /// No tag: `H(trust_root || "/" || capability_path)`, the derivation
+    /// SPECIFICATION.md §6.1 defines and the default.
+    #[default]
+    Legacy,
+    /// `H("agent-dht-v1" || 0x00 || trust_root || "/" || capability_path)`,
+    /// for networks whose every node opts in.
+    V1,
+}
+
+impl KeyVersion {
+    /// Returns the domain-separation tag hashed ahead of the key input, or
+    /// `None` for [`Legacy`](Self::Legacy) keys. Tags end in a NUL byte,
+    /// which no trust root contains.
+    #[must_use]
+    pub const fn tag(self) -> Option<&'static [u8]> {
+        match self {
+            Self::Legacy => None,
+            Self::V1 => Some(b"agent-dht-v1\0"),
+        }
+    }
+}
+
+impl fmt::Display for KeyVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Legacy => "legacy",
+            Self::V1 => "v1",
+        })
+    }
+}
+
 /// DHT key derived from trust root and capability path.
 ///
 /// A 256-bit hash used as the key in a Kademlia-style DHT.
@@ -14,9 +109,15 @@ use sha2::{Digest, Sha256};
 /// # Key Derivation
 ///
 /// ```text
-/// key = SHA256(trust_root || "/" || capability_path)
+/// key = H(trust_root || "/" || capability_path)
 /// ```
 ///
+/// where `H` is SHA-256 by default, or another [`KeyAlgorithm`] chosen
+/// with [`derive_with`](Self::derive_with). Each key remembers its
+/// algorithm, and its `Debug` form names it. See [`KeyVersion`] and
+/// [`derive_versioned`](Self::derive_versioned) for keys domain-separated
+/// by a leading tag.
+///
 /// # Examples
 ///
 /// ```
@@ -32,17 +133,32 @@ use sha2::{Digest, Sha256};
 /// assert_eq!(key, key2);
 /// ```
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct DhtKey([u8; 32]);
+pub struct DhtKey([u8; 32], KeyAlgorithm);
 
 impl DhtKey {
     /// Creates a `DhtKey` from raw bytes.
     ///
+    /// The key is tagged as SHA-256; use
+    /// [`from_bytes_with`](Self::from_bytes_with) for other algorithms.
+    ///
     /// # Arguments
     ///
     /// * `bytes` - 32-byte array representing the key
     #[must_use]
     pub const fn from_bytes(bytes: [u8; 32]) -> Self {
-        Self(bytes)
+        Self(bytes, KeyAlgorithm::Sha256)
+    }
+
+    /// Creates a `DhtKey` from raw bytes produced by `algorithm`.
+    #[must_use]
+    pub const fn from_bytes_with(algorithm: KeyAlgorithm, bytes: [u8; 32]) -> Self {
+        Self(bytes, algorithm)
+    }
+
+    /// Returns the algorithm the key was derived with.
+    #[must_use]
+    pub const fn algorithm(&self) -> KeyAlgorithm {
+        self.1
     }
 
     /// Returns the key as a byte slice.
@@ -51,9 +167,24 @@ impl DhtKey {
         &self.0
     }
 
-    /// Derives a DHT key from trust root and capability path.
+    /// Returns the full key as 64 lowercase hex characters.
     ///
-    /// The key is computed as: `SHA256(trust_root || "/" || capability_path)`
+    /// Unlike the `Display` form, which is truncated for readability, this
+    /// identifies the key uniquely, e.g. as a storage key.
+    #[must_use]
+    pub fn to_hex(&self) -> String {
+        use std::fmt::Write;
+
+        self.0.iter().fold(String::with_capacity(64), |mut hex, b| {
+            let _ = write!(hex, "{b:02x}");
+            hex
+        })
+    }
+
+    /// Derives a SHA-256 DHT key from trust root and capability path.
+    ///
+    /// The key is computed as:
+    /// `SHA256(trust_root || "/" || capability_path)`
     ///
     /// # Arguments
     ///
@@ -65,15 +196,71 @@ impl DhtKey {
     /// A deterministic 256-bit key for DHT lookup.
     #[must_use]
     pub fn derive(trust_root: &TrustRoot, capability_path: &CapabilityPath) -> Self {
-        let mut hasher = Sha256::new();
-        hasher.update(trust_root.as_str().as_bytes());
-        hasher.update(b"/");
-        hasher.update(capability_path.as_str().as_bytes());
+        Self::derive_with(KeyAlgorithm::Sha256, trust_root, capability_path)
+    }
 
-        let result = hasher.finalize();
-        let mut bytes = [0u8; 32];
-        bytes.copy_from_slice(&result);
-        Self(bytes)
+    /// Derives a DHT key from trust root and capability path with
+    /// `algorithm`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use agent_uri::{TrustRoot, CapabilityPath};
+    /// use agent_uri_dht::{DhtKey, KeyAlgorithm};
+    ///
+    /// let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+    /// let path = CapabilityPath::parse("assistant/chat").unwrap();
+    /// let key = DhtKey::derive_with(KeyAlgorithm::Sha256, &trust_root, &path);
+    /// assert_eq!(key, DhtKey::derive(&trust_root, &path));
+    /// assert!(format!("{key:?}").starts_with("DhtKey(sha256:"));
+    /// ```
+    #[must_use]
+    pub fn derive_with(
+        algorithm: KeyAlgorithm,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Self {
+        Self::derive_versioned(algorithm, KeyVersion::default(), trust_root, capability_path)
+    }
+
+    /// Derives a DHT key from trust root and capability path with
+    /// `algorithm`, from the input of key derivation `version`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use agent_uri::{TrustRoot, CapabilityPath};
+    /// use agent_uri_dht::{DhtKey, KeyAlgorithm, KeyVersion};
+    ///
+    /// let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+    /// let path = CapabilityPath::parse("assistant/chat").unwrap();
+    /// let v1 = DhtKey::derive_versioned(KeyAlgorithm::Sha256, KeyVersion::V1, &trust_root, &path);
+    /// assert_ne!(v1, DhtKey::derive(&trust_root, &path));
+    /// ```
+    #[must_use]
+    pub fn derive_versioned(
+        algorithm: KeyAlgorithm,
+        version: KeyVersion,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Self {
+        Self::hash_path(algorithm, version, trust_root, capability_path.as_str())
+    }
+
+    /// Hashes the key input for `path` under `trust_root`.
+    fn hash_path(
+        algorithm: KeyAlgorithm,
+        version: KeyVersion,
+        trust_root: &TrustRoot,
+        path: &str,
+    ) -> Self {
+        let bytes = algorithm.hash(&[
+            version.tag().unwrap_or_default(),
+            trust_root.as_str().as_bytes(),
+            b"/",
+            path.as_bytes(),
+        ]);
+        Self(bytes, algorithm)
     }
 
     /// Derives a DHT key for a specific path depth (prefix query support).
@@ -115,15 +302,40 @@ impl DhtKey {
         trust_root: &TrustRoot,
         capability_path: &CapabilityPath,
         depth: usize,
+    ) -> Option<Self> {
+        Self::derive_at_depth_with(KeyAlgorithm::Sha256, trust_root, capability_path, depth)
+    }
+
+    /// Derives a DHT key for a path prefix with `algorithm`.
+    ///
+    /// Returns `None` if depth is 0 or exceeds the path depth.
+    #[must_use]
+    pub fn derive_at_depth_with(
+        algorithm: KeyAlgorithm,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        depth: usize,
+    ) -> Option<Self> {
+        let version = KeyVersion::default();
+        Self::derive_at_depth_versioned(algorithm, version, trust_root, capability_path, depth)
+    }
+
+    /// Derives a DHT key for a path prefix with `algorithm`, from the input
+    /// of key derivation `version`.
+    ///
+    /// Returns `None` if depth is 0 or exceeds the path depth.
+    #[must_use]
+    pub fn derive_at_depth_versioned(
+        algorithm: KeyAlgorithm,
+        version: KeyVersion,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        depth: usize,
     ) -> Option<Self> {
         if depth == 0 || depth > capability_path.depth() {
             return None;
         }
 
-        let mut hasher = Sha256::new();
-        hasher.update(trust_root.as_str().as_bytes());
-        hasher.update(b"/");
-
         // Build truncated path
         let segments = capability_path.segments();
         let prefix_path: String = segments[..depth]
@@ -132,12 +344,7 @@ impl DhtKey {
             .collect::<Vec<_>>()
             .join("/");
 
-        hasher.update(prefix_path.as_bytes());
-
-        let result = hasher.finalize();
-        let mut bytes = [0u8; 32];
-        bytes.copy_from_slice(&result);
-        Some(Self(bytes))
+        Some(Self::hash_path(algorithm, version, trust_root, &prefix_path))
     }
 
     /// Computes the XOR distance to another key.
@@ -152,14 +359,15 @@ impl DhtKey {
     ///
     /// # Returns
     ///
-    /// A `DhtKey` representing the XOR distance (interpretable as a 256-bit unsigned integer).
+    /// A `DhtKey` representing the XOR distance (interpretable as a 256-bit unsigned integer),
+    /// tagged with this key's algorithm.
     #[must_use]
     pub fn distance(&self, other: &Self) -> Self {
         let mut result = [0u8; 32];
         for (i, (a, b)) in self.0.iter().zip(other.0.iter()).enumerate() {
             result[i] = a ^ b;
         }
-        Self(result)
+        Self(result, self.1)
     }
 
     /// Returns the leading zero bits in the key.
@@ -182,7 +390,7 @@ impl DhtKey {
 
 impl fmt::Debug for DhtKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "DhtKey({self})")
+        write!(f, "DhtKey({}:{self})", self.1)
     }
 }
 
@@ -208,9 +416,12 @@ impl serde::Serialize for DhtKey {
     where
         S: serde::Serializer,
     {
-        // Serialize as hex string
-        let hex: String = self.0.iter().map(|b| format!("{b:02x}")).collect();
-        serializer.serialize_str(&hex)
+        // SHA-256 keys keep the bare hex form; others carry their algorithm
+        if self.1 == KeyAlgorithm::Sha256 {
+            serializer.serialize_str(&self.to_hex())
+        } else {
+            serializer.serialize_str(&format!("{}:{}", self.1, self.to_hex()))
+        }
     }
 }
 
@@ -220,8 +431,17 @@ impl<'de> serde::Deserialize<'de> for DhtKey {
     where
         D: serde::Deserializer<'de>,
     {
-        use serde::de::Deserialize;
-        let hex = String::deserialize(deserializer)?;
+        let text = String::deserialize(deserializer)?;
+        let (algorithm, hex) = match text.split_once(':') {
+            None => (KeyAlgorithm::Sha256, text.as_str()),
+            #[cfg(feature = "blake3")]
+            Some(("blake3", hex)) => (KeyAlgorithm::Blake3, hex),
+            Some((name, _)) => {
+                return Err(serde::de::Error::custom(format!(
+                    "unsupported DhtKey algorithm '{name}'"
+                )));
+            }
+        };
         if hex.len() != 64 {
             return Err(serde::de::Error::custom(
                 "DhtKey hex string must be 64 characters",
@@ -232,7 +452,7 @@ impl<'de> serde::Deserialize<'de> for DhtKey {
             let s = std::str::from_utf8(chunk).map_err(serde::de::Error::custom)?;
             bytes[i] = u8::from_str_radix(s, 16).map_err(serde::de::Error::custom)?;
         }
-        Ok(Self(bytes))
+        Ok(Self(bytes, algorithm))
     }
 }
 
@@ -373,7 +593,114 @@ mod tests {
     fn debug_includes_display() {
         let key = DhtKey::from_bytes([0xab; 32]);
         let debug = format!("{key:?}");
-        assert!(debug.starts_with("DhtKey("));
+        assert!(debug.starts_with("DhtKey(sha256:"));
         assert!(debug.contains("abababab"));
     }
+
+    #[test]
+    fn versions_form_separate_keyspaces() {
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        let path = CapabilityPath::parse("assistant/chat").unwrap();
+
+        let key = DhtKey::derive(&trust_root, &path);
+        let expected: [u8; 32] = Sha256::digest(b"anthropic.com/assistant/chat").into();
+        assert_eq!(key.as_bytes(), &expected);
+
+        let v1 = DhtKey::derive_versioned(KeyAlgorithm::Sha256, KeyVersion::V1, &trust_root, &path);
+        let expected: [u8; 32] =
+            Sha256::digest(b"agent-dht-v1\0anthropic.com/assistant/chat").into();
+        assert_eq!(v1.as_bytes(), &expected);
+        assert_ne!(v1, key);
+
+        let legacy =
+            DhtKey::derive_versioned(KeyAlgorithm::Sha256, KeyVersion::Legacy, &trust_root, &path);
+        assert_eq!(legacy, key);
+
+        let long = CapabilityPath::parse("assistant/chat/streaming").unwrap();
+        assert_eq!(
+            DhtKey::derive_at_depth_versioned(
+                KeyAlgorithm::Sha256,
+                KeyVersion::Legacy,
+                &trust_root,
+                &long,
+                2
+            ),
+            Some(legacy)
+        );
+        assert_eq!(KeyVersion::default(), KeyVersion::Legacy);
+        assert_eq!(KeyVersion::Legacy.to_string(), "legacy");
+    }
+
+    #[test]
+    fn default_keys_match_published_test_vectors() {
+        let vectors: serde_json::Value =
+            serde_json::from_str(include_str!("../../test-vectors.json")).unwrap();
+        let sha256_hex = |input: &str| DhtKey::from_bytes(Sha256::digest(input).into()).to_hex();
+        let key = |vector: &serde_json::Value| {
+            DhtKey::derive(
+                &TrustRoot::parse(vector["trust_root"].as_str().unwrap()).unwrap(),
+                &CapabilityPath::parse(vector["capability_path"].as_str().unwrap()).unwrap(),
+            )
+        };
+
+        let mut checked = 0;
+        for vector in vectors["dht_keys"].as_array().unwrap() {
+            let id = vector["id"].as_str().unwrap();
+            if let Some(expected) = vector["sha256_hex"].as_str() {
+                let input = vector["input_string"].as_str().unwrap();
+                assert_eq!(sha256_hex(input), expected, "{id}: published hash");
+                assert_eq!(key(vector).to_hex(), expected, "{id}");
+                checked += 1;
+            }
+            if let Some(cases) = vector["cases"].as_array() {
+                assert_ne!(key(&cases[0]), key(&cases[1]), "{id}");
+            }
+            if let Some(prefixes) = vector["prefix_keys"].as_array() {
+                let trust_root = TrustRoot::parse(vector["trust_root"].as_str().unwrap()).unwrap();
+                let path =
+                    CapabilityPath::parse(vector["capability_path"].as_str().unwrap()).unwrap();
+                for prefix in prefixes {
+                    let depth = usize::try_from(prefix["depth"].as_u64().unwrap()).unwrap();
+                    let input = prefix["input_string"].as_str().unwrap();
+                    let derived = DhtKey::derive_at_depth(&trust_root, &path, depth).unwrap();
+                    assert_eq!(derived.to_hex(), sha256_hex(input), "{id} depth {depth}");
+                }
+            }
+        }
+        assert_eq!(checked, 4);
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn blake3_keys_form_a_separate_keyspace() {
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        let path = CapabilityPath::parse("assistant/chat/streaming").unwrap();
+
+        let key = DhtKey::derive_with(KeyAlgorithm::Blake3, &trust_root, &path);
+        let expected = blake3::hash(b"anthropic.com/assistant/chat/streaming");
+        assert_eq!(key.as_bytes(), expected.as_bytes());
+        assert_eq!(key.algorithm(), KeyAlgorithm::Blake3);
+        assert_ne!(key, DhtKey::derive(&trust_root, &path));
+        assert_ne!(key, DhtKey::from_bytes(*expected.as_bytes()));
+        assert!(format!("{key:?}").starts_with("DhtKey(blake3:"));
+        assert_eq!(
+            DhtKey::derive_at_depth_with(KeyAlgorithm::Blake3, &trust_root, &path, 3),
+            Some(key)
+        );
+    }
+
+    #[cfg(all(feature = "serde", feature = "blake3"))]
+    #[test]
+    fn serialized_keys_keep_their_algorithm() {
+        let sha = DhtKey::from_bytes([0xab; 32]);
+        let json = serde_json::to_string(&sha).unwrap();
+        assert_eq!(json, format!("\"{}\"", sha.to_hex()));
+        assert_eq!(serde_json::from_str::<DhtKey>(&json).unwrap(), sha);
+
+        let blake = DhtKey::from_bytes_with(KeyAlgorithm::Blake3, [0xab; 32]);
+        let json = serde_json::to_string(&blake).unwrap();
+        assert!(json.starts_with("\"blake3:abab"));
+        assert_eq!(serde_json::from_str::<DhtKey>(&json).unwrap(), blake);
+        assert!(serde_json::from_str::<DhtKey>("\"md5:00\"").is_err());
+    }
 }
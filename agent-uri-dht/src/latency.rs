@@ -0,0 +1,666 @@
+//! Latency models for simulated DHT operations.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The kinds of DHT operation a [`LatencyModel`] can delay separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DhtOperation {
+    /// Storing a new registration
+    Register,
+    /// Replacing a registration's endpoints
+    Update,
+    /// Extending a registration's TTL
+    Renew,
+    /// Removing a registration
+    Deregister,
+    /// Lookups at a single capability path
+    ExactLookup,
+    /// Lookups under one trust root: prefix, pattern and trust-root scans
+    PrefixLookup,
+    /// Lookups across every trust root
+    GlobalLookup,
+    /// A simulated migration to a new endpoint
+    Migrate,
+}
+
+/// A distribution simulated delays are drawn from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LatencyDistribution {
+    /// Always the same delay
+    Constant(Duration),
+    /// Uniform between `min` and `max`, inclusive
+    Uniform {
+        /// Shortest delay
+        min: Duration,
+        /// Longest delay
+        max: Duration,
+    },
+    /// Normal with the given mean and standard deviation, truncated at zero
+    Normal {
+        /// Mean delay
+        mean: Duration,
+        /// Standard deviation
+        std_dev: Duration,
+    },
+    /// One of the given measured delays, each equally likely, or no delay if
+    /// empty
+    Empirical(Vec<Duration>),
+}
+
+/// Per-operation delays for the simulated DHT.
+///
+/// Each operation draws a fresh delay from its distribution, or from the
+/// default distribution if it has none of its own. Draws come from a seeded
+/// generator, so a single-threaded run is reproducible.
+///
+/// By default delays are slept through, as a real network would make the
+/// caller wait. In [virtual time](Self::with_virtual_time) nothing sleeps:
+/// delays are only recorded in [`DhtStats`](crate::DhtStats) and added to
+/// reported latencies, so large evaluations run at full speed. To also
+/// have the delays pass on the clock expiry is checked against, sleep on a
+/// [`VirtualClock`](crate::VirtualClock) instead; see
+/// [`SimulatedDht::with_clock`](crate::SimulatedDht::with_clock).
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use agent_uri_dht::{DhtOperation, LatencyDistribution, LatencyModel, SimulationConfig};
+///
+/// let latency = LatencyModel::new(LatencyDistribution::Normal {
+///     mean: Duration::from_millis(40),
+///     std_dev: Duration::from_millis(10),
+/// })
+/// .with_operation(DhtOperation::ExactLookup, LatencyDistribution::Uniform {
+///     min: Duration::from_millis(5),
+///     max: Duration::from_millis(15),
+/// })
+/// .with_virtual_time(true);
+///
+/// let config = SimulationConfig::new().with_latency(latency);
+/// ```
+#[derive(Debug)]
+pub struct LatencyModel {
+    default: Option<LatencyDistribution>,
+    operations: BTreeMap<DhtOperation, LatencyDistribution>,
+    virtual_time: bool,
+    seed: u64,
+    draws: AtomicU64,
+}
+
+impl Default for LatencyModel {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl Clone for LatencyModel {
+    fn clone(&self) -> Self {
+        Self {
+            default: self.default.clone(),
+            operations: self.operations.clone(),
+            virtual_time: self.virtual_time,
+            seed: self.seed,
+            draws: AtomicU64::new(self.draws.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl LatencyModel {
+    /// Creates a model that adds no delay.
+    #[must_use]
+    pub const fn none() -> Self {
+        Self {
+            default: None,
+            operations: BTreeMap::new(),
+            virtual_time: false,
+            seed: 0,
+            draws: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates a model that delays every operation by draws from
+    /// `distribution`.
+    #[must_use]
+    pub const fn new(distribution: LatencyDistribution) -> Self {
+        Self {
+            default: Some(distribution),
+            operations: BTreeMap::new(),
+            virtual_time: false,
+            seed: 0,
+            draws: AtomicU64::new(0),
+        }
+    }
+
+    /// Delays `operation` by draws from `distribution` instead of the
+    /// default.
+    #[must_use]
+    pub fn with_operation(
+        mut self,
+        operation: DhtOperation,
+        distribution: LatencyDistribution,
+    ) -> Self {
+        self.operations.insert(operation, distribution);
+        self
+    }
+
+    /// Records delays without sleeping through them.
+    #[must_use]
+    pub const fn with_virtual_time(mut self, virtual_time: bool) -> Self {
+        self.virtual_time = virtual_time;
+        self
+    }
+
+    /// Sets the seed delays are drawn with.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.draws = AtomicU64::new(0);
+        self
+    }
+
+    /// Returns true if delays are recorded rather than slept through.
+    #[must_use]
+    pub const fn is_virtual_time(&self) -> bool {
+        self.virtual_time
+    }
+
+    /// Returns the distribution `operation` is delayed by, if any.
+    #[must_use]
+    pub fn distribution(&self, operation: DhtOperation) -> Option<&LatencyDistribution> {
+        self.operations.get(&operation).or(self.default.as_ref())
+    }
+
+    /// Draws a delay for `operation`, or returns `None` if it is not
+    /// delayed.
+    #[must_use]
+    pub fn sample(&self, operation: DhtOperation) -> Option<Duration> {
+        let delay = match self.distribution(operation)? {
+            LatencyDistribution::Constant(delay) => *delay,
+            LatencyDistribution::Uniform { min, max } => {
+                let (low, high) = if min <= max { (min, max) } else { (max, min) };
+                *low + high.saturating_sub(*low).mul_f64(self.unit())
+            }
+            LatencyDistribution::Normal { mean, std_dev } => {
+                // Box-Muller transform; 1 - unit is in (0, 1], so ln is finite
+                let radius = (-2.0 * (1.0 - self.unit()).ln()).sqrt();
+                let z = radius * (std::f64::consts::TAU * self.unit()).cos();
+                Duration::from_secs_f64(
+                    z.mul_add(std_dev.as_secs_f64(), mean.as_secs_f64()).max(0.0),
+                )
+            }
+            LatencyDistribution::Empirical(samples) => {
+                if samples.is_empty() {
+                    Duration::ZERO
+                } else {
+                    let index = self.draw() % samples.len() as u64;
+                    samples[usize::try_from(index).unwrap_or(0)]
+                }
+            }
+        };
+        Some(delay)
+    }
+
+    /// Returns the next pseudo-random value (`SplitMix64`).
+    fn draw(&self) -> u64 {
+        let n = self.draws.fetch_add(1, Ordering::Relaxed);
+        let mut z = self
+            .seed
+            .wrapping_add(n.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniform value in [0, 1).
+    fn unit(&self) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let unit = (self.draw() >> 11) as f64 / (1u64 << 53) as f64;
+        unit
+    }
+}
+
+/// Simulated delays recorded for one kind of operation.
+///
+/// Besides the totals, delays are counted in a log-scale
+/// [histogram](LatencyHistogram), from which [`p50`](Self::p50),
+/// [`p95`](Self::p95) and [`p99`](Self::p99) are read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// Number of delays recorded
+    pub samples: u64,
+    /// Sum of all delays
+    pub total: Duration,
+    /// Shortest delay
+    pub min: Duration,
+    /// Longest delay
+    pub max: Duration,
+    /// Distribution of the delays
+    pub histogram: LatencyHistogram,
+}
+
+impl LatencyStats {
+    /// Returns the mean delay, or zero if none was recorded.
+    #[must_use]
+    pub fn mean(&self) -> Duration {
+        u32::try_from(self.samples)
+            .ok()
+            .filter(|&samples| samples > 0)
+            .map_or(Duration::ZERO, |samples| self.total / samples)
+    }
+
+    /// Returns the delay below which a `quantile` share of delays fall,
+    /// clamped to `0.0..=1.0`, or zero if none was recorded.
+    ///
+    /// Accurate to within an eighth of the true value, and exact at the
+    /// shortest and longest delays.
+    #[must_use]
+    pub fn quantile(&self, quantile: f64) -> Duration {
+        if quantile <= 0.0 {
+            self.min
+        } else if quantile >= 1.0 {
+            self.max
+        } else {
+            self.histogram.quantile(quantile).clamp(self.min, self.max)
+        }
+    }
+
+    /// Returns the median delay.
+    #[must_use]
+    pub fn p50(&self) -> Duration {
+        self.quantile(0.5)
+    }
+
+    /// Returns the 95th percentile delay.
+    #[must_use]
+    pub fn p95(&self) -> Duration {
+        self.quantile(0.95)
+    }
+
+    /// Returns the 99th percentile delay.
+    #[must_use]
+    pub fn p99(&self) -> Duration {
+        self.quantile(0.99)
+    }
+
+    /// Adds a delay to the record.
+    pub(crate) fn record(&mut self, delay: Duration) {
+        self.min = if self.samples == 0 { delay } else { self.min.min(delay) };
+        self.max = self.max.max(delay);
+        self.total += delay;
+        self.samples += 1;
+        self.histogram.record(delay);
+    }
+}
+
+/// Counts of delays in log-scale buckets.
+///
+/// Each power of two nanoseconds is split into eight equal buckets, so a
+/// bucket's width is at most an eighth of its lower bound, whatever the
+/// scale. Buckets are allocated up to the longest delay recorded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+}
+
+impl LatencyHistogram {
+    /// Buckets per power of two.
+    const SUB_BUCKETS: u64 = 8;
+
+    /// Counts one delay.
+    pub fn record(&mut self, delay: Duration) {
+        let index = Self::index(u64::try_from(delay.as_nanos()).unwrap_or(u64::MAX));
+        if self.counts.len() <= index {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+    }
+
+    /// Returns the number of delays counted.
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the bucket midpoint below which a `quantile` share of the
+    /// delays fall, clamped to `0.0..=1.0`, or zero if none was counted.
+    #[must_use]
+    pub fn quantile(&self, quantile: f64) -> Duration {
+        let count = self.count();
+        if count == 0 {
+            return Duration::ZERO;
+        }
+        let rank = rank(quantile, count);
+        let mut seen = 0;
+        for (index, &bucket) in self.counts.iter().enumerate() {
+            seen += bucket;
+            if seen >= rank {
+                let (low, high) = Self::bounds(index);
+                return Duration::from_nanos(low + (high - low) / 2);
+            }
+        }
+        Duration::ZERO
+    }
+
+    /// Returns the bucket holding `nanos`.
+    fn index(nanos: u64) -> usize {
+        let index = if nanos < Self::SUB_BUCKETS {
+            nanos
+        } else {
+            // 2^exponent <= nanos; the next three bits pick the sub-bucket
+            let exponent = u64::from(nanos.ilog2());
+            let sub = (nanos >> (exponent - 3)) & (Self::SUB_BUCKETS - 1);
+            (exponent - 2) * Self::SUB_BUCKETS + sub
+        };
+        usize::try_from(index).unwrap_or(usize::MAX)
+    }
+
+    /// Returns the inclusive range of nanoseconds in bucket `index`.
+    fn bounds(index: usize) -> (u64, u64) {
+        let index = index as u64;
+        if index < Self::SUB_BUCKETS {
+            return (index, index);
+        }
+        let shift = index / Self::SUB_BUCKETS - 1;
+        let low = (Self::SUB_BUCKETS + index % Self::SUB_BUCKETS) << shift;
+        (low, low + ((1 << shift) - 1))
+    }
+}
+
+/// The latency of one operation, as kept in a [`LatencyLog`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperationLatency {
+    /// The kind of operation
+    pub operation: DhtOperation,
+    /// Delay drawn from the latency model, whether slept through or not
+    pub modeled: Duration,
+    /// Time spent running the operation, not counting sleeping through the
+    /// modeled delay
+    pub actual: Duration,
+    /// Whether the operation succeeded
+    pub succeeded: bool,
+}
+
+impl OperationLatency {
+    /// Returns the latency a caller would see: the modeled delay plus the
+    /// time actually spent.
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.modeled + self.actual
+    }
+}
+
+/// The latencies of the most recent operations, in a ring buffer.
+///
+/// Unlike [`LatencyStats`], which summarizes modeled delays alone, the log
+/// keeps each operation's modeled and actual latency, so percentiles are
+/// exact and the samples can be exported for analysis. Once full, each new
+/// sample replaces the oldest.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{
+///     Dht, DhtOperation, Endpoint, LatencyDistribution, LatencyModel, Registration, SimulatedDht,
+///     SimulationConfig,
+/// };
+///
+/// let latency = LatencyModel::new(LatencyDistribution::Constant(Duration::from_millis(20)))
+///     .with_virtual_time(true);
+/// let config = SimulationConfig::new().with_latency(latency).with_latency_log(10_000);
+/// let dht = SimulatedDht::new(config);
+///
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// dht.register(Registration::new(uri.clone(), vec![Endpoint::https("agent.acme.com")]))?;
+/// dht.lookup_exact(uri.trust_root(), uri.capability_path())?;
+///
+/// let log = dht.latency_log().unwrap();
+/// assert_eq!(log.len(), 2);
+/// assert!(log.p99(DhtOperation::ExactLookup).unwrap() >= Duration::from_millis(20));
+/// let samples = log.export();
+/// assert_eq!(samples[0].operation, DhtOperation::Register);
+/// # Ok::<(), agent_uri_dht::DhtError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyLog {
+    samples: VecDeque<OperationLatency>,
+    capacity: usize,
+    recorded: u64,
+}
+
+impl LatencyLog {
+    /// Creates a log keeping the latest `capacity` samples, at least one.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            samples: VecDeque::with_capacity(capacity.min(4096)),
+            capacity,
+            recorded: 0,
+        }
+    }
+
+    /// Adds a sample, dropping the oldest if the log is full.
+    pub fn record(&mut self, sample: OperationLatency) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        self.recorded += 1;
+    }
+
+    /// Returns the number of samples the log keeps.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of samples kept.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns true if nothing has been recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Returns the number of samples recorded, including those since
+    /// dropped.
+    #[must_use]
+    pub const fn recorded(&self) -> u64 {
+        self.recorded
+    }
+
+    /// Returns the kept samples, oldest first.
+    pub fn samples(&self) -> impl Iterator<Item = &OperationLatency> {
+        self.samples.iter()
+    }
+
+    /// Returns the total latency below which a `quantile` share of the kept
+    /// `operation` samples fall, or None if there are none.
+    ///
+    /// `quantile` is clamped to `0.0..=1.0`; the result is a recorded
+    /// latency, by nearest rank.
+    #[must_use]
+    pub fn quantile(&self, operation: DhtOperation, quantile: f64) -> Option<Duration> {
+        let mut totals: Vec<Duration> = self
+            .samples
+            .iter()
+            .filter(|sample| sample.operation == operation)
+            .map(OperationLatency::total)
+            .collect();
+        if totals.is_empty() {
+            return None;
+        }
+        totals.sort_unstable();
+        let rank = rank(quantile, totals.len() as u64);
+        usize::try_from(rank - 1).ok().map(|index| totals[index])
+    }
+
+    /// Returns the median total latency of `operation`.
+    #[must_use]
+    pub fn p50(&self, operation: DhtOperation) -> Option<Duration> {
+        self.quantile(operation, 0.5)
+    }
+
+    /// Returns the 95th percentile total latency of `operation`.
+    #[must_use]
+    pub fn p95(&self, operation: DhtOperation) -> Option<Duration> {
+        self.quantile(operation, 0.95)
+    }
+
+    /// Returns the 99th percentile total latency of `operation`.
+    #[must_use]
+    pub fn p99(&self, operation: DhtOperation) -> Option<Duration> {
+        self.quantile(operation, 0.99)
+    }
+
+    /// Returns the kept samples, oldest first, for analysis elsewhere.
+    #[must_use]
+    pub fn export(&self) -> Vec<OperationLatency> {
+        self.samples.iter().copied().collect()
+    }
+
+    /// Discards every sample.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.recorded = 0;
+    }
+}
+
+/// Returns the 1-based rank of the `quantile` element among `count`.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn rank(quantile: f64, count: u64) -> u64 {
+    let quantile = if quantile.is_nan() { 0.0 } else { quantile.clamp(0.0, 1.0) };
+    ((quantile * count as f64).ceil() as u64).clamp(1, count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operations_fall_back_to_the_default_distribution() {
+        let model = LatencyModel::new(LatencyDistribution::Constant(Duration::from_millis(5)))
+            .with_operation(
+                DhtOperation::Register,
+                LatencyDistribution::Constant(Duration::from_millis(50)),
+            );
+        assert_eq!(model.sample(DhtOperation::Register), Some(Duration::from_millis(50)));
+        assert_eq!(model.sample(DhtOperation::ExactLookup), Some(Duration::from_millis(5)));
+        assert_eq!(LatencyModel::none().sample(DhtOperation::Register), None);
+    }
+
+    #[test]
+    fn samples_stay_within_their_distribution() {
+        let (min, max) = (Duration::from_millis(10), Duration::from_millis(20));
+        let uniform = LatencyModel::new(LatencyDistribution::Uniform { min, max });
+        let measured = [Duration::from_millis(3), Duration::from_millis(7)];
+        let empirical = LatencyModel::new(LatencyDistribution::Empirical(measured.to_vec()));
+        for _ in 0..1000 {
+            let delay = uniform.sample(DhtOperation::ExactLookup).unwrap();
+            assert!(delay >= min && delay <= max);
+            assert!(measured.contains(&empirical.sample(DhtOperation::ExactLookup).unwrap()));
+        }
+    }
+
+    #[test]
+    fn normal_samples_center_on_the_mean() {
+        let model = LatencyModel::new(LatencyDistribution::Normal {
+            mean: Duration::from_millis(100),
+            std_dev: Duration::from_millis(10),
+        })
+        .with_seed(42);
+        let mut stats = LatencyStats::default();
+        for _ in 0..2000 {
+            stats.record(model.sample(DhtOperation::GlobalLookup).unwrap());
+        }
+        let mean = stats.mean();
+        assert!(mean > Duration::from_millis(98) && mean < Duration::from_millis(102));
+        assert!(stats.min < Duration::from_millis(85));
+        assert!(stats.max > Duration::from_millis(115));
+    }
+
+    #[test]
+    fn histogram_buckets_tile_the_range() {
+        for nanos in (0..4096).chain([u64::MAX / 3, u64::MAX]) {
+            let (low, high) = LatencyHistogram::bounds(LatencyHistogram::index(nanos));
+            assert!(low <= nanos && nanos <= high, "{nanos} not in {low}..={high}");
+        }
+    }
+
+    #[test]
+    fn percentiles_track_the_distribution() {
+        let mut stats = LatencyStats::default();
+        assert_eq!(stats.p99(), Duration::ZERO);
+        for millis in 1..=100 {
+            stats.record(Duration::from_millis(millis));
+        }
+
+        let within = |actual: Duration, expected: u64| {
+            let expected = Duration::from_millis(expected);
+            actual.abs_diff(expected) <= expected / 8
+        };
+        assert!(within(stats.p50(), 50));
+        assert!(within(stats.p95(), 95));
+        assert!(within(stats.p99(), 99));
+        assert_eq!(stats.quantile(0.0), Duration::from_millis(1));
+        assert_eq!(stats.quantile(1.0), Duration::from_millis(100));
+        assert_eq!(stats.histogram.count(), 100);
+    }
+
+    #[test]
+    fn latency_log_keeps_the_latest_samples() {
+        let sample = |operation, millis| OperationLatency {
+            operation,
+            modeled: Duration::from_millis(millis),
+            actual: Duration::from_millis(1),
+            succeeded: true,
+        };
+        let mut log = LatencyLog::new(100);
+        assert_eq!(log.p50(DhtOperation::Register), None);
+        for millis in 1..=150 {
+            log.record(sample(DhtOperation::ExactLookup, millis));
+        }
+        log.record(sample(DhtOperation::Register, 7));
+
+        // Lookups 1..=51 were dropped to make room; totals add 1ms each
+        assert_eq!(log.len(), 100);
+        assert_eq!(log.recorded(), 151);
+        assert_eq!(log.samples().next().unwrap().modeled, Duration::from_millis(52));
+        assert_eq!(log.quantile(DhtOperation::ExactLookup, 0.0), Some(Duration::from_millis(53)));
+        assert_eq!(log.p50(DhtOperation::ExactLookup), Some(Duration::from_millis(102)));
+        assert_eq!(log.p99(DhtOperation::ExactLookup), Some(Duration::from_millis(151)));
+        assert_eq!(log.p95(DhtOperation::Register), Some(Duration::from_millis(8)));
+        assert_eq!(log.export().last(), Some(&sample(DhtOperation::Register, 7)));
+
+        log.clear();
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn same_seed_draws_same_delays() {
+        let distribution = LatencyDistribution::Uniform {
+            min: Duration::ZERO,
+            max: Duration::from_secs(1),
+        };
+        let a = LatencyModel::new(distribution.clone()).with_seed(7);
+        let b = LatencyModel::new(distribution).with_seed(7);
+        for _ in 0..10 {
+            assert_eq!(a.sample(DhtOperation::Update), b.sample(DhtOperation::Update));
+        }
+    }
+}
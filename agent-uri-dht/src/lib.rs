@@ -3,11 +3,81 @@
 //! This crate provides DHT (Distributed Hash Table) infrastructure for
 //! discovering agents by their capabilities. It includes:
 //!
-//! - **Key derivation**: [`DhtKey`] for Kademlia-style routing
-//! - **Registration records**: [`Registration`] with endpoints and attestations
-//! - **Trait interface**: [`Dht`] trait for abstracting DHT implementations
+//! - **Key derivation**: [`DhtKey`] for Kademlia-style routing, hashed with
+//!   SHA-256 or another [`KeyAlgorithm`] (BLAKE3 with feature `blake3`),
+//!   optionally domain-separated by a [`KeyVersion`] tag
+//! - **Keyspace analysis**: [`KeyspaceDistribution`] reports bucket
+//!   occupancy, hot keys and load inequality across key prefixes
+//! - **Registration records**: [`Registration`] with endpoints and attestations,
+//!   versioned by sequence number so [`Dht::update_registration`] rejects
+//!   stale and conflicting writes
+//! - **Trait interface**: [`Dht`] trait for abstracting DHT implementations,
+//!   and [`AsyncDht`] for network backends
+//! - **Composition**: [`Dht`] is object-safe and implemented for `Arc`,
+//!   `Box` and references; [`DynDht`] erases a backend so wrappers can be
+//!   stacked at run time
 //! - **In-memory simulation**: [`SimulatedDht`] for evaluation and testing
-//! - **Prefix matching**: [`PathTrie`] for efficient hierarchical discovery
+//! - **Latency models**: [`LatencyModel`] delays simulated operations by
+//!   draws from per-operation distributions, optionally in virtual time;
+//!   [`LatencyStats`] reports p50/p95/p99 per operation, and a
+//!   [`LatencyLog`] keeps each recent operation's modeled and actual latency
+//! - **Virtual time**: [`VirtualClock`] stands in for the system clock, so
+//!   simulated latency and expiry take no wall-clock time
+//! - **Fault injection**: [`FaultModel`] fails simulated operations, drops
+//!   requests and replies, and schedules [`Partition`]s
+//! - **Network simulation**: [`NetworkSimulation`] models many Kademlia nodes
+//!   and measures lookup hops and latency
+//! - **Churn**: [`ChurnSchedule`] drives joins, departures and crashes and
+//!   reports availability, lookup success and repair traffic per round
+//! - **Replication**: [`ReplicationStrategy`] and [`ReadStrategy`] control
+//!   how records reach and are read from their replicas, and
+//!   [`ReplicationReport`] measures durability and consistency; quorum
+//!   writes and read repair are measured with [`StalenessReport`]
+//! - **Gossip**: [`NetworkSimulation::gossip`] spreads a record epidemically
+//!   with a [`GossipConfig`] fanout, push rounds and anti-entropy, and
+//!   [`GossipReport`] measures convergence time and message cost
+//! - **libp2p addresses**: [`Endpoint::multiaddr`] for p2p-native agents,
+//!   validated with feature `multiaddr`
+//! - **Redis registry**: `RedisDht` (feature `redis`) for centralized deployments
+//! - **HTTP registry**: `RestDht` (feature `rest`) is a client for a registry
+//!   service's REST API, with retries and auth headers
+//! - **gRPC protocol**: `RegistryService` (feature `grpc`) serves any [`Dht`]
+//!   over the protocol in `proto/registry.proto`, with a generated client
+//! - **Local discovery**: `MdnsDht` (feature `mdns`) advertises and finds
+//!   agents on the local network over mDNS/DNS-SD (`_agent._tcp`)
+//! - **DNS publishing**: [`DnsBridge`] mirrors a trust root's registrations
+//!   into DNS-SD records through a [`DnsUpdater`] for DNS-only clients
+//! - **Lookup caching**: [`CachingDht`] answers repeated lookups from memory
+//!   for as long as the agents' `ttl` query hints allow, coalescing misses;
+//!   empty results are cached briefly and dropped when a match registers
+//! - **Tracing**: operations run in `tracing` spans carrying the trust root,
+//!   path depth, result count and outcome (feature `tracing`)
+//! - **Prefix matching**: [`PathTrie`] for efficient hierarchical discovery,
+//!   and [`ConcurrentPathTrie`] sharded by first segment for shared use
+//! - **Filtering**: [`LookupFilter`] narrows lookups by protocol, freshness
+//!   and metadata before results are returned
+//! - **Ranking**: [`AttestationRanking`] orders results verified agents
+//!   first, then by remaining token lifetime and capability specificity;
+//!   [`RankedDht`] applies it to every lookup
+//! - **Change notifications**: [`Dht::watch_prefix`] streams [`DhtEvent`]s
+//! - **Admission control**: [`RegistrationValidator`] to reject unattested
+//!   registrations, and [`AdmissionPolicy`] to require an attestation or a
+//!   [`ProofOfWork`] in open registries
+//! - **Health checking**: [`HealthChecker`] probes endpoints and drops dead
+//!   ones from lookups; [`Dht::health`] and [`Dht::stats`] monitor any
+//!   backend
+//! - **Endpoint selection**: [`EndpointSelector`] strategies for
+//!   [`Registration::select_endpoint`], honoring SRV-style priorities and
+//!   weights; [`Endpoint::parse`] reads endpoints from configuration strings
+//! - **Heartbeats**: [`HeartbeatScheduler`] for renewing registrations before expiry
+//! - **Expiry jitter**: [`ExpiryPolicy`] spreads registrations' expiries and
+//!   sets refresh-ahead windows checked with [`Registration::should_refresh`]
+//! - **Background expiry**: [`ExpirySweeper`] removes expired registrations
+//!   in bounded batches on its own thread
+//! - **Snapshots**: [`DhtSnapshot`] persists and restores a [`SimulatedDht`]'s
+//!   registrations with their expiry times
+//! - **Capacity limits**: [`SimulationConfig::max_registrations`] and an
+//!   [`EvictionPolicy`] bound how many registrations a node stores
 //!
 //! # Overview
 //!
@@ -51,11 +121,19 @@
 //! # Key Derivation
 //!
 //! DHT keys are derived deterministically from trust root and capability path
-//! using SHA-256. This enables:
+//! using SHA-256, behind a versioned domain tag so they cannot collide with
+//! other hashes of the same strings. This enables:
 //!
-//! - **Exact lookup**: Find agents at a specific capability path
-//! - **Prefix lookup**: Find agents at a path and all child paths
-//! - **Cross-trust-root lookup**: Find agents with a capability across all authorities
+//! - **Exact lookup**: Find agents at a specific capability path, or at many
+//!   paths in one batch with [`Dht::lookup_many`]
+//! - **Prefix lookup**: Find agents at a path and all child paths, a page at a
+//!   time with [`Dht::lookup_prefix_paged`] for popular prefixes
+//! - **Cross-trust-root lookup**: Find agents with a capability across all authorities,
+//!   optionally grouped per authority with [`Dht::lookup_global_grouped`]
+//! - **Wildcard lookup**: Find agents matching a [`PathPattern`] such as
+//!   `assistant/*/streaming`
+//! - **Trust root enumeration**: List every agent under an authority with
+//!   [`Dht::lookup_trust_root`]
 //!
 //! ```rust
 //! use agent_uri::{TrustRoot, CapabilityPath};
@@ -109,22 +187,102 @@
 #![deny(clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
+mod admission;
+mod async_dht;
+mod caching;
+mod churn;
+mod clock;
+mod concurrent_trie;
 mod config;
+mod distribution;
+mod dns_bridge;
+mod dyn_dht;
 mod endpoint;
 mod error;
+mod expiry;
+mod fault;
+mod filter;
+mod gossip;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod group;
+mod health;
+mod heartbeat;
 mod key;
+mod latency;
+#[cfg(feature = "mdns")]
+mod mdns_dht;
+mod network;
+mod page;
+mod pattern;
+mod ranking;
+#[cfg(feature = "redis")]
+mod redis_dht;
 mod registration;
+mod replication;
+#[cfg(feature = "rest")]
+mod rest_dht;
+mod selector;
+mod sharded;
 mod simulation;
+mod snapshot;
 mod stats;
+mod sweeper;
+mod telemetry;
 mod traits;
 mod trie;
+mod txt;
+mod validator;
+mod watch;
 
-pub use config::SimulationConfig;
+#[cfg(feature = "tokio")]
+pub use async_dht::TokioDht;
+pub use async_dht::AsyncDht;
+pub use caching::{CacheStats, CachingDht};
+pub use admission::{AdmissionPolicy, ProofOfWork};
+pub use churn::{ChurnEvent, ChurnReport, ChurnRound, ChurnSchedule};
+pub use clock::{Clock, SystemClock, VirtualClock};
+pub use concurrent_trie::ConcurrentPathTrie;
+pub use config::{EvictionPolicy, SimulationConfig};
+pub use distribution::{KeyLoad, KeyspaceDistribution};
+pub use dns_bridge::{DnsBridge, DnsRecord, DnsSyncReport, DnsUpdater};
+pub use dyn_dht::DynDht;
 pub use endpoint::Endpoint;
 pub use error::DhtError;
-pub use key::DhtKey;
+pub use expiry::ExpiryPolicy;
+pub use fault::{FaultModel, Partition};
+pub use filter::LookupFilter;
+pub use gossip::{GossipConfig, GossipReport};
+#[cfg(feature = "grpc")]
+pub use grpc::{RegistryService, proto};
+pub use group::TrustRootGroup;
+pub use health::{EndpointHealth, HealthChecker, HealthProbe, HealthStatus, HttpProbe, TcpProbe};
+pub use heartbeat::HeartbeatScheduler;
+pub use key::{DhtKey, KeyAlgorithm, KeyVersion};
+pub use latency::{
+    DhtOperation, LatencyDistribution, LatencyHistogram, LatencyLog, LatencyModel, LatencyStats,
+    OperationLatency,
+};
+#[cfg(feature = "mdns")]
+pub use mdns_dht::MdnsDht;
+pub use network::{LookupMetrics, NetworkConfig, NetworkLookup, NetworkSimulation, WriteOutcome};
+pub use page::{LookupCursor, LookupPage};
+pub use pattern::PathPattern;
+pub use ranking::{AttestationRanking, AttestationStrength, RankedDht};
+#[cfg(feature = "redis")]
+pub use redis_dht::RedisDht;
 pub use registration::Registration;
+pub use replication::{ReadStrategy, ReplicationReport, ReplicationStrategy, StalenessReport};
+#[cfg(feature = "rest")]
+pub use rest_dht::RestDht;
+pub use selector::{
+    EndpointSelector, FirstEndpoint, LatencyAware, RandomEndpoint, RoundRobin, Weighted,
+};
 pub use simulation::SimulatedDht;
-pub use stats::{DhtStats, MigrationResult};
+pub use snapshot::DhtSnapshot;
+pub use stats::{DhtHealth, DhtStats, MigrationResult, TtlStats};
+pub use sweeper::ExpirySweeper;
 pub use traits::Dht;
 pub use trie::PathTrie;
+pub use validator::RegistrationValidator;
+pub use watch::DhtEvent;
@@ -0,0 +1,482 @@
+//! Local network discovery over mDNS/DNS-SD.
+
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+use mdns_sd::{DaemonStatus, ServiceDaemon, ServiceEvent, ServiceInfo};
+
+use crate::txt::{self, instance_name};
+use crate::{
+    Dht, DhtError, DhtEvent, DhtStats, Endpoint, LookupCursor, LookupFilter, LookupPage,
+    PathPattern, Registration, SimulatedDht, SimulationConfig, TrustRootGroup,
+};
+
+/// A [`Dht`] that advertises and discovers agents on the local network
+/// over mDNS/DNS-SD (feature `mdns`).
+///
+/// For local development and edge deployments that should find agents on
+/// the same network without a DHT or registry server. Each registration is
+/// advertised as an instance of [`SERVICE_TYPE`](Self::SERVICE_TYPE), with
+/// the record in its TXT properties:
+///
+/// | Key | Contents |
+/// |-----|----------|
+/// | `uri` | Agent URI |
+/// | `ep0`, `ep1`, ... | Endpoint URIs, see [`Endpoint::to_uri`] |
+/// | `att0`, `att1`, ... | Attestation token, split to fit TXT strings |
+/// | `pow` | Proof-of-work nonce |
+/// | `seq` | Sequence number |
+/// | `signed`, `exp` | Signing and expiry times, in Unix milliseconds |
+/// | `md.{key}` | Metadata entries |
+///
+/// Endpoints keep only their protocol, address and path, and each TXT
+/// property must fit in 255 bytes.
+///
+/// Services resolved on the network, this node's own included, are kept in
+/// a [`SimulatedDht`] that answers lookups and change notifications, and
+/// are dropped when their advertisement is withdrawn. A discovered agent's
+/// new versions replace the cached one by sequence number, as with
+/// [`update_registration`](Dht::update_registration).
+///
+/// Only registrations made through this `MdnsDht` can be updated, renewed
+/// or deregistered; for other agents these fail with `DhtError::NotFound`.
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+/// use agent_uri_dht::{Dht, Endpoint, MdnsDht, Registration};
+///
+/// let dht = MdnsDht::new()?;
+///
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// dht.register(Registration::new(uri, vec![Endpoint::https("192.168.1.20:8443")]))?;
+///
+/// // Agents advertised by other hosts appear as they are resolved
+/// std::thread::sleep(std::time::Duration::from_secs(1));
+/// let found = dht.lookup_prefix(
+///     &TrustRoot::parse("acme.com").unwrap(),
+///     &CapabilityPath::parse("assistant").unwrap(),
+/// )?;
+/// # Ok::<(), agent_uri_dht::DhtError>(())
+/// ```
+pub struct MdnsDht {
+    daemon: ServiceDaemon,
+    /// Advertised and discovered registrations
+    cache: Arc<SimulatedDht>,
+    /// Registrations this node advertises, by agent URI
+    advertised: Mutex<HashMap<String, Registration>>,
+}
+
+impl MdnsDht {
+    /// DNS-SD service type agents are advertised under.
+    pub const SERVICE_TYPE: &str = "_agent._tcp.local.";
+
+    /// Starts an mDNS daemon and browses for agents on the local network.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::Backend` if the daemon cannot be started, e.g.
+    /// no network interface supports multicast.
+    pub fn new() -> Result<Self, DhtError> {
+        Self::with_config(SimulationConfig::default())
+    }
+
+    /// Like [`new`](Self::new), caching registrations in a [`SimulatedDht`]
+    /// configured with `config`, e.g. to bound how many it keeps.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::Backend` if the daemon cannot be started.
+    pub fn with_config(config: SimulationConfig) -> Result<Self, DhtError> {
+        let daemon = ServiceDaemon::new().map_err(backend)?;
+        let events = daemon.browse(Self::SERVICE_TYPE).map_err(backend)?;
+        let cache = Arc::new(SimulatedDht::new(config));
+
+        let listener = Arc::clone(&cache);
+        std::thread::Builder::new()
+            .name("agent-uri-dht-mdns".to_string())
+            .spawn(move || listen(&events, &listener))
+            .map_err(|e| DhtError::backend(e.to_string()))?;
+
+        Ok(Self {
+            daemon,
+            cache,
+            advertised: Mutex::default(),
+        })
+    }
+
+    /// Returns the registrations this node has discovered and advertises.
+    #[must_use]
+    pub fn cache(&self) -> &SimulatedDht {
+        &self.cache
+    }
+
+    /// Applies `update` to the advertised copy of an agent's registration
+    /// and `commit` to the cached one, then advertises the new copy.
+    fn readvertise(
+        &self,
+        agent_uri: &AgentUri,
+        update: impl FnOnce(&mut Registration),
+        commit: impl FnOnce(&SimulatedDht, &Registration) -> Result<(), DhtError>,
+    ) -> Result<(), DhtError> {
+        let mut advertised = self.advertised.lock().expect("lock poisoned");
+        let registration = advertised
+            .get_mut(agent_uri.as_str())
+            .ok_or_else(|| DhtError::not_found(agent_uri.as_str()))?;
+
+        let mut updated = registration.clone();
+        update(&mut updated);
+        let info = service_info(&updated)?;
+        commit(&self.cache, &updated)?;
+        self.daemon.register(info).map_err(backend)?;
+        *registration = updated;
+        Ok(())
+    }
+}
+
+impl Drop for MdnsDht {
+    fn drop(&mut self) {
+        // Also ends the listener, whose event channel closes
+        let _ = self.daemon.shutdown();
+    }
+}
+
+impl std::fmt::Debug for MdnsDht {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let advertised = self.advertised.lock().expect("lock poisoned").len();
+        f.debug_struct("MdnsDht")
+            .field("advertised", &advertised)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Dht for MdnsDht {
+    fn register(&self, registration: Registration) -> Result<(), DhtError> {
+        let info = service_info(&registration)?;
+        let mut advertised = self.advertised.lock().expect("lock poisoned");
+        self.cache.register(registration.clone())?;
+        if let Err(error) = self.daemon.register(info) {
+            let _ = self.cache.deregister(registration.agent_uri());
+            return Err(backend(error));
+        }
+        advertised.insert(registration.agent_uri().as_str().to_string(), registration);
+        Ok(())
+    }
+
+    fn update_endpoint(
+        &self,
+        agent_uri: &AgentUri,
+        new_endpoints: Vec<Endpoint>,
+    ) -> Result<(), DhtError> {
+        self.readvertise(
+            agent_uri,
+            |registration| registration.update_endpoints(new_endpoints),
+            |cache, registration| cache.update_registration(registration.clone()),
+        )
+    }
+
+    fn update_registration(&self, registration: Registration) -> Result<(), DhtError> {
+        let agent_uri = registration.agent_uri().clone();
+        self.readvertise(
+            &agent_uri,
+            |advertised| *advertised = registration,
+            |cache, registration| cache.update_registration(registration.clone()),
+        )
+    }
+
+    fn renew(&self, agent_uri: &AgentUri, ttl: Duration) -> Result<(), DhtError> {
+        self.readvertise(
+            agent_uri,
+            |registration| registration.refresh(ttl),
+            |cache, _| cache.renew(agent_uri, ttl),
+        )
+    }
+
+    fn deregister(&self, agent_uri: &AgentUri) -> Result<(), DhtError> {
+        let mut advertised = self.advertised.lock().expect("lock poisoned");
+        if advertised.remove(agent_uri.as_str()).is_none() {
+            return Err(DhtError::not_found(agent_uri.as_str()));
+        }
+        self.daemon
+            .unregister(&service_name(agent_uri))
+            .map_err(backend)?;
+        self.cache.deregister(agent_uri)
+    }
+
+    fn lookup_exact(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.cache.lookup_exact(trust_root, capability_path)
+    }
+
+    fn lookup_many(
+        &self,
+        queries: &[(TrustRoot, CapabilityPath)],
+    ) -> Vec<Result<Vec<Registration>, DhtError>> {
+        self.cache.lookup_many(queries)
+    }
+
+    fn lookup_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.cache.lookup_prefix(trust_root, capability_path)
+    }
+
+    fn lookup_prefix_paged(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        self.cache
+            .lookup_prefix_paged(trust_root, capability_path, limit, cursor)
+    }
+
+    fn lookup_global(
+        &self,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.cache.lookup_global(capability_path)
+    }
+
+    fn lookup_global_grouped(
+        &self,
+        capability_path: &CapabilityPath,
+        per_root_limit: Option<usize>,
+    ) -> Result<Vec<TrustRootGroup>, DhtError> {
+        self.cache.lookup_global_grouped(capability_path, per_root_limit)
+    }
+
+    fn lookup_pattern(
+        &self,
+        trust_root: &TrustRoot,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.cache.lookup_pattern(trust_root, pattern)
+    }
+
+    fn lookup_pattern_global(&self, pattern: &PathPattern) -> Result<Vec<Registration>, DhtError> {
+        self.cache.lookup_pattern_global(pattern)
+    }
+
+    fn lookup_trust_root(&self, trust_root: &TrustRoot) -> Result<Vec<Registration>, DhtError> {
+        self.cache.lookup_trust_root(trust_root)
+    }
+
+    fn lookup_trust_root_paged(
+        &self,
+        trust_root: &TrustRoot,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        self.cache.lookup_trust_root_paged(trust_root, limit, cursor)
+    }
+
+    fn watch_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Receiver<DhtEvent>, DhtError> {
+        self.cache.watch_prefix(trust_root, capability_path)
+    }
+
+    fn lookup_exact_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.cache
+            .lookup_exact_filtered(trust_root, capability_path, filter)
+    }
+
+    fn lookup_prefix_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.cache
+            .lookup_prefix_filtered(trust_root, capability_path, filter)
+    }
+
+    fn lookup_prefix_paged_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+        filter: &LookupFilter,
+    ) -> Result<LookupPage, DhtError> {
+        self.cache.lookup_prefix_paged_filtered(
+            trust_root,
+            capability_path,
+            limit,
+            cursor,
+            filter,
+        )
+    }
+
+    fn lookup_global_filtered(
+        &self,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.cache.lookup_global_filtered(capability_path, filter)
+    }
+
+    fn ping(&self) -> Result<(), DhtError> {
+        let status = self.daemon.status().map_err(backend)?;
+        match status.recv_timeout(Duration::from_secs(1)) {
+            Ok(DaemonStatus::Running) => Ok(()),
+            Ok(_) => Err(DhtError::backend("mDNS daemon has shut down")),
+            Err(e) => Err(DhtError::backend(format!("mDNS daemon did not answer: {e}"))),
+        }
+    }
+
+    fn stats(&self) -> Result<DhtStats, DhtError> {
+        Ok(SimulatedDht::stats(&self.cache))
+    }
+}
+
+/// Mirrors services resolved on the network into `cache` until the daemon
+/// shuts down.
+fn listen(events: &mdns_sd::Receiver<ServiceEvent>, cache: &SimulatedDht) {
+    // Agent URIs of resolved services, by service name
+    let mut resolved: HashMap<String, AgentUri> = HashMap::new();
+    while let Ok(event) = events.recv() {
+        match event {
+            ServiceEvent::ServiceResolved(info) => {
+                let Some(registration) = from_service_info(&info) else {
+                    continue;
+                };
+                resolved.insert(
+                    info.get_fullname().to_string(),
+                    registration.agent_uri().clone(),
+                );
+                if let Err(DhtError::AlreadyRegistered { .. }) =
+                    cache.register(registration.clone())
+                {
+                    // Stale and conflicting versions are dropped
+                    let _ = cache.update_registration(registration);
+                }
+            }
+            ServiceEvent::ServiceRemoved(_, fullname) => {
+                if let Some(agent_uri) = resolved.remove(&fullname) {
+                    let _ = cache.deregister(&agent_uri);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the full service name an agent is advertised under.
+fn service_name(agent_uri: &AgentUri) -> String {
+    format!("{}.{}", instance_name(agent_uri), MdnsDht::SERVICE_TYPE)
+}
+
+/// Builds the advertisement of `registration`.
+fn service_info(registration: &Registration) -> Result<ServiceInfo, DhtError> {
+    let properties = txt::encode(registration)?;
+    let instance = instance_name(registration.agent_uri());
+    let port = registration
+        .endpoints()
+        .iter()
+        .find_map(Endpoint::port)
+        .unwrap_or(0);
+    ServiceInfo::new(
+        MdnsDht::SERVICE_TYPE,
+        &instance,
+        &format!("{instance}.local."),
+        "",
+        port,
+        &properties[..],
+    )
+    .map(ServiceInfo::enable_addr_auto)
+    .map_err(backend)
+}
+
+/// Decodes an advertisement into a registration, or None if it does not
+/// carry an agent URI.
+fn from_service_info(info: &ServiceInfo) -> Option<Registration> {
+    txt::decode(info.get_properties().iter().map(|entry| (entry.key(), entry.val_str())))
+}
+
+#[allow(clippy::needless_pass_by_value)] // Used with `map_err`
+fn backend(error: mdns_sd::Error) -> DhtError {
+    DhtError::backend(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+
+    fn test_uri() -> AgentUri {
+        AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q")
+            .unwrap()
+    }
+
+    #[test]
+    fn advertisements_round_trip_registrations() {
+        let expires_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_900_000_000);
+        let registration = Registration::new(
+            test_uri(),
+            vec![
+                Endpoint::https("192.168.1.20:8443"),
+                Endpoint::https_with_path("agent.local", "/v1/agent"),
+                Endpoint::multiaddr("/ip4/192.168.1.20/tcp/4001"),
+            ],
+        )
+        .with_seq(7)
+        .with_expires_at(expires_at)
+        .with_attestation("v4.public.".to_string() + &"x".repeat(600))
+        .with_proof_of_work(42)
+        .with_metadata("tier", "edge");
+
+        let info = service_info(&registration).unwrap();
+        assert_eq!(info.get_port(), 8443);
+        assert_eq!(info.get_fullname(), service_name(&test_uri()));
+        let decoded = from_service_info(&info).unwrap();
+
+        assert_eq!(decoded.agent_uri(), &test_uri());
+        assert_eq!(decoded.endpoints(), registration.endpoints());
+        assert_eq!(decoded.attestation(), registration.attestation());
+        assert_eq!(decoded.proof_of_work(), Some(42));
+        assert_eq!(decoded.metadata(), registration.metadata());
+        assert_eq!(decoded.seq(), 7);
+        assert_eq!(decoded.expires_at(), expires_at);
+    }
+
+    #[test]
+    #[ignore = "requires multicast on a network interface"]
+    fn discovers_its_own_advertisements() {
+        let dht = MdnsDht::new().unwrap();
+        assert!(dht.health().is_healthy());
+        let uri = test_uri();
+        let trust_root = TrustRoot::parse("acme.com").unwrap();
+        let path = CapabilityPath::parse("assistant/chat").unwrap();
+
+        dht.register(Registration::new(uri.clone(), vec![Endpoint::https("127.0.0.1:8443")]))
+            .unwrap();
+        dht.update_endpoint(&uri, vec![Endpoint::https("127.0.0.1:9443")])
+            .unwrap();
+        let found = dht.lookup_exact(&trust_root, &path).unwrap();
+        assert_eq!(found[0].endpoints(), &[Endpoint::https("127.0.0.1:9443")]);
+
+        dht.deregister(&uri).unwrap();
+        assert!(dht.lookup_exact(&trust_root, &path).unwrap().is_empty());
+        assert!(dht.deregister(&uri).unwrap_err().is_not_found());
+    }
+}
@@ -0,0 +1,1107 @@
+//! Multi-node Kademlia simulation for routing experiments.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::time::Duration;
+
+use agent_uri::{CapabilityPath, TrustRoot};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    DhtError, DhtKey, KeyAlgorithm, KeyVersion, ReadStrategy, Registration, ReplicationStrategy,
+};
+
+/// Configuration for a [`NetworkSimulation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkConfig {
+    /// Number of nodes in the network.
+    ///
+    /// Default: 100
+    pub nodes: usize,
+
+    /// Kademlia bucket size: each routing-table bucket holds up to `k`
+    /// contacts, and lookups converge on the `k` closest nodes they find.
+    ///
+    /// Default: 20
+    pub k: usize,
+
+    /// Replication factor: each record is stored at this many of the nodes
+    /// closest to its key. None means `k`.
+    ///
+    /// Default: None
+    pub replication: Option<usize>,
+
+    /// How writes reach a record's replicas.
+    ///
+    /// Default: [`ReplicationStrategy::Eager`]
+    pub replication_strategy: ReplicationStrategy,
+
+    /// How many replicas a lookup reads before answering.
+    ///
+    /// Default: [`ReadStrategy::First`]
+    pub read_strategy: ReadStrategy,
+
+    /// Whether [`read`](NetworkSimulation::read) writes the newest version
+    /// it saw back to the stale replicas it read.
+    ///
+    /// Default: false
+    pub read_repair: bool,
+
+    /// Lookup parallelism: nodes queried per round.
+    ///
+    /// Default: 3
+    pub alpha: usize,
+
+    /// Simulated round-trip time of one lookup round.
+    ///
+    /// Each node's own round-trip time, which decides how soon it
+    /// acknowledges a write, is fixed between half and twice this.
+    ///
+    /// Default: 10 ms
+    pub hop_latency: Duration,
+
+    /// Seed from which node IDs are derived, so runs are reproducible.
+    ///
+    /// Default: 0
+    pub seed: u64,
+
+    /// Hash function DHT keys are derived with.
+    ///
+    /// Node IDs are tagged with the same algorithm.
+    /// Default: [`KeyAlgorithm::Sha256`]
+    pub key_algorithm: KeyAlgorithm,
+
+    /// Key derivation input DHT keys are derived from.
+    ///
+    /// Default: [`KeyVersion::Legacy`]
+    pub key_version: KeyVersion,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            nodes: 100,
+            k: 20,
+            replication: None,
+            replication_strategy: ReplicationStrategy::Eager,
+            read_strategy: ReadStrategy::First,
+            read_repair: false,
+            alpha: 3,
+            hop_latency: Duration::from_millis(10),
+            seed: 0,
+            key_algorithm: KeyAlgorithm::Sha256,
+            key_version: KeyVersion::Legacy,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Creates a new configuration with defaults.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of nodes.
+    #[must_use]
+    pub const fn with_nodes(mut self, nodes: usize) -> Self {
+        self.nodes = nodes;
+        self
+    }
+
+    /// Sets the bucket size and replication factor.
+    #[must_use]
+    pub const fn with_k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    /// Sets the replication factor, independently of `k`.
+    #[must_use]
+    pub const fn with_replication(mut self, replication: usize) -> Self {
+        self.replication = Some(replication);
+        self
+    }
+
+    /// Sets how writes reach a record's replicas.
+    #[must_use]
+    pub const fn with_replication_strategy(mut self, strategy: ReplicationStrategy) -> Self {
+        self.replication_strategy = strategy;
+        self
+    }
+
+    /// Sets how many replicas a lookup reads.
+    #[must_use]
+    pub const fn with_read_strategy(mut self, strategy: ReadStrategy) -> Self {
+        self.read_strategy = strategy;
+        self
+    }
+
+    /// Enables or disables read repair.
+    #[must_use]
+    pub const fn with_read_repair(mut self, read_repair: bool) -> Self {
+        self.read_repair = read_repair;
+        self
+    }
+
+    /// Returns the number of replicas each record is stored at.
+    #[must_use]
+    pub fn replication_factor(&self) -> usize {
+        self.replication.unwrap_or(self.k)
+    }
+
+    /// Sets the lookup parallelism.
+    #[must_use]
+    pub const fn with_alpha(mut self, alpha: usize) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Sets the simulated latency of one lookup round.
+    #[must_use]
+    pub const fn with_hop_latency(mut self, latency: Duration) -> Self {
+        self.hop_latency = latency;
+        self
+    }
+
+    /// Sets the seed node IDs are derived from.
+    #[must_use]
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the hash function DHT keys are derived with.
+    #[must_use]
+    pub const fn with_key_algorithm(mut self, algorithm: KeyAlgorithm) -> Self {
+        self.key_algorithm = algorithm;
+        self
+    }
+
+    /// Sets the key derivation input DHT keys are derived from.
+    #[must_use]
+    pub const fn with_key_version(mut self, version: KeyVersion) -> Self {
+        self.key_version = version;
+        self
+    }
+}
+
+/// Routing cost of one iterative lookup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LookupMetrics {
+    /// Rounds of queries until the lookup converged.
+    pub hops: usize,
+    /// Nodes queried in total.
+    pub messages: usize,
+    /// Simulated time taken: `hops` times the per-round latency.
+    pub latency: Duration,
+}
+
+/// What a [`NetworkSimulation`] write reached before it was acknowledged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOutcome {
+    /// Routing cost of finding the replicas
+    pub metrics: LookupMetrics,
+    /// Replicas the write was addressed to
+    pub replicas: usize,
+    /// Replicas that stored the write before it was acknowledged
+    pub acks: usize,
+    /// Time until the write was acknowledged: routing, then the round trip
+    /// of the slowest acknowledging replica
+    pub latency: Duration,
+}
+
+/// Registrations found by a [`NetworkSimulation`] lookup, with its cost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkLookup {
+    /// Live registrations stored under the key, the newest version of each
+    /// among the replicas read
+    pub registrations: Vec<Registration>,
+    /// First node that returned records, or `None` if no node had the key
+    pub found_at: Option<usize>,
+    /// Number of replicas that returned records
+    pub replicas_read: usize,
+    /// True if records were found but some agent registered under the key
+    /// is missing from them or was returned at an older version than its
+    /// latest write
+    pub stale: bool,
+    /// For a stale result, how long ago the oldest write it missed was
+    /// acknowledged; zero otherwise
+    pub staleness: Duration,
+    /// Replicas brought up to date by read repair
+    pub repaired: usize,
+    /// Routing cost of the lookup
+    pub metrics: LookupMetrics,
+}
+
+/// One simulated Kademlia node.
+#[derive(Debug, Clone)]
+struct Node {
+    id: DhtKey,
+    /// False once the node has left or failed
+    online: bool,
+    /// Time this node takes to answer a request
+    rtt: Duration,
+    /// Known contacts, at most `k` per distance bucket
+    contacts: Vec<usize>,
+    records: HashMap<DhtKey, Vec<Stored>>,
+}
+
+/// The latest write of one agent's record.
+#[derive(Debug, Clone, Copy)]
+struct Written {
+    version: u64,
+    /// Simulated time the write was acknowledged
+    at: Duration,
+}
+
+/// A write still travelling to a replica.
+#[derive(Debug, Clone)]
+struct Delivery {
+    /// Simulated time the replica stores it
+    at: Duration,
+    node: usize,
+    key: DhtKey,
+    stored: Stored,
+}
+
+/// A registration as one node stores it.
+#[derive(Debug, Clone)]
+pub(crate) struct Stored {
+    /// Position of the write in the network's history; higher is newer
+    pub(crate) version: u64,
+    pub(crate) registration: Registration,
+}
+
+/// A simulated network of Kademlia nodes.
+///
+/// Unlike [`SimulatedDht`](crate::SimulatedDht), which keeps every record
+/// in one map, this models what a distributed deployment does: every node
+/// has an ID in the same 256-bit space as [`DhtKey`]s and a routing table
+/// of up to `k` contacts per XOR-distance bucket, records are replicated to
+/// the nodes closest to their key, and lookups walk the network
+/// iteratively, `alpha` queries per round. Each operation reports its
+/// [`LookupMetrics`], so routing behavior can be measured as the network
+/// grows.
+///
+/// Every write is versioned, so lookups can tell when a replica they read
+/// lags behind; see [`ReplicationStrategy`] and [`ReadStrategy`].
+///
+/// Time is simulated: nothing sleeps.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{Endpoint, NetworkConfig, NetworkSimulation, Registration};
+///
+/// let mut network = NetworkSimulation::new(NetworkConfig::new().with_nodes(200).with_k(8));
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// network
+///     .register(0, Registration::new(uri.clone(), vec![Endpoint::https("agent.acme.com")]))
+///     .unwrap();
+///
+/// let lookup = network.lookup_exact(150, uri.trust_root(), uri.capability_path());
+/// assert_eq!(lookup.registrations.len(), 1);
+/// assert!(lookup.metrics.hops <= 8);
+/// ```
+#[derive(Debug, Clone)]
+pub struct NetworkSimulation {
+    config: NetworkConfig,
+    nodes: Vec<Node>,
+    /// Keys registered so far, for availability checks
+    published: BTreeSet<DhtKey>,
+    /// Writes made so far; the version of the latest one
+    writes: u64,
+    /// Latest write for each agent URI, by key
+    latest: HashMap<DhtKey, HashMap<String, Written>>,
+    /// Simulated time elapsed; moved only by `advance`
+    now: Duration,
+    /// Quorum writes not yet stored at every replica
+    pending: Vec<Delivery>,
+}
+
+impl NetworkSimulation {
+    /// Builds a network of `config.nodes` nodes (at least one) with fully
+    /// populated routing tables.
+    #[must_use]
+    pub fn new(config: NetworkConfig) -> Self {
+        let config = NetworkConfig {
+            nodes: config.nodes.max(1),
+            k: config.k.max(1),
+            replication: config.replication.map(|replication| replication.max(1)),
+            alpha: config.alpha.max(1),
+            ..config
+        };
+        let ids: Vec<DhtKey> = (0..config.nodes)
+            .map(|index| node_id(&config, index))
+            .collect();
+
+        let nodes = ids
+            .iter()
+            .enumerate()
+            .map(|(index, id)| {
+                // Each bucket keeps the first k contacts it learns of
+                let mut bucket_sizes = [0usize; 256];
+                let mut contacts = Vec::new();
+                for (other, other_id) in ids.iter().enumerate() {
+                    if other == index {
+                        continue;
+                    }
+                    let bucket = bucket(id, other_id);
+                    if bucket_sizes[bucket] < config.k {
+                        bucket_sizes[bucket] += 1;
+                        contacts.push(other);
+                    }
+                }
+                Node {
+                    id: *id,
+                    online: true,
+                    rtt: node_rtt(config.hop_latency, id),
+                    contacts,
+                    records: HashMap::new(),
+                }
+            })
+            .collect();
+
+        Self {
+            config,
+            nodes,
+            published: BTreeSet::new(),
+            writes: 0,
+            latest: HashMap::new(),
+            now: Duration::ZERO,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the configuration, with zero counts raised to one.
+    #[must_use]
+    pub const fn config(&self) -> &NetworkConfig {
+        &self.config
+    }
+
+    /// Returns the number of nodes ever in the network, including those
+    /// that have left or failed.
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the number of nodes still in the network.
+    #[must_use]
+    pub fn online_count(&self) -> usize {
+        self.nodes.iter().filter(|node| node.online).count()
+    }
+
+    /// Returns true if node `index` exists and has not left or failed.
+    #[must_use]
+    pub fn is_online(&self, index: usize) -> bool {
+        self.nodes.get(index).is_some_and(|node| node.online)
+    }
+
+    /// Returns the ID of node `index`, or `None` if there is no such node.
+    #[must_use]
+    pub fn node_id(&self, index: usize) -> Option<DhtKey> {
+        self.nodes.get(index).map(|node| node.id)
+    }
+
+    /// Returns the `count` online nodes closest to `key` by XOR distance,
+    /// closest first, computed with global knowledge of the network.
+    ///
+    /// Useful as ground truth for what an iterative lookup should find.
+    #[must_use]
+    pub fn closest_nodes(&self, key: &DhtKey, count: usize) -> Vec<usize> {
+        let mut all: Vec<usize> = (0..self.nodes.len()).filter(|&i| self.is_online(i)).collect();
+        self.sort_by_distance(&mut all, key);
+        all.truncate(count);
+        all
+    }
+
+    /// Returns true if some online node holds live records under `key`.
+    #[must_use]
+    pub fn is_available(&self, key: &DhtKey) -> bool {
+        (0..self.nodes.len()).any(|index| self.live(index, key).next().is_some())
+    }
+
+    /// Returns the number of online nodes holding the latest version of
+    /// every agent registered under `key`.
+    #[must_use]
+    pub fn up_to_date_replicas(&self, key: &DhtKey) -> usize {
+        let Some(latest) = self.latest.get(key) else {
+            return 0;
+        };
+        (0..self.nodes.len())
+            .filter(|&index| {
+                let held: Vec<&Stored> = self.live(index, key).collect();
+                latest.iter().all(|(uri, written)| {
+                    held.iter().any(|s| {
+                        s.version == written.version && s.registration.agent_uri().as_str() == uri
+                    })
+                })
+            })
+            .count()
+    }
+
+    /// Returns the keys registered so far, whether or not still available.
+    #[must_use]
+    pub const fn published(&self) -> &BTreeSet<DhtKey> {
+        &self.published
+    }
+
+    /// Returns the nodes currently storing records under `key`.
+    #[must_use]
+    pub fn replicas(&self, key: &DhtKey) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|&index| self.nodes[index].records.contains_key(key))
+            .collect()
+    }
+
+    /// Registers an agent from node `origin`, storing the record at the
+    /// closest nodes the origin's lookup finds, as the replication factor
+    /// and [`ReplicationStrategy`] direct.
+    ///
+    /// Registering an agent again is an update: the new version replaces
+    /// the old one wherever it is written. Under
+    /// [`ReplicationStrategy::Quorum`] the replicas that did not acknowledge
+    /// store the write as their round trips elapse; see
+    /// [`advance`](Self::advance).
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::NoEndpoints` if the registration has no
+    /// endpoints, or `DhtError::InvalidEndpoint` if one is malformed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `origin` is not an online node.
+    pub fn register(
+        &mut self,
+        origin: usize,
+        registration: Registration,
+    ) -> Result<WriteOutcome, DhtError> {
+        let (key, stored) = self.version(registration)?;
+        let replication = self.config.replication_factor();
+        let (mut targets, _, metrics) = self.iterate(origin, &key, replication, 0);
+
+        let acks = match self.config.replication_strategy {
+            ReplicationStrategy::Eager => targets.len(),
+            ReplicationStrategy::Lazy(acks) => acks.max(1).min(targets.len()),
+            ReplicationStrategy::Quorum(acks) => {
+                // The fastest replicas acknowledge first
+                targets.sort_by_key(|&index| self.nodes[index].rtt);
+                acks.max(1).min(targets.len())
+            }
+        };
+        let ack_rtt = targets[..acks]
+            .iter()
+            .map(|&index| self.nodes[index].rtt)
+            .max()
+            .unwrap_or_default();
+
+        for &index in &targets[..acks] {
+            self.store(index, key, stored.clone());
+        }
+        if matches!(self.config.replication_strategy, ReplicationStrategy::Quorum(_)) {
+            for &index in &targets[acks..] {
+                self.pending.push(Delivery {
+                    at: self.now + self.nodes[index].rtt.saturating_sub(ack_rtt),
+                    node: index,
+                    key,
+                    stored: stored.clone(),
+                });
+            }
+        }
+
+        Ok(WriteOutcome {
+            metrics,
+            replicas: targets.len(),
+            acks,
+            latency: metrics.latency + ack_rtt,
+        })
+    }
+
+    /// Validates `registration` and records it as the newest write of its
+    /// agent, returning its key and the versioned record to store.
+    pub(crate) fn version(
+        &mut self,
+        registration: Registration,
+    ) -> Result<(DhtKey, Stored), DhtError> {
+        if registration.endpoints().is_empty() {
+            return Err(DhtError::NoEndpoints);
+        }
+        for endpoint in registration.endpoints() {
+            endpoint.validate()?;
+        }
+
+        let agent_uri = registration.agent_uri();
+        let key = DhtKey::derive_versioned(
+            self.config.key_algorithm,
+            self.config.key_version,
+            agent_uri.trust_root(),
+            agent_uri.capability_path(),
+        );
+        self.writes += 1;
+        self.published.insert(key);
+        let written = Written {
+            version: self.writes,
+            at: self.now,
+        };
+        self.latest
+            .entry(key)
+            .or_default()
+            .insert(agent_uri.as_str().to_string(), written);
+        let stored = Stored {
+            version: self.writes,
+            registration,
+        };
+        Ok((key, stored))
+    }
+
+    /// Returns the simulated time elapsed.
+    #[must_use]
+    pub const fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Returns the number of quorum writes still travelling to replicas.
+    #[must_use]
+    pub fn pending_writes(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Moves simulated time forward, letting replicas that did not
+    /// acknowledge a quorum write store it once their round trip has
+    /// elapsed.
+    ///
+    /// Returns the number of records stored. Writes to replicas that went
+    /// offline meanwhile are dropped.
+    pub fn advance(&mut self, by: Duration) -> usize {
+        self.now += by;
+        let now = self.now;
+        let (mut due, pending): (Vec<Delivery>, Vec<Delivery>) =
+            std::mem::take(&mut self.pending)
+                .into_iter()
+                .partition(|delivery| delivery.at <= now);
+        self.pending = pending;
+
+        due.sort_by_key(|delivery| delivery.at);
+        let mut stored = 0;
+        for delivery in due {
+            if self.nodes[delivery.node].online
+                && self.store(delivery.node, delivery.key, delivery.stored)
+            {
+                stored += 1;
+            }
+        }
+        stored
+    }
+
+    /// Stores `stored` at node `index` unless it already holds that
+    /// version of the agent's record or a newer one.
+    ///
+    /// Returns true if the record was written.
+    pub(crate) fn store(&mut self, index: usize, key: DhtKey, stored: Stored) -> bool {
+        let records = self.nodes[index].records.entry(key).or_default();
+        match records.iter_mut().find(|s| s.registration == stored.registration) {
+            Some(existing) if existing.version >= stored.version => false,
+            Some(existing) => {
+                *existing = stored;
+                true
+            }
+            None => {
+                records.push(stored);
+                true
+            }
+        }
+    }
+
+    /// Stores each of `records` at the closest online nodes that lack it or
+    /// hold an older version, returning the number of records copied.
+    fn replicate(&mut self, key: DhtKey, records: &[Stored]) -> usize {
+        let mut copied = 0;
+        for index in self.closest_nodes(&key, self.config.replication_factor()) {
+            for stored in records {
+                if self.store(index, key, stored.clone()) {
+                    copied += 1;
+                }
+            }
+        }
+        copied
+    }
+
+    /// Returns the contacts in node `index`'s routing table.
+    pub(crate) fn contacts(&self, index: usize) -> &[usize] {
+        &self.nodes[index].contacts
+    }
+
+    /// Adds a new node to the network, returning its index.
+    ///
+    /// The newcomer learns of every online node, and every online node with
+    /// room in the matching bucket, or a departed contact there to evict,
+    /// learns of the newcomer. It holds no records until the next
+    /// [`repair`](Self::repair).
+    pub fn join(&mut self) -> usize {
+        let index = self.nodes.len();
+        let id = node_id(&self.config, index);
+        self.nodes.push(Node {
+            id,
+            online: true,
+            rtt: node_rtt(self.config.hop_latency, &id),
+            contacts: Vec::new(),
+            records: HashMap::new(),
+        });
+        for other in 0..index {
+            if self.nodes[other].online {
+                self.add_contact(index, other);
+                self.add_contact(other, index);
+            }
+        }
+        index
+    }
+
+    /// Removes node `index` gracefully: before going offline it hands its
+    /// live records to the closest remaining nodes.
+    ///
+    /// Returns the number of records copied, or 0 if the node was already
+    /// offline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not a node index.
+    pub fn leave(&mut self, index: usize) -> usize {
+        if !self.nodes[index].online {
+            return 0;
+        }
+        self.nodes[index].online = false;
+        let records = std::mem::take(&mut self.nodes[index].records);
+        records
+            .into_iter()
+            .map(|(key, mut stored)| {
+                stored.retain(|s| !s.registration.is_expired());
+                self.replicate(key, &stored)
+            })
+            .sum()
+    }
+
+    /// Crashes node `index`: it goes offline and its records are lost.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not a node index.
+    pub fn fail(&mut self, index: usize) {
+        self.nodes[index].online = false;
+        self.nodes[index].records.clear();
+    }
+
+    /// Republishes the newest surviving version of every record to the
+    /// online nodes now closest to its key, as periodic Kademlia
+    /// republishing does. This also completes lazy writes.
+    ///
+    /// Returns the number of records copied. Keys whose every replica was
+    /// lost stay lost.
+    pub fn repair(&mut self) -> usize {
+        let keys: Vec<DhtKey> = self.published.iter().copied().collect();
+        let mut copied = 0;
+        for key in keys {
+            let survivors = self.newest(0..self.nodes.len(), &key);
+            copied += self.replicate(key, &survivors);
+        }
+        copied
+    }
+
+    /// Looks up the agents at an exact capability path from node `origin`,
+    /// reading replicas as the configured [`ReadStrategy`] directs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `origin` is not an online node.
+    #[must_use]
+    pub fn lookup_exact(
+        &self,
+        origin: usize,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> NetworkLookup {
+        let (algorithm, version) = (self.config.key_algorithm, self.config.key_version);
+        let key = DhtKey::derive_versioned(algorithm, version, trust_root, capability_path);
+        self.lookup_key(origin, &key)
+    }
+
+    /// Looks up the records stored under `key` from node `origin`, reading
+    /// replicas as the configured [`ReadStrategy`] directs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `origin` is not an online node.
+    #[must_use]
+    pub fn lookup_key(&self, origin: usize, key: &DhtKey) -> NetworkLookup {
+        self.lookup_key_with(origin, key, self.config.read_strategy)
+    }
+
+    /// Looks up the records stored under `key` from node `origin` with the
+    /// given read strategy.
+    ///
+    /// The lookup stops once enough replicas have answered, or when it
+    /// converges with fewer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `origin` is not an online node.
+    #[must_use]
+    pub fn lookup_key_with(
+        &self,
+        origin: usize,
+        key: &DhtKey,
+        strategy: ReadStrategy,
+    ) -> NetworkLookup {
+        self.read_replicas(origin, key, strategy).0
+    }
+
+    /// Looks up the records stored under `key` from node `origin` like
+    /// [`lookup_key`](Self::lookup_key), then, if read repair is enabled,
+    /// writes the newest version of each record back to the replicas read
+    /// that lacked it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `origin` is not an online node.
+    pub fn read(&mut self, origin: usize, key: &DhtKey) -> NetworkLookup {
+        let (mut lookup, holders, newest) =
+            self.read_replicas(origin, key, self.config.read_strategy);
+        if self.config.read_repair {
+            for index in holders {
+                let mut updated = false;
+                for stored in &newest {
+                    updated |= self.store(index, *key, stored.clone());
+                }
+                lookup.repaired += usize::from(updated);
+            }
+        }
+        lookup
+    }
+
+    /// Runs a lookup, also returning the holders read and the newest
+    /// records among them.
+    fn read_replicas(
+        &self,
+        origin: usize,
+        key: &DhtKey,
+        strategy: ReadStrategy,
+    ) -> (NetworkLookup, Vec<usize>, Vec<Stored>) {
+        let quorum = match strategy {
+            ReadStrategy::First => 1,
+            ReadStrategy::Quorum(replicas) => replicas.max(1),
+        };
+        let (_, holders, metrics) = self.iterate(origin, key, self.config.k, quorum);
+        let newest = self.newest(holders.iter().copied(), key);
+
+        // Acknowledgement times of the latest writes the result misses
+        let missed: Vec<Duration> = self
+            .latest
+            .get(key)
+            .into_iter()
+            .flatten()
+            .filter(|(uri, written)| {
+                !newest.iter().any(|s| {
+                    s.version == written.version && s.registration.agent_uri().as_str() == *uri
+                })
+            })
+            .map(|(_, written)| written.at)
+            .collect();
+        let stale = !newest.is_empty() && !missed.is_empty();
+        let staleness = missed
+            .iter()
+            .min()
+            .filter(|_| stale)
+            .map_or(Duration::ZERO, |&at| self.now.saturating_sub(at));
+
+        let lookup = NetworkLookup {
+            registrations: newest.iter().map(|s| s.registration.clone()).collect(),
+            found_at: holders.first().copied(),
+            replicas_read: holders.len(),
+            stale,
+            staleness,
+            repaired: 0,
+            metrics,
+        };
+        (lookup, holders, newest)
+    }
+
+    /// Returns the `k` nodes closest to `key` that an iterative lookup
+    /// from node `origin` finds, closest first, with the lookup's cost.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `origin` is not an online node.
+    #[must_use]
+    pub fn find_closest(&self, origin: usize, key: &DhtKey) -> (Vec<usize>, LookupMetrics) {
+        let (closest, _, metrics) = self.iterate(origin, key, self.config.k, 0);
+        (closest, metrics)
+    }
+
+    /// Returns the unexpired records node `index` stores under `key`, or
+    /// nothing if the node is offline.
+    fn live(&self, index: usize, key: &DhtKey) -> impl Iterator<Item = &Stored> {
+        let node = &self.nodes[index];
+        node.records
+            .get(key)
+            .filter(|_| node.online)
+            .into_iter()
+            .flatten()
+            .filter(|s| !s.registration.is_expired())
+    }
+
+    /// Returns the newest live version of each agent's record that the
+    /// given nodes hold under `key`.
+    fn newest(&self, nodes: impl IntoIterator<Item = usize>, key: &DhtKey) -> Vec<Stored> {
+        let mut newest: Vec<Stored> = Vec::new();
+        for index in nodes {
+            for stored in self.live(index, key) {
+                match newest.iter_mut().find(|s| s.registration == stored.registration) {
+                    Some(existing) if existing.version >= stored.version => {}
+                    Some(existing) => *existing = stored.clone(),
+                    None => newest.push(stored.clone()),
+                }
+            }
+        }
+        newest
+    }
+
+    /// Walks the network from `origin` towards `key`, querying the `alpha`
+    /// closest unqueried candidates each round until the `width` closest
+    /// known nodes have all been queried, or, if `quorum` is non-zero, until
+    /// that many nodes holding live records for `key` have answered.
+    ///
+    /// Returns the closest nodes found, the record holders that answered
+    /// and the cost. Offline nodes cost a message but never answer, and
+    /// drop out of the candidates.
+    fn iterate(
+        &self,
+        origin: usize,
+        key: &DhtKey,
+        width: usize,
+        quorum: usize,
+    ) -> (Vec<usize>, Vec<usize>, LookupMetrics) {
+        assert!(self.is_online(origin), "origin node {origin} is not online");
+        let alpha = self.config.alpha;
+        let mut metrics = LookupMetrics::default();
+        let holds_value = |index: usize| quorum > 0 && self.live(index, key).next().is_some();
+
+        let mut holders = Vec::new();
+        if holds_value(origin) {
+            holders.push(origin);
+            if holders.len() >= quorum {
+                return (self.closest_nodes(key, width), holders, metrics);
+            }
+        }
+
+        let mut queried = HashSet::from([origin]);
+        let mut seen: HashSet<usize> = HashSet::from([origin]);
+        let mut shortlist = vec![origin];
+        for &contact in &self.nodes[origin].contacts {
+            if seen.insert(contact) {
+                shortlist.push(contact);
+            }
+        }
+
+        loop {
+            self.sort_by_distance(&mut shortlist, key);
+            let batch: Vec<usize> = shortlist
+                .iter()
+                .take(width)
+                .copied()
+                .filter(|index| !queried.contains(index))
+                .take(alpha)
+                .collect();
+            if batch.is_empty() {
+                break;
+            }
+
+            metrics.hops += 1;
+            for index in batch {
+                queried.insert(index);
+                metrics.messages += 1;
+                if !self.nodes[index].online {
+                    shortlist.retain(|&candidate| candidate != index);
+                    continue;
+                }
+                if holds_value(index) {
+                    holders.push(index);
+                }
+                for contact in self.nodes[index].closest_contacts(&self.nodes, key, self.config.k) {
+                    if seen.insert(contact) {
+                        shortlist.push(contact);
+                    }
+                }
+            }
+            if quorum > 0 && holders.len() >= quorum {
+                break;
+            }
+        }
+
+        metrics.latency = self.config.hop_latency * hops_u32(metrics.hops);
+        self.sort_by_distance(&mut shortlist, key);
+        shortlist.truncate(width);
+        (shortlist, holders, metrics)
+    }
+
+    fn sort_by_distance(&self, indices: &mut [usize], key: &DhtKey) {
+        indices.sort_by_cached_key(|&index| self.nodes[index].id.distance(key));
+    }
+
+    /// Adds `contact` to the routing table of `node` if its bucket has room
+    /// or holds an offline contact to evict.
+    fn add_contact(&mut self, node: usize, contact: usize) {
+        let id = self.nodes[node].id;
+        let target = bucket(&id, &self.nodes[contact].id);
+        let in_bucket: Vec<usize> = self.nodes[node]
+            .contacts
+            .iter()
+            .copied()
+            .filter(|&c| bucket(&id, &self.nodes[c].id) == target)
+            .collect();
+        let evict = in_bucket.iter().copied().find(|&c| !self.nodes[c].online);
+        let contacts = &mut self.nodes[node].contacts;
+        if let Some(evicted) = evict {
+            contacts.retain(|&c| c != evicted);
+            contacts.push(contact);
+        } else if in_bucket.len() < self.config.k {
+            contacts.push(contact);
+        }
+    }
+}
+
+impl Node {
+    /// Returns this node's `count` contacts closest to `key`.
+    fn closest_contacts(&self, nodes: &[Self], key: &DhtKey, count: usize) -> Vec<usize> {
+        let mut contacts = self.contacts.clone();
+        contacts.sort_by_cached_key(|&index| nodes[index].id.distance(key));
+        contacts.truncate(count);
+        contacts
+    }
+}
+
+/// Returns the routing-table bucket `other` falls in for node `id`.
+fn bucket(id: &DhtKey, other: &DhtKey) -> usize {
+    id.distance(other).leading_zeros().min(255) as usize
+}
+
+/// Returns the round-trip time of the node with ID `id`, between half and
+/// twice `hop_latency`.
+fn node_rtt(hop_latency: Duration, id: &DhtKey) -> Duration {
+    hop_latency.mul_f64(0.5 + f64::from(id.as_bytes()[31]) / 170.0)
+}
+
+/// Derives the ID of node `index` from the configured seed.
+fn node_id(config: &NetworkConfig, index: usize) -> DhtKey {
+    let digest = Sha256::digest(format!("node/{}/{index}", config.seed).as_bytes());
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&digest);
+    DhtKey::from_bytes_with(config.key_algorithm, bytes)
+}
+
+fn hops_u32(hops: usize) -> u32 {
+    u32::try_from(hops).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Endpoint;
+    use agent_uri::AgentUri;
+
+    fn registration(suffix: &str) -> Registration {
+        let uri = AgentUri::parse(&format!(
+            "agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn0{suffix}"
+        ))
+        .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+    }
+
+    #[test]
+    fn records_are_stored_at_the_k_closest_nodes() {
+        let mut network = NetworkSimulation::new(NetworkConfig::new().with_nodes(128).with_k(4));
+        let registration = registration("2q");
+        let uri = registration.agent_uri().clone();
+        network.register(17, registration).unwrap();
+
+        let key = DhtKey::derive(uri.trust_root(), uri.capability_path());
+        let mut replicas = network.replicas(&key);
+        let mut expected = network.closest_nodes(&key, 4);
+        replicas.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(replicas, expected);
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn blake3_networks_store_records_under_blake3_keys() {
+        let config = NetworkConfig::new()
+            .with_nodes(64)
+            .with_k(4)
+            .with_key_algorithm(KeyAlgorithm::Blake3);
+        let mut network = NetworkSimulation::new(config);
+        let registration = registration("2q");
+        let uri = registration.agent_uri().clone();
+        network.register(5, registration).unwrap();
+
+        let (trust_root, path) = (uri.trust_root(), uri.capability_path());
+        let key = DhtKey::derive_with(KeyAlgorithm::Blake3, trust_root, path);
+        assert_eq!(network.replicas(&key).len(), 4);
+        assert!(network.replicas(&DhtKey::derive(trust_root, path)).is_empty());
+        let lookup = network.lookup_exact(40, trust_root, path);
+        assert_eq!(lookup.registrations.len(), 1);
+    }
+
+    #[test]
+    fn every_node_can_find_a_record() {
+        let mut network = NetworkSimulation::new(NetworkConfig::new().with_nodes(64).with_k(3));
+        let registration = registration("2q");
+        let uri = registration.agent_uri().clone();
+        network.register(0, registration).unwrap();
+
+        for origin in 0..network.node_count() {
+            let lookup = network.lookup_exact(origin, uri.trust_root(), uri.capability_path());
+            assert_eq!(lookup.registrations.len(), 1, "lookup from node {origin}");
+            assert!(lookup.metrics.messages >= lookup.metrics.hops);
+            assert_eq!(
+                lookup.metrics.latency,
+                Duration::from_millis(10) * u32::try_from(lookup.metrics.hops).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn missing_keys_are_not_found() {
+        let network = NetworkSimulation::new(NetworkConfig::new().with_nodes(32));
+        let uri = registration("2q").agent_uri().clone();
+        let lookup = network.lookup_exact(5, uri.trust_root(), uri.capability_path());
+        assert!(lookup.registrations.is_empty());
+        assert!(lookup.found_at.is_none());
+        assert!(lookup.metrics.hops > 0);
+    }
+
+    #[test]
+    fn single_node_network_answers_locally() {
+        let mut network = NetworkSimulation::new(NetworkConfig::new().with_nodes(0));
+        assert_eq!(network.node_count(), 1);
+        let registration = registration("2q");
+        let uri = registration.agent_uri().clone();
+        let outcome = network.register(0, registration).unwrap();
+        assert_eq!(outcome.metrics.hops, 0);
+        assert_eq!(outcome.acks, 1);
+
+        let lookup = network.lookup_exact(0, uri.trust_root(), uri.capability_path());
+        assert_eq!(lookup.found_at, Some(0));
+        assert_eq!(lookup.metrics, LookupMetrics::default());
+    }
+
+    #[test]
+    fn same_seed_builds_same_network() {
+        let a = NetworkSimulation::new(NetworkConfig::new().with_seed(7));
+        let b = NetworkSimulation::new(NetworkConfig::new().with_seed(7));
+        let c = NetworkSimulation::new(NetworkConfig::new().with_seed(8));
+        assert_eq!(a.node_id(3), b.node_id(3));
+        assert_ne!(a.node_id(3), c.node_id(3));
+    }
+}
@@ -0,0 +1,135 @@
+//! Paginated lookup results.
+
+use std::fmt;
+
+use agent_uri::CapabilityPath;
+
+use crate::Registration;
+
+/// Continuation token for [`Dht::lookup_prefix_paged`](crate::Dht::lookup_prefix_paged).
+///
+/// Opaque to callers: pass the cursor of one page to fetch the next. It
+/// marks a position in the stable ordering of registrations by capability
+/// path, then agent URI, so registrations added or removed between pages
+/// neither shift nor repeat the others. Cursors can be sent over the wire
+/// with [`as_str`](Self::as_str) and restored with [`new`](Self::new).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LookupCursor(String);
+
+impl LookupCursor {
+    /// Restores a cursor from its string form.
+    #[must_use]
+    pub fn new(token: impl Into<String>) -> Self {
+        Self(token.into())
+    }
+
+    /// Returns the cursor positioned just past `registration`.
+    pub(crate) fn after(registration: &Registration) -> Self {
+        let agent_uri = registration.agent_uri();
+        Self(sort_key(agent_uri.capability_path(), agent_uri.as_str()))
+    }
+
+    /// Returns the cursor's string form.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for LookupCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// One page of a prefix lookup.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LookupPage {
+    /// Registrations on this page, ordered by capability path, then agent
+    /// URI
+    pub registrations: Vec<Registration>,
+    /// Cursor for the next page, or `None` if this is the last one
+    pub next_cursor: Option<LookupCursor>,
+}
+
+impl LookupPage {
+    /// Builds a page of at most `limit` entries from `(sort key,
+    /// registration)` pairs already in order and past the cursor.
+    pub(crate) fn collect(
+        entries: impl IntoIterator<Item = (String, Registration)>,
+        limit: usize,
+    ) -> Self {
+        let mut entries = entries.into_iter();
+        let mut page = Self::default();
+        let mut last = None;
+        for (key, registration) in entries.by_ref().take(limit) {
+            page.registrations.push(registration);
+            last = Some(key);
+        }
+        if entries.next().is_some() {
+            page.next_cursor = last.map(LookupCursor);
+        }
+        page
+    }
+}
+
+/// Returns the key registrations are paged in order of.
+///
+/// The trailing `/` makes a range over `{prefix}/` match the prefix itself
+/// and its descendants but not siblings such as `{prefix}x`; capability
+/// paths never contain the space separator.
+pub(crate) fn sort_key(capability_path: &CapabilityPath, agent_uri: &str) -> String {
+    format!("{}/ {agent_uri}", capability_path.as_str())
+}
+
+/// Returns the exclusive lower bound for a page under `prefix`, or under
+/// the whole trust root if `None`, ignoring a cursor that points before the
+/// prefix.
+pub(crate) fn start_after<'a>(
+    prefix: Option<&CapabilityPath>,
+    cursor: Option<&'a LookupCursor>,
+) -> Option<&'a str> {
+    let start = prefix.map_or_else(String::new, |prefix| format!("{}/", prefix.as_str()));
+    cursor
+        .map(LookupCursor::as_str)
+        .filter(|cursor| *cursor >= start.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Endpoint;
+    use agent_uri::AgentUri;
+
+    fn entry(suffix: &str) -> (String, Registration) {
+        let uri = AgentUri::parse(&format!(
+            "agent://acme.com/assistant/llm_01h455vb4pex5vsknk084sn0{suffix}"
+        ))
+        .unwrap();
+        let key = sort_key(uri.capability_path(), uri.as_str());
+        (key, Registration::new(uri, vec![Endpoint::https("agent.acme.com")]))
+    }
+
+    #[test]
+    fn collect_sets_cursor_only_when_more_remain() {
+        let entries = vec![entry("2q"), entry("3q"), entry("4q")];
+
+        let first = LookupPage::collect(entries.clone(), 2);
+        assert_eq!(first.registrations.len(), 2);
+        assert_eq!(first.next_cursor.as_ref().unwrap().as_str(), entries[1].0);
+
+        let all = LookupPage::collect(entries, 3);
+        assert_eq!(all.registrations.len(), 3);
+        assert!(all.next_cursor.is_none());
+    }
+
+    #[test]
+    fn cursors_before_the_prefix_are_ignored() {
+        let prefix = CapabilityPath::parse("billing").unwrap();
+        let early = LookupCursor::new("assistant/ agent://acme.com/assistant");
+        let inside = LookupCursor::new("billing/ agent://acme.com/billing");
+        assert_eq!(start_after(Some(&prefix), Some(&early)), None);
+        assert_eq!(start_after(Some(&prefix), Some(&inside)), Some(inside.as_str()));
+        assert_eq!(start_after(None, Some(&early)), Some(early.as_str()));
+    }
+}
@@ -0,0 +1,197 @@
+//! Capability path patterns with wildcard segments.
+
+use std::fmt;
+
+use agent_uri::{
+    CapabilityPath, CapabilityPathError, MAX_CAPABILITY_PATH_LENGTH, MAX_PATH_SEGMENTS,
+    PathSegment,
+};
+
+/// One segment of a [`PathPattern`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum PatternSegment {
+    /// `*`: any single segment
+    Any,
+    /// A segment that must match exactly
+    Literal(PathSegment),
+}
+
+/// A capability path in which `*` segments match any single segment.
+///
+/// `assistant/*/streaming` matches `assistant/chat/streaming` and
+/// `assistant/code/streaming`, but not `assistant/streaming` or
+/// `assistant/chat/fast/streaming`: patterns match paths of exactly their
+/// own depth.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::{AgentUri, TrustRoot};
+/// use agent_uri_dht::{Dht, Endpoint, PathPattern, Registration, SimulatedDht};
+///
+/// let dht = SimulatedDht::with_defaults();
+/// for uri in [
+///     "agent://acme.com/assistant/chat/streaming/llm_01h455vb4pex5vsknk084sn02q",
+///     "agent://acme.com/assistant/code/batch/llm_01h455vb4pex5vsknk084sn03q",
+/// ] {
+///     let uri = AgentUri::parse(uri).unwrap();
+///     dht.register(Registration::new(uri, vec![Endpoint::https("agent.acme.com")])).unwrap();
+/// }
+///
+/// let pattern = PathPattern::parse("assistant/*/streaming").unwrap();
+/// let found = dht.lookup_pattern(&TrustRoot::parse("acme.com").unwrap(), &pattern).unwrap();
+/// assert_eq!(found.len(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PathPattern {
+    segments: Vec<PatternSegment>,
+    normalized: String,
+}
+
+impl PathPattern {
+    /// Parses a pattern, validating literal segments as
+    /// [`CapabilityPath`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CapabilityPathError` if the pattern is empty or too long,
+    /// has too many segments, or has an invalid literal segment.
+    pub fn parse(input: &str) -> Result<Self, CapabilityPathError> {
+        if input.is_empty() {
+            return Err(CapabilityPathError::Empty);
+        }
+        if input.len() > MAX_CAPABILITY_PATH_LENGTH {
+            return Err(CapabilityPathError::TooLong {
+                max: MAX_CAPABILITY_PATH_LENGTH,
+                actual: input.len(),
+            });
+        }
+
+        let segment_strs: Vec<&str> = input.split('/').collect();
+        if segment_strs.len() > MAX_PATH_SEGMENTS {
+            return Err(CapabilityPathError::TooManySegments {
+                max: MAX_PATH_SEGMENTS,
+                actual: segment_strs.len(),
+            });
+        }
+
+        let mut segments = Vec::with_capacity(segment_strs.len());
+        for (i, seg_str) in segment_strs.iter().enumerate() {
+            if *seg_str == "*" {
+                segments.push(PatternSegment::Any);
+                continue;
+            }
+            let segment =
+                PathSegment::parse(seg_str).map_err(|e| CapabilityPathError::InvalidSegment {
+                    segment: (*seg_str).to_string(),
+                    index: i,
+                    reason: e,
+                })?;
+            segments.push(PatternSegment::Literal(segment));
+        }
+
+        Ok(Self {
+            segments,
+            normalized: input.to_string(),
+        })
+    }
+
+    /// Returns the number of segments a matching path has.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns true if the pattern contains at least one `*`.
+    #[must_use]
+    pub fn has_wildcards(&self) -> bool {
+        self.segments.contains(&PatternSegment::Any)
+    }
+
+    /// Returns the literal segments before the first `*`, or `None` if the
+    /// pattern starts with one.
+    ///
+    /// Every matching path lies under this prefix.
+    #[must_use]
+    pub fn literal_prefix(&self) -> Option<CapabilityPath> {
+        let literals: Vec<PathSegment> = self
+            .segments
+            .iter()
+            .map_while(|segment| match segment {
+                PatternSegment::Literal(segment) => Some(segment.clone()),
+                PatternSegment::Any => None,
+            })
+            .collect();
+        CapabilityPath::from_segments(literals).ok()
+    }
+
+    /// Returns true if `path` matches the pattern.
+    #[must_use]
+    pub fn matches(&self, path: &CapabilityPath) -> bool {
+        path.depth() == self.segments.len()
+            && self
+                .segments
+                .iter()
+                .zip(path.segments())
+                .all(|(pattern, segment)| match pattern {
+                    PatternSegment::Any => true,
+                    PatternSegment::Literal(literal) => literal == segment,
+                })
+    }
+
+    /// Returns the pattern as a string.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.normalized
+    }
+
+    pub(crate) fn segments(&self) -> &[PatternSegment] {
+        &self.segments
+    }
+}
+
+impl fmt::Display for PathPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.normalized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(input: &str) -> CapabilityPath {
+        CapabilityPath::parse(input).unwrap()
+    }
+
+    #[test]
+    fn wildcards_match_exactly_one_segment() {
+        let pattern = PathPattern::parse("assistant/*/streaming").unwrap();
+        assert!(pattern.has_wildcards());
+        assert!(pattern.matches(&path("assistant/chat/streaming")));
+        assert!(!pattern.matches(&path("assistant/streaming")));
+        assert!(!pattern.matches(&path("assistant/chat/fast/streaming")));
+        assert!(!pattern.matches(&path("billing/chat/streaming")));
+    }
+
+    #[test]
+    fn literal_prefix_stops_at_first_wildcard() {
+        let pattern = PathPattern::parse("assistant/*/streaming").unwrap();
+        assert_eq!(pattern.literal_prefix(), Some(path("assistant")));
+        assert_eq!(PathPattern::parse("*/chat").unwrap().literal_prefix(), None);
+        assert_eq!(
+            PathPattern::parse("assistant/chat").unwrap().literal_prefix(),
+            Some(path("assistant/chat"))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_invalid_segments() {
+        assert!(matches!(PathPattern::parse(""), Err(CapabilityPathError::Empty)));
+        assert!(matches!(
+            PathPattern::parse("assistant/ch*t"),
+            Err(CapabilityPathError::InvalidSegment { index: 1, .. })
+        ));
+        assert!(PathPattern::parse("assistant//chat").is_err());
+    }
+}
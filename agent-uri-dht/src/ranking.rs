@@ -0,0 +1,478 @@
+//! Ordering lookup results by the strength of their attestations.
+
+use std::cmp::Reverse;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, SystemTime};
+
+use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+
+use crate::{
+    Dht, DhtError, DhtEvent, DhtHealth, DhtStats, Endpoint, LookupCursor, LookupFilter,
+    LookupPage, PathPattern, Registration, RegistrationValidator, TrustRootGroup,
+};
+
+/// How strongly a registration is vouched for, as judged by an
+/// [`AttestationRanking`].
+///
+/// Strengths compare field by field in declaration order, so a verified
+/// attestation outranks any unverified one, a longer remaining token
+/// lifetime breaks ties between verified ones, and a deeper capability
+/// path breaks what ties remain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AttestationStrength {
+    /// Whether the registration carries an attestation the validator
+    /// accepts
+    pub verified: bool,
+    /// Time until the verified attestation expires, or None if it is
+    /// unverified or the validator does not report token lifetimes
+    pub remaining: Option<Duration>,
+    /// Depth of the capability path the registration is indexed under
+    pub specificity: usize,
+}
+
+/// Orders registrations strongest attestation first.
+///
+/// Each registration is checked with a [`RegistrationValidator`], usually
+/// `agent_uri_attestation::Verifier`, whose
+/// [`attested_until`](RegistrationValidator::attested_until) also reports
+/// when the token expires. Registrations of equal
+/// [strength](AttestationStrength) keep the order they were found in.
+///
+/// Wrap a backend in a [`RankedDht`] to rank every lookup's results.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{AttestationRanking, DhtError, Endpoint, Registration};
+///
+/// let ranking = AttestationRanking::new(|registration: &Registration| {
+///     match registration.attestation() {
+///         Some("valid") => Ok(()),
+///         _ => Err(DhtError::invalid_attestation(registration.agent_uri().as_str(), "forged")),
+///     }
+/// });
+///
+/// let registration = |uri: &str, token: &str| {
+///     let uri = AgentUri::parse(uri).unwrap();
+///     Registration::new(uri, vec![Endpoint::https("agent.acme.com")]).with_attestation(token)
+/// };
+/// let ranked = ranking.rank(vec![
+///     registration("agent://acme.com/assistant/llm_01h455vb4pex5vsknk084sn02q", "forged"),
+///     registration("agent://acme.com/assistant/llm_01h455vb4pex5vsknk084sn02r", "valid"),
+///     registration("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02s", "valid"),
+/// ]);
+///
+/// // Verified first, the more specific capability ahead
+/// let attested: Vec<_> = ranked.iter().map(|r| r.agent_uri().capability_path().depth()).collect();
+/// assert_eq!(attested, [2, 1, 1]);
+/// assert_eq!(ranked[2].attestation(), Some("forged"));
+/// ```
+#[derive(Clone)]
+pub struct AttestationRanking {
+    validator: Arc<dyn RegistrationValidator>,
+}
+
+impl AttestationRanking {
+    /// Creates a ranking that verifies attestations with `validator`.
+    #[must_use]
+    pub fn new(validator: impl RegistrationValidator + 'static) -> Self {
+        Self {
+            validator: Arc::new(validator),
+        }
+    }
+
+    /// Returns the strength of `registration`'s attestation now.
+    #[must_use]
+    pub fn strength(&self, registration: &Registration) -> AttestationStrength {
+        self.strength_at(registration, SystemTime::now())
+    }
+
+    /// Returns the strength of `registration`'s attestation at `now`.
+    #[must_use]
+    pub fn strength_at(&self, registration: &Registration, now: SystemTime) -> AttestationStrength {
+        let attested_until = registration
+            .attestation()
+            .and_then(|_| self.validator.attested_until(registration).ok());
+        AttestationStrength {
+            verified: attested_until.is_some(),
+            remaining: attested_until
+                .flatten()
+                .map(|until| until.duration_since(now).unwrap_or_default()),
+            specificity: registration.agent_uri().capability_path().depth(),
+        }
+    }
+
+    /// Sorts `registrations` strongest first, judged now.
+    #[must_use]
+    pub fn rank(&self, registrations: Vec<Registration>) -> Vec<Registration> {
+        self.rank_at(registrations, SystemTime::now())
+    }
+
+    /// Sorts `registrations` strongest first, judged at `now`.
+    #[must_use]
+    pub fn rank_at(
+        &self,
+        mut registrations: Vec<Registration>,
+        now: SystemTime,
+    ) -> Vec<Registration> {
+        registrations.sort_by_cached_key(|registration| {
+            Reverse(self.strength_at(registration, now))
+        });
+        registrations
+    }
+}
+
+impl fmt::Debug for AttestationRanking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttestationRanking").finish_non_exhaustive()
+    }
+}
+
+/// A [`Dht`] whose lookups return results ordered by an
+/// [`AttestationRanking`].
+///
+/// Every unpaged lookup is ranked, including each batch of
+/// [`lookup_many`](Dht::lookup_many) and each group of
+/// [`lookup_global_grouped`](Dht::lookup_global_grouped), where the
+/// per-root limit keeps the strongest registrations. Paged lookups are
+/// passed through unranked, since their cursors depend on path order.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+/// use agent_uri_dht::{
+///     AttestationRanking, Dht, Endpoint, RankedDht, Registration, SimulatedDht,
+/// };
+///
+/// // Accepts any attestation; use agent_uri_attestation::Verifier in practice
+/// let ranking = AttestationRanking::new(|_: &Registration| Ok(()));
+/// let dht = RankedDht::new(SimulatedDht::with_defaults(), ranking);
+///
+/// let registration = |id: &str| {
+///     let uri = AgentUri::parse(&format!("agent://acme.com/assistant/chat/{id}")).unwrap();
+///     Registration::new(uri, vec![Endpoint::https("agent.acme.com")])
+/// };
+/// dht.register(registration("llm_01h455vb4pex5vsknk084sn02q"))?;
+/// dht.register(registration("llm_01h455vb4pex5vsknk084sn02r").with_attestation("v4.public.token"))?;
+///
+/// let found = dht.lookup_exact(
+///     &TrustRoot::parse("acme.com").unwrap(),
+///     &CapabilityPath::parse("assistant/chat").unwrap(),
+/// )?;
+/// assert!(found[0].attestation().is_some());
+/// # Ok::<(), agent_uri_dht::DhtError>(())
+/// ```
+#[derive(Debug)]
+pub struct RankedDht<D> {
+    inner: D,
+    ranking: AttestationRanking,
+}
+
+impl<D: Dht> RankedDht<D> {
+    /// Ranks the lookup results of `inner` with `ranking`.
+    #[must_use]
+    pub const fn new(inner: D, ranking: AttestationRanking) -> Self {
+        Self { inner, ranking }
+    }
+
+    /// Returns the wrapped DHT.
+    #[must_use]
+    pub const fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Returns the ranking applied to lookups.
+    #[must_use]
+    pub const fn ranking(&self) -> &AttestationRanking {
+        &self.ranking
+    }
+
+    fn rank(
+        &self,
+        result: Result<Vec<Registration>, DhtError>,
+    ) -> Result<Vec<Registration>, DhtError> {
+        result.map(|registrations| self.ranking.rank(registrations))
+    }
+}
+
+impl<D: Dht> Dht for RankedDht<D> {
+    fn register(&self, registration: Registration) -> Result<(), DhtError> {
+        self.inner.register(registration)
+    }
+
+    fn update_endpoint(
+        &self,
+        agent_uri: &AgentUri,
+        new_endpoints: Vec<Endpoint>,
+    ) -> Result<(), DhtError> {
+        self.inner.update_endpoint(agent_uri, new_endpoints)
+    }
+
+    fn update_registration(&self, registration: Registration) -> Result<(), DhtError> {
+        self.inner.update_registration(registration)
+    }
+
+    fn renew(&self, agent_uri: &AgentUri, ttl: Duration) -> Result<(), DhtError> {
+        self.inner.renew(agent_uri, ttl)
+    }
+
+    fn deregister(&self, agent_uri: &AgentUri) -> Result<(), DhtError> {
+        self.inner.deregister(agent_uri)
+    }
+
+    fn lookup_exact(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.rank(self.inner.lookup_exact(trust_root, capability_path))
+    }
+
+    fn lookup_many(
+        &self,
+        queries: &[(TrustRoot, CapabilityPath)],
+    ) -> Vec<Result<Vec<Registration>, DhtError>> {
+        self.inner
+            .lookup_many(queries)
+            .into_iter()
+            .map(|result| self.rank(result))
+            .collect()
+    }
+
+    fn lookup_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.rank(self.inner.lookup_prefix(trust_root, capability_path))
+    }
+
+    fn lookup_prefix_paged(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        self.inner
+            .lookup_prefix_paged(trust_root, capability_path, limit, cursor)
+    }
+
+    fn lookup_global(
+        &self,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.rank(self.inner.lookup_global(capability_path))
+    }
+
+    fn lookup_global_grouped(
+        &self,
+        capability_path: &CapabilityPath,
+        per_root_limit: Option<usize>,
+    ) -> Result<Vec<TrustRootGroup>, DhtError> {
+        let mut groups = self.inner.lookup_global_grouped(capability_path, None)?;
+        for group in &mut groups {
+            group.registrations = self.ranking.rank(std::mem::take(&mut group.registrations));
+            if let Some(limit) = per_root_limit {
+                group.registrations.truncate(limit);
+            }
+        }
+        Ok(groups)
+    }
+
+    fn lookup_pattern(
+        &self,
+        trust_root: &TrustRoot,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.rank(self.inner.lookup_pattern(trust_root, pattern))
+    }
+
+    fn lookup_pattern_global(&self, pattern: &PathPattern) -> Result<Vec<Registration>, DhtError> {
+        self.rank(self.inner.lookup_pattern_global(pattern))
+    }
+
+    fn lookup_trust_root(&self, trust_root: &TrustRoot) -> Result<Vec<Registration>, DhtError> {
+        self.rank(self.inner.lookup_trust_root(trust_root))
+    }
+
+    fn lookup_trust_root_paged(
+        &self,
+        trust_root: &TrustRoot,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        self.inner.lookup_trust_root_paged(trust_root, limit, cursor)
+    }
+
+    fn watch_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Receiver<DhtEvent>, DhtError> {
+        self.inner.watch_prefix(trust_root, capability_path)
+    }
+
+    fn lookup_exact_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.rank(
+            self.inner
+                .lookup_exact_filtered(trust_root, capability_path, filter),
+        )
+    }
+
+    fn lookup_prefix_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.rank(
+            self.inner
+                .lookup_prefix_filtered(trust_root, capability_path, filter),
+        )
+    }
+
+    fn lookup_prefix_paged_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+        filter: &LookupFilter,
+    ) -> Result<LookupPage, DhtError> {
+        self.inner.lookup_prefix_paged_filtered(
+            trust_root,
+            capability_path,
+            limit,
+            cursor,
+            filter,
+        )
+    }
+
+    fn lookup_global_filtered(
+        &self,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.rank(self.inner.lookup_global_filtered(capability_path, filter))
+    }
+
+    fn ping(&self) -> Result<(), DhtError> {
+        self.inner.ping()
+    }
+
+    fn health(&self) -> DhtHealth {
+        self.inner.health()
+    }
+
+    fn stats(&self) -> Result<DhtStats, DhtError> {
+        self.inner.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimulatedDht;
+
+    /// Accepts tokens of the form `valid:{secs}`, attested for `secs`
+    /// seconds after the epoch.
+    fn validator(registration: &Registration) -> Result<Option<SystemTime>, DhtError> {
+        registration
+            .attestation()
+            .and_then(|token| token.strip_prefix("valid:"))
+            .and_then(|secs| secs.parse().ok())
+            .map(|secs| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)))
+            .ok_or_else(|| {
+                DhtError::invalid_attestation(registration.agent_uri().as_str(), "bad token")
+            })
+    }
+
+    struct Lifetimes;
+
+    impl RegistrationValidator for Lifetimes {
+        fn validate(&self, registration: &Registration) -> Result<(), DhtError> {
+            validator(registration).map(|_| ())
+        }
+
+        fn attested_until(
+            &self,
+            registration: &Registration,
+        ) -> Result<Option<SystemTime>, DhtError> {
+            validator(registration)
+        }
+    }
+
+    fn registration(path: &str, suffix: char, token: Option<&str>) -> Registration {
+        let uri = AgentUri::parse(&format!(
+            "agent://acme.com/{path}/llm_01h455vb4pex5vsknk084sn02{suffix}"
+        ))
+        .unwrap();
+        let registration = Registration::new(uri, vec![Endpoint::https("agent.acme.com")]);
+        match token {
+            Some(token) => registration.with_attestation(token),
+            None => registration,
+        }
+    }
+
+    fn suffixes(registrations: &[Registration]) -> String {
+        registrations
+            .iter()
+            .map(|registration| registration.agent_uri().as_str().chars().last().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn ranks_by_verification_then_lifetime_then_specificity() {
+        let ranking = AttestationRanking::new(Lifetimes);
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let registrations = vec![
+            registration("assistant/chat", 'a', None),
+            registration("assistant", 'b', Some("forged")),
+            registration("assistant", 'c', Some("valid:200")),
+            registration("assistant", 'd', Some("valid:500")),
+            registration("assistant/chat", 'e', Some("valid:200")),
+            registration("assistant", 'f', None),
+        ];
+
+        let strength = ranking.strength_at(&registrations[4], now);
+        assert!(strength.verified);
+        assert_eq!(strength.remaining, Some(Duration::from_secs(100)));
+        assert_eq!(strength.specificity, 2);
+        assert!(!ranking.strength_at(&registrations[1], now).verified);
+
+        let ranked = ranking.rank_at(registrations, now);
+        assert_eq!(suffixes(&ranked), "decabf");
+    }
+
+    #[test]
+    fn ranked_dht_ranks_unpaged_lookups() {
+        let ranking = AttestationRanking::new(|registration: &Registration| {
+            validator(registration).map(|_| ())
+        });
+        let dht = RankedDht::new(SimulatedDht::with_defaults(), ranking);
+        for (suffix, token) in [('a', None), ('b', Some("valid:0")), ('c', None)] {
+            dht.register(registration("assistant", suffix, token)).unwrap();
+        }
+        let trust_root = TrustRoot::parse("acme.com").unwrap();
+        let path = CapabilityPath::parse("assistant").unwrap();
+
+        let found = dht.lookup_exact(&trust_root, &path).unwrap();
+        assert_eq!(found[0].attestation(), Some("valid:0"));
+        let found = dht.lookup_global_filtered(&path, &LookupFilter::new()).unwrap();
+        assert_eq!(found[0].attestation(), Some("valid:0"));
+
+        let groups = dht.lookup_global_grouped(&path, Some(1)).unwrap();
+        assert_eq!(groups[0].total, 3);
+        assert_eq!(suffixes(&groups[0].registrations), "b");
+    }
+}
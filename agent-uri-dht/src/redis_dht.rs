@@ -0,0 +1,893 @@
+//! Redis-backed registry implementing the [`Dht`] trait.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+use redis::{Commands, Connection, RedisError};
+
+use crate::churn::ratio;
+use crate::page;
+use crate::telemetry;
+use crate::watch::Watchers;
+use crate::{
+    Dht, DhtError, DhtEvent, DhtKey, DhtStats, Endpoint, KeyAlgorithm, KeyVersion, LookupCursor,
+    LookupPage, PathPattern, Registration, RegistrationValidator,
+};
+
+/// A centralized registry stored in Redis.
+///
+/// For deployments that want a horizontally scaled registry rather than a
+/// true DHT. Registrations are laid out under a namespace prefix (default
+/// [`DEFAULT_NAMESPACE`](Self::DEFAULT_NAMESPACE)):
+///
+/// | Key | Type | Contents |
+/// |-----|------|----------|
+/// | `{ns}:reg:{agent_uri}` | string | JSON registration, expiring with it |
+/// | `{ns}:key:{dht_key}` | set | Agent URIs at a [`DhtKey`] (full hex) |
+/// | `{ns}:path:{trust_root}` | sorted set | `{capability_path}/ {agent_uri}` members |
+/// | `{ns}:global` | sorted set | The same members across all trust roots |
+///
+/// Expiry is left to Redis: each registration key carries the
+/// registration's remaining TTL. Index entries pointing at expired
+/// registrations are skipped and removed by the next lookup that finds
+/// them. Prefix lookups are `ZRANGEBYLEX` range scans over the sorted sets,
+/// whose members all score zero.
+///
+/// Operations run on a single connection serialized by a mutex. Redis
+/// failures are reported as `DhtError::Backend`.
+///
+/// [`watch_prefix`](Dht::watch_prefix) only reports changes made through
+/// this `RedisDht`, and expiries once a lookup finds them, not writes by
+/// other clients of the same database.
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+/// use agent_uri_dht::{Dht, Endpoint, RedisDht, Registration};
+///
+/// let dht = RedisDht::connect("redis://127.0.0.1/")?.with_namespace("acme-registry");
+///
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// dht.register(Registration::new(uri, vec![Endpoint::https("agent.acme.com")]))?;
+///
+/// let found = dht.lookup_prefix(
+///     &TrustRoot::parse("acme.com").unwrap(),
+///     &CapabilityPath::parse("assistant").unwrap(),
+/// )?;
+/// assert_eq!(found.len(), 1);
+/// # Ok::<(), agent_uri_dht::DhtError>(())
+/// ```
+pub struct RedisDht {
+    connection: Mutex<Connection>,
+    namespace: String,
+    key_version: KeyVersion,
+    max_registrations_per_key: usize,
+    max_results_per_query: Option<usize>,
+    validator: Option<Arc<dyn RegistrationValidator>>,
+    watchers: Watchers,
+}
+
+impl RedisDht {
+    /// Key prefix used unless [`with_namespace`](Self::with_namespace) is set.
+    pub const DEFAULT_NAMESPACE: &str = "agent-dht";
+
+    /// Connects to the Redis server at `url`, e.g. `redis://127.0.0.1/`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::Backend` if the URL is invalid or the server
+    /// cannot be reached.
+    pub fn connect(url: &str) -> Result<Self, DhtError> {
+        let connection = redis::Client::open(url)
+            .and_then(|client| client.get_connection())
+            .map_err(backend)?;
+        Ok(Self::from_connection(connection))
+    }
+
+    /// Uses an established connection.
+    #[must_use]
+    pub fn from_connection(connection: Connection) -> Self {
+        Self {
+            connection: Mutex::new(connection),
+            namespace: Self::DEFAULT_NAMESPACE.to_string(),
+            key_version: KeyVersion::Legacy,
+            max_registrations_per_key: 1000,
+            max_results_per_query: None,
+            validator: None,
+            watchers: Watchers::default(),
+        }
+    }
+
+    /// Sets the prefix of every key this registry uses, so several
+    /// registries can share a Redis database.
+    #[must_use]
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Sets the key derivation the `{ns}:key:` index is keyed by (default
+    /// [`KeyVersion::Legacy`]).
+    ///
+    /// Use [`KeyVersion::V1`] for domain-separated keys; an index written
+    /// with one version is not found under the other.
+    #[must_use]
+    pub const fn with_key_version(mut self, version: KeyVersion) -> Self {
+        self.key_version = version;
+        self
+    }
+
+    /// Sets the maximum registrations per DHT key (default 1000).
+    #[must_use]
+    pub const fn with_max_registrations_per_key(mut self, max: usize) -> Self {
+        self.max_registrations_per_key = max;
+        self
+    }
+
+    /// Sets the maximum registrations a single lookup may return (default
+    /// unlimited).
+    ///
+    /// Pages are capped at this size, and unpaged lookups matching more
+    /// fail with `DhtError::ResultLimitExceeded`.
+    #[must_use]
+    pub const fn with_max_results_per_query(mut self, max: usize) -> Self {
+        self.max_results_per_query = Some(max);
+        self
+    }
+
+    /// Runs `validator` on every registration before it is stored; see
+    /// [`RegistrationValidator`].
+    #[must_use]
+    pub fn with_validator(mut self, validator: impl RegistrationValidator + 'static) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
+    /// Returns the key prefix.
+    #[must_use]
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn registration_key(&self, agent_uri: &str) -> String {
+        format!("{}:reg:{agent_uri}", self.namespace)
+    }
+
+    fn key(&self, trust_root: &TrustRoot, capability_path: &CapabilityPath) -> DhtKey {
+        DhtKey::derive_versioned(
+            KeyAlgorithm::Sha256,
+            self.key_version,
+            trust_root,
+            capability_path,
+        )
+    }
+
+    fn dht_key(&self, key: &DhtKey) -> String {
+        format!("{}:key:{}", self.namespace, key.to_hex())
+    }
+
+    fn path_index(&self, trust_root: &str) -> String {
+        format!("{}:path:{trust_root}", self.namespace)
+    }
+
+    fn global_index(&self) -> String {
+        format!("{}:global", self.namespace)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().expect("lock poisoned")
+    }
+
+    /// Removes `agent_uri` from every index.
+    fn unindex(&self, conn: &mut Connection, agent_uri: &AgentUri) -> Result<(), DhtError> {
+        let key = self.key(agent_uri.trust_root(), agent_uri.capability_path());
+        let member = page::sort_key(agent_uri.capability_path(), agent_uri.as_str());
+        redis::pipe()
+            .atomic()
+            .srem(self.dht_key(&key), agent_uri.as_str())
+            .ignore()
+            .zrem(self.path_index(agent_uri.trust_root().as_str()), &member)
+            .ignore()
+            .zrem(self.global_index(), &member)
+            .ignore()
+            .exec(conn)
+            .map_err(backend)
+    }
+
+    /// Applies `update` to a live registration, replacing its TTL with
+    /// `ttl` if given. `update` returns whether it changed the
+    /// registration; if not, nothing is written.
+    fn modify(
+        &self,
+        agent_uri: &AgentUri,
+        ttl: Option<Duration>,
+        update: impl FnOnce(&mut Registration) -> Result<bool, DhtError>,
+    ) -> Result<(), DhtError> {
+        let uri_str = agent_uri.as_str();
+        let record_key = self.registration_key(uri_str);
+        let mut conn = self.lock();
+
+        let record: Option<String> = conn.get(&record_key).map_err(backend)?;
+        let mut registration = decode(&record.ok_or_else(|| DhtError::not_found(uri_str))?)?;
+        if registration.is_expired() {
+            return Err(DhtError::expired(uri_str));
+        }
+        if !update(&mut registration)? {
+            return Ok(());
+        }
+
+        // XX fails if the registration expired since it was read
+        let mut set = redis::cmd("SET");
+        set.arg(&record_key).arg(encode(&registration)?).arg("XX");
+        match ttl {
+            Some(ttl) => set.arg("PX").arg(ttl_millis(ttl)),
+            None => set.arg("KEEPTTL"),
+        };
+        let updated: Option<String> = set.query(&mut *conn).map_err(backend)?;
+        if updated.is_none() {
+            return Err(DhtError::expired(uri_str));
+        }
+        self.watchers.publish(&DhtEvent::Updated(registration));
+        Ok(())
+    }
+
+    /// Loads the registrations of `agent_uris`, pruning index entries whose
+    /// registration has expired.
+    fn load(
+        &self,
+        conn: &mut Connection,
+        agent_uris: &[String],
+    ) -> Result<Vec<Registration>, DhtError> {
+        if agent_uris.is_empty() {
+            return Ok(Vec::new());
+        }
+        let keys: Vec<_> = agent_uris
+            .iter()
+            .map(|uri| self.registration_key(uri))
+            .collect();
+        let records: Vec<Option<String>> = conn.mget(keys).map_err(backend)?;
+
+        let mut registrations = Vec::new();
+        for (uri, record) in agent_uris.iter().zip(records) {
+            match record {
+                Some(json) => registrations.push(decode(&json)?),
+                None => {
+                    if let Ok(uri) = AgentUri::parse(uri) {
+                        self.unindex(conn, &uri)?;
+                        self.watchers.publish(&DhtEvent::Expired(uri));
+                    }
+                }
+            }
+        }
+        Ok(registrations)
+    }
+
+    /// Loads every registration in the sorted set `index` at or below
+    /// `capability_path`, or the whole index if `None`.
+    fn load_prefix(
+        &self,
+        conn: &mut Connection,
+        index: &str,
+        capability_path: Option<&CapabilityPath>,
+    ) -> Result<Vec<Registration>, DhtError> {
+        if let Some(max) = self.max_results_per_query {
+            // Load at most one page past the cap
+            let page = self.load_page(conn, index, capability_path, max, None)?;
+            return match page.next_cursor {
+                Some(_) => Err(DhtError::result_limit_exceeded(max)),
+                None => Ok(page.registrations),
+            };
+        }
+
+        let (min, max) = lex_range(capability_path);
+        let members: Vec<String> = conn.zrangebylex(index, min, max).map_err(backend)?;
+        self.load(conn, &member_uris(&members))
+    }
+
+    /// Loads up to `limit` registrations in the sorted set `index` at or
+    /// below `capability_path`, or anywhere in it if `None`, after `cursor`.
+    fn load_page(
+        &self,
+        conn: &mut Connection,
+        index: &str,
+        capability_path: Option<&CapabilityPath>,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        let (mut min, max) = lex_range(capability_path);
+        if let Some(after) = page::start_after(capability_path, cursor) {
+            min = format!("({after}");
+        }
+
+        // One registration past the page tells whether another follows;
+        // keep reading while expired entries leave the page short
+        let wanted = limit.saturating_add(1);
+        let mut registrations = Vec::new();
+        loop {
+            let missing = wanted - registrations.len();
+            let count = isize::try_from(missing).unwrap_or(isize::MAX);
+            let members: Vec<String> = conn
+                .zrangebylex_limit(index, &min, &max, 0, count)
+                .map_err(backend)?;
+            let exhausted = members.len() < missing;
+            if let Some(last) = members.last() {
+                min = format!("({last}");
+            }
+            registrations.extend(self.load(conn, &member_uris(&members))?);
+            if exhausted || registrations.len() >= wanted {
+                break;
+            }
+        }
+
+        Ok(LookupPage::collect(
+            registrations.into_iter().map(|r| {
+                (page::sort_key(r.agent_uri().capability_path(), r.agent_uri().as_str()), r)
+            }),
+            limit,
+        ))
+    }
+
+    /// Validates and stores a new registration.
+    fn store(&self, registration: Registration) -> Result<(), DhtError> {
+        Endpoint::validate_all(registration.endpoints())?;
+        if let Some(validator) = &self.validator {
+            validator.validate(&registration)?;
+        }
+        let agent_uri = registration.agent_uri();
+        let uri_str = agent_uri.as_str();
+        let ttl = registration
+            .remaining_ttl()
+            .filter(|ttl| !ttl.is_zero())
+            .ok_or_else(|| DhtError::expired(uri_str))?;
+        let key = self.key(agent_uri.trust_root(), agent_uri.capability_path());
+        let mut conn = self.lock();
+
+        let current: Vec<String> = conn.smembers(self.dht_key(&key)).map_err(backend)?;
+        if current.len() >= self.max_registrations_per_key
+            && self.load(&mut conn, &current)?.len() >= self.max_registrations_per_key
+        {
+            return Err(DhtError::key_capacity_exceeded(
+                format!("{key}"),
+                self.max_registrations_per_key,
+            ));
+        }
+
+        let created: Option<String> = redis::cmd("SET")
+            .arg(self.registration_key(uri_str))
+            .arg(encode(&registration)?)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl_millis(ttl))
+            .query(&mut *conn)
+            .map_err(backend)?;
+        if created.is_none() {
+            return Err(DhtError::already_registered(uri_str));
+        }
+
+        let member = page::sort_key(agent_uri.capability_path(), uri_str);
+        redis::pipe()
+            .atomic()
+            .sadd(self.dht_key(&key), uri_str)
+            .ignore()
+            .zadd(self.path_index(agent_uri.trust_root().as_str()), &member, 0)
+            .ignore()
+            .zadd(self.global_index(), &member, 0)
+            .ignore()
+            .exec(&mut *conn)
+            .map_err(backend)?;
+        self.watchers.publish(&DhtEvent::Registered(registration));
+        Ok(())
+    }
+
+    /// Deletes a registration and its index entries.
+    fn erase(&self, agent_uri: &AgentUri) -> Result<(), DhtError> {
+        let uri_str = agent_uri.as_str();
+        let mut conn = self.lock();
+
+        let removed: usize = conn.del(self.registration_key(uri_str)).map_err(backend)?;
+        self.unindex(&mut conn, agent_uri)?;
+        if removed == 0 {
+            return Err(DhtError::not_found(uri_str));
+        }
+        self.watchers.publish(&DhtEvent::Deregistered(agent_uri.clone()));
+        Ok(())
+    }
+
+    /// Replaces a registration with a newer version, keeping the TTL the
+    /// new version carries.
+    fn replace(&self, registration: Registration) -> Result<(), DhtError> {
+        Endpoint::validate_all(registration.endpoints())?;
+        if let Some(validator) = &self.validator {
+            validator.validate(&registration)?;
+        }
+        let agent_uri = registration.agent_uri().clone();
+        let ttl = registration
+            .remaining_ttl()
+            .filter(|ttl| !ttl.is_zero())
+            .ok_or_else(|| DhtError::expired(agent_uri.as_str()))?;
+        self.modify(&agent_uri, Some(ttl), |stored| {
+            let changed = registration.check_update(stored)?;
+            if changed {
+                *stored = registration;
+            }
+            Ok(changed)
+        })
+    }
+
+    /// Loads the registrations stored under one [`DhtKey`].
+    fn load_exact(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let key = self.key(trust_root, capability_path);
+        let mut conn = self.lock();
+
+        let agent_uris: Vec<String> = conn.smembers(self.dht_key(&key)).map_err(backend)?;
+        let registrations = self.load(&mut conn, &agent_uris)?;
+        match self.max_results_per_query {
+            Some(max) if registrations.len() > max => Err(DhtError::result_limit_exceeded(max)),
+            _ => Ok(registrations),
+        }
+    }
+
+    /// Loads the registrations stored under each query's [`DhtKey`] in two
+    /// round trips: one for every key's members, one for their records.
+    fn load_many(
+        &self,
+        queries: &[(TrustRoot, CapabilityPath)],
+    ) -> Result<Vec<Result<Vec<Registration>, DhtError>>, DhtError> {
+        let keys: Vec<DhtKey> = queries
+            .iter()
+            .map(|(trust_root, capability_path)| self.key(trust_root, capability_path))
+            .collect();
+        let mut conn = self.lock();
+
+        let mut members = redis::pipe();
+        for key in &keys {
+            members.smembers(self.dht_key(key));
+        }
+        let members: Vec<Vec<String>> = members.query(&mut *conn).map_err(backend)?;
+        let mut agent_uris: Vec<String> = members.into_iter().flatten().collect();
+        agent_uris.sort_unstable();
+        agent_uris.dedup();
+
+        let mut by_key: HashMap<DhtKey, Vec<Registration>> = HashMap::new();
+        for registration in self.load(&mut conn, &agent_uris)? {
+            let agent_uri = registration.agent_uri();
+            let key = self.key(agent_uri.trust_root(), agent_uri.capability_path());
+            by_key.entry(key).or_default().push(registration);
+        }
+        Ok(keys
+            .iter()
+            .map(|key| {
+                let registrations = by_key.get(key).cloned().unwrap_or_default();
+                match self.max_results_per_query {
+                    Some(max) if registrations.len() > max => {
+                        Err(DhtError::result_limit_exceeded(max))
+                    }
+                    _ => Ok(registrations),
+                }
+            })
+            .collect())
+    }
+
+    /// Counts the live registrations by scanning their keys, so entries
+    /// left in the indexes by expired registrations are not counted.
+    fn count(&self) -> Result<DhtStats, DhtError> {
+        let prefix = self.registration_key("");
+        let mut conn = self.lock();
+        let records: Vec<String> = conn
+            .scan_match(format!("{prefix}*"))
+            .map_err(backend)?
+            .collect();
+        drop(conn);
+
+        let mut stats = DhtStats::new();
+        let mut per_key: HashMap<DhtKey, usize> = HashMap::new();
+        let mut trust_roots = HashSet::new();
+        for record in &records {
+            let Ok(agent_uri) = AgentUri::parse(&record[prefix.len()..]) else {
+                continue;
+            };
+            let depth = agent_uri.capability_path().depth();
+            if stats.path_depth_histogram.len() <= depth {
+                stats.path_depth_histogram.resize(depth + 1, 0);
+            }
+            stats.path_depth_histogram[depth] += 1;
+            stats.total_registrations += 1;
+            *per_key
+                .entry(self.key(agent_uri.trust_root(), agent_uri.capability_path()))
+                .or_default() += 1;
+            trust_roots.insert(agent_uri.trust_root().as_str().to_string());
+        }
+        stats.unique_keys = per_key.len();
+        stats.unique_trust_roots = trust_roots.len();
+        stats.max_registrations_per_key = per_key.values().copied().max().unwrap_or(0);
+        if stats.unique_keys > 0 {
+            stats.avg_registrations_per_key = ratio(stats.total_registrations, stats.unique_keys);
+        }
+        Ok(stats)
+    }
+
+    /// Loads the registrations in `index` whose paths match `pattern`.
+    fn load_pattern(
+        &self,
+        index: &str,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let prefix = pattern.literal_prefix();
+        let mut registrations = self.load_prefix(&mut self.lock(), index, prefix.as_ref())?;
+        registrations.retain(|r| pattern.matches(r.agent_uri().capability_path()));
+        Ok(registrations)
+    }
+}
+
+impl std::fmt::Debug for RedisDht {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisDht")
+            .field("namespace", &self.namespace)
+            .field("max_registrations_per_key", &self.max_registrations_per_key)
+            .field("max_results_per_query", &self.max_results_per_query)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Dht for RedisDht {
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.register", level = "debug", skip_all,
+        fields(agent_uri = %registration.agent_uri(),
+            trust_root = %registration.agent_uri().trust_root(),
+            depth = registration.agent_uri().capability_path().depth()),
+    ))]
+    fn register(&self, registration: Registration) -> Result<(), DhtError> {
+        telemetry::finish(self.store(registration))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.update_endpoint", level = "debug", skip_all,
+        fields(agent_uri = %agent_uri, trust_root = %agent_uri.trust_root(),
+            depth = agent_uri.capability_path().depth()),
+    ))]
+    fn update_endpoint(
+        &self,
+        agent_uri: &AgentUri,
+        new_endpoints: Vec<Endpoint>,
+    ) -> Result<(), DhtError> {
+        telemetry::finish(Endpoint::validate_all(&new_endpoints).and_then(|()| {
+            self.modify(agent_uri, None, |registration| {
+                registration.update_endpoints(new_endpoints);
+                Ok(true)
+            })
+        }))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.update_registration", level = "debug", skip_all,
+        fields(agent_uri = %registration.agent_uri(),
+            trust_root = %registration.agent_uri().trust_root(),
+            depth = registration.agent_uri().capability_path().depth(), seq = registration.seq()),
+    ))]
+    fn update_registration(&self, registration: Registration) -> Result<(), DhtError> {
+        telemetry::finish(self.replace(registration))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.renew", level = "debug", skip_all,
+        fields(agent_uri = %agent_uri, trust_root = %agent_uri.trust_root(),
+            depth = agent_uri.capability_path().depth()),
+    ))]
+    fn renew(&self, agent_uri: &AgentUri, ttl: Duration) -> Result<(), DhtError> {
+        telemetry::finish(self.modify(agent_uri, Some(ttl), |registration| {
+            registration.refresh(ttl);
+            Ok(true)
+        }))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.deregister", level = "debug", skip_all,
+        fields(agent_uri = %agent_uri, trust_root = %agent_uri.trust_root(),
+            depth = agent_uri.capability_path().depth()),
+    ))]
+    fn deregister(&self, agent_uri: &AgentUri) -> Result<(), DhtError> {
+        telemetry::finish(self.erase(agent_uri))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_exact", level = "debug", skip_all,
+        fields(trust_root = %trust_root, depth = capability_path.depth()),
+    ))]
+    fn lookup_exact(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        telemetry::finish(self.load_exact(trust_root, capability_path))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_many", level = "debug", skip_all, fields(queries = queries.len()),
+    ))]
+    fn lookup_many(
+        &self,
+        queries: &[(TrustRoot, CapabilityPath)],
+    ) -> Vec<Result<Vec<Registration>, DhtError>> {
+        match self.load_many(queries) {
+            Ok(results) => results.into_iter().map(telemetry::finish).collect(),
+            // A failed round trip fails every query in the batch
+            Err(error) => vec![telemetry::finish(Err(error)); queries.len()],
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_prefix", level = "debug", skip_all,
+        fields(trust_root = %trust_root, depth = capability_path.depth()),
+    ))]
+    fn lookup_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let index = self.path_index(trust_root.as_str());
+        telemetry::finish(self.load_prefix(&mut self.lock(), &index, Some(capability_path)))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_prefix_paged", level = "debug", skip_all,
+        fields(trust_root = %trust_root, depth = capability_path.depth(), limit),
+    ))]
+    fn lookup_prefix_paged(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        let limit = self
+            .max_results_per_query
+            .map_or(limit, |max| limit.min(max))
+            .max(1);
+        let index = self.path_index(trust_root.as_str());
+        let page = self.load_page(&mut self.lock(), &index, Some(capability_path), limit, cursor);
+        telemetry::finish(page)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_global", level = "debug", skip_all,
+        fields(depth = capability_path.depth()),
+    ))]
+    fn lookup_global(
+        &self,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let index = self.global_index();
+        telemetry::finish(self.load_prefix(&mut self.lock(), &index, Some(capability_path)))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_pattern", level = "debug", skip_all,
+        fields(trust_root = %trust_root, pattern = %pattern),
+    ))]
+    fn lookup_pattern(
+        &self,
+        trust_root: &TrustRoot,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let index = self.path_index(trust_root.as_str());
+        telemetry::finish(self.load_pattern(&index, pattern))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_pattern_global", level = "debug", skip_all,
+        fields(pattern = %pattern),
+    ))]
+    fn lookup_pattern_global(
+        &self,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        telemetry::finish(self.load_pattern(&self.global_index(), pattern))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_trust_root", level = "debug", skip_all,
+        fields(trust_root = %trust_root),
+    ))]
+    fn lookup_trust_root(&self, trust_root: &TrustRoot) -> Result<Vec<Registration>, DhtError> {
+        let index = self.path_index(trust_root.as_str());
+        telemetry::finish(self.load_prefix(&mut self.lock(), &index, None))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_trust_root_paged", level = "debug", skip_all,
+        fields(trust_root = %trust_root, limit),
+    ))]
+    fn lookup_trust_root_paged(
+        &self,
+        trust_root: &TrustRoot,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        let limit = self
+            .max_results_per_query
+            .map_or(limit, |max| limit.min(max))
+            .max(1);
+        let index = self.path_index(trust_root.as_str());
+        telemetry::finish(self.load_page(&mut self.lock(), &index, None, limit, cursor))
+    }
+
+    fn watch_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Receiver<DhtEvent>, DhtError> {
+        Ok(self.watchers.subscribe(trust_root, capability_path))
+    }
+
+    fn ping(&self) -> Result<(), DhtError> {
+        redis::cmd("PING").exec(&mut *self.lock()).map_err(backend)
+    }
+
+    /// Scans every registration key in the namespace; `memory_bytes` and
+    /// `latency` are not reported.
+    fn stats(&self) -> Result<DhtStats, DhtError> {
+        self.count()
+    }
+}
+
+/// Converts a TTL to whole milliseconds, rounding up so it never reaches
+/// zero.
+fn ttl_millis(ttl: Duration) -> u64 {
+    u64::try_from(ttl.as_millis().max(1)).unwrap_or(u64::MAX)
+}
+
+/// Returns the `ZRANGEBYLEX` bounds of the members at or below
+/// `capability_path`, or of every member if `None`.
+fn lex_range(capability_path: Option<&CapabilityPath>) -> (String, String) {
+    match capability_path {
+        // Members are "{path}/ {uri}", and '0' is the byte after '/'
+        Some(path) => (format!("[{}/", path.as_str()), format!("({}0", path.as_str())),
+        None => ("-".to_string(), "+".to_string()),
+    }
+}
+
+/// Returns the agent URIs of sorted-set members.
+fn member_uris(members: &[String]) -> Vec<String> {
+    members
+        .iter()
+        .filter_map(|member| member.split_once(' ').map(|(_, uri)| uri.to_string()))
+        .collect()
+}
+
+fn encode(registration: &Registration) -> Result<String, DhtError> {
+    serde_json::to_string(registration)
+        .map_err(|e| DhtError::internal(format!("failed to encode registration: {e}")))
+}
+
+fn decode(json: &str) -> Result<Registration, DhtError> {
+    serde_json::from_str(json)
+        .map_err(|e| DhtError::backend(format!("stored registration is malformed: {e}")))
+}
+
+#[allow(clippy::needless_pass_by_value)] // Used with `map_err`
+fn backend(error: RedisError) -> DhtError {
+    DhtError::backend(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LookupFilter;
+
+    fn test_uri(suffix: &str) -> AgentUri {
+        AgentUri::parse(&format!(
+            "agent://anthropic.com/assistant/chat/llm_01h455vb4pex5vsknk084sn0{suffix}"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn index_members_sort_within_their_prefix_range() {
+        let path = |p: &str| CapabilityPath::parse(p).unwrap();
+        let uri = test_uri("2q");
+        let (low, high) = ("assistant/", "assistant0");
+
+        for inside in ["assistant", "assistant/chat", "assistant/chat/x"] {
+            let member = page::sort_key(&path(inside), uri.as_str());
+            assert!(low <= member.as_str() && member.as_str() < high, "{member}");
+        }
+        for outside in ["assistantx", "assist", "billing"] {
+            let member = page::sort_key(&path(outside), uri.as_str());
+            assert!(!(low <= member.as_str() && member.as_str() < high), "{member}");
+        }
+    }
+
+    #[test]
+    fn ttl_is_never_rounded_to_zero() {
+        assert_eq!(ttl_millis(Duration::from_micros(10)), 1);
+        assert_eq!(ttl_millis(Duration::from_secs(2)), 2000);
+    }
+
+    /// Runs against the server in `REDIS_URL`, flushing its namespace.
+    #[test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    fn register_lookup_and_expire_against_server() {
+        let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".into());
+        let dht = RedisDht::connect(&url)
+            .unwrap()
+            .with_namespace(format!("agent-dht-test-{}", std::process::id()));
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        let endpoint = Endpoint::https("agent.anthropic.com:443");
+        let events = dht
+            .watch_prefix(&trust_root, &CapabilityPath::parse("assistant").unwrap())
+            .unwrap();
+
+        let long = Registration::new(test_uri("2q"), vec![endpoint.clone()]);
+        let short = Registration::new(test_uri("3q"), vec![endpoint.clone()])
+            .with_ttl(Duration::from_millis(200));
+        assert!(dht.health().is_healthy());
+        dht.register(long.clone()).unwrap();
+        dht.register(short).unwrap();
+        let stats = dht.stats().unwrap();
+        assert_eq!((stats.total_registrations, stats.unique_keys), (2, 1));
+        assert!(matches!(
+            dht.register(long.clone()),
+            Err(DhtError::AlreadyRegistered { .. })
+        ));
+        dht.update_registration(long.clone()).unwrap();
+        assert!(matches!(
+            dht.update_registration(long.clone().with_metadata("tier", "beta")),
+            Err(DhtError::SequenceConflict { seq: 0, .. })
+        ));
+
+        let chat = CapabilityPath::parse("assistant/chat").unwrap();
+        assert_eq!(dht.lookup_exact(&trust_root, &chat).unwrap().len(), 2);
+        let code = CapabilityPath::parse("assistant/code").unwrap();
+        let batch =
+            dht.lookup_many(&[(trust_root.clone(), chat.clone()), (trust_root.clone(), code)]);
+        assert_eq!(batch[0].as_ref().map(Vec::len), Ok(2));
+        assert_eq!(batch[1].as_ref().map(Vec::len), Ok(0));
+        let assistant = CapabilityPath::parse("assistant").unwrap();
+        assert_eq!(dht.lookup_global(&assistant).unwrap().len(), 2);
+        let first = dht.lookup_prefix_paged(&trust_root, &assistant, 1, None).unwrap();
+        assert_eq!(first.registrations[0], long);
+        let rest = dht
+            .lookup_prefix_paged(&trust_root, &assistant, 1, first.next_cursor.as_ref())
+            .unwrap();
+        assert_eq!(rest.registrations.len(), 1);
+        assert!(rest.next_cursor.is_none());
+        let grpc = LookupFilter::new().with_protocol("grpc");
+        let none = dht
+            .lookup_prefix_paged_filtered(&trust_root, &assistant, 1, None, &grpc)
+            .unwrap();
+        assert!(none.registrations.is_empty() && none.next_cursor.is_none());
+        assert_eq!(dht.lookup_trust_root(&trust_root).unwrap().len(), 2);
+        let pattern = PathPattern::parse("*/chat").unwrap();
+        assert_eq!(dht.lookup_pattern(&trust_root, &pattern).unwrap().len(), 2);
+        assert_eq!(dht.lookup_pattern_global(&pattern).unwrap().len(), 2);
+        let whole = dht.lookup_trust_root_paged(&trust_root, 1, None).unwrap();
+        assert!(whole.next_cursor.is_some());
+
+        std::thread::sleep(Duration::from_millis(300));
+        let found = dht.lookup_prefix(&trust_root, &assistant).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0], long);
+
+        let moved = vec![Endpoint::https("eu.agent.anthropic.com")];
+        dht.update_endpoint(long.agent_uri(), moved.clone()).unwrap();
+        dht.renew(long.agent_uri(), Duration::from_mins(1)).unwrap();
+        assert_eq!(dht.lookup_exact(&trust_root, &chat).unwrap()[0].endpoints(), moved);
+
+        dht.deregister(long.agent_uri()).unwrap();
+        assert!(dht.deregister(long.agent_uri()).unwrap_err().is_not_found());
+        assert!(dht.lookup_global(&assistant).unwrap().is_empty());
+
+        let received: Vec<_> = events.try_iter().collect();
+        assert_eq!(received[0], DhtEvent::Registered(long.clone()));
+        assert_eq!(received[2], DhtEvent::Expired(test_uri("3q")));
+        assert_eq!(received.last(), Some(&DhtEvent::Deregistered(test_uri("2q"))));
+    }
+}
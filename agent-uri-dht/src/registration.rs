@@ -1,15 +1,26 @@
 //! Registration records for DHT storage.
 
+use std::collections::BTreeMap;
 use std::time::{Duration, SystemTime};
 
 use agent_uri::AgentUri;
 
-use crate::Endpoint;
+use crate::{DhtError, Endpoint, EndpointSelector};
 
 /// A registration record stored in the DHT.
 ///
 /// Contains all information needed to contact an agent and verify its identity.
-/// Registrations have a TTL and must be refreshed to remain active.
+/// Registrations have a TTL and must be refreshed to remain active. Each
+/// registration picks its own TTL and refresh-ahead window, so short-lived
+/// burst agents and long-lived services can share a registry; registries
+/// may bound the TTLs they grant (see
+/// [`SimulationConfig::min_ttl`](crate::SimulationConfig::min_ttl)).
+///
+/// Each version of a record carries a sequence number and the time its
+/// signer wrote it. Updates through
+/// [`Dht::update_registration`](crate::Dht::update_registration) must
+/// carry a higher sequence number than the stored record, so concurrent
+/// writers cannot silently overwrite each other.
 ///
 /// # Examples
 ///
@@ -32,15 +43,26 @@ pub struct Registration {
     endpoints: Vec<Endpoint>,
     /// Attestation token proving capability claims (PASETO).
     attestation: Option<String>,
+    /// Nonce solving the registry's proof-of-work challenge.
+    proof_of_work: Option<u64>,
+    /// Free-form key/value annotations, e.g. deployment tier.
+    metadata: BTreeMap<String, String>,
     /// When this registration expires.
     expires_at: SystemTime,
     /// When this registration was created.
     registered_at: SystemTime,
+    /// Version of this record, increased by every update.
+    seq: u64,
+    /// When the signer wrote this version of the record.
+    signed_at: SystemTime,
+    /// How long before expiry the registration is due for renewal; None
+    /// means the last half of its lifetime.
+    refresh_ahead: Option<Duration>,
 }
 
 impl Registration {
     /// Default TTL for registrations (1 hour).
-    pub const DEFAULT_TTL: Duration = Duration::from_secs(3600);
+    pub const DEFAULT_TTL: Duration = Duration::from_hours(1);
 
     /// Creates a new registration with default TTL.
     ///
@@ -55,8 +77,13 @@ impl Registration {
             agent_uri,
             endpoints,
             attestation: None,
+            proof_of_work: None,
+            metadata: BTreeMap::new(),
             expires_at: now + Self::DEFAULT_TTL,
             registered_at: now,
+            seq: 0,
+            signed_at: now,
+            refresh_ahead: None,
         }
     }
 
@@ -67,6 +94,14 @@ impl Registration {
         self
     }
 
+    /// Sets how long before expiry the registration is due for renewal;
+    /// see [`should_refresh`](Self::should_refresh).
+    #[must_use]
+    pub const fn with_refresh_ahead(mut self, window: Duration) -> Self {
+        self.refresh_ahead = Some(window);
+        self
+    }
+
     /// Sets the attestation token.
     #[must_use]
     pub fn with_attestation(mut self, attestation: impl Into<String>) -> Self {
@@ -74,6 +109,20 @@ impl Registration {
         self
     }
 
+    /// Sets the proof-of-work nonce; see [`ProofOfWork`](crate::ProofOfWork).
+    #[must_use]
+    pub const fn with_proof_of_work(mut self, nonce: u64) -> Self {
+        self.proof_of_work = Some(nonce);
+        self
+    }
+
+    /// Adds a metadata entry, replacing any previous value for `key`.
+    #[must_use]
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
     /// Sets the expiration time directly.
     #[must_use]
     pub fn with_expires_at(mut self, expires_at: SystemTime) -> Self {
@@ -88,6 +137,20 @@ impl Registration {
         self
     }
 
+    /// Sets the sequence number; see [`seq`](Self::seq).
+    #[must_use]
+    pub const fn with_seq(mut self, seq: u64) -> Self {
+        self.seq = seq;
+        self
+    }
+
+    /// Sets the time the signer wrote this version of the record.
+    #[must_use]
+    pub fn with_signed_at(mut self, signed_at: SystemTime) -> Self {
+        self.signed_at = signed_at;
+        self
+    }
+
     /// Returns the agent URI.
     #[must_use]
     pub fn agent_uri(&self) -> &AgentUri {
@@ -100,28 +163,86 @@ impl Registration {
         &self.endpoints
     }
 
+    /// Returns the endpoint `selector` picks, or `None` if it finds none
+    /// suitable.
+    #[must_use]
+    pub fn select_endpoint<S>(&self, selector: &S) -> Option<&Endpoint>
+    where
+        S: EndpointSelector + ?Sized,
+    {
+        selector.select(&self.endpoints)
+    }
+
     /// Returns the attestation token, if any.
     #[must_use]
     pub fn attestation(&self) -> Option<&str> {
         self.attestation.as_deref()
     }
 
+    /// Returns the proof-of-work nonce, if any.
+    #[must_use]
+    pub const fn proof_of_work(&self) -> Option<u64> {
+        self.proof_of_work
+    }
+
+    /// Returns the metadata entries.
+    #[must_use]
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
     /// Returns the expiration time.
     #[must_use]
     pub fn expires_at(&self) -> SystemTime {
         self.expires_at
     }
 
+    /// Returns the lifetime granted at registration or the last renewal:
+    /// the time from [`registered_at`](Self::registered_at) to expiry.
+    #[must_use]
+    pub fn ttl(&self) -> Duration {
+        self.expires_at
+            .duration_since(self.registered_at)
+            .unwrap_or_default()
+    }
+
+    /// Returns the refresh-ahead window set with
+    /// [`with_refresh_ahead`](Self::with_refresh_ahead), or None if the
+    /// registration uses the default.
+    #[must_use]
+    pub const fn refresh_policy(&self) -> Option<Duration> {
+        self.refresh_ahead
+    }
+
     /// Returns the registration time.
     #[must_use]
     pub fn registered_at(&self) -> SystemTime {
         self.registered_at
     }
 
+    /// Returns the sequence number: 0 for a new registration, increased
+    /// by every update.
+    #[must_use]
+    pub const fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Returns the time the signer wrote this version of the record.
+    #[must_use]
+    pub fn signed_at(&self) -> SystemTime {
+        self.signed_at
+    }
+
     /// Returns true if this registration has expired.
     #[must_use]
     pub fn is_expired(&self) -> bool {
-        SystemTime::now() >= self.expires_at
+        self.is_expired_at(SystemTime::now())
+    }
+
+    /// Returns true if this registration has expired by `now`.
+    #[must_use]
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        now >= self.expires_at
     }
 
     /// Returns the remaining TTL, or None if expired.
@@ -130,14 +251,69 @@ impl Registration {
         self.expires_at.duration_since(SystemTime::now()).ok()
     }
 
-    /// Updates the endpoints for this registration.
+    /// Returns how long before expiry the registration is due for renewal:
+    /// the window set with [`with_refresh_ahead`](Self::with_refresh_ahead),
+    /// or else the last half of its lifetime, never more than the whole
+    /// lifetime.
+    #[must_use]
+    pub fn refresh_ahead(&self) -> Duration {
+        let lifetime = self.ttl();
+        self.refresh_ahead.unwrap_or(lifetime / 2).min(lifetime)
+    }
+
+    /// Returns when the registration becomes due for renewal.
+    #[must_use]
+    pub fn refresh_at(&self) -> SystemTime {
+        self.expires_at
+            .checked_sub(self.refresh_ahead())
+            .unwrap_or(self.registered_at)
+    }
+
+    /// Returns true if the registration is within its refresh-ahead window
+    /// at `now`, or has expired, and should be renewed.
+    #[must_use]
+    pub fn should_refresh(&self, now: SystemTime) -> bool {
+        now >= self.refresh_at()
+    }
+
+    /// Updates the endpoints for this registration, moving it to the next
+    /// sequence number signed now.
     pub fn update_endpoints(&mut self, endpoints: Vec<Endpoint>) {
         self.endpoints = endpoints;
+        self.seq = self.seq.saturating_add(1);
+        self.signed_at = SystemTime::now();
+    }
+
+    /// Checks that this record may replace `current`, the stored version
+    /// of the same agent's record.
+    ///
+    /// Returns false if this is the stored version delivered again, which
+    /// leaves nothing to write.
+    pub(crate) fn check_update(&self, current: &Self) -> Result<bool, DhtError> {
+        let uri_str = self.agent_uri.as_str();
+        if self.seq < current.seq {
+            return Err(DhtError::stale_write(uri_str, current.seq, self.seq));
+        }
+        if self.seq > current.seq {
+            return Ok(true);
+        }
+        if self.endpoints == current.endpoints
+            && self.attestation == current.attestation
+            && self.metadata == current.metadata
+        {
+            Ok(false)
+        } else {
+            Err(DhtError::sequence_conflict(uri_str, self.seq))
+        }
     }
 
     /// Refreshes the registration with a new TTL from now.
     pub fn refresh(&mut self, ttl: Duration) {
-        let now = SystemTime::now();
+        self.refresh_from(SystemTime::now(), ttl);
+    }
+
+    /// Refreshes the registration with a new TTL from `now`.
+    pub fn refresh_from(&mut self, now: SystemTime, ttl: Duration) {
         self.registered_at = now;
         self.expires_at = now + ttl;
     }
@@ -158,15 +334,12 @@ impl std::hash::Hash for Registration {
     }
 }
 
-#[cfg(feature = "serde")]
-fn system_time_to_millis(time: SystemTime) -> u64 {
+pub(crate) fn system_time_to_millis(time: SystemTime) -> u64 {
     time.duration_since(SystemTime::UNIX_EPOCH)
-        .map(|d| d.as_millis() as u64)
-        .unwrap_or(0)
+        .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
 }
 
-#[cfg(feature = "serde")]
-fn millis_to_system_time(millis: u64) -> SystemTime {
+pub(crate) fn millis_to_system_time(millis: u64) -> SystemTime {
     SystemTime::UNIX_EPOCH + Duration::from_millis(millis)
 }
 
@@ -177,12 +350,20 @@ impl serde::Serialize for Registration {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("Registration", 5)?;
+        let mut state = serializer.serialize_struct("Registration", 10)?;
         state.serialize_field("agent_uri", self.agent_uri.as_str())?;
         state.serialize_field("endpoints", &self.endpoints)?;
         state.serialize_field("attestation", &self.attestation)?;
+        state.serialize_field("proof_of_work", &self.proof_of_work)?;
+        state.serialize_field("metadata", &self.metadata)?;
         state.serialize_field("expires_at", &system_time_to_millis(self.expires_at))?;
         state.serialize_field("registered_at", &system_time_to_millis(self.registered_at))?;
+        state.serialize_field("seq", &self.seq)?;
+        state.serialize_field("signed_at", &system_time_to_millis(self.signed_at))?;
+        let refresh_ahead = self.refresh_ahead.map(|window| {
+            u64::try_from(window.as_millis()).unwrap_or(u64::MAX)
+        });
+        state.serialize_field("refresh_ahead_ms", &refresh_ahead)?;
         state.end()
     }
 }
@@ -193,15 +374,23 @@ impl<'de> serde::Deserialize<'de> for Registration {
     where
         D: serde::Deserializer<'de>,
     {
-        use serde::de::Deserialize;
-
         #[derive(serde::Deserialize)]
         struct RegistrationData {
             agent_uri: String,
             endpoints: Vec<Endpoint>,
             attestation: Option<String>,
+            #[serde(default)]
+            proof_of_work: Option<u64>,
+            #[serde(default)]
+            metadata: BTreeMap<String, String>,
             expires_at: u64,
             registered_at: u64,
+            #[serde(default)]
+            seq: u64,
+            #[serde(default)]
+            signed_at: Option<u64>,
+            #[serde(default)]
+            refresh_ahead_ms: Option<u64>,
         }
 
         let data = RegistrationData::deserialize(deserializer)?;
@@ -212,8 +401,13 @@ impl<'de> serde::Deserialize<'de> for Registration {
             agent_uri,
             endpoints: data.endpoints,
             attestation: data.attestation,
+            proof_of_work: data.proof_of_work,
+            metadata: data.metadata,
             expires_at: millis_to_system_time(data.expires_at),
             registered_at: millis_to_system_time(data.registered_at),
+            seq: data.seq,
+            signed_at: millis_to_system_time(data.signed_at.unwrap_or(data.registered_at)),
+            refresh_ahead: data.refresh_ahead_ms.map(Duration::from_millis),
         })
     }
 }
@@ -241,7 +435,7 @@ mod tests {
     #[test]
     fn with_ttl_sets_expiration() {
         let registration = Registration::new(test_uri(), vec![test_endpoint()])
-            .with_ttl(Duration::from_secs(60));
+            .with_ttl(Duration::from_mins(1));
         let remaining = registration.remaining_ttl().unwrap();
         // Should be close to 60 seconds, allow for some test execution time
         assert!(remaining.as_secs() <= 60);
@@ -255,6 +449,28 @@ mod tests {
         assert_eq!(registration.attestation(), Some("token123"));
     }
 
+    #[test]
+    fn with_metadata_replaces_existing_values() {
+        let registration = Registration::new(test_uri(), vec![test_endpoint()])
+            .with_metadata("tier", "beta")
+            .with_metadata("tier", "stable");
+        assert_eq!(registration.metadata().get("tier").map(String::as_str), Some("stable"));
+    }
+
+    #[test]
+    fn select_endpoint_delegates_to_selector() {
+        let grpc = Endpoint::grpc("agent.anthropic.com:50051");
+        let registration = Registration::new(test_uri(), vec![test_endpoint(), grpc.clone()]);
+        assert_eq!(registration.select_endpoint(&crate::FirstEndpoint), Some(&test_endpoint()));
+
+        let round_robin = crate::RoundRobin::new();
+        assert_eq!(registration.select_endpoint(&round_robin), Some(&test_endpoint()));
+        assert_eq!(registration.select_endpoint(&round_robin), Some(&grpc));
+
+        let empty = Registration::new(test_uri(), Vec::new());
+        assert!(empty.select_endpoint(&crate::FirstEndpoint).is_none());
+    }
+
     #[test]
     fn expired_registration() {
         let past = SystemTime::now() - Duration::from_secs(10);
@@ -264,24 +480,69 @@ mod tests {
         assert!(registration.remaining_ttl().is_none());
     }
 
+    #[test]
+    fn refresh_ahead_window() {
+        let registration = Registration::new(test_uri(), vec![test_endpoint()])
+            .with_ttl(Duration::from_secs(100));
+        let registered_at = registration.registered_at();
+        assert_eq!(registration.refresh_ahead(), Duration::from_secs(50));
+        assert!(!registration.should_refresh(registered_at + Duration::from_secs(49)));
+        assert!(registration.should_refresh(registered_at + Duration::from_secs(50)));
+
+        let registration = registration.with_refresh_ahead(Duration::from_secs(10));
+        assert_eq!(registration.refresh_at(), registered_at + Duration::from_secs(90));
+        assert!(!registration.should_refresh(registered_at + Duration::from_secs(89)));
+        assert!(registration.should_refresh(registered_at + Duration::from_secs(200)));
+
+        // A window longer than the lifetime makes the registration due at once
+        let registration = registration.with_refresh_ahead(Duration::from_secs(500));
+        assert!(registration.should_refresh(registered_at));
+    }
+
     #[test]
     fn update_endpoints() {
         let mut registration = Registration::new(test_uri(), vec![test_endpoint()]);
         let new_endpoint = Endpoint::grpc("agent.anthropic.com:50051");
         registration.update_endpoints(vec![new_endpoint.clone()]);
         assert_eq!(registration.endpoints(), &[new_endpoint]);
+        assert_eq!(registration.seq(), 1);
+    }
+
+    #[test]
+    fn check_update_orders_by_sequence() {
+        let signed_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let current = Registration::new(test_uri(), vec![test_endpoint()])
+            .with_seq(2)
+            .with_signed_at(signed_at);
+        let grpc = vec![Endpoint::grpc("agent.anthropic.com:50051")];
+
+        let newer = Registration::new(test_uri(), grpc.clone()).with_seq(3);
+        assert_eq!(newer.check_update(&current), Ok(true));
+        let redelivered = current.clone();
+        assert_eq!(redelivered.check_update(&current), Ok(false));
+
+        let stale = Registration::new(test_uri(), grpc.clone()).with_seq(1);
+        assert_eq!(
+            stale.check_update(&current),
+            Err(DhtError::stale_write(test_uri().as_str(), 2, 1))
+        );
+        let rival = Registration::new(test_uri(), grpc).with_seq(2).with_signed_at(signed_at);
+        assert_eq!(
+            rival.check_update(&current),
+            Err(DhtError::sequence_conflict(test_uri().as_str(), 2))
+        );
     }
 
     #[test]
     fn refresh_updates_times() {
-        let past = SystemTime::now() - Duration::from_secs(3600);
+        let past = SystemTime::now() - Duration::from_hours(1);
         let mut registration = Registration::new(test_uri(), vec![test_endpoint()])
             .with_registered_at(past)
             .with_expires_at(past);
 
         assert!(registration.is_expired());
 
-        registration.refresh(Duration::from_secs(60));
+        registration.refresh(Duration::from_mins(1));
 
         assert!(!registration.is_expired());
         assert!(registration.remaining_ttl().is_some());
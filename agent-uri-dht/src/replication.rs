@@ -0,0 +1,402 @@
+//! Replication strategies and durability measurements for a
+//! [`NetworkSimulation`].
+
+use std::time::Duration;
+
+use crate::NetworkSimulation;
+use crate::churn::{SplitMix, ratio};
+
+/// How a write reaches the replicas of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReplicationStrategy {
+    /// Every replica is written before the write completes
+    Eager,
+    /// Only the given number of closest replicas are written; the rest
+    /// catch up at the next [`repair`](NetworkSimulation::repair)
+    Lazy(usize),
+    /// Every replica is sent the write, which is acknowledged once the
+    /// given number of them (W) have stored it; the slower replicas store
+    /// it as simulated time [advances](NetworkSimulation::advance)
+    Quorum(usize),
+}
+
+/// How many replicas a lookup reads before answering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReadStrategy {
+    /// Answer from the first replica that holds the record
+    First,
+    /// Read the given number of replicas (R) and answer with the newest
+    /// version of each record among them
+    ///
+    /// With W + R greater than the replication factor, a read overlaps
+    /// every acknowledged write.
+    Quorum(usize),
+}
+
+/// Durability and consistency of the records in a [`NetworkSimulation`].
+///
+/// Returned by [`NetworkSimulation::replication_report`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplicationReport {
+    /// Keys registered in the network
+    pub keys: usize,
+    /// Keys with at least one live replica on an online node
+    pub durable_keys: usize,
+    /// Keys whose latest writes are held by at least the replication factor
+    /// of online nodes
+    pub fully_replicated_keys: usize,
+    /// Up-to-date replicas summed over all keys
+    pub up_to_date_replicas: usize,
+    /// Lookups run
+    pub reads: usize,
+    /// Lookups that found records
+    pub successful_reads: usize,
+    /// Successful lookups that returned the latest write of every agent
+    pub consistent_reads: usize,
+}
+
+impl ReplicationReport {
+    /// Returns the fraction of keys that survived, or 1.0 if none are
+    /// registered.
+    #[must_use]
+    pub fn durability(&self) -> f64 {
+        ratio(self.durable_keys, self.keys)
+    }
+
+    /// Returns the fraction of keys at full replication, or 1.0 if none are
+    /// registered.
+    #[must_use]
+    pub fn full_replication(&self) -> f64 {
+        ratio(self.fully_replicated_keys, self.keys)
+    }
+
+    /// Returns the mean number of up-to-date replicas per key, or 0.0 if
+    /// none are registered.
+    #[must_use]
+    pub fn mean_replicas(&self) -> f64 {
+        if self.keys == 0 {
+            0.0
+        } else {
+            ratio(self.up_to_date_replicas, self.keys)
+        }
+    }
+
+    /// Returns the fraction of lookups that found records, or 1.0 if none
+    /// ran.
+    #[must_use]
+    pub fn read_success_rate(&self) -> f64 {
+        ratio(self.successful_reads, self.reads)
+    }
+
+    /// Returns the fraction of successful lookups that were not stale, or
+    /// 1.0 if none succeeded.
+    #[must_use]
+    pub fn consistency(&self) -> f64 {
+        ratio(self.consistent_reads, self.successful_reads)
+    }
+}
+
+/// Staleness of reads made while writes propagate.
+///
+/// Returned by [`NetworkSimulation::measure_staleness`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StalenessReport {
+    /// Reads made
+    pub reads: usize,
+    /// Reads that found records
+    pub successful_reads: usize,
+    /// Successful reads that missed a latest write
+    pub stale_reads: usize,
+    /// Staleness summed over stale reads
+    pub total_staleness: Duration,
+    /// Largest staleness of any read
+    pub max_staleness: Duration,
+    /// Replicas brought up to date by read repair
+    pub repaired: usize,
+    /// Routing latency summed over all reads
+    pub total_latency: Duration,
+}
+
+impl StalenessReport {
+    /// Returns the fraction of successful reads that were stale, or 0.0 if
+    /// none succeeded.
+    #[must_use]
+    pub fn stale_rate(&self) -> f64 {
+        if self.successful_reads == 0 {
+            0.0
+        } else {
+            ratio(self.stale_reads, self.successful_reads)
+        }
+    }
+
+    /// Returns the mean staleness of stale reads.
+    #[must_use]
+    pub fn mean_staleness(&self) -> Duration {
+        mean(self.total_staleness, self.stale_reads)
+    }
+
+    /// Returns the mean routing latency of a read.
+    #[must_use]
+    pub fn mean_latency(&self) -> Duration {
+        mean(self.total_latency, self.reads)
+    }
+}
+
+fn mean(total: Duration, count: usize) -> Duration {
+    u32::try_from(count)
+        .ok()
+        .filter(|&count| count > 0)
+        .map_or(Duration::ZERO, |count| total / count)
+}
+
+impl NetworkSimulation {
+    /// Makes `reads` [`read`](Self::read)s of random keys from random
+    /// online nodes, advancing simulated time by `interval` after each, and
+    /// reports how stale they were.
+    ///
+    /// Register under [`ReplicationStrategy::Quorum`] first, then compare
+    /// reports across W, R, read repair and read intervals to see how soon
+    /// registrations become visible everywhere.
+    pub fn measure_staleness(
+        &mut self,
+        reads: usize,
+        interval: Duration,
+        seed: u64,
+    ) -> StalenessReport {
+        let keys: Vec<_> = self.published().iter().copied().collect();
+        let mut report = StalenessReport::default();
+        if keys.is_empty() {
+            return report;
+        }
+
+        let mut rng = SplitMix(seed);
+        for _ in 0..reads {
+            if self.online_count() == 0 {
+                break;
+            }
+            let origin = self.random_online(&mut rng);
+            let lookup = self.read(origin, &keys[rng.below(keys.len())]);
+            report.reads += 1;
+            report.repaired += lookup.repaired;
+            report.total_latency += lookup.metrics.latency;
+            if !lookup.registrations.is_empty() {
+                report.successful_reads += 1;
+            }
+            if lookup.stale {
+                report.stale_reads += 1;
+                report.total_staleness += lookup.staleness;
+                report.max_staleness = report.max_staleness.max(lookup.staleness);
+            }
+            self.advance(interval);
+        }
+        report
+    }
+
+    /// Measures how well the network's records have survived: how many
+    /// keys still have replicas and how many of those are current, then
+    /// runs `reads` lookups of random keys from random online nodes with
+    /// the configured [`ReadStrategy`].
+    ///
+    /// Fail nodes with [`fail`](Self::fail) or a churn schedule first to
+    /// measure durability under failure; comparing reports across
+    /// replication factors shows what each one buys.
+    #[must_use]
+    pub fn replication_report(&self, reads: usize, seed: u64) -> ReplicationReport {
+        let replication = self.config().replication_factor();
+        let keys: Vec<_> = self.published().iter().copied().collect();
+        let mut report = ReplicationReport {
+            keys: keys.len(),
+            ..ReplicationReport::default()
+        };
+
+        for key in &keys {
+            if self.is_available(key) {
+                report.durable_keys += 1;
+            }
+            let replicas = self.up_to_date_replicas(key);
+            report.up_to_date_replicas += replicas;
+            if replicas >= replication {
+                report.fully_replicated_keys += 1;
+            }
+        }
+
+        if keys.is_empty() || self.online_count() == 0 {
+            return report;
+        }
+        let mut rng = SplitMix(seed);
+        for _ in 0..reads {
+            let origin = self.random_online(&mut rng);
+            let lookup = self.lookup_key(origin, &keys[rng.below(keys.len())]);
+            report.reads += 1;
+            if !lookup.registrations.is_empty() {
+                report.successful_reads += 1;
+                if !lookup.stale {
+                    report.consistent_reads += 1;
+                }
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Endpoint, NetworkConfig, Registration};
+    use agent_uri::AgentUri;
+
+    fn registration(i: usize, host: &str) -> Registration {
+        let uri = AgentUri::parse(&format!(
+            "agent://acme.com/assistant/skill{i}/llm_01h455vb4pex5vsknk084sn02q"
+        ))
+        .unwrap();
+        Registration::new(uri, vec![Endpoint::https(host)])
+    }
+
+    fn populated(config: NetworkConfig, agents: usize) -> NetworkSimulation {
+        let mut network = NetworkSimulation::new(config);
+        for i in 0..agents {
+            network
+                .register(i % network.node_count(), registration(i, "v1.acme.com"))
+                .unwrap();
+        }
+        network
+    }
+
+    #[test]
+    fn higher_replication_survives_more_failures() {
+        let durability = |replication: usize| {
+            let config = NetworkConfig::new()
+                .with_nodes(200)
+                .with_k(8)
+                .with_replication(replication);
+            let mut network = populated(config, 50);
+            for index in (0..200).step_by(2) {
+                network.fail(index);
+            }
+            network.replication_report(0, 0).durability()
+        };
+
+        let (one, three, eight) = (durability(1), durability(3), durability(8));
+        assert!(one < 0.8, "replication 1 kept {one}");
+        assert!(one <= three && three <= eight);
+        assert!(eight > 0.95, "replication 8 kept {eight}");
+    }
+
+    #[test]
+    fn lazy_writes_leave_stale_replicas_until_repair() {
+        let build = |read: ReadStrategy| {
+            let config = NetworkConfig::new()
+                .with_nodes(100)
+                .with_k(8)
+                .with_replication_strategy(ReplicationStrategy::Lazy(1))
+                .with_read_strategy(read);
+            let mut network = populated(config, 10);
+            network.repair();
+            for i in 0..10 {
+                network.register(i, registration(i, "v2.acme.com")).unwrap();
+            }
+            network
+        };
+
+        let first = build(ReadStrategy::First);
+        let report = first.replication_report(200, 1);
+        assert_eq!(report.successful_reads, report.reads);
+        assert!(report.consistent_reads < report.successful_reads);
+        assert_eq!(report.fully_replicated_keys, 0);
+        assert!((report.mean_replicas() - 1.0).abs() < f64::EPSILON);
+
+        let quorum = build(ReadStrategy::Quorum(8));
+        let report = quorum.replication_report(200, 1);
+        assert_eq!(report.consistent_reads, report.reads);
+
+        let mut repaired = first;
+        repaired.repair();
+        let report = repaired.replication_report(200, 1);
+        assert_eq!(report.consistent_reads, report.reads);
+        assert_eq!(report.fully_replicated_keys, report.keys);
+    }
+
+    #[test]
+    fn eager_writes_are_consistent_immediately() {
+        let config = NetworkConfig::new().with_nodes(100).with_k(8).with_replication(4);
+        let mut network = populated(config, 10);
+        for i in 0..10 {
+            network.register(i, registration(i, "v2.acme.com")).unwrap();
+        }
+
+        let report = network.replication_report(100, 2);
+        assert_eq!(report.fully_replicated_keys, report.keys);
+        assert_eq!(report.consistent_reads, report.reads);
+        assert_eq!(report.up_to_date_replicas, 40);
+    }
+
+    fn quorum_network(write: usize, read: ReadStrategy, read_repair: bool) -> NetworkSimulation {
+        let config = NetworkConfig::new()
+            .with_nodes(100)
+            .with_k(8)
+            .with_replication(5)
+            .with_replication_strategy(ReplicationStrategy::Quorum(write))
+            .with_read_strategy(read)
+            .with_read_repair(read_repair);
+        let mut network = populated(config, 10);
+        network.advance(Duration::from_secs(1));
+        for i in 0..10 {
+            let outcome = network.register(i, registration(i, "v2.acme.com")).unwrap();
+            assert_eq!((outcome.acks, outcome.replicas), (write, 5));
+        }
+        network
+    }
+
+    #[test]
+    fn quorum_writes_propagate_as_time_advances() {
+        let mut network = quorum_network(1, ReadStrategy::First, false);
+        assert_eq!(network.pending_writes(), 40);
+
+        let report = network.measure_staleness(100, Duration::ZERO, 3);
+        assert_eq!(report.successful_reads, report.reads);
+        assert!(report.stale_reads > 0);
+
+        network.advance(Duration::from_secs(1));
+        assert_eq!(network.pending_writes(), 0);
+        let report = network.measure_staleness(100, Duration::ZERO, 3);
+        assert_eq!(report.stale_reads, 0);
+    }
+
+    #[test]
+    fn overlapping_quorums_are_never_stale() {
+        let mut network = quorum_network(3, ReadStrategy::Quorum(3), false);
+        let report = network.measure_staleness(200, Duration::ZERO, 4);
+        assert_eq!(report.successful_reads, report.reads);
+        assert_eq!(report.stale_reads, 0);
+    }
+
+    #[test]
+    fn read_repair_updates_the_replicas_read() {
+        let mut network = quorum_network(1, ReadStrategy::Quorum(5), true);
+        let key = *network.published().iter().next().unwrap();
+        assert_eq!(network.up_to_date_replicas(&key), 1);
+
+        let lookup = network.read(0, &key);
+        assert!(!lookup.stale);
+        assert_eq!(lookup.repaired, 4);
+        assert_eq!(network.up_to_date_replicas(&key), 5);
+    }
+
+    #[test]
+    fn larger_write_quorums_take_longer_to_acknowledge() {
+        let latency = |write: usize| {
+            let config = NetworkConfig::new()
+                .with_nodes(100)
+                .with_replication(5)
+                .with_replication_strategy(ReplicationStrategy::Quorum(write));
+            let mut network = NetworkSimulation::new(config);
+            (0..10)
+                .map(|i| network.register(i, registration(i, "v1.acme.com")).unwrap())
+                .map(|outcome| outcome.latency.saturating_sub(outcome.metrics.latency))
+                .sum::<Duration>()
+        };
+        assert!(latency(1) < latency(3));
+        assert!(latency(3) < latency(5));
+    }
+}
@@ -0,0 +1,784 @@
+//! HTTP registry client implementing the [`Dht`] trait.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+use serde::Deserialize;
+use ureq::http::Request;
+
+use crate::telemetry;
+use crate::watch::Watchers;
+use crate::{
+    Dht, DhtError, DhtEvent, Endpoint, LookupCursor, LookupPage, PathPattern, Registration,
+};
+
+/// Produces the `Authorization` header value for a request.
+type AuthProvider = dyn Fn() -> Result<String, DhtError> + Send + Sync;
+
+/// A client for a centralized registry served over HTTP (feature `rest`).
+///
+/// For organizations that run a registry service first and move to a DHT
+/// later: code written against [`Dht`] works unchanged with either. The
+/// registry is expected to serve this API under the base URL:
+///
+/// | Method | Path | Body | Response |
+/// |--------|------|------|----------|
+/// | `POST` | `/registrations` | Registration | `201` |
+/// | `PUT` | `/registrations/{agent_uri}` | Registration | The stored registration |
+/// | `PUT` | `/registrations/{agent_uri}/endpoints` | Endpoint array | The stored registration |
+/// | `POST` | `/registrations/{agent_uri}/renew` | `{"ttl_ms": n}` | The stored registration |
+/// | `DELETE` | `/registrations/{agent_uri}` | | `204` |
+/// | `GET` | `/lookup?root=&path=&mode=` | | `{"registrations": [...], "next_cursor": ...}` |
+/// | `GET` | `/health` | | `200` |
+///
+/// Bodies are JSON, with registrations and endpoints in their `serde`
+/// encoding, and `{agent_uri}` is percent-encoded. `mode` is `exact`,
+/// `prefix`, `pattern` (with `path` a [`PathPattern`]) or `all` (every
+/// agent under `root`); leaving out `root` searches every trust root. Paged
+/// lookups add `limit` and, after the first page, `cursor`. Unpaged lookups
+/// such as [`lookup_prefix`](Dht::lookup_prefix) follow `next_cursor` until
+/// the registry stops returning one, failing with `DhtError::Backend` if a
+/// cursor repeats or more than [`MAX_LOOKUP_PAGES`](Self::MAX_LOOKUP_PAGES)
+/// pages arrive.
+///
+/// The registry reports errors by status code: `404` for an unknown agent,
+/// `409` for an agent that is already registered or, on `PUT`, a write
+/// whose sequence number is not newer than the stored one (with
+/// `{"current_seq": n}` in the body), and `410` for an expired
+/// registration. Other failures become `DhtError::Backend` with the
+/// response body.
+///
+/// Connection failures and `429`, `502`, `503` and `504` responses are
+/// retried with exponential backoff (see [`with_retries`](Self::with_retries)).
+/// A retried `POST` may report `DhtError::AlreadyRegistered` if an earlier
+/// attempt reached the registry. Headers set with
+/// [`with_header`](Self::with_header) and [`with_auth`](Self::with_auth) are
+/// sent with every request.
+///
+/// [`watch_prefix`](Dht::watch_prefix) only reports changes made through
+/// this `RestDht`.
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+/// use agent_uri_dht::{Dht, Endpoint, Registration, RestDht};
+///
+/// let dht = RestDht::new("https://registry.acme.com/v1").with_bearer_token("s3cr3t");
+///
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// dht.register(Registration::new(uri, vec![Endpoint::https("agent.acme.com")]))?;
+///
+/// let found = dht.lookup_prefix(
+///     &TrustRoot::parse("acme.com").unwrap(),
+///     &CapabilityPath::parse("assistant").unwrap(),
+/// )?;
+/// # Ok::<(), agent_uri_dht::DhtError>(())
+/// ```
+pub struct RestDht {
+    agent: ureq::Agent,
+    base_url: String,
+    headers: Vec<(String, String)>,
+    auth: Option<Arc<AuthProvider>>,
+    retries: u32,
+    retry_backoff: Duration,
+    watchers: Watchers,
+}
+
+/// A lookup response.
+#[derive(Deserialize)]
+struct PageBody {
+    registrations: Vec<Registration>,
+    #[serde(default)]
+    next_cursor: Option<String>,
+}
+
+/// The body of a `409` response to a write with a sequence number.
+#[derive(Deserialize)]
+struct ConflictBody {
+    current_seq: u64,
+}
+
+impl RestDht {
+    /// Retries after a failed attempt, unless
+    /// [`with_retries`](Self::with_retries) is used.
+    pub const DEFAULT_RETRIES: u32 = 2;
+
+    /// Delay before the first retry, doubling for each one after it, unless
+    /// [`with_retry_backoff`](Self::with_retry_backoff) is used.
+    pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+    /// Time allowed for each attempt, unless
+    /// [`with_timeout`](Self::with_timeout) is used.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Most pages an unpaged lookup follows before giving up on the
+    /// registry's cursors.
+    pub const MAX_LOOKUP_PAGES: usize = 1024;
+
+    /// Creates a client for the registry at `base_url`, e.g.
+    /// `https://registry.acme.com/v1`. No connection is made until the
+    /// first request.
+    #[must_use]
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            agent: agent(Self::DEFAULT_TIMEOUT),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            headers: Vec::new(),
+            auth: None,
+            retries: Self::DEFAULT_RETRIES,
+            retry_backoff: Self::DEFAULT_RETRY_BACKOFF,
+            watchers: Watchers::default(),
+        }
+    }
+
+    /// Adds a header sent with every request.
+    #[must_use]
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sends `token` as a bearer token with every request.
+    #[must_use]
+    pub fn with_bearer_token(self, token: impl Into<String>) -> Self {
+        let value = format!("Bearer {}", token.into());
+        self.with_auth(move || Ok(value.clone()))
+    }
+
+    /// Calls `provider` before every request for the value of its
+    /// `Authorization` header, e.g. to refresh short-lived tokens. A
+    /// provider error fails the request without sending it.
+    #[must_use]
+    pub fn with_auth(
+        mut self,
+        provider: impl Fn() -> Result<String, DhtError> + Send + Sync + 'static,
+    ) -> Self {
+        self.auth = Some(Arc::new(provider));
+        self
+    }
+
+    /// Sets how many times a failed request is retried.
+    #[must_use]
+    pub const fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets the delay before the first retry; each later retry waits twice
+    /// as long as the one before.
+    #[must_use]
+    pub const fn with_retry_backoff(mut self, backoff: Duration) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Sets the time allowed for each attempt, from connecting to reading
+    /// the response.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.agent = agent(timeout);
+        self
+    }
+
+    /// Returns the base URL of the registry.
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Sends a request, retrying transient failures, and returns the
+    /// response status and body.
+    fn send(
+        &self,
+        method: &str,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<&str>,
+    ) -> Result<(u16, String), DhtError> {
+        let mut url = format!("{}{path}", self.base_url);
+        for (i, (key, value)) in query.iter().enumerate() {
+            let separator = if i == 0 { '?' } else { '&' };
+            let _ = write!(url, "{separator}{key}={}", percent_encode(value));
+        }
+
+        let mut attempt = 0;
+        loop {
+            let mut request = Request::builder()
+                .method(method)
+                .uri(&url)
+                .header("accept", "application/json");
+            for (name, value) in &self.headers {
+                request = request.header(name, value);
+            }
+            if let Some(auth) = &self.auth {
+                request = request.header("authorization", auth()?);
+            }
+            let response = match body {
+                Some(body) => request
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .map_err(|e| DhtError::internal(format!("invalid request: {e}")))
+                    .map(|request| self.agent.run(request)),
+                None => request
+                    .body(())
+                    .map_err(|e| DhtError::internal(format!("invalid request: {e}")))
+                    .map(|request| self.agent.run(request)),
+            }?;
+
+            let outcome = match response {
+                Ok(mut response) => {
+                    let status = response.status().as_u16();
+                    let text = response.body_mut().read_to_string().map_err(backend);
+                    match status {
+                        429 | 502..=504 => Err(DhtError::backend(format!(
+                            "registry returned {status}: {}",
+                            text.unwrap_or_default().trim()
+                        ))),
+                        _ => return text.map(|text| (status, text)),
+                    }
+                }
+                Err(error) => Err(backend(error)),
+            };
+            if attempt >= self.retries {
+                return outcome;
+            }
+            std::thread::sleep(self.retry_backoff.saturating_mul(1 << attempt.min(16)));
+            attempt += 1;
+        }
+    }
+
+    /// Sends a write about `agent_uri` and returns the response body,
+    /// mapping error statuses to [`DhtError`]s.
+    fn write(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&str>,
+        agent_uri: &AgentUri,
+        seq: Option<u64>,
+    ) -> Result<String, DhtError> {
+        let (status, text) = self.send(method, path, &[], body)?;
+        let uri = agent_uri.as_str();
+        match status {
+            200..=299 => Ok(text),
+            404 => Err(DhtError::not_found(uri)),
+            410 => Err(DhtError::expired(uri)),
+            409 => Err(match seq {
+                Some(attempted) => match serde_json::from_str::<ConflictBody>(&text) {
+                    Ok(body) if body.current_seq > attempted => {
+                        DhtError::stale_write(uri, body.current_seq, attempted)
+                    }
+                    _ => DhtError::sequence_conflict(uri, attempted),
+                },
+                None => DhtError::already_registered(uri),
+            }),
+            _ => Err(status_error(status, &text)),
+        }
+    }
+
+    /// Sends a write that returns the stored registration, and notifies
+    /// watchers of the update.
+    fn update(
+        &self,
+        method: &str,
+        path: &str,
+        body: &str,
+        agent_uri: &AgentUri,
+        seq: Option<u64>,
+    ) -> Result<(), DhtError> {
+        let text = self.write(method, path, Some(body), agent_uri, seq)?;
+        let registration: Registration = serde_json::from_str(&text).map_err(|e| {
+            DhtError::backend(format!("registry returned a malformed registration: {e}"))
+        })?;
+        self.watchers.publish(&DhtEvent::Updated(registration));
+        Ok(())
+    }
+
+    /// Runs a lookup and returns the page the registry answered with.
+    fn lookup(
+        &self,
+        trust_root: Option<&TrustRoot>,
+        path: &str,
+        mode: &str,
+        limit: Option<usize>,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        let limit = limit.map(|limit| limit.max(1).to_string());
+        let mut query = vec![("mode", mode)];
+        if let Some(trust_root) = trust_root {
+            query.push(("root", trust_root.as_str()));
+        }
+        if !path.is_empty() {
+            query.push(("path", path));
+        }
+        if let Some(limit) = &limit {
+            query.push(("limit", limit));
+        }
+        if let Some(cursor) = cursor {
+            query.push(("cursor", cursor.as_str()));
+        }
+
+        let (status, text) = self.send("GET", "/lookup", &query, None)?;
+        if !(200..=299).contains(&status) {
+            return Err(status_error(status, &text));
+        }
+        let body: PageBody = serde_json::from_str(&text).map_err(|e| {
+            DhtError::backend(format!("registry returned a malformed lookup response: {e}"))
+        })?;
+        Ok(LookupPage {
+            registrations: body.registrations,
+            next_cursor: body.next_cursor.map(LookupCursor::new),
+        })
+    }
+
+    /// Runs an unpaged lookup, following `next_cursor` through every page
+    /// the registry splits the result into.
+    fn lookup_all(
+        &self,
+        trust_root: Option<&TrustRoot>,
+        path: &str,
+        mode: &str,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let mut page = self.lookup(trust_root, path, mode, None, None)?;
+        let mut registrations = std::mem::take(&mut page.registrations);
+        let mut seen = HashSet::new();
+        while let Some(cursor) = page.next_cursor.take() {
+            if seen.len() + 1 >= Self::MAX_LOOKUP_PAGES {
+                return Err(DhtError::backend(format!(
+                    "registry returned more than {} lookup pages",
+                    Self::MAX_LOOKUP_PAGES
+                )));
+            }
+            if !seen.insert(cursor.clone()) {
+                return Err(DhtError::backend(format!(
+                    "registry returned lookup cursor '{}' again",
+                    cursor.as_str()
+                )));
+            }
+            page = self.lookup(trust_root, path, mode, None, Some(&cursor))?;
+            registrations.append(&mut page.registrations);
+        }
+        Ok(registrations)
+    }
+}
+
+impl std::fmt::Debug for RestDht {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RestDht")
+            .field("base_url", &self.base_url)
+            .field("retries", &self.retries)
+            .field("retry_backoff", &self.retry_backoff)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Dht for RestDht {
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.register", level = "debug", skip_all,
+        fields(agent_uri = %registration.agent_uri(),
+            trust_root = %registration.agent_uri().trust_root(),
+            depth = registration.agent_uri().capability_path().depth()),
+    ))]
+    fn register(&self, registration: Registration) -> Result<(), DhtError> {
+        let result = encode(&registration).and_then(|body| {
+            self.write("POST", "/registrations", Some(&body), registration.agent_uri(), None)
+        });
+        if result.is_ok() {
+            self.watchers.publish(&DhtEvent::Registered(registration));
+        }
+        telemetry::finish(result.map(drop))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.update_endpoint", level = "debug", skip_all,
+        fields(agent_uri = %agent_uri, trust_root = %agent_uri.trust_root(),
+            depth = agent_uri.capability_path().depth()),
+    ))]
+    fn update_endpoint(
+        &self,
+        agent_uri: &AgentUri,
+        new_endpoints: Vec<Endpoint>,
+    ) -> Result<(), DhtError> {
+        let path = format!("/registrations/{}/endpoints", percent_encode(agent_uri.as_str()));
+        telemetry::finish(
+            Endpoint::validate_all(&new_endpoints)
+                .and_then(|()| {
+                    serde_json::to_string(&new_endpoints).map_err(|e| {
+                        DhtError::internal(format!("failed to encode endpoints: {e}"))
+                    })
+                })
+                .and_then(|body| self.update("PUT", &path, &body, agent_uri, None)),
+        )
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.update_registration", level = "debug", skip_all,
+        fields(agent_uri = %registration.agent_uri(),
+            trust_root = %registration.agent_uri().trust_root(),
+            depth = registration.agent_uri().capability_path().depth(), seq = registration.seq()),
+    ))]
+    fn update_registration(&self, registration: Registration) -> Result<(), DhtError> {
+        let agent_uri = registration.agent_uri();
+        let path = format!("/registrations/{}", percent_encode(agent_uri.as_str()));
+        telemetry::finish(
+            encode(&registration)
+                .and_then(|body| {
+                    self.update("PUT", &path, &body, agent_uri, Some(registration.seq()))
+                }),
+        )
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.renew", level = "debug", skip_all,
+        fields(agent_uri = %agent_uri, trust_root = %agent_uri.trust_root(),
+            depth = agent_uri.capability_path().depth()),
+    ))]
+    fn renew(&self, agent_uri: &AgentUri, ttl: Duration) -> Result<(), DhtError> {
+        let path = format!("/registrations/{}/renew", percent_encode(agent_uri.as_str()));
+        let ttl_ms = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+        let body = format!("{{\"ttl_ms\":{ttl_ms}}}");
+        telemetry::finish(self.update("POST", &path, &body, agent_uri, None))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.deregister", level = "debug", skip_all,
+        fields(agent_uri = %agent_uri, trust_root = %agent_uri.trust_root(),
+            depth = agent_uri.capability_path().depth()),
+    ))]
+    fn deregister(&self, agent_uri: &AgentUri) -> Result<(), DhtError> {
+        let path = format!("/registrations/{}", percent_encode(agent_uri.as_str()));
+        let result = self.write("DELETE", &path, None, agent_uri, None);
+        if result.is_ok() {
+            self.watchers.publish(&DhtEvent::Deregistered(agent_uri.clone()));
+        }
+        telemetry::finish(result.map(drop))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_exact", level = "debug", skip_all,
+        fields(trust_root = %trust_root, depth = capability_path.depth()),
+    ))]
+    fn lookup_exact(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        telemetry::finish(self.lookup_all(Some(trust_root), capability_path.as_str(), "exact"))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_prefix", level = "debug", skip_all,
+        fields(trust_root = %trust_root, depth = capability_path.depth()),
+    ))]
+    fn lookup_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        telemetry::finish(self.lookup_all(Some(trust_root), capability_path.as_str(), "prefix"))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_prefix_paged", level = "debug", skip_all,
+        fields(trust_root = %trust_root, depth = capability_path.depth(), limit),
+    ))]
+    fn lookup_prefix_paged(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        let path = capability_path.as_str();
+        telemetry::finish(self.lookup(Some(trust_root), path, "prefix", Some(limit), cursor))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_global", level = "debug", skip_all,
+        fields(depth = capability_path.depth()),
+    ))]
+    fn lookup_global(
+        &self,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        telemetry::finish(self.lookup_all(None, capability_path.as_str(), "prefix"))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_pattern", level = "debug", skip_all,
+        fields(trust_root = %trust_root, pattern = %pattern),
+    ))]
+    fn lookup_pattern(
+        &self,
+        trust_root: &TrustRoot,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        telemetry::finish(self.lookup_all(Some(trust_root), &pattern.to_string(), "pattern"))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_pattern_global", level = "debug", skip_all,
+        fields(pattern = %pattern),
+    ))]
+    fn lookup_pattern_global(
+        &self,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        telemetry::finish(self.lookup_all(None, &pattern.to_string(), "pattern"))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_trust_root", level = "debug", skip_all,
+        fields(trust_root = %trust_root),
+    ))]
+    fn lookup_trust_root(&self, trust_root: &TrustRoot) -> Result<Vec<Registration>, DhtError> {
+        telemetry::finish(self.lookup_all(Some(trust_root), "", "all"))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_trust_root_paged", level = "debug", skip_all,
+        fields(trust_root = %trust_root, limit),
+    ))]
+    fn lookup_trust_root_paged(
+        &self,
+        trust_root: &TrustRoot,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        telemetry::finish(self.lookup(Some(trust_root), "", "all", Some(limit), cursor))
+    }
+
+    fn watch_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Receiver<DhtEvent>, DhtError> {
+        Ok(self.watchers.subscribe(trust_root, capability_path))
+    }
+
+    fn ping(&self) -> Result<(), DhtError> {
+        match self.send("GET", "/health", &[], None)? {
+            (200..=299, _) => Ok(()),
+            (status, text) => Err(status_error(status, &text)),
+        }
+    }
+}
+
+/// Builds an HTTP agent whose attempts time out after `timeout` and that
+/// returns error statuses as responses.
+fn agent(timeout: Duration) -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .http_status_as_error(false)
+        .build()
+        .into()
+}
+
+/// Percent-encodes everything but RFC 3986 unreserved characters.
+fn percent_encode(value: &str) -> String {
+    value.bytes().fold(String::with_capacity(value.len()), |mut encoded, b| {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(char::from(b));
+        } else {
+            let _ = write!(encoded, "%{b:02X}");
+        }
+        encoded
+    })
+}
+
+fn encode(registration: &Registration) -> Result<String, DhtError> {
+    serde_json::to_string(registration)
+        .map_err(|e| DhtError::internal(format!("failed to encode registration: {e}")))
+}
+
+/// Returns the error for an unexpected response status.
+fn status_error(status: u16, body: &str) -> DhtError {
+    DhtError::backend(format!("registry returned {status}: {}", body.trim()))
+}
+
+#[allow(clippy::needless_pass_by_value)] // Used with `map_err`
+fn backend(error: ureq::Error) -> DhtError {
+    DhtError::backend(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write as _};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    use super::*;
+
+    fn test_uri() -> AgentUri {
+        AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q")
+            .unwrap()
+    }
+
+    /// Serves `responses` in order, one per connection, and returns the base
+    /// URL and a handle yielding each request's head and body.
+    fn serve(responses: Vec<(&'static str, String)>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("content-length")
+                    {
+                        length = value.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                    request.push_str(&line);
+                }
+                let mut content = vec![0; length];
+                reader.read_exact(&mut content).unwrap();
+                request.push_str(&String::from_utf8(content).unwrap());
+                requests.push(request);
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+            requests
+        });
+        (base_url, server)
+    }
+
+    fn page(registrations: &[Registration], next_cursor: Option<&str>) -> String {
+        format!(
+            "{{\"registrations\":{},\"next_cursor\":{}}}",
+            serde_json::to_string(registrations).unwrap(),
+            serde_json::to_string(&next_cursor).unwrap()
+        )
+    }
+
+    #[test]
+    fn registers_and_looks_up_with_auth_headers() {
+        let registration = Registration::new(test_uri(), vec![Endpoint::https("agent.acme.com")]);
+        let (base_url, server) = serve(vec![
+            ("201 Created", String::new()),
+            ("200 OK", page(std::slice::from_ref(&registration), Some("next"))),
+        ]);
+        let dht = RestDht::new(format!("{base_url}/v1/"))
+            .with_bearer_token("s3cr3t")
+            .with_header("x-tenant", "acme");
+        let trust_root = TrustRoot::parse("acme.com").unwrap();
+        let path = CapabilityPath::parse("assistant").unwrap();
+        let events = dht.watch_prefix(&trust_root, &path).unwrap();
+
+        dht.register(registration.clone()).unwrap();
+        let page = dht.lookup_prefix_paged(&trust_root, &path, 10, None).unwrap();
+        assert_eq!(page.registrations.len(), 1);
+        assert_eq!(page.next_cursor, Some(LookupCursor::new("next")));
+        assert_eq!(events.try_recv().unwrap(), DhtEvent::Registered(registration));
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /v1/registrations HTTP/1.1\r\n"));
+        assert!(requests[0].contains("authorization: Bearer s3cr3t\r\n"));
+        assert!(requests[0].contains("x-tenant: acme\r\n"));
+        assert!(requests[0].contains(test_uri().as_str()));
+        assert!(requests[1].starts_with(
+            "GET /v1/lookup?mode=prefix&root=acme.com&path=assistant&limit=10 HTTP/1.1\r\n"
+        ));
+    }
+
+    #[test]
+    fn unpaged_lookups_follow_cursors() {
+        let first = Registration::new(test_uri(), vec![Endpoint::https("agent.acme.com")]);
+        let second = Registration::new(
+            AgentUri::parse("agent://acme.com/assistant/code/llm_01h455vb4pex5vsknk084sn02r")
+                .unwrap(),
+            vec![Endpoint::https("code.acme.com")],
+        );
+        let (base_url, server) = serve(vec![
+            ("200 OK", page(std::slice::from_ref(&first), Some("p2"))),
+            ("200 OK", page(std::slice::from_ref(&second), None)),
+            ("200 OK", page(&[], Some("loop"))),
+            ("200 OK", page(&[], Some("loop"))),
+            ("200 OK", page(&[], Some("a"))),
+            ("200 OK", page(&[], Some("b"))),
+            ("200 OK", page(&[], Some("a"))),
+        ]);
+        let dht = RestDht::new(&base_url);
+        let trust_root = TrustRoot::parse("acme.com").unwrap();
+        let path = CapabilityPath::parse("assistant").unwrap();
+
+        let found = dht.lookup_prefix(&trust_root, &path).unwrap();
+        assert_eq!(found, vec![first, second]);
+        let error = dht.lookup_trust_root(&trust_root).unwrap_err();
+        assert!(error.to_string().contains("'loop' again"), "{error}");
+        let error = dht.lookup_trust_root(&trust_root).unwrap_err();
+        assert!(error.to_string().contains("'a' again"), "{error}");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /lookup?mode=prefix&root=acme.com&path=assistant "));
+        assert!(requests[1].starts_with(
+            "GET /lookup?mode=prefix&root=acme.com&path=assistant&cursor=p2 HTTP/1.1\r\n"
+        ));
+        assert!(requests[3].starts_with("GET /lookup?mode=all&root=acme.com&cursor=loop "));
+        assert!(requests[6].starts_with("GET /lookup?mode=all&root=acme.com&cursor=b "));
+        assert_eq!(requests.len(), 7);
+    }
+
+    #[test]
+    fn transient_failures_are_retried() {
+        let (base_url, server) = serve(vec![
+            ("503 Service Unavailable", "busy".to_string()),
+            ("200 OK", page(&[], None)),
+            ("429 Too Many Requests", String::new()),
+        ]);
+        let dht = RestDht::new(&base_url).with_retry_backoff(Duration::from_millis(1));
+        let trust_root = TrustRoot::parse("acme.com").unwrap();
+        assert!(dht.lookup_trust_root(&trust_root).unwrap().is_empty());
+
+        let error = dht.with_retries(0).ping().unwrap_err();
+        assert!(error.to_string().contains("429"), "{error}");
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn error_statuses_map_to_dht_errors() {
+        let (base_url, server) = serve(vec![
+            ("404 Not Found", String::new()),
+            ("409 Conflict", String::new()),
+            ("409 Conflict", "{\"current_seq\":9}".to_string()),
+            ("400 Bad Request", "missing endpoints".to_string()),
+        ]);
+        let dht = RestDht::new(&base_url);
+        let registration = Registration::new(test_uri(), vec![Endpoint::https("agent.acme.com")]);
+
+        assert!(dht.deregister(&test_uri()).unwrap_err().is_not_found());
+        assert!(matches!(
+            dht.register(registration.clone()),
+            Err(DhtError::AlreadyRegistered { .. })
+        ));
+        assert!(matches!(
+            dht.update_registration(registration.with_seq(3)),
+            Err(DhtError::StaleWrite { current: 9, attempted: 3, .. })
+        ));
+        let error = dht.renew(&test_uri(), Duration::from_mins(1)).unwrap_err();
+        assert!(error.to_string().contains("missing endpoints"), "{error}");
+
+        let requests = server.join().unwrap();
+        let encoded = percent_encode(test_uri().as_str());
+        assert!(requests[0].starts_with(&format!("DELETE /registrations/{encoded} ")));
+        assert!(requests[3].ends_with("{\"ttl_ms\":60000}"));
+        assert_eq!(percent_encode("agent://a.b/c d"), "agent%3A%2F%2Fa.b%2Fc%20d");
+    }
+}
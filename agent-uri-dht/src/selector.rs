@@ -0,0 +1,313 @@
+//! Choosing which of an agent's endpoints to contact.
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::{Endpoint, HealthChecker};
+
+/// Picks one endpoint out of a registration's endpoints.
+///
+/// Used through [`Registration::select_endpoint`](crate::Registration::select_endpoint).
+/// Strategies keep any state they need behind `&self`, so one selector can
+/// be shared by all callers.
+///
+/// The strategies here honor [`priority`](Endpoint::priority) as DNS SRV
+/// does: they only pick among the endpoints with the lowest priority they
+/// can use, and fall back to higher ones only when those are unusable.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{Endpoint, Registration, RoundRobin};
+///
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let registration = Registration::new(
+///     uri,
+///     vec![Endpoint::https("a.agent.acme.com"), Endpoint::https("b.agent.acme.com")],
+/// );
+///
+/// let selector = RoundRobin::new();
+/// let first = registration.select_endpoint(&selector).unwrap();
+/// let second = registration.select_endpoint(&selector).unwrap();
+/// assert_ne!(first, second);
+/// ```
+pub trait EndpointSelector: Send + Sync {
+    /// Returns the endpoint to contact, or `None` if none is suitable.
+    fn select<'a>(&self, endpoints: &'a [Endpoint]) -> Option<&'a Endpoint>;
+}
+
+/// Always picks the first endpoint of the lowest priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FirstEndpoint;
+
+impl EndpointSelector for FirstEndpoint {
+    fn select<'a>(&self, endpoints: &'a [Endpoint]) -> Option<&'a Endpoint> {
+        endpoints.iter().min_by_key(|endpoint| priority(endpoint))
+    }
+}
+
+/// Picks an endpoint of the lowest priority uniformly at random.
+#[derive(Debug, Default)]
+pub struct RandomEndpoint {
+    dice: Dice,
+}
+
+impl RandomEndpoint {
+    /// Creates a random selector.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EndpointSelector for RandomEndpoint {
+    fn select<'a>(&self, endpoints: &'a [Endpoint]) -> Option<&'a Endpoint> {
+        let endpoints = preferred(endpoints);
+        if endpoints.is_empty() {
+            return None;
+        }
+        let roll = self.dice.below(endpoints.len() as u64);
+        endpoints.get(usize::try_from(roll).ok()?).copied()
+    }
+}
+
+/// Cycles through the endpoints of the lowest priority in order.
+///
+/// The position is shared across calls regardless of which endpoint list
+/// is passed, so use one selector per agent for an even spread.
+#[derive(Debug, Default)]
+pub struct RoundRobin {
+    next: AtomicUsize,
+}
+
+impl RoundRobin {
+    /// Creates a selector starting at the first endpoint.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EndpointSelector for RoundRobin {
+    fn select<'a>(&self, endpoints: &'a [Endpoint]) -> Option<&'a Endpoint> {
+        let endpoints = preferred(endpoints);
+        if endpoints.is_empty() {
+            return None;
+        }
+        let turn = self.next.fetch_add(1, Ordering::Relaxed);
+        endpoints.get(turn % endpoints.len()).copied()
+    }
+}
+
+/// Picks an endpoint of the lowest priority at random in proportion to its
+/// [`weight`](Endpoint::weight).
+///
+/// Endpoints without a weight count as weight 1; endpoints with weight 0
+/// are never picked unless every endpoint of that priority has weight 0,
+/// in which case the first is.
+#[derive(Debug, Default)]
+pub struct Weighted {
+    dice: Dice,
+}
+
+impl Weighted {
+    /// Creates a weighted selector.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EndpointSelector for Weighted {
+    fn select<'a>(&self, endpoints: &'a [Endpoint]) -> Option<&'a Endpoint> {
+        let endpoints = preferred(endpoints);
+        let weight = |endpoint: &Endpoint| u64::from(endpoint.weight().unwrap_or(1));
+        let total: u64 = endpoints.iter().copied().map(weight).sum();
+        if total == 0 {
+            return endpoints.first().copied();
+        }
+        let mut roll = self.dice.below(total);
+        endpoints.into_iter().find(|endpoint| {
+            let weight = weight(endpoint);
+            if roll < weight {
+                return true;
+            }
+            roll -= weight;
+            false
+        })
+    }
+}
+
+/// Picks the healthy endpoint with the lowest latency recorded by a
+/// [`HealthChecker`].
+///
+/// Lower priorities still come first. Within a priority, endpoints the
+/// checker has no latency for rank after measured ones, in their original
+/// order. Unhealthy endpoints are never picked.
+#[derive(Clone, Copy)]
+pub struct LatencyAware<'c> {
+    checker: &'c HealthChecker,
+}
+
+impl<'c> LatencyAware<'c> {
+    /// Creates a selector ranking endpoints by `checker`'s measurements.
+    #[must_use]
+    pub const fn new(checker: &'c HealthChecker) -> Self {
+        Self { checker }
+    }
+}
+
+impl EndpointSelector for LatencyAware<'_> {
+    fn select<'a>(&self, endpoints: &'a [Endpoint]) -> Option<&'a Endpoint> {
+        endpoints
+            .iter()
+            .filter_map(|endpoint| match self.checker.health(endpoint) {
+                Some(health) if !health.is_healthy() => None,
+                health => Some((health.and_then(|health| health.latency()), endpoint)),
+            })
+            // `None` sorts first, so rank unmeasured endpoints as slowest
+            .min_by_key(|(latency, endpoint)| (priority(endpoint), latency.is_none(), *latency))
+            .map(|(_, endpoint)| endpoint)
+    }
+}
+
+/// Returns an endpoint's priority, counting a missing one as 0.
+fn priority(endpoint: &Endpoint) -> u32 {
+    endpoint.priority().unwrap_or(0)
+}
+
+/// Returns the endpoints sharing the lowest priority, in order.
+fn preferred(endpoints: &[Endpoint]) -> Vec<&Endpoint> {
+    let Some(lowest) = endpoints.iter().map(priority).min() else {
+        return Vec::new();
+    };
+    endpoints
+        .iter()
+        .filter(|endpoint| priority(endpoint) == lowest)
+        .collect()
+}
+
+/// Source of pseudo-random numbers without a `rand` dependency.
+#[derive(Debug, Default)]
+struct Dice {
+    random: RandomState,
+    rolls: AtomicU64,
+}
+
+impl Dice {
+    /// Returns a number in `0..bound`; `bound` must be non-zero.
+    fn below(&self, bound: u64) -> u64 {
+        let roll = self.rolls.fetch_add(1, Ordering::Relaxed);
+        self.random.hash_one(roll) % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn endpoints() -> Vec<Endpoint> {
+        vec![
+            Endpoint::https("a.acme.com"),
+            Endpoint::https("b.acme.com"),
+            Endpoint::https("c.acme.com"),
+        ]
+    }
+
+    #[test]
+    fn empty_endpoints_select_nothing() {
+        assert!(FirstEndpoint.select(&[]).is_none());
+        assert!(RandomEndpoint::new().select(&[]).is_none());
+        assert!(RoundRobin::new().select(&[]).is_none());
+        assert!(Weighted::new().select(&[]).is_none());
+    }
+
+    #[test]
+    fn round_robin_cycles() {
+        let endpoints = endpoints();
+        let selector = RoundRobin::new();
+        let picks: Vec<_> = (0..4).map(|_| selector.select(&endpoints).unwrap()).collect();
+        assert_eq!(picks, [&endpoints[0], &endpoints[1], &endpoints[2], &endpoints[0]]);
+    }
+
+    #[test]
+    fn random_stays_in_bounds() {
+        let endpoints = endpoints();
+        let selector = RandomEndpoint::new();
+        for _ in 0..100 {
+            assert!(endpoints.contains(selector.select(&endpoints).unwrap()));
+        }
+    }
+
+    #[test]
+    fn weighted_skips_zero_weights() {
+        let endpoints = vec![
+            Endpoint::https("a.acme.com").with_weight(0),
+            Endpoint::https("b.acme.com").with_weight(5),
+            Endpoint::https("c.acme.com").with_weight(0),
+        ];
+        let selector = Weighted::new();
+        for _ in 0..100 {
+            assert_eq!(selector.select(&endpoints), Some(&endpoints[1]));
+        }
+
+        let all_zero = [Endpoint::https("a.acme.com").with_weight(0)];
+        assert_eq!(selector.select(&all_zero), Some(&all_zero[0]));
+    }
+
+    #[test]
+    fn selectors_prefer_the_lowest_priority() {
+        let endpoints = vec![
+            Endpoint::https("backup.acme.com").with_priority(10),
+            Endpoint::https("a.acme.com").with_priority(1).with_weight(0),
+            Endpoint::https("b.acme.com").with_priority(1),
+            Endpoint::https("c.acme.com").with_priority(1),
+        ];
+        let primary = &endpoints[1..];
+        assert_eq!(FirstEndpoint.select(&endpoints), Some(&endpoints[1]));
+
+        let round_robin = RoundRobin::new();
+        let random = RandomEndpoint::new();
+        let weighted = Weighted::new();
+        for _ in 0..50 {
+            assert!(primary.contains(round_robin.select(&endpoints).unwrap()));
+            assert!(primary.contains(random.select(&endpoints).unwrap()));
+            assert!(endpoints[2..].contains(weighted.select(&endpoints).unwrap()));
+        }
+
+        // Unprioritized endpoints count as priority 0
+        let mixed = [Endpoint::https("x.acme.com").with_priority(1), Endpoint::https("y.acme.com")];
+        assert_eq!(Weighted::new().select(&mixed), Some(&mixed[1]));
+    }
+
+    #[test]
+    fn latency_aware_prefers_fast_healthy_endpoints() {
+        let endpoints = endpoints();
+        let checker = HealthChecker::default();
+        checker.record_success(&endpoints[0], Duration::from_millis(80));
+        checker.record_success(&endpoints[1], Duration::from_millis(20));
+        checker.record_failure(&endpoints[1], "connection reset");
+
+        // b is unhealthy, c is unmeasured, so a wins
+        let selector = LatencyAware::new(&checker);
+        assert_eq!(selector.select(&endpoints), Some(&endpoints[0]));
+
+        checker.record_failure(&endpoints[0], "connection reset");
+        assert_eq!(selector.select(&endpoints), Some(&endpoints[2]));
+
+        // A slower endpoint of a lower priority wins; higher priorities are
+        // used once it fails
+        let endpoints = [
+            Endpoint::https("near.acme.com").with_priority(5),
+            Endpoint::https("far.acme.com").with_priority(1),
+        ];
+        checker.record_success(&endpoints[0], Duration::from_millis(5));
+        checker.record_success(&endpoints[1], Duration::from_millis(90));
+        assert_eq!(selector.select(&endpoints), Some(&endpoints[1]));
+        checker.record_failure(&endpoints[1], "connection reset");
+        assert_eq!(selector.select(&endpoints), Some(&endpoints[0]));
+    }
+}
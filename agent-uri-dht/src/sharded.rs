@@ -0,0 +1,149 @@
+//! Hash map split across independently locked shards.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A `HashMap` split into shards, each behind its own `RwLock`.
+///
+/// Operations on different keys usually take different locks, so writers
+/// only block readers and writers that hash to the same shard.
+pub(crate) struct ShardedMap<K, V> {
+    shards: Box<[RwLock<HashMap<K, V>>]>,
+    hasher: RandomState,
+}
+
+impl<K: Hash + Eq, V> ShardedMap<K, V> {
+    /// Creates an empty map with `count` shards, at least one.
+    pub(crate) fn new(count: usize) -> Self {
+        Self {
+            shards: (0..count.max(1)).map(|_| RwLock::default()).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Returns the shard holding `key`.
+    fn shard<Q>(&self, key: &Q) -> &RwLock<HashMap<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        &self.shards[self.shard_index(key)]
+    }
+
+    /// Returns the index of the shard holding `key`, for grouping keys
+    /// that share a lock.
+    pub(crate) fn shard_index<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        let count = self.shards.len() as u64;
+        usize::try_from(self.hasher.hash_one(key) % count).unwrap_or(0)
+    }
+
+    /// Read-locks the shard at `index`, wrapping around the shard count.
+    pub(crate) fn read_shard(&self, index: usize) -> RwLockReadGuard<'_, HashMap<K, V>> {
+        self.shards[index % self.shards.len()].read().expect("lock poisoned")
+    }
+
+    /// Read-locks the shard holding `key`.
+    pub(crate) fn read<Q>(&self, key: &Q) -> RwLockReadGuard<'_, HashMap<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        self.shard(key).read().expect("lock poisoned")
+    }
+
+    /// Write-locks the shard holding `key`.
+    pub(crate) fn write<Q>(&self, key: &Q) -> RwLockWriteGuard<'_, HashMap<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        self.shard(key).write().expect("lock poisoned")
+    }
+
+    /// Returns the number of shards.
+    pub(crate) fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Write-locks the shard at `index`, wrapping around the shard count.
+    pub(crate) fn write_shard(&self, index: usize) -> RwLockWriteGuard<'_, HashMap<K, V>> {
+        self.shards[index % self.shards.len()].write().expect("lock poisoned")
+    }
+
+    /// Read-locks each shard in turn.
+    ///
+    /// Only one shard is locked at a time, so the shards seen are not a
+    /// single snapshot of the map.
+    pub(crate) fn read_each(&self) -> impl Iterator<Item = RwLockReadGuard<'_, HashMap<K, V>>> {
+        self.shards.iter().map(|shard| shard.read().expect("lock poisoned"))
+    }
+
+    /// Returns a map with the same shard layout holding a copy of `shards`,
+    /// this map's shards as locked by [`read_each`](Self::read_each), with
+    /// each value copied by `copy`.
+    pub(crate) fn copy_from(
+        &self,
+        shards: &[RwLockReadGuard<'_, HashMap<K, V>>],
+        copy: impl Fn(&V) -> V,
+    ) -> Self
+    where
+        K: Clone,
+    {
+        Self {
+            shards: shards
+                .iter()
+                .map(|shard| {
+                    let copied = shard.iter().map(|(key, value)| (key.clone(), copy(value)));
+                    RwLock::new(copied.collect())
+                })
+                .collect(),
+            hasher: self.hasher.clone(),
+        }
+    }
+
+    /// Returns a copy of the map, copying each value with `copy`, one shard
+    /// at a time.
+    pub(crate) fn fork(&self, copy: impl Fn(&V) -> V) -> Self
+    where
+        K: Clone,
+    {
+        let shards: Vec<_> = self.read_each().collect();
+        self.copy_from(&shards, copy)
+    }
+
+    /// Write-locks each shard in turn.
+    pub(crate) fn write_each(&self) -> impl Iterator<Item = RwLockWriteGuard<'_, HashMap<K, V>>> {
+        self.shards.iter().map(|shard| shard.write().expect("lock poisoned"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_spread_across_shards() {
+        let map: ShardedMap<String, usize> = ShardedMap::new(8);
+        for i in 0..100 {
+            map.write(&format!("key{i}")).insert(format!("key{i}"), i);
+        }
+        assert_eq!(map.read("key42").get("key42"), Some(&42));
+        assert_eq!(map.read_each().map(|shard| shard.len()).sum::<usize>(), 100);
+        assert!(map.read_each().all(|shard| shard.len() < 100));
+    }
+
+    #[test]
+    fn zero_shards_means_one() {
+        let map: ShardedMap<u8, u8> = ShardedMap::new(0);
+        map.write(&1).insert(1, 2);
+        assert_eq!(map.read_each().count(), 1);
+        assert_eq!(map.read(&1).get(&1), Some(&2));
+    }
+}
@@ -1,15 +1,24 @@
 //! Simulated DHT implementation for evaluation.
 
-use std::collections::HashMap;
-use std::sync::RwLock;
-use std::time::Instant;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::mpsc::Receiver;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLockReadGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
 
 use crate::{
-    Dht, DhtError, DhtKey, DhtStats, Endpoint, MigrationResult, PathTrie, Registration,
-    SimulationConfig,
+    Clock, ConcurrentPathTrie, Dht, DhtError, DhtEvent, DhtKey, DhtOperation, DhtSnapshot,
+    DhtStats, Endpoint, EvictionPolicy, KeyspaceDistribution, LatencyLog, LatencyStats,
+    LookupCursor, LookupFilter, LookupPage, MigrationResult, OperationLatency, PathPattern,
+    Registration, RegistrationValidator, SimulationConfig, SystemClock, TtlStats,
 };
+use crate::page;
+use crate::sharded::ShardedMap;
+use crate::telemetry;
+use crate::watch::Watchers;
 
 /// Simulated DHT for evaluation.
 ///
@@ -19,8 +28,18 @@ use crate::{
 ///
 /// # Thread Safety
 ///
-/// Uses `RwLock` for interior mutability, allowing concurrent reads
-/// and exclusive writes.
+/// Each index is split into [`SimulationConfig::shards`] shards, each
+/// behind its own `RwLock`. Registrations and lookups that hash to
+/// different shards proceed in parallel instead of serializing on one
+/// global lock. Global lookups lock one shard at a time.
+///
+/// # Capacity
+///
+/// With [`SimulationConfig::max_registrations`] or
+/// [`SimulationConfig::max_registrations_per_trust_root`] set, a
+/// registration that would exceed a limit is rejected or makes room by
+/// evicting another, as [`SimulationConfig::eviction`] says. Concurrent
+/// registrations may briefly overshoot a limit by one each.
 ///
 /// # Examples
 ///
@@ -44,16 +63,53 @@ use crate::{
 /// ```
 pub struct SimulatedDht {
     /// Primary index: `DhtKey` -> Registrations
-    by_key: RwLock<HashMap<DhtKey, Vec<Registration>>>,
+    by_key: ShardedMap<DhtKey, Vec<Registration>>,
 
-    /// Secondary index: trust root string -> `PathTrie<Registration>`
-    by_path: RwLock<HashMap<String, PathTrie<Registration>>>,
+    /// Secondary index: trust root string -> `ConcurrentPathTrie<Registration>`
+    ///
+    /// The outer lock is only write-locked to add or drop a trust root;
+    /// registrations under an existing one lock just their first segment.
+    by_path: ShardedMap<String, ConcurrentPathTrie<Registration>>,
 
     /// Tertiary index: `AgentUri` string -> `DhtKey`
-    by_uri: RwLock<HashMap<String, DhtKey>>,
+    by_uri: ShardedMap<String, DhtKey>,
+
+    /// `AgentUri` string -> last use, for least-recently-used eviction
+    last_used: ShardedMap<String, AtomicU64>,
 
     /// Configuration
     config: SimulationConfig,
+
+    /// Admission check run on every registration
+    validator: Option<Arc<dyn RegistrationValidator>>,
+
+    /// Subscribers to registration changes
+    watchers: Watchers,
+
+    /// Simulated delays drawn so far, per operation
+    latency: Mutex<BTreeMap<DhtOperation, LatencyStats>>,
+
+    /// Latencies of the latest operations, if configured
+    log: Option<Mutex<LatencyLog>>,
+
+    /// Shard the next limited expiry sweep starts from
+    sweep_cursor: AtomicUsize,
+
+    /// Logical clock stamped into `last_used`
+    clock: AtomicU64,
+
+    /// Time source expiry is checked against and latency is slept on
+    time: Arc<dyn Clock>,
+
+    /// Creation time, which fault partitions are scheduled from
+    created: SystemTime,
+}
+
+thread_local! {
+    /// Delay drawn by the operation running on this thread, and the wall
+    /// clock time spent sleeping through it
+    static DRAWN: Cell<(Duration, Duration)> =
+        const { Cell::new((Duration::ZERO, Duration::ZERO)) };
 }
 
 impl SimulatedDht {
@@ -61,10 +117,19 @@ impl SimulatedDht {
     #[must_use]
     pub fn new(config: SimulationConfig) -> Self {
         Self {
-            by_key: RwLock::new(HashMap::new()),
-            by_path: RwLock::new(HashMap::new()),
-            by_uri: RwLock::new(HashMap::new()),
+            by_key: ShardedMap::new(config.shards),
+            by_path: ShardedMap::new(config.shards),
+            by_uri: ShardedMap::new(config.shards),
+            last_used: ShardedMap::new(config.shards),
+            log: config.latency_log.map(|capacity| Mutex::new(LatencyLog::new(capacity))),
             config,
+            validator: None,
+            watchers: Watchers::default(),
+            latency: Mutex::new(BTreeMap::new()),
+            sweep_cursor: AtomicUsize::new(0),
+            clock: AtomicU64::new(0),
+            time: Arc::new(SystemClock),
+            created: SystemTime::now(),
         }
     }
 
@@ -74,6 +139,112 @@ impl SimulatedDht {
         Self::new(SimulationConfig::default())
     }
 
+    /// Runs `operation` with the configured [`FaultModel`](crate::FaultModel)
+    /// injecting failures: before it takes effect for faults on
+    /// `trust_root` or the request, and after for a lost reply.
+    fn faulty<T>(
+        &self,
+        operation: DhtOperation,
+        trust_root: Option<&TrustRoot>,
+        run: impl FnOnce() -> Result<T, DhtError>,
+    ) -> Result<T, DhtError> {
+        let start = self.start_timing();
+        let result = (|| {
+            let faults = &self.config.faults;
+            faults.request(operation, trust_root, self.elapsed())?;
+            let value = run()?;
+            faults.reply()?;
+            Ok(value)
+        })();
+        self.log_latency(operation, start, result.is_ok());
+        result
+    }
+
+    /// Starts timing an operation for the latency log, if there is one.
+    fn start_timing(&self) -> Option<Instant> {
+        self.log.as_ref().map(|_| {
+            DRAWN.set((Duration::ZERO, Duration::ZERO));
+            Instant::now()
+        })
+    }
+
+    /// Logs the latency of an operation started at `start`: the delay it
+    /// drew, and the time it took apart from sleeping through that delay.
+    fn log_latency(&self, operation: DhtOperation, start: Option<Instant>, succeeded: bool) {
+        let (Some(log), Some(start)) = (&self.log, start) else {
+            return;
+        };
+        let (modeled, slept) = DRAWN.take();
+        log.lock().expect("lock poisoned").record(OperationLatency {
+            operation,
+            modeled,
+            actual: start.elapsed().saturating_sub(slept),
+            succeeded,
+        });
+    }
+
+    /// Runs `validator` on every registration before it is stored.
+    ///
+    /// Use `agent_uri_attestation::Verifier` to only accept registrations
+    /// whose attestation covers their agent URI and capability path.
+    #[must_use]
+    pub fn with_validator(mut self, validator: impl RegistrationValidator + 'static) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
+    /// Reads the time from `clock` and sleeps on it, in place of the
+    /// system clock.
+    ///
+    /// With a [`VirtualClock`](crate::VirtualClock), simulated latency
+    /// advances the clock instead of blocking, and registrations expire as
+    /// the clock is advanced, so long latency-modeled runs finish in
+    /// milliseconds and repeat exactly. Fault partitions are scheduled from
+    /// the clock's time when this is called.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.created = clock.now();
+        self.time = Arc::new(clock);
+        self
+    }
+
+    /// Returns the current time on the DHT's clock.
+    #[must_use]
+    pub fn now(&self) -> SystemTime {
+        self.time.now()
+    }
+
+    /// Returns the time passed on the DHT's clock since it was created.
+    fn elapsed(&self) -> Duration {
+        self.now().duration_since(self.created).unwrap_or_default()
+    }
+
+    /// Derives the DHT key for a trust root and path under the configured
+    /// algorithm and version.
+    fn key(&self, trust_root: &TrustRoot, capability_path: &CapabilityPath) -> DhtKey {
+        DhtKey::derive_versioned(
+            self.config.key_algorithm,
+            self.config.key_version,
+            trust_root,
+            capability_path,
+        )
+    }
+
+    /// Returns the latencies of the latest operations, if
+    /// [`SimulationConfig::latency_log`] is set.
+    ///
+    /// Each register, update, renewal, removal and lookup is logged, failed
+    /// ones included; a [`lookup_many`](Dht::lookup_many) batch is logged
+    /// once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    #[must_use]
+    pub fn latency_log(&self) -> Option<LatencyLog> {
+        (self.log.as_ref()).map(|log| log.lock().expect("lock poisoned").clone())
+    }
+
     /// Returns the configuration.
     #[must_use]
     pub const fn config(&self) -> &SimulationConfig {
@@ -109,15 +280,29 @@ impl SimulatedDht {
     /// Panics if any of the internal locks are poisoned.
     #[must_use]
     pub fn stats(&self) -> DhtStats {
-        let by_key = self.by_key.read().expect("lock poisoned");
-        let by_path = self.by_path.read().expect("lock poisoned");
-        let by_uri = self.by_uri.read().expect("lock poisoned");
-
-        let total_registrations = by_uri.len();
-        let unique_keys = by_key.len();
-        let unique_trust_roots = by_path.len();
-
-        let max_registrations_per_key = by_key.values().map(Vec::len).max().unwrap_or(0);
+        let (mut unique_keys, mut max_registrations_per_key) = (0, 0);
+        let mut path_depth_histogram: Vec<usize> = Vec::new();
+        let mut ttl = TtlStats::default();
+        let now = self.now();
+        for by_key in self.by_key.read_each() {
+            unique_keys += by_key.len();
+            let largest = by_key.values().map(Vec::len).max().unwrap_or(0);
+            max_registrations_per_key = max_registrations_per_key.max(largest);
+
+            // All registrations under a key share its capability path
+            for registrations in by_key.values() {
+                let depth = registrations[0].agent_uri().capability_path().depth();
+                if path_depth_histogram.len() <= depth {
+                    path_depth_histogram.resize(depth + 1, 0);
+                }
+                path_depth_histogram[depth] += registrations.len();
+                for registration in registrations {
+                    ttl.record(registration, now);
+                }
+            }
+        }
+        let unique_trust_roots = self.by_path.read_each().map(|by_path| by_path.len()).sum();
+        let total_registrations = self.by_uri.read_each().map(|by_uri| by_uri.len()).sum();
 
         // Use f64::from for u32 to avoid precision loss; saturate for stats safety
         let total_u32 = u32::try_from(total_registrations).unwrap_or(u32::MAX);
@@ -129,7 +314,8 @@ impl SimulatedDht {
         };
 
         // Estimate memory usage
-        let memory_bytes = Self::estimate_memory_usage_inner(&by_key, &by_uri);
+        let memory_bytes = Self::estimate_memory_usage(unique_keys, total_registrations);
+        let latency = self.latency.lock().expect("lock poisoned").clone();
 
         DhtStats {
             total_registrations,
@@ -137,16 +323,22 @@ impl SimulatedDht {
             unique_trust_roots,
             max_registrations_per_key,
             avg_registrations_per_key,
-            path_depth_histogram: Vec::new(),
+            path_depth_histogram,
             memory_bytes,
+            latency,
+            ttl,
         }
     }
 
     /// Simulates agent migration with timing.
     ///
+    /// The update is delayed as a [`DhtOperation::Migrate`]; in virtual
+    /// time the drawn delay is added to the measured update latency.
+    ///
     /// # Errors
     ///
-    /// Returns `DhtError::NotFound` if the agent is not registered.
+    /// Returns `DhtError::NotFound` if the agent is not registered, or
+    /// `DhtError::InvalidEndpoint` if the new endpoint is malformed.
     ///
     /// # Panics
     ///
@@ -160,14 +352,15 @@ impl SimulatedDht {
 
         // Get old endpoints
         let old_endpoints = {
-            let by_uri = self.by_uri.read().expect("lock poisoned");
-            let key = by_uri
+            let key = *self
+                .by_uri
+                .read(uri_str.as_str())
                 .get(&uri_str)
                 .ok_or_else(|| DhtError::not_found(&uri_str))?;
 
-            let by_key = self.by_key.read().expect("lock poisoned");
+            let by_key = self.by_key.read(&key);
             let registrations = by_key
-                .get(key)
+                .get(&key)
                 .ok_or_else(|| DhtError::not_found(&uri_str))?;
 
             registrations
@@ -177,15 +370,16 @@ impl SimulatedDht {
                 .ok_or_else(|| DhtError::not_found(&uri_str))?
         };
 
-        // Time the update
-        let start = Instant::now();
-        self.update_endpoint(agent_uri, vec![new_endpoint.clone()])?;
-        let update_latency = start.elapsed();
+        new_endpoint.validate()?;
 
-        // Add simulated delay if configured
-        if let Some(delay) = self.config.simulated_delay {
-            std::thread::sleep(delay);
-        }
+        // Time the update
+        let start = self.now();
+        let endpoints = vec![new_endpoint.clone()];
+        let unslept = self.modify(DhtOperation::Migrate, agent_uri, |registration| {
+            registration.update_endpoints(endpoints);
+            Ok(true)
+        })?;
+        let update_latency = self.now().duration_since(start).unwrap_or_default() + unslept;
 
         Ok(MigrationResult::success(
             uri_str,
@@ -197,117 +391,570 @@ impl SimulatedDht {
 
     /// Clears all registrations.
     ///
+    /// Watchers receive a [`DhtEvent::Deregistered`] for each one.
+    ///
     /// # Panics
     ///
     /// Panics if any of the internal locks are poisoned.
     pub fn clear(&self) {
-        let mut by_key = self.by_key.write().expect("lock poisoned");
-        let mut by_path = self.by_path.write().expect("lock poisoned");
-        let mut by_uri = self.by_uri.write().expect("lock poisoned");
+        let removed: Vec<AgentUri> = {
+            // Hold every shard so no registration lands between the indices
+            let mut by_key: Vec<_> = self.by_key.write_each().collect();
+            let mut by_path: Vec<_> = self.by_path.write_each().collect();
+            let mut by_uri: Vec<_> = self.by_uri.write_each().collect();
 
-        by_key.clear();
-        by_path.clear();
-        by_uri.clear();
+            let mut removed: Vec<AgentUri> = by_key
+                .iter_mut()
+                .flat_map(|shard| shard.drain())
+                .flat_map(|(_, registrations)| registrations)
+                .map(|r| r.agent_uri().clone())
+                .collect();
+            self.order(&mut removed, AgentUri::as_str);
+            for shard in &mut by_path {
+                shard.clear();
+            }
+            for shard in &mut by_uri {
+                shard.clear();
+            }
+            for mut shard in self.last_used.write_each() {
+                shard.clear();
+            }
+            removed
+        };
+
+        for agent_uri in removed {
+            self.watchers.publish(&DhtEvent::Deregistered(agent_uri));
+        }
     }
 
-    /// Removes expired registrations.
+    /// Deregisters every agent at `capability_path` or below it under
+    /// `trust_root`, such as a decommissioned team's namespace.
     ///
-    /// Returns the number of registrations removed.
+    /// Watchers receive a [`DhtEvent::Deregistered`] for each one. Returns
+    /// the number of agents removed.
     ///
     /// # Panics
     ///
     /// Panics if any of the internal locks are poisoned.
-    pub fn expire_stale(&self) -> usize {
-        let mut by_key = self.by_key.write().expect("lock poisoned");
-        let mut by_path = self.by_path.write().expect("lock poisoned");
-        let mut by_uri = self.by_uri.write().expect("lock poisoned");
-
-        let mut expired_uris: Vec<String> = Vec::new();
-
-        // Find expired registrations
-        for registrations in by_key.values() {
-            for reg in registrations {
-                if reg.is_expired() {
-                    expired_uris.push(reg.agent_uri().as_str().to_string());
+    pub fn deregister_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> usize {
+        let trust_root_str = trust_root.as_str();
+        let Some(mut removed) =
+            self.remove_from_path_index(trust_root_str, |trie| trie.remove_subtree(capability_path))
+        else {
+            return 0;
+        };
+        self.order(&mut removed, |r| r.agent_uri().as_str());
+
+        for registration in &removed {
+            let uri_str = registration.agent_uri().as_str();
+            if let Some(key) = self.by_uri.write(uri_str).remove(uri_str) {
+                let mut by_key = self.by_key.write(&key);
+                if let Some(registrations) = by_key.get_mut(&key) {
+                    registrations.retain(|r| r.agent_uri().as_str() != uri_str);
+                    if registrations.is_empty() {
+                        by_key.remove(&key);
+                    }
                 }
             }
+            self.last_used.write(uri_str).remove(uri_str);
         }
 
-        // Remove from all indices
-        for uri_str in &expired_uris {
-            if let Some(key) = by_uri.remove(uri_str)
-                && let Some(registrations) = by_key.get_mut(&key)
-            {
-                registrations.retain(|r| r.agent_uri().as_str() != uri_str);
-                if registrations.is_empty() {
-                    by_key.remove(&key);
-                }
+        let count = removed.len();
+        for registration in removed {
+            self.watchers.publish(&DhtEvent::Deregistered(registration.agent_uri().clone()));
+        }
+        count
+    }
+
+    /// Captures every stored registration with its expiry time.
+    ///
+    /// Expired registrations not yet removed are included. Shards are read
+    /// one at a time, so registrations changing while the snapshot is taken
+    /// may or may not appear in it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    #[must_use]
+    pub fn export_snapshot(&self) -> DhtSnapshot {
+        let mut registrations: Vec<Registration> = self
+            .by_key
+            .read_each()
+            .flat_map(|by_key| by_key.values().flatten().cloned().collect::<Vec<_>>())
+            .collect();
+        self.order(&mut registrations, |r| r.agent_uri().as_str());
+        DhtSnapshot::new(registrations)
+    }
+
+    /// Reports how the stored registrations spread over the keyspace,
+    /// bucketed by the leading `prefix_bits` key bits.
+    ///
+    /// Keys are derived with the configured
+    /// [`key_algorithm`](SimulationConfig::key_algorithm).
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    #[must_use]
+    pub fn keyspace_distribution(&self, prefix_bits: u32) -> KeyspaceDistribution {
+        let snapshot = self.export_snapshot();
+        KeyspaceDistribution::analyze_versioned(
+            self.config.key_algorithm,
+            self.config.key_version,
+            &snapshot.registrations,
+            prefix_bits,
+        )
+    }
+
+    /// Returns an independent copy of the DHT's current state, for
+    /// branching one populated baseline into several what-if scenarios
+    /// without registering every agent again.
+    ///
+    /// The copy holds every registration, expired ones not yet removed
+    /// included, along with recency for eviction and the latency recorded
+    /// so far; changes to either DHT afterwards do not show in the other.
+    /// Latency and fault draws carry on from the same point in both, so
+    /// branches issuing the same operations see the same delays and
+    /// faults. The copy keeps the [validator](Self::with_validator) and
+    /// [clock](Self::with_clock), but not the watchers. A shared
+    /// [`VirtualClock`](crate::VirtualClock) moves for every branch; give
+    /// each its own with [`with_clock`](Self::with_clock) to keep their
+    /// time apart.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agent_uri::AgentUri;
+    /// use agent_uri_dht::{Dht, Endpoint, Registration, SimulatedDht};
+    ///
+    /// let baseline = SimulatedDht::with_defaults();
+    /// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+    /// baseline.register(Registration::new(uri.clone(), vec![Endpoint::https("agent.acme.com")]))?;
+    ///
+    /// let outage = baseline.fork();
+    /// outage.deregister(&uri)?;
+    /// assert_eq!(outage.stats().total_registrations, 0);
+    /// assert_eq!(baseline.stats().total_registrations, 1);
+    /// # Ok::<(), agent_uri_dht::DhtError>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    #[must_use]
+    pub fn fork(&self) -> Self {
+        let (by_key, by_path, by_uri) = {
+            // Hold every shard so the indices are copied at one moment
+            let by_key: Vec<_> = self.by_key.read_each().collect();
+            let by_path: Vec<_> = self.by_path.read_each().collect();
+            let by_uri: Vec<_> = self.by_uri.read_each().collect();
+            (
+                self.by_key.copy_from(&by_key, Clone::clone),
+                self.by_path.copy_from(&by_path, ConcurrentPathTrie::fork),
+                self.by_uri.copy_from(&by_uri, Clone::clone),
+            )
+        };
+        Self {
+            by_key,
+            by_path,
+            by_uri,
+            last_used: self.last_used.fork(|used| AtomicU64::new(used.load(Ordering::Relaxed))),
+            config: self.config.clone(),
+            validator: self.validator.clone(),
+            watchers: Watchers::default(),
+            latency: Mutex::new(self.latency.lock().expect("lock poisoned").clone()),
+            log: (self.log.as_ref())
+                .map(|log| Mutex::new(log.lock().expect("lock poisoned").clone())),
+            sweep_cursor: AtomicUsize::new(self.sweep_cursor.load(Ordering::Relaxed)),
+            clock: AtomicU64::new(self.clock.load(Ordering::Relaxed)),
+            time: Arc::clone(&self.time),
+            created: self.created,
+        }
+    }
+
+    /// Restores the registrations in `snapshot`, keeping their expiry
+    /// times.
+    ///
+    /// Registrations are stored without validation or simulated delay, as
+    /// they were checked when first registered. Capacity limits still
+    /// apply. Agents already registered are skipped, as are registrations
+    /// that expired since the snapshot was taken unless
+    /// [`SimulationConfig::auto_expire`] is off. Watchers receive a
+    /// [`DhtEvent::Registered`] for each one restored.
+    ///
+    /// Returns the number of registrations restored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::UnsupportedSnapshotVersion` if `snapshot` was
+    /// written in a different format version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn import_snapshot(&self, snapshot: DhtSnapshot) -> Result<usize, DhtError> {
+        if snapshot.version != DhtSnapshot::VERSION {
+            return Err(DhtError::unsupported_snapshot_version(
+                snapshot.version,
+                DhtSnapshot::VERSION,
+            ));
+        }
+
+        let mut restored = 0;
+        let now = self.now();
+        for registration in snapshot.registrations {
+            if registration.is_expired_at(now) && self.config.auto_expire {
+                continue;
+            }
+            if self.insert(registration).is_ok() {
+                restored += 1;
             }
         }
+        Ok(restored)
+    }
 
-        // Rebuild path index if there were expirations
-        if !expired_uris.is_empty() {
-            by_path.clear();
-            for registrations in by_key.values() {
-                for reg in registrations {
-                    let trust_root_str = reg.agent_uri().trust_root().as_str().to_string();
-                    let trie = by_path.entry(trust_root_str).or_default();
-                    trie.insert(reg.agent_uri().capability_path(), reg.clone());
-                }
+    /// Removes expired registrations.
+    ///
+    /// Returns the number of registrations removed. Watchers receive a
+    /// [`DhtEvent::Expired`] for each one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn expire_stale(&self) -> usize {
+        self.expire_stale_batch(usize::MAX)
+    }
+
+    /// Removes at most `limit` expired registrations.
+    ///
+    /// Shards are swept one at a time, starting where the previous limited
+    /// sweep stopped, so repeated small batches eventually cover the whole
+    /// DHT without holding any lock for long. Returns the number of
+    /// registrations removed; watchers receive a [`DhtEvent::Expired`] for
+    /// each one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the internal locks are poisoned.
+    pub fn expire_stale_batch(&self, limit: usize) -> usize {
+        let mut expired: Vec<AgentUri> = Vec::new();
+        let now = self.now();
+
+        // Remove from the primary index, one shard at a time
+        let start = self.sweep_cursor.load(Ordering::Relaxed);
+        for index in start..start + self.by_key.shard_count() {
+            self.by_key.write_shard(index).retain(|_, registrations| {
+                registrations.retain(|r| {
+                    let expire = expired.len() < limit && r.is_expired_at(now);
+                    if expire {
+                        expired.push(r.agent_uri().clone());
+                    }
+                    !expire
+                });
+                !registrations.is_empty()
+            });
+            if expired.len() >= limit {
+                // This shard may hold more; start the next sweep here
+                self.sweep_cursor.store(index % self.by_key.shard_count(), Ordering::Relaxed);
+                break;
             }
         }
 
-        expired_uris.len()
+        // Remove from the other indices. Only expired copies are removed, so
+        // an agent re-registered in the meantime is left alone.
+        for agent_uri in &expired {
+            let trust_root_str = agent_uri.trust_root().as_str();
+            self.remove_from_path_index(trust_root_str, |trie| {
+                trie.remove(agent_uri.capability_path(), |r| {
+                    r.agent_uri() == agent_uri && r.is_expired_at(now)
+                })
+            });
+            self.by_uri.write(agent_uri.as_str()).remove(agent_uri.as_str());
+            self.last_used.write(agent_uri.as_str()).remove(agent_uri.as_str());
+        }
+
+        let count = expired.len();
+        self.order(&mut expired, AgentUri::as_str);
+        for agent_uri in expired {
+            self.watchers.publish(&DhtEvent::Expired(agent_uri));
+        }
+        count
     }
 
-    fn estimate_memory_usage_inner(
-        by_key: &HashMap<DhtKey, Vec<Registration>>,
-        by_uri: &HashMap<String, DhtKey>,
-    ) -> usize {
+    const fn estimate_memory_usage(unique_keys: usize, registrations: usize) -> usize {
         // Rough estimate:
         // - Each DhtKey: 32 bytes
         // - Each Registration: ~500 bytes (URI + endpoints + attestation)
         // - HashMap overhead: ~64 bytes per entry
         // - PathTrie: ~100 bytes per node
 
-        let key_bytes = by_key.len() * (32 + 64);
-        let registration_bytes = by_uri.len() * 500;
-        let uri_index_bytes = by_uri.len() * (100 + 32 + 64);
+        let key_bytes = unique_keys * (32 + 64);
+        let registration_bytes = registrations * 500;
+        let uri_index_bytes = registrations * (100 + 32 + 64);
 
         key_bytes + registration_bytes + uri_index_bytes
     }
-}
 
-impl Dht for SimulatedDht {
-    fn register(&self, registration: Registration) -> Result<(), DhtError> {
-        // Validate
-        if registration.endpoints().is_empty() {
-            return Err(DhtError::NoEndpoints);
+    /// Returns true if `registration` is visible to a lookup with `filter`.
+    fn is_visible(
+        &self,
+        registration: &Registration,
+        filter: &LookupFilter,
+        now: SystemTime,
+    ) -> bool {
+        (!registration.is_expired_at(now) || !self.config.auto_expire)
+            && filter.matches_at(registration, now)
+    }
+
+    /// Clones lookup matches, unless there are more than a query may
+    /// return.
+    fn capped(&self, mut matches: Vec<&Registration>) -> Result<Vec<Registration>, DhtError> {
+        match self.config.max_results_per_query {
+            Some(max) if matches.len() > max => Err(DhtError::result_limit_exceeded(max)),
+            _ => {
+                self.order(&mut matches, |r| r.agent_uri().as_str());
+                Ok(matches.into_iter().inspect(|r| self.touch(r)).cloned().collect())
+            }
+        }
+    }
+
+    /// Sorts `items` by canonical agent URI, if the configuration asks for
+    /// deterministic ordering.
+    fn order<T>(&self, items: &mut [T], uri: impl Fn(&T) -> &str) {
+        if self.config.deterministic {
+            items.sort_unstable_by(|a, b| uri(a).cmp(uri(b)));
+        }
+    }
+
+    /// Returns the visible registrations `find` selects from every trust
+    /// root, reading one shard at a time.
+    fn scan_global(
+        &self,
+        filter: &LookupFilter,
+        find: impl Fn(&ConcurrentPathTrie<Registration>, &mut dyn FnMut(Vec<&Registration>)),
+    ) -> Result<Vec<Registration>, DhtError> {
+        let now = self.now();
+        let elapsed = self.elapsed();
+        let mut matches = Vec::new();
+
+        for by_path in self.by_path.read_each() {
+            let mut visible = Vec::new();
+            for trie in by_path.values() {
+                find(trie, &mut |found| {
+                    let found = found.into_iter().filter(|r| {
+                        self.is_visible(r, filter, now)
+                            && !self.config.faults.isolates(r.agent_uri().trust_root(), elapsed)
+                    });
+                    visible.extend(found.cloned());
+                });
+            }
+            if let Some(max) = self.config.max_results_per_query
+                && matches.len() + visible.len() > max
+            {
+                return Err(DhtError::result_limit_exceeded(max));
+            }
+            matches.extend(visible.into_iter().inspect(|r| self.touch(r)));
         }
 
+        self.order(&mut matches, |r| r.agent_uri().as_str());
+        Ok(matches)
+    }
+
+    /// Returns the visible registrations under `capability_path`, or under
+    /// the whole trust root if `None`.
+    fn scan(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: Option<&CapabilityPath>,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        // Simulate delay if configured
+        self.simulate_latency(DhtOperation::PrefixLookup);
+
+        let now = self.now();
+        let trust_root_str = trust_root.as_str();
+        let by_path = self.by_path.read(trust_root_str);
+        let Some(trie) = by_path.get(trust_root_str) else {
+            return Ok(Vec::new());
+        };
+
+        let visible = |found: Vec<&Registration>| {
+            self.capped(found.into_iter().filter(|r| self.is_visible(r, filter, now)).collect())
+        };
+        match capability_path {
+            Some(path) => trie.with_prefix(path, visible),
+            None => trie.with_all(visible),
+        }
+    }
+
+    /// Returns one page of [`scan`](Self::scan).
+    fn scan_page(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: Option<&CapabilityPath>,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+        filter: &LookupFilter,
+    ) -> LookupPage {
+        // Simulate delay if configured
+        self.simulate_latency(DhtOperation::PrefixLookup);
+
+        let limit = self
+            .config
+            .max_results_per_query
+            .map_or(limit, |max| limit.min(max))
+            .max(1);
+        let after = page::start_after(capability_path, cursor);
+        let now = self.now();
+
+        let by_path = self.by_path.read(trust_root.as_str());
+        let Some(trie) = by_path.get(trust_root.as_str()) else {
+            return LookupPage::default();
+        };
+
+        // Sort references so only the page itself is cloned
+        let collect = |found: Vec<&Registration>| {
+            let mut matches: Vec<(String, &Registration)> = found
+                .into_iter()
+                .filter(|r| self.is_visible(r, filter, now))
+                .map(|r| {
+                    (page::sort_key(r.agent_uri().capability_path(), r.agent_uri().as_str()), r)
+                })
+                .filter(|(key, _)| after.is_none_or(|after| key.as_str() > after))
+                .collect();
+            matches.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            LookupPage::collect(matches.into_iter().map(|(key, r)| (key, r.clone())), limit)
+        };
+        let page = match capability_path {
+            Some(path) => trie.with_prefix(path, collect),
+            None => trie.with_all(collect),
+        };
+        page.registrations.iter().for_each(|r| self.touch(r));
+        page
+    }
+
+    /// Returns true if registrations are stamped on use for
+    /// least-recently-used eviction.
+    fn tracks_use(&self) -> bool {
+        self.config.eviction == EvictionPolicy::LeastRecentlyUsed
+            && (self.config.max_registrations.is_some()
+                || self.config.max_registrations_per_trust_root.is_some())
+    }
+
+    /// Marks `registration` as just used.
+    fn touch(&self, registration: &Registration) {
+        if self.tracks_use() {
+            let uri_str = registration.agent_uri().as_str();
+            let stamp = self.clock.fetch_add(1, Ordering::Relaxed);
+            if let Some(last_used) = self.last_used.read(uri_str).get(uri_str) {
+                last_used.store(stamp, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Returns how early the eviction policy removes `registration`; lower
+    /// goes first.
+    fn eviction_rank(&self, registration: &Registration) -> u128 {
+        if self.config.eviction == EvictionPolicy::LeastRecentlyUsed {
+            let uri_str = registration.agent_uri().as_str();
+            let last_used = self.last_used.read(uri_str);
+            // Not yet stamped means just registered
+            let stamp = last_used
+                .get(uri_str)
+                .map_or(u64::MAX, |stamp| stamp.load(Ordering::Relaxed));
+            u128::from(stamp)
+        } else {
+            let expires_at = registration.expires_at().duration_since(UNIX_EPOCH);
+            expires_at.map_or(0, |since_epoch| since_epoch.as_nanos())
+        }
+    }
+
+    /// Returns the registration to evict from `trust_root`, or from the
+    /// whole DHT if None.
+    fn eviction_victim(&self, trust_root: Option<&str>) -> Option<AgentUri> {
+        let ranked = |r: &Registration| (self.eviction_rank(r), r.agent_uri().clone());
+        match trust_root {
+            Some(trust_root) => {
+                let by_path = self.by_path.read(trust_root);
+                let trie = by_path.get(trust_root)?;
+                trie.with_all(|all| all.into_iter().map(ranked).min())
+            }
+            None => self
+                .by_key
+                .read_each()
+                .filter_map(|by_key| by_key.values().flatten().map(ranked).min())
+                .min(),
+        }
+        .map(|(_, agent_uri)| agent_uri)
+    }
+
+    /// Frees a slot under each full capacity limit a registration under
+    /// `trust_root` falls within, evicting per the configured policy.
+    fn make_room(&self, trust_root: &str) -> Result<(), DhtError> {
+        let limits = [
+            self.config.max_registrations_per_trust_root.map(|max| (Some(trust_root), max)),
+            self.config.max_registrations.map(|max| (None, max)),
+        ];
+        for (scope, max) in limits.into_iter().flatten() {
+            let count = match scope {
+                Some(trust_root) => {
+                    self.by_path.read(trust_root).get(trust_root).map_or(0, ConcurrentPathTrie::len)
+                }
+                None => self.by_uri.read_each().map(|by_uri| by_uri.len()).sum(),
+            };
+            if count < max {
+                continue;
+            }
+            let victim = match self.config.eviction {
+                EvictionPolicy::Reject => None,
+                EvictionPolicy::LeastRecentlyUsed | EvictionPolicy::SoonestExpiry => {
+                    self.eviction_victim(scope)
+                }
+            };
+            let Some(victim) = victim else {
+                return Err(DhtError::capacity_exceeded(scope, max));
+            };
+            // Already gone if a concurrent operation removed it first
+            let _ = self.remove(&DhtEvent::Evicted(victim));
+        }
+        Ok(())
+    }
+
+    /// Stores a validated registration in every index, making room under
+    /// capacity limits first.
+    fn insert(&self, registration: Registration) -> Result<(), DhtError> {
         let uri_str = registration.agent_uri().as_str().to_string();
         let trust_root_str = registration.agent_uri().trust_root().as_str().to_string();
-        let key = DhtKey::derive(
+        let key = self.key(
             registration.agent_uri().trust_root(),
             registration.agent_uri().capability_path(),
         );
 
-        // Simulate delay if configured
-        if let Some(delay) = self.config.simulated_delay {
-            std::thread::sleep(delay);
+        // Make room under capacity limits, unless the duplicate check below
+        // will reject the registration anyway
+        if !self.by_uri.read(uri_str.as_str()).contains_key(&uri_str) {
+            self.make_room(&trust_root_str)?;
         }
 
-        // Check if already registered
+        // Insert into all indices, checking under the same shard locks so a
+        // concurrent registration cannot slip in between. Shards are always
+        // locked in this order: key, path, URI.
+        let event = DhtEvent::Registered(registration.clone());
         {
-            let by_uri = self.by_uri.read().expect("lock poisoned");
+            let mut by_key = self.by_key.write(&key);
+            let by_path = self.path_index(&trust_root_str);
+            let mut by_uri = self.by_uri.write(uri_str.as_str());
+
+            // Check if already registered
             if by_uri.contains_key(&uri_str) {
                 return Err(DhtError::already_registered(&uri_str));
             }
-        }
 
-        // Check key capacity
-        {
-            let by_key = self.by_key.read().expect("lock poisoned");
+            // Check key capacity
             if let Some(registrations) = by_key.get(&key)
                 && registrations.len() >= self.config.max_registrations_per_key
             {
@@ -316,55 +963,155 @@ impl Dht for SimulatedDht {
                     self.config.max_registrations_per_key,
                 ));
             }
-        }
-
-        // Insert into all indices
-        {
-            let mut by_key = self.by_key.write().expect("lock poisoned");
-            let mut by_path = self.by_path.write().expect("lock poisoned");
-            let mut by_uri = self.by_uri.write().expect("lock poisoned");
 
             // Secondary index (path trie) - must insert first since we need to borrow registration
-            let trie = by_path.entry(trust_root_str).or_default();
-            trie.insert(registration.agent_uri().capability_path(), registration.clone());
+            by_path[&trust_root_str]
+                .insert(registration.agent_uri().capability_path(), registration.clone());
 
             // Primary index
             by_key.entry(key).or_default().push(registration);
 
             // Tertiary index
-            by_uri.insert(uri_str, key);
+            by_uri.insert(uri_str.clone(), key);
         }
 
+        if self.tracks_use() {
+            let stamp = AtomicU64::new(self.clock.fetch_add(1, Ordering::Relaxed));
+            self.last_used.write(uri_str.as_str()).insert(uri_str, stamp);
+        }
+        self.watchers.publish(&event);
         Ok(())
     }
 
-    fn update_endpoint(
+    /// Removes the registration `event` concerns from every index and
+    /// announces `event`.
+    fn remove(&self, event: &DhtEvent) -> Result<(), DhtError> {
+        let agent_uri = event.agent_uri();
+        let uri_str = agent_uri.as_str();
+
+        // Get and remove from URI index
+        let key = self
+            .by_uri
+            .write(uri_str)
+            .remove(uri_str)
+            .ok_or_else(|| DhtError::not_found(uri_str))?;
+
+        // Remove from primary index
+        {
+            let mut by_key = self.by_key.write(&key);
+            if let Some(registrations) = by_key.get_mut(&key) {
+                registrations.retain(|r| r.agent_uri().as_str() != uri_str);
+                if registrations.is_empty() {
+                    by_key.remove(&key);
+                }
+            }
+        }
+
+        // Remove from path trie
+        self.remove_from_path_index(agent_uri.trust_root().as_str(), |trie| {
+            trie.remove(agent_uri.capability_path(), |r| r.agent_uri().as_str() == uri_str)
+        });
+
+        self.last_used.write(uri_str).remove(uri_str);
+        self.watchers.publish(event);
+        Ok(())
+    }
+
+    /// Read-locks the path index shard holding `trust_root`, adding an
+    /// empty trie for it first if there is none.
+    fn path_index(
         &self,
-        agent_uri: &AgentUri,
-        new_endpoints: Vec<Endpoint>,
-    ) -> Result<(), DhtError> {
-        if new_endpoints.is_empty() {
-            return Err(DhtError::NoEndpoints);
+        trust_root: &str,
+    ) -> RwLockReadGuard<'_, HashMap<String, ConcurrentPathTrie<Registration>>> {
+        loop {
+            let by_path = self.by_path.read(trust_root);
+            if by_path.contains_key(trust_root) {
+                return by_path;
+            }
+            drop(by_path);
+            // May be dropped again as empty before it is read-locked; retry
+            self.by_path
+                .write(trust_root)
+                .entry(trust_root.to_string())
+                .or_insert_with(|| ConcurrentPathTrie::new(self.config.shards));
+        }
+    }
+
+    /// Runs `remove` on the path trie of `trust_root`, if there is one,
+    /// then drops the trie if that left it empty.
+    fn remove_from_path_index<R>(
+        &self,
+        trust_root: &str,
+        remove: impl FnOnce(&ConcurrentPathTrie<Registration>) -> R,
+    ) -> Option<R> {
+        let (removed, emptied) = {
+            let by_path = self.by_path.read(trust_root);
+            let trie = by_path.get(trust_root)?;
+            (remove(trie), trie.is_empty())
+        };
+        if emptied {
+            // Only write-lock once empty, and check again: a registration
+            // may have arrived since
+            let mut by_path = self.by_path.write(trust_root);
+            if by_path.get(trust_root).is_some_and(ConcurrentPathTrie::is_empty) {
+                by_path.remove(trust_root);
+            }
+        }
+        Some(removed)
+    }
+
+    /// Draws a delay for `operation` and records it, sleeping through it
+    /// unless the latency model runs in virtual time.
+    ///
+    /// Returns the part of the delay that was not slept through.
+    fn simulate_latency(&self, operation: DhtOperation) -> Duration {
+        let Some(delay) = self.config.latency.sample(operation) else {
+            return Duration::ZERO;
+        };
+        self.latency
+            .lock()
+            .expect("lock poisoned")
+            .entry(operation)
+            .or_default()
+            .record(delay);
+
+        if self.config.latency.is_virtual_time() {
+            DRAWN.set((delay, Duration::ZERO));
+            delay
+        } else {
+            let sleep = Instant::now();
+            self.time.sleep(delay);
+            DRAWN.set((delay, sleep.elapsed()));
+            Duration::ZERO
         }
+    }
 
+    /// Applies `update` to a live registration in every index. `update`
+    /// returns whether it changed the registration; if not, no event is
+    /// published.
+    ///
+    /// Returns the simulated delay not slept through.
+    fn modify(
+        &self,
+        operation: DhtOperation,
+        agent_uri: &AgentUri,
+        update: impl FnOnce(&mut Registration) -> Result<bool, DhtError>,
+    ) -> Result<Duration, DhtError> {
         let uri_str = agent_uri.as_str();
 
         // Simulate delay if configured
-        if let Some(delay) = self.config.simulated_delay {
-            std::thread::sleep(delay);
-        }
+        let unslept = self.simulate_latency(operation);
 
         // Get the key
-        let key = {
-            let by_uri = self.by_uri.read().expect("lock poisoned");
-            *by_uri
-                .get(uri_str)
-                .ok_or_else(|| DhtError::not_found(uri_str))?
-        };
+        let key = *self
+            .by_uri
+            .read(uri_str)
+            .get(uri_str)
+            .ok_or_else(|| DhtError::not_found(uri_str))?;
 
         // Update in primary index
         let updated_registration = {
-            let mut by_key = self.by_key.write().expect("lock poisoned");
+            let mut by_key = self.by_key.write(&key);
             let registrations = by_key
                 .get_mut(&key)
                 .ok_or_else(|| DhtError::not_found(uri_str))?;
@@ -374,162 +1121,433 @@ impl Dht for SimulatedDht {
                 .find(|r| r.agent_uri().as_str() == uri_str)
                 .ok_or_else(|| DhtError::not_found(uri_str))?;
 
-            if registration.is_expired() && self.config.auto_expire {
+            if registration.is_expired_at(self.now()) && self.config.auto_expire {
                 return Err(DhtError::expired(uri_str));
             }
 
-            registration.update_endpoints(new_endpoints);
+            if !update(registration)? {
+                return Ok(unslept);
+            }
             registration.clone()
         };
+        self.touch(&updated_registration);
 
         // Update in path trie
-        {
-            let mut by_path = self.by_path.write().expect("lock poisoned");
-            let trust_root_str = agent_uri.trust_root().as_str().to_string();
-
-            if let Some(trie) = by_path.get_mut(&trust_root_str) {
-                // Remove old and insert updated
-                let capability_path = agent_uri.capability_path();
-                let uri_str_owned = uri_str.to_string();
-                trie.remove(capability_path, |r| {
-                    r.agent_uri().as_str() == uri_str_owned
-                });
-                trie.insert(capability_path, updated_registration);
-            }
+        let trust_root_str = agent_uri.trust_root().as_str();
+        if let Some(trie) = self.by_path.read(trust_root_str).get(trust_root_str) {
+            trie.replace(
+                agent_uri.capability_path(),
+                |r| r.agent_uri().as_str() == uri_str,
+                updated_registration.clone(),
+            );
         }
 
-        Ok(())
+        self.watchers.publish(&DhtEvent::Updated(updated_registration));
+        Ok(unslept)
     }
 
-    fn deregister(&self, agent_uri: &AgentUri) -> Result<(), DhtError> {
-        let uri_str = agent_uri.as_str();
+    /// Checks a registration's endpoints, attestation and validator.
+    fn validate(&self, registration: &Registration) -> Result<(), DhtError> {
+        Endpoint::validate_all(registration.endpoints())?;
+        self.check_ttl(registration.agent_uri(), registration.ttl())?;
+        if self.config.verify_attestations && registration.attestation().is_none() {
+            return Err(DhtError::invalid_attestation(
+                registration.agent_uri().as_str(),
+                "missing attestation token",
+            ));
+        }
+        match &self.validator {
+            Some(validator) => validator.validate(registration),
+            None => Ok(()),
+        }
+    }
 
-        // Simulate delay if configured
-        if let Some(delay) = self.config.simulated_delay {
-            std::thread::sleep(delay);
+    /// Checks that `ttl` is within the configured bounds.
+    fn check_ttl(&self, agent_uri: &AgentUri, ttl: Duration) -> Result<(), DhtError> {
+        let min = self.config.min_ttl.unwrap_or(Duration::ZERO);
+        let max = self.config.max_ttl.unwrap_or(Duration::MAX);
+        if (min..=max).contains(&ttl) {
+            Ok(())
+        } else {
+            Err(DhtError::ttl_out_of_bounds(agent_uri.as_str(), ttl, min, max))
         }
+    }
 
-        // Get and remove from URI index
-        let key = {
-            let mut by_uri = self.by_uri.write().expect("lock poisoned");
-            by_uri
-                .remove(uri_str)
-                .ok_or_else(|| DhtError::not_found(uri_str))?
-        };
+    /// Looks up a batch of exact queries in one simulated round trip.
+    ///
+    /// Returns the error of a fault that fails the whole batch, or each
+    /// query's result.
+    fn lookup_batch(
+        &self,
+        queries: &[(TrustRoot, CapabilityPath)],
+    ) -> Result<Vec<Result<Vec<Registration>, DhtError>>, DhtError> {
+        // One simulated round trip for the whole batch
+        let faults = &self.config.faults;
+        let elapsed = self.elapsed();
+        faults.request(DhtOperation::ExactLookup, None, elapsed)?;
+        self.simulate_latency(DhtOperation::ExactLookup);
+
+        let keys: Vec<DhtKey> = queries
+            .iter()
+            .map(|(trust_root, capability_path)| {
+                self.key(trust_root, capability_path)
+            })
+            .collect();
+        let mut by_shard: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (i, key) in keys.iter().enumerate() {
+            by_shard.entry(self.by_key.shard_index(key)).or_default().push(i);
+        }
 
-        // Remove from primary index
-        {
-            let mut by_key = self.by_key.write().expect("lock poisoned");
-            if let Some(registrations) = by_key.get_mut(&key) {
-                let uri_str_owned = uri_str.to_string();
-                registrations.retain(|r| r.agent_uri().as_str() != uri_str_owned);
-                if registrations.is_empty() {
-                    by_key.remove(&key);
-                }
+        // Lock each shard once for all the queries it holds
+        let filter = LookupFilter::default();
+        let now = self.now();
+        let mut results = vec![Ok(Vec::new()); queries.len()];
+        for (shard, indices) in by_shard {
+            let by_key = self.by_key.read_shard(shard);
+            for i in indices {
+                let matches = by_key
+                    .get(&keys[i])
+                    .map(|registrations| {
+                        registrations
+                            .iter()
+                            .filter(|r| self.is_visible(r, &filter, now))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let result = faults.reach(&queries[i].0, elapsed);
+                results[i] = telemetry::finish(result.and_then(|()| self.capped(matches)));
             }
         }
+        faults.reply()?;
+        Ok(results)
+    }
+}
 
-        // Remove from path trie
-        {
-            let mut by_path = self.by_path.write().expect("lock poisoned");
-            let trust_root_str = agent_uri.trust_root().as_str().to_string();
+impl Dht for SimulatedDht {
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.register", level = "debug", skip_all,
+        fields(agent_uri = %registration.agent_uri(),
+            trust_root = %registration.agent_uri().trust_root(),
+            depth = registration.agent_uri().capability_path().depth()),
+    ))]
+    fn register(&self, registration: Registration) -> Result<(), DhtError> {
+        let trust_root = registration.agent_uri().trust_root().clone();
+        telemetry::finish(self.faulty(DhtOperation::Register, Some(&trust_root), || {
+            self.validate(&registration)?;
 
-            if let Some(trie) = by_path.get_mut(&trust_root_str) {
-                let uri_str_owned = uri_str.to_string();
-                trie.remove(agent_uri.capability_path(), |r| {
-                    r.agent_uri().as_str() == uri_str_owned
-                });
+            // Simulate delay if configured
+            self.simulate_latency(DhtOperation::Register);
+
+            self.insert(self.config.expiry.apply(registration))
+        }))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.update_endpoint", level = "debug", skip_all,
+        fields(agent_uri = %agent_uri, trust_root = %agent_uri.trust_root(),
+            depth = agent_uri.capability_path().depth()),
+    ))]
+    fn update_endpoint(
+        &self,
+        agent_uri: &AgentUri,
+        new_endpoints: Vec<Endpoint>,
+    ) -> Result<(), DhtError> {
+        let result = self.faulty(DhtOperation::Update, Some(agent_uri.trust_root()), || {
+            Endpoint::validate_all(&new_endpoints)?;
+            self.modify(DhtOperation::Update, agent_uri, |registration| {
+                registration.update_endpoints(new_endpoints);
+                Ok(true)
+            })
+        });
+        telemetry::finish(result.map(drop))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.update_registration", level = "debug", skip_all,
+        fields(agent_uri = %registration.agent_uri(),
+            trust_root = %registration.agent_uri().trust_root(),
+            depth = registration.agent_uri().capability_path().depth(), seq = registration.seq()),
+    ))]
+    fn update_registration(&self, registration: Registration) -> Result<(), DhtError> {
+        let agent_uri = registration.agent_uri().clone();
+        let result = self.faulty(DhtOperation::Update, Some(agent_uri.trust_root()), || {
+            self.validate(&registration)?;
+            if registration.is_expired_at(self.now()) && self.config.auto_expire {
+                return Err(DhtError::expired(agent_uri.as_str()));
             }
-        }
+            self.modify(DhtOperation::Update, &agent_uri, |stored| {
+                let changed = registration.check_update(stored)?;
+                if changed {
+                    *stored = registration;
+                }
+                Ok(changed)
+            })
+        });
+        telemetry::finish(result.map(drop))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.renew", level = "debug", skip_all,
+        fields(agent_uri = %agent_uri, trust_root = %agent_uri.trust_root(),
+            depth = agent_uri.capability_path().depth()),
+    ))]
+    fn renew(&self, agent_uri: &AgentUri, ttl: Duration) -> Result<(), DhtError> {
+        let result = self.faulty(DhtOperation::Renew, Some(agent_uri.trust_root()), || {
+            self.check_ttl(agent_uri, ttl)?;
+            let now = self.now();
+            let ttl = self.config.expiry.ttl(agent_uri, ttl, now);
+            self.modify(DhtOperation::Renew, agent_uri, |registration| {
+                registration.refresh_from(now, ttl);
+                Ok(true)
+            })
+        });
+        telemetry::finish(result.map(drop))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.deregister", level = "debug", skip_all,
+        fields(agent_uri = %agent_uri, trust_root = %agent_uri.trust_root(),
+            depth = agent_uri.capability_path().depth()),
+    ))]
+    fn deregister(&self, agent_uri: &AgentUri) -> Result<(), DhtError> {
+        telemetry::finish(self.faulty(DhtOperation::Deregister, Some(agent_uri.trust_root()), || {
+            // Simulate delay if configured
+            self.simulate_latency(DhtOperation::Deregister);
+
+            self.remove(&DhtEvent::Deregistered(agent_uri.clone()))
+        }))
+    }
+
+    fn lookup_exact(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.lookup_exact_filtered(trust_root, capability_path, &LookupFilter::default())
+    }
+
+    fn stats(&self) -> Result<DhtStats, DhtError> {
+        Ok(Self::stats(self))
+    }
+
+    fn lookup_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.lookup_prefix_filtered(trust_root, capability_path, &LookupFilter::default())
+    }
+
+    fn lookup_prefix_paged(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        self.lookup_prefix_paged_filtered(
+            trust_root,
+            capability_path,
+            limit,
+            cursor,
+            &LookupFilter::default(),
+        )
+    }
+
+    fn lookup_global(
+        &self,
+        capability_path: &CapabilityPath,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.lookup_global_filtered(capability_path, &LookupFilter::default())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_pattern", level = "debug", skip_all,
+        fields(trust_root = %trust_root, pattern = %pattern),
+    ))]
+    fn lookup_pattern(
+        &self,
+        trust_root: &TrustRoot,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let result = self.faulty(DhtOperation::PrefixLookup, Some(trust_root), || {
+            // Simulate delay if configured
+            self.simulate_latency(DhtOperation::PrefixLookup);
+
+            let now = self.now();
+            let filter = LookupFilter::default();
+            let by_path = self.by_path.read(trust_root.as_str());
+
+            by_path.get(trust_root.as_str()).map_or_else(
+                || Ok(Vec::new()),
+                |trie| {
+                    trie.with_matching(pattern, |found| {
+                        let found = found.into_iter().filter(|r| self.is_visible(r, &filter, now));
+                        self.capped(found.collect())
+                    })
+                },
+            )
+        });
 
-        Ok(())
+        telemetry::finish(result)
     }
 
-    fn lookup_exact(
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_pattern_global", level = "debug", skip_all,
+        fields(pattern = %pattern),
+    ))]
+    fn lookup_pattern_global(
         &self,
-        trust_root: &TrustRoot,
-        capability_path: &CapabilityPath,
+        pattern: &PathPattern,
     ) -> Result<Vec<Registration>, DhtError> {
-        // Simulate delay if configured
-        if let Some(delay) = self.config.simulated_delay {
-            std::thread::sleep(delay);
-        }
+        let result = self.faulty(DhtOperation::GlobalLookup, None, || {
+            // Simulate delay if configured
+            self.simulate_latency(DhtOperation::GlobalLookup);
 
-        let key = DhtKey::derive(trust_root, capability_path);
+            self.scan_global(&LookupFilter::default(), |trie, found| {
+                trie.with_matching(pattern, found);
+            })
+        });
+        telemetry::finish(result)
+    }
 
-        let by_key = self.by_key.read().expect("lock poisoned");
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_trust_root", level = "debug", skip_all,
+        fields(trust_root = %trust_root),
+    ))]
+    fn lookup_trust_root(&self, trust_root: &TrustRoot) -> Result<Vec<Registration>, DhtError> {
+        telemetry::finish(self.faulty(DhtOperation::PrefixLookup, Some(trust_root), || {
+            self.scan(trust_root, None, &LookupFilter::default())
+        }))
+    }
 
-        let results = by_key
-            .get(&key)
-            .map(|registrations| {
-                registrations
-                    .iter()
-                    .filter(|r| !r.is_expired() || !self.config.auto_expire)
-                    .cloned()
-                    .collect()
-            })
-            .unwrap_or_default();
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_trust_root_paged", level = "debug", skip_all,
+        fields(trust_root = %trust_root, limit),
+    ))]
+    fn lookup_trust_root_paged(
+        &self,
+        trust_root: &TrustRoot,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError> {
+        telemetry::finish(self.faulty(DhtOperation::PrefixLookup, Some(trust_root), || {
+            Ok(self.scan_page(trust_root, None, limit, cursor, &LookupFilter::default()))
+        }))
+    }
 
-        Ok(results)
+    fn watch_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Receiver<DhtEvent>, DhtError> {
+        Ok(self.watchers.subscribe(trust_root, capability_path))
     }
 
-    fn lookup_prefix(
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_exact", level = "debug", skip_all,
+        fields(trust_root = %trust_root, depth = capability_path.depth()),
+    ))]
+    fn lookup_exact_filtered(
         &self,
         trust_root: &TrustRoot,
         capability_path: &CapabilityPath,
+        filter: &LookupFilter,
     ) -> Result<Vec<Registration>, DhtError> {
-        // Simulate delay if configured
-        if let Some(delay) = self.config.simulated_delay {
-            std::thread::sleep(delay);
-        }
-
-        let by_path = self.by_path.read().expect("lock poisoned");
-        let trust_root_str = trust_root.as_str();
-
-        let results = by_path
-            .get(trust_root_str)
-            .map(|trie| {
-                trie.get_prefix(capability_path)
-                    .into_iter()
-                    .filter(|r| !r.is_expired() || !self.config.auto_expire)
-                    .cloned()
-                    .collect()
-            })
-            .unwrap_or_default();
+        telemetry::finish(self.faulty(DhtOperation::ExactLookup, Some(trust_root), || {
+            // Simulate delay if configured
+            self.simulate_latency(DhtOperation::ExactLookup);
+
+            let key = self.key(trust_root, capability_path);
+            let now = self.now();
+
+            let by_key = self.by_key.read(&key);
+
+            let matches = by_key
+                .get(&key)
+                .map(|registrations| {
+                    registrations
+                        .iter()
+                        .filter(|r| self.is_visible(r, filter, now))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            self.capped(matches)
+        }))
+    }
 
-        Ok(results)
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_many", level = "debug", skip_all, fields(queries = queries.len()),
+    ))]
+    fn lookup_many(
+        &self,
+        queries: &[(TrustRoot, CapabilityPath)],
+    ) -> Vec<Result<Vec<Registration>, DhtError>> {
+        let start = self.start_timing();
+        let result = self.lookup_batch(queries);
+        self.log_latency(DhtOperation::ExactLookup, start, result.is_ok());
+        result.unwrap_or_else(|error| vec![Err(error); queries.len()])
     }
 
-    fn lookup_global(
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_prefix", level = "debug", skip_all,
+        fields(trust_root = %trust_root, depth = capability_path.depth()),
+    ))]
+    fn lookup_prefix_filtered(
         &self,
+        trust_root: &TrustRoot,
         capability_path: &CapabilityPath,
+        filter: &LookupFilter,
     ) -> Result<Vec<Registration>, DhtError> {
-        // Simulate delay if configured
-        if let Some(delay) = self.config.simulated_delay {
-            std::thread::sleep(delay);
-        }
-
-        let by_path = self.by_path.read().expect("lock poisoned");
+        telemetry::finish(self.faulty(DhtOperation::PrefixLookup, Some(trust_root), || {
+            self.scan(trust_root, Some(capability_path), filter)
+        }))
+    }
 
-        let mut results = Vec::new();
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_prefix_paged", level = "debug", skip_all,
+        fields(trust_root = %trust_root, depth = capability_path.depth(), limit),
+    ))]
+    fn lookup_prefix_paged_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+        filter: &LookupFilter,
+    ) -> Result<LookupPage, DhtError> {
+        telemetry::finish(self.faulty(DhtOperation::PrefixLookup, Some(trust_root), || {
+            Ok(self.scan_page(trust_root, Some(capability_path), limit, cursor, filter))
+        }))
+    }
 
-        for trie in by_path.values() {
-            let matches: Vec<Registration> = trie
-                .get_prefix(capability_path)
-                .into_iter()
-                .filter(|r| !r.is_expired() || !self.config.auto_expire)
-                .cloned()
-                .collect();
-            results.extend(matches);
-        }
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "dht.lookup_global", level = "debug", skip_all,
+        fields(depth = capability_path.depth()),
+    ))]
+    fn lookup_global_filtered(
+        &self,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        let result = self.faulty(DhtOperation::GlobalLookup, None, || {
+            // Simulate delay if configured
+            self.simulate_latency(DhtOperation::GlobalLookup);
 
-        Ok(results)
+            self.scan_global(filter, |trie, found| {
+                trie.with_prefix(capability_path, found);
+            })
+        });
+        telemetry::finish(result)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{ExpiryPolicy, LatencyDistribution, LatencyModel};
+    use std::time::Instant;
 
     fn test_uri(suffix: &str) -> AgentUri {
         AgentUri::parse(&format!(
@@ -572,6 +1590,19 @@ mod tests {
         assert!(matches!(result, Err(DhtError::NoEndpoints)));
     }
 
+    #[test]
+    fn register_rejects_malformed_endpoints() {
+        let dht = SimulatedDht::with_defaults();
+        let registration = Registration::new(test_uri("2q"), vec![Endpoint::quic("agent.com")]);
+        let result = dht.register(registration);
+        assert!(matches!(result, Err(DhtError::InvalidEndpoint { .. })));
+
+        let registration = Registration::new(test_uri("2q"), vec![test_endpoint()]);
+        dht.register(registration).unwrap();
+        let result = dht.update_endpoint(&test_uri("2q"), vec![Endpoint::https("a.com:0")]);
+        assert!(matches!(result, Err(DhtError::InvalidEndpoint { .. })));
+    }
+
     #[test]
     fn double_registration_fails() {
         let dht = SimulatedDht::with_defaults();
@@ -680,6 +1711,327 @@ mod tests {
         assert_eq!(results[0].endpoints(), &[new_endpoint]);
     }
 
+    #[test]
+    fn update_registration_rejects_stale_and_conflicting_writes() {
+        let dht = SimulatedDht::with_defaults();
+        let uri = test_uri("2q");
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        let path = CapabilityPath::parse("assistant/chat").unwrap();
+        let events = dht.watch_prefix(&trust_root, &path).unwrap();
+        dht.register(Registration::new(uri.clone(), vec![test_endpoint()])).unwrap();
+        let _ = events.try_recv();
+
+        // Two replicas both write version 1; the second is surfaced
+        let east = Registration::new(uri.clone(), vec![Endpoint::https("east.anthropic.com")])
+            .with_seq(1);
+        let west = Registration::new(uri.clone(), vec![Endpoint::https("west.anthropic.com")])
+            .with_seq(1);
+        dht.update_registration(east.clone()).unwrap();
+        assert_eq!(
+            dht.update_registration(west.clone()),
+            Err(DhtError::sequence_conflict(uri.as_str(), 1))
+        );
+
+        // Retrying the stored version is a no-op
+        assert!(matches!(events.try_recv(), Ok(DhtEvent::Updated(_))));
+        dht.update_registration(east).unwrap();
+        assert!(events.try_recv().is_err());
+
+        dht.update_registration(west.with_seq(2)).unwrap();
+        let stale = Registration::new(uri.clone(), vec![test_endpoint()]).with_seq(1);
+        assert_eq!(dht.update_registration(stale), Err(DhtError::stale_write(uri.as_str(), 2, 1)));
+
+        let stored = dht.lookup_exact(&trust_root, &path).unwrap();
+        assert_eq!(stored[0].seq(), 2);
+        assert_eq!(stored[0].endpoints(), &[Endpoint::https("west.anthropic.com")]);
+
+        // Unversioned updates move to the next sequence number
+        dht.update_endpoint(&uri, vec![test_endpoint()]).unwrap();
+        assert_eq!(dht.lookup_exact(&trust_root, &path).unwrap()[0].seq(), 3);
+
+        let unknown = Registration::new(test_uri("3q"), vec![test_endpoint()]).with_seq(1);
+        assert!(dht.update_registration(unknown).unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn validator_rejects_registrations_before_storing() {
+        let dht = SimulatedDht::new(SimulationConfig::new().with_verify_attestations(true))
+            .with_validator(|registration: &Registration| {
+                if registration.attestation() == Some("valid") {
+                    Ok(())
+                } else {
+                    Err(DhtError::capability_mismatch("assistant/chat", "billing"))
+                }
+            });
+        let registration = Registration::new(test_uri("2q"), vec![test_endpoint()]);
+
+        let missing = dht.register(registration.clone()).unwrap_err();
+        assert!(matches!(missing, DhtError::InvalidAttestation { .. }));
+        let rejected = dht.register(registration.clone().with_attestation("forged"));
+        assert!(matches!(rejected, Err(DhtError::CapabilityMismatch { .. })));
+        assert_eq!(dht.stats().total_registrations, 0);
+
+        dht.register(registration.with_attestation("valid")).unwrap();
+        assert_eq!(dht.stats().total_registrations, 1);
+    }
+
+    #[test]
+    fn watch_prefix_reports_changes_below_the_prefix() {
+        let dht = SimulatedDht::with_defaults();
+        let events = dht
+            .watch_prefix(
+                &TrustRoot::parse("anthropic.com").unwrap(),
+                &CapabilityPath::parse("assistant").unwrap(),
+            )
+            .unwrap();
+        let uri = test_uri("2q");
+        let other_root =
+            AgentUri::parse("agent://openai.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q")
+                .unwrap();
+
+        dht.register(Registration::new(uri.clone(), vec![test_endpoint()]))
+            .unwrap();
+        dht.register(Registration::new(other_root, vec![test_endpoint()]))
+            .unwrap();
+        dht.renew(&uri, Duration::from_mins(1)).unwrap();
+        dht.deregister(&uri).unwrap();
+
+        let received: Vec<DhtEvent> = events.try_iter().collect();
+        assert!(received.iter().all(|event| event.agent_uri() == &uri));
+        assert!(matches!(
+            received.as_slice(),
+            [DhtEvent::Registered(_), DhtEvent::Updated(_), DhtEvent::Deregistered(_)]
+        ));
+    }
+
+    #[test]
+    fn lookup_prefix_paged_walks_every_registration_once() {
+        let dht = SimulatedDht::with_defaults();
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        let assistant = CapabilityPath::parse("assistant").unwrap();
+        let suffixes = ["2q", "3q", "4q", "5q", "6q"];
+        for suffix in suffixes {
+            dht.register(Registration::new(test_uri(suffix), vec![test_endpoint()]))
+                .unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = dht
+                .lookup_prefix_paged(&trust_root, &assistant, 2, cursor.as_ref())
+                .unwrap();
+            assert!(page.registrations.len() <= 2);
+            seen.extend(page.registrations.iter().map(|r| r.agent_uri().clone()));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let expected: Vec<AgentUri> = suffixes.iter().map(|s| test_uri(s)).collect();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn lookup_pattern_matches_wildcards_within_and_across_trust_roots() {
+        let dht = SimulatedDht::with_defaults();
+        for uri in [
+            "agent://anthropic.com/assistant/chat/streaming/llm_01h455vb4pex5vsknk084sn02q",
+            "agent://anthropic.com/assistant/code/streaming/llm_01h455vb4pex5vsknk084sn03q",
+            "agent://anthropic.com/assistant/chat/llm_01h455vb4pex5vsknk084sn04q",
+            "agent://openai.com/assistant/chat/streaming/llm_01h455vb4pex5vsknk084sn05q",
+        ] {
+            let uri = AgentUri::parse(uri).unwrap();
+            dht.register(Registration::new(uri, vec![test_endpoint()])).unwrap();
+        }
+
+        let pattern = PathPattern::parse("assistant/*/streaming").unwrap();
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        assert_eq!(dht.lookup_pattern(&trust_root, &pattern).unwrap().len(), 2);
+        assert_eq!(dht.lookup_pattern_global(&pattern).unwrap().len(), 3);
+
+        let pattern = PathPattern::parse("*/chat").unwrap();
+        assert_eq!(dht.lookup_pattern_global(&pattern).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn lookup_global_grouped_limits_each_trust_root() {
+        let dht = SimulatedDht::with_defaults();
+        for suffix in ["2q", "3q", "4q"] {
+            dht.register(Registration::new(test_uri(suffix), vec![test_endpoint()]))
+                .unwrap();
+        }
+        let other =
+            AgentUri::parse("agent://openai.com/assistant/chat/llm_01h455vb4pex5vsknk084sn05q")
+                .unwrap();
+        dht.register(Registration::new(other, vec![test_endpoint()])).unwrap();
+
+        let assistant = CapabilityPath::parse("assistant").unwrap();
+        let groups = dht.lookup_global_grouped(&assistant, Some(1)).unwrap();
+        let summary: Vec<_> = groups
+            .iter()
+            .map(|g| (g.trust_root.as_str(), g.total, g.registrations.len()))
+            .collect();
+        assert_eq!(summary, [("anthropic.com", 3, 1), ("openai.com", 1, 1)]);
+        assert_eq!(groups[0].registrations[0].agent_uri(), &test_uri("2q"));
+    }
+
+    #[test]
+    fn lookup_trust_root_enumerates_every_path() {
+        let dht = SimulatedDht::with_defaults();
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        let uris = [
+            "agent://anthropic.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q",
+            "agent://anthropic.com/billing/llm_01h455vb4pex5vsknk084sn03q",
+            "agent://anthropic.com/search/web/llm_01h455vb4pex5vsknk084sn04q",
+            "agent://openai.com/assistant/chat/llm_01h455vb4pex5vsknk084sn05q",
+        ];
+        for uri in uris {
+            let uri = AgentUri::parse(uri).unwrap();
+            dht.register(Registration::new(uri, vec![test_endpoint()])).unwrap();
+        }
+
+        assert_eq!(dht.lookup_trust_root(&trust_root).unwrap().len(), 3);
+
+        let first = dht.lookup_trust_root_paged(&trust_root, 2, None).unwrap();
+        let paths: Vec<_> = first
+            .registrations
+            .iter()
+            .map(|r| r.agent_uri().capability_path().as_str().to_string())
+            .collect();
+        assert_eq!(paths, ["assistant/chat", "billing"]);
+        let rest = dht
+            .lookup_trust_root_paged(&trust_root, 2, first.next_cursor.as_ref())
+            .unwrap();
+        assert_eq!(rest.registrations[0].agent_uri().as_str(), uris[2]);
+        assert!(rest.next_cursor.is_none());
+    }
+
+    #[test]
+    fn max_results_per_query_caps_lookups() {
+        let dht = SimulatedDht::new(SimulationConfig::new().with_max_results_per_query(2));
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        let assistant = CapabilityPath::parse("assistant").unwrap();
+        for suffix in ["2q", "3q", "4q"] {
+            dht.register(Registration::new(test_uri(suffix), vec![test_endpoint()]))
+                .unwrap();
+        }
+
+        assert!(matches!(
+            dht.lookup_prefix(&trust_root, &assistant),
+            Err(DhtError::ResultLimitExceeded { max: 2 })
+        ));
+        assert!(dht.lookup_global(&assistant).is_err());
+
+        let page = dht
+            .lookup_prefix_paged(&trust_root, &assistant, 100, None)
+            .unwrap();
+        assert_eq!(page.registrations.len(), 2);
+        assert!(page.next_cursor.is_some());
+    }
+
+    #[test]
+    fn trait_reports_health_and_stats() {
+        fn monitor(dht: &dyn Dht) -> (bool, usize) {
+            (dht.health().is_healthy(), dht.stats().unwrap().total_registrations)
+        }
+
+        let dht = SimulatedDht::with_defaults();
+        dht.register(Registration::new(test_uri("2q"), vec![test_endpoint()]))
+            .unwrap();
+        assert_eq!(monitor(&dht), (true, 1));
+    }
+
+    #[test]
+    fn lookup_many_answers_each_query_in_order() {
+        let dht = SimulatedDht::new(SimulationConfig::new().with_max_results_per_query(2));
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        for suffix in ["2q", "3q", "4q"] {
+            dht.register(Registration::new(test_uri(suffix), vec![test_endpoint()]))
+                .unwrap();
+        }
+        let code = AgentUri::parse(
+            "agent://anthropic.com/assistant/code/llm_01h455vb4pex5vsknk084sn02q",
+        )
+        .unwrap();
+        dht.register(Registration::new(code, vec![test_endpoint()])).unwrap();
+
+        let query = |path: &str| (trust_root.clone(), CapabilityPath::parse(path).unwrap());
+        let results = dht.lookup_many(&[
+            query("assistant/code"),
+            query("assistant/missing"),
+            query("assistant/chat"),
+            query("assistant/code"),
+        ]);
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().len(), 1);
+        assert!(results[1].as_ref().unwrap().is_empty());
+        assert_eq!(results[2], Err(DhtError::result_limit_exceeded(2)));
+        assert_eq!(results[3], results[0]);
+        assert!(dht.lookup_many(&[]).is_empty());
+    }
+
+    #[test]
+    fn filters_apply_before_the_result_cap() {
+        let dht = SimulatedDht::new(SimulationConfig::new().with_max_results_per_query(1));
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        let assistant = CapabilityPath::parse("assistant").unwrap();
+        dht.register(Registration::new(test_uri("2q"), vec![test_endpoint()]))
+            .unwrap();
+        dht.register(
+            Registration::new(test_uri("3q"), vec![test_endpoint()]).with_metadata("tier", "gpu"),
+        )
+        .unwrap();
+        dht.register(Registration::new(
+            test_uri("4q"),
+            vec![Endpoint::grpc("agent.anthropic.com:50051")],
+        ))
+        .unwrap();
+        assert!(dht.lookup_prefix(&trust_root, &assistant).is_err());
+
+        let gpu = LookupFilter::new().with_required_metadata("tier");
+        let found = dht
+            .lookup_prefix_filtered(&trust_root, &assistant, &gpu)
+            .unwrap();
+        assert_eq!(found[0].agent_uri(), &test_uri("3q"));
+
+        let grpc = LookupFilter::new().with_protocol("grpc");
+        let page = dht
+            .lookup_prefix_paged_filtered(&trust_root, &assistant, 10, None, &grpc)
+            .unwrap();
+        assert_eq!(page.registrations[0].agent_uri(), &test_uri("4q"));
+        assert!(page.next_cursor.is_none());
+        assert_eq!(dht.lookup_global_filtered(&assistant, &grpc).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn renew_extends_registration_in_place() {
+        let dht = SimulatedDht::with_defaults();
+        let uri = test_uri("2q");
+        let expired = test_uri("3q");
+        let root = TrustRoot::parse("anthropic.com").unwrap();
+        let path = CapabilityPath::parse("assistant").unwrap();
+
+        dht.register(
+            Registration::new(uri.clone(), vec![test_endpoint()]).with_ttl(Duration::from_secs(1)),
+        )
+        .unwrap();
+        dht.register(
+            Registration::new(expired.clone(), vec![test_endpoint()]).with_ttl(Duration::ZERO),
+        )
+        .unwrap();
+
+        dht.renew(&uri, Duration::from_hours(1)).unwrap();
+        let results = dht.lookup_prefix(&root, &path).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].remaining_ttl().unwrap() > Duration::from_secs(3500));
+
+        assert!(dht.renew(&expired, Duration::from_mins(1)).unwrap_err().is_expired());
+        assert!(dht.renew(&test_uri("4q"), Duration::from_mins(1)).unwrap_err().is_not_found());
+    }
+
     #[test]
     fn stats_reports_correct_counts() {
         let dht = SimulatedDht::with_defaults();
@@ -700,6 +2052,61 @@ mod tests {
         assert_eq!(stats.unique_trust_roots(), 1);
     }
 
+    #[test]
+    fn stats_histogram_counts_path_depths() {
+        let dht = SimulatedDht::with_defaults();
+        for uri in [
+            "agent://anthropic.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q",
+            "agent://anthropic.com/assistant/code/llm_01h455vb4pex5vsknk084sn02r",
+            "agent://anthropic.com/search/llm_01h455vb4pex5vsknk084sn02s",
+        ] {
+            let uri = AgentUri::parse(uri).unwrap();
+            dht.register(Registration::new(uri, vec![test_endpoint()])).unwrap();
+        }
+
+        assert_eq!(dht.stats().path_depth_histogram(), &[0, 1, 2]);
+    }
+
+    #[test]
+    fn deregister_prefix_removes_a_namespace() {
+        let dht = SimulatedDht::with_defaults();
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        for uri in [
+            "agent://anthropic.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q",
+            "agent://anthropic.com/assistant/code/llm_01h455vb4pex5vsknk084sn02r",
+            "agent://anthropic.com/search/llm_01h455vb4pex5vsknk084sn02s",
+        ] {
+            let uri = AgentUri::parse(uri).unwrap();
+            dht.register(Registration::new(uri, vec![test_endpoint()])).unwrap();
+        }
+        let watcher = dht
+            .watch_prefix(&trust_root, &CapabilityPath::parse("assistant").unwrap())
+            .unwrap();
+
+        let assistant = CapabilityPath::parse("assistant").unwrap();
+        assert_eq!(dht.deregister_prefix(&trust_root, &assistant), 2);
+        assert_eq!(watcher.try_iter().count(), 2);
+        assert!(dht.lookup_prefix(&trust_root, &assistant).unwrap().is_empty());
+        assert_eq!(dht.stats().total_registrations, 1);
+        assert!(dht.register(Registration::new(test_uri("2q"), vec![test_endpoint()])).is_ok());
+
+        let search = CapabilityPath::parse("search").unwrap();
+        assert_eq!(dht.deregister_prefix(&trust_root, &search), 1);
+        assert_eq!(dht.deregister_prefix(&trust_root, &search), 0);
+    }
+
+    #[test]
+    fn keyspace_distribution_covers_stored_registrations() {
+        let dht = SimulatedDht::with_defaults();
+        dht.register(Registration::new(test_uri("2q"), vec![test_endpoint()])).unwrap();
+        dht.register(Registration::new(test_uri("3q"), vec![test_endpoint()])).unwrap();
+
+        let distribution = dht.keyspace_distribution(4);
+        assert_eq!(distribution.total_registrations(), 2);
+        assert_eq!(distribution.unique_keys(), 1);
+        assert_eq!(distribution.hot_keys(1)[0].path, "anthropic.com/assistant/chat");
+    }
+
     #[test]
     fn key_capacity_exceeded() {
         let config = SimulationConfig::new().with_max_registrations_per_key(1);
@@ -744,4 +2151,320 @@ mod tests {
 
         assert_eq!(count, 2);
     }
+
+    #[test]
+    fn virtual_latency_is_recorded_without_sleeping() {
+        let latency = LatencyModel::new(LatencyDistribution::Constant(Duration::from_secs(5)))
+            .with_operation(
+                DhtOperation::Migrate,
+                LatencyDistribution::Constant(Duration::from_secs(30)),
+            )
+            .with_virtual_time(true);
+        let dht = SimulatedDht::new(SimulationConfig::new().with_latency(latency));
+        let uri = test_uri("2q");
+
+        let start = Instant::now();
+        dht.register(Registration::new(uri.clone(), vec![test_endpoint()])).unwrap();
+        dht.lookup_exact(uri.trust_root(), uri.capability_path()).unwrap();
+        dht.lookup_exact(uri.trust_root(), uri.capability_path()).unwrap();
+        let migration = dht.simulate_migration(&uri, Endpoint::https("new.acme.com")).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(migration.update_latency() >= Duration::from_secs(30));
+
+        let stats = dht.stats();
+        let lookups = stats.latency(DhtOperation::ExactLookup).unwrap();
+        assert_eq!(lookups.samples, 2);
+        assert_eq!(lookups.total, Duration::from_secs(10));
+        assert_eq!(lookups.p99(), Duration::from_secs(5));
+        assert_eq!(stats.latency(DhtOperation::Migrate).unwrap().samples, 1);
+        assert!(stats.latency(DhtOperation::Update).is_none());
+    }
+
+    #[test]
+    fn latency_log_records_each_operation() {
+        let latency = LatencyModel::new(LatencyDistribution::Constant(Duration::from_millis(5)))
+            .with_virtual_time(true);
+        let config = SimulationConfig::new().with_latency(latency).with_latency_log(3);
+        let dht = SimulatedDht::new(config);
+        let uri = test_uri("2q");
+        let queries = [(uri.trust_root().clone(), uri.capability_path().clone())];
+
+        dht.register(Registration::new(uri.clone(), vec![test_endpoint()])).unwrap();
+        dht.register(Registration::new(uri.clone(), vec![test_endpoint()])).unwrap_err();
+        dht.lookup_many(&queries);
+        dht.lookup_prefix(uri.trust_root(), uri.capability_path()).unwrap();
+
+        let log = dht.latency_log().unwrap();
+        assert_eq!(log.recorded(), 4);
+        let operations: Vec<_> = log.samples().map(|s| (s.operation, s.succeeded)).collect();
+        assert_eq!(
+            operations,
+            [
+                (DhtOperation::Register, false),
+                (DhtOperation::ExactLookup, true),
+                (DhtOperation::PrefixLookup, true),
+            ]
+        );
+        assert!(log.samples().all(|s| s.modeled == Duration::from_millis(5)));
+        assert!(log.p50(DhtOperation::PrefixLookup).unwrap() >= Duration::from_millis(5));
+        assert!(SimulatedDht::with_defaults().latency_log().is_none());
+    }
+
+    #[test]
+    fn registrations_keep_their_own_ttl_within_bounds() {
+        let config = SimulationConfig::new()
+            .with_min_ttl(Duration::from_secs(10))
+            .with_max_ttl(Duration::from_hours(1))
+            .with_expiry_policy(ExpiryPolicy::new().with_refresh_ahead(0.5));
+        let dht = SimulatedDht::new(config);
+        let registration = |suffix, ttl| {
+            Registration::new(test_uri(suffix), vec![test_endpoint()])
+                .with_ttl(Duration::from_secs(ttl))
+        };
+
+        let burst = registration("2q", 30).with_refresh_ahead(Duration::from_secs(5));
+        dht.register(burst).unwrap();
+        dht.register(registration("3q", 3600)).unwrap();
+        let too_short = dht.register(registration("4q", 5)).unwrap_err();
+        assert_eq!(
+            too_short,
+            DhtError::ttl_out_of_bounds(
+                test_uri("4q").as_str(),
+                Duration::from_secs(5),
+                Duration::from_secs(10),
+                Duration::from_hours(1),
+            )
+        );
+        let too_long = dht.renew(&test_uri("2q"), Duration::from_hours(2));
+        assert!(matches!(too_long, Err(DhtError::TtlOutOfBounds { .. })));
+
+        // The burst agent's own refresh window wins over the policy's
+        let uri = test_uri("2q");
+        let stored = dht.lookup_exact(uri.trust_root(), uri.capability_path()).unwrap();
+        let burst = stored.iter().find(|r| r.agent_uri() == &uri).unwrap();
+        assert_eq!(burst.refresh_ahead(), Duration::from_secs(5));
+
+        let ttl = dht.stats().ttl;
+        assert_eq!(ttl.registrations, 2);
+        assert_eq!(ttl.min, Duration::from_secs(30));
+        assert_eq!(ttl.max, Duration::from_hours(1));
+        assert_eq!(ttl.mean(), Duration::from_secs(1815));
+        // Set by the registration or, for the service, the policy
+        assert_eq!(ttl.custom_refresh, 2);
+        assert_eq!(ttl.due_for_refresh, 0);
+    }
+
+    #[test]
+    fn full_registry_rejects_by_default() {
+        let dht = SimulatedDht::new(SimulationConfig::new().with_max_registrations(2));
+        for suffix in ["2q", "3q"] {
+            dht.register(Registration::new(test_uri(suffix), vec![test_endpoint()])).unwrap();
+        }
+
+        let result = dht.register(Registration::new(test_uri("4q"), vec![test_endpoint()]));
+        assert_eq!(result, Err(DhtError::capacity_exceeded(None, 2)));
+        let result = dht.register(Registration::new(test_uri("2q"), vec![test_endpoint()]));
+        assert!(matches!(result, Err(DhtError::AlreadyRegistered { .. })));
+        assert_eq!(dht.stats().total_registrations, 2);
+    }
+
+    #[test]
+    fn lru_eviction_spares_recently_looked_up_agents() {
+        let dht = SimulatedDht::new(
+            SimulationConfig::new()
+                .with_max_registrations_per_trust_root(2)
+                .with_eviction(EvictionPolicy::LeastRecentlyUsed),
+        );
+        let trust_root = TrustRoot::parse("anthropic.com").unwrap();
+        let events = dht
+            .watch_prefix(&trust_root, &CapabilityPath::parse("assistant").unwrap())
+            .unwrap();
+        for suffix in ["2q", "3q"] {
+            dht.register(Registration::new(test_uri(suffix), vec![test_endpoint()])).unwrap();
+        }
+        dht.renew(&test_uri("2q"), Duration::from_mins(1)).unwrap();
+
+        // Another trust root has its own budget
+        let other =
+            AgentUri::parse("agent://acme.com/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+        dht.register(Registration::new(other, vec![test_endpoint()])).unwrap();
+        dht.register(Registration::new(test_uri("4q"), vec![test_endpoint()])).unwrap();
+
+        let remaining: Vec<_> = dht
+            .lookup_trust_root(&trust_root)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.agent_uri().clone())
+            .collect();
+        assert_eq!(remaining.len(), 2);
+        assert!(!remaining.contains(&test_uri("3q")));
+        assert!(events.try_iter().any(|event| event == DhtEvent::Evicted(test_uri("3q"))));
+    }
+
+    #[test]
+    fn soonest_expiry_eviction() {
+        let dht = SimulatedDht::new(
+            SimulationConfig::new()
+                .with_max_registrations(2)
+                .with_eviction(EvictionPolicy::SoonestExpiry),
+        );
+        for (suffix, ttl) in [("2q", 600), ("3q", 60)] {
+            let registration = Registration::new(test_uri(suffix), vec![test_endpoint()])
+                .with_ttl(Duration::from_secs(ttl));
+            dht.register(registration).unwrap();
+        }
+
+        dht.register(Registration::new(test_uri("4q"), vec![test_endpoint()])).unwrap();
+        assert!(dht.simulate_migration(&test_uri("3q"), test_endpoint()).is_err());
+        assert_eq!(dht.stats().total_registrations, 2);
+    }
+
+    #[test]
+    fn concurrent_writers_and_readers() {
+        let dht = SimulatedDht::new(SimulationConfig::new().with_shards(4));
+        let path = CapabilityPath::parse("assistant/chat").unwrap();
+
+        std::thread::scope(|scope| {
+            for org in 0..4 {
+                let dht = &dht;
+                scope.spawn(move || {
+                    for i in 0..50 {
+                        let uri = AgentUri::parse(&format!(
+                            "agent://org{org}.com/assistant/chat/llm_01h455vb4pex5vsknk084s{i:04x}"
+                        ))
+                        .unwrap();
+                        dht.register(Registration::new(uri, vec![test_endpoint()])).unwrap();
+                    }
+                });
+            }
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for _ in 0..50 {
+                        assert!(dht.lookup_global(&path).unwrap().len() <= 200);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(dht.lookup_global(&path).unwrap().len(), 200);
+        let stats = dht.stats();
+        assert_eq!(stats.total_registrations, 200);
+        assert_eq!(stats.unique_trust_roots, 4);
+    }
+
+    #[test]
+    fn racing_registrations_of_one_agent_admit_one() {
+        let dht = SimulatedDht::with_defaults();
+        let admitted = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        dht.register(Registration::new(test_uri("2q"), vec![test_endpoint()]))
+                            .is_ok()
+                    })
+                })
+                .collect();
+            handles.into_iter().filter_map(|h| h.join().ok()).filter(|&ok| ok).count()
+        });
+        assert_eq!(admitted, 1);
+        assert_eq!(dht.stats().total_registrations, 1);
+    }
+
+    #[test]
+    fn deterministic_mode_orders_results_by_uri() {
+        let uris: Vec<AgentUri> = ["anthropic.com", "acme.com", "example.org"]
+            .iter()
+            .flat_map(|trust_root| {
+                (0..20).map(move |i| {
+                    AgentUri::parse(&format!(
+                        "agent://{trust_root}/assistant/skill{i}/llm_01h455vb4pex5vsknk084sn02q"
+                    ))
+                    .unwrap()
+                })
+            })
+            .collect();
+        let populate = |order: &mut dyn Iterator<Item = &AgentUri>| {
+            let dht = SimulatedDht::new(SimulationConfig::new().with_deterministic(true));
+            for uri in order {
+                dht.register(Registration::new(uri.clone(), vec![test_endpoint()])).unwrap();
+            }
+            dht
+        };
+        let forward = populate(&mut uris.iter());
+        let backward = populate(&mut uris.iter().rev());
+
+        let uris = |registrations: Vec<Registration>| -> Vec<String> {
+            registrations.iter().map(|r| r.agent_uri().to_string()).collect()
+        };
+        let assistant = CapabilityPath::parse("assistant").unwrap();
+        let global = uris(forward.lookup_global(&assistant).unwrap());
+        assert!(global.is_sorted());
+        assert_eq!(global.len(), 60);
+        assert_eq!(global, uris(backward.lookup_global(&assistant).unwrap()));
+
+        let acme = TrustRoot::parse("acme.com").unwrap();
+        let prefix = uris(forward.lookup_prefix(&acme, &assistant).unwrap());
+        assert!(prefix.is_sorted());
+        assert_eq!(prefix, uris(backward.lookup_prefix(&acme, &assistant).unwrap()));
+        assert_eq!(
+            uris(forward.export_snapshot().registrations),
+            uris(backward.export_snapshot().registrations)
+        );
+
+        let events = forward.watch_prefix(&acme, &assistant).unwrap();
+        forward.clear();
+        let cleared: Vec<String> = events.try_iter().map(|e| e.agent_uri().to_string()).collect();
+        assert!(cleared.is_sorted());
+        assert_eq!(cleared.len(), 20);
+    }
+
+    #[test]
+    fn forks_branch_independently() {
+        let latency = LatencyModel::new(LatencyDistribution::Constant(Duration::from_millis(1)))
+            .with_virtual_time(true);
+        let baseline = SimulatedDht::new(
+            SimulationConfig::new()
+                .with_latency(latency)
+                .with_eviction(EvictionPolicy::LeastRecentlyUsed),
+        );
+        let uris: Vec<AgentUri> = (0..50)
+            .map(|i| {
+                AgentUri::parse(&format!(
+                    "agent://anthropic.com/assistant/skill{i}/llm_01h455vb4pex5vsknk084sn02q"
+                ))
+                .unwrap()
+            })
+            .collect();
+        for uri in &uris {
+            baseline.register(Registration::new(uri.clone(), vec![test_endpoint()])).unwrap();
+        }
+        let events = baseline.watch_prefix(uris[0].trust_root(), uris[0].capability_path());
+        let events = events.unwrap();
+
+        let migration = baseline.fork();
+        let outage = baseline.fork();
+        let registers = |dht: &SimulatedDht| dht.stats().latency(DhtOperation::Register).cloned();
+        assert_eq!(registers(&migration), registers(&baseline));
+        assert_eq!(registers(&migration).unwrap().samples, 50);
+
+        let moved = Endpoint::https("new.anthropic.com");
+        migration.simulate_migration(&uris[0], moved.clone()).unwrap();
+        for uri in &uris[..25] {
+            outage.deregister(uri).unwrap();
+        }
+        baseline.register(Registration::new(test_uri("2q"), vec![test_endpoint()])).unwrap();
+
+        let (trust_root, path) = (uris[0].trust_root(), uris[0].capability_path());
+        assert_eq!(migration.lookup_exact(trust_root, path).unwrap()[0].endpoints(), [moved]);
+        assert_eq!(baseline.lookup_exact(trust_root, path).unwrap()[0].endpoints(), [
+            test_endpoint()
+        ]);
+        assert!(outage.lookup_exact(trust_root, path).unwrap().is_empty());
+        assert_eq!(baseline.stats().total_registrations, 51);
+        assert_eq!(migration.stats().total_registrations, 50);
+        assert_eq!(outage.stats().total_registrations, 25);
+
+        // Only the baseline's own changes reach its watchers
+        assert!(events.try_recv().is_err());
+    }
 }
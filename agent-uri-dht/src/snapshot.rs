@@ -0,0 +1,130 @@
+//! Versioned snapshots of DHT state.
+
+use crate::Registration;
+
+/// Every registration held by a [`SimulatedDht`](crate::SimulatedDht) at
+/// one moment, for persisting and restoring its state.
+///
+/// Registrations keep their registration and expiry times, so a restored
+/// DHT expires them on the original schedule. With the `serde` feature a
+/// snapshot serializes to any serde format; the [`version`](Self::version)
+/// field lets a later release refuse, or migrate, snapshots it does not
+/// understand.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{Dht, Endpoint, Registration, SimulatedDht};
+///
+/// let dht = SimulatedDht::with_defaults();
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// dht.register(Registration::new(uri, vec![Endpoint::https("agent.acme.com")])).unwrap();
+///
+/// let snapshot = dht.export_snapshot();
+/// let restored = SimulatedDht::with_defaults();
+/// assert_eq!(restored.import_snapshot(snapshot).unwrap(), 1);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DhtSnapshot {
+    /// Snapshot format version
+    pub version: u32,
+    /// Registrations, ordered by agent URI
+    pub registrations: Vec<Registration>,
+}
+
+impl DhtSnapshot {
+    /// The snapshot format version this release writes and reads.
+    pub const VERSION: u32 = 1;
+
+    /// Creates a current-version snapshot of `registrations`, sorted by
+    /// agent URI so the same state always serializes the same way.
+    #[must_use]
+    pub fn new(mut registrations: Vec<Registration>) -> Self {
+        registrations.sort_by(|a, b| a.agent_uri().as_str().cmp(b.agent_uri().as_str()));
+        Self {
+            version: Self::VERSION,
+            registrations,
+        }
+    }
+
+    /// Returns the number of registrations in the snapshot.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.registrations.len()
+    }
+
+    /// Returns true if the snapshot holds no registrations.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use agent_uri::AgentUri;
+
+    use super::*;
+    use crate::{Dht, DhtError, Endpoint, SimulatedDht, SimulationConfig};
+
+    fn registration(suffix: &str, ttl: Duration) -> Registration {
+        let uri = AgentUri::parse(&format!(
+            "agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn0{suffix}"
+        ))
+        .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.acme.com")]).with_ttl(ttl)
+    }
+
+    #[test]
+    fn restore_keeps_expiry_and_skips_stale_entries() {
+        let dht = SimulatedDht::new(SimulationConfig::new().with_auto_expire(false));
+        let kept = registration("3q", Duration::from_mins(10));
+        dht.register(kept.clone()).unwrap();
+        dht.register(registration("2q", Duration::ZERO)).unwrap();
+
+        let snapshot = dht.export_snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert!(snapshot.registrations[0].agent_uri().as_str().ends_with("2q"));
+
+        let restored = SimulatedDht::with_defaults();
+        assert_eq!(restored.import_snapshot(snapshot.clone()).unwrap(), 1);
+        assert_eq!(restored.import_snapshot(snapshot).unwrap(), 0);
+        let found = restored
+            .lookup_exact(kept.agent_uri().trust_root(), kept.agent_uri().capability_path())
+            .unwrap();
+        assert_eq!(found[0].expires_at(), kept.expires_at());
+    }
+
+    #[test]
+    fn unknown_versions_are_refused() {
+        let mut snapshot = DhtSnapshot::new(vec![registration("2q", Duration::from_mins(1))]);
+        snapshot.version = DhtSnapshot::VERSION + 1;
+        let result = SimulatedDht::with_defaults().import_snapshot(snapshot);
+        assert_eq!(
+            result,
+            Err(DhtError::unsupported_snapshot_version(DhtSnapshot::VERSION + 1, 1))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshots_round_trip_through_json() {
+        let dht = SimulatedDht::with_defaults();
+        let refresh = Duration::from_mins(2);
+        dht.register(registration("2q", Duration::from_mins(10)).with_refresh_ahead(refresh))
+            .unwrap();
+
+        let json = serde_json::to_string(&dht.export_snapshot()).unwrap();
+        assert!(json.starts_with(r#"{"version":1,"registrations":[{"agent_uri""#));
+        let snapshot: DhtSnapshot = serde_json::from_str(&json).unwrap();
+
+        let restored = SimulatedDht::with_defaults();
+        assert_eq!(restored.import_snapshot(snapshot).unwrap(), 1);
+        assert_eq!(restored.stats().total_registrations, 1);
+        assert_eq!(restored.stats().ttl.custom_refresh, 1);
+    }
+}
@@ -1,8 +1,9 @@
 //! Statistics and result types for DHT evaluation.
 
-use std::time::Duration;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
 
-use crate::Endpoint;
+use crate::{DhtError, DhtOperation, Endpoint, HealthStatus, LatencyStats, Registration};
 
 /// Statistics about the DHT state.
 ///
@@ -19,10 +20,15 @@ pub struct DhtStats {
     pub max_registrations_per_key: usize,
     /// Average registrations per key.
     pub avg_registrations_per_key: f64,
-    /// Histogram of path depths.
+    /// Registrations per capability path depth: entry `d` counts
+    /// registrations whose path has `d` segments.
     pub path_depth_histogram: Vec<usize>,
     /// Estimated memory usage in bytes.
     pub memory_bytes: usize,
+    /// Simulated delays drawn so far, per operation.
+    pub latency: BTreeMap<DhtOperation, LatencyStats>,
+    /// Lifetimes and refresh policies of the stored registrations.
+    pub ttl: TtlStats,
 }
 
 impl DhtStats {
@@ -62,11 +68,111 @@ impl DhtStats {
         self.avg_registrations_per_key
     }
 
+    /// Returns the registrations per capability path depth.
+    #[must_use]
+    pub fn path_depth_histogram(&self) -> &[usize] {
+        &self.path_depth_histogram
+    }
+
     /// Returns the memory usage estimate.
     #[must_use]
     pub const fn memory_bytes(&self) -> usize {
         self.memory_bytes
     }
+
+    /// Returns the simulated delays recorded for `operation`, if any.
+    #[must_use]
+    pub fn latency(&self, operation: DhtOperation) -> Option<&LatencyStats> {
+        self.latency.get(&operation)
+    }
+
+    /// Returns the lifetimes of the stored registrations.
+    #[must_use]
+    pub const fn ttl(&self) -> &TtlStats {
+        &self.ttl
+    }
+}
+
+/// Lifetimes and refresh policies of stored registrations, as reported in
+/// [`DhtStats::ttl`].
+///
+/// Each registration's TTL is the lifetime it was granted at registration
+/// or its last renewal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TtlStats {
+    /// Number of registrations counted
+    pub registrations: usize,
+    /// Shortest TTL
+    pub min: Duration,
+    /// Longest TTL
+    pub max: Duration,
+    /// Sum of all TTLs
+    pub total: Duration,
+    /// Registrations with a refresh-ahead window of their own or from the
+    /// registry's expiry policy, rather than the default half lifetime
+    pub custom_refresh: usize,
+    /// Registrations within their refresh-ahead window or expired
+    pub due_for_refresh: usize,
+}
+
+impl TtlStats {
+    /// Returns the mean TTL, or zero if no registration was counted.
+    #[must_use]
+    pub fn mean(&self) -> Duration {
+        u32::try_from(self.registrations)
+            .ok()
+            .filter(|&registrations| registrations > 0)
+            .map_or(Duration::ZERO, |registrations| self.total / registrations)
+    }
+
+    /// Counts `registration` as of `now`.
+    pub(crate) fn record(&mut self, registration: &Registration, now: SystemTime) {
+        let ttl = registration.ttl();
+        self.min = if self.registrations == 0 { ttl } else { self.min.min(ttl) };
+        self.max = self.max.max(ttl);
+        self.total += ttl;
+        self.registrations += 1;
+        self.custom_refresh += usize::from(registration.refresh_policy().is_some());
+        self.due_for_refresh += usize::from(registration.should_refresh(now));
+    }
+}
+
+/// Whether a DHT backend is reachable, as reported by
+/// [`Dht::health`](crate::Dht::health).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhtHealth {
+    /// Whether the backend answered
+    pub status: HealthStatus,
+    /// How long the check took
+    pub latency: Duration,
+    /// Why the check failed, if it did
+    pub error: Option<String>,
+}
+
+impl DhtHealth {
+    /// Creates a report from the outcome of a
+    /// [`Dht::ping`](crate::Dht::ping) that took `latency`.
+    #[must_use]
+    pub fn from_ping(result: Result<(), DhtError>, latency: Duration) -> Self {
+        match result {
+            Ok(()) => Self {
+                status: HealthStatus::Healthy,
+                latency,
+                error: None,
+            },
+            Err(error) => Self {
+                status: HealthStatus::Unhealthy,
+                latency,
+                error: Some(error.to_string()),
+            },
+        }
+    }
+
+    /// Returns true if the backend is reachable.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.status == HealthStatus::Healthy
+    }
 }
 
 /// Result of a simulated migration operation.
@@ -170,6 +276,17 @@ mod tests {
         assert_eq!(stats.memory_bytes(), 0);
     }
 
+    #[test]
+    fn health_from_ping() {
+        let healthy = DhtHealth::from_ping(Ok(()), Duration::from_millis(3));
+        assert!(healthy.is_healthy());
+        assert_eq!(healthy.error, None);
+
+        let failed = DhtHealth::from_ping(Err(DhtError::backend("refused")), Duration::ZERO);
+        assert_eq!(failed.status, HealthStatus::Unhealthy);
+        assert_eq!(failed.error.as_deref(), Some("DHT backend error: refused"));
+    }
+
     #[test]
     fn successful_migration() {
         let old = vec![Endpoint::https("old.example.com")];
@@ -0,0 +1,150 @@
+//! Background expiration of stale registrations.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::SimulatedDht;
+
+/// Runs [`SimulatedDht::expire_stale_batch`] on a background thread.
+///
+/// Every `interval` the sweeper removes at most `batch_limit` expired
+/// registrations, continuing from where the previous sweep stopped. A
+/// small limit bounds how long each sweep holds index locks; a backlog is
+/// worked off over several intervals instead of all at once.
+///
+/// The sweeper keeps the DHT alive until it is stopped, either with
+/// [`stop`](Self::stop) or by dropping it.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use agent_uri_dht::{ExpirySweeper, SimulatedDht};
+///
+/// let dht = Arc::new(SimulatedDht::with_defaults());
+/// let sweeper = ExpirySweeper::spawn(Arc::clone(&dht), Duration::from_secs(1), 1000);
+/// // ... serve lookups; expired registrations disappear in the background
+/// let removed = sweeper.stop();
+/// assert_eq!(removed, 0);
+/// ```
+#[derive(Debug)]
+pub struct ExpirySweeper {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+    removed: Arc<AtomicU64>,
+}
+
+impl ExpirySweeper {
+    /// Starts sweeping `dht` every `interval`, removing at most
+    /// `batch_limit` registrations per sweep.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operating system cannot spawn a thread.
+    #[must_use]
+    pub fn spawn(dht: Arc<SimulatedDht>, interval: Duration, batch_limit: usize) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        let removed = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&removed);
+
+        let thread = std::thread::Builder::new()
+            .name("agent-uri-dht-sweeper".to_string())
+            .spawn(move || {
+                // Any message or a dropped sender means stop
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    let count = dht.expire_stale_batch(batch_limit);
+                    counter.fetch_add(count as u64, Ordering::Relaxed);
+                }
+            })
+            .expect("failed to spawn sweeper thread");
+
+        Self {
+            stop: Some(stop),
+            thread: Some(thread),
+            removed,
+        }
+    }
+
+    /// Returns the number of registrations removed so far.
+    #[must_use]
+    pub fn removed(&self) -> u64 {
+        self.removed.load(Ordering::Relaxed)
+    }
+
+    /// Stops the sweeper, waiting for a sweep in progress to finish.
+    ///
+    /// Returns the number of registrations removed in total.
+    #[must_use = "returns the number of registrations removed"]
+    pub fn stop(mut self) -> u64 {
+        self.shutdown();
+        self.removed()
+    }
+
+    fn shutdown(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            // A panicking sweep has nothing left to clean up
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ExpirySweeper {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use agent_uri::AgentUri;
+
+    use super::*;
+    use crate::{Dht, Endpoint, Registration, SimulationConfig};
+
+    fn expiring(i: usize) -> Registration {
+        let uri = AgentUri::parse(&format!(
+            "agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084s{i:04x}"
+        ))
+        .unwrap();
+        Registration::new(uri, vec![Endpoint::https("agent.acme.com")]).with_ttl(Duration::ZERO)
+    }
+
+    #[test]
+    fn batches_are_bounded_and_resume() {
+        let dht = SimulatedDht::new(SimulationConfig::new().with_shards(4));
+        for i in 0..10 {
+            dht.register(expiring(i)).unwrap();
+        }
+
+        assert_eq!(dht.expire_stale_batch(0), 0);
+        assert_eq!(dht.expire_stale_batch(3), 3);
+        assert_eq!(dht.expire_stale_batch(3), 3);
+        assert_eq!(dht.stats().total_registrations, 4);
+        assert_eq!(dht.expire_stale_batch(10), 4);
+        assert_eq!(dht.stats().total_registrations, 0);
+    }
+
+    #[test]
+    fn sweeper_removes_expired_registrations() {
+        let dht = Arc::new(SimulatedDht::with_defaults());
+        for i in 0..20 {
+            dht.register(expiring(i)).unwrap();
+        }
+
+        let sweeper = ExpirySweeper::spawn(Arc::clone(&dht), Duration::from_millis(1), 5);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sweeper.removed() < 20 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(sweeper.stop(), 20);
+        assert_eq!(SimulatedDht::stats(&dht).total_registrations, 0);
+    }
+}
@@ -0,0 +1,57 @@
+//! Structured tracing of DHT operations.
+//!
+//! With the `tracing` feature, registrations, updates, renewals,
+//! deregistrations and lookups each run in a `DEBUG` span named after the
+//! operation, carrying the trust root and capability path depth involved.
+//! When the operation finishes, [`finish`] emits one event inside the span
+//! with the outcome, the number of registrations a lookup returned and any
+//! error. Successes are logged at `DEBUG` and failures at `WARN`. Without
+//! the feature, this compiles to nothing.
+
+use crate::{DhtError, LookupPage, Registration};
+
+/// A value an operation returns, summarized for tracing.
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+pub(crate) trait Outcome {
+    /// Returns the number of registrations returned, for lookups.
+    fn results(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl Outcome for () {}
+
+impl Outcome for Vec<Registration> {
+    fn results(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl Outcome for LookupPage {
+    fn results(&self) -> Option<usize> {
+        Some(self.registrations.len())
+    }
+}
+
+/// Records an operation's outcome in the current span and passes `result`
+/// through.
+pub(crate) fn finish<T: Outcome>(result: Result<T, DhtError>) -> Result<T, DhtError> {
+    #[cfg(feature = "tracing")]
+    match &result {
+        Ok(value) => tracing::debug!(outcome = "ok", results = value.results()),
+        Err(error) => tracing::warn!(outcome = "failed", error = %error),
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finish_passes_result_through() {
+        assert_eq!(finish(Ok(())), Ok(()));
+        assert_eq!(finish(Ok(Vec::new())).map(|r: Vec<Registration>| r.results()), Ok(Some(0)));
+        assert_eq!(finish::<()>(Err(DhtError::NoEndpoints)), Err(DhtError::NoEndpoints));
+    }
+}
@@ -1,8 +1,14 @@
 //! DHT trait definition for capability-based agent discovery.
 
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
 use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
 
-use crate::{DhtError, Endpoint, Registration};
+use crate::{
+    DhtError, DhtEvent, DhtHealth, DhtStats, Endpoint, LookupCursor, LookupFilter, LookupPage,
+    PathPattern, Registration, TrustRootGroup,
+};
 
 /// Abstract DHT operations.
 ///
@@ -13,8 +19,9 @@ use crate::{DhtError, Endpoint, Registration};
 /// # Async Considerations
 ///
 /// This trait uses synchronous methods for simplicity in the simulated
-/// implementation. For async/distributed implementations, wrap in a
-/// runtime-specific async layer.
+/// implementation. Async/distributed implementations should implement
+/// [`AsyncDht`](crate::AsyncDht) instead, which every `Dht` also
+/// implements.
 pub trait Dht: Send + Sync {
     /// Registers an agent at its capability path.
     ///
@@ -30,14 +37,21 @@ pub trait Dht: Send + Sync {
     /// Returns `DhtError` if:
     /// - The agent is already registered (`AlreadyRegistered`)
     /// - The endpoints list is empty (`NoEndpoints`)
+    /// - An endpoint fails [`Endpoint::validate`] (`InvalidEndpoint`)
     /// - The DHT key is at capacity (`KeyCapacityExceeded`)
-    /// - Attestation verification fails (`InvalidAttestation`)
+    /// - Attestation verification fails (`InvalidAttestation`) or does not
+    ///   cover the capability path (`CapabilityMismatch`), for
+    ///   implementations configured with a
+    ///   [`RegistrationValidator`](crate::RegistrationValidator)
     fn register(&self, registration: Registration) -> Result<(), DhtError>;
 
     /// Updates an existing registration's endpoints.
     ///
     /// Used for agent migration (changing network location without
-    /// changing identity).
+    /// changing identity). The stored record moves to the next
+    /// [`seq`](Registration::seq); replicas of an agent that may write
+    /// concurrently should use
+    /// [`update_registration`](Self::update_registration) instead.
     ///
     /// # Arguments
     ///
@@ -50,12 +64,55 @@ pub trait Dht: Send + Sync {
     /// - The agent is not registered (`NotFound`)
     /// - The registration has expired (`Expired`)
     /// - The endpoints list is empty (`NoEndpoints`)
+    /// - An endpoint fails [`Endpoint::validate`] (`InvalidEndpoint`)
     fn update_endpoint(
         &self,
         agent_uri: &AgentUri,
         new_endpoints: Vec<Endpoint>,
     ) -> Result<(), DhtError>;
 
+    /// Replaces an existing registration with a newer version of it.
+    ///
+    /// The update must carry a higher [`seq`](Registration::seq) than the
+    /// stored record. Writing the stored version again is a no-op, so
+    /// retries are safe.
+    ///
+    /// # Arguments
+    ///
+    /// * `registration` - The new version of the record
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if:
+    /// - The agent is not registered (`NotFound`)
+    /// - The stored registration, or the update, has expired (`Expired`)
+    /// - The update's sequence number is lower than the stored one
+    ///   (`StaleWrite`)
+    /// - The update has the stored sequence number but different contents
+    ///   (`SequenceConflict`)
+    /// - The update fails the checks [`register`](Self::register) applies
+    fn update_registration(&self, registration: Registration) -> Result<(), DhtError>;
+
+    /// Extends a registration so it expires `ttl` from now.
+    ///
+    /// Keeps a live agent discoverable without the deregister/register
+    /// gap in which lookups would miss it. See
+    /// [`HeartbeatScheduler`](crate::HeartbeatScheduler) for renewing on a
+    /// schedule.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent_uri` - The agent's URI
+    /// * `ttl` - The registration's new time to live
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if:
+    /// - The agent is not registered (`NotFound`)
+    /// - The registration has already expired (`Expired`); register it
+    ///   again instead
+    fn renew(&self, agent_uri: &AgentUri, ttl: Duration) -> Result<(), DhtError>;
+
     /// Removes a registration.
     ///
     /// # Arguments
@@ -82,13 +139,42 @@ pub trait Dht: Send + Sync {
     ///
     /// # Errors
     ///
-    /// Returns `DhtError` if an internal error occurs.
+    /// Returns `DhtError` if an internal error occurs, or
+    /// `ResultLimitExceeded` if more registrations match than the
+    /// implementation returns per query.
     fn lookup_exact(
         &self,
         trust_root: &TrustRoot,
         capability_path: &CapabilityPath,
     ) -> Result<Vec<Registration>, DhtError>;
 
+    /// Looks up agents at several exact capability paths in one call.
+    ///
+    /// Lets callers resolving many capabilities at once, such as an
+    /// orchestrator planning a workflow, pay for one batch instead of one
+    /// lookup per capability. The default implementation calls
+    /// [`lookup_exact`](Self::lookup_exact) per query; implementations
+    /// that can share locks or round trips across queries should override
+    /// it.
+    ///
+    /// # Arguments
+    ///
+    /// * `queries` - Trust root and exact capability path of each query
+    ///
+    /// # Returns
+    ///
+    /// The result of each query, in the order of `queries`. A query that
+    /// fails, e.g. with `ResultLimitExceeded`, does not fail the others.
+    fn lookup_many(
+        &self,
+        queries: &[(TrustRoot, CapabilityPath)],
+    ) -> Vec<Result<Vec<Registration>, DhtError>> {
+        queries
+            .iter()
+            .map(|(trust_root, capability_path)| self.lookup_exact(trust_root, capability_path))
+            .collect()
+    }
+
     /// Looks up agents at capability path and all child paths.
     ///
     /// Returns agents registered at the specified path and any paths
@@ -105,13 +191,40 @@ pub trait Dht: Send + Sync {
     ///
     /// # Errors
     ///
-    /// Returns `DhtError` if an internal error occurs.
+    /// Returns `DhtError` if an internal error occurs, or
+    /// `ResultLimitExceeded` if more registrations match than the
+    /// implementation returns per query.
     fn lookup_prefix(
         &self,
         trust_root: &TrustRoot,
         capability_path: &CapabilityPath,
     ) -> Result<Vec<Registration>, DhtError>;
 
+    /// Looks up one page of agents at a capability path and all child paths.
+    ///
+    /// Registrations are ordered by capability path, then agent URI. Pass
+    /// the returned [`LookupPage::next_cursor`] to fetch the following page.
+    ///
+    /// # Arguments
+    ///
+    /// * `trust_root` - The trust root to search
+    /// * `capability_path` - The path prefix to search
+    /// * `limit` - Maximum registrations on the page; zero is treated as
+    ///   one, and implementations may cap it further
+    /// * `cursor` - Where the previous page ended, or `None` for the first
+    ///   page
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if an internal error occurs.
+    fn lookup_prefix_paged(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError>;
+
     /// Looks up agents across all trust roots (global discovery).
     ///
     /// # Arguments
@@ -124,7 +237,258 @@ pub trait Dht: Send + Sync {
     ///
     /// # Errors
     ///
-    /// Returns `DhtError` if an internal error occurs.
+    /// Returns `DhtError` if an internal error occurs, or
+    /// `ResultLimitExceeded` if more registrations match than the
+    /// implementation returns per query.
     fn lookup_global(&self, capability_path: &CapabilityPath)
         -> Result<Vec<Registration>, DhtError>;
+
+    /// Like [`lookup_global`](Self::lookup_global), grouping the results
+    /// by trust root.
+    ///
+    /// Groups are ordered by trust root and report how many registrations
+    /// matched under each. With `per_root_limit`, each group keeps at most
+    /// that many registrations, so no single trust root dominates the
+    /// results.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` as [`lookup_global`](Self::lookup_global) does;
+    /// the per-query cap applies to all matches before grouping.
+    fn lookup_global_grouped(
+        &self,
+        capability_path: &CapabilityPath,
+        per_root_limit: Option<usize>,
+    ) -> Result<Vec<TrustRootGroup>, DhtError> {
+        self.lookup_global(capability_path)
+            .map(|registrations| TrustRootGroup::group(registrations, per_root_limit))
+    }
+
+    /// Looks up agents whose capability path matches `pattern` within a
+    /// trust root.
+    ///
+    /// Expresses queries such as "any assistant that supports streaming"
+    /// (`assistant/*/streaming`) that a single prefix cannot.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if an internal error occurs, or
+    /// `ResultLimitExceeded` if more registrations match than the
+    /// implementation returns per query. Implementations that match by
+    /// scanning the pattern's [literal prefix](PathPattern::literal_prefix)
+    /// may apply the cap before matching.
+    fn lookup_pattern(
+        &self,
+        trust_root: &TrustRoot,
+        pattern: &PathPattern,
+    ) -> Result<Vec<Registration>, DhtError>;
+
+    /// Looks up agents whose capability path matches `pattern` across all
+    /// trust roots.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` as [`lookup_pattern`](Self::lookup_pattern) does.
+    fn lookup_pattern_global(&self, pattern: &PathPattern)
+        -> Result<Vec<Registration>, DhtError>;
+
+    /// Looks up every agent registered under a trust root.
+    ///
+    /// Lets administrators audit their own namespace without knowing its
+    /// capability paths up front.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if an internal error occurs, or
+    /// `ResultLimitExceeded` if more registrations match than the
+    /// implementation returns per query; use
+    /// [`lookup_trust_root_paged`](Self::lookup_trust_root_paged) for large
+    /// namespaces.
+    fn lookup_trust_root(&self, trust_root: &TrustRoot) -> Result<Vec<Registration>, DhtError>;
+
+    /// Looks up one page of the agents registered under a trust root.
+    ///
+    /// Pages are ordered and continued like those of
+    /// [`lookup_prefix_paged`](Self::lookup_prefix_paged).
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if an internal error occurs.
+    fn lookup_trust_root_paged(
+        &self,
+        trust_root: &TrustRoot,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+    ) -> Result<LookupPage, DhtError>;
+
+    /// Subscribes to changes of registrations under a capability prefix.
+    ///
+    /// Lets routing layers react to agents joining, moving and leaving
+    /// instead of polling [`lookup_prefix`](Self::lookup_prefix). Only
+    /// changes after the call are delivered, so look up the current
+    /// registrations once after subscribing. Dropping the receiver ends the
+    /// subscription.
+    ///
+    /// # Arguments
+    ///
+    /// * `trust_root` - The trust root to watch
+    /// * `capability_path` - The path prefix to watch
+    ///
+    /// # Returns
+    ///
+    /// A receiver of events for agents whose paths start with the query
+    /// path.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the implementation cannot deliver change
+    /// notifications.
+    fn watch_prefix(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+    ) -> Result<Receiver<DhtEvent>, DhtError>;
+
+    /// Like [`lookup_exact`](Self::lookup_exact), returning only
+    /// registrations that match `filter`.
+    ///
+    /// The default implementation filters the unfiltered results, so any
+    /// per-query cap applies before filtering; implementations that can
+    /// filter while scanning should override it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` as [`lookup_exact`](Self::lookup_exact) does.
+    fn lookup_exact_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.lookup_exact(trust_root, capability_path)
+            .map(|registrations| filter.apply(registrations))
+    }
+
+    /// Like [`lookup_prefix`](Self::lookup_prefix), returning only
+    /// registrations that match `filter`.
+    ///
+    /// The default implementation filters the unfiltered results, like
+    /// [`lookup_exact_filtered`](Self::lookup_exact_filtered).
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` as [`lookup_prefix`](Self::lookup_prefix) does.
+    fn lookup_prefix_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.lookup_prefix(trust_root, capability_path)
+            .map(|registrations| filter.apply(registrations))
+    }
+
+    /// Like [`lookup_prefix_paged`](Self::lookup_prefix_paged), returning
+    /// only registrations that match `filter`.
+    ///
+    /// Pages hold up to `limit` matching registrations. The default
+    /// implementation reads unfiltered pages until it has enough, so a
+    /// page may be followed by an empty last page.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` as [`lookup_prefix_paged`](Self::lookup_prefix_paged)
+    /// does.
+    fn lookup_prefix_paged_filtered(
+        &self,
+        trust_root: &TrustRoot,
+        capability_path: &CapabilityPath,
+        limit: usize,
+        cursor: Option<&LookupCursor>,
+        filter: &LookupFilter,
+    ) -> Result<LookupPage, DhtError> {
+        let mut limit = limit.max(1);
+        let mut found = Vec::new();
+        let mut cursor = cursor.cloned();
+        loop {
+            let page =
+                self.lookup_prefix_paged(trust_root, capability_path, limit, cursor.as_ref())?;
+            let more = page.next_cursor.is_some();
+            if more && page.registrations.len() < limit {
+                // The implementation caps pages below the requested limit
+                limit = page.registrations.len().max(1);
+            }
+
+            let mut scanned = page.registrations.into_iter();
+            for registration in scanned.by_ref() {
+                cursor = Some(LookupCursor::after(&registration));
+                if filter.matches(&registration) {
+                    found.push(registration);
+                    if found.len() >= limit {
+                        break;
+                    }
+                }
+            }
+
+            if found.len() >= limit || !more {
+                let more = more || scanned.next().is_some();
+                return Ok(LookupPage {
+                    registrations: found,
+                    next_cursor: cursor.filter(|_| more),
+                });
+            }
+        }
+    }
+
+    /// Like [`lookup_global`](Self::lookup_global), returning only
+    /// registrations that match `filter`.
+    ///
+    /// The default implementation filters the unfiltered results, like
+    /// [`lookup_exact_filtered`](Self::lookup_exact_filtered).
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` as [`lookup_global`](Self::lookup_global) does.
+    fn lookup_global_filtered(
+        &self,
+        capability_path: &CapabilityPath,
+        filter: &LookupFilter,
+    ) -> Result<Vec<Registration>, DhtError> {
+        self.lookup_global(capability_path)
+            .map(|registrations| filter.apply(registrations))
+    }
+
+    /// Checks that the backend is reachable.
+    ///
+    /// The default implementation always succeeds, as in-process
+    /// implementations have nothing to reach; network backends should
+    /// override it with a round trip.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::Backend` if the backend cannot be reached.
+    fn ping(&self) -> Result<(), DhtError> {
+        Ok(())
+    }
+
+    /// Reports whether the backend is reachable and how long a
+    /// [`ping`](Self::ping) took.
+    fn health(&self) -> DhtHealth {
+        let start = Instant::now();
+        let result = self.ping();
+        DhtHealth::from_ping(result, start.elapsed())
+    }
+
+    /// Returns statistics about the registrations stored.
+    ///
+    /// The default implementation reports empty statistics; implementations
+    /// that can count their registrations should override it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError` if the statistics cannot be gathered, e.g. the
+    /// backend is unreachable.
+    fn stats(&self) -> Result<DhtStats, DhtError> {
+        Ok(DhtStats::default())
+    }
 }
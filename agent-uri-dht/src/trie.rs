@@ -4,6 +4,9 @@ use std::collections::HashMap;
 
 use agent_uri::{CapabilityPath, PathSegment};
 
+use crate::PathPattern;
+use crate::pattern::PatternSegment;
+
 /// Trie structure for efficient prefix matching on capability paths.
 ///
 /// Supports:
@@ -37,12 +40,24 @@ use agent_uri::{CapabilityPath, PathSegment};
 /// let prefix = CapabilityPath::parse("assistant").unwrap();
 /// let all_assistants = trie.get_prefix(&prefix);
 /// assert_eq!(all_assistants.len(), 2);
+///
+/// // Inspection
+/// assert_eq!(trie.len(), 2);
+/// assert_eq!(trie.subtree_size(&prefix), 2);
+/// let paths: Vec<String> = trie.keys().map(|path| path.to_string()).collect();
+/// assert_eq!(paths, ["assistant/chat", "assistant/code"]);
 /// ```
+///
+/// With the `serde` feature, a trie serializes as nested maps of path
+/// segments, each node holding its `values` and `children`.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PathTrie<V> {
     /// Children indexed by path segment
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "HashMap::is_empty"))]
     children: HashMap<String, PathTrie<V>>,
     /// Values stored at this node
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
     values: Vec<V>,
 }
 
@@ -62,16 +77,16 @@ impl<V> PathTrie<V> {
         }
     }
 
-    /// Returns the number of values stored at this node.
+    /// Returns the number of values in the trie, at every path.
     #[must_use]
     pub fn len(&self) -> usize {
-        self.values.len()
+        self.total_count()
     }
 
-    /// Returns true if this node has no values.
+    /// Returns true if the trie holds no values.
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.values.is_empty()
+        self.values.is_empty() && self.children.values().all(Self::is_empty)
     }
 
     /// Returns the total number of values in this trie and all descendants.
@@ -99,6 +114,103 @@ impl<V> PathTrie<V> {
     pub fn has_children(&self) -> bool {
         !self.children.is_empty()
     }
+
+    /// Returns the number of nodes below this one.
+    ///
+    /// Removals prune nodes left without values or children, so this counts
+    /// only nodes on the way to a stored value.
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.children.values().map(|child| 1 + child.node_count()).sum()
+    }
+
+    /// Removes `prefix` and every path below it, returning their values.
+    ///
+    /// Ancestors left empty are pruned.
+    pub fn remove_subtree(&mut self, prefix: &CapabilityPath) -> Vec<V> {
+        self.take_subtree(prefix.segments())
+            .map(Self::into_values)
+            .unwrap_or_default()
+    }
+
+    fn take_subtree(&mut self, segments: &[PathSegment]) -> Option<Self> {
+        let (first, rest) = segments.split_first()?;
+        let segment = first.as_str();
+        if rest.is_empty() {
+            return self.children.remove(segment);
+        }
+
+        let child = self.children.get_mut(segment)?;
+        let taken = child.take_subtree(rest);
+        if child.is_empty() {
+            self.children.remove(segment);
+        }
+        taken
+    }
+
+    fn into_values(self) -> Vec<V> {
+        let mut values = self.values;
+        for child in self.children.into_values() {
+            values.extend(child.into_values());
+        }
+        values
+    }
+
+    /// Returns the number of values at `path` and all descendant paths,
+    /// or 0 if the path is not in the trie.
+    #[must_use]
+    pub fn subtree_size(&self, path: &CapabilityPath) -> usize {
+        path.segments()
+            .iter()
+            .try_fold(self, |node, segment| node.children.get(segment.as_str()))
+            .map_or(0, Self::total_count)
+    }
+
+    /// Returns every path holding at least one value, in path order.
+    pub fn keys(&self) -> impl Iterator<Item = CapabilityPath> {
+        let mut keys = Vec::new();
+        self.walk(&mut Vec::new(), &mut |path, _| keys.push(path));
+        keys.into_iter()
+    }
+
+    /// Returns every value with the path it is stored at, in path order.
+    pub fn iter(&self) -> impl Iterator<Item = (CapabilityPath, &V)> {
+        let mut entries = Vec::new();
+        self.walk(&mut Vec::new(), &mut |path, values| {
+            entries.extend(values.iter().map(|value| (path.clone(), value)));
+        });
+        entries.into_iter()
+    }
+
+    /// Visits each node with values, depth first with children in segment
+    /// order, passing its path and values.
+    fn walk<'a>(
+        &'a self,
+        prefix: &mut Vec<&'a str>,
+        visit: &mut impl FnMut(CapabilityPath, &'a [V]),
+    ) {
+        if !self.values.is_empty()
+            && let Ok(path) = CapabilityPath::try_from_strs(prefix)
+        {
+            visit(path, &self.values);
+        }
+        let mut children: Vec<_> = self.children.iter().collect();
+        children.sort_unstable_by_key(|&(segment, _)| segment);
+        for (segment, child) in children {
+            prefix.push(segment);
+            child.walk(prefix, visit);
+            prefix.pop();
+        }
+    }
+}
+
+impl<'a, V> IntoIterator for &'a PathTrie<V> {
+    type Item = (CapabilityPath, &'a V);
+    type IntoIter = std::vec::IntoIter<(CapabilityPath, &'a V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter().collect::<Vec<_>>().into_iter()
+    }
 }
 
 impl<V: Clone> PathTrie<V> {
@@ -160,6 +272,40 @@ impl<V: Clone> PathTrie<V> {
         }
     }
 
+    /// Returns values at every path matching `pattern` (not including
+    /// descendants).
+    #[must_use]
+    pub fn get_matching(&self, pattern: &PathPattern) -> Vec<&V> {
+        let mut result = Vec::new();
+        self.collect_matching(pattern.segments(), &mut result);
+        result
+    }
+
+    fn collect_matching<'a>(&'a self, segments: &[PatternSegment], result: &mut Vec<&'a V>) {
+        let Some((first, rest)) = segments.split_first() else {
+            result.extend(&self.values);
+            return;
+        };
+        match first {
+            PatternSegment::Any => {
+                for child in self.children.values() {
+                    child.collect_matching(rest, result);
+                }
+            }
+            PatternSegment::Literal(segment) => {
+                if let Some(child) = self.children.get(segment.as_str()) {
+                    child.collect_matching(rest, result);
+                }
+            }
+        }
+    }
+
+    /// Returns every value in the trie.
+    #[must_use]
+    pub fn get_all(&self) -> Vec<&V> {
+        self.collect_all()
+    }
+
     /// Collects all values at this node and all descendants.
     fn collect_all(&self) -> Vec<&V> {
         let mut result: Vec<&V> = self.values.iter().collect();
@@ -199,7 +345,7 @@ impl<V: Clone> PathTrie<V> {
         if let Some(child) = self.children.get_mut(&segment) {
             let removed = child.remove_at_segments(segments, index + 1, predicate);
             // Clean up empty children
-            if child.is_empty() && !child.has_children() {
+            if child.is_empty() {
                 self.children.remove(&segment);
             }
             removed
@@ -252,6 +398,22 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    #[test]
+    fn get_matching_expands_wildcards() {
+        let mut trie: PathTrie<String> = PathTrie::new();
+        for path in ["assistant/chat/streaming", "assistant/code/streaming", "assistant/chat"] {
+            trie.insert(&CapabilityPath::parse(path).unwrap(), path.to_string());
+        }
+
+        let pattern = PathPattern::parse("assistant/*/streaming").unwrap();
+        let mut results = trie.get_matching(&pattern);
+        results.sort();
+        assert_eq!(results, ["assistant/chat/streaming", "assistant/code/streaming"]);
+
+        let pattern = PathPattern::parse("*/chat").unwrap();
+        assert_eq!(trie.get_matching(&pattern), ["assistant/chat"]);
+    }
+
     #[test]
     fn get_exact_returns_empty_for_nonexistent_path() {
         let trie: PathTrie<String> = PathTrie::new();
@@ -404,6 +566,92 @@ mod tests {
         assert!(trie.is_empty());
     }
 
+    #[test]
+    fn remove_subtree_takes_descendants_and_prunes() {
+        let mut trie: PathTrie<&str> = PathTrie::new();
+        for path in ["team/a/b", "team/a/c", "team/a", "team/d", "other"] {
+            trie.insert(&CapabilityPath::parse(path).unwrap(), path);
+        }
+        assert_eq!(trie.node_count(), 6);
+
+        let mut removed = trie.remove_subtree(&CapabilityPath::parse("team/a").unwrap());
+        removed.sort_unstable();
+        assert_eq!(removed, ["team/a", "team/a/b", "team/a/c"]);
+        assert_eq!(trie.node_count(), 3);
+
+        assert_eq!(trie.remove_subtree(&CapabilityPath::parse("team").unwrap()), ["team/d"]);
+        assert_eq!(trie.node_count(), 1);
+        assert!(trie.remove_subtree(&CapabilityPath::parse("missing/x").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn removing_a_deep_leaf_prunes_its_ancestors() {
+        let mut trie: PathTrie<&str> = PathTrie::new();
+        trie.insert(&CapabilityPath::parse("a/b/c/d").unwrap(), "deep");
+        trie.insert(&CapabilityPath::parse("a/x").unwrap(), "shallow");
+
+        trie.remove(&CapabilityPath::parse("a/b/c/d").unwrap(), |_| true);
+        assert_eq!(trie.node_count(), 2);
+        assert_eq!(trie.keys().count(), 1);
+    }
+
+    #[test]
+    fn len_counts_every_path() {
+        let mut trie: PathTrie<String> = PathTrie::new();
+        trie.insert(&CapabilityPath::parse("a/b").unwrap(), "ab".to_string());
+        trie.insert(&CapabilityPath::parse("a/b/c").unwrap(), "abc".to_string());
+
+        assert_eq!(trie.len(), 2);
+        assert!(!trie.is_empty());
+        trie.remove(&CapabilityPath::parse("a/b/c").unwrap(), |_| true);
+        trie.remove(&CapabilityPath::parse("a/b").unwrap(), |_| true);
+        assert!(trie.is_empty());
+        assert!(!trie.has_children());
+    }
+
+    #[test]
+    fn iteration_is_in_path_order() {
+        let mut trie: PathTrie<String> = PathTrie::new();
+        for path in ["workflow", "assistant/code", "assistant", "assistant/chat", "assistant"] {
+            trie.insert(&CapabilityPath::parse(path).unwrap(), path.to_string());
+        }
+
+        let keys: Vec<String> = trie.keys().map(|path| path.to_string()).collect();
+        assert_eq!(keys, ["assistant", "assistant/chat", "assistant/code", "workflow"]);
+
+        let entries: Vec<(String, &String)> =
+            trie.iter().map(|(path, value)| (path.to_string(), value)).collect();
+        assert_eq!(entries.len(), 5);
+        assert!(entries.iter().all(|(path, value)| path == *value));
+        assert_eq!(entries[1].0, "assistant");
+        assert_eq!((&trie).into_iter().count(), trie.len());
+    }
+
+    #[test]
+    fn subtree_size_counts_descendants() {
+        let mut trie: PathTrie<u32> = PathTrie::new();
+        trie.insert(&CapabilityPath::parse("a").unwrap(), 1);
+        trie.insert(&CapabilityPath::parse("a/b").unwrap(), 2);
+        trie.insert(&CapabilityPath::parse("a/b/c").unwrap(), 3);
+        trie.insert(&CapabilityPath::parse("d").unwrap(), 4);
+
+        assert_eq!(trie.subtree_size(&CapabilityPath::parse("a").unwrap()), 3);
+        assert_eq!(trie.subtree_size(&CapabilityPath::parse("a/b").unwrap()), 2);
+        assert_eq!(trie.subtree_size(&CapabilityPath::parse("a/x").unwrap()), 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut trie: PathTrie<u32> = PathTrie::new();
+        trie.insert(&CapabilityPath::parse("a/b").unwrap(), 7);
+
+        let json = serde_json::to_string(&trie).unwrap();
+        assert_eq!(json, r#"{"children":{"a":{"children":{"b":{"values":[7]}}}}}"#);
+        let restored: PathTrie<u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.get_exact(&CapabilityPath::parse("a/b").unwrap()), [&7]);
+    }
+
     #[test]
     fn deep_hierarchy() {
         let mut trie: PathTrie<String> = PathTrie::new();
@@ -0,0 +1,187 @@
+//! TXT record encoding of registrations, shared by the DNS-SD backends.
+
+use std::collections::BTreeMap;
+
+use agent_uri::AgentUri;
+use sha2::{Digest, Sha256};
+
+use crate::endpoint::split_uri;
+use crate::registration::{millis_to_system_time, system_time_to_millis};
+use crate::{DhtError, Endpoint, Registration};
+
+/// Longest TXT property, key and value together.
+pub(crate) const MAX_PROPERTY_LEN: usize = 255;
+
+/// Longest piece of an attestation token in one `att{i}` property.
+const ATTESTATION_CHUNK_LEN: usize = 240;
+
+/// Returns the DNS-SD instance name of an agent: a hash of its URI, as
+/// URIs may exceed the 63-byte label limit.
+pub(crate) fn instance_name(agent_uri: &AgentUri) -> String {
+    use std::fmt::Write;
+
+    let digest = Sha256::digest(agent_uri.as_str().as_bytes());
+    digest[..8].iter().fold(String::from("agent-"), |mut name, b| {
+        let _ = write!(name, "{b:02x}");
+        name
+    })
+}
+
+/// Encodes `registration` as TXT properties.
+///
+/// # Errors
+///
+/// Returns `DhtError::Backend` if a property is longer than
+/// [`MAX_PROPERTY_LEN`].
+pub(crate) fn encode(registration: &Registration) -> Result<Vec<(String, String)>, DhtError> {
+    let mut properties = vec![
+        ("uri".to_string(), registration.agent_uri().to_string()),
+        ("seq".to_string(), registration.seq().to_string()),
+        ("signed".to_string(), system_time_to_millis(registration.signed_at()).to_string()),
+        ("exp".to_string(), system_time_to_millis(registration.expires_at()).to_string()),
+    ];
+    for (i, endpoint) in registration.endpoints().iter().enumerate() {
+        properties.push((format!("ep{i}"), endpoint.to_uri()));
+    }
+    if let Some(attestation) = registration.attestation() {
+        for (i, chunk) in chunks(attestation, ATTESTATION_CHUNK_LEN).into_iter().enumerate() {
+            properties.push((format!("att{i}"), chunk.to_string()));
+        }
+    }
+    if let Some(nonce) = registration.proof_of_work() {
+        properties.push(("pow".to_string(), nonce.to_string()));
+    }
+    for (key, value) in registration.metadata() {
+        properties.push((format!("md.{key}"), value.clone()));
+    }
+
+    match properties
+        .iter()
+        .find(|(key, value)| key.len() + 1 + value.len() > MAX_PROPERTY_LEN)
+    {
+        Some((key, _)) => Err(DhtError::backend(format!(
+            "TXT property '{key}' is longer than {MAX_PROPERTY_LEN} bytes"
+        ))),
+        None => Ok(properties),
+    }
+}
+
+/// Decodes TXT properties into a registration, or None if they do not
+/// carry an agent URI.
+pub(crate) fn decode<'a>(
+    properties: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Option<Registration> {
+    let properties: BTreeMap<&str, &str> = properties.into_iter().collect();
+    let property = |key: &str| properties.get(key).copied();
+    let number = |key: &str| property(key).and_then(|value| value.parse::<u64>().ok());
+
+    let agent_uri = AgentUri::parse(property("uri")?).ok()?;
+    let endpoints = (0..)
+        .map_while(|i| property(&format!("ep{i}")))
+        .filter_map(parse_endpoint)
+        .collect();
+    let mut registration =
+        Registration::new(agent_uri, endpoints).with_seq(number("seq").unwrap_or(0));
+    if let Some(signed) = number("signed") {
+        registration = registration.with_signed_at(millis_to_system_time(signed));
+    }
+    if let Some(expires) = number("exp") {
+        registration = registration.with_expires_at(millis_to_system_time(expires));
+    }
+    let attestation: String = (0..).map_while(|i| property(&format!("att{i}"))).collect();
+    if !attestation.is_empty() {
+        registration = registration.with_attestation(attestation);
+    }
+    if let Some(nonce) = number("pow") {
+        registration = registration.with_proof_of_work(nonce);
+    }
+    for (key, value) in &properties {
+        if let Some(key) = key.strip_prefix("md.") {
+            registration = registration.with_metadata(key, *value);
+        }
+    }
+    Some(registration)
+}
+
+/// Parses an endpoint URI written by [`Endpoint::to_uri`], as written.
+fn parse_endpoint(uri: &str) -> Option<Endpoint> {
+    let (protocol, address, path) = split_uri(uri)?;
+    Some(Endpoint::new(protocol, address, path))
+}
+
+/// Splits `value` into pieces of at most `max` bytes, on character
+/// boundaries.
+fn chunks(value: &str, max: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = value;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(max);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    fn test_uri() -> AgentUri {
+        AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q")
+            .unwrap()
+    }
+
+    #[test]
+    fn properties_round_trip_registrations() {
+        let expires_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_900_000_000);
+        let registration = Registration::new(
+            test_uri(),
+            vec![
+                Endpoint::https("192.168.1.20:8443"),
+                Endpoint::https_with_path("agent.local", "/v1/agent"),
+                Endpoint::multiaddr("/ip4/192.168.1.20/tcp/4001"),
+            ],
+        )
+        .with_seq(7)
+        .with_expires_at(expires_at)
+        .with_attestation("v4.public.".to_string() + &"x".repeat(600))
+        .with_proof_of_work(42)
+        .with_metadata("tier", "edge");
+
+        let properties = encode(&registration).unwrap();
+        let decoded =
+            decode(properties.iter().map(|(k, v)| (k.as_str(), v.as_str()))).unwrap();
+
+        assert_eq!(decoded.agent_uri(), &test_uri());
+        assert_eq!(decoded.endpoints(), registration.endpoints());
+        assert_eq!(decoded.attestation(), registration.attestation());
+        assert_eq!(decoded.proof_of_work(), Some(42));
+        assert_eq!(decoded.metadata(), registration.metadata());
+        assert_eq!(decoded.seq(), 7);
+        assert_eq!(decoded.expires_at(), expires_at);
+        assert!(decode([("seq", "1")]).is_none());
+    }
+
+    #[test]
+    fn oversized_properties_are_rejected() {
+        let registration = Registration::new(test_uri(), vec![Endpoint::https("agent.local")])
+            .with_metadata("notes", "x".repeat(300));
+        let error = encode(&registration).unwrap_err();
+        assert!(error.to_string().contains("'md.notes'"));
+    }
+
+    #[test]
+    fn instance_names_fit_a_dns_label() {
+        let name = instance_name(&test_uri());
+        assert_eq!(name, instance_name(&test_uri()));
+        assert!(name.len() <= 63);
+        assert_eq!(chunks("ab€cd", 3), ["ab", "€", "cd"]);
+        assert!(parse_endpoint("not an endpoint").is_none());
+    }
+}
@@ -0,0 +1,71 @@
+//! Admission checks run before a registration is stored.
+
+use std::time::SystemTime;
+
+use crate::{DhtError, Registration};
+
+/// Decides whether a registration may be written to the DHT.
+///
+/// Without a validator, anyone can register any agent URI and capability
+/// path and poison lookups for it. A validator closes that gap by checking
+/// the registration's attestation token against the agent URI it claims,
+/// including that the attested capabilities cover the capability path the
+/// registration is indexed under.
+///
+/// With the `dht` feature, `agent_uri_attestation::Verifier` implements
+/// this trait. Closures taking a `&Registration` implement it too.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri::AgentUri;
+/// use agent_uri_dht::{Dht, DhtError, Endpoint, Registration, SimulatedDht};
+///
+/// let dht = SimulatedDht::with_defaults().with_validator(|registration: &Registration| {
+///     match registration.attestation() {
+///         Some(_) => Ok(()),
+///         None => Err(DhtError::invalid_attestation(
+///             registration.agent_uri().as_str(),
+///             "missing attestation token",
+///         )),
+///     }
+/// });
+///
+/// let uri = AgentUri::parse("agent://acme.com/assistant/chat/llm_01h455vb4pex5vsknk084sn02q").unwrap();
+/// let registration = Registration::new(uri, vec![Endpoint::https("agent.acme.com")]);
+/// assert!(dht.register(registration.clone()).is_err());
+/// assert!(dht.register(registration.with_attestation("v4.public.token")).is_ok());
+/// ```
+pub trait RegistrationValidator: Send + Sync {
+    /// Checks `registration` before it is stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DhtError::InvalidAttestation` if the attestation is missing
+    /// or does not verify, or `DhtError::CapabilityMismatch` if it does not
+    /// cover the registration's capability path.
+    fn validate(&self, registration: &Registration) -> Result<(), DhtError>;
+
+    /// Checks `registration` as [`validate`](Self::validate) does and
+    /// returns when its attestation expires, if the validator knows.
+    ///
+    /// [`AttestationRanking`](crate::AttestationRanking) ranks verified
+    /// registrations by the token lifetime this reports. The default
+    /// validates and reports the lifetime as unknown.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors [`validate`](Self::validate) does.
+    fn attested_until(&self, registration: &Registration) -> Result<Option<SystemTime>, DhtError> {
+        self.validate(registration).map(|()| None)
+    }
+}
+
+impl<F> RegistrationValidator for F
+where
+    F: Fn(&Registration) -> Result<(), DhtError> + Send + Sync,
+{
+    fn validate(&self, registration: &Registration) -> Result<(), DhtError> {
+        self(registration)
+    }
+}
@@ -0,0 +1,91 @@
+//! Change notifications for registrations under a capability prefix.
+
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use agent_uri::{AgentUri, CapabilityPath, TrustRoot};
+
+use crate::Registration;
+
+/// A change to a registration, delivered by
+/// [`Dht::watch_prefix`](crate::Dht::watch_prefix).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DhtEvent {
+    /// A new registration was stored.
+    Registered(Registration),
+    /// A registration's endpoints or expiry changed; carries the updated
+    /// registration.
+    Updated(Registration),
+    /// An agent was deregistered.
+    Deregistered(AgentUri),
+    /// A registration expired and was removed.
+    Expired(AgentUri),
+    /// A registration was evicted to make room for another.
+    Evicted(AgentUri),
+}
+
+impl DhtEvent {
+    /// Returns the URI of the agent the event concerns.
+    #[must_use]
+    pub fn agent_uri(&self) -> &AgentUri {
+        match self {
+            Self::Registered(registration) | Self::Updated(registration) => {
+                registration.agent_uri()
+            }
+            Self::Deregistered(agent_uri) | Self::Expired(agent_uri) | Self::Evicted(agent_uri) => {
+                agent_uri
+            }
+        }
+    }
+}
+
+/// Subscribers to [`DhtEvent`]s, for `Dht` implementations.
+#[derive(Debug, Default)]
+pub(crate) struct Watchers {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+#[derive(Debug)]
+struct Subscriber {
+    trust_root: TrustRoot,
+    prefix: CapabilityPath,
+    sender: Sender<DhtEvent>,
+}
+
+impl Watchers {
+    /// Returns a receiver for events about agents at `prefix` or below it
+    /// under `trust_root`.
+    pub(crate) fn subscribe(
+        &self,
+        trust_root: &TrustRoot,
+        prefix: &CapabilityPath,
+    ) -> Receiver<DhtEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .expect("lock poisoned")
+            .push(Subscriber {
+                trust_root: trust_root.clone(),
+                prefix: prefix.clone(),
+                sender,
+            });
+        receiver
+    }
+
+    /// Delivers `event` to every matching subscriber, forgetting those whose
+    /// receiver was dropped.
+    pub(crate) fn publish(&self, event: &DhtEvent) {
+        let agent_uri = event.agent_uri();
+        self.subscribers
+            .lock()
+            .expect("lock poisoned")
+            .retain(|subscriber| {
+                if subscriber.trust_root != *agent_uri.trust_root()
+                    || !agent_uri.capability_path().starts_with(&subscriber.prefix)
+                {
+                    return true;
+                }
+                subscriber.sender.send(event.clone()).is_ok()
+            });
+    }
+}
@@ -168,8 +168,9 @@ fn run_queries(
 
     // Use a subset of registered paths as queries
     // This ensures queries will have at least some matches
-    for path in paths.iter().take(num_queries) {
-        match evaluator.evaluate_query(path, mode) {
+    let queries = &paths[..num_queries.min(paths.len())];
+    for (path, result) in queries.iter().zip(evaluator.evaluate_queries(queries, mode)) {
+        match result {
             Ok(result) => results.push(result),
             Err(e) => {
                 // Log error but continue with other queries
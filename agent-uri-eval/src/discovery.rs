@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::error::DiscoveryError;
 use crate::metrics::{count_as_f64, PrecisionRecallMetrics};
+use crate::parallel;
 
 /// Configuration for discovery evaluation.
 #[derive(Debug, Clone)]
@@ -242,6 +243,19 @@ impl DiscoveryEvaluator {
         })
     }
 
+    /// Evaluates a query for each of `paths`, returning the results in the
+    /// same order.
+    ///
+    /// With the `parallel` feature, queries run on all cores.
+    #[must_use]
+    pub fn evaluate_queries(
+        &self,
+        paths: &[CapabilityPath],
+        mode: MatchMode,
+    ) -> Vec<Result<QueryResult, DiscoveryError>> {
+        parallel::map(paths, |path| self.evaluate_query(path, mode))
+    }
+
     /// Returns registered paths for generating queries.
     #[must_use]
     pub fn registered_paths(&self) -> Vec<String> {
@@ -433,6 +447,31 @@ mod tests {
         assert!((result.metrics.recall - 1.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn evaluate_queries_keeps_query_order() {
+        let config = DiscoveryConfig::default();
+        let mut eval = DiscoveryEvaluator::new(&config).unwrap();
+        let paths: Vec<CapabilityPath> = ["assistant/chat", "assistant", "workflow/approval"]
+            .iter()
+            .map(|path| CapabilityPath::parse(path).unwrap())
+            .collect();
+        eval.register_agent(&paths[0], "agenta").unwrap();
+        eval.register_agent(&paths[2], "agentb").unwrap();
+
+        let results = eval.evaluate_queries(&paths, MatchMode::Prefix);
+        let returned: Vec<(&str, usize)> = results
+            .iter()
+            .map(|result| {
+                let result = result.as_ref().unwrap();
+                (result.query_path.as_str(), result.returned_count)
+            })
+            .collect();
+        assert_eq!(
+            returned,
+            [("assistant/chat", 1), ("assistant", 1), ("workflow/approval", 1)]
+        );
+    }
+
     #[test]
     fn aggregate_results_computes_means() {
         let results = vec![
@@ -25,6 +25,20 @@ pub enum EvalError {
         /// Error message.
         message: String,
     },
+    /// Failed to export results to a tabular format.
+    Export {
+        /// Output format (`csv` or `parquet`).
+        format: String,
+        /// Error message.
+        message: String,
+    },
+    /// Invalid or unreadable runner configuration.
+    Config {
+        /// Path to the configuration file.
+        path: String,
+        /// Error message.
+        message: String,
+    },
 }
 
 impl fmt::Display for EvalError {
@@ -39,6 +53,12 @@ impl fmt::Display for EvalError {
             Self::Json { context, message } => {
                 write!(f, "JSON error in {context}: {message}")
             }
+            Self::Export { format, message } => {
+                write!(f, "{format} export failed: {message}")
+            }
+            Self::Config { path, message } => {
+                write!(f, "invalid config '{path}': {message}")
+            }
         }
     }
 }
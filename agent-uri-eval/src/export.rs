@@ -0,0 +1,599 @@
+//! Tabular export of evaluation results to CSV and Parquet.
+//!
+//! Results are flattened into row types ([`ExpressivenessRow`],
+//! [`DiscoveryRow`], [`QueryRow`]) that load directly into pandas or duckdb:
+//!
+//! ```rust
+//! use agent_uri_eval::{
+//!     evaluate_expressiveness, write_csv, ExpressivenessRow, MappingConfig, ToolDef,
+//!     ToolSource,
+//! };
+//!
+//! let tools = vec![ToolDef::with_category("readFile", "filesystem", ToolSource::Mcp)];
+//! let results = evaluate_expressiveness(&tools, &MappingConfig::default());
+//!
+//! let mut csv = Vec::new();
+//! write_csv(&mut csv, &[ExpressivenessRow::new("hierarchical", &results)]).unwrap();
+//! assert!(String::from_utf8(csv).unwrap().starts_with("label,total_tools,"));
+//! ```
+//!
+//! Parquet output requires the `parquet` feature.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::discovery::{DiscoveryResults, MatchMode, QueryResult};
+use crate::error::EvalError;
+use crate::expressiveness::ExpressivenessResults;
+use crate::report::EvaluationReport;
+
+/// Type of an exported column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    /// UTF-8 text.
+    Text,
+    /// Unsigned 64-bit integer.
+    Integer,
+    /// 64-bit float.
+    Float,
+    /// Boolean.
+    Boolean,
+}
+
+/// Name and type of an exported column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    /// Column name, used as the CSV header and Parquet field name.
+    pub name: &'static str,
+    /// Column type.
+    pub kind: ColumnKind,
+}
+
+impl Column {
+    const fn new(name: &'static str, kind: ColumnKind) -> Self {
+        Self { name, kind }
+    }
+}
+
+/// A single value in an exported row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    /// Text value.
+    Text(String),
+    /// Integer value.
+    Integer(u64),
+    /// Float value.
+    Float(f64),
+    /// Boolean value.
+    Boolean(bool),
+}
+
+impl Cell {
+    fn to_field(&self) -> String {
+        match self {
+            Self::Text(s) => s.clone(),
+            Self::Integer(n) => n.to_string(),
+            Self::Float(x) => x.to_string(),
+            Self::Boolean(b) => b.to_string(),
+        }
+    }
+}
+
+/// A flat record that can be written as one row of a table.
+pub trait TableRow {
+    /// Columns of the table, in order.
+    const COLUMNS: &'static [Column];
+
+    /// Values of this row, one per entry in [`COLUMNS`](Self::COLUMNS).
+    fn cells(&self) -> Vec<Cell>;
+}
+
+/// One row per expressiveness run (hierarchical or flat ablation).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpressivenessRow {
+    /// Run label, e.g. `hierarchical` or `flat`.
+    pub label: String,
+    /// Total tools in the corpus.
+    pub total_tools: usize,
+    /// Tools that mapped successfully.
+    pub mapped_tools: usize,
+    /// Tools that failed to map.
+    pub unmapped_tools: usize,
+    /// Coverage rate (mapped / total).
+    pub coverage_rate: f64,
+    /// Number of unique paths.
+    pub unique_paths: usize,
+    /// Number of paths with collisions.
+    pub collision_count: usize,
+    /// Collision rate.
+    pub collision_rate: f64,
+    /// Mean path depth.
+    pub depth_mean: f64,
+    /// Standard deviation of path depth.
+    pub depth_stddev: f64,
+    /// Minimum path depth.
+    pub depth_min: usize,
+    /// Maximum path depth.
+    pub depth_max: usize,
+    /// Whether all success criteria were met.
+    pub all_passed: bool,
+}
+
+impl ExpressivenessRow {
+    /// Flattens `results` into a row labelled `label`.
+    #[must_use]
+    pub fn new(label: impl Into<String>, results: &ExpressivenessResults) -> Self {
+        Self {
+            label: label.into(),
+            total_tools: results.coverage.total_tools,
+            mapped_tools: results.coverage.mapped_tools,
+            unmapped_tools: results.coverage.unmapped_tools,
+            coverage_rate: results.coverage.coverage_rate,
+            unique_paths: results.collisions.unique_paths,
+            collision_count: results.collisions.collision_count,
+            collision_rate: results.collisions.collision_rate,
+            depth_mean: results.depth_distribution.mean,
+            depth_stddev: results.depth_distribution.stddev,
+            depth_min: results.depth_distribution.min,
+            depth_max: results.depth_distribution.max,
+            all_passed: results.criteria.all_passed(),
+        }
+    }
+
+    /// Rows for the report's `hierarchical` and `flat` results, if present.
+    #[must_use]
+    pub fn from_report(report: &EvaluationReport) -> Vec<Self> {
+        [
+            ("hierarchical", &report.expressiveness),
+            ("flat", &report.expressiveness_flat),
+        ]
+        .into_iter()
+        .filter_map(|(label, results)| results.as_ref().map(|r| Self::new(label, r)))
+        .collect()
+    }
+}
+
+impl TableRow for ExpressivenessRow {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("label", ColumnKind::Text),
+        Column::new("total_tools", ColumnKind::Integer),
+        Column::new("mapped_tools", ColumnKind::Integer),
+        Column::new("unmapped_tools", ColumnKind::Integer),
+        Column::new("coverage_rate", ColumnKind::Float),
+        Column::new("unique_paths", ColumnKind::Integer),
+        Column::new("collision_count", ColumnKind::Integer),
+        Column::new("collision_rate", ColumnKind::Float),
+        Column::new("depth_mean", ColumnKind::Float),
+        Column::new("depth_stddev", ColumnKind::Float),
+        Column::new("depth_min", ColumnKind::Integer),
+        Column::new("depth_max", ColumnKind::Integer),
+        Column::new("all_passed", ColumnKind::Boolean),
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::Text(self.label.clone()),
+            Cell::Integer(self.total_tools as u64),
+            Cell::Integer(self.mapped_tools as u64),
+            Cell::Integer(self.unmapped_tools as u64),
+            Cell::Float(self.coverage_rate),
+            Cell::Integer(self.unique_paths as u64),
+            Cell::Integer(self.collision_count as u64),
+            Cell::Float(self.collision_rate),
+            Cell::Float(self.depth_mean),
+            Cell::Float(self.depth_stddev),
+            Cell::Integer(self.depth_min as u64),
+            Cell::Integer(self.depth_max as u64),
+            Cell::Boolean(self.all_passed),
+        ]
+    }
+}
+
+/// One row per aggregated discovery run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveryRow {
+    /// Match mode tested.
+    pub match_mode: MatchMode,
+    /// Number of agents registered.
+    pub num_agents: usize,
+    /// Number of queries run.
+    pub num_queries: usize,
+    /// Mean precision across queries.
+    pub mean_precision: f64,
+    /// Mean recall across queries.
+    pub mean_recall: f64,
+    /// Mean F1 across queries.
+    pub mean_f1: f64,
+    /// Standard deviation of precision.
+    pub stddev_precision: f64,
+    /// Standard deviation of recall.
+    pub stddev_recall: f64,
+    /// Mean result set size.
+    pub mean_result_size: f64,
+}
+
+impl DiscoveryRow {
+    /// Rows for the report's prefix and exact results, if present.
+    #[must_use]
+    pub fn from_report(report: &EvaluationReport) -> Vec<Self> {
+        [&report.discovery_prefix, &report.discovery_exact]
+            .into_iter()
+            .flatten()
+            .map(Self::from)
+            .collect()
+    }
+}
+
+impl From<&DiscoveryResults> for DiscoveryRow {
+    fn from(results: &DiscoveryResults) -> Self {
+        Self {
+            match_mode: results.match_mode,
+            num_agents: results.num_agents,
+            num_queries: results.num_queries,
+            mean_precision: results.mean_precision,
+            mean_recall: results.mean_recall,
+            mean_f1: results.mean_f1,
+            stddev_precision: results.stddev_precision,
+            stddev_recall: results.stddev_recall,
+            mean_result_size: results.mean_result_size,
+        }
+    }
+}
+
+impl TableRow for DiscoveryRow {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("match_mode", ColumnKind::Text),
+        Column::new("num_agents", ColumnKind::Integer),
+        Column::new("num_queries", ColumnKind::Integer),
+        Column::new("mean_precision", ColumnKind::Float),
+        Column::new("mean_recall", ColumnKind::Float),
+        Column::new("mean_f1", ColumnKind::Float),
+        Column::new("stddev_precision", ColumnKind::Float),
+        Column::new("stddev_recall", ColumnKind::Float),
+        Column::new("mean_result_size", ColumnKind::Float),
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::Text(mode_name(self.match_mode).to_string()),
+            Cell::Integer(self.num_agents as u64),
+            Cell::Integer(self.num_queries as u64),
+            Cell::Float(self.mean_precision),
+            Cell::Float(self.mean_recall),
+            Cell::Float(self.mean_f1),
+            Cell::Float(self.stddev_precision),
+            Cell::Float(self.stddev_recall),
+            Cell::Float(self.mean_result_size),
+        ]
+    }
+}
+
+/// One row per discovery query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryRow {
+    /// The query path.
+    pub query_path: String,
+    /// Match mode used.
+    pub match_mode: MatchMode,
+    /// Number of agents returned.
+    pub returned_count: usize,
+    /// Number of relevant agents (ground truth).
+    pub relevant_count: usize,
+    /// Precision.
+    pub precision: f64,
+    /// Recall.
+    pub recall: f64,
+    /// F1 score.
+    pub f1: f64,
+    /// Relevant agents returned.
+    pub true_positives: usize,
+    /// Irrelevant agents returned.
+    pub false_positives: usize,
+    /// Relevant agents not returned.
+    pub false_negatives: usize,
+}
+
+impl QueryRow {
+    /// Rows for the per-query details kept in the report.
+    ///
+    /// Details are only present when results were aggregated with
+    /// `include_details`; otherwise this is empty.
+    #[must_use]
+    pub fn from_report(report: &EvaluationReport) -> Vec<Self> {
+        [&report.discovery_prefix, &report.discovery_exact]
+            .into_iter()
+            .flatten()
+            .filter_map(|d| d.query_results.as_ref())
+            .flatten()
+            .map(Self::from)
+            .collect()
+    }
+}
+
+impl From<&QueryResult> for QueryRow {
+    fn from(result: &QueryResult) -> Self {
+        Self {
+            query_path: result.query_path.clone(),
+            match_mode: result.match_mode,
+            returned_count: result.returned_count,
+            relevant_count: result.relevant_count,
+            precision: result.metrics.precision,
+            recall: result.metrics.recall,
+            f1: result.metrics.f1,
+            true_positives: result.metrics.true_positives,
+            false_positives: result.metrics.false_positives,
+            false_negatives: result.metrics.false_negatives,
+        }
+    }
+}
+
+impl TableRow for QueryRow {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("query_path", ColumnKind::Text),
+        Column::new("match_mode", ColumnKind::Text),
+        Column::new("returned_count", ColumnKind::Integer),
+        Column::new("relevant_count", ColumnKind::Integer),
+        Column::new("precision", ColumnKind::Float),
+        Column::new("recall", ColumnKind::Float),
+        Column::new("f1", ColumnKind::Float),
+        Column::new("true_positives", ColumnKind::Integer),
+        Column::new("false_positives", ColumnKind::Integer),
+        Column::new("false_negatives", ColumnKind::Integer),
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::Text(self.query_path.clone()),
+            Cell::Text(mode_name(self.match_mode).to_string()),
+            Cell::Integer(self.returned_count as u64),
+            Cell::Integer(self.relevant_count as u64),
+            Cell::Float(self.precision),
+            Cell::Float(self.recall),
+            Cell::Float(self.f1),
+            Cell::Integer(self.true_positives as u64),
+            Cell::Integer(self.false_positives as u64),
+            Cell::Integer(self.false_negatives as u64),
+        ]
+    }
+}
+
+/// Writes `rows` as CSV with a header line.
+///
+/// # Errors
+///
+/// Returns `EvalError::Export` if writing fails.
+pub fn write_csv<W: Write, R: TableRow>(writer: W, rows: &[R]) -> Result<(), EvalError> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(R::COLUMNS.iter().map(|c| c.name))
+        .map_err(|e| export_error("csv", e))?;
+    for row in rows {
+        csv.write_record(row.cells().iter().map(Cell::to_field))
+            .map_err(|e| export_error("csv", e))?;
+    }
+    csv.flush().map_err(|e| export_error("csv", e))
+}
+
+/// Writes `rows` as CSV to the file at `path`, replacing it if it exists.
+///
+/// # Errors
+///
+/// Returns `EvalError::Io` if the file cannot be created and
+/// `EvalError::Export` if writing fails.
+pub fn write_csv_file<R: TableRow>(path: &Path, rows: &[R]) -> Result<(), EvalError> {
+    write_csv(create(path)?, rows)
+}
+
+/// Writes `rows` as a single-row-group Parquet file.
+///
+/// # Errors
+///
+/// Returns `EvalError::Export` if encoding or writing fails.
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: Write + Send, R: TableRow>(writer: W, rows: &[R]) -> Result<(), EvalError> {
+    let batch = record_batch(rows)?;
+    let mut parquet = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)
+        .map_err(|e| export_error("parquet", e))?;
+    parquet
+        .write(&batch)
+        .map_err(|e| export_error("parquet", e))?;
+    parquet.close().map_err(|e| export_error("parquet", e))?;
+    Ok(())
+}
+
+/// Writes `rows` as Parquet to the file at `path`, replacing it if it exists.
+///
+/// # Errors
+///
+/// Returns `EvalError::Io` if the file cannot be created and
+/// `EvalError::Export` if encoding or writing fails.
+#[cfg(feature = "parquet")]
+pub fn write_parquet_file<R: TableRow>(path: &Path, rows: &[R]) -> Result<(), EvalError> {
+    write_parquet(create(path)?, rows)
+}
+
+/// Builds an Arrow record batch with one array per column.
+#[cfg(feature = "parquet")]
+fn record_batch<R: TableRow>(rows: &[R]) -> Result<arrow_array::RecordBatch, EvalError> {
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, BooleanArray, Float64Array, StringArray, UInt64Array};
+    use arrow_schema::{DataType, Field, Schema};
+
+    let cells: Vec<Vec<Cell>> = rows.iter().map(TableRow::cells).collect();
+    let column = |i: usize| cells.iter().map(move |row| &row[i]);
+
+    let mut fields = Vec::with_capacity(R::COLUMNS.len());
+    let mut arrays: Vec<ArrayRef> = Vec::with_capacity(R::COLUMNS.len());
+    for (i, col) in R::COLUMNS.iter().enumerate() {
+        let (data_type, array): (_, ArrayRef) = match col.kind {
+            ColumnKind::Text => (
+                DataType::Utf8,
+                Arc::new(
+                    column(i)
+                        .map(|c| match c {
+                            Cell::Text(s) => Some(s.as_str()),
+                            _ => None,
+                        })
+                        .collect::<StringArray>(),
+                ),
+            ),
+            ColumnKind::Integer => (
+                DataType::UInt64,
+                Arc::new(
+                    column(i)
+                        .map(|c| match c {
+                            Cell::Integer(n) => Some(*n),
+                            _ => None,
+                        })
+                        .collect::<UInt64Array>(),
+                ),
+            ),
+            ColumnKind::Float => (
+                DataType::Float64,
+                Arc::new(
+                    column(i)
+                        .map(|c| match c {
+                            Cell::Float(x) => Some(*x),
+                            _ => None,
+                        })
+                        .collect::<Float64Array>(),
+                ),
+            ),
+            ColumnKind::Boolean => (
+                DataType::Boolean,
+                Arc::new(
+                    column(i)
+                        .map(|c| match c {
+                            Cell::Boolean(b) => Some(*b),
+                            _ => None,
+                        })
+                        .collect::<BooleanArray>(),
+                ),
+            ),
+        };
+        fields.push(Field::new(col.name, data_type, false));
+        arrays.push(array);
+    }
+
+    arrow_array::RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+        .map_err(|e| export_error("parquet", e))
+}
+
+fn create(path: &Path) -> Result<File, EvalError> {
+    File::create(path).map_err(|e| EvalError::Io {
+        operation: format!("creating '{}'", path.display()),
+        message: e.to_string(),
+    })
+}
+
+fn export_error(format: &str, e: impl std::fmt::Display) -> EvalError {
+    EvalError::Export {
+        format: format.to_string(),
+        message: e.to_string(),
+    }
+}
+
+const fn mode_name(mode: MatchMode) -> &'static str {
+    match mode {
+        MatchMode::Exact => "Exact",
+        MatchMode::Prefix => "Prefix",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::{aggregate_results, DiscoveryConfig, DiscoveryEvaluator};
+    use crate::generator::PathGenerator;
+
+    fn discovery_report() -> EvaluationReport {
+        let config = DiscoveryConfig::default();
+        let mut evaluator = DiscoveryEvaluator::new(&config).unwrap();
+        let paths = PathGenerator::with_seed(7).generate_hierarchical(20);
+        for path in &paths {
+            evaluator.register_agent(path, "llm").unwrap();
+        }
+        let results: Vec<_> = evaluator
+            .evaluate_queries(&paths[..5], MatchMode::Prefix)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        EvaluationReport::new()
+            .with_discovery_prefix(aggregate_results(&results, evaluator.agent_count(), true))
+    }
+
+    #[test]
+    fn rows_match_declared_columns() {
+        let report = discovery_report();
+        let discovery = DiscoveryRow::from_report(&report);
+        let queries = QueryRow::from_report(&report);
+
+        assert_eq!(discovery.len(), 1);
+        assert_eq!(queries.len(), 5);
+        assert_eq!(discovery[0].cells().len(), DiscoveryRow::COLUMNS.len());
+        assert_eq!(queries[0].cells().len(), QueryRow::COLUMNS.len());
+        assert!(ExpressivenessRow::from_report(&report).is_empty());
+    }
+
+    #[test]
+    fn csv_has_header_and_one_line_per_row() {
+        let queries = QueryRow::from_report(&discovery_report());
+        let mut out = Vec::new();
+        write_csv(&mut out, &queries).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), queries.len() + 1);
+        assert!(lines[0].starts_with("query_path,match_mode,returned_count,"));
+        assert!(lines[1].starts_with(&format!("{},Prefix,", queries[0].query_path)));
+    }
+
+    #[test]
+    fn csv_writes_floats_in_shortest_form() {
+        let row = DiscoveryRow {
+            match_mode: MatchMode::Exact,
+            num_agents: 3,
+            num_queries: 1,
+            mean_precision: 0.5,
+            mean_recall: 1.0,
+            mean_f1: 2.0 / 3.0,
+            stddev_precision: 0.0,
+            stddev_recall: 0.0,
+            mean_result_size: 2.0,
+        };
+        let mut out = Vec::new();
+        write_csv(&mut out, &[row]).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(
+            text.lines().nth(1).unwrap(),
+            format!("Exact,3,1,0.5,1,{},0,0,2", 2.0 / 3.0)
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_round_trips_columns() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let queries = QueryRow::from_report(&discovery_report());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queries.parquet");
+        write_parquet_file(&path, &queries).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 5);
+        let names: Vec<_> = metadata
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect();
+        let expected: Vec<_> = QueryRow::COLUMNS.iter().map(|c| c.name).collect();
+        assert_eq!(names, expected);
+    }
+}
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::collision::{detect_collisions, CollisionReport};
 use crate::mapping::{map_tools_batch, MappingConfig, MappingResult};
 use crate::metrics::{count_as_f64, mean, stddev, CoverageMetrics, Histogram};
+use crate::parallel;
 use crate::tool_def::ToolDef;
 
 /// Complete results for capability expressiveness evaluation.
@@ -88,6 +89,9 @@ impl CriteriaResults {
 
 /// Runs the capability expressiveness evaluation.
 ///
+/// With the `parallel` feature, the corpus is mapped and analyzed on all
+/// cores.
+///
 /// # Arguments
 ///
 /// * `tools` - Corpus of tool definitions
@@ -101,14 +105,17 @@ pub fn evaluate_expressiveness(tools: &[ToolDef], config: &MappingConfig) -> Exp
     // Map all tools
     let results = map_tools_batch(tools, config);
 
-    // Compute coverage
-    let coverage = CoverageMetrics::compute(&results);
-
-    // Detect collisions
-    let collisions = detect_collisions(&results);
-
-    // Compute depth distribution
-    let depth_distribution = compute_depth_distribution(&results);
+    // Compute coverage, detect collisions and compute the depth
+    // distribution, independently of each other
+    let (coverage, (collisions, depth_distribution)) = parallel::join(
+        || CoverageMetrics::compute(&results),
+        || {
+            parallel::join(
+                || detect_collisions(&results),
+                || compute_depth_distribution(&results),
+            )
+        },
+    );
 
     // Check criteria
     let criteria = check_criteria(&coverage, &collisions, &depth_distribution);
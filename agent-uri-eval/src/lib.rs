@@ -46,7 +46,7 @@
 //! use agent_uri::CapabilityPath;
 //! use agent_uri_eval::{
 //!     DiscoveryConfig, DiscoveryEvaluator, MatchMode,
-//!     PathGenerator, TreeConfig, AgentIdGenerator,
+//!     PathGenerator, TreeConfig,
 //!     aggregate_results,
 //! };
 //!
@@ -60,11 +60,10 @@
 //!
 //! // Generate and register agents
 //! let mut path_gen = PathGenerator::with_seed(42);
-//! let mut id_gen = AgentIdGenerator::new("eval");
 //! let paths = path_gen.generate_hierarchical(config.num_agents);
 //!
 //! for path in &paths {
-//!     evaluator.register_agent(path, &id_gen.generate_next()).unwrap();
+//!     evaluator.register_agent(path, "llm").unwrap();
 //! }
 //!
 //! // Run queries and compute metrics
@@ -81,6 +80,15 @@
 //! println!("Mean F1: {:.2}", summary.mean_f1);
 //! ```
 //!
+//! ## Running from a config file
+//!
+//! The `agent-uri-eval` binary runs both evaluations end-to-end from a TOML
+//! or JSON [`RunnerConfig`] and writes one report per run:
+//!
+//! ```bash
+//! cargo run --release --package agent-uri-eval -- agent-uri-eval/eval.toml
+//! ```
+//!
 //! # Success Criteria
 //!
 //! From the paper specification:
@@ -94,6 +102,13 @@
 //! | Discovery precision | >= 0.80 |
 //! | Discovery recall | >= 0.70 |
 //! | Discovery F1 | >= 0.75 |
+//!
+//! # Features
+//!
+//! | Feature | Enables |
+//! |---------|---------|
+//! | `parallel` | Runs [`evaluate_expressiveness`], [`map_tools_batch`] and [`DiscoveryEvaluator::evaluate_queries`] on all cores with rayon; results are identical to a sequential run |
+//! | `parquet` | [`write_parquet`] and [`write_parquet_file`] for the [`export`] row types |
 
 #![deny(missing_docs)]
 #![deny(clippy::all)]
@@ -104,11 +119,14 @@ pub mod collision;
 pub mod corpus;
 pub mod discovery;
 pub mod error;
+pub mod export;
 pub mod expressiveness;
 pub mod generator;
 pub mod mapping;
 pub mod metrics;
+mod parallel;
 pub mod report;
+pub mod runner;
 pub mod tool_def;
 
 // Re-exports
@@ -122,6 +140,12 @@ pub use discovery::{
     QueryResult,
 };
 pub use error::{CorpusError, DiscoveryError, EvalError, MappingError};
+pub use export::{
+    write_csv, write_csv_file, Cell, Column, ColumnKind, DiscoveryRow, ExpressivenessRow, QueryRow,
+    TableRow,
+};
+#[cfg(feature = "parquet")]
+pub use export::{write_parquet, write_parquet_file};
 pub use expressiveness::{
     evaluate_expressiveness, evaluate_flat_namespace, CriteriaResults, CriterionStatus,
     DepthDistribution, ExpressivenessResults,
@@ -130,4 +154,5 @@ pub use generator::{AgentIdGenerator, PathGenerator, TreeConfig};
 pub use mapping::{map_tool_to_path, map_tools_batch, MappingConfig, MappingResult};
 pub use metrics::{mean, stddev, CoverageMetrics, Histogram, PrecisionRecallMetrics};
 pub use report::{EvaluationReport, EvaluationSummary, ReportMetadata};
+pub use runner::{DiscoveryRun, ExpressivenessRun, RunOutput, RunnerConfig};
 pub use tool_def::{ToolDef, ToolSource};
@@ -0,0 +1,125 @@
+//! `agent-uri-eval`: run Eval 1 and Eval 2 end-to-end from a config file.
+//!
+//! # Usage
+//!
+//! ```bash
+//! cargo run --release --package agent-uri-eval -- agent-uri-eval/eval.toml
+//!
+//! # Override the output directory from the config
+//! cargo run --release --package agent-uri-eval -- eval.json --output-dir /tmp/results
+//! ```
+//!
+//! See [`agent_uri_eval::runner`] for the config format.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use agent_uri_eval::{EvaluationReport, RunnerConfig};
+
+fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    let (config_path, output_dir) = parse_args(&args)?;
+
+    let mut config = RunnerConfig::from_path(&config_path)?;
+    if let Some(dir) = output_dir {
+        config.output_dir = dir;
+    }
+
+    println!("=== agent-uri-eval: {} ===\n", config_path.display());
+
+    let outputs = config.run()?;
+    let mut all_passed = true;
+    for output in &outputs {
+        for warning in &output.warnings {
+            println!("  Warning: {warning}");
+        }
+        print_report(&output.report);
+        println!("  Written to: {}\n", output.path.display());
+        all_passed &= output.report.summary.failed_criteria.is_empty();
+    }
+
+    if all_passed {
+        println!("[PASS] All criteria met across {} report(s)", outputs.len());
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!("[FAIL] Some criteria not met");
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<(PathBuf, Option<PathBuf>), Box<dyn std::error::Error>> {
+    let mut config_path = None;
+    let mut output_dir = None;
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--output-dir" => {
+                if i + 1 >= args.len() {
+                    return Err("--output-dir requires a path argument".into());
+                }
+                output_dir = Some(PathBuf::from(&args[i + 1]));
+                i += 2;
+            }
+            "-h" | "--help" => {
+                print_usage();
+                std::process::exit(0);
+            }
+            arg if arg.starts_with('-') => {
+                return Err(format!("Unknown argument: {arg}").into());
+            }
+            arg => {
+                if config_path.is_some() {
+                    return Err(format!("Unexpected argument: {arg}").into());
+                }
+                config_path = Some(PathBuf::from(arg));
+                i += 1;
+            }
+        }
+    }
+
+    let config_path = config_path.ok_or("missing CONFIG argument (see --help)")?;
+    Ok((config_path, output_dir))
+}
+
+fn print_usage() {
+    println!("Usage: agent-uri-eval CONFIG [OPTIONS]");
+    println!();
+    println!("Runs the evaluations described by CONFIG (.toml or .json).");
+    println!();
+    println!("Options:");
+    println!("  --output-dir PATH  Write reports to PATH instead of the configured directory");
+    println!("  -h, --help         Print this help message");
+}
+
+fn print_report(report: &EvaluationReport) {
+    if let Some(ref e) = report.expressiveness {
+        println!("Eval 1: Capability Expressiveness");
+        println!("  Total tools:     {}", e.coverage.total_tools);
+        println!("  Coverage:        {:.1}%", e.coverage.coverage_rate * 100.0);
+        println!("  Collision rate:  {:.2}%", e.collisions.collision_rate * 100.0);
+        println!("  Mean depth:      {:.2}", e.depth_distribution.mean);
+    }
+    if let Some(ref flat) = report.expressiveness_flat {
+        println!(
+            "  Flat collision rate (ablation): {:.2}%",
+            flat.collisions.collision_rate * 100.0
+        );
+    }
+    for (label, results) in [
+        ("prefix", &report.discovery_prefix),
+        ("exact, ablation", &report.discovery_exact),
+    ] {
+        if let Some(d) = results {
+            println!("Eval 2: Discovery Precision ({label})");
+            println!("  Agents:          {}", d.num_agents);
+            println!("  Queries:         {}", d.num_queries);
+            println!("  Mean precision:  {:.3}", d.mean_precision);
+            println!("  Mean recall:     {:.3}", d.mean_recall);
+            println!("  Mean F1:         {:.3}", d.mean_f1);
+        }
+    }
+    for criterion in &report.summary.failed_criteria {
+        println!("  FAIL: {criterion}");
+    }
+}
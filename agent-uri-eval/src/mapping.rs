@@ -3,6 +3,7 @@
 use agent_uri::{CapabilityPath, MAX_PATH_SEGMENTS};
 
 use crate::error::MappingError;
+use crate::parallel;
 use crate::tool_def::ToolDef;
 
 /// Result of mapping a tool to a capability path.
@@ -221,16 +222,15 @@ fn normalize_segment(s: &str, collapse_hyphens: bool) -> String {
     result.trim_matches('-').to_string()
 }
 
-/// Maps a batch of tools to capability paths.
+/// Maps a batch of tools to capability paths, in the order given.
+///
+/// With the `parallel` feature, tools are mapped on all cores.
 #[must_use]
 pub fn map_tools_batch(tools: &[ToolDef], config: &MappingConfig) -> Vec<MappingResult> {
-    tools
-        .iter()
-        .map(|tool| MappingResult {
-            tool: tool.clone(),
-            path: map_tool_to_path(tool, config),
-        })
-        .collect()
+    parallel::map(tools, |tool| MappingResult {
+        tool: tool.clone(),
+        path: map_tool_to_path(tool, config),
+    })
 }
 
 #[cfg(test)]
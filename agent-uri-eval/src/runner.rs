@@ -0,0 +1,488 @@
+//! Config-driven end-to-end runs of Eval 1 and Eval 2.
+//!
+//! A [`RunnerConfig`] describes which evaluations to run, where the corpus
+//! lives, which seeds and agent counts to sweep, and where reports go. It can
+//! be read from TOML or JSON; the `agent-uri-eval` binary is a thin wrapper
+//! around [`RunnerConfig::from_path`] and [`RunnerConfig::run`].
+//!
+//! ```toml
+//! output_dir = "results"
+//!
+//! [expressiveness]
+//! corpus_dirs = ["corpus"]
+//!
+//! [discovery]
+//! seeds = [42, 43]
+//! agent_counts = [1000, 10000]
+//! num_queries = 1000
+//! match_modes = ["Prefix", "Exact"]
+//! ```
+//!
+//! Relative paths in a config file are resolved against the directory that
+//! contains the file, so a config can be run from anywhere.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::corpus::{load_corpus_directory, LoadedCorpus};
+use crate::discovery::{aggregate_results, DiscoveryConfig, DiscoveryEvaluator, MatchMode};
+use crate::error::EvalError;
+use crate::expressiveness::{evaluate_expressiveness, evaluate_flat_namespace};
+use crate::generator::PathGenerator;
+use crate::mapping::MappingConfig;
+use crate::report::EvaluationReport;
+
+/// Origin reported in errors for configs parsed from a string.
+const INLINE_ORIGIN: &str = "<inline>";
+
+/// Agent type prefixes cycled through when registering generated agents.
+const TYPE_PREFIXES: [&str; 6] = ["llm", "rule", "hybrid", "sensor", "actuator", "composite"];
+
+/// Top-level runner configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RunnerConfig {
+    /// Directory reports are written to (default: `results`).
+    pub output_dir: PathBuf,
+    /// Eval 1 settings; Eval 1 is skipped when absent.
+    pub expressiveness: Option<ExpressivenessRun>,
+    /// Eval 2 settings; Eval 2 is skipped when absent.
+    pub discovery: Option<DiscoveryRun>,
+}
+
+impl Default for RunnerConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: PathBuf::from("results"),
+            expressiveness: None,
+            discovery: None,
+        }
+    }
+}
+
+/// Eval 1 (capability expressiveness) settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExpressivenessRun {
+    /// Corpus directories whose tools are merged into one corpus.
+    pub corpus_dirs: Vec<PathBuf>,
+    /// Whether to run the flat-namespace ablation (default: true).
+    pub flat_ablation: bool,
+    /// Report file name, relative to the output directory.
+    pub output: PathBuf,
+}
+
+impl Default for ExpressivenessRun {
+    fn default() -> Self {
+        Self {
+            corpus_dirs: vec![PathBuf::from("corpus")],
+            flat_ablation: true,
+            output: PathBuf::from("eval1_expressiveness.json"),
+        }
+    }
+}
+
+/// Eval 2 (discovery precision) settings.
+///
+/// Every combination of [`agent_counts`](Self::agent_counts) and
+/// [`seeds`](Self::seeds) is run and written to its own report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscoveryRun {
+    /// Random seeds to sweep.
+    pub seeds: Vec<u64>,
+    /// Numbers of registered agents to sweep.
+    pub agent_counts: Vec<usize>,
+    /// Number of queries per run.
+    pub num_queries: usize,
+    /// Match modes to evaluate (`Prefix`, and `Exact` as the ablation).
+    pub match_modes: Vec<MatchMode>,
+    /// Trust root for all agents.
+    pub trust_root: String,
+    /// Report file name, relative to the output directory.
+    ///
+    /// When more than one run is configured, `_n{agents}_s{seed}` is appended
+    /// to the file stem.
+    pub output: PathBuf,
+}
+
+impl Default for DiscoveryRun {
+    fn default() -> Self {
+        Self {
+            seeds: vec![42],
+            agent_counts: vec![10_000],
+            num_queries: 1_000,
+            match_modes: vec![MatchMode::Prefix, MatchMode::Exact],
+            trust_root: DiscoveryConfig::default().trust_root,
+            output: PathBuf::from("eval2_discovery.json"),
+        }
+    }
+}
+
+/// A report produced by [`RunnerConfig::run`] and the file it was written to.
+#[derive(Debug, Clone)]
+pub struct RunOutput {
+    /// Path the report was written to.
+    pub path: PathBuf,
+    /// The report.
+    pub report: EvaluationReport,
+    /// Corpus loading warnings (Eval 1 only).
+    pub warnings: Vec<String>,
+}
+
+impl RunnerConfig {
+    /// Reads a config file, choosing the format from its extension.
+    ///
+    /// Files ending in `.json` are parsed as JSON, anything else as TOML.
+    /// Relative paths are resolved against the file's directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EvalError::Io` if the file cannot be read and
+    /// `EvalError::Config` if it cannot be parsed or is invalid.
+    pub fn from_path(path: &Path) -> Result<Self, EvalError> {
+        let text = fs::read_to_string(path).map_err(|e| EvalError::Io {
+            operation: format!("reading config '{}'", path.display()),
+            message: e.to_string(),
+        })?;
+        let origin = path.display().to_string();
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let config = if is_json {
+            Self::parse_json(&text, &origin)?
+        } else {
+            Self::parse_toml(&text, &origin)?
+        };
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        Ok(config.resolve(base))
+    }
+
+    /// Parses a config from a JSON string.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EvalError::Config` if the JSON is malformed or invalid.
+    pub fn from_json(text: &str) -> Result<Self, EvalError> {
+        Self::parse_json(text, INLINE_ORIGIN)
+    }
+
+    /// Parses a config from a TOML string.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EvalError::Config` if the TOML is malformed or invalid.
+    pub fn from_toml(text: &str) -> Result<Self, EvalError> {
+        Self::parse_toml(text, INLINE_ORIGIN)
+    }
+
+    /// Runs every configured evaluation and writes one report per run.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first corpus, discovery, serialization or I/O error.
+    pub fn run(&self) -> Result<Vec<RunOutput>, EvalError> {
+        let mut outputs = Vec::new();
+
+        if let Some(ref run) = self.expressiveness {
+            let (report, warnings) = run.run()?;
+            let path = self.write(&run.output, &report)?;
+            outputs.push(RunOutput {
+                path,
+                report,
+                warnings,
+            });
+        }
+
+        if let Some(ref run) = self.discovery {
+            let sweep = run.agent_counts.len() * run.seeds.len() > 1;
+            for &num_agents in &run.agent_counts {
+                for &seed in &run.seeds {
+                    let report = run.run(num_agents, seed)?;
+                    let file = if sweep {
+                        suffixed(&run.output, num_agents, seed)
+                    } else {
+                        run.output.clone()
+                    };
+                    let path = self.write(&file, &report)?;
+                    outputs.push(RunOutput {
+                        path,
+                        report,
+                        warnings: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    fn parse_json(text: &str, origin: &str) -> Result<Self, EvalError> {
+        let config: Self =
+            serde_json::from_str(text).map_err(|e| config_error(origin, e.to_string()))?;
+        config.validate(origin)
+    }
+
+    fn parse_toml(text: &str, origin: &str) -> Result<Self, EvalError> {
+        let config: Self =
+            toml::from_str(text).map_err(|e| config_error(origin, e.message().to_string()))?;
+        config.validate(origin)
+    }
+
+    fn validate(self, origin: &str) -> Result<Self, EvalError> {
+        if self.expressiveness.is_none() && self.discovery.is_none() {
+            return Err(config_error(
+                origin,
+                "no evaluations configured; add an [expressiveness] or [discovery] section",
+            ));
+        }
+        if let Some(ref run) = self.expressiveness
+            && run.corpus_dirs.is_empty()
+        {
+            return Err(config_error(origin, "expressiveness.corpus_dirs is empty"));
+        }
+        if let Some(ref run) = self.discovery {
+            let empty = [
+                ("seeds", run.seeds.is_empty()),
+                ("agent_counts", run.agent_counts.is_empty()),
+                ("match_modes", run.match_modes.is_empty()),
+            ];
+            if let Some((field, _)) = empty.iter().find(|(_, is_empty)| *is_empty) {
+                return Err(config_error(origin, format!("discovery.{field} is empty")));
+            }
+            if run.num_queries == 0 {
+                return Err(config_error(origin, "discovery.num_queries must be positive"));
+            }
+        }
+        Ok(self)
+    }
+
+    fn resolve(mut self, base: &Path) -> Self {
+        self.output_dir = base.join(&self.output_dir);
+        if let Some(ref mut run) = self.expressiveness {
+            for dir in &mut run.corpus_dirs {
+                *dir = base.join(&*dir);
+            }
+        }
+        self
+    }
+
+    fn write(&self, file: &Path, report: &EvaluationReport) -> Result<PathBuf, EvalError> {
+        fs::create_dir_all(&self.output_dir).map_err(|e| EvalError::Io {
+            operation: format!("creating '{}'", self.output_dir.display()),
+            message: e.to_string(),
+        })?;
+        let path = self.output_dir.join(file);
+        fs::write(&path, report.to_json()?).map_err(|e| EvalError::Io {
+            operation: format!("writing '{}'", path.display()),
+            message: e.to_string(),
+        })?;
+        Ok(path)
+    }
+}
+
+impl ExpressivenessRun {
+    /// Loads and merges the corpus directories, then runs Eval 1.
+    ///
+    /// Returns the report together with any corpus loading warnings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EvalError::Corpus` if a corpus directory cannot be loaded.
+    pub fn run(&self) -> Result<(EvaluationReport, Vec<String>), EvalError> {
+        let mut corpus = LoadedCorpus::new();
+        for dir in &self.corpus_dirs {
+            let loaded = load_corpus_directory(dir)?;
+            corpus.add_tools(loaded.tools);
+            corpus.files_loaded += loaded.files_loaded;
+            corpus.warnings.extend(loaded.warnings);
+        }
+
+        let mut report = EvaluationReport::new()
+            .with_expressiveness(evaluate_expressiveness(&corpus.tools, &MappingConfig::default()));
+        if self.flat_ablation {
+            report = report.with_expressiveness_flat(evaluate_flat_namespace(&corpus.tools));
+        }
+        Ok((report.compute_summary(), corpus.warnings))
+    }
+}
+
+impl DiscoveryRun {
+    /// Runs Eval 2 once with `num_agents` agents and the given seed.
+    ///
+    /// Queries that fail are left out of the aggregated results.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EvalError::Discovery` if the evaluator cannot be created or an
+    /// agent cannot be registered.
+    pub fn run(&self, num_agents: usize, seed: u64) -> Result<EvaluationReport, EvalError> {
+        let config = DiscoveryConfig {
+            num_agents,
+            num_queries: self.num_queries,
+            trust_root: self.trust_root.clone(),
+            seed,
+            ..Default::default()
+        };
+        let mut evaluator = DiscoveryEvaluator::new(&config)?;
+
+        let paths = PathGenerator::with_seed(seed).generate_hierarchical(num_agents);
+        for (i, path) in paths.iter().enumerate() {
+            let prefix = TYPE_PREFIXES[i % TYPE_PREFIXES.len()];
+            evaluator.register_agent(path, &format!("{prefix}_{}", letter_suffix(i)))?;
+        }
+
+        let queries = &paths[..self.num_queries.min(paths.len())];
+        let mut report = EvaluationReport::new();
+        for &mode in &self.match_modes {
+            let results: Vec<_> = evaluator
+                .evaluate_queries(queries, mode)
+                .into_iter()
+                .filter_map(Result::ok)
+                .collect();
+            let summary = aggregate_results(&results, evaluator.agent_count(), false);
+            report = match mode {
+                MatchMode::Prefix => report.with_discovery_prefix(summary),
+                MatchMode::Exact => report.with_discovery_exact(summary),
+            };
+        }
+        Ok(report.compute_summary())
+    }
+}
+
+fn config_error(origin: &str, message: impl Into<String>) -> EvalError {
+    EvalError::Config {
+        path: origin.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Appends `_n{agents}_s{seed}` to the stem of `file`.
+fn suffixed(file: &Path, num_agents: usize, seed: u64) -> PathBuf {
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match file.extension() {
+        Some(ext) => format!("{stem}_n{num_agents}_s{seed}.{}", ext.to_string_lossy()),
+        None => format!("{stem}_n{num_agents}_s{seed}"),
+    };
+    file.with_file_name(name)
+}
+
+/// Letter-only (base-26) suffix, since agent ID prefixes may not contain digits.
+///
+/// `0 -> "a"`, `25 -> "z"`, `26 -> "aa"`.
+fn letter_suffix(index: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = index;
+    loop {
+        #[allow(clippy::cast_possible_truncation)]
+        letters.push(b'a' + (n % 26) as u8);
+        n /= 26;
+        if n == 0 {
+            break;
+        }
+        n -= 1;
+    }
+    letters.reverse();
+    String::from_utf8(letters).expect("ASCII letters")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn toml_and_json_configs_agree() {
+        let toml = r#"
+            output_dir = "out"
+
+            [discovery]
+            seeds = [1, 2]
+            agent_counts = [50]
+            num_queries = 10
+            match_modes = ["Prefix"]
+        "#;
+        let json = r#"{
+            "output_dir": "out",
+            "discovery": {
+                "seeds": [1, 2],
+                "agent_counts": [50],
+                "num_queries": 10,
+                "match_modes": ["Prefix"]
+            }
+        }"#;
+
+        let from_toml = RunnerConfig::from_toml(toml).unwrap();
+        assert_eq!(from_toml, RunnerConfig::from_json(json).unwrap());
+        assert!(from_toml.expressiveness.is_none());
+        let discovery = from_toml.discovery.unwrap();
+        assert_eq!(discovery.seeds, vec![1, 2]);
+        assert_eq!(discovery.output, PathBuf::from("eval2_discovery.json"));
+    }
+
+    #[test]
+    fn rejects_empty_and_unknown_settings() {
+        let err = RunnerConfig::from_toml("output_dir = \"out\"").unwrap_err();
+        assert!(err.to_string().contains("no evaluations configured"));
+
+        let err = RunnerConfig::from_toml("[discovery]\nseeds = []").unwrap_err();
+        assert!(err.to_string().contains("discovery.seeds is empty"));
+
+        let err = RunnerConfig::from_toml("[discovery]\nagents = 5").unwrap_err();
+        assert!(matches!(err, EvalError::Config { .. }));
+    }
+
+    #[test]
+    fn from_path_resolves_relative_to_config_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("eval.json");
+        fs::write(&path, r#"{"expressiveness": {"corpus_dirs": ["tools"]}}"#).unwrap();
+
+        let config = RunnerConfig::from_path(&path).unwrap();
+        assert_eq!(config.output_dir, dir.path().join("results"));
+        assert_eq!(
+            config.expressiveness.unwrap().corpus_dirs,
+            vec![dir.path().join("tools")]
+        );
+    }
+
+    #[test]
+    fn discovery_sweep_writes_one_report_per_run() {
+        let dir = tempdir().unwrap();
+        let config = RunnerConfig {
+            output_dir: dir.path().to_path_buf(),
+            expressiveness: None,
+            discovery: Some(DiscoveryRun {
+                seeds: vec![7, 8],
+                agent_counts: vec![30],
+                num_queries: 10,
+                ..Default::default()
+            }),
+        };
+
+        let outputs = config.run().unwrap();
+        let names: Vec<_> = outputs
+            .iter()
+            .map(|o| o.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            ["eval2_discovery_n30_s7.json", "eval2_discovery_n30_s8.json"]
+        );
+        for output in &outputs {
+            assert!(output.path.exists());
+            assert!(output.report.discovery_prefix.is_some());
+            assert!(output.report.discovery_exact.is_some());
+        }
+    }
+
+    #[test]
+    fn letter_suffix_is_base_26() {
+        assert_eq!(letter_suffix(0), "a");
+        assert_eq!(letter_suffix(25), "z");
+        assert_eq!(letter_suffix(26), "aa");
+        assert_eq!(letter_suffix(27), "ab");
+    }
+}
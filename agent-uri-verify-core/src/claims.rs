@@ -0,0 +1,323 @@
+//! Attestation claims and the checks applied to them.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::error::VerifyError;
+use crate::time::{parse_rfc3339, Timestamp};
+
+/// Newest claims schema version this crate verifies.
+///
+/// Matches `CLAIMS_VERSION` in `agent-uri-attestation`; tokens without a
+/// `ver` claim use the legacy version 1 schema, which parses the same way.
+pub const CLAIMS_VERSION: u32 = 2;
+
+/// Claims of a verified attestation token.
+///
+/// Timestamps are whole seconds since the Unix epoch, rounded so that
+/// checks never accept a token early or late: `iat` and `exp` round down,
+/// `nbf` rounds up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Claims {
+    /// The full agent URI being attested
+    pub agent_uri: String,
+    /// Unconstrained capabilities granted to the agent
+    pub capabilities: Vec<String>,
+    /// Capabilities excluded from the granted coverage
+    pub denied_capabilities: Vec<String>,
+    /// Issuer (trust root) that created this attestation
+    pub iss: String,
+    /// When the token was issued
+    pub iat: i64,
+    /// When the token expires
+    pub exp: i64,
+    /// Optional time before which the token must not be accepted
+    pub nbf: Option<i64>,
+    /// Optional audience restriction
+    pub aud: Option<String>,
+    /// Optional index of this token in the issuer's status list
+    pub status_idx: Option<u64>,
+}
+
+impl Claims {
+    /// Parses the claims JSON of a token.
+    ///
+    /// Constrained capabilities (objects in the `capabilities` array) are
+    /// skipped: their constraints need request context this crate does not
+    /// model, so they are never treated as granted. Tokens bound to a holder
+    /// key (`cnf`) or a challenge (`nonce`) are refused, since this crate
+    /// cannot check the proof of possession those claims demand.
+    ///
+    /// # Errors
+    ///
+    /// Returns `UnsupportedClaimsVersion` if `ver` is newer than
+    /// [`CLAIMS_VERSION`], or `InvalidClaims` if a required claim is missing
+    /// or malformed, or the token carries `cnf` or `nonce`.
+    pub fn from_json(json: &[u8]) -> Result<Self, VerifyError> {
+        let json: Value = serde_json::from_slice(json).map_err(|e| VerifyError::InvalidClaims {
+            reason: format!("claims are not valid JSON: {e}"),
+        })?;
+
+        if let Some(ver) = json.get("ver") {
+            let version = ver
+                .as_u64()
+                .ok_or_else(|| invalid("ver claim must be an unsigned integer"))?;
+            if version > u64::from(CLAIMS_VERSION) {
+                return Err(VerifyError::UnsupportedClaimsVersion {
+                    version,
+                    supported: CLAIMS_VERSION,
+                });
+            }
+        }
+        if json.get("cnf").is_some() {
+            return Err(invalid("holder-bound tokens (cnf) are not supported"));
+        }
+        if json.get("nonce").is_some() {
+            return Err(invalid("nonce-bound tokens are not supported"));
+        }
+
+        let capabilities = match json.get("capabilities") {
+            None => Vec::new(),
+            Some(Value::Array(entries)) => entries
+                .iter()
+                .filter_map(|entry| entry.as_str().map(String::from))
+                .collect(),
+            Some(_) => return Err(invalid("capabilities claim must be an array")),
+        };
+        let denied_capabilities = match json.get("denied_capabilities") {
+            None => Vec::new(),
+            Some(Value::Array(entries)) => entries
+                .iter()
+                .map(|entry| entry.as_str().map(String::from))
+                .collect::<Option<_>>()
+                .ok_or_else(|| invalid("denied_capabilities must be an array of strings"))?,
+            Some(_) => return Err(invalid("denied_capabilities must be an array of strings")),
+        };
+        let status_idx = json
+            .get("status_idx")
+            .map(|v| {
+                v.as_u64()
+                    .ok_or_else(|| invalid("status_idx claim must be an unsigned integer"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            agent_uri: string_claim(&json, "agent_uri")?,
+            capabilities,
+            denied_capabilities,
+            iss: string_claim(&json, "iss")?,
+            iat: time_claim(&json, "iat")?.floor(),
+            exp: time_claim(&json, "exp")?.floor(),
+            nbf: json
+                .get("nbf")
+                .map(|_| time_claim(&json, "nbf").map(Timestamp::ceil))
+                .transpose()?,
+            aud: json.get("aud").and_then(Value::as_str).map(String::from),
+            status_idx,
+        })
+    }
+
+    /// Checks the token is within its validity window at `now`.
+    ///
+    /// `now` is supplied by the caller in seconds since the Unix epoch,
+    /// typically from a real-time clock or a trusted time source.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenNotYetValid` before `nbf` and `TokenExpired` from
+    /// `exp` onwards.
+    pub fn check_validity(&self, now: i64) -> Result<(), VerifyError> {
+        if let Some(nbf) = self.nbf
+            && now < nbf
+        {
+            return Err(VerifyError::TokenNotYetValid { nbf });
+        }
+        if now >= self.exp {
+            return Err(VerifyError::TokenExpired { exp: self.exp });
+        }
+        Ok(())
+    }
+
+    /// Checks that the claims grant `required`, a capability path such as
+    /// `"actuator/valve"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CapabilityDenied` if a denial overlaps `required`, or
+    /// `InsufficientCapabilities` if no granted capability covers it.
+    pub fn check_capability(&self, required: &str) -> Result<(), VerifyError> {
+        if let Some(denied) = self
+            .denied_capabilities
+            .iter()
+            .find(|denied| covers(denied, required) || covers(required, denied))
+        {
+            return Err(VerifyError::CapabilityDenied {
+                required: required.to_string(),
+                denied: denied.clone(),
+            });
+        }
+        if self.capabilities.iter().any(|cap| covers(cap, required)) {
+            Ok(())
+        } else {
+            Err(VerifyError::InsufficientCapabilities {
+                required: required.to_string(),
+            })
+        }
+    }
+}
+
+/// Returns true if capability `cap` covers `required`.
+///
+/// A capability covers itself and every path beneath it: `workflow`
+/// covers `workflow/approval` but not `workflowx`.
+///
+/// # Example
+///
+/// ```
+/// use agent_uri_verify_core::covers;
+///
+/// assert!(covers("workflow", "workflow/approval"));
+/// assert!(!covers("workflow", "workflows"));
+/// ```
+#[must_use]
+pub fn covers(cap: &str, required: &str) -> bool {
+    required == cap
+        || required
+            .strip_prefix(cap)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn invalid(reason: &str) -> VerifyError {
+    VerifyError::InvalidClaims {
+        reason: reason.to_string(),
+    }
+}
+
+fn string_claim(json: &Value, name: &str) -> Result<String, VerifyError> {
+    json.get(name)
+        .and_then(Value::as_str)
+        .map(String::from)
+        .ok_or_else(|| invalid(&format!("missing {name} claim")))
+}
+
+fn time_claim(json: &Value, name: &str) -> Result<Timestamp, VerifyError> {
+    json.get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid(&format!("missing {name} claim")))
+        .and_then(|s| parse_rfc3339(s).ok_or_else(|| invalid(&format!("invalid {name} format"))))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    const CLAIMS: &[u8] = br#"{
+        "agent_uri": "agent://acme.com/actuator/valve/plc_01h455vb4pex5vsknk084sn02q",
+        "capabilities": ["actuator", {"cap": "actuator/pump", "max_uses": 1}],
+        "denied_capabilities": ["actuator/valve/override"],
+        "iss": "acme.com",
+        "iat": "2024-01-15T14:30:00.000Z",
+        "exp": "2024-01-15T15:30:00.750Z",
+        "nbf": "2024-01-15T14:35:00.250Z"
+    }"#;
+
+    #[test]
+    fn parses_claims_with_conservative_rounding() {
+        let claims = Claims::from_json(CLAIMS).unwrap();
+
+        assert_eq!(claims.iss, "acme.com");
+        assert_eq!(claims.capabilities, vec!["actuator"]);
+        assert_eq!(claims.exp, 1_705_332_600);
+        assert_eq!(claims.nbf, Some(1_705_329_301));
+    }
+
+    #[test]
+    fn validity_window() {
+        let claims = Claims::from_json(CLAIMS).unwrap();
+
+        assert!(matches!(
+            claims.check_validity(1_705_329_300),
+            Err(VerifyError::TokenNotYetValid { .. })
+        ));
+        assert!(claims.check_validity(1_705_329_301).is_ok());
+        assert!(matches!(
+            claims.check_validity(1_705_332_600),
+            Err(VerifyError::TokenExpired { .. })
+        ));
+    }
+
+    #[test]
+    fn capability_checks_apply_denials() {
+        let claims = Claims::from_json(CLAIMS).unwrap();
+
+        assert!(claims.check_capability("actuator/valve").is_err());
+        assert!(claims.check_capability("actuator/heater").is_ok());
+        assert!(matches!(
+            claims.check_capability("actuator/valve/override/force"),
+            Err(VerifyError::CapabilityDenied { .. })
+        ));
+    }
+
+    #[test]
+    fn constrained_grants_are_not_honoured() {
+        let json = br#"{"agent_uri":"a","iss":"b","iat":"2024-01-15T14:30:00Z",
+            "exp":"2024-01-15T14:30:00Z","capabilities":[{"cap":"actuator/pump"}]}"#;
+        let claims = Claims::from_json(json).unwrap();
+
+        assert!(matches!(
+            claims.check_capability("actuator/pump"),
+            Err(VerifyError::InsufficientCapabilities { .. })
+        ));
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        let json = br#"{"agent_uri":"a","iss":"b","iat":"2024-01-15T14:30:00Z",
+            "exp":"2024-01-15T14:30:00Z","ver":3}"#;
+
+        assert_eq!(
+            Claims::from_json(json),
+            Err(VerifyError::UnsupportedClaimsVersion {
+                version: 3,
+                supported: CLAIMS_VERSION,
+            })
+        );
+
+        let json = br#"{"agent_uri":"a","iss":"b","iat":"2024-01-15T14:30:00Z",
+            "exp":"2024-01-15T14:30:00Z","ver":2}"#;
+        assert!(Claims::from_json(json).is_ok());
+    }
+
+    #[test]
+    fn holder_and_nonce_bound_tokens_are_refused() {
+        let cnf = br#"{"agent_uri":"a","iss":"b","iat":"2024-01-15T14:30:00Z",
+            "exp":"2024-01-15T14:30:00Z","cnf":{"jwk":{"kty":"OKP"}}}"#;
+        let nonce = br#"{"agent_uri":"a","iss":"b","iat":"2024-01-15T14:30:00Z",
+            "exp":"2024-01-15T14:30:00Z","nonce":"n-123"}"#;
+
+        assert!(matches!(
+            Claims::from_json(cnf),
+            Err(VerifyError::InvalidClaims { .. })
+        ));
+        assert!(matches!(
+            Claims::from_json(nonce),
+            Err(VerifyError::InvalidClaims { .. })
+        ));
+    }
+
+    #[test]
+    fn malformed_denials_are_rejected() {
+        let json = br#"{"agent_uri":"a","iss":"b","iat":"2024-01-15T14:30:00Z",
+            "exp":"2024-01-15T14:30:00Z","denied_capabilities":[1]}"#;
+
+        assert!(matches!(
+            Claims::from_json(json),
+            Err(VerifyError::InvalidClaims { .. })
+        ));
+    }
+}
@@ -0,0 +1,87 @@
+//! Error types for core verification.
+
+use alloc::string::String;
+use core::fmt;
+
+/// Errors that can occur while verifying a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The token is not a well-formed PASETO v4.public token.
+    InvalidTokenFormat {
+        /// Why the token was rejected
+        reason: &'static str,
+    },
+    /// The signature does not verify against the public key.
+    InvalidSignature,
+    /// The claims are missing or malformed.
+    InvalidClaims {
+        /// Description of what's wrong
+        reason: String,
+    },
+    /// The token uses a claims schema newer than this crate understands.
+    UnsupportedClaimsVersion {
+        /// The token's `ver` claim
+        version: u64,
+        /// The newest version this crate supports
+        supported: u32,
+    },
+    /// The token has expired.
+    TokenExpired {
+        /// Expiration time in seconds since the Unix epoch
+        exp: i64,
+    },
+    /// The token's not-before time has not been reached.
+    TokenNotYetValid {
+        /// Not-before time in seconds since the Unix epoch
+        nbf: i64,
+    },
+    /// No granted capability covers the required one.
+    InsufficientCapabilities {
+        /// The capability that was required
+        required: String,
+    },
+    /// The required capability falls under a denied capability.
+    CapabilityDenied {
+        /// The capability that was required
+        required: String,
+        /// The denial that matched it
+        denied: String,
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidTokenFormat { reason } => {
+                write!(f, "invalid token format: {reason}")
+            }
+            Self::InvalidSignature => {
+                write!(f, "signature verification failed; check the issuer's public key")
+            }
+            Self::InvalidClaims { reason } => {
+                write!(f, "invalid claims: {reason}")
+            }
+            Self::UnsupportedClaimsVersion { version, supported } => {
+                write!(
+                    f,
+                    "claims schema version {version} is newer than the supported version \
+                     {supported}"
+                )
+            }
+            Self::TokenExpired { exp } => {
+                write!(f, "token expired at {exp} (Unix seconds); request a new attestation")
+            }
+            Self::TokenNotYetValid { nbf } => {
+                write!(f, "token is not valid until {nbf} (Unix seconds); check the device clock")
+            }
+            Self::InsufficientCapabilities { required } => {
+                write!(f, "no granted capability covers '{required}'")
+            }
+            Self::CapabilityDenied { required, denied } => {
+                write!(f, "capability '{required}' is excluded by denied capability '{denied}'")
+            }
+        }
+    }
+}
+
+impl core::error::Error for VerifyError {}
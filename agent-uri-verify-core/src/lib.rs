@@ -0,0 +1,95 @@
+//! `no_std` verification of `agent-uri` attestation tokens.
+//!
+//! This crate verifies PASETO v4.public attestations issued by
+//! `agent-uri-attestation` on devices without an operating system, such as
+//! sensors and actuators. It needs only `alloc`: there is no clock, so the
+//! caller supplies the current time, and there is no issuance, which stays
+//! in the `std`-only `agent-uri-attestation` crate.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use agent_uri_verify_core::{verify, VerifyError};
+//!
+//! // Provisioned at manufacture: the issuer's 32-byte Ed25519 public key
+//! let public_key: [u8; 32] = [0; 32];
+//! // Read from the device's real-time clock, in seconds since the Unix epoch
+//! let now: i64 = 1_705_329_000;
+//!
+//! let token = "v4.public.eyJhZ2VudF91cmkiOi...";
+//! let claims = verify(token, &public_key, now)?;
+//! claims.check_capability("actuator/valve")?;
+//! # Ok::<(), VerifyError>(())
+//! ```
+//!
+//! # Checks
+//!
+//! [`verify`] checks the signature and the `nbf`/`exp` validity window.
+//! The caller then applies the checks it needs to the returned [`Claims`]:
+//! compare `iss` and `agent_uri` against expectations and call
+//! [`Claims::check_capability`] for the operation being authorized.
+//!
+//! Capabilities carrying constraints are never treated as granted,
+//! tokens bound to a holder key or nonce are refused, and status-list
+//! revocation is not checked; relying parties that need any of these
+//! should use `agent-uri-attestation`.
+
+#![no_std]
+#![deny(missing_docs)]
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
+#![allow(clippy::module_name_repetitions)]
+
+extern crate alloc;
+
+mod claims;
+mod error;
+mod paseto;
+mod time;
+
+pub use claims::{covers, Claims, CLAIMS_VERSION};
+pub use error::VerifyError;
+pub use paseto::open;
+
+/// Verifies a token's signature and validity window at time `now`.
+///
+/// `now` is in seconds since the Unix epoch.
+///
+/// # Errors
+///
+/// Returns `VerifyError` if the token is malformed, the signature does not
+/// verify against `public_key`, the claims cannot be parsed, or `now` is
+/// outside the validity window.
+pub fn verify(token: &str, public_key: &[u8; 32], now: i64) -> Result<Claims, VerifyError> {
+    let message = open(token, public_key)?;
+    let claims = Claims::from_json(&message)?;
+    claims.check_validity(now)?;
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+
+    use super::*;
+
+    const CLAIMS: &[u8] = br#"{"agent_uri":"agent://acme.com/sensor/temp_01h455vb4pex5vsknk084sn02q",
+        "capabilities":["sensor"],"iss":"acme.com",
+        "iat":"2024-01-15T14:30:00Z","exp":"2024-01-15T15:30:00Z"}"#;
+
+    #[test]
+    fn verify_checks_signature_and_time() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let token = paseto::tests::sign(&key, CLAIMS, b"");
+        let public_key = key.verifying_key().to_bytes();
+
+        let claims = verify(&token, &public_key, 1_705_329_000).unwrap();
+        assert_eq!(claims.iss, "acme.com");
+        assert!(claims.check_capability("sensor/temp").is_ok());
+
+        assert!(matches!(
+            verify(&token, &public_key, 1_705_332_600),
+            Err(VerifyError::TokenExpired { .. })
+        ));
+    }
+}
@@ -0,0 +1,272 @@
+//! PASETO v4.public signature verification.
+
+use alloc::vec::Vec;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+
+use crate::error::VerifyError;
+
+/// Header shared by every v4.public token.
+const HEADER: &str = "v4.public.";
+
+/// Length of an Ed25519 signature in bytes.
+const SIGNATURE_LEN: usize = 64;
+
+/// Verifies a PASETO v4.public token and returns its message.
+///
+/// The message is the token's claims JSON. Only the signature is checked
+/// here; time and capability checks happen on the parsed [`Claims`].
+///
+/// # Errors
+///
+/// Returns `InvalidTokenFormat` if the token cannot be decoded and
+/// `InvalidSignature` if the key is malformed or the signature does not
+/// verify.
+///
+/// [`Claims`]: crate::Claims
+pub fn open(token: &str, public_key: &[u8; 32]) -> Result<Vec<u8>, VerifyError> {
+    open_with_assertion(token, public_key, b"")
+}
+
+/// Verifies a v4.public token bound to `implicit` and returns its message.
+///
+/// Each signed token has exactly one accepted encoding: a `.` must be
+/// followed by a non-empty footer, and the base64url must be canonical.
+fn open_with_assertion(
+    token: &str,
+    public_key: &[u8; 32],
+    implicit: &[u8],
+) -> Result<Vec<u8>, VerifyError> {
+    let body = token
+        .strip_prefix(HEADER)
+        .ok_or(VerifyError::InvalidTokenFormat {
+            reason: "token must start with v4.public.",
+        })?;
+    let (payload, footer) = match body.split_once('.') {
+        Some((_, "")) => {
+            return Err(VerifyError::InvalidTokenFormat {
+                reason: "footer separator without a footer",
+            });
+        }
+        Some((payload, footer)) => (payload, footer),
+        None => (body, ""),
+    };
+
+    let mut payload = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|_| VerifyError::InvalidTokenFormat {
+            reason: "payload is not base64url",
+        })?;
+    let footer = URL_SAFE_NO_PAD
+        .decode(footer)
+        .map_err(|_| VerifyError::InvalidTokenFormat {
+            reason: "footer is not base64url",
+        })?;
+    if payload.len() < SIGNATURE_LEN {
+        return Err(VerifyError::InvalidTokenFormat {
+            reason: "payload is shorter than a signature",
+        });
+    }
+
+    let signature = payload.split_off(payload.len() - SIGNATURE_LEN);
+    let signature =
+        Signature::from_slice(&signature).map_err(|_| VerifyError::InvalidSignature)?;
+    let key = VerifyingKey::from_bytes(public_key).map_err(|_| VerifyError::InvalidSignature)?;
+
+    let signed = pae(&[HEADER.as_bytes(), &payload, &footer, implicit]);
+    key.verify_strict(&signed, &signature)
+        .map_err(|_| VerifyError::InvalidSignature)?;
+
+    Ok(payload)
+}
+
+/// Pre-authentication encoding of `pieces`.
+fn pae(pieces: &[&[u8]]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + pieces.iter().map(|p| 8 + p.len()).sum::<usize>());
+    out.extend_from_slice(&le64(pieces.len()));
+    for piece in pieces {
+        out.extend_from_slice(&le64(piece.len()));
+        out.extend_from_slice(piece);
+    }
+    out
+}
+
+/// Little-endian 64-bit length with the most significant bit cleared.
+fn le64(n: usize) -> [u8; 8] {
+    ((n as u64) & (u64::MAX >> 1)).to_le_bytes()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use alloc::format;
+    use alloc::string::String;
+
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+
+    /// Signs `message` as a v4.public token with an optional footer.
+    pub(crate) fn sign(key: &SigningKey, message: &[u8], footer: &[u8]) -> String {
+        let signature = key.sign(&pae(&[HEADER.as_bytes(), message, footer, b""]));
+        let mut payload = message.to_vec();
+        payload.extend_from_slice(&signature.to_bytes());
+
+        let mut token = format!("{HEADER}{}", URL_SAFE_NO_PAD.encode(payload));
+        if !footer.is_empty() {
+            token = format!("{token}.{}", URL_SAFE_NO_PAD.encode(footer));
+        }
+        token
+    }
+
+    #[test]
+    fn pae_matches_specification_vectors() {
+        assert_eq!(pae(&[]), [0u8; 8]);
+        assert_eq!(
+            pae(&[b"test"]),
+            *b"\x01\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00test"
+        );
+    }
+
+    #[test]
+    fn open_returns_message() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let token = sign(&key, br#"{"iss":"acme.com"}"#, b"");
+
+        let message = open(&token, key.verifying_key().as_bytes()).unwrap();
+
+        assert_eq!(message, br#"{"iss":"acme.com"}"#);
+    }
+
+    #[test]
+    fn open_authenticates_footer() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let token = sign(&key, b"{}", b"kid-1");
+        let (signed, _) = token.rsplit_once('.').unwrap();
+        let forged = format!("{signed}.{}", URL_SAFE_NO_PAD.encode(b"kid-2"));
+
+        assert!(open(&token, key.verifying_key().as_bytes()).is_ok());
+        assert_eq!(
+            open(&forged, key.verifying_key().as_bytes()),
+            Err(VerifyError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn open_rejects_wrong_key() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let other = SigningKey::generate(&mut rand::rngs::OsRng);
+        let token = sign(&key, b"{}", b"");
+
+        assert_eq!(
+            open(&token, other.verifying_key().as_bytes()),
+            Err(VerifyError::InvalidSignature)
+        );
+    }
+
+    /// Public key of the official PASETO v4.public test vectors.
+    const VECTOR_KEY: &str = "1eb9dbbbbc047c03fd70604e0071f0987e16b28b757225c11f00415d0e20b1a2";
+
+    /// Message signed by 4-S-1, 4-S-2 and 4-S-3.
+    const VECTOR_MESSAGE: &[u8] =
+        br#"{"data":"this is a signed message","exp":"2022-01-01T00:00:00+00:00"}"#;
+
+    const VECTOR_4_S_1: &str = "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9bg_XBBzds8lTZShVlwwKSgeKpLT3yukTw6JUz3W4h_ExsQV-P0V54zemZDcAxFaSeef1QlXEFtkqxT1ciiQEDA";
+
+    const VECTOR_4_S_2: &str = "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9v3Jt8mx_TdM2ceTGoqwrh4yDFn0XsHvvV_D0DtwQxVrJEBMl0F2caAdgnpKlt4p7xBnx1HcO-SPo8FPp214HDw.eyJraWQiOiJ6VmhNaVBCUDlmUmYyc25FY1Q3Z0ZUaW9lQTlDT2NOeTlEZmdMMVc2MGhhTiJ9";
+
+    const VECTOR_4_S_3: &str = "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9NPWciuD3d0o5eXJXG5pJy-DiVEoyPYWs1YSTwWHNJq6DZD3je5gf-0M4JR9ipdUSJbIovzmBECeaWmaqcaP0DQ.eyJraWQiOiJ6VmhNaVBCUDlmUmYyc25FY1Q3Z0ZUaW9lQTlDT2NOeTlEZmdMMVc2MGhhTiJ9";
+
+    /// Implicit assertion of 4-S-3.
+    const VECTOR_4_S_3_ASSERTION: &[u8] = br#"{"test-vector":"4-S-3"}"#;
+
+    fn vector_key() -> [u8; 32] {
+        let mut key = [0; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&VECTOR_KEY[2 * i..2 * i + 2], 16).unwrap();
+        }
+        key
+    }
+
+    #[test]
+    fn open_accepts_official_vectors() {
+        let key = vector_key();
+
+        assert_eq!(open(VECTOR_4_S_1, &key).unwrap(), VECTOR_MESSAGE);
+        assert_eq!(open(VECTOR_4_S_2, &key).unwrap(), VECTOR_MESSAGE);
+        assert_eq!(
+            open_with_assertion(VECTOR_4_S_3, &key, VECTOR_4_S_3_ASSERTION).unwrap(),
+            VECTOR_MESSAGE
+        );
+    }
+
+    #[test]
+    fn open_rejects_tampered_official_vectors() {
+        let key = vector_key();
+        let (signed, _) = VECTOR_4_S_2.rsplit_once('.').unwrap();
+        let other_footer = format!("{signed}.{}", URL_SAFE_NO_PAD.encode(br#"{"kid":"x"}"#));
+
+        // Footer stripped, replaced, or implicit assertion missing or wrong.
+        assert_eq!(open(signed, &key), Err(VerifyError::InvalidSignature));
+        assert_eq!(open(&other_footer, &key), Err(VerifyError::InvalidSignature));
+        assert_eq!(open(VECTOR_4_S_3, &key), Err(VerifyError::InvalidSignature));
+        assert_eq!(
+            open_with_assertion(VECTOR_4_S_3, &key, br#"{"test-vector":"4-S-2"}"#),
+            Err(VerifyError::InvalidSignature)
+        );
+        // Same body under another purpose or version.
+        let body = VECTOR_4_S_1.strip_prefix(HEADER).unwrap();
+        for header in ["v4.local.", "v3.public.", "v2.public."] {
+            assert!(matches!(
+                open(&format!("{header}{body}"), &key),
+                Err(VerifyError::InvalidTokenFormat { .. })
+            ));
+        }
+        // A flipped signature bit.
+        let at = VECTOR_4_S_1.len() - 10;
+        let swapped = if &VECTOR_4_S_1[at..=at] == "A" { "B" } else { "A" };
+        let flipped = format!("{}{swapped}{}", &VECTOR_4_S_1[..at], &VECTOR_4_S_1[at + 1..]);
+        assert_eq!(open(&flipped, &key), Err(VerifyError::InvalidSignature));
+    }
+
+    #[test]
+    fn open_rejects_alternate_encodings() {
+        let key = vector_key();
+        // Non-zero trailing bits in the final base64url character.
+        let mut trailing = String::from(VECTOR_4_S_1);
+        trailing.pop();
+        trailing.push('B');
+        assert!(matches!(
+            open(&trailing, &key),
+            Err(VerifyError::InvalidTokenFormat { .. })
+        ));
+
+        for token in [
+            format!("{VECTOR_4_S_1}."),
+            format!("{VECTOR_4_S_2}."),
+            format!("{VECTOR_4_S_1}.."),
+            format!("{VECTOR_4_S_1}="),
+        ] {
+            assert!(
+                matches!(
+                    open(&token, &key),
+                    Err(VerifyError::InvalidTokenFormat { .. })
+                ),
+                "{token} must not verify"
+            );
+        }
+    }
+
+    #[test]
+    fn open_rejects_other_versions() {
+        assert!(matches!(
+            open("v4.local.abc", &[0; 32]),
+            Err(VerifyError::InvalidTokenFormat { .. })
+        ));
+        assert!(matches!(
+            open("v4.public.AAAA", &[0; 32]),
+            Err(VerifyError::InvalidTokenFormat { .. })
+        ));
+    }
+}
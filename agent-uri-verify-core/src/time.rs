@@ -0,0 +1,155 @@
+//! RFC 3339 timestamp parsing without `std`.
+
+/// A point in time as whole seconds since the Unix epoch plus a fraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Timestamp {
+    /// Whole seconds since the Unix epoch
+    pub(crate) secs: i64,
+    /// Whether a non-zero sub-second fraction was present
+    pub(crate) fractional: bool,
+}
+
+impl Timestamp {
+    /// Seconds rounded towards the past.
+    pub(crate) fn floor(self) -> i64 {
+        self.secs
+    }
+
+    /// Seconds rounded towards the future.
+    pub(crate) fn ceil(self) -> i64 {
+        self.secs + i64::from(self.fractional)
+    }
+}
+
+/// Parses an RFC 3339 date-time such as `2024-01-15T14:30:00.123Z`.
+pub(crate) fn parse_rfc3339(s: &str) -> Option<Timestamp> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+
+    let year = digits(&bytes[0..4])?;
+    let month = digits(&bytes[5..7])?;
+    let day = digits(&bytes[8..10])?;
+    let hour = digits(&bytes[11..13])?;
+    let minute = digits(&bytes[14..16])?;
+    let second = digits(&bytes[17..19])?;
+    if !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut rest = &bytes[19..];
+    let mut fractional = false;
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let len = fraction.iter().take_while(|b| b.is_ascii_digit()).count();
+        if len == 0 {
+            return None;
+        }
+        fractional = fraction[..len].iter().any(|&b| b != b'0');
+        rest = &fraction[len..];
+    }
+
+    let offset = match rest {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+            let hours = digits(&[*h1, *h2])?;
+            let minutes = digits(&[*m1, *m2])?;
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60;
+            if *sign == b'-' { -offset } else { offset }
+        }
+        _ => return None,
+    };
+
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    Some(Timestamp { secs, fractional })
+}
+
+/// Parses a run of ASCII digits.
+fn digits(bytes: &[u8]) -> Option<i64> {
+    bytes.iter().try_fold(0i64, |acc, &b| {
+        b.is_ascii_digit().then(|| acc * 10 + i64::from(b - b'0'))
+    })
+}
+
+/// Returns the number of days in `month` of `year`.
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: &str) -> i64 {
+        parse_rfc3339(s).unwrap().secs
+    }
+
+    #[test]
+    fn parses_epoch_and_known_instants() {
+        assert_eq!(secs("1970-01-01T00:00:00Z"), 0);
+        assert_eq!(secs("2000-03-01T00:00:00Z"), 951_868_800);
+        assert_eq!(secs("2024-01-15T14:30:00.123Z"), 1_705_329_000);
+    }
+
+    #[test]
+    fn applies_offsets() {
+        assert_eq!(secs("2024-01-15T16:30:00+02:00"), secs("2024-01-15T14:30:00Z"));
+        assert_eq!(secs("2024-01-15T09:30:00-05:00"), secs("2024-01-15T14:30:00Z"));
+    }
+
+    #[test]
+    fn rounds_fractions_conservatively() {
+        let ts = parse_rfc3339("2024-01-15T14:30:00.500Z").unwrap();
+        assert_eq!(ts.ceil(), ts.floor() + 1);
+
+        let whole = parse_rfc3339("2024-01-15T14:30:00.000Z").unwrap();
+        assert_eq!(whole.ceil(), whole.floor());
+    }
+
+    #[test]
+    fn rejects_malformed_timestamps() {
+        for bad in [
+            "2024-01-15",
+            "2024-13-01T00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "2024-01-15T14:30:00",
+            "2024-01-15T14:30:00.Z",
+            "2024-01-15T24:00:00Z",
+            "2024-01-15T14:30:00+2:00",
+        ] {
+            assert_eq!(parse_rfc3339(bad), None, "{bad}");
+        }
+    }
+}